@@ -0,0 +1,173 @@
+use crate::{
+    common::{ensure_dir_exists, strip_off_newlines},
+    SECRETS_DIR_FLAG, VALIDATOR_DIR_FLAG,
+};
+use clap::{App, Arg, ArgMatches};
+use eth2_keystore::{Keystore, PlainText};
+use std::fs;
+use std::path::{Path, PathBuf};
+use validator_dir::Builder as ValidatorDirBuilder;
+
+pub const CMD: &str = "import";
+pub const KEYSTORE_FLAG: &str = "keystore";
+pub const DIR_FLAG: &str = "directory";
+pub const PASSWORD_FLAG: &str = "password-file";
+
+/// The prefix of the keystore files created by the `eth2.0-deposit-cli`, which are the only files
+/// considered when importing from a directory.
+pub const KEYSTORE_PREFIX: &str = "keystore";
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD)
+        .about(
+            "Imports one or more EIP-2335 keystores into a Lighthouse validator directory, \
+            such that they can be used by the validator client.",
+        )
+        .arg(
+            Arg::with_name(KEYSTORE_FLAG)
+                .long(KEYSTORE_FLAG)
+                .value_name("KEYSTORE_PATH")
+                .help("Path to a single EIP-2335 keystore to be imported.")
+                .conflicts_with(DIR_FLAG)
+                .required_unless(DIR_FLAG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(DIR_FLAG)
+                .long(DIR_FLAG)
+                .value_name("KEYSTORES_DIRECTORY")
+                .help(
+                    "Path to a directory which contains zero or more keystores for import. \
+                    Only files with names starting with \"keystore\" and ending with \".json\" \
+                    will be imported.",
+                )
+                .conflicts_with(KEYSTORE_FLAG)
+                .required_unless(KEYSTORE_FLAG)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(PASSWORD_FLAG)
+                .long(PASSWORD_FLAG)
+                .value_name("KEYSTORE_PASSWORD_PATH")
+                .help(
+                    "A path to a file containing the password which will unlock the keystores. \
+                    The same password is used for all keystores.",
+                )
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name(VALIDATOR_DIR_FLAG)
+                .long(VALIDATOR_DIR_FLAG)
+                .value_name("VALIDATOR_DIRECTORY")
+                .help(
+                    "The path where the validator directories will be created. \
+                    Defaults to ~/.lighthouse/validators",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(SECRETS_DIR_FLAG)
+                .long(SECRETS_DIR_FLAG)
+                .value_name("SECRETS_DIR")
+                .help(
+                    "The path where the validator keystore passwords will be stored. \
+                    Defaults to ~/.lighthouse/secrets",
+                )
+                .takes_value(true),
+        )
+}
+
+pub fn cli_run(matches: &ArgMatches) -> Result<(), String> {
+    let keystore: Option<PathBuf> = clap_utils::parse_optional(matches, KEYSTORE_FLAG)?;
+    let keystores_dir: Option<PathBuf> = clap_utils::parse_optional(matches, DIR_FLAG)?;
+    let password_path: PathBuf = clap_utils::parse_required(matches, PASSWORD_FLAG)?;
+    let validator_dir = clap_utils::parse_path_with_default_in_home_dir(
+        matches,
+        VALIDATOR_DIR_FLAG,
+        PathBuf::new().join(".lighthouse").join("validators"),
+    )?;
+    let secrets_dir = clap_utils::parse_path_with_default_in_home_dir(
+        matches,
+        SECRETS_DIR_FLAG,
+        PathBuf::new().join(".lighthouse").join("secrets"),
+    )?;
+
+    ensure_dir_exists(&validator_dir)?;
+    ensure_dir_exists(&secrets_dir)?;
+
+    let keystore_paths = match (keystore, keystores_dir) {
+        (Some(keystore), None) => vec![keystore],
+        (None, Some(keystores_dir)) => keystores_in_dir(&keystores_dir)?,
+        _ => {
+            return Err(format!(
+                "Must supply either --{} or --{}",
+                KEYSTORE_FLAG, DIR_FLAG
+            ))
+        }
+    };
+
+    if keystore_paths.is_empty() {
+        eprintln!("No keystores found to import");
+        return Ok(());
+    }
+
+    let password = fs::read(&password_path)
+        .map_err(|e| format!("Unable to read {:?}: {:?}", password_path, e))
+        .map(|bytes| PlainText::from(strip_off_newlines(bytes)))?;
+
+    let n = keystore_paths.len();
+    for (i, keystore_path) in keystore_paths.into_iter().enumerate() {
+        let keystore = fs::File::open(&keystore_path)
+            .map_err(|e| format!("Unable to open {:?}: {:?}", keystore_path, e))
+            .and_then(|file| {
+                Keystore::from_json_reader(file)
+                    .map_err(|e| format!("Unable to parse {:?}: {:?}", keystore_path, e))
+            })?;
+
+        // Ensure the password is correct before storing it alongside the keystore.
+        keystore
+            .decrypt_keypair(password.as_bytes())
+            .map_err(|e| format!("Unable to decrypt {:?}: {:?}", keystore_path, e))?;
+
+        let voting_pubkey = keystore.pubkey().to_string();
+
+        ValidatorDirBuilder::new(validator_dir.clone(), secrets_dir.clone())
+            .voting_keystore(keystore, password.as_bytes())
+            .store_withdrawal_keystore(false)
+            .build()
+            .map_err(|e| format!("Unable to build validator directory: {:?}", e))?;
+
+        println!("{}/{}\t0x{}", i + 1, n, voting_pubkey);
+    }
+
+    Ok(())
+}
+
+/// Returns the paths of all the keystore files in `dir`, sorted by file name.
+fn keystores_in_dir(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut paths = fs::read_dir(dir)
+        .map_err(|e| format!("Unable to read {:?}: {:?}", dir, e))?
+        .map(|entry| {
+            entry
+                .map(|entry| entry.path())
+                .map_err(|e| format!("Unable to read {:?}: {:?}", dir, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|path| {
+            let is_keystore_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| {
+                    name.starts_with(KEYSTORE_PREFIX) && name.ends_with(".json")
+                });
+
+            path.is_file() && is_keystore_name
+        })
+        .collect::<Vec<_>>();
+
+    paths.sort();
+
+    Ok(paths)
+}
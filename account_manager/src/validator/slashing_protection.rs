@@ -0,0 +1,149 @@
+use crate::VALIDATOR_DIR_FLAG;
+use clap::{App, Arg, ArgMatches};
+use environment::Environment;
+use slashing_protection::{Interchange, SlashingDatabase, SLASHING_PROTECTION_FILENAME};
+use std::fs::File;
+use std::path::PathBuf;
+use types::{EthSpec, Hash256};
+
+pub const CMD: &str = "slashing-protection";
+pub const IMPORT_CMD: &str = "import";
+pub const EXPORT_CMD: &str = "export";
+
+pub const IMPORT_FILE_ARG: &str = "IMPORT-FILE";
+pub const EXPORT_FILE_ARG: &str = "EXPORT-FILE";
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD)
+        .about("Import or export slashing protection data to or from another client")
+        .arg(
+            Arg::with_name(VALIDATOR_DIR_FLAG)
+                .long(VALIDATOR_DIR_FLAG)
+                .value_name("VALIDATOR_DIRECTORY")
+                .help(
+                    "The path to the validator client data directory, which contains the \
+                    slashing protection database. Defaults to ~/.lighthouse/validators",
+                )
+                .takes_value(true)
+                .global(true),
+        )
+        .subcommand(
+            App::new(IMPORT_CMD)
+                .about(
+                    "Import an EIP-3076 interchange file. The validator client must not be \
+                    running. Signing at or below the most recent block and attestation of each \
+                    validator in the file is refused from then on.",
+                )
+                .arg(
+                    Arg::with_name(IMPORT_FILE_ARG)
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("The slashing protection interchange file to import (.json)"),
+                ),
+        )
+        .subcommand(
+            App::new(EXPORT_CMD)
+                .about(
+                    "Export an EIP-3076 interchange file. The validator client must not be \
+                    running.",
+                )
+                .arg(
+                    Arg::with_name(EXPORT_FILE_ARG)
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("The filename to export the interchange file to"),
+                ),
+        )
+}
+
+pub fn cli_run<T: EthSpec>(matches: &ArgMatches<'_>, env: Environment<T>) -> Result<(), String> {
+    let validator_dir = clap_utils::parse_path_with_default_in_home_dir(
+        matches,
+        VALIDATOR_DIR_FLAG,
+        PathBuf::new().join(".lighthouse").join("validators"),
+    )?;
+    let slashing_protection_db_path = validator_dir.join(SLASHING_PROTECTION_FILENAME);
+
+    let genesis_validators_root = genesis_validators_root(&env)?;
+
+    match matches.subcommand() {
+        (IMPORT_CMD, Some(matches)) => {
+            let import_filename: PathBuf = clap_utils::parse_required(matches, IMPORT_FILE_ARG)?;
+            let import_file = File::open(&import_filename).map_err(|e| {
+                format!("Unable to open import file {:?}: {:?}", import_filename, e)
+            })?;
+            let interchange = Interchange::from_json_reader(&import_file)
+                .map_err(|e| format!("Error parsing file for import: {:?}", e))?;
+
+            let slashing_protection_database =
+                SlashingDatabase::open_or_create(&slashing_protection_db_path).map_err(|e| {
+                    format!(
+                        "Unable to open database at {:?}: {:?}",
+                        slashing_protection_db_path, e
+                    )
+                })?;
+
+            slashing_protection_database
+                .import_interchange_info(&interchange, genesis_validators_root)
+                .map_err(|e| format!("Error during import: {:?}", e))?;
+
+            eprintln!(
+                "Imported slashing protection data for {} validators",
+                interchange.data.len()
+            );
+            Ok(())
+        }
+        (EXPORT_CMD, Some(matches)) => {
+            let export_filename: PathBuf = clap_utils::parse_required(matches, EXPORT_FILE_ARG)?;
+
+            if !slashing_protection_db_path.exists() {
+                return Err(format!(
+                    "No slashing protection database exists at: {:?}",
+                    slashing_protection_db_path
+                ));
+            }
+
+            let slashing_protection_database = SlashingDatabase::open(&slashing_protection_db_path)
+                .map_err(|e| {
+                    format!(
+                        "Unable to open database at {:?}: {:?}",
+                        slashing_protection_db_path, e
+                    )
+                })?;
+
+            let interchange = slashing_protection_database
+                .export_interchange_info(genesis_validators_root)
+                .map_err(|e| format!("Error during export: {:?}", e))?;
+
+            let output_file = File::create(&export_filename).map_err(|e| {
+                format!("Error creating output file {:?}: {:?}", export_filename, e)
+            })?;
+
+            interchange
+                .write_to(&output_file)
+                .map_err(|e| format!("Error writing output file: {:?}", e))?;
+
+            eprintln!(
+                "Exported slashing protection data for {} validators",
+                interchange.data.len()
+            );
+            Ok(())
+        }
+        ("", _) => Err("No subcommand provided, see --help for options".to_string()),
+        (command, _) => Err(format!("No such subcommand `{}`", command)),
+    }
+}
+
+/// Returns the genesis validators root of the testnet, which ties the interchange file to it.
+fn genesis_validators_root<T: EthSpec>(env: &Environment<T>) -> Result<Hash256, String> {
+    env.testnet
+        .as_ref()
+        .ok_or_else(|| "Unable to get testnet configuration from the environment".to_string())?
+        .genesis_state
+        .as_ref()
+        .map(|state| state.genesis_validators_root)
+        .ok_or_else(|| {
+            "Unable to get genesis state, has genesis occurred? Check the --testnet-dir flag"
+                .to_string()
+        })
+}
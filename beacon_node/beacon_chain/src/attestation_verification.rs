@@ -52,7 +52,7 @@ use std::borrow::Cow;
 use tree_hash::TreeHash;
 use types::{
     Attestation, BeaconCommittee, CommitteeIndex, Epoch, EthSpec, Hash256, IndexedAttestation,
-    RelativeEpoch, SelectionProof, SignedAggregateAndProof, Slot,
+    RelativeEpoch, SelectionProof, SignedAggregateAndProof, Slot, SubnetId,
 };
 
 /// Returned when an attestation was not successfully verified. It might not have been verified for
@@ -107,6 +107,12 @@ pub enum Error {
     NoCommitteeForSlotAndIndex { slot: Slot, index: CommitteeIndex },
     /// The unaggregated attestation doesn't have only one aggregation bit set.
     NotExactlyOneAggregationBitSet(usize),
+    /// The attestation was received on a subnet that does not match the subnet of its committee
+    /// index.
+    InvalidSubnetId {
+        received: SubnetId,
+        expected: SubnetId,
+    },
     /// We have already observed an attestation for the `validator_index` and refuse to process
     /// another.
     PriorAttestationKnown { validator_index: u64, epoch: Epoch },
@@ -259,7 +265,12 @@ impl<T: BeaconChainTypes> VerifiedAggregatedAttestation<T> {
             .observed_aggregators
             .validator_has_been_observed(attestation, aggregator_index as usize)
         {
-            Ok(true) => Err(Error::AggregatorAlreadyKnown(aggregator_index)),
+            Ok(true) => {
+                // A repeated aggregate is rejected before its signatures are verified, however
+                // it may contain a double vote that the slasher should know about.
+                register_repeat_aggregate_with_slasher(chain, &signed_aggregate);
+                Err(Error::AggregatorAlreadyKnown(aggregator_index))
+            }
             Ok(false) => Ok(()),
             Err(ObservedAttestersError::ValidatorIndexTooHigh(i)) => {
                 Err(Error::ValidatorIndexTooHigh(i))
@@ -332,6 +343,9 @@ impl<T: BeaconChainTypes> VerifiedAggregatedAttestation<T> {
             return Err(Error::InvalidSignature);
         }
 
+        // The slasher must see the attestation even if it loses the race below.
+        register_with_slasher(chain, &indexed_attestation);
+
         // Observe the valid attestation so we do not re-process it.
         //
         // It's important to double check that the attestation is not already known, otherwise two
@@ -382,6 +396,16 @@ impl<T: BeaconChainTypes> VerifiedAggregatedAttestation<T> {
     pub fn attestation(&self) -> &Attestation<T::EthSpec> {
         &self.signed_aggregate.message.aggregate
     }
+
+    /// Returns the wrapped `signed_aggregate`.
+    pub fn aggregate(&self) -> &SignedAggregateAndProof<T::EthSpec> {
+        &self.signed_aggregate
+    }
+
+    /// Returns the `IndexedAttestation` of the aggregate.
+    pub fn indexed_attestation(&self) -> &IndexedAttestation<T::EthSpec> {
+        &self.indexed_attestation
+    }
 }
 
 impl<T: BeaconChainTypes> VerifiedUnaggregatedAttestation<T> {
@@ -389,13 +413,42 @@ impl<T: BeaconChainTypes> VerifiedUnaggregatedAttestation<T> {
     /// network.
     pub fn verify(
         attestation: Attestation<T::EthSpec>,
+        subnet_id: SubnetId,
         chain: &BeaconChain<T>,
     ) -> Result<Self, Error> {
+        let (indexed_attestation, validator_index) =
+            Self::verify_without_signature(&attestation, subnet_id, chain)?;
+
+        // The aggregate signature of the attestation is valid.
+        verify_attestation_signature(chain, &indexed_attestation)?;
+
+        Self::observe_attester(attestation, indexed_attestation, validator_index, chain)
+    }
+
+    /// Runs all of the checks of `Self::verify` which precede the verification of the signature,
+    /// returning the indexed attestation and the index of the attesting validator.
+    fn verify_without_signature(
+        attestation: &Attestation<T::EthSpec>,
+        subnet_id: SubnetId,
+        chain: &BeaconChain<T>,
+    ) -> Result<(IndexedAttestation<T::EthSpec>, u64), Error> {
         // Ensure attestation is within the last ATTESTATION_PROPAGATION_SLOT_RANGE slots (within a
         // MAXIMUM_GOSSIP_CLOCK_DISPARITY allowance).
         //
         // We do not queue future attestations for later processing.
-        verify_propagation_slot_range(chain, &attestation)?;
+        verify_propagation_slot_range(chain, attestation)?;
+
+        // Check to ensure that the attestation was published on the subnet that corresponds to
+        // its committee index.
+        let expected_subnet_id = attestation
+            .subnet_id(&chain.spec)
+            .map_err(BeaconChainError::from)?;
+        if expected_subnet_id != subnet_id {
+            return Err(Error::InvalidSubnetId {
+                received: subnet_id,
+                expected: expected_subnet_id,
+            });
+        }
 
         // Check to ensure that the attestation is "unaggregated". I.e., it has exactly one
         // aggregation bit set.
@@ -406,9 +459,9 @@ impl<T: BeaconChainTypes> VerifiedUnaggregatedAttestation<T> {
 
         // Attestations must be for a known block. If the block is unknown, we simply drop the
         // attestation and do not delay consideration for later.
-        verify_head_block_is_known(chain, &attestation)?;
+        verify_head_block_is_known(chain, attestation)?;
 
-        let indexed_attestation = obtain_indexed_attestation(chain, &attestation)?;
+        let indexed_attestation = obtain_indexed_attestation(chain, attestation)?;
 
         let validator_index = *indexed_attestation
             .attesting_indices
@@ -421,17 +474,35 @@ impl<T: BeaconChainTypes> VerifiedUnaggregatedAttestation<T> {
          */
         if chain
             .observed_attesters
-            .validator_has_been_observed(&attestation, validator_index as usize)
+            .validator_has_been_observed(attestation, validator_index as usize)
             .map_err(|e| BeaconChainError::from(e))?
         {
+            // A repeated attestation is rejected before its signature is verified, however it
+            // may be a double vote that the slasher should know about.
+            if chain.slasher.is_some()
+                && verify_attestation_signature(chain, &indexed_attestation).is_ok()
+            {
+                register_with_slasher(chain, &indexed_attestation);
+            }
             return Err(Error::PriorAttestationKnown {
                 validator_index,
                 epoch: attestation.data.target.epoch,
             });
         }
 
-        // The aggregate signature of the attestation is valid.
-        verify_attestation_signature(chain, &indexed_attestation)?;
+        Ok((indexed_attestation, validator_index))
+    }
+
+    /// Completes the verification of an attestation which has passed all other checks, including
+    /// the verification of its signature.
+    fn observe_attester(
+        attestation: Attestation<T::EthSpec>,
+        indexed_attestation: IndexedAttestation<T::EthSpec>,
+        validator_index: u64,
+        chain: &BeaconChain<T>,
+    ) -> Result<Self, Error> {
+        // The slasher must see the attestation even if it loses the race below.
+        register_with_slasher(chain, &indexed_attestation);
 
         // Now that the attestation has been fully verified, store that we have received a valid
         // attestation from this validator.
@@ -466,6 +537,11 @@ impl<T: BeaconChainTypes> VerifiedUnaggregatedAttestation<T> {
         &self.attestation
     }
 
+    /// Returns the `IndexedAttestation` of the wrapped `attestation`.
+    pub fn indexed_attestation(&self) -> &IndexedAttestation<T::EthSpec> {
+        &self.indexed_attestation
+    }
+
     /// Returns a mutable reference to the underlying attestation.
     ///
     /// Only use during testing since modifying the `IndexedAttestation` can cause the attestation
@@ -687,6 +763,108 @@ pub fn verify_attestation_signature<T: BeaconChainTypes>(
     }
 }
 
+/// Verifies the signatures of all of the `indexed_attestations` using BLS batch verification.
+///
+/// # Returns
+///
+/// - `Ok(true)`: if all signatures are valid.
+/// - `Ok(false)`: if one or more signatures are invalid.
+/// - `Err(e)`: if there was an error preventing signature verification.
+pub fn verify_attestation_signatures<T: BeaconChainTypes>(
+    chain: &BeaconChain<T>,
+    indexed_attestations: &[&IndexedAttestation<T::EthSpec>],
+) -> Result<bool, Error> {
+    let signature_setup_timer =
+        metrics::start_timer(&metrics::ATTESTATION_PROCESSING_SIGNATURE_SETUP_TIMES);
+
+    let pubkey_cache = chain
+        .validator_pubkey_cache
+        .try_read_for(VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT)
+        .ok_or_else(|| BeaconChainError::ValidatorPubkeyCacheLockTimeout)?;
+
+    let fork = chain
+        .canonical_head
+        .try_read_for(HEAD_LOCK_TIMEOUT)
+        .ok_or_else(|| BeaconChainError::CanonicalHeadLockTimeout)
+        .map(|head| head.beacon_state.fork.clone())?;
+
+    let signature_sets = indexed_attestations
+        .iter()
+        .map(|indexed_attestation| {
+            indexed_attestation_signature_set_from_pubkeys(
+                |validator_index| pubkey_cache.get(validator_index).map(Cow::Borrowed),
+                &indexed_attestation.signature,
+                indexed_attestation,
+                &fork,
+                chain.genesis_validators_root,
+                &chain.spec,
+            )
+            .map_err(BeaconChainError::SignatureSetError)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    metrics::stop_timer(signature_setup_timer);
+
+    let _signature_verification_timer =
+        metrics::start_timer(&metrics::ATTESTATION_PROCESSING_SIGNATURE_TIMES);
+
+    Ok(verify_signature_sets(signature_sets))
+}
+
+/// Verifies a batch of unaggregated attestations as `VerifiedUnaggregatedAttestation::verify`
+/// would, returning the result for each attestation in the order they were given.
+///
+/// The signatures of the attestations which pass all other checks are verified with a single
+/// batch verification. The signatures are only verified individually if the batch is invalid, in
+/// order to find the invalid attestations.
+pub fn batch_verify_unaggregated_attestations<T: BeaconChainTypes>(
+    attestations: Vec<(Attestation<T::EthSpec>, SubnetId)>,
+    chain: &BeaconChain<T>,
+) -> Vec<Result<VerifiedUnaggregatedAttestation<T>, Error>> {
+    let checked_attestations = attestations
+        .into_iter()
+        .map(|(attestation, subnet_id)| {
+            let (indexed_attestation, validator_index) =
+                VerifiedUnaggregatedAttestation::verify_without_signature(
+                    &attestation,
+                    subnet_id,
+                    chain,
+                )?;
+            Ok((attestation, indexed_attestation, validator_index))
+        })
+        .collect::<Vec<Result<_, Error>>>();
+
+    let indexed_attestations = checked_attestations
+        .iter()
+        .filter_map(|result| result.as_ref().ok())
+        .map(|(_, indexed_attestation, _)| indexed_attestation)
+        .collect::<Vec<_>>();
+
+    // An error whilst setting up the batch is treated as an invalid batch, so that the error is
+    // attributed to the individual attestations.
+    let batch_is_valid = indexed_attestations.is_empty()
+        || verify_attestation_signatures(chain, &indexed_attestations).unwrap_or(false);
+    if !batch_is_valid {
+        metrics::inc_counter(&metrics::ATTESTATION_PROCESSING_BATCH_SIGNATURE_FAILURES);
+    }
+
+    checked_attestations
+        .into_iter()
+        .map(|result| {
+            let (attestation, indexed_attestation, validator_index) = result?;
+            if !batch_is_valid {
+                verify_attestation_signature(chain, &indexed_attestation)?;
+            }
+            VerifiedUnaggregatedAttestation::observe_attester(
+                attestation,
+                indexed_attestation,
+                validator_index,
+                chain,
+            )
+        })
+        .collect()
+}
+
 /// Verifies all the signatures in a `SignedAggregateAndProof` using BLS batch verification. This
 /// includes three signatures:
 ///
@@ -751,6 +929,41 @@ pub fn verify_signed_aggregate_signatures<T: BeaconChainTypes>(
     Ok(verify_signature_sets(signature_sets))
 }
 
+/// Queues an attestation with a verified signature for processing by the slasher, if any.
+fn register_with_slasher<T: BeaconChainTypes>(
+    chain: &BeaconChain<T>,
+    indexed_attestation: &IndexedAttestation<T::EthSpec>,
+) {
+    if let Some(slasher) = chain.slasher.as_ref() {
+        slasher.accept_attestation(indexed_attestation.clone());
+    }
+}
+
+/// Verifies the signatures of an aggregate which was not otherwise verified because its
+/// aggregator is already known, queueing its attestation for the slasher if they are valid.
+fn register_repeat_aggregate_with_slasher<T: BeaconChainTypes>(
+    chain: &BeaconChain<T>,
+    signed_aggregate: &SignedAggregateAndProof<T::EthSpec>,
+) {
+    if chain.slasher.is_none() {
+        return;
+    }
+
+    let attestation = &signed_aggregate.message.aggregate;
+    if verify_head_block_is_known(chain, attestation).is_err() {
+        return;
+    }
+    let indexed_attestation = match obtain_indexed_attestation(chain, attestation) {
+        Ok(indexed_attestation) => indexed_attestation,
+        Err(_) => return,
+    };
+    if let Ok(true) =
+        verify_signed_aggregate_signatures(chain, signed_aggregate, &indexed_attestation)
+    {
+        register_with_slasher(chain, &indexed_attestation);
+    }
+}
+
 /// Returns the `indexed_attestation` for the `attestation` using the public keys cached in the
 /// `chain`.
 pub fn obtain_indexed_attestation<T: BeaconChainTypes>(
@@ -1,7 +1,9 @@
 use crate::attestation_verification::{
-    Error as AttestationError, ForkChoiceVerifiedAttestation, IntoForkChoiceVerifiedAttestation,
+    batch_verify_unaggregated_attestations, Error as AttestationError,
+    ForkChoiceVerifiedAttestation, IntoForkChoiceVerifiedAttestation,
     VerifiedAggregatedAttestation, VerifiedUnaggregatedAttestation,
 };
+use crate::block_delay_cache::BlockDelayCache;
 use crate::block_verification::{
     check_block_relevancy, get_block_root, signature_verify_chain_segment, BlockError,
     FullyVerifiedBlock, GossipVerifiedBlock, IntoFullyVerifiedBlock,
@@ -11,6 +13,7 @@ use crate::eth1_chain::{Eth1Chain, Eth1ChainBackend};
 use crate::events::{EventHandler, EventKind};
 use crate::fork_choice::{Error as ForkChoiceError, ForkChoice};
 use crate::head_tracker::HeadTracker;
+use crate::invalid_block_cache::InvalidBlockCache;
 use crate::metrics;
 use crate::migrate::Migrate;
 use crate::naive_aggregation_pool::{Error as NaiveAggregationError, NaiveAggregationPool};
@@ -21,17 +24,27 @@ use crate::persisted_beacon_chain::PersistedBeaconChain;
 use crate::shuffling_cache::ShufflingCache;
 use crate::snapshot_cache::SnapshotCache;
 use crate::timeout_rw_lock::TimeoutRwLock;
+use crate::validator_monitor::ValidatorMonitor;
 use crate::validator_pubkey_cache::ValidatorPubkeyCache;
 use crate::BeaconSnapshot;
+use futures::channel::mpsc::Sender;
 use itertools::process_results;
 use operation_pool::{OperationPool, PersistedOperationPool};
+use parking_lot::RwLock;
+use slasher::Slasher;
 use slog::{crit, debug, error, info, trace, warn, Logger};
 use slot_clock::SlotClock;
 use state_processing::per_block_processing::errors::{
     AttestationValidationError, AttesterSlashingValidationError, ExitValidationError,
     ProposerSlashingValidationError,
 };
-use state_processing::{per_block_processing, per_slot_processing, BlockSignatureStrategy};
+use state_processing::per_block_processing::{
+    verify_attester_slashing, verify_exit_time_independent_only, verify_proposer_slashing,
+};
+use state_processing::{
+    common::get_indexed_attestation, per_block_processing, per_slot_processing,
+    BlockSignatureStrategy, VerifySignatures,
+};
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -123,6 +136,20 @@ pub enum AttestationProcessingOutcome {
     Invalid(AttestationValidationError),
 }
 
+/// The result of verifying an exit or slashing received on gossip.
+#[derive(Debug, PartialEq)]
+pub enum GossipOperationOutcome<E> {
+    /// The operation is valid against the head state and was queued for inclusion in a block.
+    Queued,
+    /// The operation is valid against the head state but was not queued, since this node does not
+    /// produce blocks (i.e., it has no eth1 chain).
+    Verified,
+    /// The operation was not verified because the head state was unavailable.
+    Unverified,
+    /// The operation is invalid against the head state.
+    Invalid(E),
+}
+
 /// Defines how a `BeaconState` should be "skipped" through skip-slots.
 pub enum StateSkipConfig {
     /// Calculate the state root during each skip slot, producing a fully-valid `BeaconState`.
@@ -185,6 +212,8 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     pub observed_aggregators: ObservedAggregators<T::EthSpec>,
     /// Maintains a record of which validators have proposed blocks for each slot.
     pub observed_block_producers: ObservedBlockProducers<T::EthSpec>,
+    /// Maintains a record of how late each block was received on gossip, until it is imported.
+    pub(crate) block_delay_cache: BlockDelayCache,
     /// Provides information from the Ethereum 1 (PoW) chain.
     pub eth1_chain: Option<Eth1Chain<T::Eth1Chain, T::EthSpec>>,
     /// Stores a "snapshot" of the chain at the time the head-of-the-chain block was received.
@@ -202,12 +231,29 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     pub(crate) head_tracker: Arc<HeadTracker>,
     /// A cache dedicated to block processing.
     pub(crate) snapshot_cache: TimeoutRwLock<SnapshotCache<T::EthSpec>>,
+    /// The head state, advanced into the next slot by `Self::advance_head_state`, alongside the
+    /// root of the head block it was advanced from.
+    pub(crate) advanced_head_state: TimeoutRwLock<Option<(Hash256, BeaconState<T::EthSpec>)>>,
+    /// The roots of recent blocks which failed the state transition.
+    pub(crate) invalid_block_cache: InvalidBlockCache,
     /// Caches the shuffling for a given epoch and state root.
     pub(crate) shuffling_cache: TimeoutRwLock<ShufflingCache>,
     /// Caches a map of `validator_index -> validator_pubkey`.
     pub(crate) validator_pubkey_cache: TimeoutRwLock<ValidatorPubkeyCache>,
+    /// Logs and records metrics about the activity of specific validators.
+    pub validator_monitor: RwLock<ValidatorMonitor<T::EthSpec>>,
+    /// Detects slashable offences amongst the attestations and blocks verified by the chain.
+    pub slasher: Option<Arc<Slasher<T::EthSpec>>>,
+    /// A checkpoint which the canonical chain must pass through, if any.
+    pub weak_subjectivity_checkpoint: Option<Checkpoint>,
+    /// Used to request a shutdown of the beacon node when the chain conflicts with the weak
+    /// subjectivity checkpoint.
+    pub(crate) shutdown_sender: Option<Sender<&'static str>>,
     /// A list of any hard-coded forks that have been disabled.
     pub disabled_forks: Vec<String>,
+    /// If `true`, state roots are computed without a tree hash cache. This is very slow and is
+    /// only intended for debugging the cache.
+    pub disable_tree_hash_cache: bool,
     /// Logging to CLI, etc.
     pub(crate) log: Logger,
 }
@@ -225,12 +271,27 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     ///
     /// We want to ensure that the head never out dates the fork choice to avoid having references
     /// to blocks that do not exist in fork choice.
+    ///
+    /// The states which are read when loading the persisted head and running fork choice (i.e.,
+    /// those of the head and the justified and finalized checkpoints) are first written from the
+    /// store's pre-finalization cache, so they survive an unclean shutdown.
     pub fn persist_head_and_fork_choice(&self) -> Result<(), Error> {
-        let canonical_head_block_root = self
-            .canonical_head
-            .try_read_for(HEAD_LOCK_TIMEOUT)
-            .ok_or_else(|| Error::CanonicalHeadLockTimeout)?
-            .beacon_block_root;
+        let (canonical_head_block_root, canonical_head_state_root) = {
+            let head = self
+                .canonical_head
+                .try_read_for(HEAD_LOCK_TIMEOUT)
+                .ok_or_else(|| Error::CanonicalHeadLockTimeout)?;
+            (head.beacon_block_root, head.beacon_state_root)
+        };
+
+        self.store
+            .persist_epoch_boundary_state(&canonical_head_state_root)?;
+        for block_root in self.fork_choice.checkpoint_block_roots() {
+            if let Some(block) = self.get_block(&block_root)? {
+                self.store
+                    .persist_epoch_boundary_state(&block.state_root())?;
+            }
+        }
 
         let persisted_head = PersistedBeaconChain {
             canonical_head_block_root,
@@ -275,6 +336,18 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(())
     }
 
+    /// Persists the head, fork choice, head tracker, op pool and eth1 cache to disk, so that they
+    /// may be restored after a restart.
+    ///
+    /// The unfinalized states held in the store's pre-finalization cache are written first, since
+    /// they are otherwise lost when the process exits.
+    pub fn persist_to_disk(&self) -> Result<(), Error> {
+        self.store.flush_prefinalization_cache()?;
+        self.persist_head_and_fork_choice()?;
+        self.persist_op_pool()?;
+        self.persist_eth1_cache()
+    }
+
     /// Persists `self.eth1_chain` and its caches to disk.
     pub fn persist_eth1_cache(&self) -> Result<(), Error> {
         let timer = metrics::start_timer(&metrics::PERSIST_OP_POOL);
@@ -375,6 +448,37 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             .map(|result| result.map_err(|e| e.into())))
     }
 
+    /// Returns the root of the most recent block shared by the chains of `old_head` and
+    /// `new_head`, along with the highest slot at which both chains contain that block.
+    ///
+    /// If one of the blocks descends from the other, the ancestor is returned.
+    pub fn find_common_ancestor(
+        &self,
+        old_head: Hash256,
+        new_head: Hash256,
+    ) -> Result<(Hash256, Slot), Error> {
+        let mut old_iter = self.rev_iter_block_roots_from(old_head)?;
+        let mut new_iter = self.rev_iter_block_roots_from(new_head)?;
+        let no_common_ancestor = || Error::NoCommonAncestor { old_head, new_head };
+
+        let mut old = old_iter.next().ok_or_else(no_common_ancestor)??;
+        let mut new = new_iter.next().ok_or_else(no_common_ancestor)??;
+
+        // Both iterators descend one slot at a time, so step the one at the higher slot until the
+        // slots line up, then step both until the roots match.
+        loop {
+            match old.1.cmp(&new.1) {
+                Ordering::Greater => old = old_iter.next().ok_or_else(no_common_ancestor)??,
+                Ordering::Less => new = new_iter.next().ok_or_else(no_common_ancestor)??,
+                Ordering::Equal if old.0 == new.0 => return Ok(old),
+                Ordering::Equal => {
+                    old = old_iter.next().ok_or_else(no_common_ancestor)??;
+                    new = new_iter.next().ok_or_else(no_common_ancestor)??;
+                }
+            }
+        }
+    }
+
     /// Traverse backwards from `block_root` to find the root of the ancestor block at `slot`.
     pub fn get_ancestor_block_root(
         &self,
@@ -508,7 +612,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         slot: Slot,
         config: StateSkipConfig,
     ) -> Result<BeaconState<T::EthSpec>, Error> {
-        let head_state = self.head()?.beacon_state;
+        let head = self.head()?;
+        let head_state = head.beacon_state;
 
         match slot.cmp(&head_state.slot) {
             Ordering::Equal => Ok(head_state),
@@ -527,7 +632,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 let max_task_runtime = Duration::from_millis(self.spec.milliseconds_per_slot);
 
                 let head_state_slot = head_state.slot;
-                let mut state = head_state;
+                // Skip from the state advanced by the state advance timer, if it is suitable.
+                let mut state = self
+                    .get_advanced_head_state(head.beacon_block_root, slot)
+                    .unwrap_or(head_state);
 
                 let skip_state_root = match config {
                     StateSkipConfig::WithStateRoots => None,
@@ -735,6 +843,19 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.naive_aggregation_pool.get(data).map_err(Into::into)
     }
 
+    /// Returns an aggregated `Attestation`, if any, at `slot` whose `attestation.data` has a tree
+    /// hash root of `attestation_data_root`.
+    ///
+    /// The attestation will be obtained from `self.naive_aggregation_pool`.
+    pub fn get_aggregated_attestation_by_slot_and_root(
+        &self,
+        slot: Slot,
+        attestation_data_root: &Hash256,
+    ) -> Option<Attestation<T::EthSpec>> {
+        self.naive_aggregation_pool
+            .get_by_slot_and_root(slot, attestation_data_root)
+    }
+
     /// Produce an unaggregated `Attestation` that is valid for the given `slot` and `index`.
     ///
     /// The produced `Attestation` will not be valid until it has been signed by exactly one
@@ -855,21 +976,51 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     /// it is valid to be (re)broadcast on the gossip network.
     ///
     /// The attestation must be "unaggregated", that is it must have exactly one
-    /// aggregation bit set.
+    /// aggregation bit set. The `subnet_id` is the subnet on which the attestation was received,
+    /// it must match the subnet of the attestation's committee.
     pub fn verify_unaggregated_attestation_for_gossip(
         &self,
         attestation: Attestation<T::EthSpec>,
+        subnet_id: SubnetId,
     ) -> Result<VerifiedUnaggregatedAttestation<T>, AttestationError> {
         metrics::inc_counter(&metrics::UNAGGREGATED_ATTESTATION_PROCESSING_REQUESTS);
         let _timer =
             metrics::start_timer(&metrics::UNAGGREGATED_ATTESTATION_GOSSIP_VERIFICATION_TIMES);
 
-        VerifiedUnaggregatedAttestation::verify(attestation, self).map(|v| {
+        VerifiedUnaggregatedAttestation::verify(attestation, subnet_id, self).map(|v| {
             metrics::inc_counter(&metrics::UNAGGREGATED_ATTESTATION_PROCESSING_SUCCESSES);
+            self.register_unaggregated_attestation_with_validator_monitor(&v);
             v
         })
     }
 
+    /// Performs the same verification as `Self::verify_unaggregated_attestation_for_gossip` on
+    /// each of the `attestations`, returning the results in the same order.
+    ///
+    /// The signatures of the attestations are verified in a single batch, which is considerably
+    /// cheaper than verifying them individually whilst all of them are valid.
+    pub fn batch_verify_unaggregated_attestations_for_gossip(
+        &self,
+        attestations: Vec<(Attestation<T::EthSpec>, SubnetId)>,
+    ) -> Vec<Result<VerifiedUnaggregatedAttestation<T>, AttestationError>> {
+        metrics::inc_counter_by(
+            &metrics::UNAGGREGATED_ATTESTATION_PROCESSING_REQUESTS,
+            attestations.len() as i64,
+        );
+        let _timer =
+            metrics::start_timer(&metrics::UNAGGREGATED_ATTESTATION_GOSSIP_VERIFICATION_TIMES);
+
+        let results = batch_verify_unaggregated_attestations(attestations, self);
+        metrics::inc_counter_by(
+            &metrics::UNAGGREGATED_ATTESTATION_PROCESSING_SUCCESSES,
+            results.iter().filter(|result| result.is_ok()).count() as i64,
+        );
+        for verified in results.iter().filter_map(|result| result.as_ref().ok()) {
+            self.register_unaggregated_attestation_with_validator_monitor(verified);
+        }
+        results
+    }
+
     /// Accepts some `SignedAggregateAndProof` from the network and attempts to verify it,
     /// returning `Ok(_)` if it is valid to be (re)broadcast on the gossip network.
     pub fn verify_aggregated_attestation_for_gossip(
@@ -882,10 +1033,36 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         VerifiedAggregatedAttestation::verify(signed_aggregate, self).map(|v| {
             metrics::inc_counter(&metrics::AGGREGATED_ATTESTATION_PROCESSING_SUCCESSES);
+            if let Some(seen_timestamp) = self.slot_clock.now_duration() {
+                self.validator_monitor
+                    .read()
+                    .register_gossip_aggregated_attestation(
+                        seen_timestamp,
+                        v.aggregate().message.aggregator_index,
+                        v.indexed_attestation(),
+                        &self.slot_clock,
+                    );
+            }
             v
         })
     }
 
+    /// Informs the validator monitor that `verified` has been received.
+    fn register_unaggregated_attestation_with_validator_monitor(
+        &self,
+        verified: &VerifiedUnaggregatedAttestation<T>,
+    ) {
+        if let Some(seen_timestamp) = self.slot_clock.now_duration() {
+            self.validator_monitor
+                .read()
+                .register_gossip_unaggregated_attestation(
+                    seen_timestamp,
+                    verified.indexed_attestation(),
+                    &self.slot_clock,
+                );
+        }
+    }
+
     /// Accepts some attestation-type object and attempts to verify it in the context of fork
     /// choice. If it is valid it is applied to `self.fork_choice`.
     ///
@@ -992,6 +1169,91 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(signed_aggregate)
     }
 
+    /// Returns the attestations of `self.op_pool` to include in a block produced on `state`.
+    ///
+    /// Only attestations whose shuffling is compatible with `state` are included, such that the
+    /// block remains valid.
+    pub fn get_attestations_for_block(
+        &self,
+        state: &BeaconState<T::EthSpec>,
+    ) -> Result<Vec<Attestation<T::EthSpec>>, BlockProductionError> {
+        // Map from attestation head block root to shuffling compatibility.
+        // Used to memoize the `attestation_shuffling_is_compatible` function.
+        let mut shuffling_filter_cache = HashMap::new();
+        let attestation_filter = |att: &&Attestation<T::EthSpec>| -> bool {
+            *shuffling_filter_cache
+                .entry((att.data.beacon_block_root, att.data.target.epoch))
+                .or_insert_with(|| {
+                    self.shuffling_is_compatible(
+                        &att.data.beacon_block_root,
+                        att.data.target.epoch,
+                        state,
+                    )
+                })
+        };
+
+        self.op_pool
+            .get_attestations(state, attestation_filter, &self.spec)
+            .map_err(BlockProductionError::OpPoolError)
+    }
+
+    /// Adds the committee cache of `state` at `relative_epoch` to `self.shuffling_cache`, such that
+    /// it can be shared with attestation verification instead of being recomputed.
+    ///
+    /// The cache is keyed by the root of the first block of the epoch, therefore the shuffling is
+    /// only cached if that root is known to `state`.
+    pub fn cache_committee_shuffling(
+        &self,
+        state: &BeaconState<T::EthSpec>,
+        relative_epoch: RelativeEpoch,
+    ) {
+        let epoch = relative_epoch.into_epoch(state.current_epoch());
+        let epoch_start_slot = epoch.start_slot(T::EthSpec::slots_per_epoch());
+        if epoch_start_slot >= state.slot {
+            return;
+        }
+
+        let result = state
+            .get_block_root(epoch_start_slot)
+            .and_then(|target_root| {
+                state
+                    .committee_cache(relative_epoch)
+                    .map(|committee_cache| (*target_root, committee_cache))
+            })
+            .map_err(Error::BeaconStateError)
+            .and_then(|(target_root, committee_cache)| {
+                self.shuffling_cache
+                    .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+                    .ok_or_else(|| Error::AttestationCacheLockTimeout)?
+                    .insert(epoch, target_root, committee_cache);
+                Ok(())
+            });
+
+        if let Err(e) = result {
+            debug!(
+                self.log,
+                "Unable to cache committee shuffling";
+                "epoch" => epoch,
+                "error" => format!("{:?}", e)
+            );
+        }
+    }
+
+    /// Returns the committee cache for `epoch` from `self.shuffling_cache`, if the shuffling
+    /// descending from `target_root` (the root of the first block of `epoch`) has been cached.
+    pub fn cached_committee_shuffling(
+        &self,
+        epoch: Epoch,
+        target_root: Hash256,
+    ) -> Result<Option<CommitteeCache>, Error> {
+        Ok(self
+            .shuffling_cache
+            .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+            .ok_or_else(|| Error::AttestationCacheLockTimeout)?
+            .get(epoch, target_root)
+            .cloned())
+    }
+
     /// Check that the shuffling at `block_root` is equal to one of the shufflings of `state`.
     ///
     /// The `target_epoch` argument determines which shuffling to check compatibility with, it
@@ -1139,6 +1401,100 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         }
     }
 
+    /// Verifies a voluntary exit received on gossip against the head state and queues it for
+    /// inclusion in a block.
+    pub fn verify_voluntary_exit_for_gossip(
+        &self,
+        exit: SignedVoluntaryExit,
+    ) -> GossipOperationOutcome<ExitValidationError> {
+        self.verify_operation_for_gossip(
+            "voluntary exit",
+            exit,
+            |state, exit| {
+                verify_exit_time_independent_only(state, exit, VerifySignatures::True, &self.spec)
+            },
+            |state, exit| self.op_pool.insert_voluntary_exit(exit, state, &self.spec),
+        )
+    }
+
+    /// Verifies a proposer slashing received on gossip against the head state and queues it for
+    /// inclusion in a block.
+    pub fn verify_proposer_slashing_for_gossip(
+        &self,
+        proposer_slashing: ProposerSlashing,
+    ) -> GossipOperationOutcome<ProposerSlashingValidationError> {
+        self.verify_operation_for_gossip(
+            "proposer slashing",
+            proposer_slashing,
+            |state, slashing| {
+                verify_proposer_slashing(slashing, state, VerifySignatures::True, &self.spec)
+            },
+            |state, slashing| {
+                self.op_pool
+                    .insert_proposer_slashing(slashing, state, &self.spec)
+            },
+        )
+    }
+
+    /// Verifies an attester slashing received on gossip against the head state and queues it for
+    /// inclusion in a block.
+    pub fn verify_attester_slashing_for_gossip(
+        &self,
+        attester_slashing: AttesterSlashing<T::EthSpec>,
+    ) -> GossipOperationOutcome<AttesterSlashingValidationError> {
+        self.verify_operation_for_gossip(
+            "attester slashing",
+            attester_slashing,
+            |state, slashing| {
+                verify_attester_slashing(state, slashing, VerifySignatures::True, &self.spec)
+            },
+            |state, slashing| {
+                self.op_pool
+                    .insert_attester_slashing(slashing, state, &self.spec)
+            },
+        )
+    }
+
+    /// Verifies a gossip operation against a copy of the head state.
+    ///
+    /// Nodes with an eth1 chain run `insert`, which verifies the operation and queues it in the
+    /// op pool. All other nodes only run `verify`, so they still validate and forward operations
+    /// that they will never include in a block.
+    ///
+    /// The head lock is released before either closure runs, since both verify signatures.
+    fn verify_operation_for_gossip<O, E>(
+        &self,
+        operation_name: &str,
+        operation: O,
+        verify: impl FnOnce(&BeaconState<T::EthSpec>, &O) -> Result<(), E>,
+        insert: impl FnOnce(&BeaconState<T::EthSpec>, O) -> Result<(), E>,
+    ) -> GossipOperationOutcome<E> {
+        let state = match self.canonical_head.try_read_for(HEAD_LOCK_TIMEOUT) {
+            Some(head) => head.beacon_state.clone_with_only_committee_caches(),
+            None => {
+                debug!(
+                    self.log,
+                    "Unable to verify gossip operation";
+                    "operation" => operation_name,
+                    "reason" => "head lock timeout"
+                );
+                return GossipOperationOutcome::Unverified;
+            }
+        };
+
+        if self.eth1_chain.is_some() {
+            match insert(&state, operation) {
+                Ok(()) => GossipOperationOutcome::Queued,
+                Err(e) => GossipOperationOutcome::Invalid(e),
+            }
+        } else {
+            match verify(&state, &operation) {
+                Ok(()) => GossipOperationOutcome::Verified,
+                Err(e) => GossipOperationOutcome::Invalid(e),
+            }
+        }
+    }
+
     /// Attempt to verify and import a chain of blocks to `self`.
     ///
     /// The provided blocks _must_ each reference the previous block via `block.parent_root` (i.e.,
@@ -1211,6 +1567,14 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 // However, we will potentially get a `ParentUnknown` on a later block. The sync
                 // protocol will need to ensure this is handled gracefully.
                 Err(BlockError::WouldRevertFinalizedSlot { .. }) => continue,
+                // If the block is known to be invalid, reject the segment without processing any of
+                // its blocks.
+                Err(BlockError::KnownInvalidBlock(root)) => {
+                    return ChainSegmentResult::Failed {
+                        imported_blocks,
+                        error: BlockError::KnownInvalidBlock(root),
+                    }
+                }
                 // If there was an error whilst determining if the block was invalid, return that
                 // error.
                 Err(BlockError::BeaconChainError(e)) => {
@@ -1249,16 +1613,18 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             let mut blocks = filtered_chain_segment.split_off(last_index);
             std::mem::swap(&mut blocks, &mut filtered_chain_segment);
 
-            // Verify the signature of the blocks, returning early if the signature is invalid.
-            let signature_verified_blocks = match signature_verify_chain_segment(blocks, self) {
-                Ok(blocks) => blocks,
-                Err(error) => {
-                    return ChainSegmentResult::Failed {
-                        imported_blocks,
-                        error,
+            // Verify the signature of the blocks, keeping only the blocks prior to the first block
+            // with an invalid signature, if any.
+            let (signature_verified_blocks, invalid_block_root) =
+                match signature_verify_chain_segment(blocks, self) {
+                    Ok(result) => result,
+                    Err(error) => {
+                        return ChainSegmentResult::Failed {
+                            imported_blocks,
+                            error,
+                        }
                     }
-                }
-            };
+                };
 
             // Import the blocks into the chain.
             for signature_verified_block in signature_verified_blocks {
@@ -1272,6 +1638,21 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     }
                 }
             }
+
+            // The blocks prior to the invalid block have been imported, such that only the block
+            // with an invalid signature (and its descendants) are rejected.
+            if let Some(block_root) = invalid_block_root {
+                debug!(
+                    self.log,
+                    "Invalid signature in chain segment";
+                    "block_root" => format!("{}", block_root),
+                    "imported_blocks" => imported_blocks,
+                );
+                return ChainSegmentResult::Failed {
+                    imported_blocks,
+                    error: BlockError::InvalidSignature,
+                };
+            }
         }
 
         ChainSegmentResult::Successful { imported_blocks }
@@ -1294,6 +1675,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let slot = block.message.slot;
         let graffiti_string = String::from_utf8(block.message.body.graffiti[..].to_vec())
             .unwrap_or_else(|_| format!("{:?}", &block.message.body.graffiti[..]));
+        let slasher_header = self.slasher.as_ref().map(|_| SignedBeaconBlockHeader {
+            message: block.message.block_header(),
+            signature: block.signature.clone(),
+        });
 
         match GossipVerifiedBlock::new(block, self) {
             Ok(verified) => {
@@ -1305,9 +1690,21 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     "root" => format!("{:?}", verified.block_root()),
                 );
 
+                if let Some(header) = slasher_header {
+                    self.register_block_header_with_slasher(header);
+                }
+
                 Ok(verified)
             }
             Err(e) => {
+                // A repeated proposal is rejected before its signature is verified, however it
+                // may be a double proposal that the slasher should know about.
+                if let (BlockError::RepeatProposal { .. }, Some(header)) = (&e, slasher_header) {
+                    if self.verify_block_header_signature(&header) {
+                        self.register_block_header_with_slasher(header);
+                    }
+                }
+
                 debug!(
                     self.log,
                     "Rejected gossip block";
@@ -1321,6 +1718,83 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         }
     }
 
+    /// Returns `true` if the signature of `header` is valid under the fork of the head state.
+    fn verify_block_header_signature(&self, header: &SignedBeaconBlockHeader) -> bool {
+        let fork = match self.head_info() {
+            Ok(head_info) => head_info.fork,
+            Err(_) => return false,
+        };
+        let pubkey_cache = match self
+            .validator_pubkey_cache
+            .try_read_for(VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT)
+        {
+            Some(pubkey_cache) => pubkey_cache,
+            None => return false,
+        };
+        let pubkey = match pubkey_cache.get(header.message.proposer_index as usize) {
+            Some(pubkey) => pubkey,
+            None => return false,
+        };
+
+        let domain = self.spec.get_domain(
+            header.message.slot.epoch(T::EthSpec::slots_per_epoch()),
+            Domain::BeaconProposer,
+            &fork,
+            self.genesis_validators_root,
+        );
+        let message = header.message.signing_root(domain);
+        header.signature.verify(message.as_bytes(), pubkey)
+    }
+
+    /// Queues a block header with a verified signature for processing by the slasher, if any.
+    fn register_block_header_with_slasher(&self, header: SignedBeaconBlockHeader) {
+        if let Some(slasher) = self.slasher.as_ref() {
+            slasher.accept_block_header(header);
+        }
+    }
+
+    /// Records that `verified_block` was received on gossip at `seen_timestamp`, a duration since
+    /// the UNIX epoch.
+    ///
+    /// The delay between the start of the slot of the block and `seen_timestamp` is added to the
+    /// metrics, and to the `BeaconBlockImported` event if the block is imported. Blocks received
+    /// after the attestation deadline (a third of the way through the slot) are counted as late
+    /// for their proposer, if it is monitored.
+    pub fn observe_gossip_block_delay(
+        &self,
+        verified_block: &GossipVerifiedBlock<T>,
+        seen_timestamp: Duration,
+    ) {
+        let block = &verified_block.block.message;
+        let slot_start = match self.slot_clock.start_of(block.slot) {
+            Some(slot_start) => slot_start,
+            None => return,
+        };
+        // A block received before the start of its slot (due to clock disparity) is not delayed.
+        let delay = seen_timestamp
+            .checked_sub(slot_start)
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        metrics::observe(&metrics::BLOCK_GOSSIP_DELAY, delay.as_secs_f64());
+
+        if delay > self.slot_clock.slot_duration() / 3 {
+            self.validator_monitor
+                .read()
+                .register_late_gossip_block(block);
+            debug!(
+                self.log,
+                "Late block received on gossip";
+                "delay_ms" => delay.as_millis() as u64,
+                "proposer_index" => block.proposer_index,
+                "slot" => block.slot,
+                "root" => format!("{:?}", verified_block.block_root),
+            );
+        }
+
+        self.block_delay_cache
+            .observe(verified_block.block_root, block.slot, delay);
+    }
+
     /// Returns `Ok(block_root)` if the given `unverified_block` was successfully verified and
     /// imported into the chain.
     ///
@@ -1366,9 +1840,15 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 // Increment the Prometheus counter for block processing successes.
                 metrics::inc_counter(&metrics::BLOCK_PROCESSING_SUCCESSES);
 
+                let observed_delay_ms = self
+                    .block_delay_cache
+                    .take(&block_root)
+                    .map(|delay| delay.as_millis() as u64);
+
                 let _ = self.event_handler.register(EventKind::BeaconBlockImported {
                     block_root: block_root,
                     block: Box::new(block),
+                    observed_delay_ms,
                 });
 
                 Ok(block_root)
@@ -1397,6 +1877,13 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     "reason" => format!("{:?}", other),
                 );
 
+                if self
+                    .invalid_block_cache
+                    .observe_error(block.canonical_root(), &other)
+                {
+                    metrics::inc_counter(&metrics::BLOCK_PROCESSING_INVALID_BLOCKS_CACHED);
+                }
+
                 let _ = self.event_handler.register(EventKind::BeaconBlockRejected {
                     reason: format!("Invalid block: {:?}", other),
                     block: Box::new(block),
@@ -1412,6 +1899,136 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         result
     }
 
+    /// Verifies that the canonical chain does not conflict with the weak subjectivity checkpoint,
+    /// if the checkpoint epoch has been finalized.
+    ///
+    /// This is called when the chain is built, since the database may contain blocks which were
+    /// imported before the checkpoint was given.
+    pub fn verify_weak_subjectivity_checkpoint(&self) -> Result<(), Error> {
+        let checkpoint = match &self.weak_subjectivity_checkpoint {
+            Some(checkpoint) => checkpoint.clone(),
+            None => return Ok(()),
+        };
+
+        let head = self.head_info()?;
+        if head.finalized_checkpoint.epoch < checkpoint.epoch {
+            info!(
+                self.log,
+                "Weak subjectivity checkpoint not yet finalized";
+                "checkpoint_epoch" => checkpoint.epoch,
+                "checkpoint_root" => format!("{}", checkpoint.root),
+                "finalized_epoch" => head.finalized_checkpoint.epoch,
+            );
+            return Ok(());
+        }
+
+        // As for checkpoints, a skipped slot takes the root of the closest prior block.
+        let checkpoint_slot = checkpoint.epoch.start_slot(T::EthSpec::slots_per_epoch());
+        let found = process_results(self.rev_iter_block_roots()?, |mut iter| {
+            iter.find(|(_, slot)| *slot <= checkpoint_slot)
+                .map(|(root, _)| root)
+        })?;
+
+        match found {
+            Some(found) if found != checkpoint.root => {
+                self.halt_on_weak_subjectivity_conflict(&checkpoint, found);
+                Err(Error::WeakSubjectivityConflict { checkpoint, found })
+            }
+            _ => {
+                info!(
+                    self.log,
+                    "Weak subjectivity checkpoint verified";
+                    "checkpoint_epoch" => checkpoint.epoch,
+                    "checkpoint_root" => format!("{}", checkpoint.root),
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns an error if `state`, the post-state of the block with `block_root`, has finalized
+    /// the epoch of the weak subjectivity checkpoint through a different root.
+    ///
+    /// Such a block is rejected and a shutdown of the beacon node is requested, since the network
+    /// it is following is either under attack or the checkpoint is incorrect.
+    ///
+    /// The root at the checkpoint epoch is only available from `state` for
+    /// `SLOTS_PER_HISTORICAL_ROOT` slots. Past that, the block must descend from our own finalized
+    /// block, which was itself checked if it is at or after the checkpoint epoch.
+    fn check_block_against_weak_subjectivity_checkpoint(
+        &self,
+        block_root: Hash256,
+        state: &BeaconState<T::EthSpec>,
+    ) -> Result<(), BlockError> {
+        let checkpoint = match &self.weak_subjectivity_checkpoint {
+            Some(checkpoint) => checkpoint.clone(),
+            None => return Ok(()),
+        };
+
+        let finalized_checkpoint = &state.finalized_checkpoint;
+        let found = if finalized_checkpoint.epoch < checkpoint.epoch {
+            return Ok(());
+        } else if finalized_checkpoint.epoch == checkpoint.epoch {
+            finalized_checkpoint.root
+        } else {
+            match state.get_block_root(checkpoint.epoch.start_slot(T::EthSpec::slots_per_epoch())) {
+                Ok(root) => *root,
+                Err(_) => {
+                    let finalized_epoch = self.head_info()?.finalized_checkpoint.epoch;
+                    if finalized_epoch < checkpoint.epoch {
+                        warn!(
+                            self.log,
+                            "Unable to check block against weak subjectivity checkpoint";
+                            "msg" => "the checkpoint root is no longer in the block's state",
+                            "block_root" => format!("{}", block_root),
+                            "block_slot" => state.slot,
+                            "checkpoint_epoch" => checkpoint.epoch,
+                            "finalized_epoch" => finalized_epoch,
+                        );
+                    }
+                    return Ok(());
+                }
+            }
+        };
+
+        if found == checkpoint.root {
+            Ok(())
+        } else {
+            crit!(
+                self.log,
+                "Block conflicts with weak subjectivity checkpoint";
+                "block_root" => format!("{}", block_root),
+                "block_slot" => state.slot,
+            );
+            self.halt_on_weak_subjectivity_conflict(&checkpoint, found);
+            Err(BlockError::WeakSubjectivityConflict { checkpoint, found })
+        }
+    }
+
+    /// Loudly reports a conflict with the weak subjectivity checkpoint and requests a shutdown of
+    /// the beacon node, since it is either following an attacker's chain or was misconfigured.
+    fn halt_on_weak_subjectivity_conflict(&self, checkpoint: &Checkpoint, found: Hash256) {
+        crit!(
+            self.log,
+            "Weak subjectivity checkpoint verification failed";
+            "msg" => "the chain may be under a long-range attack, or the checkpoint is incorrect. \
+                      Shutting down",
+            "checkpoint_epoch" => checkpoint.epoch,
+            "checkpoint_root" => format!("{}", checkpoint.root),
+            "found_root" => format!("{}", found),
+        );
+
+        if let Some(mut sender) = self.shutdown_sender.clone() {
+            if let Err(e) = sender.try_send("Weak subjectivity checkpoint verification failed") {
+                crit!(
+                    self.log,
+                    "Unable to request shutdown";
+                    "error" => format!("{:?}", e),
+                );
+            }
+        }
+    }
+
     /// Accepts a fully-verified block and imports it into the chain without performing any
     /// additional verification.
     ///
@@ -1428,6 +2045,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let parent_block = fully_verified_block.parent_block;
         let intermediate_states = fully_verified_block.intermediate_states;
 
+        // Refuse to import a block which finalizes a chain conflicting with the weak subjectivity
+        // checkpoint, before it reaches fork choice or the database.
+        self.check_block_against_weak_subjectivity_checkpoint(block_root, &state)?;
+
         let attestation_observation_timer =
             metrics::start_timer(&metrics::BLOCK_PROCESSING_ATTESTATION_OBSERVATION);
 
@@ -1454,10 +2075,15 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         // We perform this _before_ adding the block to fork choice because the pubkey cache is
         // used by attestation processing which will only process an attestation if the block is
         // known to fork choice. This ordering ensure that the pubkey cache is always up-to-date.
-        self.validator_pubkey_cache
+        let mut validator_pubkey_cache = self
+            .validator_pubkey_cache
             .try_write_for(VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT)
-            .ok_or_else(|| Error::ValidatorPubkeyCacheLockTimeout)?
-            .import_new_pubkeys(&state)?;
+            .ok_or_else(|| Error::ValidatorPubkeyCacheLockTimeout)?;
+        validator_pubkey_cache.import_new_pubkeys(&state)?;
+        self.validator_monitor
+            .write()
+            .update_validator_indices(&validator_pubkey_cache);
+        drop(validator_pubkey_cache);
 
         // If the imported block is in the previous or current epochs (according to the
         // wall-clock), check to see if this is the first block of the epoch. If so, add the
@@ -1499,6 +2125,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         metrics::stop_timer(fork_choice_register_timer);
 
+        self.register_block_with_validator_monitor(block, parent_block.slot(), &state);
+        self.register_block_with_slasher(&signed_block, &state);
+
         metrics::observe(
             &metrics::OPERATIONS_PER_BLOCK_ATTESTATION,
             block.body.attestations.len() as f64,
@@ -1551,6 +2180,97 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(block_root)
     }
 
+    /// Computes the root of `state`, using and updating its tree hash cache unless the cache is
+    /// disabled.
+    pub(crate) fn compute_state_root(
+        &self,
+        state: &mut BeaconState<T::EthSpec>,
+    ) -> Result<Hash256, BeaconStateError> {
+        if self.disable_tree_hash_cache {
+            state.drop_tree_hash_cache();
+            Ok(state.canonical_root())
+        } else {
+            state.update_tree_hash_cache()
+        }
+    }
+
+    /// Informs the validator monitor of the proposer, attestations and slashings of `block`, and of
+    /// the proposers of any slots skipped between `parent_slot` and `block`.
+    ///
+    /// `state` must be the post-state of `block`.
+    fn register_block_with_validator_monitor(
+        &self,
+        block: &BeaconBlock<T::EthSpec>,
+        parent_slot: Slot,
+        state: &BeaconState<T::EthSpec>,
+    ) {
+        let validator_monitor = self.validator_monitor.read();
+        if validator_monitor.num_validators() == 0 {
+            return;
+        }
+
+        validator_monitor.register_block(block);
+
+        // The proposers of skipped slots can only be computed from `state` for its own epoch.
+        let epoch_start_slot = state
+            .current_epoch()
+            .start_slot(T::EthSpec::slots_per_epoch());
+        let first_skipped_slot = std::cmp::max(parent_slot + 1, epoch_start_slot);
+        for slot in first_skipped_slot.as_u64()..block.slot.as_u64() {
+            let slot = Slot::new(slot);
+            if let Ok(proposer_index) = state.get_beacon_proposer_index(slot, &self.spec) {
+                validator_monitor.register_missed_block(proposer_index as u64, slot);
+            }
+        }
+
+        for attestation in &block.body.attestations {
+            let indexed_attestation = state
+                .get_beacon_committee(attestation.data.slot, attestation.data.index)
+                .map_err(Into::into)
+                .and_then(|committee| get_indexed_attestation(committee.committee, attestation));
+            if let Ok(indexed_attestation) = indexed_attestation {
+                validator_monitor.register_attestation_in_block(&indexed_attestation, block.slot);
+            }
+        }
+
+        for slashing in &block.body.proposer_slashings {
+            validator_monitor.register_proposer_slashing(slashing);
+        }
+
+        for slashing in &block.body.attester_slashings {
+            validator_monitor.register_attester_slashing(slashing);
+        }
+    }
+
+    /// Queues the header and attestations of an imported block for processing by the slasher,
+    /// if any.
+    fn register_block_with_slasher(
+        &self,
+        signed_block: &SignedBeaconBlock<T::EthSpec>,
+        state: &BeaconState<T::EthSpec>,
+    ) {
+        let slasher = match self.slasher.as_ref() {
+            Some(slasher) => slasher,
+            None => return,
+        };
+
+        let block = &signed_block.message;
+        slasher.accept_block_header(SignedBeaconBlockHeader {
+            message: block.block_header(),
+            signature: signed_block.signature.clone(),
+        });
+
+        for attestation in &block.body.attestations {
+            let indexed_attestation = state
+                .get_beacon_committee(attestation.data.slot, attestation.data.index)
+                .map_err(Into::into)
+                .and_then(|committee| get_indexed_attestation(committee.committee, attestation));
+            if let Ok(indexed_attestation) = indexed_attestation {
+                slasher.accept_attestation(indexed_attestation);
+            }
+        }
+    }
+
     /// Produce a new block at the given `slot`.
     ///
     /// The produced block will not be inherently valid, it must be signed by a block producer.
@@ -1560,9 +2280,19 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         randao_reveal: Signature,
         slot: Slot,
     ) -> Result<BeaconBlockAndState<T::EthSpec>, BlockProductionError> {
-        let state = self
-            .state_at_slot(slot - 1, StateSkipConfig::WithStateRoots)
-            .map_err(|_| BlockProductionError::UnableToProduceAtSlot(slot))?;
+        let head_block_root = self
+            .head_info()
+            .map_err(|_| BlockProductionError::UnableToProduceAtSlot(slot))?
+            .block_root;
+
+        // Avoid performing epoch processing on the critical path if the state advance timer has
+        // already advanced the head state to `slot`.
+        let state = match self.get_advanced_head_state(head_block_root, slot) {
+            Some(state) => state,
+            None => self
+                .state_at_slot(slot - 1, StateSkipConfig::WithStateRoots)
+                .map_err(|_| BlockProductionError::UnableToProduceAtSlot(slot))?,
+        };
 
         self.produce_block_on_state(state, slot, randao_reveal)
     }
@@ -1618,21 +2348,6 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             .deposits_for_block_inclusion(&state, &eth1_data, &self.spec)?
             .into();
 
-        // Map from attestation head block root to shuffling compatibility.
-        // Used to memoize the `attestation_shuffling_is_compatible` function.
-        let mut shuffling_filter_cache = HashMap::new();
-        let attestation_filter = |att: &&Attestation<T::EthSpec>| -> bool {
-            *shuffling_filter_cache
-                .entry((att.data.beacon_block_root, att.data.target.epoch))
-                .or_insert_with(|| {
-                    self.shuffling_is_compatible(
-                        &att.data.beacon_block_root,
-                        att.data.target.epoch,
-                        &state,
-                    )
-                })
-        };
-
         let mut block = SignedBeaconBlock {
             message: BeaconBlock {
                 slot: state.slot,
@@ -1645,11 +2360,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     graffiti,
                     proposer_slashings: proposer_slashings.into(),
                     attester_slashings: attester_slashings.into(),
-                    attestations: self
-                        .op_pool
-                        .get_attestations(&state, attestation_filter, &self.spec)
-                        .map_err(BlockProductionError::OpPoolError)?
-                        .into(),
+                    attestations: self.get_attestations_for_block(&state)?.into(),
                     deposits,
                     voluntary_exits: self.op_pool.get_voluntary_exits(&state, &self.spec).into(),
                 },
@@ -1666,7 +2377,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             &self.spec,
         )?;
 
-        let state_root = state.update_tree_hash_cache()?;
+        let state_root = self.compute_state_root(&mut state)?;
 
         block.message.state_root = state_root;
 
@@ -1751,9 +2462,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         // Attempt to detect if the new head is not on the same chain as the previous block
         // (i.e., a re-org).
         //
-        // Note: this will declare a re-org if we skip `SLOTS_PER_HISTORICAL_ROOT` blocks
-        // between calls to fork choice without swapping between chains. This seems like an
-        // extreme-enough scenario that a warning is fine.
+        // Note: this will suspect a re-org if we skip `SLOTS_PER_HISTORICAL_ROOT` blocks
+        // between calls to fork choice without swapping between chains, so a re-org is only
+        // declared if the previous head is not the common ancestor of the two heads.
         let is_reorg = current_head.block_root
             != new_head
                 .beacon_state
@@ -1762,16 +2473,49 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 .unwrap_or_else(|_| Hash256::random());
 
         if is_reorg {
-            metrics::inc_counter(&metrics::FORK_CHOICE_REORG_COUNT);
-            warn!(
-                self.log,
-                "Beacon chain re-org";
-                "previous_head" => format!("{}", current_head.block_root),
-                "previous_slot" => current_head.slot,
-                "new_head_parent" => format!("{}", new_head.beacon_block.parent_root()),
-                "new_head" => format!("{}", beacon_block_root),
-                "new_slot" => new_head.beacon_block.slot()
-            );
+            match self.find_common_ancestor(current_head.block_root, beacon_block_root) {
+                // The new head descends from the previous head, it is just too far ahead for its
+                // state to contain the previous head.
+                Ok((common_ancestor, _)) if common_ancestor == current_head.block_root => debug!(
+                    self.log,
+                    "Head beacon block descends from previous head";
+                    "previous_head" => format!("{}", current_head.block_root),
+                    "previous_slot" => current_head.slot,
+                    "root" => format!("{}", beacon_block_root),
+                    "slot" => new_head.beacon_block.slot(),
+                ),
+                Ok((common_ancestor, common_ancestor_slot)) => {
+                    metrics::inc_counter(&metrics::FORK_CHOICE_REORG_COUNT);
+                    let depth = current_head.slot.as_u64() - common_ancestor_slot.as_u64();
+                    metrics::observe(&metrics::FORK_CHOICE_REORG_DEPTH, depth as f64);
+                    warn!(
+                        self.log,
+                        "Beacon chain re-org";
+                        "previous_head" => format!("{}", current_head.block_root),
+                        "previous_slot" => current_head.slot,
+                        "new_head_parent" => format!("{}", new_head.beacon_block.parent_root()),
+                        "new_head" => format!("{}", beacon_block_root),
+                        "new_slot" => new_head.beacon_block.slot(),
+                        "common_ancestor" => format!("{}", common_ancestor),
+                        "common_ancestor_slot" => common_ancestor_slot,
+                        "reorg_depth" => depth,
+                    );
+                    let _ = self.event_handler.register(EventKind::ChainReorg {
+                        slot: new_head.beacon_block.slot(),
+                        depth,
+                        old_head_block: current_head.block_root,
+                        new_head_block: beacon_block_root,
+                        common_ancestor,
+                    });
+                }
+                Err(e) => error!(
+                    self.log,
+                    "Unable to find re-org common ancestor";
+                    "previous_head" => format!("{}", current_head.block_root),
+                    "new_head" => format!("{}", beacon_block_root),
+                    "error" => format!("{:?}", e),
+                ),
+            }
         } else {
             debug!(
                 self.log,
@@ -1856,7 +2600,87 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         trace!(self.log, "Running beacon chain per slot tasks");
         if let Some(slot) = self.slot_clock.now() {
             self.naive_aggregation_pool.prune(slot);
+            // Any block received on gossip more than an epoch ago has either been imported or
+            // will not be.
+            self.block_delay_cache
+                .prune(slot.saturating_sub(T::EthSpec::slots_per_epoch()));
+        }
+    }
+
+    /// Advances a clone of the head state into the next slot, storing it such that block
+    /// production and state skips at the next slot do not need to perform per-slot (and
+    /// potentially per-epoch) processing on the critical path.
+    ///
+    /// Intended to be called towards the end of each slot. No work is done if the head is more
+    /// than an epoch behind the next slot, since the node is likely syncing.
+    pub fn advance_head_state(&self) -> Result<(), Error> {
+        let next_slot = self.slot()? + 1;
+        let head_info = self.head_info()?;
+
+        let already_advanced = self
+            .advanced_head_state
+            .try_read_for(HEAD_LOCK_TIMEOUT)
+            .ok_or_else(|| Error::AdvancedHeadStateLockTimeout)?
+            .as_ref()
+            .map_or(false, |(root, state)| {
+                *root == head_info.block_root && state.slot == next_slot
+            });
+
+        if already_advanced
+            || head_info.slot >= next_slot
+            || head_info.slot + T::EthSpec::slots_per_epoch() < next_slot
+        {
+            return Ok(());
+        }
+
+        let head = self.head()?;
+        let head_block_root = head.beacon_block_root;
+        let mut state = head.beacon_state;
+
+        let timer = metrics::start_timer(&metrics::STATE_ADVANCE_TIMES);
+
+        // The root of the head state is known, avoid hashing it again.
+        let mut state_root = if state.slot == head.beacon_block.slot() {
+            Some(head.beacon_state_root)
+        } else {
+            None
+        };
+        while state.slot < next_slot {
+            per_slot_processing(&mut state, state_root.take(), &self.spec)?;
         }
+
+        state.build_committee_cache(RelativeEpoch::Current, &self.spec)?;
+        state.build_committee_cache(RelativeEpoch::Next, &self.spec)?;
+
+        metrics::stop_timer(timer);
+
+        trace!(
+            self.log,
+            "Advanced head state";
+            "head_block_root" => format!("{}", head_block_root),
+            "slot" => next_slot
+        );
+
+        *self
+            .advanced_head_state
+            .try_write_for(HEAD_LOCK_TIMEOUT)
+            .ok_or_else(|| Error::AdvancedHeadStateLockTimeout)? = Some((head_block_root, state));
+
+        Ok(())
+    }
+
+    /// Returns a clone of the state stored by `Self::advance_head_state`, if it was advanced from
+    /// `head_block_root` and is not beyond `slot`.
+    fn get_advanced_head_state(
+        &self,
+        head_block_root: Hash256,
+        slot: Slot,
+    ) -> Option<BeaconState<T::EthSpec>> {
+        self.advanced_head_state
+            .try_read_for(HEAD_LOCK_TIMEOUT)?
+            .as_ref()
+            .filter(|(root, state)| *root == head_block_root && state.slot <= slot)
+            .map(|(_, state)| state.clone_with_only_committee_caches())
     }
 
     /// Called after `self` has had a new block finalized.
@@ -2096,13 +2920,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
 impl<T: BeaconChainTypes> Drop for BeaconChain<T> {
     fn drop(&mut self) {
-        let drop = || -> Result<(), Error> {
-            self.persist_head_and_fork_choice()?;
-            self.persist_op_pool()?;
-            self.persist_eth1_cache()
-        };
-
-        if let Err(e) = drop() {
+        if let Err(e) = self.persist_to_disk() {
             error!(
                 self.log,
                 "Failed to persist on BeaconChain drop";
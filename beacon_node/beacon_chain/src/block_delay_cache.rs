@@ -0,0 +1,79 @@
+//! Provides the `BlockDelayCache` struct, which records how long after the start of its slot each
+//! block was first received on gossip.
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::time::Duration;
+use types::{Hash256, Slot};
+
+/// Maintains a cache of `block_root -> (block.slot, delay)`, where `delay` is the time between the
+/// start of `block.slot` and the block first being received on gossip.
+///
+/// Entries are removed when the block is imported. Blocks which are never imported are removed by
+/// `Self::prune`, which must be called manually.
+#[derive(Default)]
+pub struct BlockDelayCache {
+    items: RwLock<HashMap<Hash256, (Slot, Duration)>>,
+}
+
+impl BlockDelayCache {
+    /// Records that the block with `block_root` was received `delay` after the start of `slot`.
+    ///
+    /// Only the first observation of each block is kept.
+    pub fn observe(&self, block_root: Hash256, slot: Slot, delay: Duration) {
+        self.items
+            .write()
+            .entry(block_root)
+            .or_insert((slot, delay));
+    }
+
+    /// Removes and returns the observed delay of the block with `block_root`, if any.
+    pub fn take(&self, block_root: &Hash256) -> Option<Duration> {
+        self.items
+            .write()
+            .remove(block_root)
+            .map(|(_slot, delay)| delay)
+    }
+
+    /// Removes the observations of all blocks with a slot prior to `slot`.
+    pub fn prune(&self, slot: Slot) {
+        self.items
+            .write()
+            .retain(|_root, (block_slot, _delay)| *block_slot >= slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_first_observation() {
+        let cache = BlockDelayCache::default();
+        let root = Hash256::from_low_u64_be(1);
+
+        cache.observe(root, Slot::new(1), Duration::from_millis(100));
+        cache.observe(root, Slot::new(1), Duration::from_millis(200));
+
+        assert_eq!(cache.take(&root), Some(Duration::from_millis(100)));
+        assert_eq!(
+            cache.take(&root),
+            None,
+            "take should remove the observation"
+        );
+    }
+
+    #[test]
+    fn prunes_prior_slots() {
+        let cache = BlockDelayCache::default();
+        let old_root = Hash256::from_low_u64_be(1);
+        let new_root = Hash256::from_low_u64_be(2);
+
+        cache.observe(old_root, Slot::new(1), Duration::from_millis(100));
+        cache.observe(new_root, Slot::new(2), Duration::from_millis(100));
+        cache.prune(Slot::new(2));
+
+        assert_eq!(cache.take(&old_root), None);
+        assert_eq!(cache.take(&new_root), Some(Duration::from_millis(100)));
+    }
+}
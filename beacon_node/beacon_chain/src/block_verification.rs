@@ -65,8 +65,8 @@ use std::io::Write;
 use store::{Error as DBError, StateBatch};
 use tree_hash::TreeHash;
 use types::{
-    BeaconBlock, BeaconState, BeaconStateError, ChainSpec, CloneConfig, EthSpec, Hash256,
-    PublicKey, RelativeEpoch, SignedBeaconBlock, Slot,
+    BeaconBlock, BeaconState, BeaconStateError, ChainSpec, Checkpoint, CloneConfig, EthSpec,
+    Hash256, PublicKey, RelativeEpoch, SignedBeaconBlock, Slot,
 };
 
 mod block_processing_outcome;
@@ -130,6 +130,18 @@ pub enum BlockError {
     NonLinearSlots,
     /// The block failed the specification's `per_block_processing` function, it is invalid.
     PerBlockProcessingError(BlockProcessingError),
+    /// The block has previously failed the state transition. See `InvalidBlockCache`.
+    ///
+    /// The block is invalid.
+    KnownInvalidBlock(Hash256),
+    /// The block finalizes a chain which does not pass through the weak subjectivity checkpoint,
+    /// having the `found` root at the epoch of the checkpoint instead.
+    ///
+    /// Either the block is on a chain built by a long-range attack or the checkpoint is wrong.
+    WeakSubjectivityConflict {
+        checkpoint: Checkpoint,
+        found: Hash256,
+    },
     /// There was an error whilst processing the block. It is not necessarily invalid.
     BeaconChainError(BeaconChainError),
 }
@@ -175,25 +187,28 @@ impl From<DBError> for BlockError {
     }
 }
 
-/// Verify all signatures (except deposit signatures) on all blocks in the `chain_segment`. If all
-/// signatures are valid, the `chain_segment` is mapped to a `Vec<SignatureVerifiedBlock>` that can
-/// later be transformed into a `FullyVerifiedBlock` without re-checking the signatures. If any
-/// signature in the block is invalid, an `Err` is returned (it is not possible to known _which_
-/// signature was invalid).
+/// Verify all signatures (except deposit signatures) on all blocks in the `chain_segment`. The
+/// blocks with valid signatures are mapped to a `Vec<SignatureVerifiedBlock>` that can later be
+/// transformed into a `FullyVerifiedBlock` without re-checking the signatures.
+///
+/// The signatures of all blocks are verified in a single batch. If the batch is invalid, it is
+/// bisected to find the first block with an invalid signature, whose root is returned alongside
+/// the blocks which precede it (it is not possible to know _which_ signature of that block was
+/// invalid).
 ///
 /// ## Errors
 ///
 /// The given `chain_segment` must span no more than two epochs, otherwise an error will be
 /// returned.
 pub fn signature_verify_chain_segment<T: BeaconChainTypes>(
-    chain_segment: Vec<(Hash256, SignedBeaconBlock<T::EthSpec>)>,
+    mut chain_segment: Vec<(Hash256, SignedBeaconBlock<T::EthSpec>)>,
     chain: &BeaconChain<T>,
-) -> Result<Vec<SignatureVerifiedBlock<T>>, BlockError> {
+) -> Result<(Vec<SignatureVerifiedBlock<T>>, Option<Hash256>), BlockError> {
     let (mut parent, slot) = if let Some(block) = chain_segment.first().map(|(_, block)| block) {
         let parent = load_parent(&block.message, chain)?;
         (parent, block.slot())
     } else {
-        return Ok(vec![]);
+        return Ok((vec![], None));
     };
 
     let highest_slot = chain_segment
@@ -208,15 +223,34 @@ pub fn signature_verify_chain_segment<T: BeaconChainTypes>(
     )?;
 
     let pubkey_cache = get_validator_pubkey_cache(chain)?;
-    let mut signature_verifier = get_signature_verifier(&state, &pubkey_cache, &chain.spec);
+    let signatures_are_valid = |blocks: &[(Hash256, SignedBeaconBlock<T::EthSpec>)]| {
+        let mut signature_verifier = get_signature_verifier(&state, &pubkey_cache, &chain.spec);
+        for (block_root, block) in blocks {
+            signature_verifier.include_all_signatures(block, Some(*block_root))?;
+        }
+        Ok::<_, BlockError>(signature_verifier.verify().is_ok())
+    };
 
-    for (block_root, block) in &chain_segment {
-        signature_verifier.include_all_signatures(block, Some(*block_root))?;
-    }
+    let invalid_block_root = if signatures_are_valid(&chain_segment)? {
+        None
+    } else {
+        // Throughout the search, all blocks prior to `low` are valid and the blocks from `low` to
+        // `high` contain an invalid signature.
+        let (mut low, mut high) = (0, chain_segment.len());
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if signatures_are_valid(&chain_segment[low..mid])? {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
 
-    if signature_verifier.verify().is_err() {
-        return Err(BlockError::InvalidSignature);
-    }
+        metrics::inc_counter(&metrics::BLOCK_PROCESSING_SEGMENT_BISECTIONS);
+        let invalid_block_root = chain_segment[low].0;
+        chain_segment.truncate(low);
+        Some(invalid_block_root)
+    };
 
     drop(pubkey_cache);
 
@@ -233,7 +267,7 @@ pub fn signature_verify_chain_segment<T: BeaconChainTypes>(
         signature_verified_block.parent = Some(parent);
     }
 
-    Ok(signature_verified_blocks)
+    Ok((signature_verified_blocks, invalid_block_root))
 }
 
 /// A wrapper around a `SignedBeaconBlock` that indicates it has been approved for re-gossiping on
@@ -319,9 +353,15 @@ impl<T: BeaconChainTypes> GossipVerifiedBlock<T> {
             });
         }
 
-        let mut parent = load_parent(&block.message, chain)?;
         let block_root = get_block_root(&block);
 
+        // Do not gossip a block which is known to fail the state transition.
+        if chain.invalid_block_cache.contains(&block_root) {
+            return Err(BlockError::KnownInvalidBlock(block_root));
+        }
+
+        let mut parent = load_parent(&block.message, chain)?;
+
         let state = cheap_state_advance_to_obtain_committees(
             &mut parent.beacon_state,
             block.slot(),
@@ -407,9 +447,15 @@ impl<T: BeaconChainTypes> SignatureVerifiedBlock<T> {
         block: SignedBeaconBlock<T::EthSpec>,
         chain: &BeaconChain<T>,
     ) -> Result<Self, BlockError> {
-        let mut parent = load_parent(&block.message, chain)?;
         let block_root = get_block_root(&block);
 
+        // Do not re-process a block which is known to fail the state transition.
+        if chain.invalid_block_cache.contains(&block_root) {
+            return Err(BlockError::KnownInvalidBlock(block_root));
+        }
+
+        let mut parent = load_parent(&block.message, chain)?;
+
         let state = cheap_state_advance_to_obtain_committees(
             &mut parent.beacon_state,
             block.slot(),
@@ -560,6 +606,15 @@ impl<T: BeaconChainTypes> FullyVerifiedBlock<T> {
 
         let mut summaries = vec![];
 
+        // The tree hash cache of the parent state is reused if it was kept with the snapshot.
+        if !chain.disable_tree_hash_cache {
+            if parent.beacon_state.tree_hash_cache.is_some() {
+                metrics::inc_counter(&metrics::BLOCK_PROCESSING_TREE_HASH_CACHE_HITS);
+            } else {
+                metrics::inc_counter(&metrics::BLOCK_PROCESSING_TREE_HASH_CACHE_MISSES);
+            }
+        }
+
         // Transition the parent state to the block slot.
         let mut state = parent.beacon_state;
         let distance = block.slot().as_u64().saturating_sub(state.slot.as_u64());
@@ -570,7 +625,7 @@ impl<T: BeaconChainTypes> FullyVerifiedBlock<T> {
                 // This is a new state we've reached, so stage it for storage in the DB.
                 // Computing the state root here is time-equivalent to computing it during slot
                 // processing, but we get early access to it.
-                let state_root = state.update_tree_hash_cache()?;
+                let state_root = chain.compute_state_root(&mut state)?;
                 intermediate_states.add_state(state_root, &state)?;
                 state_root
             };
@@ -632,7 +687,7 @@ impl<T: BeaconChainTypes> FullyVerifiedBlock<T> {
 
         let state_root_timer = metrics::start_timer(&metrics::BLOCK_PROCESSING_STATE_ROOT);
 
-        let state_root = state.update_tree_hash_cache()?;
+        let state_root = chain.compute_state_root(&mut state)?;
 
         metrics::stop_timer(state_root_timer);
 
@@ -731,6 +786,11 @@ pub fn check_block_relevancy<T: BeaconChainTypes>(
         return Err(BlockError::BlockIsAlreadyKnown);
     }
 
+    // Do not re-run the state transition of a block which is known to fail it.
+    if chain.invalid_block_cache.contains(&block_root) {
+        return Err(BlockError::KnownInvalidBlock(block_root));
+    }
+
     Ok(block_root)
 }
 
@@ -776,8 +836,13 @@ fn load_parent<T: BeaconChainTypes>(
         .snapshot_cache
         .try_write_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
         .and_then(|mut snapshot_cache| snapshot_cache.try_remove(block.parent_root))
-        .map(|snapshot| Ok(Some(snapshot)))
+        .map(|snapshot| {
+            metrics::inc_counter(&metrics::BLOCK_PROCESSING_SNAPSHOT_CACHE_HITS);
+            Ok(Some(snapshot))
+        })
         .unwrap_or_else(|| {
+            metrics::inc_counter(&metrics::BLOCK_PROCESSING_SNAPSHOT_CACHE_MISSES);
+
             // Load the blocks parent block from the database, returning invalid if that block is not
             // found.
             //
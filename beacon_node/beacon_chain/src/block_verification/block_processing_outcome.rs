@@ -1,6 +1,6 @@
 use crate::{BeaconChainError, BlockError};
 use state_processing::BlockProcessingError;
-use types::{Hash256, Slot};
+use types::{Checkpoint, Hash256, Slot};
 
 /// This is a legacy object that is being kept around to reduce merge conflicts.
 ///
@@ -64,6 +64,13 @@ pub enum BlockProcessingOutcome {
     NonLinearSlots,
     /// The block could not be applied to the state, it is invalid.
     PerBlockProcessingError(BlockProcessingError),
+    /// The block has previously failed the state transition.
+    KnownInvalidBlock(Hash256),
+    /// The block finalizes a chain which conflicts with the weak subjectivity checkpoint.
+    WeakSubjectivityConflict {
+        checkpoint: Checkpoint,
+        found: Hash256,
+    },
 }
 
 impl BlockProcessingOutcome {
@@ -124,6 +131,12 @@ impl BlockProcessingOutcome {
             Err(BlockError::PerBlockProcessingError(e)) => {
                 Ok(BlockProcessingOutcome::PerBlockProcessingError(e))
             }
+            Err(BlockError::KnownInvalidBlock(root)) => {
+                Ok(BlockProcessingOutcome::KnownInvalidBlock(root))
+            }
+            Err(BlockError::WeakSubjectivityConflict { checkpoint, found }) => {
+                Ok(BlockProcessingOutcome::WeakSubjectivityConflict { checkpoint, found })
+            }
             Err(BlockError::BeaconChainError(e)) => Err(e),
         }
     }
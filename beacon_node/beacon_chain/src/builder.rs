@@ -10,14 +10,18 @@ use crate::persisted_beacon_chain::PersistedBeaconChain;
 use crate::shuffling_cache::ShufflingCache;
 use crate::snapshot_cache::{SnapshotCache, DEFAULT_SNAPSHOT_CACHE_SIZE};
 use crate::timeout_rw_lock::TimeoutRwLock;
+use crate::validator_monitor::{ValidatorMonitor, DEFAULT_AUTO_REGISTER_LIMIT};
 use crate::validator_pubkey_cache::ValidatorPubkeyCache;
 use crate::{
     BeaconChain, BeaconChainTypes, BeaconSnapshot, Eth1Chain, Eth1ChainBackend, EventHandler,
     ForkChoice,
 };
 use eth1::Config as Eth1Config;
+use futures::channel::mpsc::Sender;
 use operation_pool::{OperationPool, PersistedOperationPool};
+use parking_lot::RwLock;
 use proto_array_fork_choice::ProtoArrayForkChoice;
+use slasher::Slasher;
 use slog::{info, Logger};
 use slot_clock::{SlotClock, TestingSlotClock};
 use std::marker::PhantomData;
@@ -26,7 +30,8 @@ use std::sync::Arc;
 use std::time::Duration;
 use store::{HotColdDB, ItemStore};
 use types::{
-    BeaconBlock, BeaconState, ChainSpec, EthSpec, Hash256, Signature, SignedBeaconBlock, Slot,
+    BeaconBlock, BeaconState, ChainSpec, Checkpoint, EthSpec, Hash256, PublicKeyBytes, Signature,
+    SignedBeaconBlock, Slot,
 };
 
 pub const PUBKEY_CACHE_FILENAME: &str = "pubkey_cache.ssz";
@@ -109,6 +114,14 @@ pub struct BeaconChainBuilder<T: BeaconChainTypes> {
     validator_pubkey_cache: Option<ValidatorPubkeyCache>,
     spec: ChainSpec,
     disabled_forks: Vec<String>,
+    disable_tree_hash_cache: bool,
+    snapshot_cache_size: usize,
+    validator_monitor_pubkeys: Vec<PublicKeyBytes>,
+    validator_monitor_auto: bool,
+    validator_monitor_auto_limit: usize,
+    slasher: Option<Arc<Slasher<T::EthSpec>>>,
+    weak_subjectivity_checkpoint: Option<Checkpoint>,
+    shutdown_sender: Option<Sender<&'static str>>,
     log: Option<Logger>,
 }
 
@@ -153,6 +166,12 @@ where
             pubkey_cache_path: None,
             data_dir: None,
             disabled_forks: Vec::new(),
+            disable_tree_hash_cache: false,
+            snapshot_cache_size: DEFAULT_SNAPSHOT_CACHE_SIZE,
+            validator_monitor_pubkeys: Vec::new(),
+            validator_monitor_auto: false,
+            validator_monitor_auto_limit: DEFAULT_AUTO_REGISTER_LIMIT,
+            slasher: None,
             validator_pubkey_cache: None,
             spec: TEthSpec::default_spec(),
             log: None,
@@ -205,6 +224,52 @@ where
         self
     }
 
+    /// If `true`, state roots are computed without a tree hash cache, for debugging.
+    pub fn disable_tree_hash_cache(mut self, disable_tree_hash_cache: bool) -> Self {
+        self.disable_tree_hash_cache = disable_tree_hash_cache;
+        self
+    }
+
+    /// Sets the number of recent block and state pairs retained for block processing.
+    pub fn snapshot_cache_size(mut self, snapshot_cache_size: usize) -> Self {
+        self.snapshot_cache_size = snapshot_cache_size;
+        self
+    }
+
+    /// Sets the validators to be monitored by the `ValidatorMonitor`. If `auto_register` is `true`,
+    /// up to `auto_register_limit` validators which request duties from the HTTP API are
+    /// monitored too.
+    pub fn monitor_validators(
+        mut self,
+        auto_register: bool,
+        auto_register_limit: usize,
+        pubkeys: Vec<PublicKeyBytes>,
+    ) -> Self {
+        self.validator_monitor_auto = auto_register;
+        self.validator_monitor_auto_limit = auto_register_limit;
+        self.validator_monitor_pubkeys = pubkeys;
+        self
+    }
+
+    /// Sets the slasher which is fed with the attestations and blocks verified by the chain.
+    pub fn slasher(mut self, slasher: Arc<Slasher<TEthSpec>>) -> Self {
+        self.slasher = Some(slasher);
+        self
+    }
+
+    /// Sets a checkpoint which the canonical chain must pass through, as a protection against
+    /// long-range attacks.
+    pub fn weak_subjectivity_checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.weak_subjectivity_checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// Sets the channel used to request a shutdown of the beacon node on an unrecoverable error.
+    pub fn shutdown_sender(mut self, sender: Sender<&'static str>) -> Self {
+        self.shutdown_sender = Some(sender);
+        self
+    }
+
     /// Attempt to load an existing eth1 cache from the builder's `Store`.
     pub fn get_persisted_eth1_backend(&self) -> Result<Option<SszEth1>, String> {
         let store = self
@@ -454,6 +519,14 @@ where
                     .map_err(|e| format!("Unable to init validator pubkey cache: {:?}", e))
             })?;
 
+        let mut validator_monitor = ValidatorMonitor::new(
+            self.validator_monitor_pubkeys,
+            self.validator_monitor_auto,
+            self.validator_monitor_auto_limit,
+            log.clone(),
+        );
+        validator_monitor.update_validator_indices(&validator_pubkey_cache);
+
         let beacon_chain = BeaconChain {
             spec: self.spec,
             store: self
@@ -478,6 +551,8 @@ where
             observed_aggregators: <_>::default(),
             // TODO: allow for persisting and loading the pool from disk.
             observed_block_producers: <_>::default(),
+            block_delay_cache: <_>::default(),
+            invalid_block_cache: <_>::default(),
             eth1_chain: self.eth1_chain,
             genesis_validators_root: canonical_head.beacon_state.genesis_validators_root,
             canonical_head: TimeoutRwLock::new(canonical_head.clone()),
@@ -492,12 +567,18 @@ where
                 .ok_or_else(|| "Cannot build without an event handler".to_string())?,
             head_tracker: Arc::new(self.head_tracker.unwrap_or_default()),
             snapshot_cache: TimeoutRwLock::new(SnapshotCache::new(
-                DEFAULT_SNAPSHOT_CACHE_SIZE,
+                self.snapshot_cache_size,
                 canonical_head,
             )),
+            advanced_head_state: TimeoutRwLock::new(None),
             shuffling_cache: TimeoutRwLock::new(ShufflingCache::new()),
             validator_pubkey_cache: TimeoutRwLock::new(validator_pubkey_cache),
+            validator_monitor: RwLock::new(validator_monitor),
+            slasher: self.slasher,
+            weak_subjectivity_checkpoint: self.weak_subjectivity_checkpoint,
+            shutdown_sender: self.shutdown_sender,
             disabled_forks: self.disabled_forks,
+            disable_tree_hash_cache: self.disable_tree_hash_cache,
             log: log.clone(),
         };
 
@@ -505,6 +586,12 @@ where
             .head()
             .map_err(|e| format!("Failed to get head: {:?}", e))?;
 
+        // The database may already contain blocks which finalized the epoch of the weak
+        // subjectivity checkpoint.
+        beacon_chain
+            .verify_weak_subjectivity_checkpoint()
+            .map_err(|e| format!("Weak subjectivity checkpoint verification failed: {:?}", e))?;
+
         info!(
             log,
             "Beacon chain initialized";
@@ -59,6 +59,7 @@ pub enum BeaconChainError {
     InvariantViolated(String),
     SszTypesError(SszTypesError),
     CanonicalHeadLockTimeout,
+    AdvancedHeadStateLockTimeout,
     AttestationCacheLockTimeout,
     ValidatorPubkeyCacheLockTimeout,
     IncorrectStateForAttestation(RelativeEpochError),
@@ -74,6 +75,17 @@ pub enum BeaconChainError {
     ObservedAttestersError(ObservedAttestersError),
     ObservedBlockProducersError(ObservedBlockProducersError),
     ArithError(ArithError),
+    AttestationError(AttestationError),
+    NoCommonAncestor {
+        old_head: Hash256,
+        new_head: Hash256,
+    },
+    /// The canonical chain has finalized the `found` root at the epoch of the weak subjectivity
+    /// checkpoint, rather than the root of the checkpoint.
+    WeakSubjectivityConflict {
+        checkpoint: Checkpoint,
+        found: Hash256,
+    },
 }
 
 easy_from_to!(SlotProcessingError, BeaconChainError);
@@ -86,6 +98,7 @@ easy_from_to!(ObservedAttestersError, BeaconChainError);
 easy_from_to!(ObservedBlockProducersError, BeaconChainError);
 easy_from_to!(BlockSignatureVerifierError, BeaconChainError);
 easy_from_to!(ArithError, BeaconChainError);
+easy_from_to!(AttestationError, BeaconChainError);
 
 #[derive(Debug)]
 pub enum BlockProductionError {
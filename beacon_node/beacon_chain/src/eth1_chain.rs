@@ -12,8 +12,8 @@ use std::iter::DoubleEndedIterator;
 use std::marker::PhantomData;
 use store::{DBColumn, Error as StoreError, StoreItem};
 use types::{
-    BeaconState, BeaconStateError, ChainSpec, Deposit, Eth1Data, EthSpec, Hash256, Slot, Unsigned,
-    DEPOSIT_TREE_DEPTH,
+    BeaconState, BeaconStateError, ChainSpec, Deposit, DepositTreeSnapshot, Eth1Data, EthSpec,
+    Hash256, Slot, Unsigned, DEPOSIT_TREE_DEPTH,
 };
 
 type BlockNumber = u64;
@@ -136,6 +136,25 @@ where
         }
     }
 
+    /// Returns a snapshot of the deposit tree at the `Eth1Data` of the `finalized_state`, which
+    /// may be used to bootstrap the deposit cache of another node.
+    ///
+    /// Returns `None` if the `finalized_state` has not processed all of the deposits in its
+    /// `Eth1Data` (since deposits in a snapshot can not be included in blocks), or the snapshot is
+    /// not known to the backend.
+    pub fn deposit_snapshot(
+        &self,
+        finalized_state: &BeaconState<E>,
+    ) -> Option<DepositTreeSnapshot> {
+        if self.use_dummy_backend
+            || finalized_state.eth1_deposit_index != finalized_state.eth1_data.deposit_count
+        {
+            None
+        } else {
+            self.backend.deposit_snapshot(&finalized_state.eth1_data)
+        }
+    }
+
     /// Instantiate `Eth1Chain` from a persisted `SszEth1`.
     ///
     /// The `Eth1Chain` will have the same caches as the persisted `SszEth1`.
@@ -187,6 +206,9 @@ pub trait Eth1ChainBackend<T: EthSpec>: Sized + Send + Sync {
         spec: &ChainSpec,
     ) -> Result<Vec<Deposit>, Error>;
 
+    /// Returns a snapshot of the deposit tree at `eth1_data`, if it is known.
+    fn deposit_snapshot(&self, eth1_data: &Eth1Data) -> Option<DepositTreeSnapshot>;
+
     /// Encode the `Eth1ChainBackend` instance to bytes.
     fn as_bytes(&self) -> Vec<u8>;
 
@@ -228,6 +250,11 @@ impl<T: EthSpec> Eth1ChainBackend<T> for DummyEth1ChainBackend<T> {
         Ok(vec![])
     }
 
+    /// The dummy back-end has no deposit tree.
+    fn deposit_snapshot(&self, _: &Eth1Data) -> Option<DepositTreeSnapshot> {
+        None
+    }
+
     /// Return empty Vec<u8> for dummy backend.
     fn as_bytes(&self) -> Vec<u8> {
         Vec::new()
@@ -383,6 +410,44 @@ impl<T: EthSpec> Eth1ChainBackend<T> for CachingEth1Backend<T> {
         }
     }
 
+    fn deposit_snapshot(&self, eth1_data: &Eth1Data) -> Option<DepositTreeSnapshot> {
+        let block_number = self
+            .core
+            .blocks()
+            .read()
+            .iter()
+            .find(|block| block.hash == eth1_data.block_hash)?
+            .number;
+
+        let snapshot = self
+            .core
+            .deposits()
+            .read()
+            .cache
+            .get_deposit_snapshot(eth1_data.deposit_count, eth1_data.block_hash, block_number)
+            .map_err(|e| {
+                debug!(
+                    self.log,
+                    "Unable to get deposit snapshot";
+                    "error" => format!("{:?}", e),
+                    "deposit_count" => eth1_data.deposit_count,
+                )
+            })
+            .ok()?;
+
+        if snapshot.deposit_root == eth1_data.deposit_root {
+            Some(snapshot)
+        } else {
+            error!(
+                self.log,
+                "Deposit snapshot root does not match eth1 data";
+                "snapshot_root" => format!("{:?}", snapshot.deposit_root),
+                "eth1_data_root" => format!("{:?}", eth1_data.deposit_root),
+            );
+            None
+        }
+    }
+
     /// Return encoded byte representation of the block and deposit caches.
     fn as_bytes(&self) -> Vec<u8> {
         self.core.as_bytes()
@@ -1,10 +1,11 @@
 use bus::Bus;
 use parking_lot::Mutex;
 use serde_derive::{Deserialize, Serialize};
-use slog::{error, Logger};
+use slog::{error, warn, Logger};
 use std::marker::PhantomData;
 use std::sync::Arc;
-use types::{Attestation, Epoch, EthSpec, Hash256, SignedBeaconBlock, SignedBeaconBlockHash};
+use tokio::sync::mpsc::{channel, error::TrySendError, Receiver, Sender};
+use types::{Attestation, Epoch, EthSpec, Hash256, SignedBeaconBlock, SignedBeaconBlockHash, Slot};
 pub use websocket_server::WebSocketSender;
 
 pub trait EventHandler<T: EthSpec>: Sized + Send + Sync {
@@ -22,25 +23,118 @@ impl<T: EthSpec> EventHandler<T> for WebSocketSender<T> {
     }
 }
 
+/// The buses on which `ServerSentEvents` publishes events, to be read by server-sent event
+/// streams.
+pub struct EventBuses<T: EthSpec> {
+    /// The roots of each new head block.
+    pub head_changed: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+    /// Head changes, re-orgs, imported blocks and finalization events.
+    pub chain_events: Arc<Mutex<EventSubscribers<EventKind<T>>>>,
+}
+
+/// Publishes events to any number of subscribers, each with its own bounded queue.
+///
+/// Unlike a `Bus`, a subscriber which does not keep up with the events does not cause them to be
+/// dropped for every other subscriber. Instead, the lagging subscriber is unsubscribed, which ends
+/// its stream once it has read the events already in its queue.
+pub struct EventSubscribers<E> {
+    senders: Vec<Sender<E>>,
+    capacity: usize,
+}
+
+impl<E: Clone> EventSubscribers<E> {
+    /// Creates an instance in which each subscriber can have up to `capacity` unread events.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            senders: vec![],
+            capacity,
+        }
+    }
+
+    /// Adds a subscriber, returning the receiver of all subsequent events.
+    pub fn subscribe(&mut self) -> Receiver<E> {
+        let (sender, receiver) = channel(self.capacity);
+        self.senders.push(sender);
+        receiver
+    }
+
+    /// Returns the number of subscribers.
+    pub fn len(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Returns `true` if there are no subscribers.
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+
+    /// Sends `event` to every subscriber, unsubscribing those which have disconnected and those
+    /// whose queue is full. Returns the number of subscribers which were unsubscribed because they
+    /// were lagging.
+    pub fn send(&mut self, event: &E) -> usize {
+        let mut lagging = 0;
+        self.senders = self
+            .senders
+            .drain(..)
+            .filter_map(|mut sender| match sender.try_send(event.clone()) {
+                Ok(()) => Some(sender),
+                Err(TrySendError::Full(_)) => {
+                    lagging += 1;
+                    None
+                }
+                Err(TrySendError::Closed(_)) => None,
+            })
+            .collect();
+        lagging
+    }
+}
+
+impl<T: EthSpec> Clone for EventBuses<T> {
+    fn clone(&self) -> Self {
+        Self {
+            head_changed: self.head_changed.clone(),
+            chain_events: self.chain_events.clone(),
+        }
+    }
+}
+
 pub struct ServerSentEvents<T: EthSpec> {
     // Bus<> is itself Sync + Send.  We use Mutex<> here only because of the surrounding code does
     // not enforce mutability statically (i.e. relies on interior mutability).
     head_changed_queue: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+    chain_events_queue: Arc<Mutex<EventSubscribers<EventKind<T>>>>,
     log: Logger,
-    _phantom: PhantomData<T>,
 }
 
 impl<T: EthSpec> ServerSentEvents<T> {
-    pub fn new(log: Logger) -> (Self, Arc<Mutex<Bus<SignedBeaconBlockHash>>>) {
-        let bus = Bus::new(T::slots_per_epoch() as usize);
-        let mutex = Mutex::new(bus);
-        let arc = Arc::new(mutex);
+    pub fn new(log: Logger) -> (Self, EventBuses<T>) {
+        let buses = EventBuses {
+            head_changed: Arc::new(Mutex::new(Bus::new(T::slots_per_epoch() as usize))),
+            // Up to one head change, one re-org and one imported block per slot, plus
+            // finalization.
+            chain_events: Arc::new(Mutex::new(EventSubscribers::new(
+                T::slots_per_epoch() as usize * 4,
+            ))),
+        };
         let this = Self {
-            head_changed_queue: arc.clone(),
+            head_changed_queue: buses.head_changed.clone(),
+            chain_events_queue: buses.chain_events.clone(),
             log: log,
-            _phantom: PhantomData,
         };
-        (this, arc)
+        (this, buses)
+    }
+
+    /// Publishes `kind` to the chain events streams, closing the streams which are full.
+    fn broadcast_chain_event(&self, kind: EventKind<T>) {
+        let lagging = self.chain_events_queue.lock().send(&kind);
+        if lagging > 0 {
+            warn!(
+                self.log,
+                "Closed lagging chain event streams";
+                "streams" => lagging,
+                "dropped_event" => kind.topic(),
+            );
+        }
     }
 }
 
@@ -59,6 +153,14 @@ impl<T: EthSpec> EventHandler<T> for ServerSentEvents<T> {
                         "dropped_change" => format!("{}", current_head_beacon_block_root),
                     );
                 }
+                drop(guard);
+                self.broadcast_chain_event(kind);
+                Ok(())
+            }
+            EventKind::ChainReorg { .. }
+            | EventKind::BeaconFinalization { .. }
+            | EventKind::BeaconBlockImported { .. } => {
+                self.broadcast_chain_event(kind);
                 Ok(())
             }
             _ => Ok(()),
@@ -78,13 +180,13 @@ impl<E: EthSpec> TeeEventHandler<E> {
     pub fn new(
         log: Logger,
         websockets_handler: WebSocketSender<E>,
-    ) -> Result<(Self, Arc<Mutex<Bus<SignedBeaconBlockHash>>>), String> {
-        let (sse_handler, bus) = ServerSentEvents::new(log);
+    ) -> Result<(Self, EventBuses<E>), String> {
+        let (sse_handler, buses) = ServerSentEvents::new(log);
         let result = Self {
             websockets_handler: websockets_handler,
             sse_handler: sse_handler,
         };
-        Ok((result, bus))
+        Ok((result, buses))
     }
 }
 
@@ -121,6 +223,15 @@ pub enum EventKind<T: EthSpec> {
         current_head_beacon_block_root: Hash256,
         previous_head_beacon_block_root: Hash256,
     },
+    /// The new head does not descend from the previous head. `depth` is the number of slots
+    /// between the previous head and `common_ancestor`.
+    ChainReorg {
+        slot: Slot,
+        depth: u64,
+        old_head_block: Hash256,
+        new_head_block: Hash256,
+        common_ancestor: Hash256,
+    },
     BeaconFinalization {
         epoch: Epoch,
         root: Hash256,
@@ -128,6 +239,9 @@ pub enum EventKind<T: EthSpec> {
     BeaconBlockImported {
         block_root: Hash256,
         block: Box<SignedBeaconBlock<T>>,
+        /// The time between the start of the slot of the block and the block being received on
+        /// gossip, if it was received on gossip.
+        observed_delay_ms: Option<u64>,
     },
     BeaconBlockRejected {
         reason: String,
@@ -141,3 +255,57 @@ pub enum EventKind<T: EthSpec> {
         attestation: Box<Attestation<T>>,
     },
 }
+
+impl<T: EthSpec> EventKind<T> {
+    /// The name of the event, as used by the `event` field when serialized.
+    pub fn topic(&self) -> &'static str {
+        match self {
+            EventKind::BeaconHeadChanged { .. } => "beacon_head_changed",
+            EventKind::ChainReorg { .. } => "chain_reorg",
+            EventKind::BeaconFinalization { .. } => "beacon_finalization",
+            EventKind::BeaconBlockImported { .. } => "beacon_block_imported",
+            EventKind::BeaconBlockRejected { .. } => "beacon_block_rejected",
+            EventKind::BeaconAttestationImported { .. } => "beacon_attestation_imported",
+            EventKind::BeaconAttestationRejected { .. } => "beacon_attestation_rejected",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn lagging_subscriber_is_unsubscribed() {
+        let mut subscribers = EventSubscribers::new(2);
+        let mut lagging = subscribers.subscribe();
+        let mut reader = subscribers.subscribe();
+
+        for event in 0..2 {
+            assert_eq!(subscribers.send(&event), 0);
+            assert_eq!(reader.try_recv().ok(), Some(event));
+        }
+
+        // The queue of `lagging` is full, it alone is unsubscribed.
+        assert_eq!(subscribers.send(&2), 1);
+        assert_eq!(subscribers.len(), 1);
+        assert_eq!(reader.try_recv().ok(), Some(2));
+
+        // `lagging` reads the events in its queue, then its stream ends.
+        assert_eq!(block_on(lagging.recv()), Some(0));
+        assert_eq!(block_on(lagging.recv()), Some(1));
+        assert_eq!(block_on(lagging.recv()), None);
+    }
+
+    #[test]
+    fn disconnected_subscriber_is_unsubscribed() {
+        let mut subscribers = EventSubscribers::new(2);
+        drop(subscribers.subscribe());
+        let mut reader = subscribers.subscribe();
+
+        assert_eq!(subscribers.send(&0), 0);
+        assert_eq!(subscribers.len(), 1);
+        assert_eq!(reader.try_recv().ok(), Some(0));
+    }
+}
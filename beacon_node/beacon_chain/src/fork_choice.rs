@@ -3,13 +3,19 @@ mod checkpoint_manager;
 use crate::{errors::BeaconChainError, metrics, BeaconChain, BeaconChainTypes};
 use checkpoint_manager::{get_effective_balances, CheckpointManager, CheckpointWithBalances};
 use parking_lot::{RwLock, RwLockReadGuard};
-use proto_array_fork_choice::{core::ProtoArray, ProtoArrayForkChoice};
+use proto_array_fork_choice::{
+    core::{BlockTreeNode, ProtoArray},
+    ForkChoiceSnapshot, ProtoArrayForkChoice,
+};
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use state_processing::common::get_indexed_attestation;
 use std::marker::PhantomData;
 use store::{DBColumn, Error as StoreError, StoreItem};
-use types::{BeaconBlock, BeaconState, BeaconStateError, Epoch, Hash256, IndexedAttestation, Slot};
+use types::{
+    BeaconBlock, BeaconState, BeaconStateError, Checkpoint, Epoch, Hash256, IndexedAttestation,
+    Slot,
+};
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -213,6 +219,19 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
         self.backend.latest_message(validator_index)
     }
 
+    /// Returns the roots of the blocks whose states may be read when running fork choice, i.e.,
+    /// those of the justified and finalized checkpoints.
+    pub fn checkpoint_block_roots(&self) -> Vec<Hash256> {
+        let mut roots = self.checkpoint_manager.read().checkpoint_roots();
+        // Resolve the zero hash alias of the genesis block.
+        for root in roots.iter_mut().filter(|root| **root == Hash256::zero()) {
+            *root = self.genesis_block_root;
+        }
+        roots.sort();
+        roots.dedup();
+        roots
+    }
+
     /// Trigger a prune on the underlying fork choice backend.
     pub fn prune(&self) -> Result<()> {
         let finalized_root = self.checkpoint_manager.read().current.finalized.root;
@@ -220,6 +239,11 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
         self.backend.maybe_prune(finalized_root).map_err(Into::into)
     }
 
+    /// Returns all the blocks in the fork choice tree, descending from the finalized block.
+    pub fn block_tree(&self) -> Vec<BlockTreeNode> {
+        self.backend.block_tree()
+    }
+
     /// Returns a read-lock to the core `ProtoArray` struct.
     ///
     /// Should only be used when encoding/decoding during troubleshooting.
@@ -227,6 +251,29 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
         self.backend.core_proto_array()
     }
 
+    /// Returns a copy of the fork choice state, along with the checkpoints and balances used to
+    /// find the head, such that the head search can be replayed offline.
+    pub fn snapshot(&self) -> ForkChoiceSnapshot {
+        let manager = self.checkpoint_manager.read();
+        let justified = &manager.current.justified;
+
+        // Resolve the zero hash alias, which the snapshot has no knowledge of.
+        let justified_root = if justified.root == Hash256::zero() {
+            self.genesis_block_root
+        } else {
+            justified.root
+        };
+
+        self.backend.snapshot(
+            Checkpoint {
+                epoch: justified.epoch,
+                root: justified_root,
+            },
+            manager.current.finalized.clone(),
+            justified.balances.clone(),
+        )
+    }
+
     /// Returns a `SszForkChoice` which contains the current state of `Self`.
     pub fn as_ssz_container(&self) -> SszForkChoice {
         SszForkChoice {
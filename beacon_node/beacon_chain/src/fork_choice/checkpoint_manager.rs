@@ -187,6 +187,15 @@ impl CheckpointManager {
         }
     }
 
+    /// Returns the roots of the current and best justified blocks, and the finalized block.
+    pub fn checkpoint_roots(&self) -> Vec<Hash256> {
+        vec![
+            self.current.justified.root,
+            self.best.justified.root,
+            self.current.finalized.root,
+        ]
+    }
+
     /// Potentially updates `self.current`, if the conditions are correct.
     ///
     /// Should be called before running the fork choice `find_head` function to ensure
@@ -0,0 +1,108 @@
+//! Provides the `InvalidBlockCache` struct, which remembers the roots of blocks which failed the
+//! state transition.
+
+use crate::BlockError;
+use lru::LruCache;
+use parking_lot::Mutex;
+use state_processing::BlockProcessingError;
+use types::Hash256;
+
+/// The number of invalid block roots which are remembered.
+pub const DEFAULT_INVALID_BLOCK_CACHE_SIZE: usize = 1_024;
+
+/// Maintains the roots of the most recent blocks which failed the state transition.
+///
+/// The outcome of the state transition depends only on the block message (which includes its
+/// parent), so a block with a cached root can be rejected without running it again, however it
+/// is delivered. Only errors which are independent of the local node are cached: blocks with an
+/// invalid proposer signature share their root with the valid block and are never cached.
+pub struct InvalidBlockCache {
+    roots: Mutex<LruCache<Hash256, ()>>,
+}
+
+impl InvalidBlockCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            roots: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Records the block with `block_root` as invalid if `error` shows it failed the state
+    /// transition. Returns `true` if the block was recorded.
+    pub fn observe_error(&self, block_root: Hash256, error: &BlockError) -> bool {
+        let is_invalid = match error {
+            BlockError::StateRootMismatch { .. } => true,
+            // A `BeaconStateError` may be caused by the local state rather than the block.
+            BlockError::PerBlockProcessingError(BlockProcessingError::BeaconStateError(_)) => false,
+            BlockError::PerBlockProcessingError(_) => true,
+            _ => false,
+        };
+
+        if is_invalid {
+            self.roots.lock().put(block_root, ());
+        }
+        is_invalid
+    }
+
+    /// Returns `true` if the block with `block_root` is known to fail the state transition.
+    pub fn contains(&self, block_root: &Hash256) -> bool {
+        self.roots.lock().contains(block_root)
+    }
+}
+
+impl Default for InvalidBlockCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_INVALID_BLOCK_CACHE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Slot;
+
+    #[test]
+    fn only_caches_state_transition_failures() {
+        let cache = InvalidBlockCache::default();
+        let root = |i| Hash256::from_low_u64_be(i);
+
+        assert!(cache.observe_error(
+            root(1),
+            &BlockError::StateRootMismatch {
+                block: Hash256::zero(),
+                local: Hash256::repeat_byte(1),
+            }
+        ));
+        assert!(cache.observe_error(
+            root(2),
+            &BlockError::PerBlockProcessingError(BlockProcessingError::StateRootMismatch)
+        ));
+        assert!(!cache.observe_error(root(3), &BlockError::ProposalSignatureInvalid));
+        assert!(!cache.observe_error(
+            root(4),
+            &BlockError::FutureSlot {
+                present_slot: Slot::new(0),
+                block_slot: Slot::new(1),
+            }
+        ));
+
+        assert!(cache.contains(&root(1)));
+        assert!(cache.contains(&root(2)));
+        assert!(!cache.contains(&root(3)));
+        assert!(!cache.contains(&root(4)));
+    }
+
+    #[test]
+    fn evicts_oldest_roots() {
+        let cache = InvalidBlockCache::new(2);
+        let error = BlockError::PerBlockProcessingError(BlockProcessingError::StateRootMismatch);
+
+        for i in 0..3 {
+            cache.observe_error(Hash256::from_low_u64_be(i), &error);
+        }
+
+        assert!(!cache.contains(&Hash256::from_low_u64_be(0)));
+        assert!(cache.contains(&Hash256::from_low_u64_be(1)));
+        assert!(cache.contains(&Hash256::from_low_u64_be(2)));
+    }
+}
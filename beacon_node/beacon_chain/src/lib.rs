@@ -5,6 +5,7 @@ extern crate lazy_static;
 pub mod attestation_verification;
 mod beacon_chain;
 mod beacon_snapshot;
+mod block_delay_cache;
 mod block_verification;
 pub mod builder;
 mod errors;
@@ -12,6 +13,7 @@ pub mod eth1_chain;
 pub mod events;
 mod fork_choice;
 mod head_tracker;
+mod invalid_block_cache;
 mod metrics;
 pub mod migrate;
 mod naive_aggregation_pool;
@@ -23,14 +25,17 @@ mod shuffling_cache;
 mod snapshot_cache;
 pub mod test_utils;
 mod timeout_rw_lock;
+pub mod validator_monitor;
 mod validator_pubkey_cache;
 
 pub use self::beacon_chain::{
     AttestationProcessingOutcome, BeaconChain, BeaconChainTypes, ChainSegmentResult,
-    StateSkipConfig,
+    GossipOperationOutcome, StateSkipConfig,
 };
 pub use self::beacon_snapshot::BeaconSnapshot;
 pub use self::errors::{BeaconChainError, BlockProductionError};
+pub use self::snapshot_cache::DEFAULT_SNAPSHOT_CACHE_SIZE;
+pub use self::validator_monitor::DEFAULT_AUTO_REGISTER_LIMIT;
 pub use attestation_verification::Error as AttestationError;
 pub use block_verification::{BlockError, BlockProcessingOutcome, GossipVerifiedBlock};
 pub use eth1_chain::{Eth1Chain, Eth1ChainBackend};
@@ -15,6 +15,24 @@ lazy_static! {
         "beacon_block_processing_successes_total",
         "Count of blocks processed without error"
     );
+    pub static ref BLOCK_PROCESSING_INVALID_BLOCKS_CACHED: Result<IntCounter> = try_create_int_counter(
+        "beacon_block_processing_invalid_blocks_cached_total",
+        "Count of blocks which failed the state transition and were added to the invalid block cache"
+    );
+    pub static ref BLOCK_PROCESSING_SEGMENT_BISECTIONS: Result<IntCounter> = try_create_int_counter(
+        "beacon_block_processing_segment_bisections_total",
+        "Count of chain segments which were bisected to find a block with an invalid signature"
+    );
+    pub static ref BLOCK_GOSSIP_DELAY: Result<Histogram> = try_create_histogram(
+        "beacon_block_gossip_delay_seconds",
+        "Time between the start of the slot of a block and the block being received on gossip"
+    );
+    pub static ref BLOCK_GOSSIP_LATE: Result<IntCounterVec> = try_create_int_counter_vec(
+        "beacon_block_gossip_late_total",
+        "Count of blocks received on gossip after the attestation deadline of their slot, by \
+        monitored proposer (or \"other\")",
+        &["proposer"]
+    );
     pub static ref BLOCK_PROCESSING_TIMES: Result<Histogram> =
         try_create_histogram("beacon_block_processing_seconds", "Full runtime of block processing");
     pub static ref BLOCK_PROCESSING_BLOCK_ROOT: Result<Histogram> = try_create_histogram(
@@ -25,6 +43,14 @@ lazy_static! {
         "beacon_block_processing_db_read_seconds",
         "Time spent loading block and state from DB for block processing"
     );
+    pub static ref BLOCK_PROCESSING_SNAPSHOT_CACHE_HITS: Result<IntCounter> = try_create_int_counter(
+        "beacon_block_processing_snapshot_cache_hits_total",
+        "Count of times the parent snapshot of a block was found in the snapshot cache"
+    );
+    pub static ref BLOCK_PROCESSING_SNAPSHOT_CACHE_MISSES: Result<IntCounter> = try_create_int_counter(
+        "beacon_block_processing_snapshot_cache_misses_total",
+        "Count of times the parent snapshot of a block was loaded from the database"
+    );
     pub static ref BLOCK_PROCESSING_CATCHUP_STATE: Result<Histogram> = try_create_histogram(
         "beacon_block_processing_catch_up_state_seconds",
         "Time spent skipping slots on a state before processing a block."
@@ -45,6 +71,14 @@ lazy_static! {
         "beacon_block_processing_state_root_seconds",
         "Time spent calculating the state root when processing a block."
     );
+    pub static ref BLOCK_PROCESSING_TREE_HASH_CACHE_HITS: Result<IntCounter> = try_create_int_counter(
+        "beacon_block_processing_tree_hash_cache_hits_total",
+        "Count of times the parent state of a block had a tree hash cache"
+    );
+    pub static ref BLOCK_PROCESSING_TREE_HASH_CACHE_MISSES: Result<IntCounter> = try_create_int_counter(
+        "beacon_block_processing_tree_hash_cache_misses_total",
+        "Count of times the tree hash cache was built from scratch for the parent state of a block"
+    );
     pub static ref BLOCK_PROCESSING_DB_WRITE: Result<Histogram> = try_create_histogram(
         "beacon_block_processing_db_write_seconds",
         "Time spent writing a newly processed block and state to DB"
@@ -179,6 +213,18 @@ lazy_static! {
         "beacon_attestation_processing_signature_seconds",
         "Time spent on the signature verification of attestation processing"
     );
+    pub static ref ATTESTATION_PROCESSING_BATCH_SIGNATURE_FAILURES: Result<IntCounter> = try_create_int_counter(
+        "beacon_attestation_processing_batch_signature_failures_total",
+        "Count of batches of attestations with an invalid signature, which are verified individually"
+    );
+
+    /*
+     * State Advance
+     */
+    pub static ref STATE_ADVANCE_TIMES: Result<Histogram> = try_create_histogram(
+        "beacon_state_advance_seconds",
+        "Time spent advancing the head state into the next slot"
+    );
 
     /*
      * Shuffling cache
@@ -226,6 +272,11 @@ lazy_static! {
         "beacon_fork_choice_reorg_total",
         "Count of occasions fork choice has switched to a different chain"
     );
+    pub static ref FORK_CHOICE_REORG_DEPTH: Result<Histogram> = try_create_histogram_with_buckets(
+        "beacon_fork_choice_reorg_depth",
+        "Number of slots reverted from the previous head by a re-org",
+        vec![1.0, 2.0, 3.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0]
+    );
     pub static ref FORK_CHOICE_TIMES: Result<Histogram> =
         try_create_histogram("beacon_fork_choice_seconds", "Full runtime of fork choice");
     pub static ref FORK_CHOICE_FIND_HEAD_TIMES: Result<Histogram> =
@@ -338,6 +389,69 @@ lazy_static! {
     );
 }
 
+// Validator monitor metrics, labelled by the public key of the validator.
+lazy_static! {
+    pub static ref VALIDATOR_MONITOR_VALIDATORS_TOTAL: Result<IntGauge> = try_create_int_gauge(
+        "validator_monitor_validators_total",
+        "Count of validators that are specifically monitored by this beacon node"
+    );
+    pub static ref VALIDATOR_MONITOR_GOSSIP_UNAGGREGATED_ATTESTATIONS_TOTAL: Result<IntCounterVec> =
+        try_create_int_counter_vec(
+            "validator_monitor_gossip_unaggregated_attestations_total",
+            "Count of unaggregated attestations by the validator seen on gossip",
+            &["validator"]
+        );
+    pub static ref VALIDATOR_MONITOR_GOSSIP_UNAGGREGATED_ATTESTATIONS_DELAY_SECONDS: Result<HistogramVec> =
+        try_create_histogram_vec(
+            "validator_monitor_gossip_unaggregated_attestations_delay_seconds",
+            "Time between the start of the slot and an unaggregated attestation by the validator \
+             being seen on gossip",
+            &["validator"]
+        );
+    pub static ref VALIDATOR_MONITOR_GOSSIP_AGGREGATED_ATTESTATIONS_TOTAL: Result<IntCounterVec> =
+        try_create_int_counter_vec(
+            "validator_monitor_gossip_aggregated_attestations_total",
+            "Count of aggregates produced by the validator seen on gossip",
+            &["validator"]
+        );
+    pub static ref VALIDATOR_MONITOR_ATTESTATION_IN_AGGREGATE_TOTAL: Result<IntCounterVec> =
+        try_create_int_counter_vec(
+            "validator_monitor_attestation_in_aggregate_total",
+            "Count of aggregates seen on gossip which include an attestation by the validator",
+            &["validator"]
+        );
+    pub static ref VALIDATOR_MONITOR_ATTESTATION_IN_BLOCK_TOTAL: Result<IntCounterVec> =
+        try_create_int_counter_vec(
+            "validator_monitor_attestation_in_block_total",
+            "Count of imported blocks which include an attestation by the validator",
+            &["validator"]
+        );
+    pub static ref VALIDATOR_MONITOR_ATTESTATION_IN_BLOCK_DELAY_SLOTS: Result<IntGaugeVec> =
+        try_create_int_gauge_vec(
+            "validator_monitor_attestation_in_block_delay_slots",
+            "The inclusion distance of the latest attestation by the validator included in a block",
+            &["validator"]
+        );
+    pub static ref VALIDATOR_MONITOR_BEACON_BLOCKS_TOTAL: Result<IntCounterVec> =
+        try_create_int_counter_vec(
+            "validator_monitor_beacon_blocks_total",
+            "Count of imported blocks proposed by the validator",
+            &["validator"]
+        );
+    pub static ref VALIDATOR_MONITOR_MISSED_BLOCKS_TOTAL: Result<IntCounterVec> =
+        try_create_int_counter_vec(
+            "validator_monitor_missed_blocks_total",
+            "Count of skipped slots at which the validator was the proposer",
+            &["validator"]
+        );
+    pub static ref VALIDATOR_MONITOR_SLASHINGS_TOTAL: Result<IntCounterVec> =
+        try_create_int_counter_vec(
+            "validator_monitor_slashings_total",
+            "Count of slashings of the validator included in imported blocks",
+            &["validator", "kind"]
+        );
+}
+
 /// Scrape the `beacon_chain` for metrics that are not constantly updated (e.g., the present slot,
 /// head state info, etc) and update the Prometheus `DEFAULT_REGISTRY`.
 pub fn scrape_for_metrics<T: BeaconChainTypes>(beacon_chain: &BeaconChain<T>) {
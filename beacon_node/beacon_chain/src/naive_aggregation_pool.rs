@@ -1,7 +1,8 @@
 use crate::metrics;
 use parking_lot::RwLock;
 use std::collections::HashMap;
-use types::{Attestation, AttestationData, EthSpec, Slot};
+use tree_hash::TreeHash;
+use types::{Attestation, AttestationData, EthSpec, Hash256, Slot};
 
 /// The number of slots that will be stored in the pool.
 ///
@@ -120,6 +121,14 @@ impl<E: EthSpec> AggregatedAttestationMap<E> {
         Ok(self.map.get(data).cloned())
     }
 
+    /// Returns an aggregated `Attestation` with an `AttestationData` matching `data_root`, if any.
+    pub fn get_by_root(&self, data_root: &Hash256) -> Option<Attestation<E>> {
+        self.map
+            .iter()
+            .find(|(data, _attestation)| data.tree_hash_root() == *data_root)
+            .map(|(_data, attestation)| attestation.clone())
+    }
+
     pub fn len(&self) -> usize {
         self.map.len()
     }
@@ -226,6 +235,15 @@ impl<E: EthSpec> NaiveAggregationPool<E> {
             .unwrap_or_else(|| Ok(None))
     }
 
+    /// Returns an aggregated `Attestation` at `slot` with an `AttestationData` matching
+    /// `data_root`, if any.
+    pub fn get_by_slot_and_root(&self, slot: Slot, data_root: &Hash256) -> Option<Attestation<E>> {
+        self.maps
+            .read()
+            .get(&slot)
+            .and_then(|map| map.get_by_root(data_root))
+    }
+
     /// Removes any attestations with a slot lower than `current_slot` and bars any future
     /// attestations with a slot lower than `current_slot - SLOTS_RETAINED`.
     pub fn prune(&self, current_slot: Slot) {
@@ -271,7 +289,7 @@ mod tests {
     use ssz_types::BitList;
     use types::{
         test_utils::{generate_deterministic_keypair, test_random_instance},
-        Fork, Hash256,
+        Fork,
     };
 
     type E = types::MainnetEthSpec;
@@ -333,6 +351,16 @@ mod tests {
             retrieved, a,
             "retrieved attestation should equal the one inserted"
         );
+        assert_eq!(
+            pool.get_by_slot_and_root(a.data.slot, &a.data.tree_hash_root()),
+            Some(a.clone()),
+            "should get the attestation by its data root"
+        );
+        assert_eq!(
+            pool.get_by_slot_and_root(a.data.slot + 1, &a.data.tree_hash_root()),
+            None,
+            "should not get the attestation at another slot"
+        );
 
         sign(&mut a, 1, Hash256::random());
 
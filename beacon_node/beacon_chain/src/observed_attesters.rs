@@ -230,6 +230,17 @@ impl<T: Item, E: EthSpec> AutoPruningContainer<T, E> {
         Ok(exists)
     }
 
+    /// Returns `true` if `validator_index` has been observed in any attestation with a target of
+    /// `epoch`.
+    ///
+    /// Unlike `Self::validator_has_been_observed`, an epoch which has been pruned is not an error.
+    pub fn index_seen_at_epoch(&self, validator_index: usize, epoch: Epoch) -> bool {
+        self.items
+            .read()
+            .get(&epoch)
+            .map_or(false, |item| item.contains(validator_index))
+    }
+
     /// Returns the number of validators that have been observed at the given `epoch`. Returns
     /// `None` if `self` does not have a cache for that epoch.
     pub fn observed_validator_count(&self, epoch: Epoch) -> Option<usize> {
@@ -324,6 +335,14 @@ mod tests {
                             Ok(true),
                             "should indicate a known attestation is known"
                         );
+                        assert!(
+                            store.index_seen_at_epoch(i, epoch),
+                            "should indicate a known attester is known"
+                        );
+                        assert!(
+                            !store.index_seen_at_epoch(i, epoch + 1),
+                            "should not indicate an attester is known at another epoch"
+                        );
                         assert_eq!(
                             store.observe_validator(a, i),
                             Ok(true),
@@ -414,7 +414,7 @@ where
     }
 
     /// Returns a newly created block, signed by the proposer for the given slot.
-    fn build_block(
+    pub fn build_block(
         &self,
         mut state: BeaconState<E>,
         slot: Slot,
@@ -575,8 +575,12 @@ where
             .for_each(|committee_attestations| {
                 // Submit each unaggregated attestation to the chain.
                 for attestation in &committee_attestations {
+                    let subnet_id = attestation
+                        .subnet_id(&self.spec)
+                        .expect("should get subnet id");
+
                     self.chain
-                        .verify_unaggregated_attestation_for_gossip(attestation.clone())
+                        .verify_unaggregated_attestation_for_gossip(attestation.clone(), subnet_id)
                         .expect("should not error during attestation processing")
                         .add_to_pool(&self.chain)
                         .expect("should add attestation to naive pool");
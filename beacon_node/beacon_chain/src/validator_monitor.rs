@@ -0,0 +1,427 @@
+//! Provides the `ValidatorMonitor` struct, which logs and records metrics about the activity of a
+//! chosen set of validators.
+//!
+//! The monitored validators are given by their public keys, either on the command line or, if
+//! enabled, by automatically registering the validators of any validator client which requests
+//! duties from this beacon node.
+
+use crate::metrics;
+use crate::validator_pubkey_cache::ValidatorPubkeyCache;
+use slog::{info, warn, Logger};
+use slot_clock::SlotClock;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::time::Duration;
+use types::{
+    AttesterSlashing, BeaconBlock, EthSpec, IndexedAttestation, ProposerSlashing, PublicKeyBytes,
+    Slot,
+};
+
+/// The default maximum number of validators which are monitored due to automatic registration.
+///
+/// Each monitored validator adds a label to the validator monitor metrics, so this bounds the
+/// number of labels that the clients of the HTTP API are able to create.
+pub const DEFAULT_AUTO_REGISTER_LIMIT: usize = 64;
+
+/// A validator which is being monitored.
+struct MonitoredValidator {
+    /// A human-readable identifier for the validator, used in logs and metric labels.
+    id: String,
+    /// The index of the validator, which is unknown until the validator has been deposited.
+    index: Option<u64>,
+}
+
+impl MonitoredValidator {
+    fn new(pubkey: &PublicKeyBytes) -> Self {
+        Self {
+            id: format!("0x{}", hex::encode(pubkey.as_slice())),
+            index: None,
+        }
+    }
+}
+
+/// Logs and records metrics about the activity of a set of validators on the network: their
+/// attestations seen on gossip, their attestations and blocks included in the chain, their missed
+/// block proposals and their slashings.
+pub struct ValidatorMonitor<T> {
+    /// The monitored validators, by public key.
+    validators: HashMap<PublicKeyBytes, MonitoredValidator>,
+    /// The public keys of the monitored validators with known indices, by index.
+    indices: HashMap<u64, PublicKeyBytes>,
+    /// If `true`, validators are monitored as soon as a validator client requests their duties.
+    auto_register: bool,
+    /// The maximum number of validators which are monitored due to automatic registration.
+    auto_register_limit: usize,
+    /// The number of validators which are monitored due to automatic registration.
+    auto_registered: usize,
+    /// If `true`, a validator was not registered because of the `auto_register_limit`.
+    auto_register_limit_reached: bool,
+    log: Logger,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: EthSpec> ValidatorMonitor<T> {
+    /// Creates a monitor of the validators with `pubkeys`, which also monitors up to
+    /// `auto_register_limit` validators of local validator clients if `auto_register` is `true`.
+    pub fn new(
+        pubkeys: Vec<PublicKeyBytes>,
+        auto_register: bool,
+        auto_register_limit: usize,
+        log: Logger,
+    ) -> Self {
+        let mut monitor = Self {
+            validators: HashMap::new(),
+            indices: HashMap::new(),
+            auto_register,
+            auto_register_limit,
+            auto_registered: 0,
+            auto_register_limit_reached: false,
+            log,
+            _phantom: PhantomData,
+        };
+        for pubkey in pubkeys {
+            monitor.add_validator_pubkey(pubkey);
+        }
+        monitor
+    }
+
+    /// Starts monitoring the validator with `pubkey`, if it is not already monitored.
+    fn add_validator_pubkey(&mut self, pubkey: PublicKeyBytes) {
+        if self.validators.contains_key(&pubkey) {
+            return;
+        }
+
+        let validator = MonitoredValidator::new(&pubkey);
+        info!(self.log, "Started monitoring validator"; "validator" => &validator.id);
+        self.validators.insert(pubkey, validator);
+        metrics::set_gauge(
+            &metrics::VALIDATOR_MONITOR_VALIDATORS_TOTAL,
+            self.validators.len() as i64,
+        );
+    }
+
+    /// Starts monitoring the validator with `pubkey`, which is managed by a validator client
+    /// connected to this beacon node, if automatic registration is enabled and the limit of
+    /// automatically registered validators has not been reached.
+    pub fn auto_register_local_validator(&mut self, pubkey: &PublicKeyBytes) {
+        if !self.auto_register || self.validators.contains_key(pubkey) {
+            return;
+        }
+
+        if self.auto_registered >= self.auto_register_limit {
+            // Only warn once, since validator clients request their duties every epoch.
+            if !self.auto_register_limit_reached {
+                warn!(
+                    self.log,
+                    "Not monitoring further validators";
+                    "info" => "the limit of automatically registered validators was reached",
+                    "limit" => self.auto_register_limit,
+                );
+                self.auto_register_limit_reached = true;
+            }
+            return;
+        }
+
+        self.add_validator_pubkey(pubkey.clone());
+        self.auto_registered += 1;
+    }
+
+    /// Looks up the indices of the monitored validators whose index is not yet known.
+    pub(crate) fn update_validator_indices(&mut self, pubkey_cache: &ValidatorPubkeyCache) {
+        let indices = &mut self.indices;
+        for (pubkey, validator) in self.validators.iter_mut() {
+            if validator.index.is_none() {
+                if let Some(index) = pubkey_cache.get_index(pubkey) {
+                    validator.index = Some(index as u64);
+                    indices.insert(index as u64, pubkey.clone());
+                }
+            }
+        }
+    }
+
+    /// Returns the number of monitored validators.
+    pub fn num_validators(&self) -> usize {
+        self.validators.len()
+    }
+
+    /// Returns the monitored validator with `validator_index`, if any.
+    fn get_validator(&self, validator_index: u64) -> Option<&MonitoredValidator> {
+        self.indices
+            .get(&validator_index)
+            .and_then(|pubkey| self.validators.get(pubkey))
+    }
+
+    /// Registers an unaggregated attestation received on gossip at `seen_timestamp`.
+    pub fn register_gossip_unaggregated_attestation<S: SlotClock>(
+        &self,
+        seen_timestamp: Duration,
+        indexed_attestation: &IndexedAttestation<T>,
+        slot_clock: &S,
+    ) {
+        let data = &indexed_attestation.data;
+        let delay = get_slot_delay(seen_timestamp, data.slot, slot_clock);
+
+        for validator_index in indexed_attestation.attesting_indices.iter() {
+            if let Some(validator) = self.get_validator(*validator_index) {
+                let id = &validator.id;
+                metrics::inc_counter_vec(
+                    &metrics::VALIDATOR_MONITOR_GOSSIP_UNAGGREGATED_ATTESTATIONS_TOTAL,
+                    &[id],
+                );
+                if let Some(histogram) = metrics::get_histogram(
+                    &metrics::VALIDATOR_MONITOR_GOSSIP_UNAGGREGATED_ATTESTATIONS_DELAY_SECONDS,
+                    &[id],
+                ) {
+                    histogram.observe(delay.as_secs_f64());
+                }
+
+                info!(
+                    self.log,
+                    "Unaggregated attestation";
+                    "head" => format!("{:?}", data.beacon_block_root),
+                    "index" => data.index,
+                    "delay_ms" => delay.as_millis() as u64,
+                    "epoch" => data.target.epoch,
+                    "slot" => data.slot,
+                    "validator" => id,
+                );
+            }
+        }
+    }
+
+    /// Registers an aggregated attestation, produced by the validator with `aggregator_index`,
+    /// received on gossip at `seen_timestamp`.
+    pub fn register_gossip_aggregated_attestation<S: SlotClock>(
+        &self,
+        seen_timestamp: Duration,
+        aggregator_index: u64,
+        indexed_attestation: &IndexedAttestation<T>,
+        slot_clock: &S,
+    ) {
+        let data = &indexed_attestation.data;
+        let delay = get_slot_delay(seen_timestamp, data.slot, slot_clock);
+
+        if let Some(validator) = self.get_validator(aggregator_index) {
+            let id = &validator.id;
+            metrics::inc_counter_vec(
+                &metrics::VALIDATOR_MONITOR_GOSSIP_AGGREGATED_ATTESTATIONS_TOTAL,
+                &[id],
+            );
+
+            info!(
+                self.log,
+                "Aggregated attestation";
+                "head" => format!("{:?}", data.beacon_block_root),
+                "index" => data.index,
+                "delay_ms" => delay.as_millis() as u64,
+                "epoch" => data.target.epoch,
+                "slot" => data.slot,
+                "validator" => id,
+            );
+        }
+
+        for validator_index in indexed_attestation.attesting_indices.iter() {
+            if let Some(validator) = self.get_validator(*validator_index) {
+                let id = &validator.id;
+                metrics::inc_counter_vec(
+                    &metrics::VALIDATOR_MONITOR_ATTESTATION_IN_AGGREGATE_TOTAL,
+                    &[id],
+                );
+
+                info!(
+                    self.log,
+                    "Attestation included in aggregate";
+                    "head" => format!("{:?}", data.beacon_block_root),
+                    "index" => data.index,
+                    "delay_ms" => delay.as_millis() as u64,
+                    "epoch" => data.target.epoch,
+                    "slot" => data.slot,
+                    "validator" => id,
+                );
+            }
+        }
+    }
+
+    /// Registers an attestation included in an imported block at `block_slot`.
+    pub fn register_attestation_in_block(
+        &self,
+        indexed_attestation: &IndexedAttestation<T>,
+        block_slot: Slot,
+    ) {
+        let data = &indexed_attestation.data;
+        let inclusion_distance = block_slot.saturating_sub(data.slot);
+
+        for validator_index in indexed_attestation.attesting_indices.iter() {
+            if let Some(validator) = self.get_validator(*validator_index) {
+                let id = &validator.id;
+                metrics::inc_counter_vec(
+                    &metrics::VALIDATOR_MONITOR_ATTESTATION_IN_BLOCK_TOTAL,
+                    &[id],
+                );
+                if let Some(gauge) = metrics::get_int_gauge(
+                    &metrics::VALIDATOR_MONITOR_ATTESTATION_IN_BLOCK_DELAY_SLOTS,
+                    &[id],
+                ) {
+                    gauge.set(inclusion_distance.as_u64() as i64);
+                }
+
+                info!(
+                    self.log,
+                    "Attestation included in block";
+                    "head" => format!("{:?}", data.beacon_block_root),
+                    "index" => data.index,
+                    "inclusion_distance" => inclusion_distance,
+                    "epoch" => data.target.epoch,
+                    "slot" => data.slot,
+                    "validator" => id,
+                );
+            }
+        }
+    }
+
+    /// Registers an imported block, which is evidence of its proposer performing its duty.
+    pub fn register_block(&self, block: &BeaconBlock<T>) {
+        if let Some(validator) = self.get_validator(block.proposer_index) {
+            let id = &validator.id;
+            metrics::inc_counter_vec(&metrics::VALIDATOR_MONITOR_BEACON_BLOCKS_TOTAL, &[id]);
+
+            info!(
+                self.log,
+                "Block included in chain";
+                "slot" => block.slot,
+                "validator" => id,
+            );
+        }
+    }
+
+    /// Registers a block received on gossip after the attestation deadline of its slot.
+    ///
+    /// Only monitored proposers have their own label, all others are counted under "other" so
+    /// that the number of labels is bounded.
+    pub fn register_late_gossip_block(&self, block: &BeaconBlock<T>) {
+        let id = self
+            .get_validator(block.proposer_index)
+            .map_or("other", |validator| validator.id.as_str());
+        metrics::inc_counter_vec(&metrics::BLOCK_GOSSIP_LATE, &[id]);
+    }
+
+    /// Registers that the validator with `proposer_index` did not produce a block at `slot`, on
+    /// the chain of an imported block.
+    pub fn register_missed_block(&self, proposer_index: u64, slot: Slot) {
+        if let Some(validator) = self.get_validator(proposer_index) {
+            let id = &validator.id;
+            metrics::inc_counter_vec(&metrics::VALIDATOR_MONITOR_MISSED_BLOCKS_TOTAL, &[id]);
+
+            warn!(
+                self.log,
+                "Missed block proposal";
+                "slot" => slot,
+                "validator" => id,
+            );
+        }
+    }
+
+    /// Registers a proposer slashing included in an imported block.
+    pub fn register_proposer_slashing(&self, slashing: &ProposerSlashing) {
+        let proposer_index = slashing.signed_header_1.message.proposer_index;
+        let slot = slashing.signed_header_1.message.slot;
+
+        if let Some(validator) = self.get_validator(proposer_index) {
+            self.register_slashing(validator, "proposer", slot);
+        }
+    }
+
+    /// Registers an attester slashing included in an imported block.
+    pub fn register_attester_slashing(&self, slashing: &AttesterSlashing<T>) {
+        let attestation_2_indices = &slashing.attestation_2.attesting_indices;
+        let slot = slashing.attestation_1.data.slot;
+
+        for validator_index in slashing.attestation_1.attesting_indices.iter() {
+            if !attestation_2_indices
+                .iter()
+                .any(|index| index == validator_index)
+            {
+                continue;
+            }
+            if let Some(validator) = self.get_validator(*validator_index) {
+                self.register_slashing(validator, "attester", slot);
+            }
+        }
+    }
+
+    fn register_slashing(&self, validator: &MonitoredValidator, kind: &str, slot: Slot) {
+        let id = &validator.id;
+        metrics::inc_counter_vec(&metrics::VALIDATOR_MONITOR_SLASHINGS_TOTAL, &[id, kind]);
+
+        warn!(
+            self.log,
+            "Validator slashed";
+            "kind" => kind,
+            "slot" => slot,
+            "validator" => id,
+        );
+    }
+}
+
+/// Returns the time between the start of `slot` and `seen_timestamp`, or zero if `slot` had not
+/// started (e.g., due to clock disparity).
+fn get_slot_delay<S: SlotClock>(seen_timestamp: Duration, slot: Slot, slot_clock: &S) -> Duration {
+    slot_clock
+        .start_of(slot)
+        .and_then(|slot_start| seen_timestamp.checked_sub(slot_start))
+        .unwrap_or_else(|| Duration::from_secs(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::{o, Discard};
+    use types::MainnetEthSpec;
+
+    fn pubkey(byte: u8) -> PublicKeyBytes {
+        PublicKeyBytes::from_bytes(&[byte; 48]).expect("should create pubkey bytes")
+    }
+
+    #[test]
+    fn auto_registers_only_when_enabled() {
+        let log = Logger::root(Discard, o!());
+
+        let mut monitor = ValidatorMonitor::<MainnetEthSpec>::new(
+            vec![pubkey(1)],
+            false,
+            DEFAULT_AUTO_REGISTER_LIMIT,
+            log.clone(),
+        );
+        monitor.auto_register_local_validator(&pubkey(2));
+        assert_eq!(monitor.num_validators(), 1);
+
+        let mut monitor = ValidatorMonitor::<MainnetEthSpec>::new(
+            vec![pubkey(1)],
+            true,
+            DEFAULT_AUTO_REGISTER_LIMIT,
+            log,
+        );
+        monitor.auto_register_local_validator(&pubkey(1));
+        monitor.auto_register_local_validator(&pubkey(2));
+        assert_eq!(monitor.num_validators(), 2);
+    }
+
+    #[test]
+    fn auto_registers_up_to_limit() {
+        let log = Logger::root(Discard, o!());
+
+        let mut monitor = ValidatorMonitor::<MainnetEthSpec>::new(vec![pubkey(1)], true, 2, log);
+        for byte in 2..10 {
+            monitor.auto_register_local_validator(&pubkey(byte));
+        }
+        assert_eq!(
+            monitor.num_validators(),
+            3,
+            "only the limit of validators should be registered in addition to the given pubkeys"
+        );
+
+        // Validators which are already monitored are not affected by the limit.
+        monitor.auto_register_local_validator(&pubkey(2));
+        assert_eq!(monitor.num_validators(), 3);
+    }
+}
@@ -14,7 +14,7 @@ use tree_hash::TreeHash;
 use types::{
     test_utils::generate_deterministic_keypair, AggregateSignature, Attestation, EthSpec, Hash256,
     Keypair, MainnetEthSpec, SecretKey, SelectionProof, Signature, SignedAggregateAndProof,
-    SignedBeaconBlock, Unsigned,
+    SignedBeaconBlock, SubnetId, Unsigned,
 };
 
 pub type E = MainnetEthSpec;
@@ -523,13 +523,17 @@ fn unaggregated_gossip_verification() {
     let (valid_attestation, expected_validator_index, validator_committee_index, validator_sk) =
         get_valid_unaggregated_attestation(&harness.chain);
 
+    let subnet_id = valid_attestation
+        .subnet_id(&harness.chain.spec)
+        .expect("should get subnet id");
+
     macro_rules! assert_invalid {
-        ($desc: tt, $attn_getter: expr, $($error: pat) |+ $( if $guard: expr )?) => {
+        ($desc: tt, $attn_getter: expr, $subnet_getter: expr, $($error: pat) |+ $( if $guard: expr )?) => {
             assert!(
                 matches!(
                     harness
                         .chain
-                        .verify_unaggregated_attestation_for_gossip($attn_getter)
+                        .verify_unaggregated_attestation_for_gossip($attn_getter, $subnet_getter)
                         .err()
                         .expect(&format!(
                             "{} should error during verify_unaggregated_attestation_for_gossip",
@@ -562,6 +566,7 @@ fn unaggregated_gossip_verification() {
             a.data.slot = future_slot;
             a
         },
+        subnet_id,
         AttnError::FutureSlot {
             attestation_slot,
             latest_permissible_slot,
@@ -581,6 +586,7 @@ fn unaggregated_gossip_verification() {
             a.data.slot = early_slot;
             a
         },
+        subnet_id,
         AttnError::PastSlot {
             attestation_slot,
             // Subtract an additional slot since the harness will be exactly on the start of the
@@ -613,6 +619,7 @@ fn unaggregated_gossip_verification() {
             );
             a
         },
+        subnet_id,
         AttnError::NotExactlyOneAggregationBitSet(0)
     );
 
@@ -625,9 +632,32 @@ fn unaggregated_gossip_verification() {
                 .expect("should set second aggregation bit");
             a
         },
+        subnet_id,
         AttnError::NotExactlyOneAggregationBitSet(2)
     );
 
+    /*
+     * The following test ensures:
+     *
+     * Spec v0.12.1
+     *
+     * The attestation is for the correct subnet (i.e. compute_subnet_for_attestation(state,
+     * attestation) == subnet_id).
+     */
+
+    let wrong_subnet_id =
+        SubnetId::new((*subnet_id + 1) % harness.chain.spec.attestation_subnet_count);
+    assert_invalid!(
+        "attestation on the wrong subnet",
+        valid_attestation.clone(),
+        wrong_subnet_id,
+        AttnError::InvalidSubnetId {
+            received,
+            expected,
+        }
+        if received == wrong_subnet_id && expected == subnet_id
+    );
+
     /*
      * The following test ensures that:
      *
@@ -644,6 +674,7 @@ fn unaggregated_gossip_verification() {
             a.data.beacon_block_root = unknown_root;
             a
         },
+        subnet_id,
         AttnError::UnknownHeadBlock {
             beacon_block_root,
         }
@@ -669,13 +700,14 @@ fn unaggregated_gossip_verification() {
 
             a
         },
+        subnet_id,
         AttnError::InvalidSignature
     );
 
     assert!(
         harness
             .chain
-            .verify_unaggregated_attestation_for_gossip(valid_attestation.clone())
+            .verify_unaggregated_attestation_for_gossip(valid_attestation.clone(), subnet_id)
             .is_ok(),
         "valid attestation should be verified"
     );
@@ -693,6 +725,7 @@ fn unaggregated_gossip_verification() {
     assert_invalid!(
         "attestation that has already been seen",
         valid_attestation.clone(),
+        subnet_id,
         AttnError::PriorAttestationKnown {
             validator_index,
             epoch,
@@ -735,9 +768,13 @@ fn fork_choice_verification() {
     let current_slot = chain.slot().expect("should get slot");
     let expected_current_epoch = chain.epoch().expect("should get epoch");
 
+    let subnet_id = valid_attestation
+        .subnet_id(&harness.chain.spec)
+        .expect("should get subnet id");
+
     let attestation = harness
         .chain
-        .verify_unaggregated_attestation_for_gossip(valid_attestation.clone())
+        .verify_unaggregated_attestation_for_gossip(valid_attestation.clone(), subnet_id)
         .expect("precondition: should gossip verify attestation");
 
     macro_rules! assert_invalid {
@@ -939,6 +976,79 @@ fn fork_choice_verification() {
     );
 }
 
+/// Tests the batch verification of unaggregated attestations, where the batch contains invalid
+/// attestations.
+#[test]
+fn unaggregated_batch_verification() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let chain = &harness.chain;
+
+    harness.extend_chain(
+        MainnetEthSpec::slots_per_epoch() as usize * 2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::SomeValidators(vec![]),
+    );
+    harness.advance_slot();
+
+    let current_slot = chain.slot().expect("should get slot");
+    let head = chain.head().expect("should get head");
+
+    let mut attestations = harness
+        .get_unaggregated_attestations(
+            &AttestationStrategy::AllValidators,
+            &head.beacon_state,
+            head.beacon_block_root,
+            current_slot,
+        )
+        .into_iter()
+        .next()
+        .expect("should have at least one committee");
+    assert!(
+        attestations.len() > 2,
+        "the test requires at least three attestations"
+    );
+
+    // Give the second attestation the signature of the first.
+    attestations[1].signature = attestations[0].signature.clone();
+    // Repeat the first attestation.
+    attestations.push(attestations[0].clone());
+
+    let batch = attestations
+        .iter()
+        .map(|attestation| {
+            let subnet_id = attestation
+                .subnet_id(&chain.spec)
+                .expect("should get subnet id");
+            (attestation.clone(), subnet_id)
+        })
+        .collect();
+    let results = chain.batch_verify_unaggregated_attestations_for_gossip(batch);
+
+    assert_eq!(results.len(), attestations.len());
+    for (i, result) in results.iter().enumerate() {
+        if i == 1 {
+            assert!(
+                matches!(result, Err(AttnError::InvalidSignature)),
+                "should reject the attestation with an invalid signature"
+            );
+        } else if i == attestations.len() - 1 {
+            assert!(
+                matches!(result, Err(AttnError::PriorAttestationKnown { .. })),
+                "should reject the repeated attestation"
+            );
+        } else {
+            assert!(result.is_ok(), "should accept attestation {}", i);
+        }
+    }
+
+    assert!(
+        chain
+            .batch_verify_unaggregated_attestations_for_gossip(vec![])
+            .is_empty(),
+        "should verify an empty batch"
+    );
+}
+
 /// Ensures that an attestation that skips epochs can still be processed.
 ///
 /// This also checks that we can do a state lookup if we don't get a hit from the shuffling cache.
@@ -1000,10 +1110,14 @@ fn attestation_that_skips_epochs() {
         "the attestation must skip more than two epochs"
     );
 
+    let subnet_id = attestation
+        .subnet_id(&harness.chain.spec)
+        .expect("should get subnet id");
+
     assert!(
         harness
             .chain
-            .verify_unaggregated_attestation_for_gossip(attestation)
+            .verify_unaggregated_attestation_for_gossip(attestation, subnet_id)
             .is_ok(),
         "should gossip verify attestation that skips slots"
     );
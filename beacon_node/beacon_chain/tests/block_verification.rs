@@ -5,7 +5,7 @@ extern crate lazy_static;
 
 use beacon_chain::{
     test_utils::{AttestationStrategy, BeaconChainHarness, BlockStrategy, HarnessType},
-    BeaconSnapshot, BlockError,
+    BeaconSnapshot, BlockError, ChainSegmentResult,
 };
 use store::config::StoreConfig;
 use types::{
@@ -227,6 +227,93 @@ fn chain_segment_non_linear_parent_roots() {
     );
 }
 
+#[test]
+fn chain_segment_known_invalid_block() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .chain
+        .slot_clock
+        .set_slot(CHAIN_SEGMENT.last().unwrap().beacon_block.slot().as_u64());
+
+    let mut snapshots = CHAIN_SEGMENT.clone();
+    snapshots[3].beacon_block.message.state_root = Hash256::repeat_byte(42);
+    update_parent_roots(&mut snapshots);
+    update_proposal_signatures(&mut snapshots, &harness);
+    let blocks = snapshots
+        .iter()
+        .map(|snapshot| snapshot.beacon_block.clone())
+        .collect::<Vec<_>>();
+    let invalid_root = blocks[3].canonical_root();
+
+    assert!(
+        matches!(
+            harness
+                .chain
+                .process_chain_segment(blocks.clone())
+                .to_block_error(),
+            Err(BlockError::StateRootMismatch { .. })
+        ),
+        "should not import a block with an invalid state root"
+    );
+
+    assert!(
+        matches!(
+            harness
+                .chain
+                .process_chain_segment(blocks.clone())
+                .to_block_error(),
+            Err(BlockError::KnownInvalidBlock(root)) if root == invalid_root
+        ),
+        "should reject a segment containing a known invalid block"
+    );
+
+    assert!(
+        matches!(
+            harness.chain.process_block(blocks[3].clone()),
+            Err(BlockError::KnownInvalidBlock(root)) if root == invalid_root
+        ),
+        "should reject a known invalid block"
+    );
+}
+
+#[test]
+fn chain_segment_invalid_signature_imports_prior_blocks() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .chain
+        .slot_clock
+        .set_slot(CHAIN_SEGMENT.last().unwrap().beacon_block.slot().as_u64());
+
+    // The invalid block is in the second epoch of the segment, which is verified separately to
+    // the first.
+    let invalid_index = 42;
+    let mut blocks = chain_segment_blocks();
+    blocks[invalid_index].signature = junk_signature();
+    blocks.truncate(2 * E::slots_per_epoch() as usize);
+
+    match harness.chain.process_chain_segment(blocks.clone()) {
+        ChainSegmentResult::Failed {
+            imported_blocks,
+            error: BlockError::InvalidSignature,
+        } => assert_eq!(
+            imported_blocks, invalid_index,
+            "should import the blocks prior to the invalid block"
+        ),
+        other => panic!("should fail with an invalid signature, got {:?}", other),
+    }
+
+    let is_imported = |block: &SignedBeaconBlock<E>| {
+        harness
+            .chain
+            .get_block(&block.canonical_root())
+            .expect("should read block")
+            .is_some()
+    };
+    assert!(is_imported(&blocks[invalid_index - 1]));
+    assert!(!is_imported(&blocks[invalid_index]));
+    assert!(!is_imported(&blocks[invalid_index + 1]));
+}
+
 #[test]
 fn chain_segment_non_linear_slots() {
     let harness = get_harness(VALIDATOR_COUNT);
@@ -11,6 +11,7 @@ use beacon_chain::BeaconSnapshot;
 use beacon_chain::StateSkipConfig;
 use rand::Rng;
 use sloggers::{null::NullLoggerBuilder, Build};
+use ssz::Encode;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -36,10 +37,16 @@ type E = MinimalEthSpec;
 type TestHarness = BeaconChainHarness<DiskHarnessType<E>>;
 
 fn get_store(db_path: &TempDir) -> Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>> {
+    get_store_with_config(db_path, StoreConfig::default())
+}
+
+fn get_store_with_config(
+    db_path: &TempDir,
+    config: StoreConfig,
+) -> Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>> {
     let spec = MinimalEthSpec::default_spec();
     let hot_path = db_path.path().join("hot_db");
     let cold_path = db_path.path().join("cold_db");
-    let config = StoreConfig::default();
     let log = NullLoggerBuilder.build().expect("logger should build");
     Arc::new(
         HotColdDB::open(&hot_path, &cold_path, config, spec, log)
@@ -253,6 +260,119 @@ fn split_slot_restore() {
     assert_eq!(store.get_split_slot(), split_slot);
 }
 
+// Check that the chain is stored correctly with the pre-finalization cache enabled, both when it
+// holds every unfinalized state and when it overflows to disk.
+#[test]
+fn prefinalization_cache() {
+    let state_size = {
+        let db_path = tempdir().unwrap();
+        let harness = get_harness(get_store(&db_path), LOW_VALIDATOR_COUNT);
+        harness
+            .chain
+            .head()
+            .expect("should get head")
+            .beacon_state
+            .ssz_bytes_len()
+    };
+
+    for &cache_states in &[1, 64] {
+        let db_path = tempdir().unwrap();
+        let config = StoreConfig {
+            prefinalization_cache_size: cache_states * state_size,
+            ..StoreConfig::default()
+        };
+
+        let (head_root, head_slot) = {
+            let store = get_store_with_config(&db_path, config.clone());
+            let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+            let num_blocks_produced = E::slots_per_epoch() * 5;
+
+            harness.extend_chain(
+                num_blocks_produced as usize,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            );
+
+            check_finalization(&harness, num_blocks_produced);
+            check_split_slot(&harness, store);
+            check_chain_dump(&harness, num_blocks_produced + 1);
+            check_iterators(&harness);
+
+            let head = harness.chain.head().expect("should get head");
+            (head.beacon_state_root, head.beacon_state.slot)
+        };
+
+        // Dropping the chain flushes the cache, so the head state can be loaded from disk.
+        let store = get_store_with_config(&db_path, config);
+        let state = store
+            .get_state(&head_root, Some(head_slot))
+            .expect("should load head state")
+            .expect("head state should exist");
+        assert_eq!(state.canonical_root(), head_root);
+    }
+}
+
+// Check that the database can be loaded after an unclean shutdown with the pre-finalization cache
+// enabled, and that the resumed chain continues to finalize.
+#[test]
+fn prefinalization_cache_unclean_shutdown() {
+    let db_path = tempdir().unwrap();
+    let config = StoreConfig {
+        prefinalization_cache_size: 1024 * 1024 * 1024,
+        ..StoreConfig::default()
+    };
+    let store = get_store_with_config(&db_path, config.clone());
+    let harness = get_harness(store, LOW_VALIDATOR_COUNT);
+
+    // Stop part way through an epoch, so that blocks were imported after the head was persisted.
+    let num_blocks_produced = E::slots_per_epoch() * 5 + E::slots_per_epoch() / 2;
+    harness.extend_chain(
+        num_blocks_produced as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+    let latest_slot = harness.chain.slot().expect("should have a slot");
+
+    // Simulate a crash by copying the database whilst the beacon chain is still running.
+    let crash_path = tempdir().unwrap();
+    for db in &["hot_db", "cold_db"] {
+        let dest = crash_path.path().join(db);
+        std::fs::create_dir(&dest).unwrap();
+        for entry in std::fs::read_dir(db_path.path().join(db)).unwrap() {
+            let entry = entry.unwrap();
+            std::fs::copy(entry.path(), dest.join(entry.file_name())).unwrap();
+        }
+    }
+
+    let resumed_harness = BeaconChainHarness::resume_from_disk_store(
+        MinimalEthSpec,
+        get_store_with_config(&crash_path, config),
+        KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec(),
+        tempdir().unwrap(),
+    );
+    drop(harness);
+
+    let resumed_head = resumed_harness.chain.head().expect("should get head");
+    assert!(resumed_head.beacon_state.finalized_checkpoint.epoch > 0);
+
+    resumed_harness
+        .chain
+        .slot_clock
+        .set_slot(latest_slot.as_u64() + 1);
+    resumed_harness.extend_chain(
+        (E::slots_per_epoch() * 3) as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head = resumed_harness.chain.head().expect("should get head");
+    assert!(
+        head.beacon_state.finalized_checkpoint.epoch
+            > resumed_head.beacon_state.finalized_checkpoint.epoch,
+        "the resumed chain should finalize"
+    );
+}
+
 // Check attestation processing and `load_epoch_boundary_state` in the presence of a split DB.
 // This is a bit of a monster test in that it tests lots of different things, but until they're
 // tested elsewhere, this is as good a place as any.
@@ -315,9 +435,13 @@ fn epoch_boundary_state_attestation_processing() {
             .finalized_checkpoint
             .epoch;
 
+        let subnet_id = attestation
+            .subnet_id(&harness.chain.spec)
+            .expect("should get subnet id");
+
         let res = harness
             .chain
-            .verify_unaggregated_attestation_for_gossip(attestation.clone());
+            .verify_unaggregated_attestation_for_gossip(attestation.clone(), subnet_id);
 
         let current_slot = harness.chain.slot().expect("should get slot");
         let expected_attestation_slot = attestation.data.slot;
@@ -8,13 +8,22 @@ use beacon_chain::{
     test_utils::{
         AttestationStrategy, BeaconChainHarness, BlockStrategy, HarnessType, OP_POOL_DB_KEY,
     },
+    BeaconChainError, BlockError, GossipOperationOutcome, StateSkipConfig,
 };
 use operation_pool::PersistedOperationPool;
+use state_processing::per_block_processing::errors::{ExitInvalid, ExitValidationError};
 use state_processing::{
     per_slot_processing, per_slot_processing::Error as SlotProcessingError, EpochProcessingError,
 };
 use store::config::StoreConfig;
-use types::{BeaconStateError, EthSpec, Hash256, Keypair, MinimalEthSpec, RelativeEpoch, Slot};
+use types::{
+    test_utils::{
+        AttesterSlashingTestTask, ProposerSlashingTestTask, TestingAttesterSlashingBuilder,
+        TestingProposerSlashingBuilder, TestingVoluntaryExitBuilder,
+    },
+    AttesterSlashing, BeaconStateError, Checkpoint, Epoch, EthSpec, Hash256, Keypair,
+    MinimalEthSpec, ProposerSlashing, RelativeEpoch, Signature, Slot,
+};
 
 // Should ideally be divisible by 3.
 pub const VALIDATOR_COUNT: usize = 24;
@@ -186,6 +195,58 @@ fn chooses_fork() {
     );
 }
 
+#[test]
+fn finds_common_ancestor_of_forks() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    let two_thirds = (VALIDATOR_COUNT / 3) * 2;
+    let delay = MinimalEthSpec::default_spec().min_attestation_inclusion_delay as usize;
+
+    let honest_validators: Vec<usize> = (0..two_thirds).collect();
+    let faulty_validators: Vec<usize> = (two_thirds..VALIDATOR_COUNT).collect();
+
+    let initial_blocks = delay + 1;
+
+    let fork_root = harness.extend_chain(
+        initial_blocks,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+    let fork_slot = Slot::from(initial_blocks);
+
+    let (honest_head, faulty_head) = harness.generate_two_forks_by_skipping_a_block(
+        &honest_validators,
+        &faulty_validators,
+        delay + 1,
+        delay + 2,
+    );
+
+    assert_eq!(
+        harness
+            .chain
+            .find_common_ancestor(honest_head, faulty_head)
+            .expect("should find common ancestor"),
+        (fork_root, fork_slot),
+        "the forks should meet at the last block before the fork"
+    );
+    assert_eq!(
+        harness
+            .chain
+            .find_common_ancestor(faulty_head, honest_head)
+            .expect("should find common ancestor"),
+        (fork_root, fork_slot),
+        "the common ancestor should not depend on the order of the heads"
+    );
+    assert_eq!(
+        harness
+            .chain
+            .find_common_ancestor(honest_head, fork_root)
+            .expect("should find common ancestor"),
+        (fork_root, fork_slot),
+        "the common ancestor of a block and its descendant should be the block"
+    );
+}
+
 #[test]
 fn finalizes_with_full_participation() {
     let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
@@ -221,6 +282,105 @@ fn finalizes_with_full_participation() {
     );
 }
 
+#[test]
+fn verifies_weak_subjectivity_checkpoint() {
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
+
+    let mut harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        num_blocks_produced as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let finalized_checkpoint = harness
+        .chain
+        .head_info()
+        .expect("should get head info")
+        .finalized_checkpoint;
+    assert!(
+        finalized_checkpoint.epoch > 0,
+        "the chain should have finalized an epoch after genesis"
+    );
+
+    harness.chain.weak_subjectivity_checkpoint = Some(finalized_checkpoint.clone());
+    assert!(
+        harness.chain.verify_weak_subjectivity_checkpoint().is_ok(),
+        "the finalized checkpoint should be consistent with the chain"
+    );
+
+    harness.chain.weak_subjectivity_checkpoint = Some(Checkpoint {
+        epoch: finalized_checkpoint.epoch + 1,
+        root: Hash256::repeat_byte(42),
+    });
+    assert!(
+        harness.chain.verify_weak_subjectivity_checkpoint().is_ok(),
+        "an unfinalized checkpoint should not be verified"
+    );
+
+    let conflicting_checkpoint = Checkpoint {
+        epoch: finalized_checkpoint.epoch,
+        root: Hash256::repeat_byte(42),
+    };
+    harness.chain.weak_subjectivity_checkpoint = Some(conflicting_checkpoint.clone());
+    match harness.chain.verify_weak_subjectivity_checkpoint() {
+        Err(BeaconChainError::WeakSubjectivityConflict { checkpoint, found }) => {
+            assert_eq!(checkpoint, conflicting_checkpoint);
+            assert_eq!(found, finalized_checkpoint.root);
+        }
+        other => panic!("expected a weak subjectivity conflict, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_block_conflicting_with_weak_subjectivity_checkpoint() {
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
+
+    let mut harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        num_blocks_produced as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head_info = harness.chain.head_info().expect("should get head info");
+    let finalized_checkpoint = head_info.finalized_checkpoint;
+
+    // Pretend that the chain was built by an attacker, which finalized a different root.
+    let conflicting_checkpoint = Checkpoint {
+        epoch: finalized_checkpoint.epoch,
+        root: Hash256::repeat_byte(42),
+    };
+    harness.chain.weak_subjectivity_checkpoint = Some(conflicting_checkpoint.clone());
+
+    harness.advance_slot();
+    let (block, _) = harness.build_block(
+        harness.get_head_state(),
+        harness.get_chain_slot(),
+        BlockStrategy::OnCanonicalHead,
+    );
+
+    match harness.chain.process_block(block) {
+        Err(BlockError::WeakSubjectivityConflict { checkpoint, found }) => {
+            assert_eq!(checkpoint, conflicting_checkpoint);
+            assert_eq!(found, finalized_checkpoint.root);
+        }
+        other => panic!("expected a weak subjectivity conflict, got {:?}", other),
+    }
+
+    assert_eq!(
+        harness
+            .chain
+            .head_info()
+            .expect("should get head info")
+            .block_root,
+        head_info.block_root,
+        "the conflicting block should not be imported"
+    );
+}
+
 #[test]
 fn finalizes_with_two_thirds_participation() {
     let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
@@ -368,6 +528,117 @@ fn roundtrip_operation_pool() {
     assert_eq!(harness.chain.op_pool, restored_op_pool);
 }
 
+fn proposer_slashing(
+    harness: &BeaconChainHarness<HarnessType<MinimalEthSpec>>,
+    test_task: ProposerSlashingTestTask,
+    proposer_index: u64,
+) -> ProposerSlashing {
+    let head_info = harness.chain.head_info().expect("should get head info");
+    TestingProposerSlashingBuilder::double_vote::<MinimalEthSpec>(
+        test_task,
+        proposer_index,
+        &KEYPAIRS[proposer_index as usize].sk,
+        &head_info.fork,
+        head_info.genesis_validators_root,
+        &harness.spec,
+    )
+}
+
+fn attester_slashing(
+    harness: &BeaconChainHarness<HarnessType<MinimalEthSpec>>,
+    test_task: AttesterSlashingTestTask,
+    validator_indices: &[u64],
+) -> AttesterSlashing<MinimalEthSpec> {
+    let head_info = harness.chain.head_info().expect("should get head info");
+    TestingAttesterSlashingBuilder::double_vote(
+        test_task,
+        validator_indices,
+        |validator_index, message| Signature::new(message, &KEYPAIRS[validator_index as usize].sk),
+        &head_info.fork,
+        head_info.genesis_validators_root,
+        &harness.spec,
+    )
+}
+
+#[test]
+fn gossip_slashings_are_queued() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    assert_eq!(
+        harness
+            .chain
+            .verify_proposer_slashing_for_gossip(proposer_slashing(
+                &harness,
+                ProposerSlashingTestTask::Valid,
+                1
+            )),
+        GossipOperationOutcome::Queued
+    );
+    assert!(harness.chain.op_pool.contains_proposer_slashing(1));
+
+    let slashing = attester_slashing(&harness, AttesterSlashingTestTask::Valid, &[2, 3]);
+    assert_eq!(
+        harness
+            .chain
+            .verify_attester_slashing_for_gossip(slashing.clone()),
+        GossipOperationOutcome::Queued
+    );
+    assert!(harness.chain.op_pool.contains_attester_slashing(&slashing));
+}
+
+#[test]
+fn invalid_gossip_slashings_are_not_queued() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    match harness
+        .chain
+        .verify_proposer_slashing_for_gossip(proposer_slashing(
+            &harness,
+            ProposerSlashingTestTask::BadProposal1Signature,
+            1,
+        )) {
+        GossipOperationOutcome::Invalid(_) => {}
+        other => panic!("should reject proposer slashing, got {:?}", other),
+    }
+    assert!(!harness.chain.op_pool.contains_proposer_slashing(1));
+
+    let slashing = attester_slashing(&harness, AttesterSlashingTestTask::NotSlashable, &[2, 3]);
+    match harness
+        .chain
+        .verify_attester_slashing_for_gossip(slashing.clone())
+    {
+        GossipOperationOutcome::Invalid(_) => {}
+        other => panic!("should reject attester slashing, got {:?}", other),
+    }
+    assert!(!harness.chain.op_pool.contains_attester_slashing(&slashing));
+}
+
+#[test]
+fn invalid_gossip_voluntary_exits_are_not_queued() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let head_info = harness.chain.head_info().expect("should get head info");
+
+    // Validators must be active for `persistent_committee_period` epochs before they may exit.
+    let validator_index = 1;
+    let exit = TestingVoluntaryExitBuilder::new(Epoch::new(0), validator_index).build(
+        &KEYPAIRS[validator_index as usize].sk,
+        &head_info.fork,
+        head_info.genesis_validators_root,
+        &harness.spec,
+    );
+
+    match harness.chain.verify_voluntary_exit_for_gossip(exit) {
+        GossipOperationOutcome::Invalid(ExitValidationError::Invalid(
+            ExitInvalid::TooYoungToExit { .. },
+        )) => {}
+        other => panic!("should reject voluntary exit, got {:?}", other),
+    }
+    assert!(!harness
+        .chain
+        .op_pool
+        .contains_voluntary_exit(validator_index));
+}
+
 #[test]
 fn unaggregated_attestations_added_to_fork_choice_some_none() {
     let num_blocks_produced = MinimalEthSpec::slots_per_epoch() / 2;
@@ -454,9 +725,13 @@ fn attestations_with_increasing_slots() {
     }
 
     for attestation in attestations.into_iter().flatten() {
+        let subnet_id = attestation
+            .subnet_id(&harness.chain.spec)
+            .expect("should get subnet id");
+
         let res = harness
             .chain
-            .verify_unaggregated_attestation_for_gossip(attestation.clone());
+            .verify_unaggregated_attestation_for_gossip(attestation.clone(), subnet_id);
 
         let current_slot = harness.chain.slot().expect("should get slot");
         let expected_attestation_slot = attestation.data.slot;
@@ -606,3 +881,40 @@ fn produces_and_processes_with_genesis_skip_slots() {
         run_skip_slot_test(i)
     }
 }
+
+#[test]
+fn advanced_head_state_matches_state_skip() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    // Stop just before an epoch boundary, such that advancing the head performs epoch processing.
+    harness.extend_chain(
+        MinimalEthSpec::slots_per_epoch() as usize - 2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let next_slot = harness.chain.slot().expect("should get slot") + 1;
+    let spec = &harness.chain.spec;
+
+    let mut expected = harness.chain.head().expect("should get head").beacon_state;
+    while expected.slot < next_slot {
+        per_slot_processing(&mut expected, None, spec).expect("should process slot");
+    }
+
+    harness
+        .chain
+        .advance_head_state()
+        .expect("should advance head state");
+
+    let advanced = harness
+        .chain
+        .state_at_slot(next_slot, StateSkipConfig::WithStateRoots)
+        .expect("should get state at next slot");
+
+    assert_eq!(advanced.slot, next_slot);
+    assert_eq!(
+        advanced.canonical_root(),
+        expected.canonical_root(),
+        "advanced state should match a regular state skip"
+    );
+}
@@ -1,7 +1,8 @@
 use crate::config::{ClientGenesis, Config as ClientConfig};
 use crate::notifier::spawn_notifier;
+use crate::slasher_service::spawn_slasher_service;
 use crate::Client;
-use beacon_chain::events::TeeEventHandler;
+use beacon_chain::events::{EventBuses, TeeEventHandler};
 use beacon_chain::{
     builder::{BeaconChainBuilder, Witness},
     eth1_chain::{CachingEth1Backend, Eth1Chain},
@@ -10,26 +11,23 @@ use beacon_chain::{
     store::{HotColdDB, ItemStore, LevelDB, StoreConfig},
     BeaconChain, BeaconChainTypes, Eth1ChainBackend, EventHandler,
 };
-use bus::Bus;
 use environment::RuntimeContext;
 use eth1::{Config as Eth1Config, Service as Eth1Service};
 use eth2_config::Eth2Config;
 use eth2_libp2p::NetworkGlobals;
 use genesis::{interop_genesis_state, Eth1GenesisService};
-use network::{NetworkConfig, NetworkMessage, NetworkService};
-use parking_lot::Mutex;
-use slog::info;
+use network::{BlockProcessorEvent, NetworkConfig, NetworkMessage, NetworkService};
+use slasher::Slasher;
+use slog::{info, warn};
 use ssz::Decode;
+use state_processing::per_epoch_processing::parallel as parallel_epoch_processing;
 use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 use timer::spawn_timer;
-use tokio::sync::mpsc::UnboundedSender;
-use types::{
-    test_utils::generate_deterministic_keypairs, BeaconState, ChainSpec, EthSpec,
-    SignedBeaconBlockHash,
-};
+use tokio::sync::{broadcast, mpsc::UnboundedSender};
+use types::{test_utils::generate_deterministic_keypairs, BeaconState, ChainSpec, EthSpec};
 use websocket_server::{Config as WebSocketConfig, WebSocketSender};
 
 /// Interval between polling the eth1 node for genesis information.
@@ -57,9 +55,11 @@ pub struct ClientBuilder<T: BeaconChainTypes> {
     beacon_chain_builder: Option<BeaconChainBuilder<T>>,
     beacon_chain: Option<Arc<BeaconChain<T>>>,
     eth1_service: Option<Eth1Service>,
+    slasher: Option<Arc<Slasher<T::EthSpec>>>,
     event_handler: Option<T::EventHandler>,
     network_globals: Option<Arc<NetworkGlobals<T::EthSpec>>>,
     network_send: Option<UnboundedSender<NetworkMessage<T::EthSpec>>>,
+    block_processor_events: Option<broadcast::Sender<BlockProcessorEvent>>,
     http_listen_addr: Option<SocketAddr>,
     websocket_listen_addr: Option<SocketAddr>,
     eth_spec_instance: T::EthSpec,
@@ -99,9 +99,11 @@ where
             beacon_chain_builder: None,
             beacon_chain: None,
             eth1_service: None,
+            slasher: None,
             event_handler: None,
             network_globals: None,
             network_send: None,
+            block_processor_events: None,
             http_listen_addr: None,
             websocket_listen_addr: None,
             eth_spec_instance,
@@ -134,6 +136,13 @@ where
         let eth_spec_instance = self.eth_spec_instance.clone();
         let data_dir = config.data_dir.clone();
         let disabled_forks = config.disabled_forks.clone();
+        let snapshot_cache_size = config.snapshot_cache_size;
+        let disable_tree_hash_cache = config.disable_tree_hash_cache;
+        let validator_monitor_auto = config.validator_monitor_auto;
+        let validator_monitor_auto_limit = config.validator_monitor_auto_limit;
+        let validator_monitor_pubkeys = config.validator_monitor_pubkeys.clone();
+        let slasher_config = config.slasher.clone();
+        let weak_subjectivity_checkpoint = config.weak_subjectivity_checkpoint.clone();
 
         let store =
             store.ok_or_else(|| "beacon_chain_start_method requires a store".to_string())?;
@@ -145,13 +154,35 @@ where
         let spec = chain_spec
             .ok_or_else(|| "beacon_chain_start_method requires a chain spec".to_string())?;
 
-        let builder = BeaconChainBuilder::new(eth_spec_instance)
+        parallel_epoch_processing::set_num_threads(config.epoch_processing_threads)?;
+
+        let mut builder = BeaconChainBuilder::new(eth_spec_instance)
             .logger(context.log().clone())
             .store(store)
             .store_migrator(store_migrator)
             .data_dir(data_dir)
             .custom_spec(spec.clone())
-            .disabled_forks(disabled_forks);
+            .disabled_forks(disabled_forks)
+            .snapshot_cache_size(snapshot_cache_size)
+            .disable_tree_hash_cache(disable_tree_hash_cache)
+            .monitor_validators(
+                validator_monitor_auto,
+                validator_monitor_auto_limit,
+                validator_monitor_pubkeys,
+            )
+            .shutdown_sender(context.executor.shutdown_sender());
+
+        if let Some(checkpoint) = weak_subjectivity_checkpoint {
+            builder = builder.weak_subjectivity_checkpoint(checkpoint);
+        }
+
+        if let Some(slasher_config) = slasher_config {
+            let slasher = Slasher::open(slasher_config, context.log().clone())
+                .map(Arc::new)
+                .map_err(|e| format!("Unable to open slasher database: {:?}", e))?;
+            builder = builder.slasher(slasher.clone());
+            self.slasher = Some(slasher);
+        }
 
         let chain_exists = builder
             .store_contains_beacon_chain()
@@ -199,7 +230,7 @@ where
                 info!(
                     context.log(),
                     "Waiting for eth2 genesis from eth1";
-                    "eth1_endpoint" => &config.eth1.endpoint,
+                    "eth1_endpoints" => format!("{:?}", &config.eth1.endpoints),
                     "contract_deploy_block" => config.eth1.deposit_contract_deploy_block,
                     "deposit_contract" => &config.eth1.deposit_contract_address
                 );
@@ -237,12 +268,13 @@ where
             .ok_or_else(|| "network requires a runtime_context")?
             .clone();
 
-        let (network_globals, network_send) =
+        let (network_globals, network_send, block_processor_events) =
             NetworkService::start(beacon_chain, config, context.executor)
                 .map_err(|e| format!("Failed to start network: {:?}", e))?;
 
         self.network_globals = Some(network_globals);
         self.network_send = Some(network_send);
+        self.block_processor_events = Some(block_processor_events);
 
         Ok(self)
     }
@@ -275,7 +307,7 @@ where
         mut self,
         client_config: &ClientConfig,
         eth2_config: &Eth2Config,
-        events: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+        events: EventBuses<TEthSpec>,
     ) -> Result<Self, String> {
         let beacon_chain = self
             .beacon_chain
@@ -294,10 +326,15 @@ where
             .network_send
             .clone()
             .ok_or_else(|| "http_server requires a libp2p network sender")?;
+        let block_processor_events = self
+            .block_processor_events
+            .clone()
+            .ok_or_else(|| "http_server requires the block processor events")?;
 
         let network_info = rest_api::NetworkInfo {
             network_globals,
             network_chan: network_send,
+            block_processor_events,
         };
 
         let listening_addr = rest_api::start_server(
@@ -353,6 +390,33 @@ where
         Ok(self)
     }
 
+    /// Immediately starts the service that processes the attestations and blocks queued in the
+    /// slasher, if the slasher is enabled.
+    pub fn slasher_service(self) -> Result<Self, String> {
+        let slasher = match self.slasher.clone() {
+            Some(slasher) => slasher,
+            None => return Ok(self),
+        };
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "slasher requires a runtime_context")?
+            .service_context("slasher".into());
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "slasher requires a beacon chain")?;
+        let network_send = self
+            .network_send
+            .clone()
+            .ok_or_else(|| "slasher requires a libp2p network sender")?;
+
+        spawn_slasher_service(context.executor, beacon_chain, slasher, network_send)
+            .map_err(|e| format!("Unable to start slasher service: {}", e))?;
+
+        Ok(self)
+    }
+
     /// Consumers the builder, returning a `Client` if all necessary components have been
     /// specified.
     ///
@@ -495,7 +559,7 @@ where
     pub fn tee_event_handler(
         mut self,
         config: WebSocketConfig,
-    ) -> Result<(Self, Arc<Mutex<Bus<SignedBeaconBlockHash>>>), String> {
+    ) -> Result<(Self, EventBuses<TEthSpec>), String> {
         let context = self
             .runtime_context
             .as_ref()
@@ -512,9 +576,9 @@ where
         };
 
         self.websocket_listen_addr = listening_addr;
-        let (tee_event_handler, bus) = TeeEventHandler::new(log, sender)?;
+        let (tee_event_handler, buses) = TeeEventHandler::new(log, sender)?;
         self.event_handler = Some(tee_event_handler);
-        Ok((self, bus))
+        Ok((self, buses))
     }
 }
 
@@ -645,15 +709,22 @@ where
         } else {
             beacon_chain_builder
                 .get_persisted_eth1_backend()?
-                .map(|persisted| {
-                    Eth1Chain::from_ssz_container(
-                        &persisted,
-                        config.clone(),
-                        &context.log().clone(),
-                    )
-                    .map(|chain| chain.into_backend())
+                .and_then(|persisted| {
+                    Eth1Chain::from_ssz_container(&persisted, config.clone(), context.log())
+                        .map(|chain| chain.into_backend())
+                        // The persisted cache may have been written by an older version with a
+                        // different layout. It is rebuilt from the eth1 node, so discard it.
+                        .map_err(|e| {
+                            warn!(
+                                context.log(),
+                                "Unable to load persisted eth1 cache";
+                                "info" => "the cache will be rebuilt from the eth1 node",
+                                "error" => e
+                            )
+                        })
+                        .ok()
                 })
-                .unwrap_or_else(|| Ok(CachingEth1Backend::new(config, context.log().clone())))?
+                .unwrap_or_else(|| CachingEth1Backend::new(config, context.log().clone()))
         };
 
         self.eth1_service = None;
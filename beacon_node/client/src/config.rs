@@ -1,7 +1,9 @@
+use beacon_chain::{DEFAULT_AUTO_REGISTER_LIMIT, DEFAULT_SNAPSHOT_CACHE_SIZE};
 use network::NetworkConfig;
 use serde_derive::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use types::{Checkpoint, PublicKeyBytes};
 
 pub const DEFAULT_DATADIR: &str = ".lighthouse";
 
@@ -55,6 +57,24 @@ pub struct Config {
     pub sync_eth1_chain: bool,
     /// A list of hard-coded forks that will be disabled.
     pub disabled_forks: Vec<String>,
+    /// The number of recent block and state pairs cached for block processing.
+    pub snapshot_cache_size: usize,
+    /// If true, state roots are computed without a tree hash cache, for debugging.
+    pub disable_tree_hash_cache: bool,
+    /// The number of threads used for parallel epoch processing, or zero to use the global
+    /// thread pool. Only used if the `parallel_epoch_processing` feature is enabled.
+    pub epoch_processing_threads: usize,
+    /// If true, the validators of any validator client which requests duties from this node are
+    /// monitored.
+    pub validator_monitor_auto: bool,
+    /// The maximum number of validators which are monitored due to `validator_monitor_auto`.
+    pub validator_monitor_auto_limit: usize,
+    /// The public keys of validators which are monitored.
+    pub validator_monitor_pubkeys: Vec<PublicKeyBytes>,
+    /// The configuration of the slasher, which is only run if present.
+    pub slasher: Option<slasher::Config>,
+    /// A checkpoint which the canonical chain must pass through, if any.
+    pub weak_subjectivity_checkpoint: Option<Checkpoint>,
     #[serde(skip)]
     /// The `genesis` field is not serialized or deserialized by `serde` to ensure it is defined
     /// via the CLI at runtime, instead of from a configuration file saved to disk.
@@ -84,6 +104,14 @@ impl Default for Config {
             sync_eth1_chain: false,
             eth1: <_>::default(),
             disabled_forks: Vec::new(),
+            snapshot_cache_size: DEFAULT_SNAPSHOT_CACHE_SIZE,
+            disable_tree_hash_cache: false,
+            epoch_processing_threads: 0,
+            validator_monitor_auto: false,
+            validator_monitor_auto_limit: DEFAULT_AUTO_REGISTER_LIMIT,
+            validator_monitor_pubkeys: vec![],
+            slasher: None,
+            weak_subjectivity_checkpoint: None,
         }
     }
 }
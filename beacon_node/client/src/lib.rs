@@ -3,6 +3,7 @@ extern crate slog;
 pub mod config;
 mod metrics;
 mod notifier;
+mod slasher_service;
 
 pub mod builder;
 pub mod error;
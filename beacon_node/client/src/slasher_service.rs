@@ -0,0 +1,171 @@
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use eth2_libp2p::PubsubMessage;
+use futures::StreamExt;
+use network::NetworkMessage;
+use slasher::Slasher;
+use slog::{debug, error, info, Logger};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+use tokio::time::{interval_at, Instant};
+
+/// Spawns a service which periodically processes the attestations and blocks queued in the
+/// slasher. If broadcasting is enabled, the detected slashings are added to the op pool and
+/// published on gossip.
+pub fn spawn_slasher_service<T: BeaconChainTypes>(
+    executor: environment::TaskExecutor,
+    beacon_chain: Arc<BeaconChain<T>>,
+    slasher: Arc<Slasher<T::EthSpec>>,
+    network_send: UnboundedSender<NetworkMessage<T::EthSpec>>,
+) -> Result<(), String> {
+    let log = executor.log().clone();
+    let update_period = Duration::from_secs(slasher.config().update_period);
+    if update_period == Duration::from_secs(0) {
+        return Err("slasher update period must be non-zero".into());
+    }
+
+    info!(
+        log,
+        "Slasher service started";
+        "database" => format!("{:?}", slasher.config().database_path),
+        "history_length" => slasher.config().history_length,
+        "broadcast" => slasher.config().broadcast,
+    );
+
+    let mut interval = interval_at(Instant::now() + update_period, update_period);
+    let inner_executor = executor.clone();
+    let slasher_future = async move {
+        while interval.next().await.is_some() {
+            // Processing the previous batch may take longer than the update period, in which
+            // case the messages queued since are left for the next tick.
+            if slasher.is_processing() {
+                debug!(log, "Slasher still processing, skipping update");
+                continue;
+            }
+
+            let beacon_chain = beacon_chain.clone();
+            let slasher = slasher.clone();
+            let network_send = network_send.clone();
+            let log = log.clone();
+            let batch_executor = inner_executor.clone();
+            inner_executor.spawn_blocking(
+                move || {
+                    process_batch(
+                        &batch_executor,
+                        &beacon_chain,
+                        &slasher,
+                        &network_send,
+                        &log,
+                    )
+                },
+                "slasher_batch",
+            );
+        }
+    };
+
+    executor.spawn(slasher_future, "slasher_service");
+    Ok(())
+}
+
+/// Processes the queued attestations and blocks, before importing and publishing the detected
+/// slashings which are still valid against the head state, if broadcasting is enabled. The outcome
+/// of publishing the slashings is logged once known.
+fn process_batch<T: BeaconChainTypes>(
+    executor: &environment::TaskExecutor,
+    beacon_chain: &BeaconChain<T>,
+    slasher: &Slasher<T::EthSpec>,
+    network_send: &UnboundedSender<NetworkMessage<T::EthSpec>>,
+    log: &Logger,
+) {
+    let current_epoch = match beacon_chain.epoch() {
+        Ok(epoch) => epoch,
+        Err(e) => {
+            error!(log, "Slasher unable to read current epoch"; "error" => format!("{:?}", e));
+            return;
+        }
+    };
+
+    if let Err(e) = slasher.process_queued(current_epoch) {
+        error!(
+            log,
+            "Error during slasher processing";
+            "epoch" => current_epoch,
+            "error" => format!("{:?}", e),
+        );
+        return;
+    }
+
+    let attester_slashings = slasher.get_attester_slashings();
+    let proposer_slashings = slasher.get_proposer_slashings();
+
+    if !slasher.config().broadcast {
+        if !attester_slashings.is_empty() || !proposer_slashings.is_empty() {
+            info!(
+                log,
+                "Slasher detected slashable offences";
+                "attester_slashings" => attester_slashings.len(),
+                "proposer_slashings" => proposer_slashings.len(),
+            );
+        }
+        return;
+    }
+
+    let mut messages = vec![];
+
+    for slashing in attester_slashings {
+        match beacon_chain.process_attester_slashing(slashing.clone()) {
+            Ok(()) => messages.push(PubsubMessage::AttesterSlashing(Box::new(slashing))),
+            // The offenders may already have been slashed.
+            Err(e) => debug!(
+                log,
+                "Attester slashing from slasher is invalid";
+                "error" => format!("{:?}", e),
+            ),
+        }
+    }
+
+    for slashing in proposer_slashings {
+        let proposer_index = slashing.signed_header_1.message.proposer_index;
+        match beacon_chain.process_proposer_slashing(slashing.clone()) {
+            Ok(()) => messages.push(PubsubMessage::ProposerSlashing(Box::new(slashing))),
+            Err(e) => debug!(
+                log,
+                "Proposer slashing from slasher is invalid";
+                "proposer_index" => proposer_index,
+                "error" => format!("{:?}", e),
+            ),
+        }
+    }
+
+    if !messages.is_empty() {
+        let count = messages.len();
+        info!(log, "Publishing slashings from slasher"; "count" => count);
+        let (result, publish_result) = oneshot::channel();
+        if let Err(e) = network_send.send(NetworkMessage::Publish {
+            messages,
+            result: Some(result),
+        }) {
+            error!(log, "Unable to publish slashings"; "error" => format!("{:?}", e));
+            return;
+        }
+
+        let log = log.clone();
+        executor.spawn(
+            async move {
+                match publish_result.await {
+                    Ok(Ok(())) => debug!(log, "Published slashings from slasher"; "count" => count),
+                    Ok(Err(e)) => error!(
+                        log,
+                        "Unable to publish slashings";
+                        "count" => count,
+                        "error" => format!("{:?}", e),
+                    ),
+                    // The network has shut down.
+                    Err(_) => (),
+                }
+            },
+            "slasher_publish_result",
+        );
+    }
+}
@@ -1,9 +1,10 @@
 use crate::DepositLog;
+use rayon::prelude::*;
 use ssz_derive::{Decode, Encode};
 use state_processing::common::DepositDataTree;
 use std::cmp::Ordering;
 use tree_hash::TreeHash;
-use types::{Deposit, Hash256, DEPOSIT_TREE_DEPTH};
+use types::{Deposit, DepositTreeSnapshot, Hash256, DEPOSIT_TREE_DEPTH};
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
@@ -18,6 +19,12 @@ pub enum Error {
         known_deposits: usize,
         requested: u64,
     },
+    /// The requested deposits are part of the finalized deposit snapshot the cache was created
+    /// from, so they are not known.
+    DepositsFinalized {
+        requested: u64,
+        finalized_deposits: u64,
+    },
     /// A log with the given index is already present in the cache and it does not match the one
     /// provided.
     DuplicateDistinctLog(u64),
@@ -37,6 +44,7 @@ pub struct SszDepositCache {
     leaves: Vec<Hash256>,
     deposit_contract_deploy_block: u64,
     deposit_roots: Vec<Hash256>,
+    deposit_snapshot: Option<DepositTreeSnapshot>,
 }
 
 impl SszDepositCache {
@@ -46,12 +54,21 @@ impl SszDepositCache {
             leaves: cache.leaves.clone(),
             deposit_contract_deploy_block: cache.deposit_contract_deploy_block,
             deposit_roots: cache.deposit_roots.clone(),
+            deposit_snapshot: cache.deposit_snapshot.clone(),
         }
     }
 
     pub fn to_deposit_cache(&self) -> Result<DepositCache, String> {
-        let deposit_tree =
-            DepositDataTree::create(&self.leaves, self.leaves.len(), DEPOSIT_TREE_DEPTH);
+        let deposit_tree = match &self.deposit_snapshot {
+            Some(snapshot) => {
+                let mut tree = DepositDataTree::from_snapshot(snapshot, DEPOSIT_TREE_DEPTH)
+                    .map_err(|e| format!("Invalid SszDepositCache snapshot: {:?}", e))?;
+                tree.push_leaves(&self.leaves)
+                    .map_err(|e| format!("Invalid SszDepositCache leaves: {:?}", e))?;
+                tree
+            }
+            None => DepositDataTree::create(&self.leaves, self.leaves.len(), DEPOSIT_TREE_DEPTH),
+        };
         // Check for invalid SszDepositCache conditions
         if self.leaves.len() != self.logs.len() {
             return Err("Invalid SszDepositCache: logs and leaves should have equal length".into());
@@ -69,6 +86,7 @@ impl SszDepositCache {
             deposit_contract_deploy_block: self.deposit_contract_deploy_block,
             deposit_tree,
             deposit_roots: self.deposit_roots.clone(),
+            deposit_snapshot: self.deposit_snapshot.clone(),
         })
     }
 }
@@ -76,6 +94,9 @@ impl SszDepositCache {
 /// Mirrors the merkle tree of deposits in the eth1 deposit contract.
 ///
 /// Provides `Deposit` objects with merkle proofs included.
+///
+/// If the cache was created from a `DepositTreeSnapshot`, it only stores the logs (and can only
+/// provide proofs) for deposits after the snapshot.
 pub struct DepositCache {
     logs: Vec<DepositLog>,
     leaves: Vec<Hash256>,
@@ -84,8 +105,10 @@ pub struct DepositCache {
     /// deposit contract tree.
     deposit_tree: DepositDataTree,
     /// Vector of deposit roots. `deposit_roots[i]` denotes `deposit_root` at
-    /// `deposit_index` `i`.
+    /// `deposit_index` `i` (offset by the snapshot deposit count, if any).
     deposit_roots: Vec<Hash256>,
+    /// The finalized snapshot from which the cache was created, if any.
+    deposit_snapshot: Option<DepositTreeSnapshot>,
 }
 
 impl Default for DepositCache {
@@ -98,6 +121,7 @@ impl Default for DepositCache {
             deposit_contract_deploy_block: 1,
             deposit_tree,
             deposit_roots,
+            deposit_snapshot: None,
         }
     }
 }
@@ -112,7 +136,50 @@ impl DepositCache {
         }
     }
 
+    /// Create a new `DepositCache` which continues from the finalized deposit tree `snapshot`,
+    /// rather than from the deployment of the deposit contract.
+    ///
+    /// ## Errors
+    ///
+    /// - If the `snapshot` hashes do not match its deposit root.
+    pub fn from_deposit_snapshot(
+        deposit_contract_deploy_block: u64,
+        snapshot: DepositTreeSnapshot,
+    ) -> Result<Self, Error> {
+        let deposit_tree = DepositDataTree::from_snapshot(&snapshot, DEPOSIT_TREE_DEPTH)
+            .map_err(Error::DepositTreeError)?;
+        let deposit_roots = vec![deposit_tree.root()];
+        Ok(DepositCache {
+            logs: Vec::new(),
+            leaves: Vec::new(),
+            deposit_contract_deploy_block,
+            deposit_tree,
+            deposit_roots,
+            deposit_snapshot: Some(snapshot),
+        })
+    }
+
+    /// Returns the snapshot from which the cache was created, if any.
+    pub fn deposit_snapshot(&self) -> Option<&DepositTreeSnapshot> {
+        self.deposit_snapshot.as_ref()
+    }
+
+    /// Returns the number of deposits in the snapshot from which the cache was created, or zero
+    /// if it was not created from a snapshot.
+    pub fn finalized_deposit_count(&self) -> u64 {
+        self.deposit_snapshot
+            .as_ref()
+            .map_or(0, |snapshot| snapshot.deposit_count)
+    }
+
+    /// Returns the total number of deposits known to the cache, including those in the snapshot.
+    pub fn deposit_count(&self) -> u64 {
+        self.finalized_deposit_count() + self.logs.len() as u64
+    }
+
     /// Returns the number of deposits available in the cache.
+    ///
+    /// Does not include the deposits in the snapshot from which the cache was created.
     pub fn len(&self) -> usize {
         self.logs.len()
     }
@@ -140,14 +207,20 @@ impl DepositCache {
     /// Adds `log` to self.
     ///
     /// This function enforces that `logs` are imported one-by-one with no gaps between
-    /// `log.index`, starting at `log.index == 0`.
+    /// `log.index`, starting at `log.index == 0` (or the snapshot deposit count). Logs which are
+    /// part of the snapshot are ignored.
     ///
     /// ## Errors
     ///
     /// - If a log with index `log.index - 1` is not already present in `self` (ignored when empty).
     /// - If a log with `log.index` is already known, but the given `log` is distinct to it.
     pub fn insert_log(&mut self, log: DepositLog) -> Result<(), Error> {
-        match log.index.cmp(&(self.logs.len() as u64)) {
+        let finalized_deposit_count = self.finalized_deposit_count();
+        if log.index < finalized_deposit_count {
+            return Ok(());
+        }
+
+        match log.index.cmp(&self.deposit_count()) {
             Ordering::Equal => {
                 let deposit = log.deposit_data.tree_hash_root();
                 self.leaves.push(deposit);
@@ -159,7 +232,7 @@ impl DepositCache {
                 Ok(())
             }
             Ordering::Less => {
-                if self.logs[log.index as usize] == log {
+                if self.logs[(log.index - finalized_deposit_count) as usize] == log {
                     Ok(())
                 } else {
                     Err(Error::DuplicateDistinctLog(log.index))
@@ -167,11 +240,81 @@ impl DepositCache {
             }
             Ordering::Greater => Err(Error::NonConsecutive {
                 log_index: log.index,
-                expected: self.logs.len(),
+                expected: self.deposit_count() as usize,
             }),
         }
     }
 
+    /// Adds a batch of consecutive `logs` to self.
+    ///
+    /// Equivalent to calling `Self::insert_log` for each log, except that the logs are only
+    /// added if they are all valid. The leaves, the deposit tree and the deposit roots of the new
+    /// logs are computed in parallel, which is much faster when syncing many deposits.
+    ///
+    /// ## Errors
+    ///
+    /// The same as `Self::insert_log`, for any of the `logs`.
+    pub fn insert_logs(&mut self, logs: Vec<DepositLog>) -> Result<(), Error> {
+        let finalized_deposit_count = self.finalized_deposit_count();
+        let initial_deposit_count = self.deposit_count();
+
+        let mut new_logs = vec![];
+        for log in logs {
+            if log.index < finalized_deposit_count {
+                continue;
+            }
+
+            let deposit_count = initial_deposit_count + new_logs.len() as u64;
+            match log.index.cmp(&deposit_count) {
+                Ordering::Equal => new_logs.push(log),
+                Ordering::Less => {
+                    let known_log = if log.index < initial_deposit_count {
+                        &self.logs[(log.index - finalized_deposit_count) as usize]
+                    } else {
+                        &new_logs[(log.index - initial_deposit_count) as usize]
+                    };
+                    if *known_log != log {
+                        return Err(Error::DuplicateDistinctLog(log.index));
+                    }
+                }
+                Ordering::Greater => {
+                    return Err(Error::NonConsecutive {
+                        log_index: log.index,
+                        expected: deposit_count as usize,
+                    })
+                }
+            }
+        }
+
+        if new_logs.is_empty() {
+            return Ok(());
+        }
+
+        let leaves = new_logs
+            .par_iter()
+            .map(|log| log.deposit_data.tree_hash_root())
+            .collect::<Vec<_>>();
+        self.deposit_tree
+            .push_leaves(&leaves)
+            .map_err(Error::DepositTreeError)?;
+
+        // The deposit roots are relative to the whole tree, including the snapshot.
+        let first_deposit_count = initial_deposit_count as usize + 1;
+        let last_deposit_count = initial_deposit_count as usize + leaves.len();
+        let deposit_tree = &self.deposit_tree;
+        let deposit_roots = (first_deposit_count..=last_deposit_count)
+            .into_par_iter()
+            .map(|deposit_count| deposit_tree.root_at(deposit_count))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Error::DepositTreeError)?;
+
+        self.leaves.extend(leaves);
+        self.logs.extend(new_logs);
+        self.deposit_roots.extend(deposit_roots);
+
+        Ok(())
+    }
+
     /// Returns a list of `Deposit` objects, within the given deposit index `range`.
     ///
     /// The `deposit_count` is used to generate the proofs for the `Deposits`. For example, if we
@@ -183,6 +326,7 @@ impl DepositCache {
     ///
     /// - If `deposit_count` is larger than `end`.
     /// - There are not sufficient deposits in the tree to generate the proof.
+    /// - If `start` is within the snapshot from which the cache was created.
     pub fn get_deposits(
         &self,
         start: u64,
@@ -190,65 +334,146 @@ impl DepositCache {
         deposit_count: u64,
         tree_depth: usize,
     ) -> Result<(Hash256, Vec<Deposit>), Error> {
+        let finalized_deposit_count = self.finalized_deposit_count();
+
         if deposit_count < end {
             // It's invalid to ask for more deposits than should exist.
             Err(Error::DepositCountInvalid {
                 deposit_count,
                 range_end: end,
             })
-        } else if end > self.logs.len() as u64 {
+        } else if end > self.deposit_count() {
             // The range of requested deposits exceeds the deposits stored locally.
             Err(Error::InsufficientDeposits {
                 requested: end,
-                known_deposits: self.logs.len(),
+                known_deposits: self.deposit_count() as usize,
             })
-        } else if deposit_count > self.leaves.len() as u64 {
+        } else if deposit_count > self.deposit_count() {
             // There are not `deposit_count` known deposit roots, so we can't build the merkle tree
             // to prove into.
             Err(Error::InsufficientDeposits {
                 requested: deposit_count,
-                known_deposits: self.logs.len(),
+                known_deposits: self.deposit_count() as usize,
+            })
+        } else if start < finalized_deposit_count {
+            // The logs (and the merkle proofs) for finalized deposits are not stored.
+            Err(Error::DepositsFinalized {
+                requested: start,
+                finalized_deposits: finalized_deposit_count,
             })
         } else {
-            let leaves = self
-                .leaves
-                .get(0..deposit_count as usize)
-                .ok_or_else(|| Error::InternalError("Unable to get known leaves".into()))?;
-
-            // Note: there is likely a more optimal solution than recreating the `DepositDataTree`
-            // each time this function is called.
-            //
-            // Perhaps a base merkle tree could be maintained that contains all deposits up to the
-            // last finalized eth1 deposit count. Then, that tree could be cloned and extended for
-            // each of these calls.
-
-            let tree = DepositDataTree::create(leaves, deposit_count as usize, tree_depth);
+            let tree = self.deposit_tree_at(deposit_count, tree_depth)?;
 
             let deposits = self
                 .logs
-                .get(start as usize..end as usize)
+                .get(
+                    (start - finalized_deposit_count) as usize
+                        ..(end - finalized_deposit_count) as usize,
+                )
                 .ok_or_else(|| Error::InternalError("Unable to get known log".into()))?
                 .iter()
                 .map(|deposit_log| {
-                    let (_leaf, proof) = tree.generate_proof(deposit_log.index as usize);
+                    let (_leaf, proof) = tree
+                        .generate_proof(deposit_log.index as usize)
+                        .map_err(Error::DepositTreeError)?;
 
-                    Deposit {
+                    Ok(Deposit {
                         proof: proof.into(),
                         data: deposit_log.deposit_data.clone(),
-                    }
+                    })
                 })
-                .collect();
+                .collect::<Result<_, _>>()?;
 
             Ok((tree.root(), deposits))
         }
     }
 
+    /// Returns a snapshot of the deposit tree when it contained `deposit_count` deposits, which
+    /// is assumed to be the case at the given eth1 block.
+    ///
+    /// The snapshot may be used to create a new `DepositCache` with `Self::from_deposit_snapshot`.
+    ///
+    /// ## Errors
+    ///
+    /// - If `deposit_count` is less than the count of the snapshot the cache was created from.
+    /// - If there are less than `deposit_count` deposits in the cache.
+    pub fn get_deposit_snapshot(
+        &self,
+        deposit_count: u64,
+        execution_block_hash: Hash256,
+        execution_block_height: u64,
+    ) -> Result<DepositTreeSnapshot, Error> {
+        if deposit_count > self.deposit_count() {
+            return Err(Error::InsufficientDeposits {
+                requested: deposit_count,
+                known_deposits: self.deposit_count() as usize,
+            });
+        }
+
+        let tree = self.deposit_tree_at(deposit_count, DEPOSIT_TREE_DEPTH)?;
+        let finalized = tree
+            .finalized_hashes(deposit_count as usize)
+            .map_err(Error::DepositTreeError)?;
+
+        Ok(DepositTreeSnapshot {
+            finalized,
+            deposit_root: tree.root(),
+            deposit_count,
+            execution_block_hash,
+            execution_block_height,
+        })
+    }
+
+    /// Returns the deposit tree with the first `deposit_count` deposits.
+    fn deposit_tree_at(
+        &self,
+        deposit_count: u64,
+        tree_depth: usize,
+    ) -> Result<DepositDataTree, Error> {
+        let finalized_deposit_count = self.finalized_deposit_count();
+        if deposit_count < finalized_deposit_count {
+            return Err(Error::DepositsFinalized {
+                requested: deposit_count,
+                finalized_deposits: finalized_deposit_count,
+            });
+        }
+
+        let leaves = self
+            .leaves
+            .get(0..(deposit_count - finalized_deposit_count) as usize)
+            .ok_or_else(|| Error::InternalError("Unable to get known leaves".into()))?;
+
+        // Note: there is likely a more optimal solution than recreating the `DepositDataTree`
+        // each time this function is called.
+        //
+        // Perhaps a base merkle tree could be maintained that contains all deposits up to the
+        // last finalized eth1 deposit count. Then, that tree could be cloned and extended for
+        // each of these calls.
+
+        match &self.deposit_snapshot {
+            Some(snapshot) => {
+                let mut tree = DepositDataTree::from_snapshot(snapshot, tree_depth)
+                    .map_err(Error::DepositTreeError)?;
+                for leaf in leaves {
+                    tree.push_leaf(*leaf).map_err(Error::DepositTreeError)?;
+                }
+                Ok(tree)
+            }
+            None => Ok(DepositDataTree::create(
+                leaves,
+                deposit_count as usize,
+                tree_depth,
+            )),
+        }
+    }
+
     /// Gets the deposit count at block height = block_number.
     ///
     /// Fetches the `DepositLog` that was emitted at or just before `block_number`
     /// and returns the deposit count as `index + 1`.
     ///
-    /// Returns `None` if block number queried is 0 or less than deposit_contract_deployed block.
+    /// Returns `None` if block number queried is 0 or less than deposit_contract_deployed block,
+    /// or if it is prior to the snapshot from which the cache was created.
     pub fn get_deposit_count_from_cache(&self, block_number: u64) -> Option<u64> {
         // Contract cannot be deployed in 0'th block
         if block_number == 0 {
@@ -257,10 +482,16 @@ impl DepositCache {
         if block_number < self.deposit_contract_deploy_block {
             return None;
         }
-        // Return 0 if block_num queried is before first deposit
+        if let Some(snapshot) = &self.deposit_snapshot {
+            if block_number < snapshot.execution_block_height {
+                return None;
+            }
+        }
+        let finalized_deposit_count = self.finalized_deposit_count();
+        // Return the snapshot count (or 0) if block_num queried is before first deposit
         if let Some(first_deposit) = self.logs.first() {
             if first_deposit.block_number > block_number {
-                return Some(0);
+                return Some(finalized_deposit_count);
             }
         }
         let index = self
@@ -271,7 +502,7 @@ impl DepositCache {
             Err(next) => Some(
                 self.logs
                     .get(next.saturating_sub(1))
-                    .map_or(0, |x| x.index + 1),
+                    .map_or(finalized_deposit_count, |x| x.index + 1),
             ),
         }
     }
@@ -282,7 +513,11 @@ impl DepositCache {
     /// and queries the `deposit_roots` map to get the corresponding `deposit_root`.
     pub fn get_deposit_root_from_cache(&self, block_number: u64) -> Option<Hash256> {
         let index = self.get_deposit_count_from_cache(block_number)?;
-        Some(*self.deposit_roots.get(index as usize)?)
+        Some(
+            *self
+                .deposit_roots
+                .get(index.checked_sub(self.finalized_deposit_count())? as usize)?,
+        )
     }
 }
 
@@ -340,6 +575,62 @@ pub mod tests {
         assert!(tree.insert_log(log).is_err());
     }
 
+    #[test]
+    fn insert_logs_matches_insert_log() {
+        let n = 2_048;
+        let logs = (0..n)
+            .map(|i| {
+                let mut log = example_log();
+                log.index = i;
+                log.block_number = i / 4;
+                log.deposit_data.withdrawal_credentials = Hash256::from_low_u64_be(i);
+                log
+            })
+            .collect::<Vec<_>>();
+
+        let mut tree = DepositCache::default();
+        for log in logs.clone() {
+            tree.insert_log(log).expect("should add consecutive logs");
+        }
+
+        // Insert in batches which overlap with the logs already known.
+        let mut batch_tree = DepositCache::default();
+        for (start, end) in &[(0, 3), (2, 1_500), (1_500, 2_048)] {
+            batch_tree
+                .insert_logs(logs[*start..*end].to_vec())
+                .expect("should add consecutive logs");
+        }
+
+        assert_eq!(batch_tree.leaves, tree.leaves);
+        assert_eq!(batch_tree.deposit_roots, tree.deposit_roots);
+        assert_eq!(batch_tree.deposit_tree.root(), tree.deposit_tree.root());
+        assert_eq!(
+            batch_tree.get_deposits(1_000, 1_100, n, TREE_DEPTH),
+            tree.get_deposits(1_000, 1_100, n, TREE_DEPTH),
+        );
+
+        // The logs of an invalid batch are not inserted.
+        let mut log = example_log();
+        log.index = n;
+        let mut skipped_log = example_log();
+        skipped_log.index = n + 2;
+        assert_eq!(
+            batch_tree.insert_logs(vec![log, skipped_log]),
+            Err(Error::NonConsecutive {
+                log_index: n + 2,
+                expected: n as usize + 1,
+            })
+        );
+        let mut distinct_log = logs[5].clone();
+        distinct_log.block_number = 99;
+        assert_eq!(
+            batch_tree.insert_logs(vec![distinct_log]),
+            Err(Error::DuplicateDistinctLog(5))
+        );
+        assert_eq!(batch_tree.deposit_count(), n);
+        assert_eq!(batch_tree.deposit_roots, tree.deposit_roots);
+    }
+
     #[test]
     fn get_deposit_valid() {
         let n = 1_024;
@@ -439,4 +730,94 @@ pub mod tests {
         // Range higher than count.
         assert!(tree.get_deposits(0, 4, 2, TREE_DEPTH).is_err());
     }
+
+    #[test]
+    fn deposit_snapshot() {
+        let n = 64;
+        let finalized = 37;
+        let mut tree = DepositCache::default();
+        let mut logs = vec![];
+
+        for i in 0..n {
+            let mut log = example_log();
+            log.index = i;
+            log.block_number = i;
+            log.deposit_data.withdrawal_credentials = Hash256::from_low_u64_be(i);
+            logs.push(log.clone());
+            tree.insert_log(log).expect("should add consecutive logs")
+        }
+
+        let snapshot = tree
+            .get_deposit_snapshot(finalized, Hash256::repeat_byte(42), finalized - 1)
+            .expect("should get snapshot");
+        assert!(snapshot.is_valid(), "snapshot should be valid");
+        assert_eq!(
+            Some(snapshot.deposit_root),
+            tree.get_deposit_root_from_cache(finalized - 1),
+            "snapshot should have the deposit root at the block"
+        );
+
+        let mut snapshot_tree = DepositCache::from_deposit_snapshot(1, snapshot.clone())
+            .expect("should create cache from snapshot");
+        assert_eq!(snapshot_tree.deposit_count(), finalized);
+
+        // Logs prior to the snapshot are ignored.
+        let mut batch_snapshot_tree = DepositCache::from_deposit_snapshot(1, snapshot)
+            .expect("should create cache from snapshot");
+        batch_snapshot_tree
+            .insert_logs(logs.clone())
+            .expect("should add consecutive logs");
+        for log in logs {
+            snapshot_tree
+                .insert_log(log)
+                .expect("should add consecutive logs");
+        }
+        assert_eq!(
+            batch_snapshot_tree.deposit_roots,
+            snapshot_tree.deposit_roots
+        );
+        assert_eq!(snapshot_tree.len(), (n - finalized) as usize);
+        assert_eq!(snapshot_tree.deposit_count(), n);
+
+        assert_eq!(
+            snapshot_tree.get_deposits(finalized, n, n, TREE_DEPTH),
+            tree.get_deposits(finalized, n, n, TREE_DEPTH),
+            "should get the same deposits as the full tree"
+        );
+        assert_eq!(
+            snapshot_tree.get_deposits(finalized + 3, n - 4, n - 2, TREE_DEPTH),
+            tree.get_deposits(finalized + 3, n - 4, n - 2, TREE_DEPTH),
+            "should get the same deposits as the full tree with a lower count"
+        );
+        assert_eq!(
+            snapshot_tree.get_deposits(finalized - 1, n, n, TREE_DEPTH),
+            Err(Error::DepositsFinalized {
+                requested: finalized - 1,
+                finalized_deposits: finalized
+            }),
+            "should not get finalized deposits"
+        );
+
+        for block_number in finalized - 1..n {
+            assert_eq!(
+                snapshot_tree.get_deposit_root_from_cache(block_number),
+                tree.get_deposit_root_from_cache(block_number),
+                "should get the same deposit root as the full tree"
+            );
+        }
+        assert_eq!(
+            snapshot_tree.get_deposit_count_from_cache(finalized - 2),
+            None,
+            "should not know the deposit count prior to the snapshot"
+        );
+
+        let recovered = SszDepositCache::from_deposit_cache(&snapshot_tree)
+            .to_deposit_cache()
+            .expect("should recover cache");
+        assert_eq!(
+            recovered.get_deposits(finalized, n, n, TREE_DEPTH),
+            tree.get_deposits(finalized, n, n, TREE_DEPTH),
+            "should get the same deposits after recovering from ssz"
+        );
+    }
 }
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+/// An endpoint is considered to have fallen behind if its head is more than this many blocks
+/// behind the highest head of all the endpoints.
+pub const MAX_BLOCKS_BEHIND: u64 = 16;
+
+/// The health of an eth1 endpoint, as determined by the last check or request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EndpointState {
+    /// The endpoint has not been checked yet, it is assumed to be healthy.
+    Unchecked,
+    /// The endpoint is responsive, on the expected network and not far behind the others.
+    Online,
+    /// The endpoint failed to respond to a request.
+    Offline,
+    /// The endpoint is connected to a network with an unexpected network id.
+    WrongNetworkId,
+    /// The endpoint is connected to a chain with an unexpected chain id.
+    WrongChainId,
+    /// The head of the endpoint is far behind the head of the other endpoints.
+    FarBehind,
+}
+
+impl EndpointState {
+    /// Returns `true` if requests may be sent to the endpoint.
+    pub fn is_healthy(self) -> bool {
+        match self {
+            EndpointState::Unchecked | EndpointState::Online => true,
+            EndpointState::Offline
+            | EndpointState::WrongNetworkId
+            | EndpointState::WrongChainId
+            | EndpointState::FarBehind => false,
+        }
+    }
+}
+
+/// Stores the `EndpointState` of each eth1 endpoint.
+#[derive(Default)]
+pub struct EndpointStates {
+    states: HashMap<String, EndpointState>,
+}
+
+impl EndpointStates {
+    /// Returns the state of `endpoint`, which is `Unchecked` if it is not known.
+    pub fn get(&self, endpoint: &str) -> EndpointState {
+        self.states
+            .get(endpoint)
+            .copied()
+            .unwrap_or(EndpointState::Unchecked)
+    }
+
+    /// Sets the state of `endpoint`, returning the previous state.
+    pub fn set(&mut self, endpoint: &str, state: EndpointState) -> EndpointState {
+        self.states
+            .insert(endpoint.to_string(), state)
+            .unwrap_or(EndpointState::Unchecked)
+    }
+
+    /// Returns the healthy members of `endpoints`, maintaining their order of priority.
+    pub fn healthy(&self, endpoints: &[String]) -> Vec<String> {
+        endpoints
+            .iter()
+            .filter(|endpoint| self.get(endpoint).is_healthy())
+            .cloned()
+            .collect()
+    }
+}
+
+/// Given the result of checking each endpoint (either its head block number or the reason it is
+/// unhealthy), returns the state of each endpoint.
+///
+/// Endpoints which are more than `MAX_BLOCKS_BEHIND` blocks behind the highest head are
+/// `FarBehind`.
+pub fn states_from_checks(
+    checks: Vec<(String, Result<u64, EndpointState>)>,
+) -> Vec<(String, EndpointState)> {
+    let highest_block = checks
+        .iter()
+        .filter_map(|(_, check)| check.as_ref().ok())
+        .max()
+        .copied()
+        .unwrap_or(0);
+
+    checks
+        .into_iter()
+        .map(|(endpoint, check)| {
+            let state = match check {
+                Ok(block_number) if block_number + MAX_BLOCKS_BEHIND < highest_block => {
+                    EndpointState::FarBehind
+                }
+                Ok(_) => EndpointState::Online,
+                Err(state) => state,
+            };
+            (endpoint, state)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("http://eth1-{}:8545", i)).collect()
+    }
+
+    #[test]
+    fn healthy_endpoints_ordering() {
+        let endpoints = endpoints(4);
+        let mut states = EndpointStates::default();
+
+        assert_eq!(states.healthy(&endpoints), endpoints);
+
+        assert_eq!(
+            states.set(&endpoints[0], EndpointState::Offline),
+            EndpointState::Unchecked
+        );
+        states.set(&endpoints[2], EndpointState::WrongNetworkId);
+        states.set(&endpoints[3], EndpointState::Online);
+        assert_eq!(
+            states.healthy(&endpoints),
+            vec![endpoints[1].clone(), endpoints[3].clone()]
+        );
+
+        assert_eq!(
+            states.set(&endpoints[0], EndpointState::Online),
+            EndpointState::Offline
+        );
+        assert_eq!(
+            states.healthy(&endpoints),
+            vec![
+                endpoints[0].clone(),
+                endpoints[1].clone(),
+                endpoints[3].clone()
+            ]
+        );
+    }
+
+    #[test]
+    fn far_behind_endpoints() {
+        let endpoints = endpoints(4);
+        let highest = 1_000;
+
+        let states = states_from_checks(vec![
+            (endpoints[0].clone(), Ok(highest - MAX_BLOCKS_BEHIND - 1)),
+            (endpoints[1].clone(), Ok(highest - MAX_BLOCKS_BEHIND)),
+            (endpoints[2].clone(), Err(EndpointState::WrongChainId)),
+            (endpoints[3].clone(), Ok(highest)),
+        ]);
+
+        assert_eq!(
+            states,
+            vec![
+                (endpoints[0].clone(), EndpointState::FarBehind),
+                (endpoints[1].clone(), EndpointState::Online),
+                (endpoints[2].clone(), EndpointState::WrongChainId),
+                (endpoints[3].clone(), EndpointState::Online),
+            ]
+        );
+    }
+}
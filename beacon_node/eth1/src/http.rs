@@ -6,16 +6,19 @@
 //! Does not use a web3 library, instead it uses `reqwest` (`hyper`) to call the remote endpoint
 //! and `serde` to decode the response.
 //!
+//! Also provides a function to download a deposit tree snapshot from an eth2 beacon node.
+//!
 //! ## Note
 //!
 //! There is no ABI parsing here, all function signatures and topics are hard-coded as constants.
 
 use futures::future::TryFutureExt;
 use reqwest::{header::CONTENT_TYPE, ClientBuilder, StatusCode};
+use serde::Deserialize;
 use serde_json::{json, Value};
 use std::ops::Range;
 use std::time::Duration;
-use types::Hash256;
+use types::{DepositTreeSnapshot, Hash256};
 
 /// `keccak("DepositEvent(bytes,bytes,bytes,bytes,bytes)")`
 pub const DEPOSIT_EVENT_TOPIC: &str =
@@ -51,6 +54,33 @@ pub async fn get_block_number(endpoint: &str, timeout: Duration) -> Result<u64,
     .map_err(|e| format!("Failed to get block number: {}", e))
 }
 
+/// Returns the network id of the eth1 node (e.g., `5` for Goerli).
+///
+/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+pub async fn get_network_id(endpoint: &str, timeout: Duration) -> Result<u64, String> {
+    let response_body = send_rpc_request(endpoint, "net_version", json!([]), timeout).await?;
+    response_result(&response_body)?
+        .ok_or_else(|| "No result field was returned for network id".to_string())?
+        .as_str()
+        .ok_or_else(|| "Data was not string")?
+        .parse::<u64>()
+        .map_err(|e| format!("Failed to get network id: {:?}", e))
+}
+
+/// Returns the chain id of the eth1 node (e.g., `5` for Goerli), as defined by EIP-695.
+///
+/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+pub async fn get_chain_id(endpoint: &str, timeout: Duration) -> Result<u64, String> {
+    let response_body = send_rpc_request(endpoint, "eth_chainId", json!([]), timeout).await?;
+    hex_to_u64_be(
+        response_result(&response_body)?
+            .ok_or_else(|| "No result field was returned for chain id".to_string())?
+            .as_str()
+            .ok_or_else(|| "Data was not string")?,
+    )
+    .map_err(|e| format!("Failed to get chain id: {}", e))
+}
+
 /// Gets a block hash by block number.
 ///
 /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
@@ -274,6 +304,54 @@ pub async fn get_deposit_logs_in_range(
         .map_err(|e| format!("Failed to get logs in range: {}", e))
 }
 
+/// Returns the finalized deposit tree snapshot of the eth2 beacon node at `beacon_node_url`.
+///
+/// Uses the `/eth/v1/beacon/deposit_snapshot` HTTP API endpoint. E.g., `http://localhost:5052`.
+pub async fn get_deposit_snapshot(
+    beacon_node_url: &str,
+    timeout: Duration,
+) -> Result<DepositTreeSnapshot, String> {
+    let url = format!(
+        "{}/eth/v1/beacon/deposit_snapshot",
+        beacon_node_url.trim_end_matches('/')
+    );
+
+    let response = ClientBuilder::new()
+        .timeout(timeout)
+        .build()
+        .expect("The builder should always build a client")
+        .get(&url)
+        .send()
+        .map_err(|e| format!("Request failed: {:?}", e))
+        .await?;
+    if response.status() != StatusCode::OK {
+        return Err(format!(
+            "Response HTTP status was not 200 OK: {}.",
+            response.status()
+        ));
+    };
+
+    let bytes = response
+        .bytes()
+        .map_err(|e| format!("Failed to receive body: {:?}", e))
+        .await?;
+
+    parse_deposit_snapshot(&bytes)
+}
+
+/// Parses a deposit tree snapshot from the JSON body of a `/eth/v1/beacon/deposit_snapshot`
+/// response.
+pub fn parse_deposit_snapshot(bytes: &[u8]) -> Result<DepositTreeSnapshot, String> {
+    #[derive(Deserialize)]
+    struct Response {
+        data: DepositTreeSnapshot,
+    }
+
+    serde_json::from_slice::<Response>(bytes)
+        .map(|response| response.data)
+        .map_err(|e| format!("Failed to parse deposit snapshot: {:?}", e))
+}
+
 /// Sends an RPC request to `endpoint`, using a POST with the given `body`.
 ///
 /// Tries to receive the response and parse the body as a `String`.
@@ -307,7 +385,7 @@ pub async fn send_rpc_request(
         .await?;
     if response.status() != StatusCode::OK {
         return Err(format!(
-            "Response HTTP status was not 200 OK:  {}.",
+            "Response HTTP status was not 200 OK: {}.",
             response.status()
         ));
     };
@@ -1,11 +1,13 @@
 use crate::Config;
 use crate::{
     block_cache::BlockCache,
-    deposit_cache::{DepositCache, SszDepositCache},
+    deposit_cache::{DepositCache, Error as DepositCacheError, SszDepositCache},
+    endpoints::EndpointStates,
 };
 use parking_lot::RwLock;
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
+use types::DepositTreeSnapshot;
 
 #[derive(Default)]
 pub struct DepositUpdater {
@@ -21,6 +23,20 @@ impl DepositUpdater {
             last_processed_block: None,
         }
     }
+
+    /// Create a `DepositUpdater` which continues from the finalized deposit tree `snapshot`, only
+    /// processing the blocks after it.
+    pub fn from_snapshot(
+        deposit_contract_deploy_block: u64,
+        snapshot: DepositTreeSnapshot,
+    ) -> Result<Self, DepositCacheError> {
+        let last_processed_block = Some(snapshot.execution_block_height);
+        let cache = DepositCache::from_deposit_snapshot(deposit_contract_deploy_block, snapshot)?;
+        Ok(DepositUpdater {
+            cache,
+            last_processed_block,
+        })
+    }
 }
 
 #[derive(Default)]
@@ -28,6 +44,7 @@ pub struct Inner {
     pub block_cache: RwLock<BlockCache>,
     pub deposit_cache: RwLock<DepositUpdater>,
     pub config: RwLock<Config>,
+    pub endpoint_states: RwLock<EndpointStates>,
 }
 
 impl Inner {
@@ -80,6 +97,7 @@ impl SszEth1Cache {
                 last_processed_block: self.last_processed_block,
             }),
             config: RwLock::new(config),
+            endpoint_states: RwLock::new(EndpointStates::default()),
         })
     }
 }
@@ -2,18 +2,30 @@ use crate::metrics;
 use crate::{
     block_cache::{BlockCache, Error as BlockCacheError, Eth1Block},
     deposit_cache::Error as DepositCacheError,
-    http::{get_block, get_block_number, get_deposit_logs_in_range, Log},
+    endpoints::{states_from_checks, EndpointState},
+    http::{
+        get_block, get_block_number, get_chain_id, get_deposit_count, get_deposit_logs_in_range,
+        get_deposit_root, get_deposit_snapshot, get_network_id, parse_deposit_snapshot, Log,
+    },
     inner::{DepositUpdater, Inner},
     DepositLog,
 };
-use futures::{future::TryFutureExt, stream, stream::TryStreamExt, StreamExt};
+use futures::{
+    future::{self, Future, TryFutureExt},
+    stream,
+    stream::TryStreamExt,
+    StreamExt,
+};
 use parking_lot::{RwLock, RwLockReadGuard};
 use serde::{Deserialize, Serialize};
-use slog::{debug, error, info, trace, Logger};
+use slog::{debug, error, info, trace, warn, Logger};
+use std::fs;
 use std::ops::{Range, RangeInclusive};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::time::{interval_at, Duration, Instant};
+use types::{DepositTreeSnapshot, Hash256};
 
 const STANDARD_TIMEOUT_MILLIS: u64 = 15_000;
 
@@ -23,6 +35,12 @@ const BLOCK_NUMBER_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
 const GET_BLOCK_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
 /// Timeout when doing an eth_getLogs to read the deposit contract logs.
 const GET_DEPOSIT_LOG_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
+/// Timeout when downloading a deposit tree snapshot from a beacon node.
+const GET_DEPOSIT_SNAPSHOT_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
+/// Timeout when doing an eth_call to read the deposit root or count of the deposit contract.
+const DEPOSIT_CONTRACT_CALL_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
+/// Timeout for each of the calls when checking the health of an eth1 endpoint.
+const ENDPOINT_CHECK_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
@@ -43,6 +61,12 @@ pub enum Error {
     GetDepositCountFailed(String),
     /// Failed to read the deposit contract root from the eth1 node.
     GetDepositLogsFailed(String),
+    /// Failed to read or download a deposit tree snapshot.
+    GetDepositSnapshotFailed(String),
+    /// The deposit tree snapshot was unable to be used to create the deposit cache.
+    InvalidDepositSnapshot(DepositCacheError),
+    /// The deposit tree snapshot does not match the deposit contract at the snapshot block.
+    DepositSnapshotMismatch(String),
     /// There was an inconsistency when adding a block to the cache.
     FailedToInsertEth1Block(BlockCacheError),
     /// There was an inconsistency when adding a deposit to the cache.
@@ -52,6 +76,10 @@ pub enum Error {
         block_range: Range<u64>,
         error: String,
     },
+    /// None of the eth1 endpoints are healthy, so no request was attempted.
+    NoHealthyEndpoints,
+    /// The request failed on each of the healthy eth1 endpoints.
+    AllEndpointsFailed(Vec<(String, Error)>),
     /// There was an unexpected internal error.
     Internal(String),
 }
@@ -71,8 +99,15 @@ pub struct DepositCacheUpdateOutcome {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    /// An Eth1 node (e.g., Geth) running a HTTP JSON-RPC endpoint.
-    pub endpoint: String,
+    /// Eth1 nodes (e.g., Geth) running a HTTP JSON-RPC endpoint, in order of priority.
+    ///
+    /// Requests are sent to the first healthy endpoint, falling back to the next healthy endpoint
+    /// if it fails.
+    pub endpoints: Vec<String>,
+    /// If set, endpoints which are not connected to a network with this network id are unhealthy.
+    pub network_id: Option<u64>,
+    /// If set, endpoints which are not connected to a chain with this chain id are unhealthy.
+    pub chain_id: Option<u64>,
     /// The address the `BlockCache` and `DepositCache` should assume is the canonical deposit contract.
     pub deposit_contract_address: String,
     /// Defines the first block that the `DepositCache` will start searching for deposit logs.
@@ -97,12 +132,23 @@ pub struct Config {
     pub max_log_requests_per_update: Option<usize>,
     /// The maximum number of log requests per update.
     pub max_blocks_per_update: Option<usize>,
+    /// A file containing a finalized deposit tree snapshot to initialize the `DepositCache` with,
+    /// if it is empty.
+    ///
+    /// Only the deposit logs after the snapshot are downloaded, so deposits prior to the snapshot
+    /// can not be included in blocks (or used to determine genesis).
+    pub deposit_snapshot_path: Option<PathBuf>,
+    /// A beacon node from which to download a finalized deposit tree snapshot, as an alternative
+    /// to `deposit_snapshot_path`.
+    pub deposit_snapshot_url: Option<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            endpoint: "http://localhost:8545".into(),
+            endpoints: vec!["http://localhost:8545".into()],
+            network_id: None,
+            chain_id: None,
             deposit_contract_address: "0x0000000000000000000000000000000000000000".into(),
             deposit_contract_deploy_block: 1,
             lowest_cached_block_number: 1,
@@ -112,6 +158,8 @@ impl Default for Config {
             blocks_per_log_query: 1_000,
             max_log_requests_per_update: None,
             max_blocks_per_update: None,
+            deposit_snapshot_path: None,
+            deposit_snapshot_url: None,
         }
     }
 }
@@ -230,6 +278,117 @@ impl Service {
         }
     }
 
+    /// Returns the last known state of each of the eth1 endpoints, in order of priority.
+    pub fn endpoint_states(&self) -> Vec<(String, EndpointState)> {
+        let endpoints = self.config().endpoints.clone();
+        let endpoint_states = self.inner.endpoint_states.read();
+        endpoints
+            .into_iter()
+            .map(|endpoint| {
+                let state = endpoint_states.get(&endpoint);
+                (endpoint, state)
+            })
+            .collect()
+    }
+
+    /// Returns the endpoints which are currently considered healthy, in order of priority.
+    fn healthy_endpoints(&self) -> Vec<String> {
+        let endpoints = self.config().endpoints.clone();
+        self.inner.endpoint_states.read().healthy(&endpoints)
+    }
+
+    /// Checks the network id, chain id and head block of each of the eth1 endpoints, updating
+    /// their states accordingly.
+    ///
+    /// Emits logs when an endpoint becomes unhealthy or recovers.
+    pub async fn update_endpoint_states(service: Self) {
+        let (endpoints, network_id, chain_id) = {
+            let config = service.config();
+            (config.endpoints.clone(), config.network_id, config.chain_id)
+        };
+
+        let checks = future::join_all(endpoints.iter().cloned().map(|endpoint| async move {
+            let check = check_endpoint(&endpoint, network_id, chain_id).await;
+            (endpoint, check)
+        }))
+        .await;
+
+        let mut endpoint_states = service.inner.endpoint_states.write();
+        for (endpoint, state) in states_from_checks(checks) {
+            let previous_state = endpoint_states.set(&endpoint, state);
+
+            if !state.is_healthy() && state != previous_state {
+                warn!(
+                    service.log,
+                    "Eth1 endpoint is unhealthy";
+                    "endpoint" => &endpoint,
+                    "state" => format!("{:?}", state),
+                );
+            } else if state.is_healthy() && !previous_state.is_healthy() {
+                info!(
+                    service.log,
+                    "Eth1 endpoint is healthy";
+                    "endpoint" => &endpoint,
+                    "previous_state" => format!("{:?}", previous_state),
+                );
+            }
+        }
+
+        if endpoint_states.healthy(&endpoints).is_empty() {
+            error!(
+                service.log,
+                "No healthy eth1 endpoints";
+                "msg" => "ensure an eth1 node is online, synced and on the correct network",
+                "endpoints" => format!("{:?}", endpoints),
+            );
+        }
+    }
+
+    /// Sends `request` to each of the healthy endpoints in order of priority, resolving with the
+    /// first successful response.
+    ///
+    /// Endpoints which fail the request are marked as unhealthy until they are next checked. If
+    /// there are no healthy endpoints, all endpoints are checked again before giving up.
+    async fn first_success<F, R, O>(&self, request: F) -> Result<O, Error>
+    where
+        F: Fn(String) -> R,
+        R: Future<Output = Result<O, Error>>,
+    {
+        let mut endpoints = self.healthy_endpoints();
+        if endpoints.is_empty() {
+            Service::update_endpoint_states(self.clone()).await;
+            endpoints = self.healthy_endpoints();
+        }
+
+        if endpoints.is_empty() {
+            return Err(Error::NoHealthyEndpoints);
+        }
+
+        let mut errors = vec![];
+        for endpoint in endpoints {
+            match request(endpoint.clone()).await {
+                Ok(output) => return Ok(output),
+                Err(e) => {
+                    let state = match e {
+                        Error::RemoteNotSynced { .. } => EndpointState::FarBehind,
+                        _ => EndpointState::Offline,
+                    };
+                    self.inner.endpoint_states.write().set(&endpoint, state);
+
+                    warn!(
+                        self.log,
+                        "Eth1 endpoint request failed";
+                        "endpoint" => &endpoint,
+                        "error" => format!("{:?}", e),
+                    );
+                    errors.push((endpoint, e));
+                }
+            }
+        }
+
+        Err(Error::AllEndpointsFailed(errors))
+    }
+
     /// Set the lowest block that the block cache will store.
     ///
     /// Note: this block may not always be present if truncating is enabled.
@@ -248,6 +407,8 @@ impl Service {
     pub async fn update(
         service: Self,
     ) -> Result<(DepositCacheUpdateOutcome, BlockCacheUpdateOutcome), String> {
+        Service::update_endpoint_states(service.clone()).await;
+
         let update_deposit_cache = async {
             let outcome = Service::update_deposit_cache(service.clone())
                 .await
@@ -339,7 +500,10 @@ impl Service {
     ///
     /// Emits logs for debugging and errors.
     pub async fn update_deposit_cache(service: Self) -> Result<DepositCacheUpdateOutcome, Error> {
-        let endpoint = service.config().endpoint.clone();
+        if service.deposits().read().last_processed_block.is_none() {
+            Service::init_deposit_cache_from_snapshot(&service).await;
+        }
+
         let follow_distance = service.config().follow_distance;
         let deposit_contract_address = service.config().deposit_contract_address.clone();
 
@@ -356,80 +520,52 @@ impl Service {
             .map(|n| n + 1)
             .unwrap_or_else(|| service.config().deposit_contract_deploy_block);
 
-        let range = get_new_block_numbers(&endpoint, next_required_block, follow_distance).await?;
-
-        let block_number_chunks = if let Some(range) = range {
-            range
-                .collect::<Vec<u64>>()
-                .chunks(blocks_per_log_query)
-                .take(max_log_requests_per_update)
-                .map(|vec| {
-                    let first = vec.first().cloned().unwrap_or_else(|| 0);
-                    let last = vec.last().map(|n| n + 1).unwrap_or_else(|| 0);
-                    first..last
-                })
-                .collect::<Vec<Range<u64>>>()
-        } else {
-            Vec::new()
-        };
-
-        let logs: Vec<(Range<u64>, Vec<Log>)> =
-            stream::try_unfold(block_number_chunks.into_iter(), |mut chunks| async {
-                match chunks.next() {
-                    Some(chunk) => {
-                        let chunk_1 = chunk.clone();
-                        match get_deposit_logs_in_range(
-                            &endpoint,
-                            &deposit_contract_address,
-                            chunk,
-                            Duration::from_millis(GET_DEPOSIT_LOG_TIMEOUT_MILLIS),
-                        )
-                        .await
-                        {
-                            Ok(logs) => Ok(Some(((chunk_1, logs), chunks))),
-                            Err(e) => Err(Error::GetDepositLogsFailed(e)),
-                        }
-                    }
-                    None => Ok(None),
+        let logs: Vec<(Range<u64>, Vec<Log>)> = service
+            .first_success(|endpoint| {
+                let deposit_contract_address = deposit_contract_address.clone();
+                async move {
+                    download_deposit_logs(
+                        &endpoint,
+                        &deposit_contract_address,
+                        next_required_block,
+                        follow_distance,
+                        blocks_per_log_query,
+                        max_log_requests_per_update,
+                    )
+                    .await
                 }
             })
-            .try_collect()
             .await?;
 
         let mut logs_imported = 0;
         for (block_range, log_chunk) in logs.iter() {
-            let mut cache = service.deposits().write();
-            log_chunk
-                .into_iter()
+            // Return early if any of the logs cannot be parsed.
+            //
+            // This costs an additional `collect`, however it enforces that no logs are
+            // imported if any one of them cannot be parsed.
+            let deposit_logs = log_chunk
+                .iter()
                 .map(|raw_log| {
                     DepositLog::from_log(&raw_log).map_err(|error| Error::FailedToParseDepositLog {
                         block_range: block_range.clone(),
                         error,
                     })
                 })
-                // Return early if any of the logs cannot be parsed.
-                //
-                // This costs an additional `collect`, however it enforces that no logs are
-                // imported if any one of them cannot be parsed.
-                .collect::<Result<Vec<_>, _>>()?
-                .into_iter()
-                .map(|deposit_log| {
-                    cache
-                        .cache
-                        .insert_log(deposit_log)
-                        .map_err(Error::FailedToInsertDeposit)?;
-
-                    logs_imported += 1;
-
-                    Ok(())
-                })
-                // Returns if a deposit is unable to be added to the cache.
-                //
-                // If this error occurs, the cache will no longer be guaranteed to hold either
-                // none or all of the logs for each block (i.e., they may exist _some_ logs for
-                // a block, but not _all_ logs for that block). This scenario can cause the
-                // node to choose an invalid genesis state or propose an invalid block.
-                .collect::<Result<_, _>>()?;
+                .collect::<Result<Vec<_>, _>>()?;
+            let num_logs = deposit_logs.len();
+
+            let mut cache = service.deposits().write();
+
+            // Returns if any deposit is unable to be added to the cache, in which case none of
+            // the logs in the chunk are added. This ensures the cache holds either none or all of
+            // the logs for each block, since choosing a genesis state or proposing a block with
+            // only _some_ of the logs for a block would be invalid.
+            cache
+                .cache
+                .insert_logs(deposit_logs)
+                .map_err(Error::FailedToInsertDeposit)?;
+
+            logs_imported += num_logs;
 
             cache.last_processed_block = Some(block_range.end.saturating_sub(1));
 
@@ -460,6 +596,119 @@ impl Service {
         Ok(DepositCacheUpdateOutcome { logs_imported })
     }
 
+    /// Replaces the (empty) deposit cache with one created from the deposit tree snapshot in
+    /// `config.deposit_snapshot_path` or downloaded from `config.deposit_snapshot_url`.
+    ///
+    /// Does nothing if no snapshot is configured. If the snapshot cannot be obtained or does not
+    /// match the deposit contract, the cache is left empty so that all deposit logs are
+    /// downloaded instead.
+    async fn init_deposit_cache_from_snapshot(service: &Self) {
+        let deposit_contract_deploy_block = service.config().deposit_contract_deploy_block;
+
+        let snapshot = match Service::get_verified_deposit_snapshot(service).await {
+            Ok(Some(snapshot)) => snapshot,
+            Ok(None) => return,
+            Err(e) => {
+                error!(
+                    service.log,
+                    "Unable to use deposit tree snapshot";
+                    "info" => "all deposit logs will be downloaded instead",
+                    "error" => format!("{:?}", e),
+                );
+                return;
+            }
+        };
+
+        let deposit_count = snapshot.deposit_count;
+        let execution_block_height = snapshot.execution_block_height;
+        let updater = match DepositUpdater::from_snapshot(deposit_contract_deploy_block, snapshot) {
+            Ok(updater) => updater,
+            Err(e) => {
+                error!(
+                    service.log,
+                    "Unable to use deposit tree snapshot";
+                    "info" => "all deposit logs will be downloaded instead",
+                    "error" => format!("{:?}", Error::InvalidDepositSnapshot(e)),
+                );
+                return;
+            }
+        };
+
+        let mut deposits = service.deposits().write();
+        // Avoid replacing a cache that was updated whilst downloading the snapshot.
+        if deposits.last_processed_block.is_none() {
+            *deposits = updater;
+
+            info!(
+                service.log,
+                "Loaded deposit tree snapshot";
+                "deposit_count" => deposit_count,
+                "eth1_block" => execution_block_height,
+            );
+        }
+    }
+
+    /// Reads or downloads the configured deposit tree snapshot and checks that its block hash,
+    /// deposit root and deposit count match those of the eth1 chain and the deposit contract at
+    /// the snapshot block.
+    ///
+    /// Returns `Ok(None)` if no snapshot is configured.
+    async fn get_verified_deposit_snapshot(
+        service: &Self,
+    ) -> Result<Option<DepositTreeSnapshot>, Error> {
+        let deposit_contract_address = service.config().deposit_contract_address.clone();
+        let deposit_snapshot_path = service.config().deposit_snapshot_path.clone();
+        let deposit_snapshot_url = service.config().deposit_snapshot_url.clone();
+
+        let snapshot = match (deposit_snapshot_path, deposit_snapshot_url) {
+            (Some(path), _) => fs::read(&path)
+                .map_err(|e| format!("Unable to read {:?}: {:?}", path, e))
+                .and_then(|bytes| parse_deposit_snapshot(&bytes))
+                .map_err(Error::GetDepositSnapshotFailed)?,
+            (None, Some(url)) => get_deposit_snapshot(
+                &url,
+                Duration::from_millis(GET_DEPOSIT_SNAPSHOT_TIMEOUT_MILLIS),
+            )
+            .await
+            .map_err(Error::GetDepositSnapshotFailed)?,
+            (None, None) => return Ok(None),
+        };
+
+        let block_number = snapshot.execution_block_height;
+        let (block_hash, deposit_root, deposit_count) = service
+            .first_success(|endpoint| {
+                let deposit_contract_address = deposit_contract_address.clone();
+                async move {
+                    download_deposit_contract_state(
+                        &endpoint,
+                        &deposit_contract_address,
+                        block_number,
+                    )
+                    .await
+                }
+            })
+            .await?;
+
+        if block_hash != snapshot.execution_block_hash {
+            Err(Error::DepositSnapshotMismatch(format!(
+                "eth1 block {} has hash {:?}, not {:?}",
+                block_number, block_hash, snapshot.execution_block_hash
+            )))
+        } else if deposit_root != Some(snapshot.deposit_root) {
+            Err(Error::DepositSnapshotMismatch(format!(
+                "deposit contract root at eth1 block {} is {:?}, not {:?}",
+                block_number, deposit_root, snapshot.deposit_root
+            )))
+        } else if deposit_count != Some(snapshot.deposit_count) {
+            Err(Error::DepositSnapshotMismatch(format!(
+                "deposit contract count at eth1 block {} is {:?}, not {}",
+                block_number, deposit_count, snapshot.deposit_count
+            )))
+        } else {
+            Ok(Some(snapshot))
+        }
+    }
+
     /// Contacts the remote eth1 node and attempts to import all blocks up to the configured
     /// follow-distance block.
     ///
@@ -486,10 +735,13 @@ impl Service {
             .map(|n| n + 1)
             .unwrap_or_else(|| service.config().lowest_cached_block_number);
 
-        let endpoint = service.config().endpoint.clone();
         let follow_distance = service.config().follow_distance;
 
-        let range = get_new_block_numbers(&endpoint, next_required_block, follow_distance).await?;
+        let range = service
+            .first_success(|endpoint| async move {
+                get_new_block_numbers(&endpoint, next_required_block, follow_distance).await
+            })
+            .await?;
         // Map the range of required blocks into a Vec.
         //
         // If the required range is larger than the size of the cache, drop the exiting cache
@@ -532,25 +784,20 @@ impl Service {
             .filter(|x| *x <= latest_in_cache)
             .take(max_blocks_per_update)
             .collect::<Vec<_>>();
-        // Produce a stream from the list of required block numbers and return a future that
-        // consumes the it.
-
-        let eth1_blocks: Vec<Eth1Block> = stream::try_unfold(
-            required_block_numbers.into_iter(),
-            |mut block_numbers| async {
-                match block_numbers.next() {
-                    Some(block_number) => {
-                        match download_eth1_block(service.inner.clone(), block_number).await {
-                            Ok(eth1_block) => Ok(Some((eth1_block, block_numbers))),
-                            Err(e) => Err(e),
-                        }
+        // Download the required blocks from the first endpoint that is able to provide all of them.
+        let eth1_blocks: Vec<Eth1Block> = if required_block_numbers.is_empty() {
+            vec![]
+        } else {
+            service
+                .first_success(|endpoint| {
+                    let required_block_numbers = required_block_numbers.clone();
+                    let inner = service.inner.clone();
+                    async move {
+                        download_eth1_blocks(&endpoint, inner, required_block_numbers).await
                     }
-                    None => Ok(None),
-                }
-            },
-        )
-        .try_collect()
-        .await?;
+                })
+                .await?
+        };
 
         let mut blocks_imported = 0;
         for eth1_block in eth1_blocks {
@@ -623,6 +870,88 @@ impl Service {
     }
 }
 
+/// Checks that `endpoint` is on the expected network and chain (if they are specified), resolving
+/// with the number of its head block.
+///
+/// Resolves with the state of the endpoint if it is unhealthy.
+async fn check_endpoint(
+    endpoint: &str,
+    network_id: Option<u64>,
+    chain_id: Option<u64>,
+) -> Result<u64, EndpointState> {
+    let timeout = Duration::from_millis(ENDPOINT_CHECK_TIMEOUT_MILLIS);
+
+    if let Some(network_id) = network_id {
+        match get_network_id(endpoint, timeout).await {
+            Ok(remote_network_id) if remote_network_id == network_id => (),
+            Ok(_) => return Err(EndpointState::WrongNetworkId),
+            Err(_) => return Err(EndpointState::Offline),
+        }
+    }
+
+    if let Some(chain_id) = chain_id {
+        match get_chain_id(endpoint, timeout).await {
+            Ok(remote_chain_id) if remote_chain_id == chain_id => (),
+            Ok(_) => return Err(EndpointState::WrongChainId),
+            Err(_) => return Err(EndpointState::Offline),
+        }
+    }
+
+    get_block_number(endpoint, timeout)
+        .await
+        .map_err(|_| EndpointState::Offline)
+}
+
+/// Downloads the deposit logs from `next_required_block` up to the follow distance block of
+/// `endpoint`, in chunks of `blocks_per_log_query` blocks.
+async fn download_deposit_logs(
+    endpoint: &str,
+    deposit_contract_address: &str,
+    next_required_block: u64,
+    follow_distance: u64,
+    blocks_per_log_query: usize,
+    max_log_requests_per_update: usize,
+) -> Result<Vec<(Range<u64>, Vec<Log>)>, Error> {
+    let range = get_new_block_numbers(endpoint, next_required_block, follow_distance).await?;
+
+    let block_number_chunks = if let Some(range) = range {
+        range
+            .collect::<Vec<u64>>()
+            .chunks(blocks_per_log_query)
+            .take(max_log_requests_per_update)
+            .map(|vec| {
+                let first = vec.first().cloned().unwrap_or_else(|| 0);
+                let last = vec.last().map(|n| n + 1).unwrap_or_else(|| 0);
+                first..last
+            })
+            .collect::<Vec<Range<u64>>>()
+    } else {
+        Vec::new()
+    };
+
+    stream::try_unfold(block_number_chunks.into_iter(), |mut chunks| async {
+        match chunks.next() {
+            Some(chunk) => {
+                let chunk_1 = chunk.clone();
+                match get_deposit_logs_in_range(
+                    endpoint,
+                    deposit_contract_address,
+                    chunk,
+                    Duration::from_millis(GET_DEPOSIT_LOG_TIMEOUT_MILLIS),
+                )
+                .await
+                {
+                    Ok(logs) => Ok(Some(((chunk_1, logs), chunks))),
+                    Err(e) => Err(Error::GetDepositLogsFailed(e)),
+                }
+            }
+            None => Ok(None),
+        }
+    })
+    .try_collect()
+    .await
+}
+
 /// Determine the range of blocks that need to be downloaded, given the remotes best block and
 /// the locally stored best block.
 async fn get_new_block_numbers<'a>(
@@ -655,13 +984,36 @@ async fn get_new_block_numbers<'a>(
     }
 }
 
+/// Downloads each of the `block_numbers` from `endpoint`, in order.
+async fn download_eth1_blocks(
+    endpoint: &str,
+    cache: Arc<Inner>,
+    block_numbers: Vec<u64>,
+) -> Result<Vec<Eth1Block>, Error> {
+    stream::try_unfold(block_numbers.into_iter(), |mut block_numbers| async {
+        match block_numbers.next() {
+            Some(block_number) => {
+                match download_eth1_block(endpoint, cache.clone(), block_number).await {
+                    Ok(eth1_block) => Ok(Some((eth1_block, block_numbers))),
+                    Err(e) => Err(e),
+                }
+            }
+            None => Ok(None),
+        }
+    })
+    .try_collect()
+    .await
+}
+
 /// Downloads the `(block, deposit_root, deposit_count)` tuple from an eth1 node for the given
 /// `block_number`.
 ///
 /// Performs three async calls to an Eth1 HTTP JSON RPC endpoint.
-async fn download_eth1_block(cache: Arc<Inner>, block_number: u64) -> Result<Eth1Block, Error> {
-    let endpoint = cache.config.read().endpoint.clone();
-
+async fn download_eth1_block(
+    endpoint: &str,
+    cache: Arc<Inner>,
+    block_number: u64,
+) -> Result<Eth1Block, Error> {
     let deposit_root = cache
         .deposit_cache
         .read()
@@ -676,7 +1028,7 @@ async fn download_eth1_block(cache: Arc<Inner>, block_number: u64) -> Result<Eth
 
     // Performs a `get_blockByNumber` call to an eth1 node.
     let http_block = get_block(
-        &endpoint,
+        endpoint,
         block_number,
         Duration::from_millis(GET_BLOCK_TIMEOUT_MILLIS),
     )
@@ -692,6 +1044,35 @@ async fn download_eth1_block(cache: Arc<Inner>, block_number: u64) -> Result<Eth
     })
 }
 
+/// Downloads the hash of the eth1 block at `block_number`, along with the deposit root and
+/// deposit count of the deposit contract at that block.
+///
+/// Performs three async calls to an Eth1 HTTP JSON RPC endpoint.
+async fn download_deposit_contract_state(
+    endpoint: &str,
+    deposit_contract_address: &str,
+    block_number: u64,
+) -> Result<(Hash256, Option<Hash256>, Option<u64>), Error> {
+    let timeout = Duration::from_millis(DEPOSIT_CONTRACT_CALL_TIMEOUT_MILLIS);
+
+    let block = get_block(
+        endpoint,
+        block_number,
+        Duration::from_millis(GET_BLOCK_TIMEOUT_MILLIS),
+    )
+    .map_err(Error::BlockDownloadFailed)
+    .await?;
+    let deposit_root = get_deposit_root(endpoint, deposit_contract_address, block_number, timeout)
+        .map_err(Error::GetDepositRootFailed)
+        .await?;
+    let deposit_count =
+        get_deposit_count(endpoint, deposit_contract_address, block_number, timeout)
+            .map_err(Error::GetDepositCountFailed)
+            .await?;
+
+    Ok((block.hash, deposit_root, deposit_count))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
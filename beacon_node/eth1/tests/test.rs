@@ -1,6 +1,8 @@
 #![cfg(test)]
 use environment::{Environment, EnvironmentBuilder};
-use eth1::http::{get_deposit_count, get_deposit_logs_in_range, get_deposit_root, Block, Log};
+use eth1::http::{
+    get_block, get_deposit_count, get_deposit_logs_in_range, get_deposit_root, Block, Log,
+};
 use eth1::{Config, Service};
 use eth1::{DepositCache, DepositLog};
 use eth1_test_rig::GanacheEth1Instance;
@@ -9,9 +11,13 @@ use merkle_proof::verify_merkle_proof;
 use slog::Logger;
 use sloggers::{null::NullLoggerBuilder, Build};
 use std::ops::Range;
+use std::path::PathBuf;
 use std::time::Duration;
 use tree_hash::TreeHash;
-use types::{DepositData, EthSpec, Hash256, Keypair, MainnetEthSpec, MinimalEthSpec, Signature};
+use types::{
+    DepositData, DepositTreeSnapshot, EthSpec, Hash256, Keypair, MainnetEthSpec, MinimalEthSpec,
+    Signature,
+};
 use web3::{transports::Http, Web3};
 
 const DEPOSIT_CONTRACT_TREE_DEPTH: usize = 32;
@@ -115,7 +121,7 @@ mod eth1_cache {
 
             let service = Service::new(
                 Config {
-                    endpoint: eth1.endpoint(),
+                    endpoints: vec![eth1.endpoint()],
                     deposit_contract_address: deposit_contract.address(),
                     lowest_cached_block_number: initial_block_number,
                     follow_distance,
@@ -186,7 +192,7 @@ mod eth1_cache {
 
         let service = Service::new(
             Config {
-                endpoint: eth1.endpoint(),
+                endpoints: vec![eth1.endpoint()],
                 deposit_contract_address: deposit_contract.address(),
                 lowest_cached_block_number: get_block_number(&web3).await,
                 follow_distance: 0,
@@ -232,7 +238,7 @@ mod eth1_cache {
 
         let service = Service::new(
             Config {
-                endpoint: eth1.endpoint(),
+                endpoints: vec![eth1.endpoint()],
                 deposit_contract_address: deposit_contract.address(),
                 lowest_cached_block_number: get_block_number(&web3).await,
                 follow_distance: 0,
@@ -275,7 +281,7 @@ mod eth1_cache {
 
         let service = Service::new(
             Config {
-                endpoint: eth1.endpoint(),
+                endpoints: vec![eth1.endpoint()],
                 deposit_contract_address: deposit_contract.address(),
                 lowest_cached_block_number: get_block_number(&web3).await,
                 follow_distance: 0,
@@ -321,7 +327,7 @@ mod deposit_tree {
 
         let service = Service::new(
             Config {
-                endpoint: eth1.endpoint(),
+                endpoints: vec![eth1.endpoint()],
                 deposit_contract_address: deposit_contract.address(),
                 deposit_contract_deploy_block: start_block,
                 follow_distance: 0,
@@ -393,7 +399,7 @@ mod deposit_tree {
 
         let service = Service::new(
             Config {
-                endpoint: eth1.endpoint(),
+                endpoints: vec![eth1.endpoint()],
                 deposit_contract_address: deposit_contract.address(),
                 deposit_contract_deploy_block: start_block,
                 lowest_cached_block_number: start_block,
@@ -518,6 +524,134 @@ mod deposit_tree {
 }
 
 /// Tests for the base HTTP requests and response handlers.
+/// Tests for initializing the deposit cache from a deposit tree snapshot.
+mod deposit_snapshot {
+    use super::*;
+
+    /// Returns a snapshot of the (empty) deposit tree at `block_number`.
+    async fn empty_snapshot(eth1: &GanacheEth1Instance, block_number: u64) -> DepositTreeSnapshot {
+        let block = get_block(&eth1.endpoint(), block_number, timeout())
+            .await
+            .expect("should get block");
+        let mut snapshot = DepositTreeSnapshot {
+            finalized: vec![],
+            deposit_root: Hash256::zero(),
+            deposit_count: 0,
+            execution_block_hash: block.hash,
+            execution_block_height: block_number,
+        };
+        snapshot.deposit_root = snapshot
+            .calculate_root()
+            .expect("should calculate deposit root");
+        snapshot
+    }
+
+    /// Writes `snapshot` to a file named `name` in the format of the beacon node API response.
+    fn write_snapshot(name: &str, snapshot: &DepositTreeSnapshot) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let bytes = serde_json::to_vec(&serde_json::json!({ "data": snapshot }))
+            .expect("should encode snapshot");
+        std::fs::write(&path, bytes).expect("should write snapshot");
+        path
+    }
+
+    /// Performs `n` deposits after the snapshot at `start_block` and returns the service after
+    /// it has updated its deposit cache.
+    async fn update_from_snapshot(
+        eth1: &GanacheEth1Instance,
+        start_block: u64,
+        snapshot_path: PathBuf,
+        n: usize,
+    ) -> Service {
+        let service = Service::new(
+            Config {
+                endpoints: vec![eth1.endpoint()],
+                deposit_contract_address: eth1.deposit_contract.address(),
+                deposit_contract_deploy_block: start_block,
+                deposit_snapshot_path: Some(snapshot_path),
+                follow_distance: 0,
+                ..Config::default()
+            },
+            null_logger(),
+        );
+
+        for _ in 0..n {
+            eth1.deposit_contract
+                .deposit(random_deposit_data())
+                .await
+                .expect("should perform a deposit");
+        }
+
+        Service::update_deposit_cache(service.clone())
+            .await
+            .expect("should perform update");
+
+        service
+    }
+
+    #[tokio::test]
+    async fn loads_verified_snapshot() {
+        let n = 4;
+
+        let eth1 = GanacheEth1Instance::new()
+            .await
+            .expect("should start eth1 environment");
+        let start_block = get_block_number(&eth1.web3()).await;
+
+        let snapshot = empty_snapshot(&eth1, start_block).await;
+        let path = write_snapshot("eth1_loads_verified_snapshot.json", &snapshot);
+
+        let service = update_from_snapshot(&eth1, start_block, path, n).await;
+
+        assert_eq!(
+            service.deposits().read().cache.deposit_snapshot(),
+            Some(&snapshot),
+            "should use the snapshot"
+        );
+        assert_eq!(service.deposit_cache_len(), n);
+    }
+
+    #[tokio::test]
+    async fn replays_logs_when_snapshot_mismatches_contract() {
+        let n = 4;
+
+        let eth1 = GanacheEth1Instance::new()
+            .await
+            .expect("should start eth1 environment");
+        let start_block = get_block_number(&eth1.web3()).await;
+
+        let mut snapshot = empty_snapshot(&eth1, start_block).await;
+        snapshot.execution_block_hash = Hash256::repeat_byte(42);
+        let path = write_snapshot("eth1_snapshot_mismatches_contract.json", &snapshot);
+
+        let service = update_from_snapshot(&eth1, start_block, path, n).await;
+
+        assert_eq!(
+            service.deposits().read().cache.deposit_snapshot(),
+            None,
+            "should not use the snapshot"
+        );
+        assert_eq!(service.deposit_cache_len(), n);
+    }
+
+    #[tokio::test]
+    async fn replays_logs_when_snapshot_is_missing() {
+        let n = 4;
+
+        let eth1 = GanacheEth1Instance::new()
+            .await
+            .expect("should start eth1 environment");
+        let start_block = get_block_number(&eth1.web3()).await;
+
+        let path = std::env::temp_dir().join("eth1_snapshot_does_not_exist.json");
+
+        let service = update_from_snapshot(&eth1, start_block, path, n).await;
+
+        assert_eq!(service.deposits().read().cache.deposit_snapshot(), None);
+        assert_eq!(service.deposit_cache_len(), n);
+    }
+}
+
 mod http {
     use super::*;
 
@@ -630,7 +764,7 @@ mod fast {
         let now = get_block_number(&web3).await;
         let service = Service::new(
             Config {
-                endpoint: eth1.endpoint(),
+                endpoints: vec![eth1.endpoint()],
                 deposit_contract_address: deposit_contract.address(),
                 deposit_contract_deploy_block: now,
                 lowest_cached_block_number: now,
@@ -700,7 +834,7 @@ mod persist {
 
         let now = get_block_number(&web3).await;
         let config = Config {
-            endpoint: eth1.endpoint(),
+            endpoints: vec![eth1.endpoint()],
             deposit_contract_address: deposit_contract.address(),
             deposit_contract_deploy_block: now,
             lowest_cached_block_number: now,
@@ -758,3 +892,97 @@ mod persist {
         );
     }
 }
+
+mod fallback {
+    use super::*;
+    use eth1::EndpointState;
+
+    /// An endpoint which does not have a server listening on it.
+    const OFFLINE_ENDPOINT: &str = "http://127.0.0.1:1";
+
+    #[tokio::test]
+    async fn falls_back_to_healthy_endpoint() {
+        let log = null_logger();
+
+        let eth1 = GanacheEth1Instance::new()
+            .await
+            .expect("should start eth1 environment");
+        let deposit_contract = &eth1.deposit_contract;
+        let web3 = eth1.web3();
+
+        let now = get_block_number(&web3).await;
+        let service = Service::new(
+            Config {
+                endpoints: vec![OFFLINE_ENDPOINT.to_string(), eth1.endpoint()],
+                deposit_contract_address: deposit_contract.address(),
+                deposit_contract_deploy_block: now,
+                lowest_cached_block_number: now,
+                follow_distance: 0,
+                ..Config::default()
+            },
+            log,
+        );
+
+        let n = 4;
+        for _ in 0..n {
+            deposit_contract
+                .deposit(random_deposit_data())
+                .await
+                .expect("should perform a deposit");
+        }
+
+        Service::update(service.clone())
+            .await
+            .expect("should update from the second endpoint");
+
+        assert_eq!(
+            service.endpoint_states(),
+            vec![
+                (OFFLINE_ENDPOINT.to_string(), EndpointState::Offline),
+                (eth1.endpoint(), EndpointState::Online),
+            ],
+            "should mark the first endpoint as offline"
+        );
+        assert!(
+            service.deposit_cache_len() >= n,
+            "should have imported n deposits"
+        );
+        assert!(
+            service.block_cache_len() >= n,
+            "should have imported n eth1 blocks"
+        );
+    }
+
+    #[tokio::test]
+    async fn wrong_chain_id() {
+        let log = null_logger();
+
+        let eth1 = GanacheEth1Instance::new()
+            .await
+            .expect("should start eth1 environment");
+        let deposit_contract = &eth1.deposit_contract;
+
+        let service = Service::new(
+            Config {
+                endpoints: vec![eth1.endpoint()],
+                deposit_contract_address: deposit_contract.address(),
+                chain_id: Some(u64::max_value()),
+                follow_distance: 0,
+                ..Config::default()
+            },
+            log,
+        );
+
+        Service::update_endpoint_states(service.clone()).await;
+
+        assert_eq!(
+            service.endpoint_states(),
+            vec![(eth1.endpoint(), EndpointState::WrongChainId)]
+        );
+        assert_eq!(
+            Service::update_deposit_cache(service.clone()).await,
+            Err(eth1::Error::NoHealthyEndpoints),
+            "should not use an endpoint on the wrong chain"
+        );
+    }
+}
@@ -1,8 +1,10 @@
-use crate::peer_manager::{PeerManager, PeerManagerEvent};
+use crate::peer_manager::{PeerAction, PeerManager, PeerManagerEvent, GOSSIP_GRAYLIST_THRESHOLD};
 use crate::rpc::*;
-use crate::types::{GossipEncoding, GossipKind, GossipTopic};
+use crate::types::{GossipEncoding, GossipKind, GossipTopic, SubscriptionFilter};
 use crate::Eth2Enr;
-use crate::{error, Enr, NetworkConfig, NetworkGlobals, PubsubMessage, TopicHash};
+use crate::{
+    error, metrics, Enr, MessageAcceptance, NetworkConfig, NetworkGlobals, PubsubMessage, TopicHash,
+};
 use futures::prelude::*;
 use handler::{BehaviourHandler, BehaviourHandlerIn, BehaviourHandlerOut, DelegateIn, DelegateOut};
 use libp2p::{
@@ -11,7 +13,7 @@ use libp2p::{
         identity::Keypair,
         Multiaddr,
     },
-    gossipsub::{Gossipsub, GossipsubEvent, MessageId},
+    gossipsub::{Gossipsub, GossipsubEvent, MessageId, Topic},
     identify::{Identify, IdentifyEvent},
     swarm::{
         NetworkBehaviour, NetworkBehaviourAction as NBAction, NotifyHandler, PollParameters,
@@ -22,6 +24,7 @@ use libp2p::{
 use lru::LruCache;
 use slog::{crit, debug, o};
 use std::{
+    collections::{HashMap, HashSet},
     marker::PhantomData,
     sync::Arc,
     task::{Context, Poll},
@@ -57,12 +60,22 @@ pub struct Behaviour<TSpec: EthSpec> {
     /// duplicates that may still be seen over gossipsub.
     // TODO: Remove this
     seen_gossip_messages: LruCache<MessageId, ()>,
+    /// The kinds of the gossip messages awaiting validation by the application, used to score the
+    /// peers that forwarded them.
+    pending_validations: LruCache<MessageId, GossipKind>,
     /// A collections of variables accessible outside the network service.
     network_globals: Arc<NetworkGlobals<TSpec>>,
     /// Keeps track of the current EnrForkId for upgrading gossipsub topics.
     // NOTE: This can be accessed via the network_globals ENR. However we keep it here for quick
     // lookups for every gossipsub message send.
     enr_fork_id: EnrForkId,
+    /// The fork digest of an upcoming fork whose topics we are already subscribed to.
+    next_fork_digest: Option<[u8; 4]>,
+    /// The connected peers subscribed to each gossipsub topic. A message can only be published
+    /// once at least one peer is subscribed to one of its topics.
+    topic_peers: HashMap<TopicHash, HashSet<PeerId>>,
+    /// Restricts the topics we subscribe to and accept messages on.
+    subscription_filter: SubscriptionFilter,
     /// Logger for behaviour actions.
     log: slog::Logger,
 }
@@ -94,6 +107,10 @@ impl<TSpec: EthSpec> NetworkBehaviour for Behaviour<TSpec> {
 
     fn inject_disconnected(&mut self, peer_id: &PeerId) {
         delegate_to_behaviours!(self, inject_disconnected, peer_id);
+        self.topic_peers.retain(|_topic, peers| {
+            peers.remove(peer_id);
+            !peers.is_empty()
+        });
     }
 
     fn inject_connection_established(
@@ -130,6 +147,7 @@ impl<TSpec: EthSpec> NetworkBehaviour for Behaviour<TSpec> {
     }
 
     fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+        self.peer_manager.notify_dial_failure(peer_id);
         delegate_to_behaviours!(self, inject_dial_failure, peer_id);
     }
 
@@ -262,17 +280,25 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             attnets,
         };
 
+        let rpc_limiter = RPCRateLimiterBuilder::from(net_conf.rpc_quotas.clone())
+            .build()
+            .map_err(|e| format!("Invalid RPC quotas: {}", e))?;
+
         Ok(Behaviour {
-            eth2_rpc: RPC::new(log.clone()),
+            eth2_rpc: RPC::new_with_limiter(rpc_limiter, log.clone()),
             gossipsub: Gossipsub::new(local_peer_id, net_conf.gs_config.clone()),
             identify,
             peer_manager: PeerManager::new(local_key, net_conf, network_globals.clone(), log)?,
             events: Vec::new(),
             peers_to_dc: Vec::new(),
             seen_gossip_messages: LruCache::new(100_000),
+            pending_validations: LruCache::new(100_000),
             meta_data,
             network_globals,
             enr_fork_id,
+            next_fork_digest: None,
+            topic_peers: HashMap::new(),
+            subscription_filter: SubscriptionFilter::from_config(net_conf),
             log: behaviour_log,
         })
     }
@@ -297,7 +323,7 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             GossipEncoding::default(),
             self.enr_fork_id.fork_digest,
         );
-        self.subscribe(gossip_topic)
+        self.subscribe_all_forks(gossip_topic)
     }
 
     /// Unsubscribes from a gossipsub topic kind, letting the network service determine the
@@ -308,7 +334,7 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             GossipEncoding::default(),
             self.enr_fork_id.fork_digest,
         );
-        self.unsubscribe(gossip_topic)
+        self.unsubscribe_all_forks(gossip_topic)
     }
 
     /// Subscribes to a specific subnet id;
@@ -318,7 +344,7 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             GossipEncoding::default(),
             self.enr_fork_id.fork_digest,
         );
-        self.subscribe(topic)
+        self.subscribe_all_forks(topic)
     }
 
     /// Un-Subscribes from a specific subnet id;
@@ -328,11 +354,16 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             GossipEncoding::default(),
             self.enr_fork_id.fork_digest,
         );
-        self.unsubscribe(topic)
+        self.unsubscribe_all_forks(topic)
     }
 
-    /// Subscribes to a gossipsub topic.
+    /// Subscribes to a gossipsub topic, unless the subscription filter forbids its kind.
     fn subscribe(&mut self, topic: GossipTopic) -> bool {
+        if !self.subscription_filter.allows(topic.kind()) {
+            debug!(self.log, "Subscription to topic refused by filter"; "topic" => topic.kind().to_string());
+            return false;
+        }
+
         // update the network globals
         self.network_globals
             .gossipsub_subscriptions
@@ -344,8 +375,12 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
         self.gossipsub.subscribe(topic.into())
     }
 
-    /// Unsubscribe from a gossipsub topic.
+    /// Unsubscribe from a gossipsub topic, unless the subscription filter forbids its kind.
     fn unsubscribe(&mut self, topic: GossipTopic) -> bool {
+        if !self.subscription_filter.can_unsubscribe(topic.kind()) {
+            return false;
+        }
+
         // update the network globals
         self.network_globals
             .gossipsub_subscriptions
@@ -355,6 +390,81 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
         self.gossipsub.unsubscribe(topic.into())
     }
 
+    /// Subscribes to a gossipsub topic of the current fork and to its counterpart under an
+    /// upcoming fork, if we are already subscribed to the upcoming fork's topics.
+    fn subscribe_all_forks(&mut self, topic: GossipTopic) -> bool {
+        if let Some(next_fork_digest) = self.next_fork_digest {
+            self.subscribe(topic.with_fork_digest(next_fork_digest));
+        }
+        self.subscribe(topic)
+    }
+
+    /// Unsubscribes from a gossipsub topic of the current fork and from its counterparts under
+    /// all other forks.
+    fn unsubscribe_all_forks(&mut self, topic: GossipTopic) -> bool {
+        let counterparts = self
+            .network_globals
+            .gossipsub_subscriptions
+            .read()
+            .iter()
+            .filter(|subscribed| {
+                subscribed.kind() == topic.kind()
+                    && subscribed.encoding() == topic.encoding()
+                    && subscribed.fork_digest() != topic.fork_digest()
+            })
+            .cloned()
+            .collect::<Vec<GossipTopic>>();
+
+        for counterpart in counterparts {
+            self.unsubscribe(counterpart);
+        }
+        self.unsubscribe(topic)
+    }
+
+    /// Subscribes to the counterparts of all gossipsub topics of the current fork under the
+    /// upcoming fork with `fork_digest`. This allows us to join the meshes of the new fork before
+    /// it takes place.
+    pub fn subscribe_to_next_fork_topics(&mut self, fork_digest: [u8; 4]) {
+        if fork_digest == self.enr_fork_id.fork_digest {
+            return;
+        }
+        self.next_fork_digest = Some(fork_digest);
+
+        let new_topics = {
+            let subscriptions = self.network_globals.gossipsub_subscriptions.read();
+            subscriptions
+                .iter()
+                .filter(|topic| topic.fork_digest() == self.enr_fork_id.fork_digest)
+                .map(|topic| topic.with_fork_digest(fork_digest))
+                .filter(|topic| !subscriptions.contains(topic))
+                .collect::<Vec<GossipTopic>>()
+        };
+
+        for topic in new_topics {
+            self.subscribe(topic);
+        }
+    }
+
+    /// Unsubscribes from all gossipsub topics that do not belong to the current fork. This is
+    /// called once the grace period following a fork has passed.
+    pub fn unsubscribe_from_old_fork_topics(&mut self) {
+        let old_topics = self
+            .network_globals
+            .gossipsub_subscriptions
+            .read()
+            .iter()
+            .filter(|topic| {
+                topic.fork_digest() != self.enr_fork_id.fork_digest
+                    && Some(topic.fork_digest()) != self.next_fork_digest
+            })
+            .cloned()
+            .collect::<Vec<GossipTopic>>();
+
+        for topic in old_topics {
+            self.unsubscribe(topic);
+        }
+    }
+
     /// Publishes a list of messages on the pubsub (gossipsub) behaviour, choosing the encoding.
     pub fn publish(&mut self, messages: Vec<PubsubMessage<TSpec>>) {
         for message in messages {
@@ -369,6 +479,18 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
         }
     }
 
+    /// Returns `true` if a connected peer is subscribed to one of the topics of `message`, i.e.,
+    /// publishing the message would send it to at least one peer.
+    pub fn can_publish(&self, message: &PubsubMessage<TSpec>) -> bool {
+        message
+            .topics(GossipEncoding::default(), self.enr_fork_id.fork_digest)
+            .into_iter()
+            .any(|topic| {
+                let topic: Topic = topic.into();
+                self.topic_peers.contains_key(&topic.no_hash())
+            })
+    }
+
     /// Forwards a message that is waiting in gossipsub's mcache. Messages are only propagated
     /// once validated by the beacon chain.
     pub fn propagate_message(&mut self, propagation_source: &PeerId, message_id: MessageId) {
@@ -376,6 +498,37 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             .propagate_message(&message_id, propagation_source);
     }
 
+    /// Informs the behaviour of the validation result of a gossipsub message.
+    ///
+    /// Accepted messages are propagated to our peers. Rejected messages are not propagated and the
+    /// peer that forwarded the message to us is penalised. Ignored messages are dropped.
+    pub fn validate_message(
+        &mut self,
+        propagation_source: &PeerId,
+        message_id: MessageId,
+        validation_result: MessageAcceptance,
+    ) {
+        if let Some(kind) = self.pending_validations.pop(&message_id) {
+            self.peer_manager.gossip_message_validated(
+                propagation_source,
+                kind,
+                &validation_result,
+            );
+        }
+
+        match validation_result {
+            MessageAcceptance::Accept => self.propagate_message(propagation_source, message_id),
+            MessageAcceptance::Ignore => {}
+            MessageAcceptance::Reject => {
+                debug!(self.log, "Rejected gossipsub message";
+                    "message_id" => message_id.to_string(),
+                    "peer_id" => propagation_source.to_string());
+                self.peer_manager
+                    .report_peer(propagation_source, PeerAction::LowToleranceError);
+            }
+        }
+    }
+
     /* Eth2 RPC behaviour functions */
 
     /// Send a request to a peer over RPC.
@@ -433,6 +586,14 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
     ///
     /// The `value` is `true` if a subnet is being added and false otherwise.
     pub fn update_enr_subnet(&mut self, subnet_id: SubnetId, value: bool) {
+        // the subnets we advertise must match the subnets the filter lets us subscribe to
+        let kind = GossipKind::CommitteeIndex(subnet_id);
+        if (value && !self.subscription_filter.allows(&kind))
+            || (!value && !self.subscription_filter.can_unsubscribe(&kind))
+        {
+            return;
+        }
+
         if let Err(e) = self
             .peer_manager
             .discovery_mut()
@@ -451,33 +612,20 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
     }
 
     /// Updates the local ENR's "eth2" field with the latest EnrForkId.
+    ///
+    /// The topics of the new fork are subscribed to (if they weren't already), whilst the topics
+    /// of the old fork are kept until `unsubscribe_from_old_fork_topics` is called.
     pub fn update_fork_version(&mut self, enr_fork_id: EnrForkId) {
         self.peer_manager
             .discovery_mut()
             .update_eth2_enr(enr_fork_id.clone());
 
-        // unsubscribe from all gossip topics and re-subscribe to their new fork counterparts
-        let subscribed_topics = self
-            .network_globals
-            .gossipsub_subscriptions
-            .read()
-            .iter()
-            .cloned()
-            .collect::<Vec<GossipTopic>>();
-
-        //  unsubscribe from all topics
-        for topic in &subscribed_topics {
-            self.unsubscribe(topic.clone());
-        }
-
-        // re-subscribe modifying the fork version
-        for mut topic in subscribed_topics {
-            *topic.digest() = enr_fork_id.fork_digest;
-            self.subscribe(topic);
-        }
+        // subscribe to the new fork counterparts of all gossip topics
+        self.subscribe_to_next_fork_topics(enr_fork_id.fork_digest);
 
         // update the local reference
         self.enr_fork_id = enr_fork_id;
+        self.next_fork_digest = None;
     }
 
     /* Private internal functions */
@@ -572,12 +720,38 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             GossipsubEvent::Message(propagation_source, id, gs_msg) => {
                 // Note: We are keeping track here of the peer that sent us the message, not the
                 // peer that originally published the message.
-                if self.seen_gossip_messages.put(id.clone(), ()).is_none() {
+                if self.has_unknown_fork_digest(&gs_msg.topics) {
+                    debug!(self.log, "Gossipsub message published under an unknown fork digest";
+                        "topics" => format!("{:?}", gs_msg.topics));
+                    self.validate_message(&propagation_source, id, MessageAcceptance::Reject);
+                } else if !self.accepts_topics(&gs_msg.topics) {
+                    // we are not subscribed to any of the message's topics, or they are filtered
+                    debug!(self.log, "Gossipsub message received on an irrelevant topic";
+                        "topics" => format!("{:?}", gs_msg.topics));
+                    self.validate_message(&propagation_source, id, MessageAcceptance::Ignore);
+                } else if self
+                    .network_globals
+                    .peers
+                    .read()
+                    .gossip_score(&propagation_source)
+                    < GOSSIP_GRAYLIST_THRESHOLD
+                {
+                    // the peer has forwarded too many invalid messages, ignore its messages until
+                    // its score has recovered
+                    debug!(self.log, "Ignoring gossipsub message from graylisted peer";
+                        "peer_id" => propagation_source.to_string());
+                } else if self.seen_gossip_messages.put(id.clone(), ()).is_none() {
                     match PubsubMessage::decode(&gs_msg.topics, &gs_msg.data) {
                         Err(e) => {
+                            metrics::inc_counter(&metrics::GOSSIP_DECODE_FAILURES);
                             debug!(self.log, "Could not decode gossipsub message"; "error" => format!("{}", e))
                         }
                         Ok(msg) => {
+                            metrics::inc_counter_vec(
+                                &metrics::GOSSIP_MESSAGES_RX_PER_TOPIC,
+                                &[&msg.kind().to_string()],
+                            );
+                            self.pending_validations.put(id.clone(), msg.kind());
                             // if this message isn't a duplicate, notify the network
                             self.events.push(BehaviourEvent::PubsubMessage {
                                 id,
@@ -599,13 +773,57 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                 }
             }
             GossipsubEvent::Subscribed { peer_id, topic } => {
+                self.topic_peers
+                    .entry(topic.clone())
+                    .or_default()
+                    .insert(peer_id.clone());
                 self.events
                     .push(BehaviourEvent::PeerSubscribed(peer_id, topic));
             }
-            GossipsubEvent::Unsubscribed { .. } => {}
+            GossipsubEvent::Unsubscribed { peer_id, topic } => {
+                if let Some(peers) = self.topic_peers.get_mut(&topic) {
+                    peers.remove(&peer_id);
+                    if peers.is_empty() {
+                        self.topic_peers.remove(&topic);
+                    }
+                }
+            }
         }
     }
 
+    /// Returns `true` if the `topics` of a message are known topics, none of which belong to a
+    /// fork whose topics we are subscribed to.
+    fn has_unknown_fork_digest(&self, topics: &[TopicHash]) -> bool {
+        let subscriptions = self.network_globals.gossipsub_subscriptions.read();
+        let fork_digests = topics
+            .iter()
+            .filter_map(|topic| GossipTopic::decode(topic.as_str()).ok())
+            .map(|topic| topic.fork_digest())
+            .collect::<Vec<_>>();
+
+        !fork_digests.is_empty()
+            && !fork_digests.iter().any(|fork_digest| {
+                subscriptions
+                    .iter()
+                    .any(|subscribed| subscribed.fork_digest() == *fork_digest)
+            })
+    }
+
+    /// Returns `true` if we are subscribed to at least one of the `topics` of a message and the
+    /// subscription filter allows all of them.
+    fn accepts_topics(&self, topics: &[TopicHash]) -> bool {
+        let subscriptions = self.network_globals.gossipsub_subscriptions.read();
+        let topics = topics
+            .iter()
+            .filter_map(|topic| GossipTopic::decode(topic.as_str()).ok())
+            .collect::<Vec<_>>();
+
+        topics
+            .iter()
+            .all(|topic| self.subscription_filter.allows(topic.kind()))
+            && topics.iter().any(|topic| subscriptions.contains(topic))
+    }
+
     /// Queues the response to be sent upwards as long at it was requested outside the Behaviour.
     fn propagate_response(&mut self, id: RequestId, peer_id: PeerId, response: Response<TSpec>) {
         if !matches!(id, RequestId::Behaviour) {
@@ -675,10 +893,14 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                     }
                     RPCRequest::Goodbye(reason) => {
                         // let the peer manager know this peer is in the process of disconnecting
-                        self.peer_manager._disconnecting_peer(&peer_id);
+                        self.peer_manager.disconnecting_peer(&peer_id);
                         // queue for disconnection without a goodbye message
                         debug!(self.log, "Received a Goodbye, queueing for disconnection";
-                            "peer_id" => peer_id.to_string());
+                            "peer_id" => peer_id.to_string(), "reason" => reason.to_string());
+                        metrics::inc_counter_vec(
+                            &metrics::PEER_GOODBYE_RECEIVED,
+                            &[&reason.to_string()],
+                        );
                         self.peers_to_dc.push(peer_id.clone());
                         // TODO: do not propagate
                         self.propagate_request(peer_request_id, peer_id, Request::Goodbye(reason));
@@ -773,9 +995,10 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                     PeerManagerEvent::MetaData(peer_id) => {
                         self.send_meta_data_request(peer_id);
                     }
-                    PeerManagerEvent::DisconnectPeer(peer_id) => {
+                    PeerManagerEvent::DisconnectPeer(peer_id, reason) => {
                         debug!(self.log, "PeerManager requested to disconnect a peer";
-                            "peer_id" => peer_id.to_string());
+                            "peer_id" => peer_id.to_string(), "reason" => reason.to_string());
+                        self.peer_manager.disconnecting_peer(&peer_id);
                         // queue for disabling
                         self.peers_to_dc.push(peer_id.clone());
                         // send one goodbye
@@ -784,7 +1007,7 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                             handler: NotifyHandler::Any,
                             event: BehaviourHandlerIn::Shutdown(Some((
                                 RequestId::Behaviour,
-                                RPCRequest::Goodbye(GoodbyeReason::Fault),
+                                RPCRequest::Goodbye(reason),
                             ))),
                         });
                     }
@@ -1,15 +1,24 @@
+use crate::rpc::RPCQuotas;
 use crate::types::GossipKind;
-use crate::Enr;
+use crate::{Enr, PeerId};
 use discv5::{Discv5Config, Discv5ConfigBuilder};
 use libp2p::gossipsub::{GossipsubConfig, GossipsubConfigBuilder, GossipsubMessage, MessageId};
 use libp2p::Multiaddr;
 use serde_derive::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use snap::raw::{decompress_len, Decoder};
 use std::path::PathBuf;
 use std::time::Duration;
 
 pub const GOSSIP_MAX_SIZE: usize = 1_048_576;
 
+/// The domain of the id of a gossip message whose payload is valid snappy compressed data.
+const MESSAGE_DOMAIN_VALID_SNAPPY: [u8; 4] = [1, 0, 0, 0];
+/// The domain of the id of a gossip message whose payload could not be decompressed.
+const MESSAGE_DOMAIN_INVALID_SNAPPY: [u8; 4] = [0, 0, 0, 0];
+/// The number of bytes of the hash kept in a gossip message id.
+const MESSAGE_ID_LEN: usize = 20;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 /// Network configuration for lighthouse.
@@ -26,6 +35,13 @@ pub struct Config {
     /// UDP port that discovery listens on.
     pub discovery_port: u16,
 
+    /// An IPv6 address that libp2p listens on in addition to `listen_address`, which must then be
+    /// an IPv4 address. Discovery only listens on `listen_address`.
+    pub listen_address_v6: Option<std::net::Ipv6Addr>,
+
+    /// The TCP port that libp2p listens on with `listen_address_v6`.
+    pub libp2p_port_v6: u16,
+
     /// The address to broadcast to peers about which address we are listening on. None indicates
     /// that no discovery address has been set in the CLI args.
     pub enr_address: Option<std::net::IpAddr>,
@@ -36,9 +52,26 @@ pub struct Config {
     /// The tcp port to broadcast to peers in order to reach back for libp2p services.
     pub enr_tcp_port: Option<u16>,
 
+    /// The IPv6 address to broadcast to peers in addition to `enr_address`.
+    pub enr_address_v6: Option<std::net::Ipv6Addr>,
+
+    /// The TCP port to broadcast to peers with `enr_address_v6`. `libp2p_port_v6` is used if this
+    /// is not set.
+    pub enr_tcp_port_v6: Option<u16>,
+
     /// Target number of connected peers.
+    pub target_peers: usize,
+
+    /// Maximum number of connected peers. Inbound connections beyond this limit are rejected.
     pub max_peers: usize,
 
+    /// Attempt to construct external port mappings with UPnP. Disabled by default.
+    pub upnp_enabled: bool,
+
+    /// Whether secio may be negotiated with peers that do not support Noise. Noise is always
+    /// preferred.
+    pub secio_enabled: bool,
+
     /// Gossipsub configuration parameters.
     #[serde(skip)]
     pub gs_config: GossipsubConfig,
@@ -53,11 +86,32 @@ pub struct Config {
     /// List of libp2p nodes to initially connect to.
     pub libp2p_nodes: Vec<Multiaddr>,
 
+    /// List of trusted peers that are never banned or pruned and are re-dialed when disconnected.
+    #[serde(skip)]
+    pub trusted_peers: Vec<PeerId>,
+
     /// Client version
     pub client_version: String,
 
     /// List of extra topics to initially subscribe to as strings.
     pub topics: Vec<GossipKind>,
+
+    /// Never subscribe to, nor accept messages on, the aggregate and attestation subnet topics.
+    pub disable_attestation_gossip: bool,
+
+    /// Subscribe to all attestation subnets on startup and never unsubscribe from them.
+    pub subscribe_all_subnets: bool,
+
+    /// The fraction of a slot to wait before requesting an unknown block referenced by a peer,
+    /// in case the block is received on gossip in the meantime. Zero disables the delay.
+    pub block_lookup_delay: f64,
+
+    /// The number of batches, starting from the next batch to be processed, that a range sync
+    /// chain may download at any one time.
+    pub batch_lookahead: u64,
+
+    /// The quotas by which the inbound RPC requests of each peer are rate limited.
+    pub rpc_quotas: RPCQuotas,
 }
 
 impl Default for Config {
@@ -76,15 +130,6 @@ impl Default for Config {
             GossipKind::AttesterSlashing,
         ];
 
-        // The function used to generate a gossipsub message id
-        // We use base64(SHA256(data)) for content addressing
-        let gossip_message_id = |message: &GossipsubMessage| {
-            MessageId(base64::encode_config(
-                &Sha256::digest(&message.data),
-                base64::URL_SAFE_NO_PAD,
-            ))
-        };
-
         // gossipsub configuration
         // Note: The topics by default are sent as plain strings. Hashes are an optional
         // parameter.
@@ -116,16 +161,133 @@ impl Default for Config {
             listen_address: "0.0.0.0".parse().expect("valid ip address"),
             libp2p_port: 9000,
             discovery_port: 9000,
+            listen_address_v6: None,
+            libp2p_port_v6: 9090,
             enr_address: None,
             enr_udp_port: None,
             enr_tcp_port: None,
-            max_peers: 50,
+            enr_address_v6: None,
+            enr_tcp_port_v6: None,
+            target_peers: 50,
+            max_peers: 55,
+            upnp_enabled: false,
+            secio_enabled: true,
             gs_config,
             discv5_config,
             boot_nodes: vec![],
             libp2p_nodes: vec![],
+            trusted_peers: vec![],
             client_version: version::version(),
             topics,
+            disable_attestation_gossip: false,
+            subscribe_all_subnets: false,
+            block_lookup_delay: 0.25,
+            batch_lookahead: 5,
+            rpc_quotas: RPCQuotas::default(),
+        }
+    }
+}
+
+/// The function used to generate a gossipsub message id.
+///
+/// The id is a hash of the topics and the uncompressed payload of the message, such that the same
+/// message is given the same id regardless of the peer it is received from or how that peer
+/// compressed it. Payloads which are not valid snappy compressed data are hashed as they are,
+/// under a different domain.
+pub fn gossip_message_id(message: &GossipsubMessage) -> MessageId {
+    let decompressed = decompress_snappy(&message.data);
+    let (domain, payload) = match &decompressed {
+        Some(decompressed) => (MESSAGE_DOMAIN_VALID_SNAPPY, decompressed),
+        None => (MESSAGE_DOMAIN_INVALID_SNAPPY, &message.data),
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.input(&domain);
+    for topic in &message.topics {
+        hasher.input(&(topic.as_str().len() as u64).to_le_bytes());
+        hasher.input(topic.as_str().as_bytes());
+    }
+    hasher.input(payload);
+
+    MessageId(base64::encode_config(
+        &hasher.result()[..MESSAGE_ID_LEN],
+        base64::URL_SAFE_NO_PAD,
+    ))
+}
+
+/// Decompresses snappy compressed `data`, returning `None` if it is invalid or would exceed
+/// `GOSSIP_MAX_SIZE` once decompressed.
+fn decompress_snappy(data: &[u8]) -> Option<Vec<u8>> {
+    match decompress_len(data) {
+        Ok(n) if n <= GOSSIP_MAX_SIZE => Decoder::new().decompress_vec(data).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::gossipsub::TopicHash;
+    use snap::raw::Encoder;
+
+    fn message(topic: &str, data: Vec<u8>) -> GossipsubMessage {
+        GossipsubMessage {
+            source: PeerId::random(),
+            data,
+            sequence_number: 0,
+            topics: vec![TopicHash::from_raw(topic)],
         }
     }
+
+    /// Encodes `payload` as a single snappy literal, without any compression.
+    fn snappy_literal(payload: &[u8]) -> Vec<u8> {
+        assert!(payload.len() <= 60, "a short literal holds up to 60 bytes");
+        // the uncompressed length (a varint) followed by a literal tag and the payload
+        let mut data = vec![payload.len() as u8, ((payload.len() - 1) << 2) as u8];
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn message_id_is_independent_of_compression() {
+        let topic = "/eth2/00000000/beacon_block/ssz_snappy";
+        let payload = [42; 48];
+
+        let compressed = Encoder::new()
+            .compress_vec(&payload)
+            .expect("should compress payload");
+        let uncompressed = snappy_literal(&payload);
+        assert!(compressed.len() < uncompressed.len());
+        assert_eq!(
+            Decoder::new().decompress_vec(&uncompressed),
+            Ok(payload.to_vec()),
+            "the literal should be valid snappy data"
+        );
+
+        assert_eq!(
+            gossip_message_id(&message(topic, compressed)),
+            gossip_message_id(&message(topic, uncompressed)),
+        );
+    }
+
+    #[test]
+    fn message_id_depends_on_topic_and_payload() {
+        let topic = "/eth2/00000000/beacon_block/ssz_snappy";
+        let data = snappy_literal(&[1; 32]);
+        let id = gossip_message_id(&message(topic, data.clone()));
+
+        assert_ne!(
+            id,
+            gossip_message_id(&message("/eth2/00000000/voluntary_exit/ssz_snappy", data))
+        );
+        assert_ne!(
+            id,
+            gossip_message_id(&message(topic, snappy_literal(&[2; 32])))
+        );
+        assert_ne!(
+            id,
+            gossip_message_id(&message(topic, vec![1; 32])),
+            "invalid snappy data should be hashed under a different domain"
+        );
+    }
 }
@@ -120,11 +120,24 @@ pub fn build_enr<T: EthSpec>(
     let tcp_port = config.enr_tcp_port.unwrap_or_else(|| config.libp2p_port);
     builder.tcp(tcp_port);
 
+    // dual-stack nodes also advertise their IPv6 address and the port libp2p listens on with it
+    if let Some(enr_address_v6) = config.enr_address_v6 {
+        builder.ip(enr_address_v6.into());
+        builder.tcp6(config.enr_tcp_port_v6.unwrap_or(config.libp2p_port_v6));
+    }
+
     // set the `eth2` field on our ENR
     builder.add_value(ETH2_ENR_KEY.into(), enr_fork_id.as_ssz_bytes());
 
-    // set the "attnets" field on our ENR
-    let bitfield = BitVector::<T::SubnetBitfieldLength>::new();
+    // set the "attnets" field on our ENR, advertising all subnets if we subscribe to all of them
+    let mut bitfield = BitVector::<T::SubnetBitfieldLength>::new();
+    if config.subscribe_all_subnets && !config.disable_attestation_gossip {
+        for subnet in 0..bitfield.len() {
+            bitfield
+                .set(subnet, true)
+                .map_err(|e| format!("Could not set ENR subnet bitfield: {:?}", e))?;
+        }
+    }
 
     builder.add_value(BITFIELD_ENR_KEY.into(), bitfield.as_ssz_bytes());
 
@@ -141,6 +154,9 @@ fn compare_enr(local_enr: &Enr, disk_enr: &Enr) -> bool {
     (local_enr.ip().is_none() || local_enr.ip() == disk_enr.ip())
         // tcp ports must match
         && local_enr.tcp() == disk_enr.tcp()
+        // take preference over disk_enr ipv6 address if one is not specified
+        && (local_enr.ip6().is_none() || local_enr.ip6() == disk_enr.ip6())
+        && local_enr.tcp6() == disk_enr.tcp6()
         // must match on the same fork
         && local_enr.get(ETH2_ENR_KEY) == disk_enr.get(ETH2_ENR_KEY)
         // take preference over disk udp port if one is not specified
@@ -0,0 +1,32 @@
+///! The fork predicate used to filter out peers of other networks or forks.
+use super::*;
+
+/// Returns the predicate for peers whose `eth2` ENR field has the fork digest of `enr_fork_id`.
+///
+/// Peers with the same fork digest but a different next fork are kept, as permitted by the
+/// specification, since we can interact with them until the earlier of the two next forks. Peers
+/// without a matching fork digest are counted in the `DISCOVERY_FORK_MISMATCHES` metric.
+pub fn fork_predicate(enr_fork_id: EnrForkId, log: &slog::Logger) -> impl Fn(&Enr) -> bool + Send {
+    let log_clone = log.clone();
+
+    move |enr: &Enr| match enr.eth2() {
+        Ok(peer_fork_id) if peer_fork_id.fork_digest == enr_fork_id.fork_digest => {
+            if peer_fork_id.next_fork_version != enr_fork_id.next_fork_version
+                || peer_fork_id.next_fork_epoch != enr_fork_id.next_fork_epoch
+            {
+                trace!(log_clone, "Peer found with a different next fork"; "peer_id" => format!("{}", enr.peer_id()), "next_fork_version" => hex::encode(peer_fork_id.next_fork_version), "next_fork_epoch" => peer_fork_id.next_fork_epoch.as_u64());
+            }
+            true
+        }
+        Ok(peer_fork_id) => {
+            metrics::inc_counter(&metrics::DISCOVERY_FORK_MISMATCHES);
+            trace!(log_clone, "Peer found on a different fork"; "peer_id" => format!("{}", enr.peer_id()), "fork_digest" => hex::encode(peer_fork_id.fork_digest));
+            false
+        }
+        Err(e) => {
+            metrics::inc_counter(&metrics::DISCOVERY_FORK_MISMATCHES);
+            trace!(log_clone, "Peer found without a valid fork"; "peer_id" => format!("{}", enr.peer_id()), "error" => e);
+            false
+        }
+    }
+}
@@ -20,7 +20,7 @@ use ssz::{Decode, Encode};
 use ssz_types::BitVector;
 use std::{
     collections::VecDeque,
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     path::Path,
     pin::Pin,
     sync::Arc,
@@ -30,7 +30,9 @@ use std::{
 use tokio::sync::mpsc;
 use types::{EnrForkId, EthSpec, SubnetId};
 
+mod fork_predicate;
 mod subnet_predicate;
+use fork_predicate::fork_predicate;
 use subnet_predicate::subnet_predicate;
 
 /// Local ENR storage filename.
@@ -303,6 +305,14 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
         }
     }
 
+    /// Returns `true` if the `eth2` field of `enr` has the fork digest of the local ENR.
+    pub fn on_local_fork(&self, enr: &Enr) -> bool {
+        match self.local_enr().eth2() {
+            Ok(enr_fork_id) => fork_predicate(enr_fork_id, &self.log)(enr),
+            Err(_) => false,
+        }
+    }
+
     /// Returns an iterator over all enr entries in the DHT.
     pub fn table_entries_enr(&mut self) -> Vec<Enr> {
         self.discv5.table_entries_enr()
@@ -363,6 +373,35 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
         Ok(())
     }
 
+    /// Updates the local ENR TCP port.
+    pub fn update_enr_tcp_port(&mut self, port: u16) -> Result<(), String> {
+        self.discv5
+            .enr_insert("tcp", port.to_be_bytes().to_vec())
+            .map_err(|e| format!("{:?}", e))?;
+
+        // replace the global version
+        *self.network_globals.local_enr.write() = self.discv5.local_enr().clone();
+        Ok(())
+    }
+
+    /// Updates the local ENR IP address and UDP port.
+    pub fn update_enr_udp_socket(&mut self, socket_addr: SocketAddr) -> Result<(), String> {
+        let ip = match socket_addr.ip() {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(_) => return Err("IPv6 ENR addresses are not supported".into()),
+        };
+        self.discv5
+            .enr_insert("ip", ip)
+            .map_err(|e| format!("{:?}", e))?;
+        self.discv5
+            .enr_insert("udp", socket_addr.port().to_be_bytes().to_vec())
+            .map_err(|e| format!("{:?}", e))?;
+
+        // replace the global version
+        *self.network_globals.local_enr.write() = self.discv5.local_enr().clone();
+        Ok(())
+    }
+
     /// Updates the `eth2` field of our local ENR.
     pub fn update_eth2_enr(&mut self, enr_fork_id: EnrForkId) {
         // to avoid having a reference to the spec constant, for the logging we assume
@@ -502,7 +541,7 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
             }
         };
         // predicate for finding nodes with a matching fork
-        let eth2_fork_predicate = move |enr: &Enr| enr.eth2() == Ok(enr_fork_id.clone());
+        let eth2_fork_predicate = fork_predicate(enr_fork_id, &self.log);
 
         // General predicate
         let predicate: Box<dyn Fn(&Enr) -> bool + Send> = match &query {
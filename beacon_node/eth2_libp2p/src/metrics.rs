@@ -9,6 +9,14 @@ lazy_static! {
         "libp2p_peer_connected_peers_total",
         "Count of libp2p peers currently connected"
     );
+    pub static ref PEERS_CONNECTED_INBOUND: Result<IntGauge> = try_create_int_gauge(
+        "libp2p_peer_connected_inbound_peers_total",
+        "Count of libp2p peers currently connected that dialed us"
+    );
+    pub static ref PEERS_CONNECTED_OUTBOUND: Result<IntGauge> = try_create_int_gauge(
+        "libp2p_peer_connected_outbound_peers_total",
+        "Count of libp2p peers currently connected that we dialed"
+    );
     pub static ref PEER_CONNECT_EVENT_COUNT: Result<IntCounter> = try_create_int_counter(
         "libp2p_peer_connect_event_total",
         "Count of libp2p peer connect events (not the current number of connected peers)"
@@ -17,6 +25,24 @@ lazy_static! {
         "libp2p_peer_disconnect_event_total",
         "Count of libp2p peer disconnect events"
     );
+    pub static ref PEER_GOODBYE_RECEIVED: Result<IntCounterVec> = try_create_int_counter_vec(
+        "libp2p_peer_goodbye_received_total",
+        "Count of GOODBYE messages received from peers, by reason",
+        &["reason"]
+    );
+    pub static ref GOSSIP_MESSAGES_RX_PER_TOPIC: Result<IntCounterVec> = try_create_int_counter_vec(
+        "libp2p_gossip_messages_rx_total",
+        "Count of decoded gossipsub messages received, by topic",
+        &["topic"]
+    );
+    pub static ref GOSSIP_DECODE_FAILURES: Result<IntCounter> = try_create_int_counter(
+        "libp2p_gossip_decode_failures_total",
+        "Count of gossipsub messages received that could not be decoded"
+    );
+    pub static ref DISCOVERY_FORK_MISMATCHES: Result<IntCounter> = try_create_int_counter(
+        "discovery_fork_mismatches_total",
+        "Count of discovered peers rejected as their ENR is not on our fork"
+    );
     pub static ref DISCOVERY_QUEUE: Result<IntGauge> = try_create_int_gauge(
         "discovery_queue_size",
         "The number of discovery queries awaiting execution"
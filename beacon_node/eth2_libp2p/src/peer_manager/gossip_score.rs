@@ -0,0 +1,272 @@
+//! Scores peers on the gossip messages they forward to us, following the topic scores of
+//! gossipsub v1.1.
+//!
+//! Our gossipsub implementation predates v1.1 and does not score peers itself, so the scores are
+//! computed from the validation results of the messages forwarded by each peer. For each topic, a
+//! peer is rewarded for being the first to deliver valid messages (P2 in the v1.1 specification)
+//! and penalized quadratically for delivering invalid messages (P4). Topics carrying few, critical
+//! messages (blocks, aggregates) are weighted heavier than attestation subnets.
+//!
+//! The counters of every peer decay on each heartbeat of the peer manager, such that a peer
+//! eventually recovers from past invalid messages. The counters of disconnected peers are retained
+//! until they have fully decayed, so a peer cannot reset its score by reconnecting. Likewise, the
+//! scores persisted by a previous run of the node are restored, and decay in the same way.
+
+use crate::types::GossipKind;
+use crate::PeerId;
+use std::collections::HashMap;
+
+/// Counters below this value are reset to zero when decaying.
+const DECAY_TO_ZERO: f64 = 0.01;
+
+/// The number of attestation subnets, amongst which the weight of attestations is shared.
+const ATTESTATION_SUBNET_COUNT: f64 = 64.0;
+
+/// The maximum score a peer can obtain from a single topic.
+const TOPIC_SCORE_CAP: f64 = 10.0;
+
+/// The factor by which the scores restored from a previous run of the node are multiplied on each
+/// decay.
+const RESTORED_SCORE_DECAY: f64 = 0.99;
+
+/// The parameters of the score of a gossipsub topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicScoreParams {
+    /// The weight of the topic in the score of a peer.
+    pub topic_weight: f64,
+    /// The weight of each message first delivered by the peer. Must be positive.
+    pub first_message_deliveries_weight: f64,
+    /// The factor by which the first deliveries are multiplied on each decay.
+    pub first_message_deliveries_decay: f64,
+    /// The maximum number of first deliveries counted.
+    pub first_message_deliveries_cap: f64,
+    /// The weight of the square of the invalid messages delivered by the peer. Must be negative.
+    pub invalid_message_deliveries_weight: f64,
+    /// The factor by which the invalid deliveries are multiplied on each decay.
+    pub invalid_message_deliveries_decay: f64,
+}
+
+impl TopicScoreParams {
+    /// Returns the score parameters of the topic of `kind`.
+    pub fn for_kind(kind: &GossipKind) -> Self {
+        match kind {
+            GossipKind::BeaconBlock => TopicScoreParams {
+                topic_weight: 0.5,
+                first_message_deliveries_weight: 1.0,
+                first_message_deliveries_decay: 0.9,
+                first_message_deliveries_cap: 20.0,
+                invalid_message_deliveries_weight: -200.0,
+                invalid_message_deliveries_decay: 0.99,
+            },
+            GossipKind::BeaconAggregateAndProof => TopicScoreParams {
+                topic_weight: 0.5,
+                first_message_deliveries_weight: 0.25,
+                first_message_deliveries_decay: 0.8,
+                first_message_deliveries_cap: 80.0,
+                invalid_message_deliveries_weight: -200.0,
+                invalid_message_deliveries_decay: 0.99,
+            },
+            GossipKind::CommitteeIndex(_) => TopicScoreParams {
+                topic_weight: 1.0 / ATTESTATION_SUBNET_COUNT,
+                first_message_deliveries_weight: 0.5,
+                first_message_deliveries_decay: 0.8,
+                first_message_deliveries_cap: 40.0,
+                invalid_message_deliveries_weight: -800.0,
+                invalid_message_deliveries_decay: 0.99,
+            },
+            GossipKind::VoluntaryExit
+            | GossipKind::ProposerSlashing
+            | GossipKind::AttesterSlashing => TopicScoreParams {
+                topic_weight: 0.05,
+                first_message_deliveries_weight: 1.0,
+                first_message_deliveries_decay: 0.95,
+                first_message_deliveries_cap: 5.0,
+                invalid_message_deliveries_weight: -2000.0,
+                invalid_message_deliveries_decay: 0.99,
+            },
+        }
+    }
+}
+
+/// The counters of the messages delivered by a peer on a topic.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TopicStats {
+    first_message_deliveries: f64,
+    invalid_message_deliveries: f64,
+}
+
+impl TopicStats {
+    /// The contribution of the topic to the score of the peer, before it is capped.
+    fn score(&self, params: &TopicScoreParams) -> f64 {
+        let first_deliveries = self
+            .first_message_deliveries
+            .min(params.first_message_deliveries_cap);
+        let score = first_deliveries * params.first_message_deliveries_weight
+            + self.invalid_message_deliveries.powi(2) * params.invalid_message_deliveries_weight;
+        score * params.topic_weight
+    }
+
+    /// Decays the counters, returning `true` once they have both reached zero.
+    fn decay(&mut self, params: &TopicScoreParams) -> bool {
+        self.first_message_deliveries = decay_counter(
+            self.first_message_deliveries,
+            params.first_message_deliveries_decay,
+        );
+        self.invalid_message_deliveries = decay_counter(
+            self.invalid_message_deliveries,
+            params.invalid_message_deliveries_decay,
+        );
+        self.first_message_deliveries == 0.0 && self.invalid_message_deliveries == 0.0
+    }
+}
+
+/// Multiplies `counter` by `decay`, resetting it to zero once it is negligible.
+fn decay_counter(counter: f64, decay: f64) -> f64 {
+    let counter = counter * decay;
+    if counter < DECAY_TO_ZERO {
+        0.0
+    } else {
+        counter
+    }
+}
+
+/// The gossip scores of all peers that have recently forwarded us messages.
+#[derive(Default)]
+pub struct GossipScores {
+    stats: HashMap<PeerId, HashMap<GossipKind, TopicStats>>,
+    /// The scores of peers persisted by a previous run of the node, which are added to the scores
+    /// of their topics until they have decayed.
+    restored: HashMap<PeerId, f64>,
+}
+
+impl GossipScores {
+    /// Records that `peer_id` was the first to deliver a valid message on the topic of `kind`.
+    pub fn first_message_delivery(&mut self, peer_id: &PeerId, kind: GossipKind) {
+        let params = TopicScoreParams::for_kind(&kind);
+        let stats = self.topic_stats_mut(peer_id, kind);
+        stats.first_message_deliveries =
+            (stats.first_message_deliveries + 1.0).min(params.first_message_deliveries_cap);
+    }
+
+    /// Records that `peer_id` delivered an invalid message on the topic of `kind`.
+    pub fn invalid_message_delivery(&mut self, peer_id: &PeerId, kind: GossipKind) {
+        self.topic_stats_mut(peer_id, kind)
+            .invalid_message_deliveries += 1.0;
+    }
+
+    /// Restores the `score` of `peer_id` persisted by a previous run of the node.
+    pub fn restore_score(&mut self, peer_id: &PeerId, score: f64) {
+        if score.is_finite() && score.abs() >= DECAY_TO_ZERO {
+            self.restored.insert(peer_id.clone(), score);
+        }
+    }
+
+    /// Returns the score of `peer_id`, which is zero for peers that have not delivered messages.
+    pub fn score(&self, peer_id: &PeerId) -> f64 {
+        let restored = self.restored.get(peer_id).copied().unwrap_or(0.0);
+        self.stats.get(peer_id).map_or(restored, |topics| {
+            topics
+                .iter()
+                .map(|(kind, stats)| {
+                    stats
+                        .score(&TopicScoreParams::for_kind(kind))
+                        .min(TOPIC_SCORE_CAP)
+                })
+                .sum::<f64>()
+                + restored
+        })
+    }
+
+    /// Decays the counters of all peers, forgetting the peers whose counters have all reached
+    /// zero.
+    pub fn decay(&mut self) {
+        self.stats.retain(|_, topics| {
+            topics.retain(|kind, stats| !stats.decay(&TopicScoreParams::for_kind(kind)));
+            !topics.is_empty()
+        });
+        self.restored.retain(|_, score| {
+            *score *= RESTORED_SCORE_DECAY;
+            score.abs() >= DECAY_TO_ZERO
+        });
+    }
+
+    fn topic_stats_mut(&mut self, peer_id: &PeerId, kind: GossipKind) -> &mut TopicStats {
+        self.stats
+            .entry(peer_id.clone())
+            .or_default()
+            .entry(kind)
+            .or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::SubnetId;
+
+    #[test]
+    fn test_invalid_blocks_weigh_heavier_than_invalid_attestations() {
+        let mut scores = GossipScores::default();
+        let block_peer = PeerId::random();
+        let attestation_peer = PeerId::random();
+
+        scores.invalid_message_delivery(&block_peer, GossipKind::BeaconBlock);
+        scores.invalid_message_delivery(
+            &attestation_peer,
+            GossipKind::CommitteeIndex(SubnetId::new(0)),
+        );
+
+        assert!(scores.score(&block_peer) < scores.score(&attestation_peer));
+        assert!(scores.score(&attestation_peer) < 0.0);
+    }
+
+    #[test]
+    fn test_first_deliveries_are_capped() {
+        let mut scores = GossipScores::default();
+        let peer = PeerId::random();
+
+        for _ in 0..1000 {
+            scores.first_message_delivery(&peer, GossipKind::BeaconBlock);
+        }
+
+        let params = TopicScoreParams::for_kind(&GossipKind::BeaconBlock);
+        let max_score = params.first_message_deliveries_cap
+            * params.first_message_deliveries_weight
+            * params.topic_weight;
+        assert_eq!(scores.score(&peer), max_score.min(TOPIC_SCORE_CAP));
+    }
+
+    #[test]
+    fn test_decayed_peers_are_forgotten() {
+        let mut scores = GossipScores::default();
+        let peer = PeerId::random();
+        scores.invalid_message_delivery(&peer, GossipKind::VoluntaryExit);
+
+        let score = scores.score(&peer);
+        scores.decay();
+        assert!(scores.score(&peer) > score);
+
+        for _ in 0..1000 {
+            scores.decay();
+        }
+        assert_eq!(scores.score(&peer), 0.0);
+        assert!(scores.stats.is_empty());
+    }
+
+    #[test]
+    fn test_restored_scores_decay() {
+        let mut scores = GossipScores::default();
+        let peer = PeerId::random();
+        scores.restore_score(&peer, -100.0);
+        assert_eq!(scores.score(&peer), -100.0);
+
+        scores.first_message_delivery(&peer, GossipKind::BeaconBlock);
+        assert!(scores.score(&peer) > -100.0);
+
+        for _ in 0..10_000 {
+            scores.decay();
+        }
+        assert_eq!(scores.score(&peer), 0.0);
+        assert!(scores.restored.is_empty());
+    }
+}
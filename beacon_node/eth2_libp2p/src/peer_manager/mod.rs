@@ -2,37 +2,47 @@
 
 pub use self::peerdb::*;
 use crate::discovery::{Discovery, DiscoveryEvent};
-use crate::rpc::{MetaData, Protocol, RPCError, RPCResponseErrorCode};
+use crate::rpc::{GoodbyeReason, MetaData, Protocol, RPCError, RPCResponseErrorCode};
+use crate::types::GossipKind;
 use crate::{error, metrics};
-use crate::{Enr, EnrExt, NetworkConfig, NetworkGlobals, PeerId};
+use crate::{Enr, EnrExt, MessageAcceptance, NetworkConfig, NetworkGlobals, PeerId};
 use futures::prelude::*;
 use futures::Stream;
+use gossip_score::GossipScores;
 use hashset_delay::HashSetDelay;
 use libp2p::core::multiaddr::Protocol as MProtocol;
 use libp2p::identify::IdentifyInfo;
 use slog::{crit, debug, error};
 use smallvec::SmallVec;
 use std::{
+    collections::{HashMap, HashSet},
     net::SocketAddr,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use types::{EthSpec, SubnetId};
 
 pub use libp2p::core::{identity::Keypair, Multiaddr};
 
 pub mod client;
+mod gossip_score;
 mod peer_info;
 mod peer_sync_status;
 mod peerdb;
 
-pub use peer_info::{PeerConnectionStatus::*, PeerInfo};
+pub use peer_info::{IpVersion, PeerConnectionStatus::*, PeerInfo};
 pub use peer_sync_status::{PeerSyncStatus, SyncInfo};
 /// The minimum reputation before a peer is disconnected.
 // Most likely this needs tweaking.
-const _MIN_REP_BEFORE_BAN: Rep = 10;
+const MIN_REP_BEFORE_BAN: Rep = 10;
+/// The gossip score below which the gossip messages of a peer are ignored.
+pub const GOSSIP_GRAYLIST_THRESHOLD: f64 = -400.0;
+/// The minimum fraction of the target number of peers that should be peers we dialed. Inbound
+/// peers may all be controlled by an attacker attempting to eclipse the node, whereas the peers we
+/// dial are chosen by us from discovery.
+const MIN_OUTBOUND_PEERS_RATIO: f64 = 0.2;
 /// The time in seconds between re-status's peers.
 const STATUS_INTERVAL: u64 = 300;
 /// The time in seconds between PING events. We do not send a ping if the other peer as PING'd us within
@@ -43,6 +53,12 @@ const PING_INTERVAL: u64 = 30;
 /// requests. This defines the interval in seconds.  
 const HEARTBEAT_INTERVAL: u64 = 30;
 
+/// The initial time in seconds to wait before re-dialing a disconnected trusted peer.
+const TRUSTED_PEER_INITIAL_BACKOFF: u64 = 5;
+
+/// The maximum time in seconds to wait before re-dialing a disconnected trusted peer.
+const TRUSTED_PEER_MAX_BACKOFF: u64 = 600;
+
 /// The main struct that handles peer's reputation and connection status.
 pub struct PeerManager<TSpec: EthSpec> {
     /// Storage of network globals to access the `PeerDB`.
@@ -55,6 +71,21 @@ pub struct PeerManager<TSpec: EthSpec> {
     status_peers: HashSetDelay<PeerId>,
     /// The target number of peers we would like to connect to.
     target_peers: usize,
+    /// The maximum number of connected peers. Inbound connections beyond this limit are rejected.
+    max_peers: usize,
+    /// Peers that are never banned or pruned and are re-dialed when disconnected.
+    trusted_peers: HashSet<PeerId>,
+    /// The addresses of trusted peers given on startup.
+    static_addresses: HashMap<PeerId, Vec<Multiaddr>>,
+    /// Disconnected trusted peers awaiting to be re-dialed.
+    redial_peers: HashSetDelay<PeerId>,
+    /// The time to wait before the next re-dial of each disconnected trusted peer.
+    redial_backoffs: HashMap<PeerId, Duration>,
+    /// The scores of the peers on the gossip messages they forwarded to us.
+    gossip_scores: GossipScores,
+    /// The IP version of the address on which a peer was last successfully dialed. Dual-stack
+    /// peers which we have never dialed are first dialed on this IP version.
+    last_dialed_ip_version: Option<IpVersion>,
     /// The discovery service.
     discovery: Discovery<TSpec>,
     /// The heartbeat interval to perform routine maintenance.
@@ -113,8 +144,23 @@ pub enum PeerManagerEvent {
     Ping(PeerId),
     /// Request METADATA from a peer.
     MetaData(PeerId),
-    /// The peer should be disconnected.
-    DisconnectPeer(PeerId),
+    /// The peer should be disconnected, informing it of the reason with a GOODBYE.
+    DisconnectPeer(PeerId, GoodbyeReason),
+}
+
+/// A known peer that is persisted across restarts of the node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PersistedPeer {
+    /// The ENR of the peer.
+    pub enr: Enr,
+    /// The reputation of the peer.
+    pub reputation: Rep,
+    /// The score of the peer on the gossip messages it forwarded to us.
+    pub gossip_score: f64,
+    /// Whether the peer is banned.
+    pub banned: bool,
+    /// The time the peer was last seen, in seconds since the UNIX epoch.
+    pub last_seen: u64,
 }
 
 impl<TSpec: EthSpec> PeerManager<TSpec> {
@@ -133,12 +179,38 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
 
         let heartbeat = tokio::time::interval(tokio::time::Duration::from_secs(HEARTBEAT_INTERVAL));
 
+        // libp2p nodes given with a peer id are trusted and their addresses are kept for re-dialing
+        let mut trusted_peers = config.trusted_peers.iter().cloned().collect::<HashSet<_>>();
+        let mut static_addresses: HashMap<PeerId, Vec<Multiaddr>> = HashMap::new();
+        for multiaddr in &config.libp2p_nodes {
+            let mut address = multiaddr.clone();
+            if let Some(MProtocol::P2p(multihash)) = address.pop() {
+                if let Ok(peer_id) = PeerId::from_multihash(multihash) {
+                    trusted_peers.insert(peer_id.clone());
+                    static_addresses.entry(peer_id).or_default().push(address);
+                }
+            }
+        }
+
+        // dial the trusted peers once discovery has had a chance to find their addresses
+        let mut redial_peers = HashSetDelay::new(Duration::from_secs(TRUSTED_PEER_INITIAL_BACKOFF));
+        for peer_id in &trusted_peers {
+            redial_peers.insert(peer_id.clone());
+        }
+
         Ok(PeerManager {
             network_globals,
             events: SmallVec::new(),
             ping_peers: HashSetDelay::new(Duration::from_secs(PING_INTERVAL)),
             status_peers: HashSetDelay::new(Duration::from_secs(STATUS_INTERVAL)),
-            target_peers: config.max_peers, //TODO: Add support for target peers and max peers
+            target_peers: config.target_peers,
+            max_peers: config.max_peers,
+            trusted_peers,
+            static_addresses,
+            redial_peers,
+            redial_backoffs: HashMap::new(),
+            gossip_scores: GossipScores::default(),
+            last_dialed_ip_version: None,
             discovery,
             heartbeat,
             log: log.clone(),
@@ -173,6 +245,91 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         self.discovery.discover_subnet_peers(subnet_id, min_ttl);
     }
 
+    /// Returns the known peers whose ENR is known, such that they can be persisted across restarts.
+    pub fn persisted_peers(&mut self) -> Vec<PersistedPeer> {
+        let peers = self
+            .network_globals
+            .peers
+            .read()
+            .peers()
+            .filter_map(|(peer_id, info)| {
+                let unseen_for = match info.connection_status {
+                    Connected { .. } => Duration::from_secs(0),
+                    Disconnected { since } | Banned { since } | Dialing { since } => {
+                        since.elapsed()
+                    }
+                    Unknown => return None,
+                };
+                Some((
+                    peer_id.clone(),
+                    info.reputation,
+                    info.gossip_score,
+                    info.connection_status.is_banned(),
+                    unseen_for,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let now = unix_time_secs();
+        peers
+            .into_iter()
+            .filter_map(|(peer_id, reputation, gossip_score, banned, unseen_for)| {
+                Some(PersistedPeer {
+                    enr: self.discovery.enr_of_peer(&peer_id)?,
+                    reputation,
+                    gossip_score,
+                    banned,
+                    last_seen: now.saturating_sub(unseen_for.as_secs()),
+                })
+            })
+            .collect()
+    }
+
+    /// Restores the peers persisted by a previous run of the node. The peers with the best
+    /// reputation are dialed immediately, rather than waiting for discovery to find peers. Banned
+    /// peers remain banned, and the gossip scores of all peers carry over.
+    pub fn restore_peers(&mut self, mut peers: Vec<PersistedPeer>) {
+        peers.sort_by(|a, b| b.reputation.cmp(&a.reputation));
+
+        let now = unix_time_secs();
+        let mut dialing = self.network_globals.connected_or_dialing_peers();
+        for peer in peers {
+            let peer_id = peer.enr.peer_id();
+            // the network may have forked since the peers were persisted
+            if !self.discovery.on_local_fork(&peer.enr) {
+                debug!(self.log, "Not restoring persisted peer on a different fork"; "peer_id" => peer_id.to_string());
+                continue;
+            }
+            let unseen_for = Duration::from_secs(now.saturating_sub(peer.last_seen));
+            let last_seen = Instant::now()
+                .checked_sub(unseen_for)
+                .unwrap_or_else(Instant::now);
+
+            // banned peers are still added to discovery, such that their ENR and ban persist
+            self.discovery.add_enr(peer.enr);
+            let connection_status = if peer.banned {
+                Banned { since: last_seen }
+            } else {
+                Disconnected { since: last_seen }
+            };
+            self.gossip_scores
+                .restore_score(&peer_id, peer.gossip_score);
+            self.network_globals.peers.write().restore_peer(
+                &peer_id,
+                peer.reputation,
+                self.gossip_scores.score(&peer_id),
+                connection_status,
+            );
+
+            if !peer.banned && peer.reputation >= MIN_REP_BEFORE_BAN && dialing < self.target_peers
+            {
+                debug!(self.log, "Dialing persisted peer"; "peer_id" => peer_id.to_string(), "reputation" => peer.reputation);
+                self.events.push(PeerManagerEvent::Dial(peer_id));
+                dialing += 1;
+            }
+        }
+    }
+
     /// A STATUS message has been received from a peer. This resets the status timer.
     pub fn peer_statusd(&mut self, peer_id: &PeerId) {
         self.status_peers.insert(peer_id.clone());
@@ -186,17 +343,39 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         // remove the ping and status timer for the peer
         self.ping_peers.remove(peer_id);
         self.status_peers.remove(peer_id);
+
+        self.schedule_redial(peer_id);
         metrics::inc_counter(&metrics::PEER_DISCONNECT_EVENT_COUNT);
-        metrics::set_gauge(
-            &metrics::PEERS_CONNECTED,
-            self.network_globals.connected_peers() as i64,
-        );
+        self.update_peer_count_metrics();
+    }
+
+    /// A dial to the peer has failed.
+    pub fn notify_dial_failure(&mut self, peer_id: &PeerId) {
+        self.schedule_redial(peer_id);
     }
 
     /// Sets a peer as connected as long as their reputation allows it
     /// Informs if the peer was accepted
     pub fn connect_ingoing(&mut self, peer_id: &PeerId) -> bool {
-        self.connect_peer(peer_id, ConnectingType::IngoingConnected)
+        let accepted = self.connect_peer(peer_id, ConnectingType::IngoingConnected);
+
+        // reject inbound connections beyond the peer limit, or beyond the slots not reserved for
+        // outbound peers, unless the peer is trusted
+        let connected_peers = self.network_globals.connected_peers();
+        let inbound_peers =
+            connected_peers.saturating_sub(self.network_globals.connected_outbound_peers());
+        let max_inbound_peers = self.max_peers.saturating_sub(self.target_outbound_peers());
+        if (connected_peers > self.max_peers || inbound_peers > max_inbound_peers)
+            && !self.trusted_peers.contains(peer_id)
+        {
+            debug!(self.log, "Disconnecting inbound peer, too many peers"; "peer_id" => peer_id.to_string(), "max_peers" => self.max_peers, "inbound_peers" => inbound_peers);
+            self.events.push(PeerManagerEvent::DisconnectPeer(
+                peer_id.clone(),
+                GoodbyeReason::TooManyPeers,
+            ));
+            return false;
+        }
+        accepted
     }
 
     /// Sets a peer as connected as long as their reputation allows it
@@ -205,15 +384,28 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         self.connect_peer(peer_id, ConnectingType::OutgoingConnected)
     }
 
+    /// Records the address on which a peer was successfully dialed, such that its IP version is
+    /// preferred when dialing peers that advertise both IPv4 and IPv6 addresses.
+    pub fn dial_succeeded(&mut self, peer_id: &PeerId, address: &Multiaddr) {
+        let ip_version = match IpVersion::of(address) {
+            Some(ip_version) => ip_version,
+            None => return,
+        };
+        self.last_dialed_ip_version = Some(ip_version);
+        if let Some(info) = self.network_globals.peers.write().peer_info_mut(peer_id) {
+            info.dialed_ip_version = Some(ip_version);
+        }
+    }
+
     /// Updates the database informing that a peer is being dialed.
     pub fn dialing_peer(&mut self, peer_id: &PeerId) -> bool {
         self.connect_peer(peer_id, ConnectingType::Dialing)
     }
 
-    /// Updates the database informing that a peer is being disconnected.
-    pub fn _disconnecting_peer(&mut self, _peer_id: &PeerId) -> bool {
-        // TODO: implement
-        true
+    /// Updates the database informing that a peer is being disconnected, having been sent or
+    /// having sent a GOODBYE.
+    pub fn disconnecting_peer(&mut self, peer_id: &PeerId) {
+        self.network_globals.peers.write().disconnecting(peer_id);
     }
 
     /// Reports a peer for some action.
@@ -222,13 +414,44 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
     pub fn report_peer(&mut self, peer_id: &PeerId, action: PeerAction) {
         //TODO: Check these. There are double disconnects for example
         // self.update_reputations();
-        self.network_globals
-            .peers
-            .write()
-            .add_reputation(peer_id, action.rep_change());
+        let mut pdb = self.network_globals.peers.write();
+        pdb.add_reputation(peer_id, action.rep_change());
+
+        // ban the peer if its reputation has become too low, unless it is trusted
+        if pdb.is_connected(peer_id)
+            && pdb.reputation(peer_id) < MIN_REP_BEFORE_BAN
+            && !self.trusted_peers.contains(peer_id)
+        {
+            debug!(self.log, "Banning peer"; "peer_id" => peer_id.to_string(), "reputation" => pdb.reputation(peer_id));
+            pdb.ban(peer_id);
+            self.events.push(PeerManagerEvent::DisconnectPeer(
+                peer_id.clone(),
+                GoodbyeReason::BadScore,
+            ));
+        }
         // self.update_reputations();
     }
 
+    /// Updates the gossip score of a peer with the validation result of a message it was the
+    /// first to forward to us on the topic of `kind`.
+    pub fn gossip_message_validated(
+        &mut self,
+        peer_id: &PeerId,
+        kind: GossipKind,
+        validation_result: &MessageAcceptance,
+    ) {
+        match validation_result {
+            MessageAcceptance::Accept => self.gossip_scores.first_message_delivery(peer_id, kind),
+            MessageAcceptance::Reject => self.gossip_scores.invalid_message_delivery(peer_id, kind),
+            MessageAcceptance::Ignore => return,
+        }
+
+        let score = self.gossip_scores.score(peer_id);
+        if let Some(info) = self.network_globals.peers.write().peer_info_mut(peer_id) {
+            info.gossip_score = score;
+        }
+    }
+
     /// Updates `PeerInfo` with `identify` information.
     pub fn identify(&mut self, peer_id: &PeerId, info: &IdentifyInfo) {
         if let Some(peer_info) = self.network_globals.peers.write().peer_info_mut(peer_id) {
@@ -293,6 +516,10 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
                 Protocol::Status => return,
             },
             RPCError::NegotiationTimeout => PeerAction::HighToleranceError,
+            RPCError::RateLimited => {
+                // The peer is requesting more than its quota allows
+                PeerAction::MidToleranceError
+            }
         };
 
         self.report_peer(peer_id, peer_action);
@@ -381,7 +608,30 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
     }
 
     // Handles the libp2p request to obtain multiaddrs for peer_id's in order to dial them.
+    //
+    // The addresses are dialed in order, so the addresses of the IP version on which the peer was
+    // last dialed, or on which any peer was last dialed, are given first.
     pub fn addresses_of_peer(&mut self, peer_id: &PeerId) -> Vec<Multiaddr> {
+        let mut addresses = self.known_addresses_of_peer(peer_id);
+
+        let preferred_ip_version = self
+            .network_globals
+            .peers
+            .read()
+            .peer_info(peer_id)
+            .and_then(|info| info.dialed_ip_version)
+            .or(self.last_dialed_ip_version);
+        if let Some(preferred_ip_version) = preferred_ip_version {
+            addresses.sort_by_key(|address| IpVersion::of(address) != Some(preferred_ip_version));
+        }
+
+        addresses
+    }
+
+    /* Internal functions */
+
+    /// Returns the addresses of a peer from its ENR, or those given on startup if it is trusted.
+    fn known_addresses_of_peer(&mut self, peer_id: &PeerId) -> Vec<Multiaddr> {
         if let Some(enr) = self.discovery.enr_of_peer(peer_id) {
             // ENR's may have multiple Multiaddrs. The multi-addr associated with the UDP
             // port is removed, which is assumed to be associated with the discv5 protocol (and
@@ -397,14 +647,15 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
             });
 
             out_list
+        } else if let Some(addresses) = self.static_addresses.get(peer_id) {
+            // the addresses of a trusted peer given on startup
+            addresses.clone()
         } else {
             // PeerId is not known
             Vec::new()
         }
     }
 
-    /* Internal functions */
-
     // The underlying discovery server has updated our external IP address. We send this up to
     // notify libp2p.
     fn socket_updated(&mut self, socket: SocketAddr) {
@@ -416,18 +667,39 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         self.events.push(PeerManagerEvent::SocketUpdated(multiaddr));
     }
 
+    /// Schedules a re-dial of a disconnected trusted peer, backing off exponentially.
+    fn schedule_redial(&mut self, peer_id: &PeerId) {
+        if !self.trusted_peers.contains(peer_id) || self.redial_peers.contains(peer_id) {
+            return;
+        }
+
+        let backoff = self
+            .redial_backoffs
+            .entry(peer_id.clone())
+            .or_insert_with(|| Duration::from_secs(TRUSTED_PEER_INITIAL_BACKOFF));
+        debug!(self.log, "Scheduling re-dial of trusted peer"; "peer_id" => peer_id.to_string(), "backoff_secs" => backoff.as_secs());
+        self.redial_peers.insert_at(peer_id.clone(), *backoff);
+        *backoff = std::cmp::min(*backoff * 2, Duration::from_secs(TRUSTED_PEER_MAX_BACKOFF));
+    }
+
     /// Peers that have been returned by discovery requests are dialed here if they are suitable.
     ///
     /// NOTE: By dialing `PeerId`s and not multiaddrs, libp2p requests the multiaddr associated
     /// with a new `PeerId` which involves a discovery routing table lookup. We could dial the
     /// multiaddr here, however this could relate to duplicate PeerId's etc. If the lookup
     /// proves resource constraining, we should switch to multiaddr dialling here.
+    ///
+    /// Peers are dialed beyond the target number of peers if too few of our peers are outbound,
+    /// in which case excess inbound peers are later pruned.
     fn peers_discovered(&mut self, peers: Vec<Enr>, min_ttl: Option<Instant>) {
+        let mut outbound_deficit = self.outbound_peers_deficit();
+
         for enr in peers {
             let peer_id = enr.peer_id();
 
             // if we need more peers, attempt a connection
-            if self.network_globals.connected_or_dialing_peers() < self.target_peers
+            if (self.network_globals.connected_or_dialing_peers() < self.target_peers
+                || outbound_deficit > 0)
                 && !self
                     .network_globals
                     .peers
@@ -446,17 +718,47 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
                         .update_min_ttl(&peer_id, min_ttl);
                 }
                 self.events.push(PeerManagerEvent::Dial(peer_id));
+                outbound_deficit = outbound_deficit.saturating_sub(1);
             }
         }
     }
 
+    /// The number of outbound peers we aim to maintain.
+    fn target_outbound_peers(&self) -> usize {
+        (self.target_peers as f64 * MIN_OUTBOUND_PEERS_RATIO).ceil() as usize
+    }
+
+    /// The number of peers we need to dial to reach the target number of outbound peers, counting
+    /// the peers being dialed.
+    fn outbound_peers_deficit(&self) -> usize {
+        let pdb = self.network_globals.peers.read();
+        let outbound_or_dialing = pdb.connected_outbound_peer_ids().count()
+            + pdb
+                .peers()
+                .filter(|(_, info)| info.connection_status.is_dialing())
+                .count();
+        self.target_outbound_peers()
+            .saturating_sub(outbound_or_dialing)
+    }
+
+    /// Updates the metrics of the number of connected peers.
+    fn update_peer_count_metrics(&self) {
+        let connected_peers = self.network_globals.connected_peers();
+        let outbound_peers = self.network_globals.connected_outbound_peers();
+        metrics::set_gauge(&metrics::PEERS_CONNECTED, connected_peers as i64);
+        metrics::set_gauge(
+            &metrics::PEERS_CONNECTED_INBOUND,
+            connected_peers.saturating_sub(outbound_peers) as i64,
+        );
+        metrics::set_gauge(&metrics::PEERS_CONNECTED_OUTBOUND, outbound_peers as i64);
+    }
+
     /// Registers a peer as connected. The `ingoing` parameter determines if the peer is being
     /// dialed or connecting to us.
     ///
     /// This is called by `connect_ingoing` and `connect_outgoing`.
     ///
     /// This informs if the peer was accepted in to the db or not.
-    // TODO: Drop peers if over max_peer limit
     fn connect_peer(&mut self, peer_id: &PeerId, connection: ConnectingType) -> bool {
         // TODO: remove after timed updates
         //self.update_reputations();
@@ -472,21 +774,31 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
 
             match connection {
                 ConnectingType::Dialing => peerdb.dialing_peer(peer_id),
-                ConnectingType::IngoingConnected => peerdb.connect_outgoing(peer_id),
-                ConnectingType::OutgoingConnected => peerdb.connect_ingoing(peer_id),
+                ConnectingType::IngoingConnected => peerdb.connect_ingoing(peer_id),
+                ConnectingType::OutgoingConnected => peerdb.connect_outgoing(peer_id),
             }
         }
 
+        // a connected trusted peer no longer needs to be re-dialed
+        if let ConnectingType::IngoingConnected | ConnectingType::OutgoingConnected = connection {
+            self.redial_peers.remove(peer_id);
+            self.redial_backoffs.remove(peer_id);
+        }
+
         // start a ping and status timer for the peer
         self.ping_peers.insert(peer_id.clone());
         self.status_peers.insert(peer_id.clone());
 
+        // request the meta-data of newly connected peers, such that their subnets are known
+        // before the first ping
+        if let ConnectingType::IngoingConnected | ConnectingType::OutgoingConnected = connection {
+            self.events
+                .push(PeerManagerEvent::MetaData(peer_id.clone()));
+        }
+
         // increment prometheus metrics
         metrics::inc_counter(&metrics::PEER_CONNECT_EVENT_COUNT);
-        metrics::set_gauge(
-            &metrics::PEERS_CONNECTED,
-            self.network_globals.connected_peers() as i64,
-        );
+        self.update_peer_count_metrics();
 
         true
     }
@@ -519,7 +831,7 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         /* Check how long have peers been in this state and update their reputations if needed */
         let mut pdb = self.network_globals.peers.write();
 
-        for (id, info) in pdb._peers_mut() {
+        for (id, info) in pdb.peers_mut() {
             // Update reputations
             match info.connection_status {
                 Connected { .. } => {
@@ -563,10 +875,10 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
                 Unknown => {} //TODO: Handle this case
             }
             // Check if the peer gets banned or unbanned and if it should be disconnected
-            if info.reputation < _MIN_REP_BEFORE_BAN && !info.connection_status.is_banned() {
+            if info.reputation < MIN_REP_BEFORE_BAN && !info.connection_status.is_banned() {
                 // This peer gets banned. Check if we should request disconnection
                 ban_queue.push(id.clone());
-            } else if info.reputation >= _MIN_REP_BEFORE_BAN && info.connection_status.is_banned() {
+            } else if info.reputation >= MIN_REP_BEFORE_BAN && info.connection_status.is_banned() {
                 // This peer gets unbanned
                 unban_queue.push(id.clone());
             }
@@ -597,16 +909,87 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         // TODO: Provide a back-off time for discovery queries. I.e Queue many initially, then only
         // perform discoveries over a larger fixed interval. Perhaps one every 6 heartbeats
         let peer_count = self.network_globals.connected_or_dialing_peers();
-        if peer_count < self.target_peers {
-            // If we need more peers, queue a discovery lookup.
+        if peer_count < self.target_peers || self.outbound_peers_deficit() > 0 {
+            // If we need more peers, or more outbound peers, queue a discovery lookup.
             self.discovery.discover_peers();
         }
 
-        // TODO: If we have too many peers, remove peers that are not required for subnet
-        // validation.
+        self.update_peer_count_metrics();
+
+        // If we have too many peers, disconnect the least useful ones.
+        self.prune_excess_peers();
+
+        self.decay_gossip_scores();
 
         // TODO: Perform peer reputation maintenance here
     }
+
+    /// Decays the gossip scores of all peers and updates them in the `PeerDB`.
+    fn decay_gossip_scores(&mut self) {
+        self.gossip_scores.decay();
+
+        let mut pdb = self.network_globals.peers.write();
+        for (peer_id, info) in pdb.peers_mut() {
+            info.gossip_score = self.gossip_scores.score(peer_id);
+        }
+    }
+
+    /// Disconnects the least useful peers until the number of connected peers no longer exceeds
+    /// the target.
+    ///
+    /// Peers required for our attestation subnets are kept over peers that are not, peers on our
+    /// chain are kept over peers that are behind or whose status is unknown, and peers with a
+    /// good reputation are kept over peers with a bad one. Outbound peers are not pruned if that
+    /// would leave fewer outbound peers than targeted.
+    fn prune_excess_peers(&mut self) {
+        let pdb = self.network_globals.peers.read();
+        let excess = pdb
+            .connected_peer_ids()
+            .count()
+            .saturating_sub(self.target_peers);
+        if excess == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut peers = pdb
+            .connected_peers()
+            .filter(|(peer_id, _)| !self.trusted_peers.contains(peer_id))
+            .map(|(peer_id, info)| {
+                let on_subnet = info.min_ttl.map(|min_ttl| min_ttl > now).unwrap_or(false);
+                let on_chain = info.sync_status.is_synced() || info.sync_status.is_advanced();
+                let outbound = info.connection_status.is_outbound();
+                (
+                    (on_subnet, on_chain, info.reputation),
+                    outbound,
+                    peer_id.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        peers.sort_by_key(|(usefulness, _, _)| *usefulness);
+
+        let target_outbound_peers = self.target_outbound_peers();
+        let mut outbound_peers = pdb.connected_outbound_peer_ids().count();
+        let mut pruned = 0;
+        for (_, outbound, peer_id) in peers {
+            if pruned == excess {
+                break;
+            }
+            if outbound {
+                if outbound_peers <= target_outbound_peers {
+                    continue;
+                }
+                outbound_peers -= 1;
+            }
+            pruned += 1;
+
+            debug!(self.log, "Pruning excess peer"; "peer_id" => peer_id.to_string(), "outbound" => outbound);
+            self.events.push(PeerManagerEvent::DisconnectPeer(
+                peer_id,
+                GoodbyeReason::TooManyPeers,
+            ));
+        }
+    }
 }
 
 impl<TSpec: EthSpec> Stream for PeerManager<TSpec> {
@@ -655,6 +1038,27 @@ impl<TSpec: EthSpec> Stream for PeerManager<TSpec> {
             }
         }
 
+        // re-dial disconnected trusted peers
+        loop {
+            match self.redial_peers.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(peer_id))) => {
+                    if !self
+                        .network_globals
+                        .peers
+                        .read()
+                        .is_connected_or_dialing(&peer_id)
+                    {
+                        debug!(self.log, "Re-dialing trusted peer"; "peer_id" => peer_id.to_string());
+                        self.events.push(PeerManagerEvent::Dial(peer_id));
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    error!(self.log, "Failed to check for trusted peers to re-dial"; "error" => format!("{}",e))
+                }
+                Poll::Ready(None) | Poll::Pending => break,
+            }
+        }
+
         if !self.events.is_empty() {
             return Poll::Ready(Some(self.events.remove(0)));
         } else {
@@ -665,6 +1069,14 @@ impl<TSpec: EthSpec> Stream for PeerManager<TSpec> {
     }
 }
 
+/// Returns the current time in seconds since the UNIX epoch.
+fn unix_time_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
 enum ConnectingType {
     /// We are in the process of dialing this peer.
     Dialing,
@@ -3,6 +3,7 @@ use super::peerdb::{Rep, DEFAULT_REPUTATION};
 use super::PeerSyncStatus;
 use crate::rpc::MetaData;
 use crate::Multiaddr;
+use libp2p::core::multiaddr::Protocol;
 use serde::{
     ser::{SerializeStructVariant, Serializer},
     Serialize,
@@ -19,12 +20,20 @@ pub struct PeerInfo<T: EthSpec> {
     _status: PeerStatus,
     /// The peers reputation
     pub reputation: Rep,
+    /// The score of the peer on the gossip messages it forwarded to us.
+    pub gossip_score: f64,
     /// Client managing this peer
     pub client: Client,
     /// Connection status of this peer
     pub connection_status: PeerConnectionStatus,
+    /// Whether the peer is still connected but being disconnected, a GOODBYE having been sent to
+    /// or received from it.
+    pub disconnecting: bool,
     /// The known listening addresses of this peer.
     pub listening_addresses: Vec<Multiaddr>,
+    /// The IP version of the address on which we last successfully dialed this peer, which is
+    /// tried first when dialing the peer again.
+    pub dialed_ip_version: Option<IpVersion>,
     /// The current syncing state of the peer. The state may be determined after it's initial
     /// connection.
     pub sync_status: PeerSyncStatus,
@@ -42,9 +51,12 @@ impl<TSpec: EthSpec> Default for PeerInfo<TSpec> {
         PeerInfo {
             _status: Default::default(),
             reputation: DEFAULT_REPUTATION,
+            gossip_score: 0.0,
             client: Client::default(),
             connection_status: Default::default(),
+            disconnecting: false,
             listening_addresses: vec![],
+            dialed_ip_version: None,
             sync_status: PeerSyncStatus::Unknown,
             meta_data: None,
             min_ttl: None,
@@ -65,6 +77,26 @@ impl<T: EthSpec> PeerInfo<T> {
     }
 }
 
+/// The IP version of an address.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum IpVersion {
+    /// An IPv4 address.
+    V4,
+    /// An IPv6 address.
+    V6,
+}
+
+impl IpVersion {
+    /// Returns the IP version of `address`, or `None` if it does not start with an IP address.
+    pub fn of(address: &Multiaddr) -> Option<Self> {
+        match address.iter().next() {
+            Some(Protocol::Ip4(_)) => Some(IpVersion::V4),
+            Some(Protocol::Ip6(_)) => Some(IpVersion::V6),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 /// The current health status of the peer.
 pub enum PeerStatus {
@@ -154,6 +186,15 @@ impl PeerConnectionStatus {
         }
     }
 
+    /// Checks if the status is connected with at least one outgoing connection, i.e., we dialed
+    /// the peer
+    pub fn is_outbound(&self) -> bool {
+        match self {
+            PeerConnectionStatus::Connected { n_out, .. } => *n_out > 0,
+            _ => false,
+        }
+    }
+
     /// Checks if the status is connected
     pub fn is_dialing(&self) -> bool {
         match self {
@@ -71,13 +71,20 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
             .map_or(DEFAULT_REPUTATION, |info| info.reputation)
     }
 
+    /// Gives the gossip score of a peer, or zero if it is unknown.
+    pub fn gossip_score(&self, peer_id: &PeerId) -> f64 {
+        self.peers
+            .get(peer_id)
+            .map_or(0.0, |info| info.gossip_score)
+    }
+
     /// Returns an iterator over all peers in the db.
     pub fn peers(&self) -> impl Iterator<Item = (&PeerId, &PeerInfo<TSpec>)> {
         self.peers.iter()
     }
 
     /// Returns an iterator over all peers in the db.
-    pub(super) fn _peers_mut(&mut self) -> impl Iterator<Item = (&PeerId, &mut PeerInfo<TSpec>)> {
+    pub(super) fn peers_mut(&mut self) -> impl Iterator<Item = (&PeerId, &mut PeerInfo<TSpec>)> {
         self.peers.iter_mut()
     }
 
@@ -129,6 +136,14 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
             .map(|(peer_id, _)| peer_id)
     }
 
+    /// Gives the ids of all connected peers that we dialed.
+    pub fn connected_outbound_peer_ids(&self) -> impl Iterator<Item = &PeerId> {
+        self.peers
+            .iter()
+            .filter(|(_, info)| info.connection_status.is_outbound())
+            .map(|(peer_id, _)| peer_id)
+    }
+
     /// Connected or dialing peers
     pub fn connected_or_dialing_peers(&self) -> impl Iterator<Item = &PeerId> {
         self.peers
@@ -238,6 +253,7 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
         if info.connection_status.is_disconnected() {
             self.n_dc = self.n_dc.saturating_sub(1);
         }
+        info.disconnecting = false;
         info.connection_status = PeerConnectionStatus::Dialing {
             since: Instant::now(),
         };
@@ -299,6 +315,15 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
         info.connection_status.connect_outgoing();
     }
 
+    /// Marks a connected peer as being disconnected, until its connections are closed.
+    pub fn disconnecting(&mut self, peer_id: &PeerId) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            if info.connection_status.is_connected() {
+                info.disconnecting = true;
+            }
+        }
+    }
+
     /// Sets the peer as disconnected. A banned peer remains banned
     pub fn disconnect(&mut self, peer_id: &PeerId) {
         let log_ref = &self.log;
@@ -307,6 +332,7 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
                 "peer_id" => peer_id.to_string());
             PeerInfo::default()
         });
+        info.disconnecting = false;
         if !info.connection_status.is_disconnected() && !info.connection_status.is_banned() {
             info.connection_status.disconnect();
             self.n_dc += 1;
@@ -345,6 +371,30 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
         info.connection_status.ban();
     }
 
+    /// Adds a peer known from a previous run of the node as disconnected or banned, unless the
+    /// peer is already known.
+    pub fn restore_peer(
+        &mut self,
+        peer_id: &PeerId,
+        reputation: Rep,
+        gossip_score: f64,
+        connection_status: PeerConnectionStatus,
+    ) {
+        if let Entry::Vacant(entry) = self.peers.entry(peer_id.clone()) {
+            let disconnected = connection_status.is_disconnected();
+            entry.insert(PeerInfo {
+                reputation,
+                gossip_score,
+                connection_status,
+                ..Default::default()
+            });
+            if disconnected {
+                self.n_dc += 1;
+                self.shrink_to_fit();
+            }
+        }
+    }
+
     /// Add the meta data of a peer.
     pub fn add_metadata(&mut self, peer_id: &PeerId, meta_data: MetaData<TSpec>) {
         if let Some(peer_info) = self.peers.get_mut(peer_id) {
@@ -491,6 +541,24 @@ mod tests {
         assert_eq!(pdb.reputation(&random_peer), Rep::max_value());
     }
 
+    #[test]
+    fn test_connected_outbound_peers() {
+        let mut pdb = get_db();
+
+        let inbound_peer = PeerId::random();
+        let outbound_peer = PeerId::random();
+        let dialing_peer = PeerId::random();
+        pdb.connect_ingoing(&inbound_peer);
+        pdb.connect_outgoing(&outbound_peer);
+        pdb.dialing_peer(&dialing_peer);
+
+        let outbound_peers = pdb.connected_outbound_peer_ids().collect::<Vec<_>>();
+        assert_eq!(outbound_peers, vec![&outbound_peer]);
+
+        pdb.disconnect(&outbound_peer);
+        assert_eq!(pdb.connected_outbound_peer_ids().count(), 0);
+    }
+
     #[test]
     fn test_disconnected_are_bounded() {
         let mut pdb = get_db();
@@ -578,4 +646,26 @@ mod tests {
         pdb.disconnect(&random_peer);
         assert_eq!(pdb.n_dc, pdb.disconnected_peers().count());
     }
+
+    #[test]
+    fn test_disconnecting_peer() {
+        let mut pdb = get_db();
+        let random_peer = PeerId::random();
+
+        // an unknown peer cannot be disconnecting
+        pdb.disconnecting(&random_peer);
+        assert!(pdb.peer_info(&random_peer).is_none());
+
+        pdb.connect_ingoing(&random_peer);
+        pdb.disconnecting(&random_peer);
+        let info = pdb.peer_info(&random_peer).unwrap();
+        assert!(info.connection_status.is_connected());
+        assert!(info.disconnecting);
+
+        pdb.disconnect(&random_peer);
+        assert!(!pdb.peer_info(&random_peer).unwrap().disconnecting);
+        // a disconnected peer is not disconnecting
+        pdb.disconnecting(&random_peer);
+        assert!(!pdb.peer_info(&random_peer).unwrap().disconnecting);
+    }
 }
@@ -138,7 +138,7 @@ where
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         // if we have only received the response code, wait for more bytes
-        if src.len() <= 1 {
+        if self.current_response_code.is_none() && src.len() <= 1 {
             return Ok(None);
         }
         // using the response code determine which kind of payload needs to be decoded.
@@ -178,6 +178,7 @@ mod tests {
     use super::super::ssz_snappy::*;
     use super::*;
     use crate::rpc::protocol::*;
+    use types::{BeaconBlock, Signature, SignedBeaconBlock, Slot};
 
     #[test]
     fn test_decode_status_message() {
@@ -214,4 +215,96 @@ mod tests {
         let _ = dbg!(snappy_decoded_chunk);
         let _ = dbg!(ssz_decoded_chunk);
     }
+
+    type Spec = types::MainnetEthSpec;
+
+    fn snappy_codecs(
+        protocol: Protocol,
+    ) -> (
+        BaseInboundCodec<SSZSnappyInboundCodec<Spec>, Spec>,
+        BaseOutboundCodec<SSZSnappyOutboundCodec<Spec>, Spec>,
+    ) {
+        let protocol_id = ProtocolId::new(protocol, Version::V1, Encoding::SSZSnappy);
+        (
+            BaseInboundCodec::new(SSZSnappyInboundCodec::new(protocol_id.clone(), 1_048_576)),
+            BaseOutboundCodec::new(SSZSnappyOutboundCodec::new(protocol_id, 1_048_576)),
+        )
+    }
+
+    #[test]
+    fn decode_blocks_by_range_chunk_by_chunk() {
+        let (mut inbound_codec, mut outbound_codec) = snappy_codecs(Protocol::BlocksByRange);
+        let spec = Spec::default_spec();
+
+        // Encode each block as its own chunk, remembering where each chunk ends.
+        let mut stream = vec![];
+        let mut chunk_ends = vec![];
+        for slot in 1..=3 {
+            let mut block = BeaconBlock::empty(&spec);
+            block.slot = Slot::new(slot);
+            let response = RPCCodedResponse::Success(RPCResponse::BlocksByRange(Box::new(
+                SignedBeaconBlock {
+                    message: block,
+                    signature: Signature::empty_signature(),
+                },
+            )));
+
+            let mut chunk = BytesMut::new();
+            inbound_codec.encode(response, &mut chunk).unwrap();
+            stream.extend_from_slice(&chunk);
+            chunk_ends.push(stream.len());
+        }
+
+        // Each block should be decoded as soon as the last byte of its chunk arrives.
+        let mut buf = BytesMut::new();
+        let mut decoded_ends = vec![];
+        let mut decoded_slots = vec![];
+        for (i, byte) in stream.iter().enumerate() {
+            buf.extend_from_slice(&[*byte]);
+            match outbound_codec.decode(&mut buf).unwrap() {
+                Some(RPCCodedResponse::Success(RPCResponse::BlocksByRange(block))) => {
+                    decoded_ends.push(i + 1);
+                    decoded_slots.push(block.message.slot);
+                    assert!(buf.is_empty(), "chunk should be consumed once decoded");
+                }
+                Some(other) => panic!("unexpected response: {:?}", other),
+                None => (),
+            }
+        }
+
+        assert_eq!(decoded_ends, chunk_ends);
+        assert_eq!(
+            decoded_slots,
+            vec![Slot::new(1), Slot::new(2), Slot::new(3)]
+        );
+    }
+
+    #[test]
+    fn decode_chunk_spanning_many_snappy_frames() {
+        let (mut inbound_codec, mut outbound_codec) = snappy_codecs(Protocol::BlocksByRange);
+
+        // An error message larger than a snappy frame is split across several frames.
+        let message = "error".repeat(50_000);
+        let mut stream = BytesMut::new();
+        inbound_codec
+            .encode(RPCCodedResponse::ServerError(message.clone()), &mut stream)
+            .unwrap();
+
+        let mut buf = BytesMut::new();
+        let mut decoded = vec![];
+        for piece in stream.chunks(1_000) {
+            buf.extend_from_slice(piece);
+            if let Some(response) = outbound_codec.decode(&mut buf).unwrap() {
+                decoded.push(response);
+            }
+        }
+
+        assert!(buf.is_empty());
+        match decoded.as_slice() {
+            [RPCCodedResponse::ServerError(decoded_message)] => {
+                assert_eq!(decoded_message, &message)
+            }
+            other => panic!("unexpected responses: {:?}", other),
+        }
+    }
 }
@@ -5,11 +5,10 @@ use crate::rpc::{
 };
 use crate::rpc::{RPCCodedResponse, RPCRequest, RPCResponse};
 use libp2p::bytes::BytesMut;
+use snap::raw::max_compress_len;
 use snap::read::FrameDecoder;
 use snap::write::FrameEncoder;
 use ssz::{Decode, Encode};
-use std::io::Cursor;
-use std::io::ErrorKind;
 use std::io::{Read, Write};
 use std::marker::PhantomData;
 use tokio_util::codec::{Decoder, Encoder};
@@ -21,7 +20,8 @@ use unsigned_varint::codec::Uvi;
 pub struct SSZSnappyInboundCodec<TSpec: EthSpec> {
     protocol: ProtocolId,
     inner: Uvi<usize>,
-    len: Option<usize>,
+    /// Decompresses the chunk currently being received, once its length prefix is known.
+    chunk: Option<ChunkDecoder>,
     /// Maximum bytes that can be sent in one req/resp chunked responses.
     max_packet_size: usize,
     phantom: PhantomData<TSpec>,
@@ -36,7 +36,7 @@ impl<T: EthSpec> SSZSnappyInboundCodec<T> {
         SSZSnappyInboundCodec {
             inner: uvi_codec,
             protocol,
-            len: None,
+            chunk: None,
             phantom: PhantomData,
             max_packet_size,
         }
@@ -95,75 +95,46 @@ impl<TSpec: EthSpec> Decoder for SSZSnappyInboundCodec<TSpec> {
     type Error = RPCError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if self.len.is_none() {
-            // Decode the length of the uncompressed bytes from an unsigned varint
-            match self.inner.decode(src).map_err(RPCError::from)? {
-                Some(length) => {
-                    self.len = Some(length);
-                }
-                None => return Ok(None), // need more bytes to decode length
-            }
-        };
-
-        let length = self.len.expect("length should be Some");
+        let decoded_buffer =
+            match decode_chunk(&mut self.inner, &mut self.chunk, self.max_packet_size, src)? {
+                Some(decoded_buffer) => decoded_buffer,
+                None => return Ok(None),
+            };
 
-        // Should not attempt to decode rpc chunks with length > max_packet_size
-        if length > self.max_packet_size {
-            return Err(RPCError::InvalidData);
-        }
-        let mut reader = FrameDecoder::new(Cursor::new(&src));
-        let mut decoded_buffer = vec![0; length];
-
-        match reader.read_exact(&mut decoded_buffer) {
-            Ok(()) => {
-                // `n` is how many bytes the reader read in the compressed stream
-                let n = reader.get_ref().position();
-                self.len = None;
-                let _read_bytes = src.split_to(n as usize);
-                match self.protocol.message_name {
-                    Protocol::Status => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCRequest::Status(StatusMessage::from_ssz_bytes(
-                            &decoded_buffer,
-                        )?))),
-                    },
-                    Protocol::Goodbye => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCRequest::Goodbye(
-                            GoodbyeReason::from_ssz_bytes(&decoded_buffer)?,
-                        ))),
-                    },
-                    Protocol::BlocksByRange => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCRequest::BlocksByRange(
-                            BlocksByRangeRequest::from_ssz_bytes(&decoded_buffer)?,
-                        ))),
-                    },
-                    Protocol::BlocksByRoot => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCRequest::BlocksByRoot(BlocksByRootRequest {
-                            block_roots: Vec::from_ssz_bytes(&decoded_buffer)?,
-                        }))),
-                    },
-                    Protocol::Ping => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCRequest::Ping(Ping::from_ssz_bytes(
-                            &decoded_buffer,
-                        )?))),
-                    },
-                    Protocol::MetaData => match self.protocol.version {
-                        Version::V1 => {
-                            if decoded_buffer.len() > 0 {
-                                Err(RPCError::InvalidData)
-                            } else {
-                                Ok(Some(RPCRequest::MetaData(PhantomData)))
-                            }
-                        }
-                    },
-                }
-            }
-            Err(e) => match e.kind() {
-                // Haven't received enough bytes to decode yet
-                // TODO: check if this is the only Error variant where we return `Ok(None)`
-                ErrorKind::UnexpectedEof => {
-                    return Ok(None);
+        match self.protocol.message_name {
+            Protocol::Status => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCRequest::Status(StatusMessage::from_ssz_bytes(
+                    &decoded_buffer,
+                )?))),
+            },
+            Protocol::Goodbye => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCRequest::Goodbye(GoodbyeReason::from_ssz_bytes(
+                    &decoded_buffer,
+                )?))),
+            },
+            Protocol::BlocksByRange => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCRequest::BlocksByRange(
+                    BlocksByRangeRequest::from_ssz_bytes(&decoded_buffer)?,
+                ))),
+            },
+            Protocol::BlocksByRoot => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCRequest::BlocksByRoot(BlocksByRootRequest {
+                    block_roots: Vec::from_ssz_bytes(&decoded_buffer)?,
+                }))),
+            },
+            Protocol::Ping => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCRequest::Ping(Ping::from_ssz_bytes(
+                    &decoded_buffer,
+                )?))),
+            },
+            Protocol::MetaData => match self.protocol.version {
+                Version::V1 => {
+                    if decoded_buffer.len() > 0 {
+                        Err(RPCError::InvalidData)
+                    } else {
+                        Ok(Some(RPCRequest::MetaData(PhantomData)))
+                    }
                 }
-                _ => return Err(e).map_err(RPCError::from),
             },
         }
     }
@@ -172,7 +143,8 @@ impl<TSpec: EthSpec> Decoder for SSZSnappyInboundCodec<TSpec> {
 /* Outbound Codec: Codec for initiating RPC requests */
 pub struct SSZSnappyOutboundCodec<TSpec: EthSpec> {
     inner: Uvi<usize>,
-    len: Option<usize>,
+    /// Decompresses the chunk currently being received, once its length prefix is known.
+    chunk: Option<ChunkDecoder>,
     protocol: ProtocolId,
     /// Maximum bytes that can be sent in one req/resp chunked responses.
     max_packet_size: usize,
@@ -189,7 +161,7 @@ impl<TSpec: EthSpec> SSZSnappyOutboundCodec<TSpec> {
             inner: uvi_codec,
             protocol,
             max_packet_size,
-            len: None,
+            chunk: None,
             phantom: PhantomData,
         }
     }
@@ -241,69 +213,41 @@ impl<TSpec: EthSpec> Decoder for SSZSnappyOutboundCodec<TSpec> {
     type Error = RPCError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if self.len.is_none() {
-            // Decode the length of the uncompressed bytes from an unsigned varint
-            match self.inner.decode(src).map_err(RPCError::from)? {
-                Some(length) => {
-                    self.len = Some(length as usize);
-                }
-                None => return Ok(None), // need more bytes to decode length
-            }
-        };
-
-        let length = self.len.expect("length should be Some");
+        let decoded_buffer =
+            match decode_chunk(&mut self.inner, &mut self.chunk, self.max_packet_size, src)? {
+                Some(decoded_buffer) => decoded_buffer,
+                None => return Ok(None),
+            };
 
-        // Should not attempt to decode rpc chunks with length > max_packet_size
-        if length > self.max_packet_size {
-            return Err(RPCError::InvalidData);
-        }
-        let mut reader = FrameDecoder::new(Cursor::new(&src));
-        let mut decoded_buffer = vec![0; length];
-        match reader.read_exact(&mut decoded_buffer) {
-            Ok(()) => {
-                // `n` is how many bytes the reader read in the compressed stream
-                let n = reader.get_ref().position();
-                self.len = None;
-                let _read_byts = src.split_to(n as usize);
-                match self.protocol.message_name {
-                    Protocol::Status => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCResponse::Status(
-                            StatusMessage::from_ssz_bytes(&decoded_buffer)?,
-                        ))),
-                    },
-                    Protocol::Goodbye => {
-                        // Goodbye does not have a response
-                        Err(RPCError::InvalidData)
-                    }
-                    Protocol::BlocksByRange => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCResponse::BlocksByRange(Box::new(
-                            SignedBeaconBlock::from_ssz_bytes(&decoded_buffer)?,
-                        )))),
-                    },
-                    Protocol::BlocksByRoot => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCResponse::BlocksByRoot(Box::new(
-                            SignedBeaconBlock::from_ssz_bytes(&decoded_buffer)?,
-                        )))),
-                    },
-                    Protocol::Ping => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCResponse::Pong(Ping {
-                            data: u64::from_ssz_bytes(&decoded_buffer)?,
-                        }))),
-                    },
-                    Protocol::MetaData => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCResponse::MetaData(MetaData::from_ssz_bytes(
-                            &decoded_buffer,
-                        )?))),
-                    },
-                }
+        match self.protocol.message_name {
+            Protocol::Status => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCResponse::Status(StatusMessage::from_ssz_bytes(
+                    &decoded_buffer,
+                )?))),
+            },
+            Protocol::Goodbye => {
+                // Goodbye does not have a response
+                Err(RPCError::InvalidData)
             }
-            Err(e) => match e.kind() {
-                // Haven't received enough bytes to decode yet
-                // TODO: check if this is the only Error variant where we return `Ok(None)`
-                ErrorKind::UnexpectedEof => {
-                    return Ok(None);
-                }
-                _ => return Err(e).map_err(RPCError::from),
+            Protocol::BlocksByRange => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCResponse::BlocksByRange(Box::new(
+                    SignedBeaconBlock::from_ssz_bytes(&decoded_buffer)?,
+                )))),
+            },
+            Protocol::BlocksByRoot => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCResponse::BlocksByRoot(Box::new(
+                    SignedBeaconBlock::from_ssz_bytes(&decoded_buffer)?,
+                )))),
+            },
+            Protocol::Ping => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCResponse::Pong(Ping {
+                    data: u64::from_ssz_bytes(&decoded_buffer)?,
+                }))),
+            },
+            Protocol::MetaData => match self.protocol.version {
+                Version::V1 => Ok(Some(RPCResponse::MetaData(MetaData::from_ssz_bytes(
+                    &decoded_buffer,
+                )?))),
             },
         }
     }
@@ -313,42 +257,123 @@ impl<TSpec: EthSpec> OutboundCodec<RPCRequest<TSpec>> for SSZSnappyOutboundCodec
     type ErrorType = String;
 
     fn decode_error(&mut self, src: &mut BytesMut) -> Result<Option<Self::ErrorType>, RPCError> {
-        if self.len.is_none() {
-            // Decode the length of the uncompressed bytes from an unsigned varint
-            match self.inner.decode(src).map_err(RPCError::from)? {
-                Some(length) => {
-                    self.len = Some(length as usize);
+        let decoded_buffer =
+            match decode_chunk(&mut self.inner, &mut self.chunk, self.max_packet_size, src)? {
+                Some(decoded_buffer) => decoded_buffer,
+                None => return Ok(None),
+            };
+
+        Ok(Some(
+            String::from_utf8_lossy(&<Vec<u8>>::from_ssz_bytes(&decoded_buffer)?).into(),
+        ))
+    }
+}
+
+/* Chunk Decoder */
+
+/// The length of the type and length header of a snappy frame.
+const FRAME_HEADER_LEN: usize = 4;
+/// The maximum number of uncompressed bytes in a snappy frame.
+const MAX_FRAME_UNCOMPRESSED_LEN: usize = 65_536;
+/// The frame which starts every snappy frame stream.
+const STREAM_IDENTIFIER: [u8; 10] = [0xff, 0x06, 0x00, 0x00, b's', b'N', b'a', b'P', b'p', b'Y'];
+
+/// Decodes the length prefix of the next chunk in `src`, then decompresses as much of the chunk
+/// as has been received. Returns the uncompressed bytes of the chunk once it is complete.
+fn decode_chunk(
+    length_codec: &mut Uvi<usize>,
+    chunk: &mut Option<ChunkDecoder>,
+    max_packet_size: usize,
+    src: &mut BytesMut,
+) -> Result<Option<Vec<u8>>, RPCError> {
+    if chunk.is_none() {
+        // Decode the length of the uncompressed bytes from an unsigned varint
+        match length_codec.decode(src).map_err(RPCError::from)? {
+            Some(length) => {
+                // Should not attempt to decode rpc chunks with length > max_packet_size
+                if length > max_packet_size {
+                    return Err(RPCError::InvalidData);
                 }
-                None => return Ok(None), // need more bytes to decode length
+                *chunk = Some(ChunkDecoder::new(length));
             }
-        };
+            None => return Ok(None), // need more bytes to decode length
+        }
+    }
+
+    let decoded = chunk
+        .as_mut()
+        .expect("chunk decoder should be Some")
+        .decode(src)?;
+    if decoded.is_some() {
+        *chunk = None;
+    }
+    Ok(decoded)
+}
 
-        let length = self.len.expect("length should be Some");
+/// Incrementally decompresses the snappy frames of a single RPC chunk.
+///
+/// Each snappy frame is removed from the codec's buffer and decompressed as soon as it has been
+/// received in full, so the compressed bytes of a chunk are not held until the whole chunk has
+/// arrived and the frames of a partially received chunk are never decompressed twice.
+struct ChunkDecoder {
+    decoded: Vec<u8>,
+    filled: usize,
+    /// Whether the stream identifier which precedes the data frames has been received.
+    has_stream_identifier: bool,
+}
 
-        // Should not attempt to decode rpc chunks with length > max_packet_size
-        if length > self.max_packet_size {
-            return Err(RPCError::InvalidData);
+impl ChunkDecoder {
+    fn new(length: usize) -> Self {
+        Self {
+            decoded: vec![0; length],
+            filled: 0,
+            has_stream_identifier: false,
         }
-        let mut reader = FrameDecoder::new(Cursor::new(&src));
-        let mut decoded_buffer = vec![0; length];
-        match reader.read_exact(&mut decoded_buffer) {
-            Ok(()) => {
-                // `n` is how many bytes the reader read in the compressed stream
-                let n = reader.get_ref().position();
-                self.len = None;
-                let _read_bytes = src.split_to(n as usize);
-                Ok(Some(
-                    String::from_utf8_lossy(&<Vec<u8>>::from_ssz_bytes(&decoded_buffer)?).into(),
-                ))
+    }
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, RPCError> {
+        while self.filled < self.decoded.len() {
+            if src.len() < FRAME_HEADER_LEN {
+                return Ok(None);
+            }
+            let frame_len = FRAME_HEADER_LEN
+                + (src[1] as usize | (src[2] as usize) << 8 | (src[3] as usize) << 16);
+            if frame_len > FRAME_HEADER_LEN + 4 + max_compress_len(MAX_FRAME_UNCOMPRESSED_LEN) {
+                return Err(RPCError::InvalidData);
             }
-            Err(e) => match e.kind() {
-                // Haven't received enough bytes to decode yet
-                // TODO: check if this is the only Error variant where we return `Ok(None)`
-                ErrorKind::UnexpectedEof => {
-                    return Ok(None);
+            if src.len() < frame_len {
+                return Ok(None);
+            }
+            let frame = src.split_to(frame_len);
+
+            if frame[0] == STREAM_IDENTIFIER[0] {
+                if frame[..] != STREAM_IDENTIFIER[..] {
+                    return Err(RPCError::InvalidData);
                 }
-                _ => return Err(e).map_err(RPCError::from),
-            },
+                self.has_stream_identifier = true;
+                continue;
+            } else if !self.has_stream_identifier {
+                return Err(RPCError::InvalidData);
+            }
+
+            // Snappy frames are independent of each other, so each frame can be decompressed on
+            // its own behind a stream identifier.
+            let mut reader = FrameDecoder::new((&STREAM_IDENTIFIER[..]).chain(&frame[..]));
+            loop {
+                match reader
+                    .read(&mut self.decoded[self.filled..])
+                    .map_err(RPCError::from)?
+                {
+                    0 => break,
+                    n => self.filled += n,
+                }
+            }
+            // The frames must not hold more bytes than the length prefix.
+            if reader.read(&mut [0]).map_err(RPCError::from)? > 0 {
+                return Err(RPCError::InvalidData);
+            }
         }
+
+        Ok(Some(std::mem::take(&mut self.decoded)))
     }
 }
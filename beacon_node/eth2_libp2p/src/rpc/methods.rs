@@ -13,11 +13,14 @@ use types::{Epoch, EthSpec, Hash256, SignedBeaconBlock, Slot};
 ///
 // NOTE: The handler stores the `RequestId` to inform back of responses and errors, but it's execution
 // is independent of the contents on this type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RequestId {
     Router,
     Sync(usize),
     Behaviour,
+    /// An id assigned by the network service to an outbound request, which is unique amongst its
+    /// requests.
+    Network(usize),
 }
 
 /// The STATUS request/response handshake message.
@@ -72,6 +75,15 @@ pub enum GoodbyeReason {
     /// Error/fault in the RPC.
     Fault = 3,
 
+    /// Too many peers are connected.
+    TooManyPeers = 129,
+
+    /// The peer's score is too low.
+    BadScore = 250,
+
+    /// The peer is banned.
+    Banned = 251,
+
     /// Unknown reason.
     Unknown = 0,
 }
@@ -82,6 +94,9 @@ impl From<u64> for GoodbyeReason {
             1 => GoodbyeReason::ClientShutdown,
             2 => GoodbyeReason::IrrelevantNetwork,
             3 => GoodbyeReason::Fault,
+            129 => GoodbyeReason::TooManyPeers,
+            250 => GoodbyeReason::BadScore,
+            251 => GoodbyeReason::Banned,
             _ => GoodbyeReason::Unknown,
         }
     }
@@ -337,6 +352,9 @@ impl std::fmt::Display for GoodbyeReason {
             GoodbyeReason::ClientShutdown => write!(f, "Client Shutdown"),
             GoodbyeReason::IrrelevantNetwork => write!(f, "Irrelevant Network"),
             GoodbyeReason::Fault => write!(f, "Fault"),
+            GoodbyeReason::TooManyPeers => write!(f, "Too Many Peers"),
+            GoodbyeReason::BadScore => write!(f, "Bad Score"),
+            GoodbyeReason::Banned => write!(f, "Banned"),
             GoodbyeReason::Unknown => write!(f, "Unknown Reason"),
         }
     }
@@ -363,6 +381,7 @@ impl slog::Value for RequestId {
             RequestId::Behaviour => slog::Value::serialize("Behaviour", record, key, serializer),
             RequestId::Router => slog::Value::serialize("Router", record, key, serializer),
             RequestId::Sync(ref id) => slog::Value::serialize(id, record, key, serializer),
+            RequestId::Network(ref id) => slog::Value::serialize(id, record, key, serializer),
         }
     }
 }
@@ -11,6 +11,7 @@ use libp2p::swarm::{
     PollParameters, SubstreamProtocol,
 };
 use libp2p::{Multiaddr, PeerId};
+use rate_limiter::{RPCRateLimiter, RateLimitedErr};
 use slog::{debug, o};
 use std::marker::PhantomData;
 use std::task::{Context, Poll};
@@ -27,11 +28,13 @@ pub use methods::{
     ResponseTermination, StatusMessage,
 };
 pub use protocol::{Protocol, RPCError};
+pub use rate_limiter::{Quota, RPCQuotas, RPCRateLimiter, RPCRateLimiterBuilder};
 
 pub(crate) mod codec;
 mod handler;
 pub mod methods;
 mod protocol;
+mod rate_limiter;
 
 /// RPC events sent from Lighthouse.
 #[derive(Debug, Clone)]
@@ -89,6 +92,8 @@ pub struct RPCMessage<TSpec: EthSpec> {
 /// Implements the libp2p `NetworkBehaviour` trait and therefore manages network-level
 /// logic.
 pub struct RPC<TSpec: EthSpec> {
+    /// Rate limiter for inbound requests.
+    limiter: RPCRateLimiter,
     /// Queue of events to be processed.
     events: Vec<NetworkBehaviourAction<RPCSend<TSpec>, RPCMessage<TSpec>>>,
     /// Slog logger for RPC behaviour.
@@ -96,9 +101,16 @@ pub struct RPC<TSpec: EthSpec> {
 }
 
 impl<TSpec: EthSpec> RPC<TSpec> {
+    /// Creates the RPC behaviour with the default inbound request quotas.
     pub fn new(log: slog::Logger) -> Self {
+        Self::new_with_limiter(RPCRateLimiter::default(), log)
+    }
+
+    /// Creates the RPC behaviour, limiting inbound requests with the given rate limiter.
+    pub fn new_with_limiter(limiter: RPCRateLimiter, log: slog::Logger) -> Self {
         let log = log.new(o!("service" => "libp2p_rpc"));
         RPC {
+            limiter,
             events: Vec::new(),
             log,
         }
@@ -171,7 +183,10 @@ where
         });
     }
 
-    fn inject_disconnected(&mut self, _peer_id: &PeerId) {}
+    fn inject_disconnected(&mut self, _peer_id: &PeerId) {
+        // forget about peers whose quotas have been replenished
+        self.limiter.prune();
+    }
 
     fn inject_connection_established(
         &mut self,
@@ -195,6 +210,41 @@ where
         conn_id: ConnectionId,
         event: <Self::ProtocolsHandler as ProtocolsHandler>::OutEvent,
     ) {
+        // check inbound requests against the peer's quota
+        if let Ok(RPCReceived::Request(id, req)) = &event {
+            if let Err(e) = self.limiter.allows(&peer_id, req) {
+                let protocol = req.protocol();
+                debug!(self.log, "Request rate limited";
+                    "peer_id" => peer_id.to_string(), "request" => req.to_string(), "reason" => format!("{:?}", e));
+                // goodbye requests have no response stream to terminate
+                if !matches!(protocol, Protocol::Goodbye) {
+                    let response = match e {
+                        RateLimitedErr::TooLarge => RPCCodedResponse::InvalidRequest(
+                            "Rate limited. Request too large".into(),
+                        ),
+                        RateLimitedErr::TooSoon(wait) => RPCCodedResponse::ServerError(format!(
+                            "Rate limited. Wait {} ms",
+                            wait.as_millis()
+                        )),
+                    };
+                    // an error response terminates the stream
+                    self.send_response(peer_id.clone(), (conn_id, *id), response);
+                }
+                // inform the user that the peer exceeded its quota
+                self.events
+                    .push(NetworkBehaviourAction::GenerateEvent(RPCMessage {
+                        peer_id,
+                        conn_id,
+                        event: Err(HandlerErr::Inbound {
+                            id: *id,
+                            proto: protocol,
+                            error: RPCError::RateLimited,
+                        }),
+                    }));
+                return;
+            }
+        }
+
         // send the event to the user
         self.events
             .push(NetworkBehaviourAction::GenerateEvent(RPCMessage {
@@ -80,6 +80,23 @@ impl std::fmt::Display for Protocol {
     }
 }
 
+impl std::str::FromStr for Protocol {
+    type Err = String;
+
+    /// Parses the name of a protocol, as displayed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "status" => Ok(Protocol::Status),
+            "goodbye" => Ok(Protocol::Goodbye),
+            "beacon_blocks_by_range" => Ok(Protocol::BlocksByRange),
+            "beacon_blocks_by_root" => Ok(Protocol::BlocksByRoot),
+            "ping" => Ok(Protocol::Ping),
+            "metadata" => Ok(Protocol::MetaData),
+            other => Err(format!("Unknown RPC protocol: {}", other)),
+        }
+    }
+}
+
 impl std::fmt::Display for Encoding {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let repr = match self {
@@ -392,6 +409,8 @@ pub enum RPCError {
     NegotiationTimeout,
     /// Handler rejected this request.
     HandlerRejected,
+    /// The peer exceeded its quota for this protocol and the request was not served.
+    RateLimited,
 }
 
 impl From<ssz::DecodeError> for RPCError {
@@ -430,6 +449,7 @@ impl std::fmt::Display for RPCError {
             RPCError::InternalError(ref err) => write!(f, "Internal error: {}", err),
             RPCError::NegotiationTimeout => write!(f, "Negotiation timeout"),
             RPCError::HandlerRejected => write!(f, "Handler rejected the request"),
+            RPCError::RateLimited => write!(f, "Request was rate limited"),
         }
     }
 }
@@ -448,6 +468,7 @@ impl std::error::Error for RPCError {
             RPCError::ErrorResponse(_, _) => None,
             RPCError::NegotiationTimeout => None,
             RPCError::HandlerRejected => None,
+            RPCError::RateLimited => None,
         }
     }
 }
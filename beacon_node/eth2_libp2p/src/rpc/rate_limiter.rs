@@ -0,0 +1,391 @@
+//! A token-bucket rate limiter for inbound RPC requests.
+//!
+//! Every peer is assigned a bucket of tokens for each protocol. A request consumes as many tokens
+//! as the number of responses it expects (i.e the `count` of a `BlocksByRange` request or the
+//! number of roots of a `BlocksByRoot` request) and is rejected if the peer's bucket does not hold
+//! enough tokens. Buckets are gradually replenished over time according to the protocol's `Quota`.
+
+use super::protocol::{Protocol, RPCRequest};
+use libp2p::PeerId;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use types::EthSpec;
+
+/// The maximum number of tokens a bucket can hold and the time taken for an empty bucket to be
+/// completely replenished.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quota {
+    /// The maximum number of tokens a bucket can hold. This is the largest request that can be
+    /// served.
+    pub max_tokens: u64,
+    /// The time taken for an empty bucket to be completely replenished.
+    pub replenish_all_every: Duration,
+}
+
+impl Quota {
+    /// A quota allowing `n` tokens to be used every `period`.
+    pub fn n_every(n: u64, period: Duration) -> Self {
+        Quota {
+            max_tokens: n,
+            replenish_all_every: period,
+        }
+    }
+}
+
+impl FromStr for Quota {
+    type Err = String;
+
+    /// Parses a quota of the form `<tokens>/<seconds>`, e.g. `1024/10`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let tokens = parts
+            .next()
+            .and_then(|tokens| tokens.trim().parse::<u64>().ok())
+            .ok_or_else(|| format!("Invalid number of tokens in quota: {}", s))?;
+        let secs = parts
+            .next()
+            .and_then(|secs| secs.trim().parse::<u64>().ok())
+            .ok_or_else(|| format!("Invalid number of seconds in quota: {}", s))?;
+        Ok(Quota::n_every(tokens, Duration::from_secs(secs)))
+    }
+}
+
+/// The quota of each protocol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RPCQuotas {
+    pub ping: Quota,
+    pub metadata: Quota,
+    pub status: Quota,
+    pub goodbye: Quota,
+    pub blocks_by_range: Quota,
+    pub blocks_by_root: Quota,
+}
+
+impl RPCQuotas {
+    /// Sets the quota of `protocol`.
+    pub fn set(&mut self, protocol: Protocol, quota: Quota) {
+        match protocol {
+            Protocol::Ping => self.ping = quota,
+            Protocol::MetaData => self.metadata = quota,
+            Protocol::Status => self.status = quota,
+            Protocol::Goodbye => self.goodbye = quota,
+            Protocol::BlocksByRange => self.blocks_by_range = quota,
+            Protocol::BlocksByRoot => self.blocks_by_root = quota,
+        }
+    }
+}
+
+impl Default for RPCQuotas {
+    /// The default quotas used by lighthouse.
+    fn default() -> Self {
+        RPCQuotas {
+            ping: Quota::n_every(2, Duration::from_secs(10)),
+            metadata: Quota::n_every(2, Duration::from_secs(5)),
+            status: Quota::n_every(5, Duration::from_secs(15)),
+            goodbye: Quota::n_every(1, Duration::from_secs(10)),
+            blocks_by_range: Quota::n_every(1024, Duration::from_secs(10)),
+            blocks_by_root: Quota::n_every(128, Duration::from_secs(10)),
+        }
+    }
+}
+
+/// The reason a request was not allowed by the rate limiter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateLimitedErr {
+    /// The request requires more tokens than a bucket can hold. It will never be allowed.
+    TooLarge,
+    /// The bucket does not hold enough tokens. The request can be allowed after the given
+    /// duration.
+    TooSoon(Duration),
+}
+
+/// The tokens available to a single peer for a single protocol.
+struct Bucket {
+    /// The number of tokens currently in the bucket.
+    tokens: f64,
+    /// The last time the bucket was replenished.
+    last_update: Instant,
+}
+
+/// Rate limits requests of a single protocol for all peers.
+struct Limiter {
+    /// The quota applied to every peer.
+    quota: Quota,
+    /// The buckets of each peer that have made requests.
+    buckets: HashMap<PeerId, Bucket>,
+}
+
+impl Limiter {
+    fn new(quota: Quota) -> Self {
+        Limiter {
+            quota,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// The number of tokens replenished per second.
+    fn tokens_per_sec(&self) -> f64 {
+        self.quota.max_tokens as f64 / self.quota.replenish_all_every.as_secs_f64()
+    }
+
+    /// Consumes `tokens` from the bucket of `peer_id`, if the bucket holds enough tokens.
+    fn allows(
+        &mut self,
+        peer_id: &PeerId,
+        tokens: u64,
+        now: Instant,
+    ) -> Result<(), RateLimitedErr> {
+        if tokens > self.quota.max_tokens {
+            return Err(RateLimitedErr::TooLarge);
+        }
+
+        let max_tokens = self.quota.max_tokens as f64;
+        let tokens_per_sec = self.tokens_per_sec();
+        let bucket = self.buckets.entry(peer_id.clone()).or_insert(Bucket {
+            tokens: max_tokens,
+            last_update: now,
+        });
+
+        // replenish the bucket for the time elapsed since the last update
+        let elapsed = now.saturating_duration_since(bucket.last_update);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * tokens_per_sec).min(max_tokens);
+        bucket.last_update = now;
+
+        let tokens = tokens as f64;
+        if bucket.tokens >= tokens {
+            bucket.tokens -= tokens;
+            Ok(())
+        } else {
+            let wait = (tokens - bucket.tokens) / tokens_per_sec;
+            Err(RateLimitedErr::TooSoon(Duration::from_secs_f64(wait)))
+        }
+    }
+
+    /// Removes all buckets that have been completely replenished.
+    fn prune(&mut self, now: Instant) {
+        let replenish_all_every = self.quota.replenish_all_every;
+        self.buckets.retain(|_, bucket| {
+            now.saturating_duration_since(bucket.last_update) < replenish_all_every
+        });
+    }
+}
+
+/// Rate limits inbound RPC requests per peer and per protocol.
+pub struct RPCRateLimiter {
+    /// Limiter for the `Ping` protocol.
+    ping: Limiter,
+    /// Limiter for the `MetaData` protocol.
+    metadata: Limiter,
+    /// Limiter for the `Status` protocol.
+    status: Limiter,
+    /// Limiter for the `Goodbye` protocol.
+    goodbye: Limiter,
+    /// Limiter for the `BlocksByRange` protocol.
+    blocks_by_range: Limiter,
+    /// Limiter for the `BlocksByRoot` protocol.
+    blocks_by_root: Limiter,
+}
+
+impl RPCRateLimiter {
+    /// Checks if `peer_id` is allowed to make `request`. If so, the tokens required by the request
+    /// are consumed.
+    pub fn allows<T: EthSpec>(
+        &mut self,
+        peer_id: &PeerId,
+        request: &RPCRequest<T>,
+    ) -> Result<(), RateLimitedErr> {
+        // a request always costs at least one token
+        let tokens = std::cmp::max(request.expected_responses() as u64, 1);
+        self.limiter_mut(request.protocol())
+            .allows(peer_id, tokens, Instant::now())
+    }
+
+    /// Removes the buckets of all peers that have not made requests recently.
+    pub fn prune(&mut self) {
+        let now = Instant::now();
+        self.ping.prune(now);
+        self.metadata.prune(now);
+        self.status.prune(now);
+        self.goodbye.prune(now);
+        self.blocks_by_range.prune(now);
+        self.blocks_by_root.prune(now);
+    }
+
+    /// Returns the limiter associated with `protocol`.
+    fn limiter_mut(&mut self, protocol: Protocol) -> &mut Limiter {
+        match protocol {
+            Protocol::Ping => &mut self.ping,
+            Protocol::MetaData => &mut self.metadata,
+            Protocol::Status => &mut self.status,
+            Protocol::Goodbye => &mut self.goodbye,
+            Protocol::BlocksByRange => &mut self.blocks_by_range,
+            Protocol::BlocksByRoot => &mut self.blocks_by_root,
+        }
+    }
+}
+
+impl Default for RPCRateLimiter {
+    fn default() -> Self {
+        RPCRateLimiterBuilder::default()
+            .build()
+            .expect("default builder has a quota for every protocol")
+    }
+}
+
+/// Builds an `RPCRateLimiter` with a quota for each protocol.
+pub struct RPCRateLimiterBuilder {
+    /// Quota for the `Ping` protocol.
+    ping_quota: Option<Quota>,
+    /// Quota for the `MetaData` protocol.
+    metadata_quota: Option<Quota>,
+    /// Quota for the `Status` protocol.
+    status_quota: Option<Quota>,
+    /// Quota for the `Goodbye` protocol.
+    goodbye_quota: Option<Quota>,
+    /// Quota for the `BlocksByRange` protocol.
+    blocks_by_range_quota: Option<Quota>,
+    /// Quota for the `BlocksByRoot` protocol.
+    blocks_by_root_quota: Option<Quota>,
+}
+
+impl RPCRateLimiterBuilder {
+    /// Creates a builder without any quotas.
+    pub fn new() -> Self {
+        RPCRateLimiterBuilder {
+            ping_quota: None,
+            metadata_quota: None,
+            status_quota: None,
+            goodbye_quota: None,
+            blocks_by_range_quota: None,
+            blocks_by_root_quota: None,
+        }
+    }
+
+    /// Sets the quota of `protocol`.
+    pub fn set_quota(mut self, protocol: Protocol, quota: Quota) -> Self {
+        let quota = Some(quota);
+        match protocol {
+            Protocol::Ping => self.ping_quota = quota,
+            Protocol::MetaData => self.metadata_quota = quota,
+            Protocol::Status => self.status_quota = quota,
+            Protocol::Goodbye => self.goodbye_quota = quota,
+            Protocol::BlocksByRange => self.blocks_by_range_quota = quota,
+            Protocol::BlocksByRoot => self.blocks_by_root_quota = quota,
+        }
+        self
+    }
+
+    /// Allows `n` tokens of `protocol` to be used every `period`.
+    pub fn n_every(self, protocol: Protocol, n: u64, period: Duration) -> Self {
+        self.set_quota(protocol, Quota::n_every(n, period))
+    }
+
+    /// Builds the rate limiter. Errors if a protocol has no quota or a quota is invalid.
+    pub fn build(self) -> Result<RPCRateLimiter, &'static str> {
+        fn limiter(quota: Option<Quota>, err: &'static str) -> Result<Limiter, &'static str> {
+            let quota = quota.ok_or(err)?;
+            if quota.max_tokens == 0 || quota.replenish_all_every == Duration::from_secs(0) {
+                return Err("Quotas must allow at least one token over a non-zero period");
+            }
+            Ok(Limiter::new(quota))
+        }
+
+        Ok(RPCRateLimiter {
+            ping: limiter(self.ping_quota, "Ping quota not specified")?,
+            metadata: limiter(self.metadata_quota, "MetaData quota not specified")?,
+            status: limiter(self.status_quota, "Status quota not specified")?,
+            goodbye: limiter(self.goodbye_quota, "Goodbye quota not specified")?,
+            blocks_by_range: limiter(
+                self.blocks_by_range_quota,
+                "BlocksByRange quota not specified",
+            )?,
+            blocks_by_root: limiter(
+                self.blocks_by_root_quota,
+                "BlocksByRoot quota not specified",
+            )?,
+        })
+    }
+}
+
+impl From<RPCQuotas> for RPCRateLimiterBuilder {
+    fn from(quotas: RPCQuotas) -> Self {
+        RPCRateLimiterBuilder::new()
+            .set_quota(Protocol::Ping, quotas.ping)
+            .set_quota(Protocol::MetaData, quotas.metadata)
+            .set_quota(Protocol::Status, quotas.status)
+            .set_quota(Protocol::Goodbye, quotas.goodbye)
+            .set_quota(Protocol::BlocksByRange, quotas.blocks_by_range)
+            .set_quota(Protocol::BlocksByRoot, quotas.blocks_by_root)
+    }
+}
+
+impl Default for RPCRateLimiterBuilder {
+    /// The default quotas used by lighthouse.
+    fn default() -> Self {
+        RPCQuotas::default().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limiter_consumes_and_replenishes_tokens() {
+        let mut limiter = Limiter::new(Quota::n_every(10, Duration::from_secs(10)));
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+
+        assert_eq!(limiter.allows(&peer_id, 8, now), Ok(()));
+        assert!(matches!(
+            limiter.allows(&peer_id, 8, now),
+            Err(RateLimitedErr::TooSoon(_))
+        ));
+        // six seconds replenish six tokens
+        assert_eq!(
+            limiter.allows(&peer_id, 8, now + Duration::from_secs(6)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn limiter_rejects_requests_larger_than_the_quota() {
+        let mut limiter = Limiter::new(Quota::n_every(10, Duration::from_secs(10)));
+        assert_eq!(
+            limiter.allows(&PeerId::random(), 11, Instant::now()),
+            Err(RateLimitedErr::TooLarge)
+        );
+    }
+
+    #[test]
+    fn limiter_tracks_peers_independently() {
+        let mut limiter = Limiter::new(Quota::n_every(10, Duration::from_secs(10)));
+        let now = Instant::now();
+        assert_eq!(limiter.allows(&PeerId::random(), 10, now), Ok(()));
+        assert_eq!(limiter.allows(&PeerId::random(), 10, now), Ok(()));
+    }
+
+    #[test]
+    fn quotas_are_parsed() {
+        assert_eq!(
+            "1024/10".parse::<Quota>(),
+            Ok(Quota::n_every(1024, Duration::from_secs(10)))
+        );
+        assert!("1024".parse::<Quota>().is_err());
+        assert!("a/10".parse::<Quota>().is_err());
+    }
+
+    #[test]
+    fn builder_rejects_empty_quotas() {
+        let mut quotas = RPCQuotas::default();
+        assert!(RPCRateLimiterBuilder::from(quotas.clone()).build().is_ok());
+
+        quotas.set(
+            Protocol::BlocksByRange,
+            Quota::n_every(0, Duration::from_secs(10)),
+        );
+        assert!(RPCRateLimiterBuilder::from(quotas).build().is_err());
+    }
+}
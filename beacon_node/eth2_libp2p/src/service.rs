@@ -27,7 +27,7 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::DelayQueue;
-use types::{EnrForkId, EthSpec};
+use types::{EnrForkId, EthSpec, SubnetId, Unsigned};
 
 pub const NETWORK_KEY_FILENAME: &str = "key";
 /// The time in milliseconds to wait before banning a peer. This allows for any Goodbye messages to be
@@ -114,7 +114,7 @@ impl<TSpec: EthSpec> Service<TSpec> {
 
         let mut swarm = {
             // Set up the transport - tcp/ws with noise/secio and mplex/yamux
-            let transport = build_transport(local_keypair.clone())
+            let transport = build_transport(local_keypair.clone(), config.secio_enabled)
                 .map_err(|e| format!("Failed to build transport: {:?}", e))?;
             // Lighthouse network behaviour
             let behaviour = Behaviour::new(&local_keypair, config, network_globals.clone(), &log)?;
@@ -139,22 +139,31 @@ impl<TSpec: EthSpec> Service<TSpec> {
             m
         };
 
-        match Swarm::listen_on(&mut swarm, listen_multiaddr.clone()) {
-            Ok(_) => {
-                let mut log_address = listen_multiaddr;
-                log_address.push(Protocol::P2p(local_peer_id.clone().into()));
-                info!(log, "Listening established"; "address" => format!("{}", log_address));
-            }
-            Err(err) => {
-                crit!(
-                    log,
-                    "Unable to listen on libp2p address";
-                    "error" => format!("{:?}", err),
-                    "listen_multiaddr" => format!("{}", listen_multiaddr),
-                );
-                return Err("Libp2p was unable to listen on the given listen address.".into());
-            }
-        };
+        // dual-stack nodes also listen on an IPv6 address
+        let listen_multiaddr_v6 = config.listen_address_v6.map(|address| {
+            let mut m = Multiaddr::from(address);
+            m.push(Protocol::Tcp(config.libp2p_port_v6));
+            m
+        });
+
+        for listen_multiaddr in std::iter::once(listen_multiaddr).chain(listen_multiaddr_v6) {
+            match Swarm::listen_on(&mut swarm, listen_multiaddr.clone()) {
+                Ok(_) => {
+                    let mut log_address = listen_multiaddr;
+                    log_address.push(Protocol::P2p(local_peer_id.clone().into()));
+                    info!(log, "Listening established"; "address" => format!("{}", log_address));
+                }
+                Err(err) => {
+                    crit!(
+                        log,
+                        "Unable to listen on libp2p address";
+                        "error" => format!("{:?}", err),
+                        "listen_multiaddr" => format!("{}", listen_multiaddr),
+                    );
+                    return Err("Libp2p was unable to listen on the given listen address.".into());
+                }
+            };
+        }
 
         // helper closure for dialing peers
         let mut dial_addr = |multiaddr: &Multiaddr| {
@@ -202,7 +211,12 @@ impl<TSpec: EthSpec> Service<TSpec> {
                 warn!(log, "Could not subscribe to topic"; "topic" => format!("{}",topic_kind));
             }
         }
-        info!(log, "Subscribed to topics"; "topics" => format!("{:?}", subscribed_topics));
+        if config.subscribe_all_subnets {
+            for subnet_id in 0..TSpec::SubnetBitfieldLength::to_u64() {
+                swarm.subscribe_to_subnet(SubnetId::new(subnet_id));
+            }
+        }
+        info!(log, "Subscribed to topics"; "topics" => format!("{:?}", subscribed_topics), "all_subnets" => config.subscribe_all_subnets);
 
         let service = Service {
             local_peer_id,
@@ -265,15 +279,19 @@ impl<TSpec: EthSpec> Service<TSpec> {
                             // has been established and update the db
                             if num_established.get() == 1 {
                                 // update the peerdb
-                                match endpoint {
+                                match &endpoint {
                                     ConnectedPoint::Listener { .. } => {
                                         self.swarm.peer_manager().connect_ingoing(&peer_id);
                                     }
-                                    ConnectedPoint::Dialer { .. } => self
-                                        .network_globals
-                                        .peers
-                                        .write()
-                                        .connect_outgoing(&peer_id),
+                                    ConnectedPoint::Dialer { address } => {
+                                        self.network_globals
+                                            .peers
+                                            .write()
+                                            .connect_outgoing(&peer_id);
+                                        self.swarm
+                                            .peer_manager()
+                                            .dial_succeeded(&peer_id, address);
+                                    }
                                 }
                                 return Libp2pEvent::PeerConnected { peer_id, endpoint };
                             }
@@ -366,8 +384,12 @@ impl<TSpec: EthSpec> Service<TSpec> {
 
 /// The implementation supports TCP/IP, WebSockets over TCP/IP, noise/secio as the encryption
 /// layer, and mplex or yamux as the multiplexing layer.
+///
+/// Noise is preferred, secio is only negotiated with peers that do not support Noise if
+/// `secio_enabled` is `true`.
 fn build_transport(
     local_private_key: Keypair,
+    secio_enabled: bool,
 ) -> Result<Boxed<(PeerId, StreamMuxerBox), Error>, Error> {
     let transport = libp2p_tcp::TokioTcpConfig::new().nodelay(true);
     let transport = libp2p::dns::DnsConfig::new(transport)?;
@@ -379,10 +401,15 @@ fn build_transport(
     // Authentication
     let transport = transport
         .and_then(move |stream, endpoint| {
-            let upgrade = core::upgrade::SelectUpgrade::new(
-                generate_noise_config(&local_private_key),
-                secio::SecioConfig::new(local_private_key),
-            );
+            let secio = if secio_enabled {
+                core::upgrade::OptionalUpgrade::some(secio::SecioConfig::new(
+                    local_private_key.clone(),
+                ))
+            } else {
+                core::upgrade::OptionalUpgrade::none()
+            };
+            let upgrade =
+                core::upgrade::SelectUpgrade::new(generate_noise_config(&local_private_key), secio);
             core::upgrade::apply(stream, upgrade, endpoint, core::upgrade::Version::V1).and_then(
                 |out| async move {
                     match out {
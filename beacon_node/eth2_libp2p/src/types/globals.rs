@@ -1,7 +1,7 @@
 //! A collection of variables that are accessible outside of the network thread itself.
 use crate::peer_manager::PeerDB;
 use crate::rpc::methods::MetaData;
-use crate::types::SyncState;
+use crate::types::{SyncState, SyncStatus, SyncingChainInfo};
 use crate::Client;
 use crate::EnrExt;
 use crate::{Enr, Eth2Enr, GossipTopic, Multiaddr, PeerId};
@@ -29,6 +29,8 @@ pub struct NetworkGlobals<TSpec: EthSpec> {
     pub gossipsub_subscriptions: RwLock<HashSet<GossipTopic>>,
     /// The current sync status of the node.
     pub sync_state: RwLock<SyncState>,
+    /// The progress of the chains known to range sync.
+    pub syncing_chains: RwLock<Vec<SyncingChainInfo>>,
 }
 
 impl<TSpec: EthSpec> NetworkGlobals<TSpec> {
@@ -51,6 +53,7 @@ impl<TSpec: EthSpec> NetworkGlobals<TSpec> {
             peers: RwLock::new(PeerDB::new(log)),
             gossipsub_subscriptions: RwLock::new(HashSet::new()),
             sync_state: RwLock::new(SyncState::Stalled),
+            syncing_chains: RwLock::new(Vec::new()),
         }
     }
 
@@ -85,6 +88,11 @@ impl<TSpec: EthSpec> NetworkGlobals<TSpec> {
         self.peers.read().connected_peer_ids().count()
     }
 
+    /// Returns the number of libp2p connected peers that we dialed.
+    pub fn connected_outbound_peers(&self) -> usize {
+        self.peers.read().connected_outbound_peer_ids().count()
+    }
+
     /// Returns the number of libp2p peers that are either connected or being dialed.
     pub fn connected_or_dialing_peers(&self) -> usize {
         self.peers.read().connected_or_dialing_peers().count()
@@ -100,6 +108,15 @@ impl<TSpec: EthSpec> NetworkGlobals<TSpec> {
         self.sync_state.read().clone()
     }
 
+    /// Returns the current sync state of the node, along with the progress of each chain known
+    /// to range sync.
+    pub fn sync_status(&self) -> SyncStatus {
+        SyncStatus {
+            state: self.sync_state(),
+            chains: self.syncing_chains.read().clone(),
+        }
+    }
+
     /// Returns a `Client` type if one is known for the `PeerId`.
     pub fn client(&self, peer_id: &PeerId) -> Client {
         self.peers
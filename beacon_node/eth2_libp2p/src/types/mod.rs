@@ -1,6 +1,7 @@
 pub mod error;
 mod globals;
 mod pubsub;
+mod subscription_filter;
 mod sync_state;
 mod topics;
 
@@ -12,6 +13,7 @@ pub type EnrBitfield<T: EthSpec> = BitVector<T::SubnetBitfieldLength>;
 pub type Enr = discv5::enr::Enr<discv5::enr::CombinedKey>;
 
 pub use globals::NetworkGlobals;
-pub use pubsub::PubsubMessage;
-pub use sync_state::SyncState;
+pub use pubsub::{MessageAcceptance, PubsubMessage};
+pub use subscription_filter::SubscriptionFilter;
+pub use sync_state::{SyncState, SyncStatus, SyncingChainInfo, SyncingChainType};
 pub use topics::{GossipEncoding, GossipKind, GossipTopic};
@@ -9,7 +9,7 @@ use std::boxed::Box;
 use types::SubnetId;
 use types::{
     Attestation, AttesterSlashing, EthSpec, ProposerSlashing, SignedAggregateAndProof,
-    SignedBeaconBlock, VoluntaryExit,
+    SignedBeaconBlock, SignedVoluntaryExit,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -21,13 +21,29 @@ pub enum PubsubMessage<T: EthSpec> {
     /// Gossipsub message providing notification of a raw un-aggregated attestation with its shard id.
     Attestation(Box<(SubnetId, Attestation<T>)>),
     /// Gossipsub message providing notification of a voluntary exit.
-    VoluntaryExit(Box<VoluntaryExit>),
+    VoluntaryExit(Box<SignedVoluntaryExit>),
     /// Gossipsub message providing notification of a new proposer slashing.
     ProposerSlashing(Box<ProposerSlashing>),
     /// Gossipsub message providing notification of a new attester slashing.
     AttesterSlashing(Box<AttesterSlashing<T>>),
 }
 
+/// The result of validating a gossipsub message, as defined by the eth2 gossip domain.
+///
+/// The beacon chain determines the outcome of each message received on gossip. Only accepted
+/// messages are propagated to our peers. Rejected messages were invalid and are attributed to the
+/// peer that sent them, whereas ignored messages are simply dropped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageAcceptance {
+    /// The message is valid and should be propagated.
+    Accept,
+    /// The message is not valid and should not be propagated, however the peer that sent it is
+    /// not considered faulty.
+    Ignore,
+    /// The message is invalid and the peer that sent it should be penalised.
+    Reject,
+}
+
 impl<T: EthSpec> PubsubMessage<T> {
     /// Returns the topics that each pubsub message will be sent across, given a supported
     /// gossipsub encoding and fork version.
@@ -115,7 +131,7 @@ impl<T: EthSpec> PubsubMessage<T> {
                             return Ok(PubsubMessage::BeaconBlock(Box::new(beacon_block)));
                         }
                         GossipKind::VoluntaryExit => {
-                            let voluntary_exit = VoluntaryExit::from_ssz_bytes(data)
+                            let voluntary_exit = SignedVoluntaryExit::from_ssz_bytes(data)
                                 .map_err(|e| format!("{:?}", e))?;
                             return Ok(PubsubMessage::VoluntaryExit(Box::new(voluntary_exit)));
                         }
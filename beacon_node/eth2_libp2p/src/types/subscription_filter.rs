@@ -0,0 +1,76 @@
+use crate::types::GossipKind;
+use crate::NetworkConfig;
+
+/// Restricts the gossipsub topics the node subscribes to and accepts messages on.
+///
+/// Blocks-only nodes never join the attestation topics, whilst nodes subscribed to all subnets
+/// never leave the attestation subnets once joined at startup.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubscriptionFilter {
+    /// Refuse the aggregate and attestation subnet topics.
+    pub disable_attestation_gossip: bool,
+    /// Remain subscribed to all attestation subnets.
+    pub subscribe_all_subnets: bool,
+}
+
+impl SubscriptionFilter {
+    pub fn from_config(config: &NetworkConfig) -> Self {
+        SubscriptionFilter {
+            disable_attestation_gossip: config.disable_attestation_gossip,
+            subscribe_all_subnets: config.subscribe_all_subnets,
+        }
+    }
+
+    /// Returns `true` if we may subscribe to and accept messages on topics of `kind`.
+    pub fn allows(&self, kind: &GossipKind) -> bool {
+        match kind {
+            GossipKind::BeaconAggregateAndProof | GossipKind::CommitteeIndex(_) => {
+                !self.disable_attestation_gossip
+            }
+            GossipKind::BeaconBlock
+            | GossipKind::VoluntaryExit
+            | GossipKind::ProposerSlashing
+            | GossipKind::AttesterSlashing => true,
+        }
+    }
+
+    /// Returns `true` if we may unsubscribe from topics of `kind`.
+    pub fn can_unsubscribe(&self, kind: &GossipKind) -> bool {
+        match kind {
+            GossipKind::CommitteeIndex(_) => !self.subscribe_all_subnets,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::SubnetId;
+
+    #[test]
+    fn test_blocks_only_filter() {
+        let filter = SubscriptionFilter {
+            disable_attestation_gossip: true,
+            subscribe_all_subnets: false,
+        };
+
+        assert!(filter.allows(&GossipKind::BeaconBlock));
+        assert!(filter.allows(&GossipKind::VoluntaryExit));
+        assert!(!filter.allows(&GossipKind::BeaconAggregateAndProof));
+        assert!(!filter.allows(&GossipKind::CommitteeIndex(SubnetId::new(3))));
+    }
+
+    #[test]
+    fn test_all_subnets_filter() {
+        let filter = SubscriptionFilter {
+            disable_attestation_gossip: false,
+            subscribe_all_subnets: true,
+        };
+        let subnet = GossipKind::CommitteeIndex(SubnetId::new(3));
+
+        assert!(filter.allows(&subnet));
+        assert!(!filter.can_unsubscribe(&subnet));
+        assert!(filter.can_unsubscribe(&GossipKind::BeaconAggregateAndProof));
+    }
+}
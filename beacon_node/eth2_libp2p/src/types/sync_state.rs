@@ -22,6 +22,41 @@ pub enum SyncState {
     Stalled,
 }
 
+/// The kind of a chain being synced by range sync.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SyncingChainType {
+    /// A chain to the finalized checkpoint of a peer.
+    Finalized,
+    /// A chain to the head of a peer.
+    Head,
+}
+
+/// The progress of a chain being synced by range sync.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncingChainInfo {
+    pub chain_type: SyncingChainType,
+    /// `false` if the chain is paused, e.g., a head chain whilst a finalized chain is syncing.
+    pub is_syncing: bool,
+    pub start_slot: Slot,
+    pub target_head_slot: Slot,
+    pub target_head_root: Hash256,
+    /// The last slot of the batches which have been processed.
+    pub processed_slot: Slot,
+    pub processed_batches: u64,
+    /// The number of peers from which the chain is downloaded.
+    pub peers: usize,
+    /// The estimated time to sync the chain, given its progress so far. `None` if the chain has
+    /// not made any progress.
+    pub estimated_seconds_remaining: Option<u64>,
+}
+
+/// The sync state of the node, along with the progress of each chain known to range sync.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub state: SyncState,
+    pub chains: Vec<SyncingChainInfo>,
+}
+
 impl PartialEq for SyncState {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
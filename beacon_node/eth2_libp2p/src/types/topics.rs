@@ -95,6 +95,20 @@ impl GossipTopic {
         &mut self.fork_digest
     }
 
+    /// Returns the fork digest of the gossipsub topic.
+    pub fn fork_digest(&self) -> [u8; 4] {
+        self.fork_digest
+    }
+
+    /// Returns the counterpart of this topic under the fork with the given `fork_digest`.
+    pub fn with_fork_digest(&self, fork_digest: [u8; 4]) -> Self {
+        GossipTopic {
+            encoding: self.encoding.clone(),
+            kind: self.kind.clone(),
+            fork_digest,
+        }
+    }
+
     /// Returns the kind of message expected on the gossipsub topic.
     pub fn kind(&self) -> &GossipKind {
         &self.kind
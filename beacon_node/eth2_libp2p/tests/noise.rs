@@ -137,8 +137,13 @@ async fn test_secio_noise_fallback() {
     let port = common::unused_port("tcp").unwrap();
     let noisy_config = common::build_config(port, vec![]);
     let (_signal, exit) = exit_future::signal();
-    let executor =
-        environment::TaskExecutor::new(tokio::runtime::Handle::current(), exit, log.clone());
+    let (shutdown_tx, _) = futures::channel::mpsc::channel(1);
+    let executor = environment::TaskExecutor::new(
+        tokio::runtime::Handle::current(),
+        exit,
+        log.clone(),
+        shutdown_tx,
+    );
     let mut noisy_node = Service::new(executor, &noisy_config, EnrForkId::default(), &log)
         .expect("should build a libp2p instance")
         .1;
@@ -61,6 +61,9 @@ impl Eth1GenesisService {
             // a `MIN_GENESIS_SECONDS`, so after `MIN_GENESIS_VALIDATOR_COUNT`
             // has been reached only a single block needs to be read.
             max_blocks_per_update: Some(BLOCKS_PER_GENESIS_POLL),
+            // Genesis requires every deposit, not just those after a snapshot.
+            deposit_snapshot_path: None,
+            deposit_snapshot_url: None,
             ..config
         };
 
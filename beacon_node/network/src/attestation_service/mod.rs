@@ -193,6 +193,16 @@ impl<T: BeaconChainTypes> AttestationService<T> {
         for subscription in subscriptions {
             //NOTE: We assume all subscriptions have been verified before reaching this service
 
+            if let Some(committee_count) = subscription.committee_count_at_slot {
+                if subscription.attestation_committee_index >= committee_count {
+                    warn!(self.log, "Ignoring subscription to an unknown committee";
+                        "slot" => subscription.slot,
+                        "committee_index" => subscription.attestation_committee_index,
+                        "committee_count" => committee_count);
+                    continue;
+                }
+            }
+
             // Registers the validator with the attestation service.
             // This will subscribe to long-lived random subnets if required.
             self.add_known_validator(subscription.validator_index);
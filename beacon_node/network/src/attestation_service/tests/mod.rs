@@ -115,6 +115,7 @@ mod tests {
         ValidatorSubscription {
             validator_index,
             attestation_committee_index,
+            committee_count_at_slot: None,
             slot,
             is_aggregator,
         }
@@ -127,6 +128,7 @@ mod tests {
             subscriptions.push(ValidatorSubscription {
                 validator_index,
                 attestation_committee_index: validator_index,
+                committee_count_at_slot: None,
                 slot,
                 is_aggregator,
             });
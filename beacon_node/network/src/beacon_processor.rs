@@ -0,0 +1,449 @@
+//! Provides the `BeaconProcessor`, which prioritises the verification and import of the blocks
+//! and attestations received from the network.
+//!
+//! Each unit of work is classified by a `WorkType` when it is submitted. The `BeaconProcessor`
+//! holds a queue for each type and, whenever a worker is free, starts the waiting work of the
+//! highest priority. Work is run on the blocking thread pool of the executor, with at most
+//! `max_workers` units of work running at once.
+//!
+//! The queues of sync work are unbounded, since sync waits on the result of every batch it
+//! submits. The queues of gossip work are bounded, so that gossip is dropped when the node is
+//! overloaded:
+//!
+//! - Blocks are processed in the order they arrive and new blocks are dropped once the queue is
+//!   full.
+//! - Attestations are processed newest first and the oldest attestations are dropped once the
+//!   queue is full, since recent attestations are the most valuable to fork choice.
+//!
+//! Work may be submitted with a drop handler, which is run in place of the work if it is dropped
+//! (e.g., to inform gossipsub that the message was ignored).
+
+use crate::metrics;
+use slog::{debug, trace};
+use std::collections::VecDeque;
+use tokio::sync::mpsc;
+
+/// The maximum number of gossip blocks waiting to be processed.
+const MAX_GOSSIP_BLOCK_QUEUE_LEN: usize = 1_024;
+
+/// The maximum number of gossip aggregates waiting to be processed.
+const MAX_GOSSIP_AGGREGATE_QUEUE_LEN: usize = 4_096;
+
+/// The maximum number of batches of unaggregated gossip attestations waiting to be processed.
+const MAX_GOSSIP_ATTESTATION_QUEUE_LEN: usize = 1_024;
+
+/// The name of the manager task of the `BeaconProcessor`.
+const MANAGER_TASK_NAME: &str = "beacon_processor_manager";
+
+/// The name of the worker tasks of the `BeaconProcessor`.
+const WORKER_TASK_NAME: &str = "beacon_processor_worker";
+
+/// The types of work, in order of priority.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorkType {
+    /// A block received on gossip. These are almost always blocks of the current slot, which
+    /// are required to follow the head of the chain.
+    GossipBlock,
+    /// A block obtained by a parent lookup or a single block lookup.
+    RpcBlock,
+    /// A batch of blocks downloaded by range sync.
+    ChainSegment,
+    /// An aggregated attestation received on gossip.
+    GossipAggregate,
+    /// A batch of unaggregated attestations received on gossip.
+    GossipAttestation,
+}
+
+/// Every `WorkType`, from the highest to the lowest priority.
+const WORK_TYPES: [WorkType; 5] = [
+    WorkType::GossipBlock,
+    WorkType::RpcBlock,
+    WorkType::ChainSegment,
+    WorkType::GossipAggregate,
+    WorkType::GossipAttestation,
+];
+
+/// The order in which the work of a queue is processed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum QueueOrder {
+    /// First in, first out. New work is dropped when the queue is full.
+    Fifo,
+    /// Last in, first out. The oldest work is dropped when the queue is full.
+    Lifo,
+}
+
+impl WorkType {
+    /// The name of the work type, used as a metrics label.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WorkType::GossipBlock => "gossip_block",
+            WorkType::RpcBlock => "rpc_block",
+            WorkType::ChainSegment => "chain_segment",
+            WorkType::GossipAggregate => "gossip_aggregate",
+            WorkType::GossipAttestation => "gossip_attestation",
+        }
+    }
+
+    /// The maximum length of the queue of the work type, if it is bounded.
+    fn max_queue_len(self) -> Option<usize> {
+        match self {
+            WorkType::GossipBlock => Some(MAX_GOSSIP_BLOCK_QUEUE_LEN),
+            WorkType::RpcBlock | WorkType::ChainSegment => None,
+            WorkType::GossipAggregate => Some(MAX_GOSSIP_AGGREGATE_QUEUE_LEN),
+            WorkType::GossipAttestation => Some(MAX_GOSSIP_ATTESTATION_QUEUE_LEN),
+        }
+    }
+
+    fn queue_order(self) -> QueueOrder {
+        match self {
+            WorkType::GossipBlock | WorkType::RpcBlock | WorkType::ChainSegment => QueueOrder::Fifo,
+            WorkType::GossipAggregate | WorkType::GossipAttestation => QueueOrder::Lifo,
+        }
+    }
+}
+
+/// A unit of work, run on a worker thread.
+type Task = Box<dyn FnOnce() + Send>;
+
+/// A `Task` waiting in a queue, along with the handler to run if it is dropped.
+struct QueuedTask {
+    task: Task,
+    on_drop: Option<Task>,
+}
+
+impl QueuedTask {
+    fn drop_task(self) {
+        if let Some(on_drop) = self.on_drop {
+            on_drop();
+        }
+    }
+}
+
+/// The queue of waiting work of a single `WorkType`.
+struct WorkQueue {
+    work_type: WorkType,
+    tasks: VecDeque<QueuedTask>,
+}
+
+impl WorkQueue {
+    fn new(work_type: WorkType) -> Self {
+        Self {
+            work_type,
+            tasks: VecDeque::new(),
+        }
+    }
+
+    /// Adds `task` to the queue, returning `false` if a task was dropped because the queue is
+    /// full. The `on_drop` handler of the dropped task, if any, is run.
+    fn push(&mut self, task: Task, on_drop: Option<Task>) -> bool {
+        let task = QueuedTask { task, on_drop };
+        let is_full = self
+            .work_type
+            .max_queue_len()
+            .map_or(false, |max_len| self.tasks.len() >= max_len);
+
+        match self.work_type.queue_order() {
+            QueueOrder::Fifo => {
+                if is_full {
+                    task.drop_task();
+                    return false;
+                }
+                self.tasks.push_back(task);
+            }
+            QueueOrder::Lifo => {
+                if is_full {
+                    if let Some(oldest) = self.tasks.pop_back() {
+                        oldest.drop_task();
+                    }
+                }
+                self.tasks.push_front(task);
+            }
+        }
+
+        !is_full
+    }
+
+    /// Returns the next task to be run.
+    fn pop(&mut self) -> Option<Task> {
+        self.tasks.pop_front().map(|queued| queued.task)
+    }
+
+    fn len(&self) -> usize {
+        self.tasks.len()
+    }
+}
+
+/// The queues of every `WorkType`.
+struct WorkQueues {
+    queues: Vec<WorkQueue>,
+}
+
+impl WorkQueues {
+    fn new() -> Self {
+        Self {
+            queues: WORK_TYPES.iter().copied().map(WorkQueue::new).collect(),
+        }
+    }
+
+    fn queue_mut(&mut self, work_type: WorkType) -> &mut WorkQueue {
+        self.queues
+            .iter_mut()
+            .find(|queue| queue.work_type == work_type)
+            .expect("there is a queue for every work type")
+    }
+
+    /// Returns the waiting work of the highest priority.
+    fn pop(&mut self) -> Option<(WorkType, Task)> {
+        self.queues
+            .iter_mut()
+            .find_map(|queue| Some((queue.work_type, queue.pop()?)))
+    }
+
+    fn update_metrics(&self) {
+        for queue in &self.queues {
+            if let Some(gauge) = metrics::get_int_gauge(
+                &metrics::BEACON_PROCESSOR_QUEUE_LENGTH,
+                &[queue.work_type.as_str()],
+            ) {
+                gauge.set(queue.len() as i64);
+            }
+        }
+    }
+}
+
+/// Events received by the manager task of the `BeaconProcessor`.
+enum InboundEvent {
+    /// Work has been submitted, along with the handler to run if it is dropped.
+    Work(WorkType, Task, Option<Task>),
+    /// A worker has finished its work.
+    WorkerIdle,
+}
+
+/// Informs the manager task when a worker finishes, even if its work panics.
+struct IdleGuard(mpsc::UnboundedSender<InboundEvent>);
+
+impl Drop for IdleGuard {
+    fn drop(&mut self) {
+        let _ = self.0.send(InboundEvent::WorkerIdle);
+    }
+}
+
+/// A handle for submitting work to a `BeaconProcessor`.
+#[derive(Clone)]
+pub struct BeaconProcessorSend {
+    inbound_send: mpsc::UnboundedSender<InboundEvent>,
+    log: slog::Logger,
+}
+
+impl BeaconProcessorSend {
+    /// Queues `task` to be run by a worker, according to the priority of `work_type`.
+    pub fn submit<F: FnOnce() + Send + 'static>(&self, work_type: WorkType, task: F) {
+        self.send_work(work_type, Box::new(task), None)
+    }
+
+    /// Queues `task` to be run by a worker, according to the priority of `work_type`. If the work
+    /// is dropped because its queue is full, `on_drop` is run instead.
+    pub fn submit_with_drop_handler<F, D>(&self, work_type: WorkType, task: F, on_drop: D)
+    where
+        F: FnOnce() + Send + 'static,
+        D: FnOnce() + Send + 'static,
+    {
+        self.send_work(work_type, Box::new(task), Some(Box::new(on_drop)))
+    }
+
+    fn send_work(&self, work_type: WorkType, task: Task, on_drop: Option<Task>) {
+        if self
+            .inbound_send
+            .send(InboundEvent::Work(work_type, task, on_drop))
+            .is_err()
+        {
+            debug!(
+                self.log,
+                "Could not submit work to the beacon processor";
+                "reason" => "likely shutting down",
+                "work_type" => work_type.as_str(),
+            );
+        }
+    }
+}
+
+/// Spawns the manager task of a `BeaconProcessor` which runs submitted work on at most
+/// `max_workers` workers, returning the handle to submit work.
+pub fn spawn(
+    executor: environment::TaskExecutor,
+    max_workers: usize,
+    log: slog::Logger,
+) -> BeaconProcessorSend {
+    let (inbound_send, mut inbound_recv) = mpsc::unbounded_channel();
+    let idle_send = inbound_send.clone();
+    let manager_log = log.clone();
+    let worker_executor = executor.clone();
+
+    let manager = async move {
+        let mut queues = WorkQueues::new();
+        let mut active_workers = 0;
+
+        while let Some(event) = inbound_recv.recv().await {
+            match event {
+                InboundEvent::Work(work_type, task, on_drop) => {
+                    if !queues.queue_mut(work_type).push(task, on_drop) {
+                        trace!(
+                            manager_log,
+                            "Beacon processor queue full, dropping work";
+                            "work_type" => work_type.as_str(),
+                        );
+                        metrics::inc_counter_vec(
+                            &metrics::BEACON_PROCESSOR_WORK_DROPPED,
+                            &[work_type.as_str()],
+                        );
+                    }
+                }
+                InboundEvent::WorkerIdle => active_workers -= 1,
+            }
+
+            while active_workers < max_workers {
+                let (work_type, task) = match queues.pop() {
+                    Some(work) => work,
+                    None => break,
+                };
+                active_workers += 1;
+
+                let idle_guard = IdleGuard(idle_send.clone());
+                worker_executor.spawn_blocking(
+                    move || {
+                        let _idle_guard = idle_guard;
+                        let _timer = metrics::get_histogram(
+                            &metrics::BEACON_PROCESSOR_WORK_TIMES,
+                            &[work_type.as_str()],
+                        )
+                        .map(|histogram| histogram.start_timer());
+                        task();
+                    },
+                    WORKER_TASK_NAME,
+                );
+            }
+
+            queues.update_metrics();
+            metrics::set_gauge(
+                &metrics::BEACON_PROCESSOR_WORKERS_ACTIVE,
+                active_workers as i64,
+            );
+        }
+    };
+
+    executor.spawn(manager, MANAGER_TASK_NAME);
+
+    BeaconProcessorSend { inbound_send, log }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Returns a task which records `id` in `record` when it is run.
+    fn task(id: usize, record: &Arc<Mutex<Vec<usize>>>) -> Task {
+        let record = record.clone();
+        Box::new(move || record.lock().unwrap().push(id))
+    }
+
+    /// Runs every queued task in order of priority.
+    fn run_all(queues: &mut WorkQueues) {
+        while let Some((_, task)) = queues.pop() {
+            task();
+        }
+    }
+
+    #[test]
+    fn work_is_run_in_order_of_priority() {
+        let record = Arc::new(Mutex::new(vec![]));
+        let mut queues = WorkQueues::new();
+
+        for (id, work_type) in WORK_TYPES.iter().rev().enumerate() {
+            assert!(queues.queue_mut(*work_type).push(task(id, &record), None));
+        }
+        run_all(&mut queues);
+
+        assert_eq!(*record.lock().unwrap(), vec![4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn full_fifo_queue_drops_new_work() {
+        let record = Arc::new(Mutex::new(vec![]));
+        let mut queue = WorkQueue::new(WorkType::GossipBlock);
+
+        for id in 0..MAX_GOSSIP_BLOCK_QUEUE_LEN {
+            assert!(queue.push(task(id, &record), None));
+        }
+        assert!(!queue.push(task(MAX_GOSSIP_BLOCK_QUEUE_LEN, &record), None));
+        assert_eq!(queue.len(), MAX_GOSSIP_BLOCK_QUEUE_LEN);
+
+        queue.pop().expect("queue is not empty")();
+        assert_eq!(*record.lock().unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn full_lifo_queue_drops_old_work() {
+        let record = Arc::new(Mutex::new(vec![]));
+        let mut queue = WorkQueue::new(WorkType::GossipAggregate);
+
+        for id in 0..MAX_GOSSIP_AGGREGATE_QUEUE_LEN {
+            assert!(queue.push(task(id, &record), None));
+        }
+        assert!(!queue.push(task(MAX_GOSSIP_AGGREGATE_QUEUE_LEN, &record), None));
+        assert_eq!(queue.len(), MAX_GOSSIP_AGGREGATE_QUEUE_LEN);
+
+        while let Some(task) = queue.pop() {
+            task();
+        }
+        let record = record.lock().unwrap();
+        assert_eq!(record.first(), Some(&MAX_GOSSIP_AGGREGATE_QUEUE_LEN));
+        assert_eq!(record.last(), Some(&1), "the oldest work should be dropped");
+    }
+
+    #[test]
+    fn dropped_work_runs_its_drop_handler() {
+        let record = Arc::new(Mutex::new(vec![]));
+        let dropped = Arc::new(Mutex::new(vec![]));
+
+        let mut fifo = WorkQueue::new(WorkType::GossipBlock);
+        for id in 0..=MAX_GOSSIP_BLOCK_QUEUE_LEN {
+            fifo.push(task(id, &record), Some(task(id, &dropped)));
+        }
+        assert_eq!(
+            *dropped.lock().unwrap(),
+            vec![MAX_GOSSIP_BLOCK_QUEUE_LEN],
+            "the new work should be dropped"
+        );
+
+        dropped.lock().unwrap().clear();
+
+        let mut lifo = WorkQueue::new(WorkType::GossipAggregate);
+        for id in 0..=MAX_GOSSIP_AGGREGATE_QUEUE_LEN {
+            lifo.push(task(id, &record), Some(task(id, &dropped)));
+        }
+        assert_eq!(
+            *dropped.lock().unwrap(),
+            vec![0],
+            "the oldest work should be dropped"
+        );
+
+        assert!(record.lock().unwrap().is_empty(), "no work should be run");
+    }
+
+    #[test]
+    fn sync_work_is_never_dropped() {
+        let record = Arc::new(Mutex::new(vec![]));
+        let mut queues = WorkQueues::new();
+
+        for id in 0..MAX_GOSSIP_ATTESTATION_QUEUE_LEN * 2 {
+            assert!(queues
+                .queue_mut(WorkType::ChainSegment)
+                .push(task(id, &record), None));
+        }
+        assert_eq!(
+            queues.queue_mut(WorkType::ChainSegment).len(),
+            MAX_GOSSIP_ATTESTATION_QUEUE_LEN * 2
+        );
+    }
+}
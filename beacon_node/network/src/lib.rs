@@ -6,10 +6,17 @@ pub mod error;
 pub mod service;
 
 mod attestation_service;
+mod beacon_processor;
 mod metrics;
+mod nat;
 mod persisted_dht;
+mod persisted_item;
+mod persisted_peers;
 mod router;
 mod sync;
 
 pub use eth2_libp2p::NetworkConfig;
-pub use service::{NetworkMessage, NetworkService};
+pub use service::{
+    NetworkMessage, NetworkService, PublishError, PublishResultReceiver, PublishResultSender,
+};
+pub use sync::BlockProcessorEvent;
@@ -21,6 +21,22 @@ lazy_static! {
         "Count of gossip aggregated attestations received"
     );
 
+    /*
+     * Gossip Validation
+     */
+    pub static ref GOSSIP_MESSAGES_ACCEPTED: Result<IntCounter> = try_create_int_counter(
+        "network_gossip_messages_accepted_total",
+        "Count of gossip messages accepted and propagated after validation"
+    );
+    pub static ref GOSSIP_MESSAGES_IGNORED: Result<IntCounter> = try_create_int_counter(
+        "network_gossip_messages_ignored_total",
+        "Count of gossip messages ignored after validation"
+    );
+    pub static ref GOSSIP_MESSAGES_REJECTED: Result<IntCounter> = try_create_int_counter(
+        "network_gossip_messages_rejected_total",
+        "Count of gossip messages rejected as invalid after validation"
+    );
+
     /*
      * Gossip Tx
      */
@@ -36,4 +52,63 @@ lazy_static! {
         "network_gossip_aggregated_attestations_tx_total",
         "Count of gossip aggregated attestations transmitted"
     );
+    pub static ref GOSSIP_PUBLISH_QUEUED: Result<IntCounter> = try_create_int_counter(
+        "network_gossip_publish_queued_total",
+        "Count of gossip messages queued as no peer was subscribed to their topics"
+    );
+    pub static ref GOSSIP_PUBLISH_FAILURES: Result<IntCounter> = try_create_int_counter(
+        "network_gossip_publish_failures_total",
+        "Count of gossip messages that were no longer valid before a peer subscribed to their topics"
+    );
+
+    /*
+     * Sync
+     */
+    pub static ref SYNC_BATCHES_IN_FLIGHT: Result<IntGauge> = try_create_int_gauge(
+        "network_sync_batches_in_flight",
+        "The number of range sync batches requested from peers and awaiting download"
+    );
+    pub static ref SYNC_BATCH_PROCESSING_TIMES: Result<Histogram> = try_create_histogram(
+        "network_sync_batch_processing_seconds",
+        "Time taken to process a downloaded range sync batch"
+    );
+
+    /*
+     * Beacon Processor
+     */
+    pub static ref BEACON_PROCESSOR_QUEUE_LENGTH: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "network_beacon_processor_queue_length",
+        "The number of units of work waiting to be processed, by work type",
+        &["work_type"]
+    );
+    pub static ref BEACON_PROCESSOR_WORK_DROPPED: Result<IntCounterVec> = try_create_int_counter_vec(
+        "network_beacon_processor_work_dropped_total",
+        "Count of units of work dropped because their queue was full, by work type",
+        &["work_type"]
+    );
+    pub static ref BEACON_PROCESSOR_WORKERS_ACTIVE: Result<IntGauge> = try_create_int_gauge(
+        "network_beacon_processor_workers_active",
+        "The number of workers of the beacon processor which are processing work"
+    );
+    pub static ref BEACON_PROCESSOR_WORK_TIMES: Result<HistogramVec> = try_create_histogram_vec(
+        "network_beacon_processor_work_seconds",
+        "Time taken to process a unit of work, by work type",
+        &["work_type"]
+    );
+
+    /*
+     * Reprocess Queue
+     */
+    pub static ref REPROCESS_QUEUE_BLOCKS: Result<IntGauge> = try_create_int_gauge(
+        "network_reprocess_queue_blocks",
+        "The number of gossip blocks from a future slot waiting for the start of their slot"
+    );
+    pub static ref REPROCESS_QUEUE_ATTESTATIONS: Result<IntGauge> = try_create_int_gauge(
+        "network_reprocess_queue_attestations",
+        "The number of gossip attestations waiting for the import of their block"
+    );
+    pub static ref REPROCESS_QUEUE_EXPIRED_ATTESTATIONS: Result<IntCounter> = try_create_int_counter(
+        "network_reprocess_queue_expired_attestations_total",
+        "Count of gossip attestations whose block was not imported in time"
+    );
 }
@@ -0,0 +1,260 @@
+//! This houses various NAT hole punching strategies.
+//!
+//! Currently supported strategies:
+//! - UPnP
+
+use crate::{NetworkConfig, NetworkMessage};
+use futures::StreamExt;
+use slog::{debug, info};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use types::EthSpec;
+
+/// The description of the port mappings created on the router.
+const MAPPING_DESCRIPTION: &str = "lighthouse";
+/// The lease duration of the port mappings in seconds, such that the mappings expire if the node
+/// stops renewing them.
+const MAPPING_LEASE_DURATION: u32 = 3_600;
+/// The interval at which the port mappings are renewed, well within their lease duration.
+const MAPPING_RENEWAL_INTERVAL: Duration = Duration::from_secs(MAPPING_LEASE_DURATION as u64 / 2);
+
+/// Configuration required to construct the UPnP port mappings.
+#[derive(Clone)]
+pub struct UPnPConfig {
+    /// The local TCP port of libp2p.
+    tcp_port: u16,
+    /// The local UDP port of discovery.
+    udp_port: u16,
+    /// The ENR address set by the user, which is not overwritten.
+    enr_address: Option<IpAddr>,
+    /// The ENR TCP port set by the user, which is not overwritten.
+    enr_tcp_port: Option<u16>,
+    /// The ENR UDP port set by the user, which is not overwritten.
+    enr_udp_port: Option<u16>,
+}
+
+impl From<&NetworkConfig> for UPnPConfig {
+    fn from(config: &NetworkConfig) -> Self {
+        UPnPConfig {
+            tcp_port: config.libp2p_port,
+            udp_port: config.discovery_port,
+            enr_address: config.enr_address,
+            enr_tcp_port: config.enr_tcp_port,
+            enr_udp_port: config.enr_udp_port,
+        }
+    }
+}
+
+impl UPnPConfig {
+    /// Returns the TCP and UDP sockets to advertise in the local ENR, given the `external_ip` of
+    /// the gateway and which of the ports were mapped.
+    ///
+    /// A port set explicitly by the user is never replaced, nor is the address of the UDP socket.
+    fn enr_sockets(
+        &self,
+        external_ip: IpAddr,
+        tcp_mapped: bool,
+        udp_mapped: bool,
+    ) -> (Option<SocketAddr>, Option<SocketAddr>) {
+        let tcp_socket = if tcp_mapped && self.enr_tcp_port.is_none() {
+            Some(SocketAddr::new(external_ip, self.tcp_port))
+        } else {
+            None
+        };
+        let udp_socket = if udp_mapped && self.enr_udp_port.is_none() {
+            Some(SocketAddr::new(
+                self.enr_address.unwrap_or(external_ip),
+                self.udp_port,
+            ))
+        } else {
+            None
+        };
+        (tcp_socket, udp_socket)
+    }
+}
+
+/// Spawns a task which maps the ports with UPnP and renews the mappings every
+/// `MAPPING_RENEWAL_INTERVAL`, before their lease expires.
+pub fn spawn_upnp_mappings<T: EthSpec>(
+    executor: &environment::TaskExecutor,
+    config: UPnPConfig,
+    network_send: mpsc::UnboundedSender<NetworkMessage<T>>,
+    log: slog::Logger,
+) {
+    let inner_executor = executor.clone();
+    let mut interval = tokio::time::interval(MAPPING_RENEWAL_INTERVAL);
+    let renewal_future = async move {
+        while interval.next().await.is_some() {
+            let config = config.clone();
+            let network_send = network_send.clone();
+            let log = log.clone();
+            inner_executor.spawn_blocking(
+                move || construct_upnp_mappings(config, network_send, log),
+                "UPnP",
+            );
+        }
+    };
+    executor.spawn(renewal_future, "UPnP renewal");
+}
+
+/// Attempts to map the libp2p TCP port and the discovery UDP port on the local router with UPnP.
+///
+/// The external sockets of any established mappings are sent to the network service, such that
+/// the local ENR can be updated.
+///
+/// NOTE: This function is blocking and should be run on a dedicated thread.
+fn construct_upnp_mappings<T: EthSpec>(
+    config: UPnPConfig,
+    network_send: mpsc::UnboundedSender<NetworkMessage<T>>,
+    log: slog::Logger,
+) {
+    debug!(log, "UPnP attempting to initialise routes");
+    let gateway = match igd::search_gateway(Default::default()) {
+        Ok(gateway) => gateway,
+        Err(e) => {
+            info!(log, "UPnP not available"; "error" => format!("{}", e));
+            return;
+        }
+    };
+
+    let local_ip = match local_ip_towards(*gateway.addr.ip()) {
+        Some(ip) => ip,
+        None => {
+            info!(
+                log,
+                "UPnP could not determine the local address of the gateway's network"
+            );
+            return;
+        }
+    };
+
+    let external_ip = match gateway.get_external_ip() {
+        Ok(ip) => IpAddr::V4(ip),
+        Err(e) => {
+            info!(log, "UPnP could not obtain the external address"; "error" => format!("{}", e));
+            return;
+        }
+    };
+
+    let tcp_mapped = add_port_mapping(
+        &gateway,
+        igd::PortMappingProtocol::TCP,
+        SocketAddrV4::new(local_ip, config.tcp_port),
+        &log,
+    );
+
+    let udp_mapped = add_port_mapping(
+        &gateway,
+        igd::PortMappingProtocol::UDP,
+        SocketAddrV4::new(local_ip, config.udp_port),
+        &log,
+    );
+
+    if tcp_mapped || udp_mapped {
+        debug!(log, "UPnP mappings established";
+            "external_ip" => format!("{}", external_ip),
+            "tcp" => tcp_mapped,
+            "udp" => udp_mapped);
+    }
+
+    let (tcp_socket, udp_socket) = config.enr_sockets(external_ip, tcp_mapped, udp_mapped);
+    if tcp_socket.is_some() || udp_socket.is_some() {
+        let _ = network_send
+            .send(NetworkMessage::UPnPMappingEstablished {
+                tcp_socket,
+                udp_socket,
+            })
+            .map_err(|_| debug!(log, "Could not send UPnP mappings to the network service"));
+    }
+}
+
+/// Maps the port of `local_socket` on the gateway to the same external port for
+/// `MAPPING_LEASE_DURATION`, returning `true` if successful. Mapping a port again renews its lease.
+fn add_port_mapping(
+    gateway: &igd::Gateway,
+    protocol: igd::PortMappingProtocol,
+    local_socket: SocketAddrV4,
+    log: &slog::Logger,
+) -> bool {
+    gateway
+        .add_port(
+            protocol,
+            local_socket.port(),
+            local_socket,
+            MAPPING_LEASE_DURATION,
+            MAPPING_DESCRIPTION,
+        )
+        .map_err(|e| {
+            info!(log, "UPnP could not map port";
+                "protocol" => format!("{:?}", protocol),
+                "port" => local_socket.port(),
+                "error" => format!("{}", e))
+        })
+        .is_ok()
+}
+
+/// Returns the local IPv4 address used to route packets to `gateway_ip`.
+fn local_ip_towards(gateway_ip: Ipv4Addr) -> Option<Ipv4Addr> {
+    // connecting a UDP socket sends no packets, but binds it to the local address of the route
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((gateway_ip, 1900)).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) => Some(ip),
+        IpAddr::V6(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTERNAL_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+
+    fn config() -> UPnPConfig {
+        UPnPConfig {
+            tcp_port: 9000,
+            udp_port: 9001,
+            enr_address: None,
+            enr_tcp_port: None,
+            enr_udp_port: None,
+        }
+    }
+
+    #[test]
+    fn mapped_ports_are_advertised() {
+        assert_eq!(
+            config().enr_sockets(EXTERNAL_IP, true, true),
+            (
+                Some(SocketAddr::new(EXTERNAL_IP, 9000)),
+                Some(SocketAddr::new(EXTERNAL_IP, 9001))
+            )
+        );
+        assert_eq!(
+            config().enr_sockets(EXTERNAL_IP, false, true),
+            (None, Some(SocketAddr::new(EXTERNAL_IP, 9001)))
+        );
+        assert_eq!(
+            config().enr_sockets(EXTERNAL_IP, false, false),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn explicit_enr_fields_are_not_overwritten() {
+        let enr_address = IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8));
+        let mut config = config();
+        config.enr_address = Some(enr_address);
+        assert_eq!(
+            config.enr_sockets(EXTERNAL_IP, true, true),
+            (
+                Some(SocketAddr::new(EXTERNAL_IP, 9000)),
+                Some(SocketAddr::new(enr_address, 9001))
+            )
+        );
+
+        config.enr_tcp_port = Some(19000);
+        config.enr_udp_port = Some(19001);
+        assert_eq!(config.enr_sockets(EXTERNAL_IP, true, true), (None, None));
+    }
+}
@@ -1,8 +1,9 @@
+use crate::persisted_item::{load_item, persist_item};
 use eth2_libp2p::Enr;
 use rlp;
 use std::sync::Arc;
 use store::{DBColumn, Error as StoreError, HotColdDB, ItemStore, StoreItem};
-use types::{EthSpec, Hash256};
+use types::EthSpec;
 
 /// 32-byte key for accessing the `DhtEnrs`.
 pub const DHT_DB_KEY: &str = "PERSISTEDDHTPERSISTEDDHTPERSISTE";
@@ -11,14 +12,7 @@ pub fn load_dht<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
     store: Arc<HotColdDB<E, Hot, Cold>>,
 ) -> Vec<Enr> {
     // Load DHT from store
-    let key = Hash256::from_slice(&DHT_DB_KEY.as_bytes());
-    match store.get_item(&key) {
-        Ok(Some(p)) => {
-            let p: PersistedDht = p;
-            p.enrs
-        }
-        _ => Vec::new(),
-    }
+    load_item::<_, _, _, PersistedDht>(store, DHT_DB_KEY).map_or_else(Vec::new, |p| p.enrs)
 }
 
 /// Attempt to persist the ENR's in the DHT to `self.store`.
@@ -26,9 +20,7 @@ pub fn persist_dht<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
     store: Arc<HotColdDB<E, Hot, Cold>>,
     enrs: Vec<Enr>,
 ) -> Result<(), store::Error> {
-    let key = Hash256::from_slice(&DHT_DB_KEY.as_bytes());
-    store.put_item(&key, &PersistedDht { enrs })?;
-    Ok(())
+    persist_item(store, DHT_DB_KEY, &PersistedDht { enrs })
 }
 
 /// Wrapper around DHT for persistence to disk.
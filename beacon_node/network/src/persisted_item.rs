@@ -0,0 +1,22 @@
+use std::sync::Arc;
+use store::{HotColdDB, ItemStore, StoreItem};
+use types::{EthSpec, Hash256};
+
+/// Loads the item persisted under the 32-byte `key`, if it exists and can be decoded.
+pub fn load_item<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>, I: StoreItem>(
+    store: Arc<HotColdDB<E, Hot, Cold>>,
+    key: &str,
+) -> Option<I> {
+    let key = Hash256::from_slice(&key.as_bytes());
+    store.get_item(&key).ok().flatten()
+}
+
+/// Attempt to persist `item` under the 32-byte `key`.
+pub fn persist_item<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>, I: StoreItem>(
+    store: Arc<HotColdDB<E, Hot, Cold>>,
+    key: &str,
+    item: &I,
+) -> Result<(), store::Error> {
+    let key = Hash256::from_slice(&key.as_bytes());
+    store.put_item(&key, item)
+}
@@ -0,0 +1,127 @@
+use crate::persisted_item::{load_item, persist_item};
+use eth2_libp2p::{Enr, PersistedPeer};
+use rlp::{Rlp, RlpStream};
+use std::sync::Arc;
+use store::{DBColumn, Error as StoreError, HotColdDB, ItemStore, StoreItem};
+use types::EthSpec;
+
+/// 32-byte key for accessing the `PersistedPeerDb`.
+pub const PEER_DB_KEY: &str = "PERSISTEDPEERDBPERSISTEDPEERDBPE";
+
+pub fn load_peers<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
+    store: Arc<HotColdDB<E, Hot, Cold>>,
+) -> Vec<PersistedPeer> {
+    // Load the peer database from store
+    load_item::<_, _, _, PersistedPeerDb>(store, PEER_DB_KEY).map_or_else(Vec::new, |p| p.peers)
+}
+
+/// Attempt to persist the known peers to `self.store`.
+pub fn persist_peers<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
+    store: Arc<HotColdDB<E, Hot, Cold>>,
+    peers: Vec<PersistedPeer>,
+) -> Result<(), store::Error> {
+    persist_item(store, PEER_DB_KEY, &PersistedPeerDb { peers })
+}
+
+/// Wrapper around the known peers for persistence to disk.
+pub struct PersistedPeerDb {
+    pub peers: Vec<PersistedPeer>,
+}
+
+impl StoreItem for PersistedPeerDb {
+    fn db_column() -> DBColumn {
+        DBColumn::PeerDb
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(self.peers.len());
+        for peer in &self.peers {
+            stream.begin_list(5);
+            stream.append(&peer.enr);
+            stream.append(&peer.reputation);
+            // rlp has no floating point encoding
+            stream.append(&peer.gossip_score.to_bits());
+            stream.append(&peer.banned);
+            stream.append(&peer.last_seen);
+        }
+        stream.out()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        let decode = |rlp: Rlp| -> Result<PersistedPeer, rlp::DecoderError> {
+            Ok(PersistedPeer {
+                enr: rlp.val_at::<Enr>(0)?,
+                reputation: rlp.val_at(1)?,
+                gossip_score: f64::from_bits(rlp.val_at(2)?),
+                banned: rlp.val_at(3)?,
+                last_seen: rlp.val_at(4)?,
+            })
+        };
+
+        let peers = Rlp::new(bytes)
+            .iter()
+            .map(decode)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| StoreError::RlpError(format!("{}", e)))?;
+        Ok(PersistedPeerDb { peers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sloggers::{null::NullLoggerBuilder, Build};
+    use std::str::FromStr;
+    use store::config::StoreConfig;
+    use store::{HotColdDB, MemoryStore};
+    use types::{ChainSpec, Hash256, MinimalEthSpec};
+
+    const ENR: &str = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+
+    #[test]
+    fn test_persisted_peers() {
+        let log = NullLoggerBuilder.build().unwrap();
+        let store: HotColdDB<
+            MinimalEthSpec,
+            MemoryStore<MinimalEthSpec>,
+            MemoryStore<MinimalEthSpec>,
+        > = HotColdDB::open_ephemeral(StoreConfig::default(), ChainSpec::minimal(), log).unwrap();
+        let peers = vec![PersistedPeer {
+            enr: Enr::from_str(ENR).unwrap(),
+            reputation: 42,
+            gossip_score: 1.5,
+            banned: false,
+            last_seen: 1_593_000_000,
+        }];
+        let key = Hash256::from_slice(&PEER_DB_KEY.as_bytes());
+        store
+            .put_item(
+                &key,
+                &PersistedPeerDb {
+                    peers: peers.clone(),
+                },
+            )
+            .unwrap();
+        let peer_db: PersistedPeerDb = store.get_item(&key).unwrap().unwrap();
+        assert_eq!(peer_db.peers, peers);
+    }
+
+    #[test]
+    fn test_persisted_peers_keep_bans_and_gossip_scores() {
+        let log = NullLoggerBuilder.build().unwrap();
+        let store: Arc<
+            HotColdDB<MinimalEthSpec, MemoryStore<MinimalEthSpec>, MemoryStore<MinimalEthSpec>>,
+        > = Arc::new(
+            HotColdDB::open_ephemeral(StoreConfig::default(), ChainSpec::minimal(), log).unwrap(),
+        );
+        let peers = vec![PersistedPeer {
+            enr: Enr::from_str(ENR).unwrap(),
+            reputation: 0,
+            gossip_score: -123.25,
+            banned: true,
+            last_seen: 1_593_000_000,
+        }];
+        persist_peers(store.clone(), peers.clone()).unwrap();
+        assert_eq!(load_peers(store), peers);
+    }
+}
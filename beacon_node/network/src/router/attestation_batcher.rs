@@ -0,0 +1,72 @@
+//! Buffers the unaggregated attestations received on gossip, so that their signatures can be
+//! verified in batches.
+//!
+//! A batch is released once it reaches `MAX_BATCH_SIZE` attestations, or once `BATCH_WINDOW` has
+//! elapsed since its first attestation arrived, whichever happens first.
+
+use eth2_libp2p::{MessageId, PeerId};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use types::{Attestation, EthSpec, SubnetId};
+
+/// The maximum number of attestations in a batch.
+const MAX_BATCH_SIZE: usize = 64;
+
+/// The maximum time an attestation waits for the rest of its batch.
+const BATCH_WINDOW: Duration = Duration::from_millis(50);
+
+/// An unaggregated attestation received on gossip, awaiting verification.
+pub struct BatchedAttestation<E: EthSpec> {
+    pub message_id: MessageId,
+    pub peer_id: PeerId,
+    pub subnet_id: SubnetId,
+    pub attestation: Attestation<E>,
+}
+
+/// Collects `BatchedAttestation`s into batches, which are given to a handler for verification.
+pub struct AttestationBatcher<E: EthSpec> {
+    pending: Arc<Mutex<Vec<BatchedAttestation<E>>>>,
+    on_batch: Arc<dyn Fn(Vec<BatchedAttestation<E>>) + Send + Sync>,
+    executor: environment::TaskExecutor,
+}
+
+impl<E: EthSpec> AttestationBatcher<E> {
+    /// Creates a batcher which calls `on_batch` with each released batch.
+    pub fn new<F>(executor: environment::TaskExecutor, on_batch: F) -> Self
+    where
+        F: Fn(Vec<BatchedAttestation<E>>) + Send + Sync + 'static,
+    {
+        Self {
+            pending: Arc::new(Mutex::new(Vec::with_capacity(MAX_BATCH_SIZE))),
+            on_batch: Arc::new(on_batch),
+            executor,
+        }
+    }
+
+    /// Adds `attestation` to the current batch, releasing the batch if it is full.
+    pub fn push(&self, attestation: BatchedAttestation<E>) {
+        let mut pending = self.pending.lock();
+        pending.push(attestation);
+
+        if pending.len() >= MAX_BATCH_SIZE {
+            let batch = std::mem::replace(&mut *pending, Vec::with_capacity(MAX_BATCH_SIZE));
+            drop(pending);
+            (self.on_batch)(batch);
+        } else if pending.len() == 1 {
+            // The first attestation of a batch starts the window of the batch.
+            let pending = self.pending.clone();
+            let on_batch = self.on_batch.clone();
+            self.executor.spawn(
+                async move {
+                    tokio::time::delay_for(BATCH_WINDOW).await;
+                    let batch = std::mem::take(&mut *pending.lock());
+                    if !batch.is_empty() {
+                        on_batch(batch);
+                    }
+                },
+                "attestation_batch_window",
+            );
+        }
+    }
+}
@@ -4,20 +4,27 @@
 //! and processes those that are
 #![allow(clippy::unit_arg)]
 
+mod attestation_batcher;
 pub mod processor;
+mod reprocess_queue;
 
+use crate::beacon_processor::{self, BeaconProcessorSend, WorkType};
 use crate::error;
 use crate::service::NetworkMessage;
-use beacon_chain::{BeaconChain, BeaconChainTypes, BlockError};
+use crate::sync::BlockProcessorEvent;
+use attestation_batcher::{AttestationBatcher, BatchedAttestation};
+use beacon_chain::{BeaconChain, BeaconChainTypes};
 use eth2_libp2p::{
     rpc::{RPCError, RequestId},
-    MessageId, NetworkGlobals, PeerId, PeerRequestId, PubsubMessage, Request, Response,
+    MessageAcceptance, MessageId, NetworkGlobals, PeerId, PeerRequestId, PubsubMessage, Request,
+    Response,
 };
 use futures::prelude::*;
 use processor::Processor;
-use slog::{debug, info, o, trace, warn};
+use reprocess_queue::{QueuedAttestation, QueuedBlock, ReadyWork};
+use slog::{debug, o, trace, warn};
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use types::EthSpec;
 
 /// Handles messages received from the network and client and organises syncing. This
@@ -32,6 +39,11 @@ pub struct Router<T: BeaconChainTypes> {
     /// Processes validated and decoded messages from the network. Has direct access to the
     /// sync manager.
     processor: Processor<T>,
+    /// Prioritises the verification and import of gossip blocks and attestations, and of the
+    /// blocks downloaded by sync.
+    beacon_processor: BeaconProcessorSend,
+    /// Collects unaggregated gossip attestations into batches for the beacon processor.
+    attestation_batcher: AttestationBatcher<T::EthSpec>,
     /// The `Router` logger.
     log: slog::Logger,
 }
@@ -61,6 +73,11 @@ pub enum RouterMessage<T: EthSpec> {
         request_id: RequestId,
         error: RPCError,
     },
+    /// A peer failed to respond to an RPC request in time.
+    RPCTimeout {
+        peer_id: PeerId,
+        request_id: RequestId,
+    },
     /// A gossip message has been received. The fields are: message id, the peer that sent us this
     /// message and the message itself.
     PubsubMessage(MessageId, PeerId, PubsubMessage<T>),
@@ -70,10 +87,14 @@ pub enum RouterMessage<T: EthSpec> {
 
 impl<T: BeaconChainTypes> Router<T> {
     /// Initializes and runs the Router.
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         beacon_chain: Arc<BeaconChain<T>>,
         network_globals: Arc<NetworkGlobals<T::EthSpec>>,
         network_send: mpsc::UnboundedSender<NetworkMessage<T::EthSpec>>,
+        block_processor_events: broadcast::Sender<BlockProcessorEvent>,
+        block_lookup_delay: f64,
+        batch_lookahead: u64,
         executor: environment::TaskExecutor,
         log: slog::Logger,
     ) -> error::Result<mpsc::UnboundedSender<RouterMessage<T::EthSpec>>> {
@@ -82,20 +103,71 @@ impl<T: BeaconChainTypes> Router<T> {
 
         let (handler_send, handler_recv) = mpsc::unbounded_channel();
 
+        let beacon_processor = beacon_processor::spawn(
+            executor.clone(),
+            num_cpus::get(),
+            log.new(o!("service" => "beacon_processor")),
+        );
+
+        let (ready_send, mut ready_recv) = mpsc::unbounded_channel();
+        let fork_choice_chain = beacon_chain.clone();
+        let reprocess_send = reprocess_queue::spawn(
+            executor.clone(),
+            block_processor_events.subscribe(),
+            ready_send,
+            move |block_root| fork_choice_chain.fork_choice.contains_block(block_root),
+            log.new(o!("service" => "reprocess_queue")),
+        );
+
         // Initialise a message instance, which itself spawns the syncing thread.
         let processor = Processor::new(
             executor.clone(),
             beacon_chain,
             network_globals.clone(),
             network_send.clone(),
+            beacon_processor.clone(),
+            block_processor_events,
+            reprocess_send,
+            block_lookup_delay,
+            batch_lookahead,
             &log,
         );
 
+        let attestation_batcher = {
+            let processor = processor.clone();
+            let beacon_processor = beacon_processor.clone();
+            AttestationBatcher::new(executor.clone(), move |batch| {
+                let on_drop = ignore_dropped_messages(&processor, batched_messages(&batch));
+                let mut processor = processor.clone();
+                beacon_processor.submit_with_drop_handler(
+                    WorkType::GossipAttestation,
+                    move || processor.process_unaggregated_attestation_batch(batch, true),
+                    on_drop,
+                );
+            })
+        };
+
+        // Messages released by the reprocess queue are verified again, but never held again.
+        {
+            let processor = processor.clone();
+            let beacon_processor = beacon_processor.clone();
+            executor.spawn(
+                async move {
+                    while let Some(work) = ready_recv.recv().await {
+                        submit_ready_work(&processor, &beacon_processor, work);
+                    }
+                },
+                "reprocess_ready_work",
+            );
+        }
+
         // generate the Message handler
         let mut handler = Router {
             network_send,
             network_globals,
             processor,
+            beacon_processor,
+            attestation_batcher,
             log: message_handler_log,
         };
 
@@ -151,6 +223,16 @@ impl<T: BeaconChainTypes> Router<T> {
                     "client" => self.network_globals.client(&peer_id).to_string());
                 self.processor.on_rpc_error(peer_id, request_id);
             }
+            RouterMessage::RPCTimeout {
+                peer_id,
+                request_id,
+            } => {
+                debug!(self.log, "RPC Timeout";
+                    "peer_id" => peer_id.to_string(),
+                    "request_id" => request_id,
+                    "client" => self.network_globals.client(&peer_id).to_string());
+                self.processor.on_rpc_timeout(peer_id, request_id);
+            }
             RouterMessage::PubsubMessage(id, peer_id, gossip) => {
                 self.handle_gossip(id, peer_id, gossip);
             }
@@ -209,6 +291,11 @@ impl<T: BeaconChainTypes> Router<T> {
     }
 
     /// Handle RPC messages
+    ///
+    /// Blocks and attestations are verified and imported by the beacon processor, according to
+    /// the priority of their `WorkType`. Unaggregated attestations are first collected into
+    /// batches, so that their signatures can be verified together. Blocks and attestations which
+    /// arrive too early to be verified are held by the reprocess queue.
     fn handle_gossip(
         &mut self,
         id: MessageId,
@@ -218,77 +305,194 @@ impl<T: BeaconChainTypes> Router<T> {
         match gossip_message {
             // Attestations should never reach the router.
             PubsubMessage::AggregateAndProofAttestation(aggregate_and_proof) => {
-                if let Some(gossip_verified) =
-                    self.processor.verify_aggregated_attestation_for_gossip(
-                        peer_id.clone(),
-                        *aggregate_and_proof.clone(),
-                    )
-                {
-                    self.propagate_message(id, peer_id.clone());
-                    self.processor
-                        .import_aggregated_attestation(peer_id, gossip_verified);
-                }
+                let on_drop =
+                    ignore_dropped_messages(&self.processor, vec![(id.clone(), peer_id.clone())]);
+                let mut processor = self.processor.clone();
+                self.beacon_processor.submit_with_drop_handler(
+                    WorkType::GossipAggregate,
+                    move || {
+                        processor.process_gossip_aggregate(id, peer_id, aggregate_and_proof, true)
+                    },
+                    on_drop,
+                );
             }
+            // Unaggregated attestations are verified in batches.
             PubsubMessage::Attestation(subnet_attestation) => {
-                if let Some(gossip_verified) =
-                    self.processor.verify_unaggregated_attestation_for_gossip(
-                        peer_id.clone(),
-                        subnet_attestation.1.clone(),
-                    )
-                {
-                    self.propagate_message(id, peer_id.clone());
-                    self.processor
-                        .import_unaggregated_attestation(peer_id, gossip_verified);
-                }
+                let (subnet_id, attestation) = *subnet_attestation;
+                self.attestation_batcher.push(BatchedAttestation {
+                    message_id: id,
+                    peer_id,
+                    subnet_id,
+                    attestation,
+                });
             }
             PubsubMessage::BeaconBlock(block) => {
-                match self.processor.should_forward_block(&peer_id, block) {
-                    Ok(verified_block) => {
-                        info!(self.log, "New block received"; "slot" => verified_block.block.slot(), "hash" => verified_block.block_root.to_string());
-                        self.propagate_message(id, peer_id.clone());
-                        self.processor.on_block_gossip(peer_id, verified_block);
-                    }
-                    Err(BlockError::ParentUnknown { .. }) => {} // performing a parent lookup
-                    Err(e) => {
-                        // performing a parent lookup
-                        warn!(self.log, "Could not verify block for gossip";
-                            "error" => format!("{:?}", e));
-                    }
-                }
+                // The time of arrival is recorded before the block waits in the beacon processor.
+                let seen_timestamp = self.processor.now_duration();
+                let on_drop =
+                    ignore_dropped_messages(&self.processor, vec![(id.clone(), peer_id.clone())]);
+                let mut processor = self.processor.clone();
+                self.beacon_processor.submit_with_drop_handler(
+                    WorkType::GossipBlock,
+                    move || {
+                        processor.process_gossip_block(id, peer_id, block, seen_timestamp, true)
+                    },
+                    on_drop,
+                );
             }
-            PubsubMessage::VoluntaryExit(_exit) => {
-                // TODO: Apply more sophisticated validation
-                self.propagate_message(id, peer_id.clone());
-                // TODO: Handle exits
-                debug!(self.log, "Received a voluntary exit"; "peer_id" => format!("{}", peer_id) );
+            PubsubMessage::VoluntaryExit(exit) => {
+                let validation_result = self
+                    .processor
+                    .process_gossip_voluntary_exit(&peer_id, *exit);
+                self.propagate_validation_result(id, peer_id, validation_result);
             }
-            PubsubMessage::ProposerSlashing(_proposer_slashing) => {
-                // TODO: Apply more sophisticated validation
-                self.propagate_message(id, peer_id.clone());
-                // TODO: Handle proposer slashings
-                debug!(self.log, "Received a proposer slashing"; "peer_id" => format!("{}", peer_id) );
+            PubsubMessage::ProposerSlashing(proposer_slashing) => {
+                let validation_result = self
+                    .processor
+                    .process_gossip_proposer_slashing(&peer_id, *proposer_slashing);
+                self.propagate_validation_result(id, peer_id, validation_result);
             }
-            PubsubMessage::AttesterSlashing(_attester_slashing) => {
-                // TODO: Apply more sophisticated validation
-                self.propagate_message(id, peer_id.clone());
-                // TODO: Handle attester slashings
-                debug!(self.log, "Received an attester slashing"; "peer_id" => format!("{}", peer_id) );
+            PubsubMessage::AttesterSlashing(attester_slashing) => {
+                let validation_result = self
+                    .processor
+                    .process_gossip_attester_slashing(&peer_id, *attester_slashing);
+                self.propagate_validation_result(id, peer_id, validation_result);
             }
         }
     }
 
-    /// Informs the network service that the message should be forwarded to other peers.
-    fn propagate_message(&mut self, message_id: MessageId, propagation_source: PeerId) {
+    /// Informs the network service of the validation result of a gossipsub message. Accepted
+    /// messages are forwarded to other peers.
+    fn propagate_validation_result(
+        &mut self,
+        message_id: MessageId,
+        propagation_source: PeerId,
+        validation_result: MessageAcceptance,
+    ) {
         self.network_send
-            .send(NetworkMessage::Propagate {
+            .send(NetworkMessage::Validate {
                 propagation_source,
                 message_id,
+                validation_result,
             })
             .unwrap_or_else(|_| {
                 warn!(
                     self.log,
-                    "Could not send propagation request to the network service"
+                    "Could not send validation result to the network service"
                 )
             });
     }
 }
+
+/// Submits the messages released by the reprocess queue to the beacon processor. Attestations
+/// whose block was not imported in time are ignored.
+fn submit_ready_work<T: BeaconChainTypes>(
+    processor: &Processor<T>,
+    beacon_processor: &BeaconProcessorSend,
+    work: ReadyWork<T::EthSpec>,
+) {
+    match work {
+        ReadyWork::Block(QueuedBlock {
+            message_id,
+            peer_id,
+            block,
+            seen_timestamp,
+        }) => {
+            let on_drop =
+                ignore_dropped_messages(processor, vec![(message_id.clone(), peer_id.clone())]);
+            let mut processor = processor.clone();
+            beacon_processor.submit_with_drop_handler(
+                WorkType::GossipBlock,
+                move || {
+                    processor.process_gossip_block(
+                        message_id,
+                        peer_id,
+                        block,
+                        seen_timestamp,
+                        false,
+                    )
+                },
+                on_drop,
+            );
+        }
+        ReadyWork::Attestations(attestations) => {
+            let mut batch = vec![];
+            for attestation in attestations {
+                match attestation {
+                    QueuedAttestation::Aggregate {
+                        message_id,
+                        peer_id,
+                        aggregate,
+                    } => {
+                        let on_drop = ignore_dropped_messages(
+                            processor,
+                            vec![(message_id.clone(), peer_id.clone())],
+                        );
+                        let mut processor = processor.clone();
+                        beacon_processor.submit_with_drop_handler(
+                            WorkType::GossipAggregate,
+                            move || {
+                                processor
+                                    .process_gossip_aggregate(message_id, peer_id, aggregate, false)
+                            },
+                            on_drop,
+                        );
+                    }
+                    QueuedAttestation::Unaggregated(attestation) => batch.push(attestation),
+                }
+            }
+            if !batch.is_empty() {
+                let on_drop = ignore_dropped_messages(processor, batched_messages(&batch));
+                let mut processor = processor.clone();
+                beacon_processor.submit_with_drop_handler(
+                    WorkType::GossipAttestation,
+                    move || processor.process_unaggregated_attestation_batch(batch, false),
+                    on_drop,
+                );
+            }
+        }
+        ReadyWork::ExpiredAttestations(attestations) => {
+            let mut processor = processor.clone();
+            for attestation in attestations {
+                let (message_id, peer_id) = match attestation {
+                    QueuedAttestation::Aggregate {
+                        message_id,
+                        peer_id,
+                        ..
+                    } => (message_id, peer_id),
+                    QueuedAttestation::Unaggregated(attestation) => {
+                        (attestation.message_id, attestation.peer_id)
+                    }
+                };
+                processor.propagate_validation_result(
+                    message_id,
+                    peer_id,
+                    MessageAcceptance::Ignore,
+                );
+            }
+        }
+    }
+}
+
+/// Returns a drop handler for gossip work, which informs the network service that the gossipsub
+/// `messages` of the work were ignored. Otherwise, the messages would remain pending until they
+/// time out.
+fn ignore_dropped_messages<T: BeaconChainTypes>(
+    processor: &Processor<T>,
+    messages: Vec<(MessageId, PeerId)>,
+) -> impl FnOnce() + Send + 'static {
+    let mut processor = processor.clone();
+    move || {
+        for (message_id, peer_id) in messages {
+            processor.propagate_validation_result(message_id, peer_id, MessageAcceptance::Ignore);
+        }
+    }
+}
+
+/// Returns the gossipsub message of each attestation of `batch`.
+fn batched_messages<E: EthSpec>(batch: &[BatchedAttestation<E>]) -> Vec<(MessageId, PeerId)> {
+    batch
+        .iter()
+        .map(|attestation| (attestation.message_id.clone(), attestation.peer_id.clone()))
+        .collect()
+}
@@ -1,22 +1,32 @@
+use super::attestation_batcher::BatchedAttestation;
+use super::reprocess_queue::{QueuedAttestation, QueuedBlock, ReprocessQueueMessage};
+use crate::beacon_processor::BeaconProcessorSend;
 use crate::service::NetworkMessage;
-use crate::sync::{PeerSyncInfo, SyncMessage};
+use crate::sync::{
+    is_invalid_block, BlockProcessorEvent, PeerSyncInfo, SyncMessage, SyncRng, SystemClock,
+};
 use beacon_chain::{
     attestation_verification::{
         Error as AttnError, IntoForkChoiceVerifiedAttestation, VerifiedAggregatedAttestation,
         VerifiedUnaggregatedAttestation,
     },
-    BeaconChain, BeaconChainTypes, BlockError, BlockProcessingOutcome, GossipVerifiedBlock,
+    BeaconChain, BeaconChainTypes, BlockError, BlockProcessingOutcome, GossipOperationOutcome,
+    GossipVerifiedBlock,
 };
 use eth2_libp2p::rpc::*;
-use eth2_libp2p::{NetworkGlobals, PeerId, PeerRequestId, Request, Response};
+use eth2_libp2p::{
+    MessageAcceptance, MessageId, NetworkGlobals, PeerId, PeerRequestId, Request, Response,
+};
 use itertools::process_results;
-use slog::{debug, error, o, trace, warn};
+use slog::{debug, error, info, o, trace, warn};
+use slot_clock::SlotClock;
 use ssz::Encode;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
 use types::{
-    Attestation, ChainSpec, Epoch, EthSpec, Hash256, SignedAggregateAndProof, SignedBeaconBlock,
-    Slot,
+    Attestation, AttesterSlashing, ChainSpec, Epoch, EthSpec, Hash256, ProposerSlashing,
+    SignedAggregateAndProof, SignedBeaconBlock, SignedVoluntaryExit, Slot, SubnetId,
 };
 
 //TODO: Rate limit requests
@@ -34,17 +44,37 @@ pub struct Processor<T: BeaconChainTypes> {
     sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
     /// A network context to return and handle RPC requests.
     network: HandlerNetworkContext<T::EthSpec>,
+    /// A channel to the queue of gossip messages which arrived too early to be verified.
+    reprocess_send: mpsc::UnboundedSender<ReprocessQueueMessage<T::EthSpec>>,
     /// The `RPCHandler` logger.
     log: slog::Logger,
 }
 
+impl<T: BeaconChainTypes> Clone for Processor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            chain: self.chain.clone(),
+            sync_send: self.sync_send.clone(),
+            network: self.network.clone(),
+            reprocess_send: self.reprocess_send.clone(),
+            log: self.log.clone(),
+        }
+    }
+}
+
 impl<T: BeaconChainTypes> Processor<T> {
     /// Instantiate a `Processor` instance
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         executor: environment::TaskExecutor,
         beacon_chain: Arc<BeaconChain<T>>,
         network_globals: Arc<NetworkGlobals<T::EthSpec>>,
         network_send: mpsc::UnboundedSender<NetworkMessage<T::EthSpec>>,
+        beacon_processor: BeaconProcessorSend,
+        block_processor_events: broadcast::Sender<BlockProcessorEvent>,
+        reprocess_send: mpsc::UnboundedSender<ReprocessQueueMessage<T::EthSpec>>,
+        block_lookup_delay: f64,
+        batch_lookahead: u64,
         log: &slog::Logger,
     ) -> Self {
         let sync_logger = log.new(o!("service"=> "sync"));
@@ -55,6 +85,12 @@ impl<T: BeaconChainTypes> Processor<T> {
             beacon_chain.clone(),
             network_globals,
             network_send.clone(),
+            beacon_processor,
+            block_processor_events,
+            block_lookup_delay,
+            batch_lookahead,
+            SyncRng::from_entropy(),
+            Arc::new(SystemClock),
             sync_logger,
         );
 
@@ -62,10 +98,23 @@ impl<T: BeaconChainTypes> Processor<T> {
             chain: beacon_chain,
             sync_send,
             network: HandlerNetworkContext::new(network_send, log.clone()),
+            reprocess_send,
             log: log.clone(),
         }
     }
 
+    /// Informs the network service of the validation result of a gossipsub message. Accepted
+    /// messages are forwarded to other peers.
+    pub fn propagate_validation_result(
+        &mut self,
+        message_id: MessageId,
+        propagation_source: PeerId,
+        validation_result: MessageAcceptance,
+    ) {
+        self.network
+            .propagate_validation_result(message_id, propagation_source, validation_result);
+    }
+
     fn send_to_sync(&mut self, message: SyncMessage<T::EthSpec>) {
         self.sync_send.send(message).unwrap_or_else(|_| {
             warn!(
@@ -75,6 +124,15 @@ impl<T: BeaconChainTypes> Processor<T> {
         });
     }
 
+    fn send_to_reprocess_queue(&mut self, message: ReprocessQueueMessage<T::EthSpec>) {
+        self.reprocess_send.send(message).unwrap_or_else(|_| {
+            warn!(
+                self.log,
+                "Could not send message to the reprocess queue";
+            )
+        });
+    }
+
     /// Handle a peer disconnect.
     ///
     /// Removes the peer from the manager.
@@ -91,6 +149,14 @@ impl<T: BeaconChainTypes> Processor<T> {
         }
     }
 
+    /// A peer failed to respond to an RPC request in time. Informs sync if the request belongs to
+    /// it.
+    pub fn on_rpc_timeout(&mut self, peer_id: PeerId, request_id: RequestId) {
+        if let RequestId::Sync(id) = request_id {
+            self.send_to_sync(SyncMessage::RpcTimeout(peer_id, id));
+        }
+    }
+
     /// Sends a `Status` message to the peer.
     ///
     /// Called when we first connect to a peer, or when the PeerManager determines we need to
@@ -178,54 +244,15 @@ impl<T: BeaconChainTypes> Processor<T> {
             }
         };
 
-        let start_slot = |epoch: Epoch| epoch.start_slot(T::EthSpec::slots_per_epoch());
-
-        if local.fork_digest != remote.fork_digest {
-            // The node is on a different network/fork, disconnect them.
+        if let Some(irrelevant_reason) = self.check_peer_relevance(&local, &remote) {
+            // The node is not on our network or chain, disconnect them.
             debug!(
                 self.log, "Handshake Failure";
                 "peer_id" => peer_id.to_string(),
-                "reason" => "incompatible forks",
+                "reason" => irrelevant_reason,
                 "our_fork" => hex::encode(local.fork_digest),
                 "their_fork" => hex::encode(remote.fork_digest)
             );
-
-            self.network
-                .disconnect(peer_id, GoodbyeReason::IrrelevantNetwork);
-        } else if remote.head_slot
-            > self.chain.slot().unwrap_or_else(|_| Slot::from(0u64)) + FUTURE_SLOT_TOLERANCE
-        {
-            // Note: If the slot_clock cannot be read, this will not error. Other system
-            // components will deal with an invalid slot clock error.
-
-            // The remotes head is on a slot that is significantly ahead of ours. This could be
-            // because they are using a different genesis time, or that theirs or our system
-            // clock is incorrect.
-            debug!(
-            self.log, "Handshake Failure";
-            "peer" => format!("{:?}", peer_id),
-            "reason" => "different system clocks or genesis time"
-            );
-            self.network
-                .disconnect(peer_id, GoodbyeReason::IrrelevantNetwork);
-        } else if remote.finalized_epoch <= local.finalized_epoch
-            && remote.finalized_root != Hash256::zero()
-            && local.finalized_root != Hash256::zero()
-            && self
-                .chain
-                .root_at_slot(start_slot(remote.finalized_epoch))
-                .map(|root_opt| root_opt != Some(remote.finalized_root))
-                .unwrap_or_else(|_| false)
-        {
-            // The remotes finalized epoch is less than or greater than ours, but the block root is
-            // different to the one in our chain.
-            //
-            // Therefore, the node is on a different chain and we should not communicate with them.
-            debug!(
-                self.log, "Handshake Failure";
-                "peer" => format!("{:?}", peer_id),
-                "reason" => "different finalized chain"
-            );
             self.network
                 .disconnect(peer_id, GoodbyeReason::IrrelevantNetwork);
         } else if remote.finalized_epoch < local.finalized_epoch {
@@ -242,12 +269,15 @@ impl<T: BeaconChainTypes> Processor<T> {
             // If a node is on a fork that has a lower finalized epoch, switching to that fork would
             // cause us to revert a finalized block. This is not permitted, therefore we have no
             // interest in their blocks.
+            //
+            // The peer is still given to sync, such that its sync state is known.
             debug!(
                 self.log,
                 "NaivePeer";
                 "peer" => format!("{:?}", peer_id),
                 "reason" => "lower finalized epoch"
             );
+            self.send_to_sync(SyncMessage::AddPeer(peer_id, remote));
         } else if self
             .chain
             .store
@@ -279,6 +309,52 @@ impl<T: BeaconChainTypes> Processor<T> {
         }
     }
 
+    /// Checks that the `Status` of a remote peer is relevant to our chain.
+    ///
+    /// Returns the reason the peer is irrelevant, or `None` if the peer is relevant.
+    fn check_peer_relevance(
+        &self,
+        local: &PeerSyncInfo,
+        remote: &PeerSyncInfo,
+    ) -> Option<&'static str> {
+        let start_slot = |epoch: Epoch| epoch.start_slot(T::EthSpec::slots_per_epoch());
+        let current_slot = self.chain.slot().ok();
+
+        if local.fork_digest != remote.fork_digest {
+            // The node is on a different network/fork.
+            Some("incompatible forks")
+        } else if remote.head_slot
+            > current_slot.unwrap_or_else(|| Slot::from(0u64)) + FUTURE_SLOT_TOLERANCE
+        {
+            // Note: If the slot_clock cannot be read, this will not error. Other system
+            // components will deal with an invalid slot clock error.
+
+            // The remotes head is on a slot that is significantly ahead of ours. This could be
+            // because they are using a different genesis time, or that theirs or our system
+            // clock is incorrect.
+            Some("different system clocks or genesis time")
+        } else if current_slot.map_or(false, |slot| start_slot(remote.finalized_epoch) > slot) {
+            // The remote has finalized an epoch that has not yet started.
+            Some("future finalized epoch")
+        } else if remote.finalized_epoch <= local.finalized_epoch
+            && remote.finalized_root != Hash256::zero()
+            && local.finalized_root != Hash256::zero()
+            && self
+                .chain
+                .root_at_slot(start_slot(remote.finalized_epoch))
+                .map(|root_opt| root_opt != Some(remote.finalized_root))
+                .unwrap_or_else(|_| false)
+        {
+            // The remotes finalized epoch is less than or greater than ours, but the block root is
+            // different to the one in our chain.
+            //
+            // Therefore, the node is on a different chain and we should not communicate with them.
+            Some("different finalized chain")
+        } else {
+            None
+        }
+    }
+
     /// Handle a `BlocksByRoot` request from the peer.
     pub fn on_blocks_by_root_request(
         &mut self,
@@ -498,17 +574,110 @@ impl<T: BeaconChainTypes> Processor<T> {
         &mut self,
         peer_id: &PeerId,
         block: Box<SignedBeaconBlock<T::EthSpec>>,
+        seen_timestamp: Duration,
     ) -> Result<GossipVerifiedBlock<T>, BlockError> {
         let result = self.chain.verify_block_for_gossip(*block.clone());
 
-        if let Err(BlockError::ParentUnknown(_)) = result {
-            // if we don't know the parent, start a parent lookup
-            // TODO: Modify the return to avoid the block clone.
-            self.send_to_sync(SyncMessage::UnknownBlock(peer_id.clone(), block));
+        match &result {
+            Ok(verified_block) => {
+                self.chain
+                    .observe_gossip_block_delay(verified_block, seen_timestamp);
+                // the block no longer needs to be looked up, if it is referenced by an attestation
+                self.send_to_sync(SyncMessage::GossipBlockReceived(verified_block.block_root));
+            }
+            Err(BlockError::ParentUnknown(_)) => {
+                // if we don't know the parent, start a parent lookup
+                // TODO: Modify the return to avoid the block clone.
+                self.send_to_sync(SyncMessage::UnknownBlock(peer_id.clone(), block));
+            }
+            Err(_) => {}
         }
         result
     }
 
+    /// Verifies a block received on gossip, informing the network of the validation result and
+    /// importing the block if it is valid.
+    ///
+    /// If `allow_reprocess` is `true`, a block from a slot which starts within one slot duration
+    /// is held by the reprocess queue until the start of its slot, rather than being ignored.
+    pub fn process_gossip_block(
+        &mut self,
+        message_id: MessageId,
+        peer_id: PeerId,
+        block: Box<SignedBeaconBlock<T::EthSpec>>,
+        seen_timestamp: Duration,
+        allow_reprocess: bool,
+    ) {
+        match self.should_forward_block(&peer_id, block.clone(), seen_timestamp) {
+            Ok(verified_block) => {
+                info!(self.log, "New block received"; "slot" => verified_block.block.slot(), "hash" => verified_block.block_root.to_string());
+                self.propagate_validation_result(
+                    message_id,
+                    peer_id.clone(),
+                    MessageAcceptance::Accept,
+                );
+                self.on_block_gossip(peer_id, verified_block);
+            }
+            Err(BlockError::ParentUnknown { .. }) => {
+                // performing a parent lookup
+                self.propagate_validation_result(message_id, peer_id, MessageAcceptance::Ignore);
+            }
+            Err(BlockError::KnownInvalidBlock(block_root)) => {
+                debug!(self.log, "Known invalid block received on gossip";
+                    "block_root" => format!("{}", block_root));
+                self.propagate_validation_result(message_id, peer_id, MessageAcceptance::Reject);
+            }
+            Err(BlockError::FutureSlot { block_slot, .. }) if allow_reprocess => {
+                let slot_clock = &self.chain.slot_clock;
+                match slot_clock.duration_to_slot(block_slot) {
+                    Some(delay) if delay <= slot_clock.slot_duration() => {
+                        debug!(self.log, "Queued early block received on gossip";
+                            "slot" => block_slot, "delay_ms" => delay.as_millis() as u64);
+                        // The validation result is given once the block is processed again.
+                        self.send_to_reprocess_queue(ReprocessQueueMessage::EarlyBlock {
+                            block: QueuedBlock {
+                                message_id,
+                                peer_id,
+                                block,
+                                seen_timestamp,
+                            },
+                            delay,
+                        });
+                    }
+                    _ => {
+                        debug!(self.log, "Ignored block from a future slot";
+                            "slot" => block_slot);
+                        self.propagate_validation_result(
+                            message_id,
+                            peer_id,
+                            MessageAcceptance::Ignore,
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                let acceptance = gossip_block_acceptance(&e);
+                if acceptance == MessageAcceptance::Reject {
+                    warn!(self.log, "Invalid block received on gossip";
+                        "error" => format!("{:?}", e));
+                } else {
+                    debug!(self.log, "Could not verify block for gossip";
+                        "error" => format!("{:?}", e));
+                }
+                self.propagate_validation_result(message_id, peer_id, acceptance);
+            }
+        }
+    }
+
+    /// Returns the present time as a duration since the UNIX epoch, according to the slot clock
+    /// of the beacon chain.
+    pub fn now_duration(&self) -> Duration {
+        self.chain
+            .slot_clock
+            .now_duration()
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+
     /// Process a gossip message declaring a new block.
     ///
     /// Attempts to apply to block to the beacon chain. May queue the block for later processing.
@@ -522,9 +691,10 @@ impl<T: BeaconChainTypes> Processor<T> {
         let block = Box::new(verified_block.block.clone());
         match BlockProcessingOutcome::shim(self.chain.process_block(verified_block)) {
             Ok(outcome) => match outcome {
-                BlockProcessingOutcome::Processed { .. } => {
+                BlockProcessingOutcome::Processed { block_root } => {
                     trace!(self.log, "Gossipsub block processed";
                             "peer_id" => format!("{:?}",peer_id));
+                    self.send_to_reprocess_queue(ReprocessQueueMessage::BlockImported(block_root));
 
                     // TODO: It would be better if we can run this _after_ we publish the block to
                     // reduce block propagation latency.
@@ -583,12 +753,20 @@ impl<T: BeaconChainTypes> Processor<T> {
 
     /// Handle an error whilst verifying an `Attestation` or `SignedAggregateAndProof` from the
     /// network.
+    ///
+    /// Informs the network of whether the message should be ignored or whether the peer
+    /// published an invalid message and should be penalised.
+    ///
+    /// If the attestation votes for an unknown block and `queued` is provided, `queued` is held
+    /// by the reprocess queue until the block is imported and no validation result is given yet.
     pub fn handle_attestation_verification_failure(
         &mut self,
         peer_id: PeerId,
+        message_id: MessageId,
         beacon_block_root: Hash256,
         attestation_type: &str,
         error: AttnError,
+        queued: Option<QueuedAttestation<T::EthSpec>>,
     ) {
         debug!(
             self.log,
@@ -598,7 +776,7 @@ impl<T: BeaconChainTypes> Processor<T> {
             "type" => format!("{:?}", attestation_type),
         );
 
-        match error {
+        let validation_result = match error {
             AttnError::FutureEpoch { .. }
             | AttnError::PastEpoch { .. }
             | AttnError::FutureSlot { .. }
@@ -609,6 +787,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message, _only_ if we trust our own clock.
                  */
+                MessageAcceptance::Ignore
             }
             AttnError::InvalidSelectionProof { .. } | AttnError::InvalidSignature => {
                 /*
@@ -616,6 +795,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
             }
             AttnError::EmptyAggregationBitfield => {
                 /*
@@ -628,6 +808,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * https://github.com/ethereum/eth2.0-specs/pull/1732
                  */
+                MessageAcceptance::Ignore
             }
             AttnError::AggregatorPubkeyUnknown(_) => {
                 /*
@@ -643,6 +824,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
             }
             AttnError::AggregatorNotInCommittee { .. } => {
                 /*
@@ -658,6 +840,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
             }
             AttnError::AttestationAlreadyKnown { .. } => {
                 /*
@@ -666,6 +849,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer is not necessarily faulty.
                  */
+                MessageAcceptance::Ignore
             }
             AttnError::AggregatorAlreadyKnown(_) => {
                 /*
@@ -674,6 +858,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer is not necessarily faulty.
                  */
+                MessageAcceptance::Ignore
             }
             AttnError::PriorAttestationKnown { .. } => {
                 /*
@@ -681,6 +866,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer is not necessarily faulty.
                  */
+                MessageAcceptance::Ignore
             }
             AttnError::ValidatorIndexTooHigh(_) => {
                 /*
@@ -689,14 +875,13 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
             }
             AttnError::UnknownHeadBlock { beacon_block_root } => {
                 // Note: its a little bit unclear as to whether or not this block is unknown or
                 // just old. See:
                 //
                 // https://github.com/sigp/lighthouse/issues/1039
-
-                // TODO: Maintain this attestation and re-process once sync completes
                 debug!(
                     self.log,
                     "Attestation for unknown block";
@@ -704,7 +889,19 @@ impl<T: BeaconChainTypes> Processor<T> {
                     "block" => format!("{}", beacon_block_root)
                 );
                 // we don't know the block, get the sync manager to handle the block lookup
-                self.send_to_sync(SyncMessage::UnknownBlockHash(peer_id, beacon_block_root));
+                self.send_to_sync(SyncMessage::UnknownBlockHash(
+                    peer_id.clone(),
+                    beacon_block_root,
+                ));
+                if let Some(attestation) = queued {
+                    // The validation result is given once the attestation is processed again.
+                    self.send_to_reprocess_queue(ReprocessQueueMessage::UnknownBlockAttestation {
+                        beacon_block_root,
+                        attestation,
+                    });
+                    return;
+                }
+                MessageAcceptance::Ignore
             }
             AttnError::UnknownTargetRoot(_) => {
                 /*
@@ -723,6 +920,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
             }
             AttnError::BadTargetEpoch => {
                 /*
@@ -731,6 +929,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
             }
             AttnError::NoCommitteeForSlotAndIndex { .. } => {
                 /*
@@ -738,6 +937,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
             }
             AttnError::NotExactlyOneAggregationBitSet(_) => {
                 /*
@@ -745,6 +945,16 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
+            }
+            AttnError::InvalidSubnetId { .. } => {
+                /*
+                 * The attestation was received on a subnet that does not correspond to its
+                 * committee index.
+                 *
+                 * The peer has published an invalid consensus message.
+                 */
+                MessageAcceptance::Reject
             }
             AttnError::AttestsToFutureBlock { .. } => {
                 /*
@@ -752,6 +962,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
             }
             AttnError::Invalid(_) => {
                 /*
@@ -759,6 +970,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                MessageAcceptance::Reject
             }
             AttnError::BeaconChainError(e) => {
                 /*
@@ -774,26 +986,70 @@ impl<T: BeaconChainTypes> Processor<T> {
                     "peer_id" => format!("{:?}", peer_id),
                     "error" => format!("{:?}", e),
                 );
+                MessageAcceptance::Ignore
             }
+        };
+
+        self.network
+            .propagate_validation_result(message_id, peer_id, validation_result);
+    }
+
+    /// Verifies an aggregate received on gossip, informing the network of the validation result
+    /// and importing the aggregate if it is valid.
+    ///
+    /// If `allow_reprocess` is `true`, an aggregate which votes for an unknown block is held by
+    /// the reprocess queue until the block is imported.
+    pub fn process_gossip_aggregate(
+        &mut self,
+        message_id: MessageId,
+        peer_id: PeerId,
+        aggregate_and_proof: Box<SignedAggregateAndProof<T::EthSpec>>,
+        allow_reprocess: bool,
+    ) {
+        if let Some(verified_aggregate) = self.verify_aggregated_attestation_for_gossip(
+            peer_id.clone(),
+            message_id.clone(),
+            aggregate_and_proof,
+            allow_reprocess,
+        ) {
+            self.propagate_validation_result(
+                message_id,
+                peer_id.clone(),
+                MessageAcceptance::Accept,
+            );
+            self.import_aggregated_attestation(peer_id, verified_aggregate);
         }
     }
 
     pub fn verify_aggregated_attestation_for_gossip(
         &mut self,
         peer_id: PeerId,
-        aggregate_and_proof: SignedAggregateAndProof<T::EthSpec>,
+        message_id: MessageId,
+        aggregate_and_proof: Box<SignedAggregateAndProof<T::EthSpec>>,
+        allow_reprocess: bool,
     ) -> Option<VerifiedAggregatedAttestation<T>> {
         // This is provided to the error handling function to assist with debugging.
         let beacon_block_root = aggregate_and_proof.message.aggregate.data.beacon_block_root;
+        let queued = if allow_reprocess {
+            Some(QueuedAttestation::Aggregate {
+                message_id: message_id.clone(),
+                peer_id: peer_id.clone(),
+                aggregate: aggregate_and_proof.clone(),
+            })
+        } else {
+            None
+        };
 
         self.chain
-            .verify_aggregated_attestation_for_gossip(aggregate_and_proof)
+            .verify_aggregated_attestation_for_gossip(*aggregate_and_proof)
             .map_err(|e| {
                 self.handle_attestation_verification_failure(
                     peer_id,
+                    message_id,
                     beacon_block_root,
                     "aggregated",
                     e,
+                    queued,
                 )
             })
             .ok()
@@ -827,24 +1083,122 @@ impl<T: BeaconChainTypes> Processor<T> {
     pub fn verify_unaggregated_attestation_for_gossip(
         &mut self,
         peer_id: PeerId,
+        message_id: MessageId,
+        subnet_id: SubnetId,
         unaggregated_attestation: Attestation<T::EthSpec>,
+        allow_reprocess: bool,
     ) -> Option<VerifiedUnaggregatedAttestation<T>> {
         // This is provided to the error handling function to assist with debugging.
         let beacon_block_root = unaggregated_attestation.data.beacon_block_root;
+        let queued = if allow_reprocess {
+            Some(QueuedAttestation::Unaggregated(BatchedAttestation {
+                message_id: message_id.clone(),
+                peer_id: peer_id.clone(),
+                subnet_id,
+                attestation: unaggregated_attestation.clone(),
+            }))
+        } else {
+            None
+        };
 
         self.chain
-            .verify_unaggregated_attestation_for_gossip(unaggregated_attestation)
+            .verify_unaggregated_attestation_for_gossip(unaggregated_attestation, subnet_id)
             .map_err(|e| {
                 self.handle_attestation_verification_failure(
                     peer_id,
+                    message_id,
                     beacon_block_root,
                     "unaggregated",
                     e,
+                    queued,
                 )
             })
             .ok()
     }
 
+    /// Verifies a batch of unaggregated attestations received on gossip, informing the network of
+    /// the validation result of each attestation and importing the valid attestations.
+    ///
+    /// The signatures of the batch are verified together, falling back to verifying each
+    /// signature individually only if the batch is invalid.
+    ///
+    /// If `allow_reprocess` is `true`, attestations which vote for an unknown block are held by
+    /// the reprocess queue until the block is imported.
+    pub fn process_unaggregated_attestation_batch(
+        &mut self,
+        batch: Vec<BatchedAttestation<T::EthSpec>>,
+        allow_reprocess: bool,
+    ) {
+        // A batch of one gains nothing from batch verification.
+        if batch.len() == 1 {
+            for item in batch {
+                if let Some(verified_attestation) = self.verify_unaggregated_attestation_for_gossip(
+                    item.peer_id.clone(),
+                    item.message_id.clone(),
+                    item.subnet_id,
+                    item.attestation,
+                    allow_reprocess,
+                ) {
+                    self.propagate_validation_result(
+                        item.message_id,
+                        item.peer_id.clone(),
+                        MessageAcceptance::Accept,
+                    );
+                    self.import_unaggregated_attestation(item.peer_id, verified_attestation);
+                }
+            }
+            return;
+        }
+
+        let (sources, attestations): (Vec<_>, Vec<_>) = batch
+            .into_iter()
+            .map(|item| {
+                let beacon_block_root = item.attestation.data.beacon_block_root;
+                let source = (
+                    item.peer_id.clone(),
+                    item.message_id.clone(),
+                    beacon_block_root,
+                );
+                let attestation = (item.attestation.clone(), item.subnet_id);
+                // The attestation is kept to be held by the reprocess queue if its block is
+                // unknown.
+                let queued = if allow_reprocess {
+                    Some(QueuedAttestation::Unaggregated(item))
+                } else {
+                    None
+                };
+                ((source, queued), attestation)
+            })
+            .unzip();
+
+        let results = self
+            .chain
+            .batch_verify_unaggregated_attestations_for_gossip(attestations);
+
+        for (((peer_id, message_id, beacon_block_root), queued), result) in
+            sources.into_iter().zip(results)
+        {
+            match result {
+                Ok(verified_attestation) => {
+                    self.propagate_validation_result(
+                        message_id,
+                        peer_id.clone(),
+                        MessageAcceptance::Accept,
+                    );
+                    self.import_unaggregated_attestation(peer_id, verified_attestation);
+                }
+                Err(e) => self.handle_attestation_verification_failure(
+                    peer_id,
+                    message_id,
+                    beacon_block_root,
+                    "unaggregated",
+                    e,
+                    queued,
+                ),
+            }
+        }
+    }
+
     pub fn import_unaggregated_attestation(
         &mut self,
         peer_id: PeerId,
@@ -898,6 +1252,107 @@ impl<T: BeaconChainTypes> Processor<T> {
             )
         }
     }
+
+    /// Validates a voluntary exit received on gossip against the head state and queues it for
+    /// inclusion in a block. Returns whether the exit should be propagated.
+    pub fn process_gossip_voluntary_exit(
+        &mut self,
+        peer_id: &PeerId,
+        exit: SignedVoluntaryExit,
+    ) -> MessageAcceptance {
+        let validator_index = exit.message.validator_index;
+        if self.chain.op_pool.contains_voluntary_exit(validator_index) {
+            return MessageAcceptance::Ignore;
+        }
+
+        match self.chain.verify_voluntary_exit_for_gossip(exit) {
+            GossipOperationOutcome::Queued => {
+                debug!(self.log, "Received a voluntary exit";
+                    "validator_index" => validator_index, "peer_id" => format!("{}", peer_id));
+                MessageAcceptance::Accept
+            }
+            GossipOperationOutcome::Verified => MessageAcceptance::Accept,
+            GossipOperationOutcome::Unverified => MessageAcceptance::Ignore,
+            GossipOperationOutcome::Invalid(e) => {
+                debug!(self.log, "Invalid voluntary exit from network";
+                    "validator_index" => validator_index,
+                    "peer_id" => format!("{}", peer_id),
+                    "error" => format!("{:?}", e));
+                MessageAcceptance::Reject
+            }
+        }
+    }
+
+    /// Validates a proposer slashing received on gossip against the head state and queues it for
+    /// inclusion in a block. Returns whether the slashing should be propagated.
+    pub fn process_gossip_proposer_slashing(
+        &mut self,
+        peer_id: &PeerId,
+        proposer_slashing: ProposerSlashing,
+    ) -> MessageAcceptance {
+        let proposer_index = proposer_slashing.signed_header_1.message.proposer_index;
+        if self
+            .chain
+            .op_pool
+            .contains_proposer_slashing(proposer_index)
+        {
+            return MessageAcceptance::Ignore;
+        }
+
+        match self
+            .chain
+            .verify_proposer_slashing_for_gossip(proposer_slashing)
+        {
+            GossipOperationOutcome::Queued => {
+                debug!(self.log, "Received a proposer slashing";
+                    "proposer_index" => proposer_index, "peer_id" => format!("{}", peer_id));
+                MessageAcceptance::Accept
+            }
+            GossipOperationOutcome::Verified => MessageAcceptance::Accept,
+            GossipOperationOutcome::Unverified => MessageAcceptance::Ignore,
+            GossipOperationOutcome::Invalid(e) => {
+                debug!(self.log, "Invalid proposer slashing from network";
+                    "proposer_index" => proposer_index,
+                    "peer_id" => format!("{}", peer_id),
+                    "error" => format!("{:?}", e));
+                MessageAcceptance::Reject
+            }
+        }
+    }
+
+    /// Validates an attester slashing received on gossip against the head state and queues it for
+    /// inclusion in a block. Returns whether the slashing should be propagated.
+    pub fn process_gossip_attester_slashing(
+        &mut self,
+        peer_id: &PeerId,
+        attester_slashing: AttesterSlashing<T::EthSpec>,
+    ) -> MessageAcceptance {
+        if self
+            .chain
+            .op_pool
+            .contains_attester_slashing(&attester_slashing)
+        {
+            return MessageAcceptance::Ignore;
+        }
+
+        match self
+            .chain
+            .verify_attester_slashing_for_gossip(attester_slashing)
+        {
+            GossipOperationOutcome::Queued => {
+                debug!(self.log, "Received an attester slashing"; "peer_id" => format!("{}", peer_id));
+                MessageAcceptance::Accept
+            }
+            GossipOperationOutcome::Verified => MessageAcceptance::Accept,
+            GossipOperationOutcome::Unverified => MessageAcceptance::Ignore,
+            GossipOperationOutcome::Invalid(e) => {
+                debug!(self.log, "Invalid attester slashing from network";
+                    "peer_id" => format!("{}", peer_id),
+                    "error" => format!("{:?}", e));
+                MessageAcceptance::Reject
+            }
+        }
+    }
 }
 
 /// Build a `StatusMessage` representing the state of the given `beacon_chain`.
@@ -921,6 +1376,7 @@ pub(crate) fn status_message<T: BeaconChainTypes>(
 
 /// Wraps a Network Channel to employ various RPC related network functionality for the
 /// processor.
+#[derive(Clone)]
 pub struct HandlerNetworkContext<T: EthSpec> {
     /// The network channel to relay messages to the Network service.
     network_send: mpsc::UnboundedSender<NetworkMessage<T>>,
@@ -958,6 +1414,20 @@ impl<T: EthSpec> HandlerNetworkContext<T> {
         })
     }
 
+    /// Informs the network service of the validation result of a gossipsub message.
+    pub fn propagate_validation_result(
+        &mut self,
+        message_id: MessageId,
+        propagation_source: PeerId,
+        validation_result: MessageAcceptance,
+    ) {
+        self.inform_network(NetworkMessage::Validate {
+            propagation_source,
+            message_id,
+            validation_result,
+        })
+    }
+
     pub fn send_response(&mut self, peer_id: PeerId, response: Response<T>, id: PeerRequestId) {
         self.inform_network(NetworkMessage::SendResponse {
             peer_id,
@@ -980,3 +1450,80 @@ impl<T: EthSpec> HandlerNetworkContext<T> {
         })
     }
 }
+
+/// Returns the validation result of a gossip block which failed verification with `error`.
+///
+/// Only blocks which are provably invalid are rejected, since rejections are attributed to the
+/// peer which sent the block. Blocks which are merely redundant, early, or which could not be
+/// verified due to an internal error are ignored.
+fn gossip_block_acceptance(error: &BlockError) -> MessageAcceptance {
+    if is_invalid_block(error) {
+        MessageAcceptance::Reject
+    } else {
+        MessageAcceptance::Ignore
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beacon_chain::BeaconChainError;
+
+    #[test]
+    fn duplicate_blocks_are_ignored() {
+        assert_eq!(
+            gossip_block_acceptance(&BlockError::BlockIsAlreadyKnown),
+            MessageAcceptance::Ignore
+        );
+        assert_eq!(
+            gossip_block_acceptance(&BlockError::RepeatProposal {
+                proposer: 0,
+                slot: Slot::new(1),
+            }),
+            MessageAcceptance::Ignore
+        );
+    }
+
+    #[test]
+    fn blocks_which_are_not_the_peers_fault_are_ignored() {
+        for error in vec![
+            BlockError::ParentUnknown(Hash256::zero()),
+            BlockError::FutureSlot {
+                present_slot: Slot::new(1),
+                block_slot: Slot::new(2),
+            },
+            BlockError::WouldRevertFinalizedSlot {
+                block_slot: Slot::new(1),
+                finalized_slot: Slot::new(32),
+            },
+            BlockError::BeaconChainError(BeaconChainError::CanonicalHeadLockTimeout),
+        ] {
+            assert_eq!(
+                gossip_block_acceptance(&error),
+                MessageAcceptance::Ignore,
+                "{:?} should be ignored",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_blocks_are_rejected() {
+        for error in vec![
+            BlockError::ProposalSignatureInvalid,
+            BlockError::InvalidSignature,
+            BlockError::IncorrectBlockProposer {
+                block: 0,
+                local_shuffling: 1,
+            },
+            BlockError::KnownInvalidBlock(Hash256::zero()),
+        ] {
+            assert_eq!(
+                gossip_block_acceptance(&error),
+                MessageAcceptance::Reject,
+                "{:?} should be rejected",
+                error
+            );
+        }
+    }
+}
@@ -0,0 +1,426 @@
+//! Holds the gossip blocks and attestations which arrived too early to be verified, and releases
+//! them to be verified again once they can be.
+//!
+//! - A block from a future slot is held until the start of its slot.
+//! - An attestation or aggregate which votes for an unknown block is held until that block is
+//!   imported, either from gossip or by a single block lookup, or until
+//!   `QUEUED_ATTESTATION_TIMEOUT` has elapsed.
+//!
+//! An attestation whose block has been imported by the time it reaches the queue is released
+//! straight away, since the import of the block will not be announced again.
+//!
+//! A message is held at most once. Messages which cannot be held because the queue is full are
+//! released straight away, such that every message eventually receives a validation result.
+
+use super::attestation_batcher::BatchedAttestation;
+use crate::metrics;
+use crate::sync::BlockProcessorEvent;
+use eth2_libp2p::{MessageId, PeerId};
+use fnv::FnvHashMap;
+use futures::prelude::*;
+use slog::{debug, error, trace};
+use std::collections::hash_map::Entry;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{delay_queue, DelayQueue};
+use types::{EthSpec, Hash256, SignedAggregateAndProof, SignedBeaconBlock};
+
+/// The maximum number of early blocks held at once.
+const MAXIMUM_QUEUED_BLOCKS: usize = 16;
+
+/// The maximum number of attestations and aggregates held at once.
+const MAXIMUM_QUEUED_ATTESTATIONS: usize = 16_384;
+
+/// The time for which attestations wait for the import of their block.
+const QUEUED_ATTESTATION_TIMEOUT: Duration = Duration::from_secs(12);
+
+/// A gossip block from a future slot.
+pub struct QueuedBlock<E: EthSpec> {
+    pub message_id: MessageId,
+    pub peer_id: PeerId,
+    pub block: Box<SignedBeaconBlock<E>>,
+    /// The time at which the block was first received.
+    pub seen_timestamp: Duration,
+}
+
+/// A gossip attestation or aggregate which votes for an unknown block.
+pub enum QueuedAttestation<E: EthSpec> {
+    Aggregate {
+        message_id: MessageId,
+        peer_id: PeerId,
+        aggregate: Box<SignedAggregateAndProof<E>>,
+    },
+    Unaggregated(BatchedAttestation<E>),
+}
+
+/// The messages accepted by the reprocess queue.
+pub enum ReprocessQueueMessage<E: EthSpec> {
+    /// A block to be released once `delay` has elapsed.
+    EarlyBlock {
+        block: QueuedBlock<E>,
+        delay: Duration,
+    },
+    /// An attestation to be released once the block `beacon_block_root` is imported.
+    UnknownBlockAttestation {
+        beacon_block_root: Hash256,
+        attestation: QueuedAttestation<E>,
+    },
+    /// The block `block_root` has been imported from gossip.
+    BlockImported(Hash256),
+}
+
+/// The messages released by the reprocess queue.
+pub enum ReadyWork<E: EthSpec> {
+    /// A block whose slot has started, or which could not be held.
+    Block(QueuedBlock<E>),
+    /// Attestations whose block has been imported.
+    Attestations(Vec<QueuedAttestation<E>>),
+    /// Attestations whose block was not imported in time, or which could not be held.
+    ExpiredAttestations(Vec<QueuedAttestation<E>>),
+}
+
+struct ReprocessQueue<E: EthSpec> {
+    /// Messages from the router and the processor.
+    input: mpsc::UnboundedReceiver<ReprocessQueueMessage<E>>,
+    /// Events of the block processor, which announce the blocks imported by single block
+    /// lookups.
+    block_events: broadcast::Receiver<BlockProcessorEvent>,
+    /// The channel on which released messages are sent.
+    ready_send: mpsc::UnboundedSender<ReadyWork<E>>,
+    /// The early blocks, which expire at the start of their slot.
+    queued_blocks: DelayQueue<QueuedBlock<E>>,
+    /// The attestations waiting for each block root, along with the key of the root in
+    /// `attestation_timeouts`.
+    awaiting_attestations: FnvHashMap<Hash256, (delay_queue::Key, Vec<QueuedAttestation<E>>)>,
+    /// The block roots of `awaiting_attestations`, which expire once their attestations have
+    /// waited for `QUEUED_ATTESTATION_TIMEOUT`.
+    attestation_timeouts: DelayQueue<Hash256>,
+    /// The total number of attestations in `awaiting_attestations`.
+    queued_attestations: usize,
+    /// Returns `true` if fork choice contains the given block root.
+    block_is_known: Box<dyn Fn(&Hash256) -> bool + Send>,
+    log: slog::Logger,
+}
+
+/// Spawns the reprocess queue, which sends the messages it releases on `ready_send`. Returns the
+/// channel on which messages are given to the queue.
+pub fn spawn<E: EthSpec>(
+    executor: environment::TaskExecutor,
+    block_events: broadcast::Receiver<BlockProcessorEvent>,
+    ready_send: mpsc::UnboundedSender<ReadyWork<E>>,
+    block_is_known: impl Fn(&Hash256) -> bool + Send + 'static,
+    log: slog::Logger,
+) -> mpsc::UnboundedSender<ReprocessQueueMessage<E>> {
+    let (reprocess_send, reprocess_recv) = mpsc::unbounded_channel();
+    let mut queue = ReprocessQueue::new(
+        reprocess_recv,
+        block_events,
+        ready_send,
+        Box::new(block_is_known),
+        log,
+    );
+
+    executor.spawn(
+        async move { Box::pin(queue.main()).await },
+        "reprocess_queue",
+    );
+    reprocess_send
+}
+
+impl<E: EthSpec> ReprocessQueue<E> {
+    fn new(
+        input: mpsc::UnboundedReceiver<ReprocessQueueMessage<E>>,
+        block_events: broadcast::Receiver<BlockProcessorEvent>,
+        ready_send: mpsc::UnboundedSender<ReadyWork<E>>,
+        block_is_known: Box<dyn Fn(&Hash256) -> bool + Send>,
+        log: slog::Logger,
+    ) -> Self {
+        Self {
+            input,
+            block_events,
+            ready_send,
+            queued_blocks: DelayQueue::new(),
+            awaiting_attestations: FnvHashMap::default(),
+            attestation_timeouts: DelayQueue::new(),
+            queued_attestations: 0,
+            block_is_known,
+            log,
+        }
+    }
+
+    fn handle_message(&mut self, message: ReprocessQueueMessage<E>) {
+        match message {
+            ReprocessQueueMessage::EarlyBlock { block, delay } => {
+                if self.queued_blocks.len() >= MAXIMUM_QUEUED_BLOCKS {
+                    debug!(self.log, "Early block queue is full"; "slot" => block.block.slot());
+                    self.send_ready(ReadyWork::Block(block));
+                } else {
+                    trace!(self.log, "Queued early block";
+                        "slot" => block.block.slot(), "delay_ms" => delay.as_millis() as u64);
+                    self.queued_blocks.insert(block, delay);
+                }
+            }
+            ReprocessQueueMessage::UnknownBlockAttestation {
+                beacon_block_root,
+                attestation,
+            } => {
+                // the block may have been imported since the attestation was verified, in which
+                // case its import has already been announced
+                if (self.block_is_known)(&beacon_block_root) {
+                    trace!(self.log, "Releasing attestation of known block";
+                        "block" => format!("{}", beacon_block_root));
+                    self.send_ready(ReadyWork::Attestations(vec![attestation]));
+                    return;
+                }
+
+                if self.queued_attestations >= MAXIMUM_QUEUED_ATTESTATIONS {
+                    debug!(self.log, "Attestation queue is full";
+                        "block" => format!("{}", beacon_block_root));
+                    self.send_ready(ReadyWork::ExpiredAttestations(vec![attestation]));
+                    return;
+                }
+
+                self.queued_attestations += 1;
+                match self.awaiting_attestations.entry(beacon_block_root) {
+                    Entry::Occupied(mut entry) => entry.get_mut().1.push(attestation),
+                    Entry::Vacant(entry) => {
+                        let key = self
+                            .attestation_timeouts
+                            .insert(beacon_block_root, QUEUED_ATTESTATION_TIMEOUT);
+                        entry.insert((key, vec![attestation]));
+                    }
+                }
+            }
+            ReprocessQueueMessage::BlockImported(block_root) => self.block_imported(block_root),
+        }
+    }
+
+    /// Releases the attestations which vote for the imported block `block_root`.
+    fn block_imported(&mut self, block_root: Hash256) {
+        if let Some((key, attestations)) = self.awaiting_attestations.remove(&block_root) {
+            self.attestation_timeouts.remove(&key);
+            self.queued_attestations -= attestations.len();
+            debug!(self.log, "Releasing attestations of imported block";
+                "block" => format!("{}", block_root), "count" => attestations.len());
+            self.send_ready(ReadyWork::Attestations(attestations));
+        }
+    }
+
+    /// The attestations which vote for `block_root` have waited for their block for too long.
+    fn attestation_timeout_elapsed(&mut self, block_root: Hash256) {
+        if let Some((_, attestations)) = self.awaiting_attestations.remove(&block_root) {
+            self.queued_attestations -= attestations.len();
+            trace!(self.log, "Attestations of unknown block expired";
+                "block" => format!("{}", block_root), "count" => attestations.len());
+            metrics::inc_counter_by(
+                &metrics::REPROCESS_QUEUE_EXPIRED_ATTESTATIONS,
+                attestations.len() as i64,
+            );
+            self.send_ready(ReadyWork::ExpiredAttestations(attestations));
+        }
+    }
+
+    fn send_ready(&mut self, work: ReadyWork<E>) {
+        self.ready_send.send(work).unwrap_or_else(|_| {
+            debug!(self.log, "Could not release work from the reprocess queue");
+        });
+    }
+
+    fn update_metrics(&self) {
+        metrics::set_gauge(
+            &metrics::REPROCESS_QUEUE_BLOCKS,
+            self.queued_blocks.len() as i64,
+        );
+        metrics::set_gauge(
+            &metrics::REPROCESS_QUEUE_ATTESTATIONS,
+            self.queued_attestations as i64,
+        );
+    }
+
+    /// The main driving future of the reprocess queue.
+    async fn main(&mut self) {
+        loop {
+            tokio::select! {
+                message = self.input.recv() => match message {
+                    Some(message) => self.handle_message(message),
+                    // The router has been dropped, we are shutting down.
+                    None => break,
+                },
+                event = self.block_events.recv() => match event {
+                    Ok(BlockProcessorEvent::BlockImported { block_root, .. }) => {
+                        self.block_imported(block_root)
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::RecvError::Lagged(skipped)) => {
+                        debug!(self.log, "Reprocess queue missed block processor events";
+                            "skipped" => skipped)
+                    }
+                    Err(broadcast::RecvError::Closed) => break,
+                },
+                // an empty `DelayQueue` is always ready, so it is only polled when it has entries
+                Some(expired) = self.queued_blocks.next(), if !self.queued_blocks.is_empty() => {
+                    match expired {
+                        Ok(expired) => self.send_ready(ReadyWork::Block(expired.into_inner())),
+                        Err(e) => {
+                            error!(self.log, "Failed to poll early blocks"; "error" => format!("{:?}", e))
+                        }
+                    }
+                }
+                Some(expired) = self.attestation_timeouts.next(),
+                    if !self.attestation_timeouts.is_empty() =>
+                {
+                    match expired {
+                        Ok(expired) => self.attestation_timeout_elapsed(expired.into_inner()),
+                        Err(e) => {
+                            error!(self.log, "Failed to poll attestation timeouts"; "error" => format!("{:?}", e))
+                        }
+                    }
+                }
+            }
+            self.update_metrics();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sloggers::{null::NullLoggerBuilder, Build};
+    use types::{
+        AggregateSignature, Attestation, AttestationData, BeaconBlock, BitList, Checkpoint, Epoch,
+        MinimalEthSpec, Signature, Slot, SubnetId,
+    };
+
+    type E = MinimalEthSpec;
+
+    fn queue() -> (ReprocessQueue<E>, mpsc::UnboundedReceiver<ReadyWork<E>>) {
+        queue_with_known_blocks(vec![])
+    }
+
+    fn queue_with_known_blocks(
+        known_blocks: Vec<Hash256>,
+    ) -> (ReprocessQueue<E>, mpsc::UnboundedReceiver<ReadyWork<E>>) {
+        let (_, input) = mpsc::unbounded_channel();
+        let (block_events_send, _) = broadcast::channel(1);
+        let (ready_send, ready_recv) = mpsc::unbounded_channel();
+        let log = NullLoggerBuilder.build().expect("logger should build");
+        let queue = ReprocessQueue::new(
+            input,
+            block_events_send.subscribe(),
+            ready_send,
+            Box::new(move |root| known_blocks.contains(root)),
+            log,
+        );
+        (queue, ready_recv)
+    }
+
+    fn attestation(beacon_block_root: Hash256) -> ReprocessQueueMessage<E> {
+        let checkpoint = Checkpoint {
+            epoch: Epoch::new(0),
+            root: Hash256::zero(),
+        };
+        let attestation = Attestation {
+            aggregation_bits: BitList::with_capacity(1).unwrap(),
+            data: AttestationData {
+                slot: Slot::new(0),
+                index: 0,
+                beacon_block_root,
+                source: checkpoint.clone(),
+                target: checkpoint,
+            },
+            signature: AggregateSignature::new(),
+        };
+        ReprocessQueueMessage::UnknownBlockAttestation {
+            beacon_block_root,
+            attestation: QueuedAttestation::Unaggregated(BatchedAttestation {
+                message_id: MessageId(String::new()),
+                peer_id: PeerId::random(),
+                subnet_id: SubnetId::new(0),
+                attestation,
+            }),
+        }
+    }
+
+    fn early_block() -> ReprocessQueueMessage<E> {
+        ReprocessQueueMessage::EarlyBlock {
+            block: QueuedBlock {
+                message_id: MessageId(String::new()),
+                peer_id: PeerId::random(),
+                block: Box::new(SignedBeaconBlock {
+                    message: BeaconBlock::empty(&E::default_spec()),
+                    signature: Signature::empty_signature(),
+                }),
+                seen_timestamp: Duration::from_secs(0),
+            },
+            delay: Duration::from_secs(12),
+        }
+    }
+
+    #[tokio::test]
+    async fn attestations_are_released_when_their_block_is_imported() {
+        let (mut queue, mut ready_recv) = queue();
+        let root_a = Hash256::from_low_u64_be(1);
+        let root_b = Hash256::from_low_u64_be(2);
+
+        queue.handle_message(attestation(root_a));
+        queue.handle_message(attestation(root_a));
+        queue.handle_message(attestation(root_b));
+        assert!(ready_recv.try_recv().is_err());
+
+        queue.handle_message(ReprocessQueueMessage::BlockImported(root_a));
+        match ready_recv.try_recv() {
+            Ok(ReadyWork::Attestations(attestations)) => assert_eq!(attestations.len(), 2),
+            _ => panic!("the attestations of the imported block should be released"),
+        }
+        assert_eq!(queue.queued_attestations, 1);
+        assert_eq!(queue.attestation_timeouts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn attestations_of_known_blocks_are_released_immediately() {
+        let root = Hash256::from_low_u64_be(1);
+        let (mut queue, mut ready_recv) = queue_with_known_blocks(vec![root]);
+
+        queue.handle_message(attestation(root));
+        match ready_recv.try_recv() {
+            Ok(ReadyWork::Attestations(attestations)) => assert_eq!(attestations.len(), 1),
+            _ => panic!("the attestation of a known block should be released"),
+        }
+        assert_eq!(queue.queued_attestations, 0);
+        assert_eq!(queue.attestation_timeouts.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn expired_attestations_are_not_released_again() {
+        let (mut queue, mut ready_recv) = queue();
+        let root = Hash256::from_low_u64_be(1);
+
+        queue.handle_message(attestation(root));
+        queue.attestation_timeout_elapsed(root);
+        match ready_recv.try_recv() {
+            Ok(ReadyWork::ExpiredAttestations(attestations)) => assert_eq!(attestations.len(), 1),
+            _ => panic!("the attestation should expire"),
+        }
+
+        queue.handle_message(ReprocessQueueMessage::BlockImported(root));
+        assert!(ready_recv.try_recv().is_err());
+        assert_eq!(queue.queued_attestations, 0);
+    }
+
+    #[tokio::test]
+    async fn full_block_queue_releases_new_blocks() {
+        let (mut queue, mut ready_recv) = queue();
+
+        for _ in 0..MAXIMUM_QUEUED_BLOCKS {
+            queue.handle_message(early_block());
+        }
+        assert!(ready_recv.try_recv().is_err());
+
+        queue.handle_message(early_block());
+        match ready_recv.try_recv() {
+            Ok(ReadyWork::Block(_)) => {}
+            _ => panic!("a block which cannot be queued should be released"),
+        }
+        assert_eq!(queue.queued_blocks.len(), MAXIMUM_QUEUED_BLOCKS);
+    }
+}
@@ -1,5 +1,8 @@
+use crate::nat::{spawn_upnp_mappings, UPnPConfig};
 use crate::persisted_dht::{load_dht, persist_dht};
+use crate::persisted_peers::{load_peers, persist_peers};
 use crate::router::{Router, RouterMessage};
+use crate::sync::BlockProcessorEvent;
 use crate::{
     attestation_service::{AttServiceMessage, AttestationService},
     NetworkConfig,
@@ -9,24 +12,41 @@ use beacon_chain::{BeaconChain, BeaconChainTypes};
 use eth2_libp2p::Service as LibP2PService;
 use eth2_libp2p::{
     rpc::{RPCResponseErrorCode, RequestId},
-    Libp2pEvent, PeerRequestId, PubsubMessage, Request, Response,
+    Libp2pEvent, MessageAcceptance, PeerRequestId, PubsubMessage, Request, Response,
 };
 use eth2_libp2p::{BehaviourEvent, MessageId, NetworkGlobals, PeerId};
 use futures::prelude::*;
+use publish_queue::PublishQueue;
 use rest_types::ValidatorSubscription;
-use slog::{debug, error, info, o, trace};
+use rpc_request_tracker::{RetryPolicies, RpcRequestTracker, RpcTrackerEvent};
+use slog::{debug, error, info, o, trace, warn};
+use slot_clock::SlotClock;
 use std::sync::Arc;
 use std::time::Duration;
 use store::HotColdDB;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tokio::time::Delay;
-use types::EthSpec;
+use types::{ChainSpec, EthSpec};
 
+pub use publish_queue::{PublishError, PublishResultReceiver, PublishResultSender};
+
+mod publish_queue;
+mod rpc_request_tracker;
 mod tests;
 
 /// The time in seconds that a peer will be banned and prevented from reconnecting.
 const BAN_PEER_TIMEOUT: u64 = 30;
 
+/// The number of block processor events buffered for each subscriber before the oldest are
+/// dropped.
+const BLOCK_PROCESSOR_EVENTS_CAPACITY: usize = 256;
+
+/// The number of epochs before a fork at which the gossipsub topics of the new fork are joined.
+const SUBSCRIBE_TO_FORK_TOPICS_EPOCHS: u64 = 2;
+
+/// The number of epochs after a fork for which the gossipsub topics of the old fork are kept.
+const UNSUBSCRIBE_FROM_FORK_TOPICS_EPOCHS: u64 = 2;
+
 /// Service that handles communication between internal services and the `eth2_libp2p` network service.
 pub struct NetworkService<T: BeaconChainTypes> {
     /// A reference to the underlying beacon chain.
@@ -44,8 +64,17 @@ pub struct NetworkService<T: BeaconChainTypes> {
     store: Arc<HotColdDB<T::EthSpec, T::HotStore, T::ColdStore>>,
     /// A collection of global variables, accessible outside of the network service.
     network_globals: Arc<NetworkGlobals<T::EthSpec>>,
+    /// Tracks the deadlines of outbound RPC requests.
+    rpc_tracker: RpcRequestTracker,
+    /// The gossipsub messages waiting for a peer to subscribe to their topics.
+    publish_queue: PublishQueue<T::EthSpec>,
     /// A delay that expires when a new fork takes place.
     next_fork_update: Option<Delay>,
+    /// A delay that expires shortly before a new fork, when the topics of the new fork are joined.
+    next_fork_subscriptions: Option<Delay>,
+    /// A delay that expires once the grace period following a fork has passed, when the topics of
+    /// the old fork are left.
+    next_unsubscribe: Option<Delay>,
     /// The logger for the network service.
     log: slog::Logger,
 }
@@ -58,10 +87,14 @@ impl<T: BeaconChainTypes> NetworkService<T> {
     ) -> error::Result<(
         Arc<NetworkGlobals<T::EthSpec>>,
         mpsc::UnboundedSender<NetworkMessage<T::EthSpec>>,
+        broadcast::Sender<BlockProcessorEvent>,
     )> {
         let network_log = executor.log().clone();
         // build the network channel
         let (network_send, network_recv) = mpsc::unbounded_channel::<NetworkMessage<T::EthSpec>>();
+        // build the channel of the block processor events, subscribers are added with
+        // `subscribe()` on the returned sender
+        let (block_processor_events, _) = broadcast::channel(BLOCK_PROCESSOR_EVENTS_CAPACITY);
         // get a reference to the beacon chain store
         let store = beacon_chain.store.clone();
 
@@ -70,11 +103,22 @@ impl<T: BeaconChainTypes> NetworkService<T> {
 
         // keep track of when our fork_id needs to be updated
         let next_fork_update = next_fork_delay(&beacon_chain);
+        let next_fork_subscriptions = next_fork_subscriptions_delay(&beacon_chain);
 
         // launch libp2p service
         let (network_globals, mut libp2p) =
             LibP2PService::new(executor.clone(), config, enr_fork_id, &network_log)?;
 
+        // attempt to map our ports on the local router
+        if config.upnp_enabled {
+            spawn_upnp_mappings::<T::EthSpec>(
+                &executor,
+                UPnPConfig::from(config),
+                network_send.clone(),
+                network_log.clone(),
+            );
+        }
+
         // Repopulate the DHT with stored ENR's.
         let enrs_to_load = load_dht::<T::EthSpec, T::HotStore, T::ColdStore>(store.clone());
         debug!(
@@ -85,6 +129,14 @@ impl<T: BeaconChainTypes> NetworkService<T> {
             libp2p.swarm.add_enr(enr.clone());
         }
 
+        // Restore the peers known before the last shutdown, reconnecting to the best of them.
+        let peers_to_load = load_peers::<T::EthSpec, T::HotStore, T::ColdStore>(store.clone());
+        debug!(
+            network_log,
+            "Restoring known peers"; "peers" => peers_to_load.len()
+        );
+        libp2p.swarm.peer_manager().restore_peers(peers_to_load);
+
         // launch derived network services
 
         // router task
@@ -92,6 +144,9 @@ impl<T: BeaconChainTypes> NetworkService<T> {
             beacon_chain.clone(),
             network_globals.clone(),
             network_send.clone(),
+            block_processor_events.clone(),
+            config.block_lookup_delay,
+            config.batch_lookahead,
             executor.clone(),
             network_log.clone(),
         )?;
@@ -110,13 +165,17 @@ impl<T: BeaconChainTypes> NetworkService<T> {
             router_send,
             store,
             network_globals: network_globals.clone(),
+            rpc_tracker: RpcRequestTracker::new(RetryPolicies::default()),
+            publish_queue: PublishQueue::default(),
             next_fork_update,
+            next_fork_subscriptions,
+            next_unsubscribe: None,
             log: network_log,
         };
 
         spawn_service(executor, network_service)?;
 
-        Ok((network_globals, network_send))
+        Ok((network_globals, network_send, block_processor_events))
     }
 }
 
@@ -156,6 +215,25 @@ fn spawn_service<T: BeaconChainTypes>(
                         ),
                     }
 
+                    let peers = service.libp2p.swarm.peer_manager().persisted_peers();
+                    debug!(
+                        service.log,
+                        "Persisting known peers to store";
+                        "Number of peers" => format!("{}", peers.len()),
+                    );
+
+                    match persist_peers::<T::EthSpec, T::HotStore, T::ColdStore>(service.store.clone(), peers) {
+                        Err(e) => error!(
+                            service.log,
+                            "Failed to persist known peers on drop";
+                            "error" => format!("{:?}", e)
+                        ),
+                        Ok(_) => info!(
+                            service.log,
+                            "Saved known peers";
+                        ),
+                    }
+
                     info!(service.log, "Network service shutdown");
                     return;
                 }
@@ -163,6 +241,7 @@ fn spawn_service<T: BeaconChainTypes>(
                 Some(message) = service.network_recv.recv() => {
                     match message {
                         NetworkMessage::SendRequest{ peer_id, request, request_id } => {
+                            let request_id = service.rpc_tracker.request_sent(&peer_id, request_id, &request);
                             service.libp2p.send_request(peer_id, request_id, request);
                         }
                         NetworkMessage::SendResponse{ peer_id, response, id } => {
@@ -171,20 +250,23 @@ fn spawn_service<T: BeaconChainTypes>(
                         NetworkMessage::SendError{ peer_id, error, id, reason } => {
                             service.libp2p.respond_with_error(peer_id, id, error, reason);
                         }
-                        NetworkMessage::Propagate {
+                        NetworkMessage::Validate {
                             propagation_source,
                             message_id,
+                            validation_result,
                         } => {
-                                trace!(service.log, "Propagating gossipsub message";
+                                trace!(service.log, "Validating gossipsub message";
                                     "propagation_peer" => format!("{:?}", propagation_source),
                                     "message_id" => message_id.to_string(),
+                                    "validation_result" => format!("{:?}", validation_result),
                                 );
+                                expose_validation_metrics(validation_result);
                                 service
                                     .libp2p
                                     .swarm
-                                    .propagate_message(&propagation_source, message_id);
+                                    .validate_message(&propagation_source, message_id, validation_result);
                         }
-                        NetworkMessage::Publish { messages } => {
+                        NetworkMessage::Publish { messages, result } => {
                                 let mut topic_kinds = Vec::new();
                                 for message in &messages {
                                     if !topic_kinds.contains(&message.kind()) {
@@ -197,8 +279,33 @@ fn spawn_service<T: BeaconChainTypes>(
                                     "count" => messages.len(),
                                     "topics" => format!("{:?}", topic_kinds)
                                 );
-                                expose_publish_metrics(&messages);
-                                service.libp2p.swarm.publish(messages);
+                                publish_messages(&mut service, messages, result);
+                        }
+                        NetworkMessage::UPnPMappingEstablished { tcp_socket, udp_socket } => {
+                            // update the local ENR with the external sockets, unless a renewal
+                            // of the mappings has not changed them
+                            let discovery = service.libp2p.swarm.peer_manager().discovery_mut();
+                            let local_enr = discovery.local_enr();
+                            let tcp_socket = tcp_socket.filter(|socket| local_enr.tcp() != Some(socket.port()));
+                            let udp_socket = udp_socket.filter(|socket| {
+                                local_enr.udp() != Some(socket.port())
+                                    || local_enr.ip().map(std::net::IpAddr::V4) != Some(socket.ip())
+                            });
+                            if let Some(tcp_socket) = tcp_socket {
+                                if let Err(e) = discovery.update_enr_tcp_port(tcp_socket.port()) {
+                                    debug!(service.log, "Could not update the ENR TCP port"; "error" => e);
+                                }
+                            }
+                            if let Some(udp_socket) = udp_socket {
+                                if let Err(e) = discovery.update_enr_udp_socket(udp_socket) {
+                                    debug!(service.log, "Could not update the ENR UDP socket"; "error" => e);
+                                }
+                            }
+                            if tcp_socket.is_some() || udp_socket.is_some() {
+                                info!(service.log, "Updated the local ENR with UPnP mappings";
+                                    "tcp" => format!("{:?}", tcp_socket),
+                                    "udp" => format!("{:?}", udp_socket));
+                            }
                         }
                         NetworkMessage::Disconnect { peer_id } => {
                             service.libp2p.disconnect_and_ban_peer(
@@ -214,6 +321,35 @@ fn spawn_service<T: BeaconChainTypes>(
                         }
                     }
                 }
+                // process any outbound RPC requests that have passed their deadline
+                Some(tracker_event) = service.rpc_tracker.next() => {
+                    match tracker_event {
+                        RpcTrackerEvent::Retry { peer_id, request_id, request } => {
+                            debug!(service.log, "Re-sending RPC request"; "peer_id" => peer_id.to_string(), "request" => format!("{:?}", request));
+                            service.libp2p.send_request(peer_id, request_id, request);
+                        }
+                        RpcTrackerEvent::Timeout { peer_id, request_id } => {
+                            debug!(service.log, "RPC request timed out"; "peer_id" => peer_id.to_string(), "request_id" => format!("{:?}", request_id));
+                            let _ = service
+                                .router_send
+                                .send(RouterMessage::RPCTimeout{ peer_id, request_id })
+                                .map_err(|_| {
+                                    debug!(service.log, "Failed to send RPC timeout to router");
+                                });
+                        }
+                    }
+                }
+                // report the queued gossipsub messages that were never published
+                Some(messages) = service.publish_queue.next() => {
+                    warn!(
+                        service.log,
+                        "Failed to publish pubsub messages";
+                        "reason" => "no peers subscribed to the topics",
+                        "count" => messages.len(),
+                        "topics" => format!("{:?}", messages.iter().map(|message| message.kind()).collect::<Vec<_>>()),
+                    );
+                    metrics::inc_counter_by(&metrics::GOSSIP_PUBLISH_FAILURES, messages.len() as i64);
+                }
                 // process any attestation service events
                 Some(attestation_service_message) = service.attestation_service.next() => {
                     match attestation_service_message {
@@ -257,22 +393,26 @@ fn spawn_service<T: BeaconChainTypes>(
                                     });
                             }
                             BehaviourEvent::ResponseReceived{peer_id, id, response} => {
-                                let _ = service
-                                    .router_send
-                                    .send(RouterMessage::RPCResponseReceived{ peer_id, request_id: id, response })
-                                    .map_err(|_| {
-                                        debug!(service.log, "Failed to send RPC to router");
-                                    });
-
+                                // responses to requests that have timed out are dropped
+                                if let Some(id) = service.rpc_tracker.response_received(id, &response) {
+                                    let _ = service
+                                        .router_send
+                                        .send(RouterMessage::RPCResponseReceived{ peer_id, request_id: id, response })
+                                        .map_err(|_| {
+                                            debug!(service.log, "Failed to send RPC to router");
+                                        });
+                                }
                             }
                             BehaviourEvent::RPCFailed{id, peer_id, error} => {
-                                let _ = service
-                                    .router_send
-                                    .send(RouterMessage::RPCFailed{ peer_id, request_id: id, error })
-                                    .map_err(|_| {
-                                        debug!(service.log, "Failed to send RPC to router");
-                                    });
-
+                                // failures of requests that have timed out are dropped
+                                if let Some(id) = service.rpc_tracker.request_failed(id) {
+                                    let _ = service
+                                        .router_send
+                                        .send(RouterMessage::RPCFailed{ peer_id, request_id: id, error })
+                                        .map_err(|_| {
+                                            debug!(service.log, "Failed to send RPC to router");
+                                        });
+                                }
                             }
                             BehaviourEvent::StatusPeer(peer_id) => {
                                 let _ = service
@@ -324,7 +464,18 @@ fn spawn_service<T: BeaconChainTypes>(
                                     }
                                 }
                             }
-                            BehaviourEvent::PeerSubscribed(_, _) => {},
+                            BehaviourEvent::PeerSubscribed(_, _) => {
+                                // publish the queued messages that can now reach a peer
+                                let swarm = &service.libp2p.swarm;
+                                let messages = service
+                                    .publish_queue
+                                    .take_publishable(|message| swarm.can_publish(message));
+                                if !messages.is_empty() {
+                                    debug!(service.log, "Publishing queued pubsub messages"; "count" => messages.len());
+                                    expose_publish_metrics(&messages);
+                                    service.libp2p.swarm.publish(messages);
+                                }
+                            }
                         }
                         Libp2pEvent::NewListenAddr(multiaddr) => {
                             service.network_globals.listen_multiaddrs.write().push(multiaddr);
@@ -352,6 +503,22 @@ fn spawn_service<T: BeaconChainTypes>(
                 }
             }
 
+            if let Some(delay) = &service.next_fork_subscriptions {
+                if delay.is_elapsed() {
+                    let enr_fork_id = service.beacon_chain.enr_fork_id();
+                    let fork_digest = ChainSpec::compute_fork_digest(
+                        enr_fork_id.next_fork_version,
+                        service.beacon_chain.genesis_validators_root,
+                    );
+                    info!(service.log, "Subscribing to new fork topics"; "fork_digest" => hex::encode(fork_digest));
+                    service
+                        .libp2p
+                        .swarm
+                        .subscribe_to_next_fork_topics(fork_digest);
+                    service.next_fork_subscriptions = None;
+                }
+            }
+
             if let Some(delay) = &service.next_fork_update {
                 if delay.is_elapsed() {
                     service
@@ -359,6 +526,20 @@ fn spawn_service<T: BeaconChainTypes>(
                         .swarm
                         .update_fork_version(service.beacon_chain.enr_fork_id());
                     service.next_fork_update = next_fork_delay(&service.beacon_chain);
+                    service.next_fork_subscriptions =
+                        next_fork_subscriptions_delay(&service.beacon_chain);
+                    service.next_unsubscribe = Some(tokio::time::delay_for(epochs_duration(
+                        &service.beacon_chain,
+                        UNSUBSCRIBE_FROM_FORK_TOPICS_EPOCHS,
+                    )));
+                }
+            }
+
+            if let Some(delay) = &service.next_unsubscribe {
+                if delay.is_elapsed() {
+                    info!(service.log, "Unsubscribing from old fork topics");
+                    service.libp2p.swarm.unsubscribe_from_old_fork_topics();
+                    service.next_unsubscribe = None;
                 }
             }
         }
@@ -379,6 +560,29 @@ fn next_fork_delay<T: BeaconChainTypes>(
     })
 }
 
+/// Returns a `Delay` that triggers `SUBSCRIBE_TO_FORK_TOPICS_EPOCHS` before the next change in the
+/// beacon chain fork version. If there is no scheduled fork, `None` is returned.
+fn next_fork_subscriptions_delay<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+) -> Option<tokio::time::Delay> {
+    beacon_chain.duration_to_next_fork().map(|until_fork| {
+        let until_subscriptions = until_fork
+            .checked_sub(epochs_duration(
+                beacon_chain,
+                SUBSCRIBE_TO_FORK_TOPICS_EPOCHS,
+            ))
+            .unwrap_or_else(|| Duration::from_secs(0));
+        tokio::time::delay_until(tokio::time::Instant::now() + until_subscriptions)
+    })
+}
+
+/// Returns the `Duration` of the given number of epochs.
+fn epochs_duration<T: BeaconChainTypes>(beacon_chain: &BeaconChain<T>, epochs: u64) -> Duration {
+    Duration::from_millis(
+        beacon_chain.spec.milliseconds_per_slot * T::EthSpec::slots_per_epoch() * epochs,
+    )
+}
+
 /// Types of messages that the network service can receive.
 #[derive(Debug)]
 pub enum NetworkMessage<T: EthSpec> {
@@ -408,14 +612,97 @@ pub enum NetworkMessage<T: EthSpec> {
         id: PeerRequestId,
     },
     /// Publish a list of messages to the gossipsub protocol.
-    Publish { messages: Vec<PubsubMessage<T>> },
-    /// Propagate a received gossipsub message.
-    Propagate {
+    ///
+    /// Messages which cannot be sent to any peer are queued until a peer subscribes to their
+    /// topics. The outcome is sent to `result` once all messages are published, or once a queued
+    /// message is no longer valid.
+    Publish {
+        messages: Vec<PubsubMessage<T>>,
+        result: Option<PublishResultSender>,
+    },
+    /// Inform the network of the validation result of a received gossipsub message. Accepted
+    /// messages are propagated to our peers.
+    Validate {
         propagation_source: PeerId,
         message_id: MessageId,
+        validation_result: MessageAcceptance,
     },
     /// Disconnect and bans a peer id.
     Disconnect { peer_id: PeerId },
+    /// Ports have been mapped on the local router with UPnP. The contained sockets are the
+    /// external sockets of the established mappings.
+    UPnPMappingEstablished {
+        tcp_socket: Option<std::net::SocketAddr>,
+        udp_socket: Option<std::net::SocketAddr>,
+    },
+}
+
+/// Publishes the `messages` that can be sent to a peer and queues the others until a peer
+/// subscribes to their topics or they are no longer valid.
+fn publish_messages<T: BeaconChainTypes>(
+    service: &mut NetworkService<T>,
+    messages: Vec<PubsubMessage<T::EthSpec>>,
+    result: Option<PublishResultSender>,
+) {
+    let swarm = &service.libp2p.swarm;
+    let (publishable, queued): (Vec<_>, Vec<_>) = messages
+        .into_iter()
+        .partition(|message| swarm.can_publish(message));
+
+    expose_publish_metrics(&publishable);
+    service.libp2p.swarm.publish(publishable);
+
+    if queued.is_empty() {
+        if let Some(result) = result {
+            let _ = result.send(Ok(()));
+        }
+        return;
+    }
+
+    // the messages are retried until the first of them is no longer valid, messages which are
+    // already invalid fail immediately
+    let validity = queued
+        .iter()
+        .map(|message| gossip_validity(&service.beacon_chain, message))
+        .min()
+        .flatten()
+        .unwrap_or_else(|| Duration::from_secs(0));
+
+    debug!(
+        service.log,
+        "Queueing pubsub messages until peers subscribe";
+        "count" => queued.len(),
+        "validity_ms" => validity.as_millis() as u64,
+    );
+    metrics::inc_counter_by(&metrics::GOSSIP_PUBLISH_QUEUED, queued.len() as i64);
+    service.publish_queue.push(queued, validity, result);
+}
+
+/// Returns the time until `message` is no longer valid on the gossip network, or `None` if it is
+/// already invalid.
+fn gossip_validity<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    message: &PubsubMessage<T::EthSpec>,
+) -> Option<Duration> {
+    let propagation_slot_range = beacon_chain.spec.attestation_propagation_slot_range;
+    let expiry_slot = match message {
+        // a block is of little use to peers once the slot after it has ended
+        PubsubMessage::BeaconBlock(block) => block.message.slot + 2,
+        PubsubMessage::Attestation(subnet_and_attestation) => {
+            subnet_and_attestation.1.data.slot + propagation_slot_range + 1
+        }
+        PubsubMessage::AggregateAndProofAttestation(aggregate) => {
+            aggregate.message.aggregate.data.slot + propagation_slot_range + 1
+        }
+        // the remaining messages do not expire, give peers an epoch to subscribe
+        PubsubMessage::VoluntaryExit(_)
+        | PubsubMessage::ProposerSlashing(_)
+        | PubsubMessage::AttesterSlashing(_) => {
+            beacon_chain.slot().ok()? + T::EthSpec::slots_per_epoch()
+        }
+    };
+
+    beacon_chain.slot_clock.duration_to_slot(expiry_slot)
 }
 
 /// Inspects the `messages` that were being sent to the network and updates Prometheus metrics.
@@ -434,6 +721,15 @@ fn expose_publish_metrics<T: EthSpec>(messages: &[PubsubMessage<T>]) {
     }
 }
 
+/// Updates the Prometheus metrics with the outcome of validating a gossipsub message.
+fn expose_validation_metrics(validation_result: MessageAcceptance) {
+    match validation_result {
+        MessageAcceptance::Accept => metrics::inc_counter(&metrics::GOSSIP_MESSAGES_ACCEPTED),
+        MessageAcceptance::Ignore => metrics::inc_counter(&metrics::GOSSIP_MESSAGES_IGNORED),
+        MessageAcceptance::Reject => metrics::inc_counter(&metrics::GOSSIP_MESSAGES_REJECTED),
+    }
+}
+
 /// Inspects a `message` received from the network and updates Prometheus metrics.
 fn expose_receive_metrics<T: EthSpec>(message: &PubsubMessage<T>) {
     match message {
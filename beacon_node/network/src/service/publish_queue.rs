@@ -0,0 +1,222 @@
+//! Queues the gossipsub messages that could not be published because no connected peer is
+//! subscribed to their topics.
+//!
+//! Queued messages are published as soon as a peer subscribes to one of their topics. Messages
+//! that are still queued once they are no longer valid on the gossip network (e.g., an attestation
+//! older than `ATTESTATION_PROPAGATION_SLOT_RANGE`) have failed to be published, which is reported
+//! to the publisher.
+
+use eth2_libp2p::{types::GossipKind, PubsubMessage};
+use futures::prelude::*;
+use hashset_delay::HashSetDelay;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use types::EthSpec;
+
+/// The reason a list of messages could not be published.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublishError {
+    /// No connected peer subscribed to the topics of the messages of these kinds before the
+    /// messages were no longer valid.
+    InsufficientPeers(Vec<GossipKind>),
+}
+
+/// Sends the outcome of publishing a list of messages to their publisher.
+pub type PublishResultSender = oneshot::Sender<Result<(), PublishError>>;
+
+/// Receives the outcome of publishing a list of messages.
+pub type PublishResultReceiver = oneshot::Receiver<Result<(), PublishError>>;
+
+/// Messages, sent in a single `NetworkMessage::Publish`, that are waiting to be published.
+struct QueuedMessages<T: EthSpec> {
+    messages: Vec<PubsubMessage<T>>,
+    result: Option<PublishResultSender>,
+}
+
+/// Holds unpublished messages until they can be published or expire.
+pub struct PublishQueue<T: EthSpec> {
+    /// The queued messages, by an identifier unique to each `NetworkMessage::Publish`.
+    queued: HashMap<u64, QueuedMessages<T>>,
+    /// The times at which the queued messages are no longer valid.
+    expiries: HashSetDelay<u64>,
+    /// The identifier of the next queued `NetworkMessage::Publish`.
+    next_id: u64,
+}
+
+impl<T: EthSpec> Default for PublishQueue<T> {
+    fn default() -> Self {
+        PublishQueue {
+            queued: HashMap::new(),
+            expiries: HashSetDelay::default(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<T: EthSpec> PublishQueue<T> {
+    /// Queues `messages` until they are published or `validity` has elapsed, after which the
+    /// failure is sent to `result`.
+    pub fn push(
+        &mut self,
+        messages: Vec<PubsubMessage<T>>,
+        validity: Duration,
+        result: Option<PublishResultSender>,
+    ) {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.expiries.insert_at(id, validity);
+        self.queued.insert(id, QueuedMessages { messages, result });
+    }
+
+    /// Removes and returns the queued messages for which `can_publish` returns `true`.
+    ///
+    /// Publishers are informed of the success once all their messages have been taken.
+    pub fn take_publishable<F>(&mut self, mut can_publish: F) -> Vec<PubsubMessage<T>>
+    where
+        F: FnMut(&PubsubMessage<T>) -> bool,
+    {
+        let mut publishable = Vec::new();
+        let mut completed = Vec::new();
+
+        for (id, queued) in self.queued.iter_mut() {
+            let (ready, waiting) = queued
+                .messages
+                .drain(..)
+                .partition::<Vec<_>, _>(|message| can_publish(message));
+            publishable.extend(ready);
+            queued.messages = waiting;
+
+            if queued.messages.is_empty() {
+                completed.push(*id);
+            }
+        }
+
+        for id in completed {
+            self.expiries.remove(&id);
+            if let Some(result) = self.queued.remove(&id).and_then(|queued| queued.result) {
+                let _ = result.send(Ok(()));
+            }
+        }
+
+        publishable
+    }
+}
+
+impl<T: EthSpec> Stream for PublishQueue<T> {
+    /// The messages that expired before they could be published.
+    type Item = Vec<PubsubMessage<T>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.expiries.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(id))) => {
+                    let queued = match self.queued.remove(&id) {
+                        Some(queued) => queued,
+                        None => continue,
+                    };
+
+                    if let Some(result) = queued.result {
+                        let mut kinds = Vec::new();
+                        for message in &queued.messages {
+                            if !kinds.contains(&message.kind()) {
+                                kinds.push(message.kind());
+                            }
+                        }
+                        let _ = result.send(Err(PublishError::InsufficientPeers(kinds)));
+                    }
+                    return Poll::Ready(Some(queued.messages));
+                }
+                // the expiry of published messages, ignore it
+                Poll::Ready(Some(Err(_))) => continue,
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{Epoch, MinimalEthSpec, Signature, SignedVoluntaryExit, VoluntaryExit};
+
+    type E = MinimalEthSpec;
+
+    /// The validity of messages which are not expected to expire during a test.
+    const LONG_VALIDITY: Duration = Duration::from_secs(60);
+
+    fn exit(validator_index: u64) -> PubsubMessage<E> {
+        PubsubMessage::VoluntaryExit(Box::new(SignedVoluntaryExit {
+            message: VoluntaryExit {
+                epoch: Epoch::new(0),
+                validator_index,
+            },
+            signature: Signature::empty_signature(),
+        }))
+    }
+
+    fn is_exit_of(message: &PubsubMessage<E>, index: u64) -> bool {
+        match message {
+            PubsubMessage::VoluntaryExit(exit) => exit.message.validator_index == index,
+            _ => false,
+        }
+    }
+
+    #[tokio::test]
+    async fn publishable_messages_are_taken() {
+        let mut queue = PublishQueue::default();
+        let (result, mut publish_result) = oneshot::channel();
+        queue.push(vec![exit(0), exit(1)], LONG_VALIDITY, Some(result));
+
+        assert_eq!(
+            queue.take_publishable(|message| is_exit_of(message, 0)),
+            vec![exit(0)]
+        );
+        assert!(
+            publish_result.try_recv().is_err(),
+            "should not report success before all messages are published"
+        );
+
+        assert_eq!(queue.take_publishable(|_| true), vec![exit(1)]);
+        assert_eq!(publish_result.try_recv(), Ok(Ok(())));
+        assert!(queue.take_publishable(|_| true).is_empty());
+    }
+
+    #[tokio::test]
+    async fn expired_messages_fail() {
+        let mut queue = PublishQueue::default();
+        let (result, mut publish_result) = oneshot::channel();
+        queue.push(
+            vec![exit(0), exit(1)],
+            Duration::from_millis(10),
+            Some(result),
+        );
+
+        assert_eq!(queue.next().await, Some(vec![exit(0), exit(1)]));
+        assert_eq!(
+            publish_result.try_recv(),
+            Ok(Err(PublishError::InsufficientPeers(vec![
+                GossipKind::VoluntaryExit
+            ])))
+        );
+        assert!(queue.take_publishable(|_| true).is_empty());
+    }
+
+    #[tokio::test]
+    async fn published_messages_do_not_expire() {
+        let mut queue = PublishQueue::default();
+        queue.push(vec![exit(0)], Duration::from_millis(10), None);
+        queue.push(vec![exit(1)], LONG_VALIDITY, None);
+
+        assert_eq!(
+            queue.take_publishable(|message| is_exit_of(message, 0)),
+            vec![exit(0)]
+        );
+
+        let expired = tokio::time::timeout(Duration::from_millis(100), queue.next()).await;
+        assert!(expired.is_err(), "should not expire published messages");
+        assert_eq!(queue.take_publishable(|_| true), vec![exit(1)]);
+    }
+}
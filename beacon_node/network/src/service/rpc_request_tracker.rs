@@ -0,0 +1,379 @@
+//! Tracks the outbound RPC requests of the network service and the deadlines by which peers must
+//! respond to them.
+//!
+//! A request whose deadline passes is re-sent to the peer according to the `RetryPolicy` of its
+//! type. Once all retries are exhausted, the request is considered timed out and any late
+//! responses to it are dropped, such that the requester can safely move on.
+//!
+//! The ids given to requests by the requesters are not unique (e.g., every request of the router
+//! has the id `RequestId::Router`), so each tracked request is sent to the peer with an id which
+//! is unique to the tracker. The ids of responses are mapped back to the ids of the requesters.
+
+use eth2_libp2p::rpc::RequestId;
+use eth2_libp2p::{PeerId, Request, Response};
+use futures::prelude::*;
+use hashset_delay::HashSetDelay;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use types::EthSpec;
+
+/// The number of seconds a timed out request is remembered, in order to drop late responses.
+const TIMED_OUT_REQUEST_MEMORY: u64 = 60;
+
+/// The time a peer has to respond to a request and the number of times the request is re-sent
+/// before it is considered timed out.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// The time a peer has to respond to the request. For streamed responses, this is the time
+    /// allowed between chunks.
+    pub timeout: Duration,
+    /// The number of times the request is re-sent after a timeout.
+    pub max_retries: u8,
+}
+
+/// The `RetryPolicy` of each type of request.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicies {
+    pub status: RetryPolicy,
+    pub blocks_by_range: RetryPolicy,
+    pub blocks_by_root: RetryPolicy,
+}
+
+impl Default for RetryPolicies {
+    fn default() -> Self {
+        RetryPolicies {
+            status: RetryPolicy {
+                timeout: Duration::from_secs(10),
+                max_retries: 1,
+            },
+            // Block requests are not re-sent, as chunks of the original response could still
+            // arrive and would be indistinguishable from responses to the re-sent request.
+            blocks_by_range: RetryPolicy {
+                timeout: Duration::from_secs(15),
+                max_retries: 0,
+            },
+            blocks_by_root: RetryPolicy {
+                timeout: Duration::from_secs(15),
+                max_retries: 0,
+            },
+        }
+    }
+}
+
+impl RetryPolicies {
+    /// Returns the policy of a request, or `None` if the request expects no response.
+    fn policy(&self, request: &Request) -> Option<RetryPolicy> {
+        match request {
+            Request::Status(_) => Some(self.status),
+            Request::BlocksByRange(_) => Some(self.blocks_by_range),
+            Request::BlocksByRoot(_) => Some(self.blocks_by_root),
+            Request::Goodbye(_) => None,
+        }
+    }
+}
+
+/// Events emitted by the `RpcRequestTracker`.
+#[derive(Debug)]
+pub enum RpcTrackerEvent {
+    /// The peer did not respond in time and the request must be re-sent with the given id.
+    Retry {
+        peer_id: PeerId,
+        request_id: RequestId,
+        request: Request,
+    },
+    /// The peer did not respond in time and the request has no retries left. The `request_id` is
+    /// the id given by the requester.
+    Timeout {
+        peer_id: PeerId,
+        request_id: RequestId,
+    },
+}
+
+/// An outbound request awaiting a response.
+struct TrackedRequest {
+    /// The peer the request was sent to.
+    peer_id: PeerId,
+    /// The id given to the request by the requester.
+    request_id: RequestId,
+    /// The request that was sent.
+    request: Request,
+    /// The number of times the request has been re-sent.
+    retries: u8,
+    /// The policy applied to the request.
+    policy: RetryPolicy,
+}
+
+/// Assigns deadlines to outbound RPC requests.
+pub struct RpcRequestTracker {
+    /// The retry policies of each type of request.
+    policies: RetryPolicies,
+    /// The id to be assigned to the next tracked request.
+    next_id: usize,
+    /// The requests awaiting a response, by their assigned id.
+    requests: HashMap<usize, TrackedRequest>,
+    /// The deadlines of the requests awaiting a response.
+    deadlines: HashSetDelay<usize>,
+    /// Requests that have timed out. Responses to these requests are dropped.
+    timed_out: HashSetDelay<usize>,
+}
+
+impl RpcRequestTracker {
+    pub fn new(policies: RetryPolicies) -> Self {
+        RpcRequestTracker {
+            policies,
+            next_id: 0,
+            requests: HashMap::new(),
+            deadlines: HashSetDelay::default(),
+            timed_out: HashSetDelay::new(Duration::from_secs(TIMED_OUT_REQUEST_MEMORY)),
+        }
+    }
+
+    /// Starts tracking a request with `request_id` that is being sent to `peer_id`.
+    ///
+    /// Returns the id with which the request must be sent. Requests which expect no response are
+    /// not tracked and keep their id.
+    pub fn request_sent(
+        &mut self,
+        peer_id: &PeerId,
+        request_id: RequestId,
+        request: &Request,
+    ) -> RequestId {
+        let policy = match self.policies.policy(request) {
+            Some(policy) => policy,
+            None => return request_id,
+        };
+
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.deadlines.insert_at(id, policy.timeout);
+        self.requests.insert(
+            id,
+            TrackedRequest {
+                peer_id: peer_id.clone(),
+                request_id,
+                request: request.clone(),
+                retries: 0,
+                policy,
+            },
+        );
+        RequestId::Network(id)
+    }
+
+    /// Registers a response to the request sent with `request_id`.
+    ///
+    /// Returns the id given to the request by the requester, or `None` if the request has timed
+    /// out, in which case the response must be dropped.
+    pub fn response_received<T: EthSpec>(
+        &mut self,
+        request_id: RequestId,
+        response: &Response<T>,
+    ) -> Option<RequestId> {
+        let id = match request_id {
+            RequestId::Network(id) => id,
+            _ => return Some(request_id),
+        };
+        let is_chunk = match response {
+            Response::BlocksByRange(block) | Response::BlocksByRoot(block) => block.is_some(),
+            Response::Status(_) => false,
+        };
+
+        if self.timed_out.contains(&id) {
+            if !is_chunk {
+                // the late response has ended
+                self.timed_out.remove(&id);
+            }
+            return None;
+        }
+
+        let tracked = self.requests.get(&id)?;
+        let requester_id = tracked.request_id;
+        if is_chunk {
+            // the peer is making progress, extend the deadline
+            self.deadlines.update_timeout(&id, tracked.policy.timeout);
+        } else {
+            // the request has completed
+            self.remove(id);
+        }
+        Some(requester_id)
+    }
+
+    /// Registers the failure of the request sent with `request_id`.
+    ///
+    /// Returns the id given to the request by the requester, or `None` if the request has already
+    /// timed out.
+    pub fn request_failed(&mut self, request_id: RequestId) -> Option<RequestId> {
+        let id = match request_id {
+            RequestId::Network(id) => id,
+            _ => return Some(request_id),
+        };
+        if self.timed_out.remove(&id) {
+            return None;
+        }
+        self.remove(id).map(|tracked| tracked.request_id)
+    }
+
+    /// Stops tracking a request.
+    fn remove(&mut self, id: usize) -> Option<TrackedRequest> {
+        self.deadlines.remove(&id);
+        self.requests.remove(&id)
+    }
+}
+
+impl Stream for RpcRequestTracker {
+    type Item = RpcTrackerEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // forget about timed out requests that are no longer expected to receive responses
+        while let Poll::Ready(Some(_)) = self.timed_out.poll_next_unpin(cx) {}
+
+        loop {
+            match self.deadlines.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(id))) => {
+                    let tracked = match self.requests.get_mut(&id) {
+                        Some(tracked) => tracked,
+                        None => continue,
+                    };
+
+                    if tracked.retries < tracked.policy.max_retries {
+                        tracked.retries += 1;
+                        let peer_id = tracked.peer_id.clone();
+                        let request = tracked.request.clone();
+                        let timeout = tracked.policy.timeout;
+                        self.deadlines.insert_at(id, timeout);
+                        return Poll::Ready(Some(RpcTrackerEvent::Retry {
+                            peer_id,
+                            request_id: RequestId::Network(id),
+                            request,
+                        }));
+                    }
+
+                    if let Some(tracked) = self.requests.remove(&id) {
+                        self.timed_out.insert(id);
+                        return Poll::Ready(Some(RpcTrackerEvent::Timeout {
+                            peer_id: tracked.peer_id,
+                            request_id: tracked.request_id,
+                        }));
+                    }
+                }
+                // the deadline of a completed request, ignore it
+                Poll::Ready(Some(Err(_))) => continue,
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth2_libp2p::rpc::{GoodbyeReason, StatusMessage};
+    use types::{Epoch, Hash256, MinimalEthSpec, Slot};
+
+    type E = MinimalEthSpec;
+
+    /// The timeout of requests which are not expected to time out during a test.
+    const LONG_TIMEOUT: Duration = Duration::from_secs(60);
+
+    fn status() -> StatusMessage {
+        StatusMessage {
+            fork_digest: [0; 4],
+            finalized_root: Hash256::zero(),
+            finalized_epoch: Epoch::new(0),
+            head_root: Hash256::zero(),
+            head_slot: Slot::new(0),
+        }
+    }
+
+    fn policies(timeout: Duration, max_retries: u8) -> RetryPolicies {
+        let policy = RetryPolicy {
+            timeout,
+            max_retries,
+        };
+        RetryPolicies {
+            status: policy,
+            blocks_by_range: policy,
+            blocks_by_root: policy,
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_with_equal_ids_are_tracked_separately() {
+        let mut tracker = RpcRequestTracker::new(policies(LONG_TIMEOUT, 0));
+        let peer_id = PeerId::random();
+        let request = Request::Status(status());
+
+        let first = tracker.request_sent(&peer_id, RequestId::Router, &request);
+        let second = tracker.request_sent(&peer_id, RequestId::Router, &request);
+        assert_ne!(first, second, "requests should be sent with unique ids");
+
+        assert_eq!(
+            tracker.response_received::<E>(first, &Response::Status(status())),
+            Some(RequestId::Router)
+        );
+        assert_eq!(
+            tracker.response_received::<E>(first, &Response::Status(status())),
+            None,
+            "the first request should have completed"
+        );
+        assert_eq!(
+            tracker.response_received::<E>(second, &Response::Status(status())),
+            Some(RequestId::Router),
+            "the second request should still be awaiting its response"
+        );
+    }
+
+    #[tokio::test]
+    async fn failures_are_mapped_to_requester_ids() {
+        let mut tracker = RpcRequestTracker::new(policies(LONG_TIMEOUT, 0));
+        let peer_id = PeerId::random();
+
+        let id = tracker.request_sent(&peer_id, RequestId::Sync(7), &Request::Status(status()));
+        assert_eq!(tracker.request_failed(id), Some(RequestId::Sync(7)));
+        assert_eq!(tracker.request_failed(id), None);
+    }
+
+    #[tokio::test]
+    async fn requests_without_responses_are_not_tracked() {
+        let mut tracker = RpcRequestTracker::new(policies(LONG_TIMEOUT, 0));
+        let peer_id = PeerId::random();
+
+        let id = tracker.request_sent(
+            &peer_id,
+            RequestId::Router,
+            &Request::Goodbye(GoodbyeReason::ClientShutdown),
+        );
+        assert_eq!(id, RequestId::Router);
+        assert_eq!(tracker.request_failed(id), Some(RequestId::Router));
+    }
+
+    #[tokio::test]
+    async fn timed_out_requests_are_retried_then_dropped() {
+        let mut tracker = RpcRequestTracker::new(policies(Duration::from_millis(10), 1));
+        let peer_id = PeerId::random();
+
+        let id = tracker.request_sent(&peer_id, RequestId::Router, &Request::Status(status()));
+
+        match tracker.next().await {
+            Some(RpcTrackerEvent::Retry { request_id, .. }) => assert_eq!(request_id, id),
+            other => panic!("expected a retry, got {:?}", other),
+        }
+        match tracker.next().await {
+            Some(RpcTrackerEvent::Timeout {
+                peer_id: timed_out_peer,
+                request_id,
+            }) => {
+                assert_eq!(timed_out_peer, peer_id);
+                assert_eq!(request_id, RequestId::Router);
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+
+        assert_eq!(
+            tracker.response_received::<E>(id, &Response::Status(status())),
+            None,
+            "late responses should be dropped"
+        );
+    }
+}
@@ -1,25 +1,39 @@
+use super::fork_choice_scheduler::{run_fork_choice, ForkChoiceScheduler};
+use crate::beacon_processor::{BeaconProcessorSend, WorkType};
+use crate::metrics;
 use crate::router::processor::FUTURE_SLOT_TOLERANCE;
 use crate::sync::manager::SyncMessage;
 use crate::sync::range_sync::{BatchId, ChainId};
 use beacon_chain::{BeaconChain, BeaconChainTypes, BlockError, ChainSegmentResult};
 use eth2_libp2p::PeerId;
-use slog::{debug, error, trace, warn};
+use parking_lot::Mutex;
+use slog::{crit, debug, error, trace, warn};
 use std::sync::{Arc, Weak};
-use tokio::sync::mpsc;
-use types::SignedBeaconBlock;
+use tokio::sync::{broadcast, mpsc};
+use types::{Hash256, SignedBeaconBlock, Slot};
 
 /// Id associated to a block processing request, either a batch or a single block.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ProcessId {
-    /// Processing Id of a range syncing batch.
-    RangeBatchId(ChainId, BatchId),
-    /// Processing Id of the parent lookup of a block
-    ParentLookup(PeerId),
+    /// Processing Id of a range syncing batch. The peer is the peer that served the batch.
+    RangeBatchId(ChainId, BatchId, PeerId),
+    /// Processing Id of the parent lookup of a block. The peer is the last peer to submit a block
+    /// to the lookup and the hash is the root of the block that triggered the lookup.
+    ParentLookup {
+        peer_id: PeerId,
+        chain_head: Hash256,
+    },
+    /// Processing Id of a block obtained from a single block lookup. The peer is the peer that
+    /// served the block.
+    SingleBlock {
+        peer_id: PeerId,
+        block_root: Hash256,
+    },
 }
 
 /// The result of a block processing request.
 // TODO: When correct batch error handling occurs, we will include an error type.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BatchProcessResult {
     /// The batch was completed successfully.
     Success,
@@ -29,18 +43,75 @@ pub enum BatchProcessResult {
     Partial,
 }
 
-/// Spawns a thread handling the block processing of a request: range syncing or parent lookup.
+/// The result of processing a block obtained from a single block lookup.
+#[derive(Debug)]
+pub enum SingleBlockProcessResult {
+    /// The block was imported, or was already known to us.
+    Imported,
+    /// The parent of the block is unknown and a parent lookup is required.
+    ParentUnknown,
+    /// The block was invalid.
+    Failed,
+    /// An internal error occurred whilst processing the block. This is not attributed to the peer.
+    Error,
+}
+
+/// Events emitted by the block processor as requests are processed.
+///
+/// These are broadcast to any interested subscriber (e.g. the HTTP API or metrics), independently
+/// of the results sent back to the sync manager.
+#[derive(Debug, Clone)]
+pub enum BlockProcessorEvent {
+    /// A block obtained from a single block lookup was imported.
+    BlockImported { block_root: Hash256, slot: Slot },
+    /// A range sync batch has been processed.
+    BatchCompleted {
+        chain_id: ChainId,
+        batch_id: BatchId,
+        imported_blocks: usize,
+        result: BatchProcessResult,
+    },
+    /// Processing failed due to a block which is invalid, sent by `peer_id`.
+    InvalidBlock { peer_id: PeerId, reason: String },
+}
+
+/// The reason a chain segment failed to be processed.
+struct ChainSegmentFailed {
+    /// A description of the failure, for logging.
+    message: String,
+    /// Whether the failure was caused by an invalid block, rather than, e.g., an internal error or
+    /// a block from the future.
+    invalid_block: bool,
+}
+
+/// Broadcasts an event to the subscribers of the block processor. Having no subscribers is not an
+/// error.
+fn send_event(events: &broadcast::Sender<BlockProcessorEvent>, event: BlockProcessorEvent) {
+    let _ = events.send(event);
+}
+
+/// Submits the block processing of a request to the beacon processor: range syncing, parent
+/// lookup or single block lookup.
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_block_processor<T: BeaconChainTypes>(
+    beacon_processor: &BeaconProcessorSend,
     chain: Weak<BeaconChain<T>>,
     process_id: ProcessId,
     downloaded_blocks: Vec<SignedBeaconBlock<T::EthSpec>>,
     sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+    events: broadcast::Sender<BlockProcessorEvent>,
+    fork_choice_scheduler: Arc<Mutex<ForkChoiceScheduler>>,
     log: slog::Logger,
 ) {
-    std::thread::spawn(move || {
+    let work_type = match process_id {
+        ProcessId::RangeBatchId(..) => WorkType::ChainSegment,
+        ProcessId::ParentLookup { .. } | ProcessId::SingleBlock { .. } => WorkType::RpcBlock,
+    };
+
+    beacon_processor.submit(work_type, move || {
         match process_id {
             // this a request from the range sync
-            ProcessId::RangeBatchId(chain_id, batch_id) => {
+            ProcessId::RangeBatchId(chain_id, batch_id, peer_id) => {
                 let len = downloaded_blocks.len();
                 let start_slot = if len > 0 {
                     downloaded_blocks[0].message.slot.as_u64()
@@ -54,22 +125,54 @@ pub fn spawn_block_processor<T: BeaconChainTypes>(
                 };
 
                 debug!(log, "Processing batch"; "id" => *batch_id, "blocks" => downloaded_blocks.len(),  "start_slot" => start_slot, "end_slot" => end_slot);
-                let result = match process_blocks(chain, downloaded_blocks.iter(), &log) {
-                    (_, Ok(_)) => {
+                let timer = metrics::start_timer(&metrics::SYNC_BATCH_PROCESSING_TIMES);
+                let (imported_blocks, outcome) = process_blocks(
+                    chain,
+                    downloaded_blocks.iter(),
+                    true,
+                    &fork_choice_scheduler,
+                    &log,
+                );
+                metrics::stop_timer(timer);
+                let result = match outcome {
+                    Ok(_) => {
                         debug!(log, "Batch processed"; "id" => *batch_id , "start_slot" => start_slot, "end_slot" => end_slot);
                         BatchProcessResult::Success
                     }
-                    (imported_blocks, Err(e)) if imported_blocks > 0 => {
-                        warn!(log, "Batch processing failed but imported some blocks";
-                            "id" => *batch_id, "error" => e, "imported_blocks"=> imported_blocks);
-                        BatchProcessResult::Partial
-                    }
-                    (_, Err(e)) => {
-                        warn!(log, "Batch processing failed"; "id" => *batch_id, "error" => e);
-                        BatchProcessResult::Failed
+                    Err(e) => {
+                        if imported_blocks > 0 {
+                            warn!(log, "Batch processing failed but imported some blocks";
+                                "id" => *batch_id, "error" => &e.message, "imported_blocks"=> imported_blocks);
+                        } else {
+                            warn!(log, "Batch processing failed"; "id" => *batch_id, "error" => &e.message);
+                        }
+                        if e.invalid_block {
+                            send_event(
+                                &events,
+                                BlockProcessorEvent::InvalidBlock {
+                                    peer_id,
+                                    reason: e.message,
+                                },
+                            );
+                        }
+                        if imported_blocks > 0 {
+                            BatchProcessResult::Partial
+                        } else {
+                            BatchProcessResult::Failed
+                        }
                     }
                 };
 
+                send_event(
+                    &events,
+                    BlockProcessorEvent::BatchCompleted {
+                        chain_id,
+                        batch_id,
+                        imported_blocks,
+                        result: result.clone(),
+                    },
+                );
+
                 let msg = SyncMessage::BatchProcessed {
                     chain_id,
                     batch_id,
@@ -84,19 +187,41 @@ pub fn spawn_block_processor<T: BeaconChainTypes>(
                 });
             }
             // this a parent lookup request from the sync manager
-            ProcessId::ParentLookup(peer_id) => {
+            ProcessId::ParentLookup {
+                peer_id,
+                chain_head,
+            } => {
                 debug!(
                     log, "Processing parent lookup";
                     "last_peer_id" => format!("{}", peer_id),
+                    "chain_head" => format!("{}", chain_head),
                     "blocks" => downloaded_blocks.len()
                 );
                 // parent blocks are ordered from highest slot to lowest, so we need to process in
                 // reverse
-                match process_blocks(chain, downloaded_blocks.iter().rev(), &log) {
+                match process_blocks(
+                    chain,
+                    downloaded_blocks.iter().rev(),
+                    false,
+                    &fork_choice_scheduler,
+                    &log,
+                ) {
                     (_, Err(e)) => {
-                        warn!(log, "Parent lookup failed"; "last_peer_id" => format!("{}", peer_id), "error" => e);
+                        warn!(log, "Parent lookup failed"; "last_peer_id" => format!("{}", peer_id), "error" => &e.message);
+                        if e.invalid_block {
+                            send_event(
+                                &events,
+                                BlockProcessorEvent::InvalidBlock {
+                                    peer_id: peer_id.clone(),
+                                    reason: e.message,
+                                },
+                            );
+                        }
                         sync_send
-                        .send(SyncMessage::ParentLookupFailed(peer_id))
+                        .send(SyncMessage::ParentLookupFailed {
+                            peer_id,
+                            chain_head,
+                        })
                         .unwrap_or_else(|_| {
                             // on failure, inform to downvote the peer
                             debug!(
@@ -110,11 +235,53 @@ pub fn spawn_block_processor<T: BeaconChainTypes>(
                     }
                 }
             }
+            // this is a block obtained from a single block lookup
+            ProcessId::SingleBlock {
+                peer_id,
+                block_root,
+            } => {
+                let block = match downloaded_blocks.into_iter().next() {
+                    Some(block) => block,
+                    None => {
+                        return error!(log, "Single block lookup processing with no block";
+                            "block_root" => format!("{}", block_root));
+                    }
+                };
+                debug!(
+                    log, "Processing single block lookup";
+                    "peer_id" => format!("{}", peer_id),
+                    "block_root" => format!("{}", block_root),
+                    "slot" => block.message.slot,
+                );
+                let result = process_single_block(
+                    chain,
+                    block.clone(),
+                    &peer_id,
+                    &events,
+                    &fork_choice_scheduler,
+                    &log,
+                );
+                let msg = SyncMessage::SingleBlockProcessed {
+                    peer_id,
+                    block_root,
+                    block: Box::new(block),
+                    result,
+                };
+                sync_send.send(msg).unwrap_or_else(|_| {
+                    debug!(
+                        log,
+                        "Block processor could not inform single block lookup result. Likely shutting down."
+                    );
+                });
+            }
         }
     });
 }
 
 /// Helper function to process blocks batches which only consumes the chain and blocks to process.
+///
+/// `is_range_sync` indicates whether the blocks are a range sync batch, which determines how often
+/// fork choice is run.
 fn process_blocks<
     'a,
     T: BeaconChainTypes,
@@ -122,10 +289,17 @@ fn process_blocks<
 >(
     chain: Weak<BeaconChain<T>>,
     downloaded_blocks: I,
+    is_range_sync: bool,
+    fork_choice_scheduler: &Mutex<ForkChoiceScheduler>,
     log: &slog::Logger,
-) -> (usize, Result<(), String>) {
+) -> (usize, Result<(), ChainSegmentFailed>) {
     if let Some(chain) = chain.upgrade() {
         let blocks = downloaded_blocks.cloned().collect::<Vec<_>>();
+        let latest_block_slot = blocks
+            .iter()
+            .map(|block| block.message.slot)
+            .max()
+            .unwrap_or_else(|| Slot::new(0));
         let (imported_blocks, r) = match chain.process_chain_segment(blocks) {
             ChainSegmentResult::Successful { imported_blocks } => {
                 if imported_blocks == 0 {
@@ -135,8 +309,16 @@ fn process_blocks<
                         log, "Imported blocks from network";
                         "count" => imported_blocks,
                     );
-                    // Batch completed successfully with at least one block, run fork choice.
-                    run_fork_choice(chain, log);
+                    // Batch completed successfully with at least one block, schedule fork choice.
+                    let run = fork_choice_scheduler.lock().blocks_imported(
+                        &chain,
+                        latest_block_slot,
+                        is_range_sync,
+                        log,
+                    );
+                    if run {
+                        run_fork_choice(&chain, log);
+                    }
                 }
 
                 (imported_blocks, Ok(()))
@@ -147,7 +329,15 @@ fn process_blocks<
             } => {
                 let r = handle_failed_chain_segment(error, log);
                 if imported_blocks > 0 {
-                    run_fork_choice(chain, log);
+                    let run = fork_choice_scheduler.lock().blocks_imported(
+                        &chain,
+                        latest_block_slot,
+                        is_range_sync,
+                        log,
+                    );
+                    if run {
+                        run_fork_choice(&chain, log);
+                    }
                 }
                 (imported_blocks, r)
             }
@@ -159,27 +349,70 @@ fn process_blocks<
     (0, Ok(()))
 }
 
-/// Runs fork-choice on a given chain. This is used during block processing after one successful
-/// block import.
-fn run_fork_choice<T: BeaconChainTypes>(chain: Arc<BeaconChain<T>>, log: &slog::Logger) {
-    match chain.fork_choice() {
-        Ok(()) => trace!(
-            log,
-            "Fork choice success";
-            "location" => "batch processing"
-        ),
-        Err(e) => error!(
-            log,
-            "Fork choice failed";
-            "error" => format!("{:?}", e),
-            "location" => "batch import error"
-        ),
+/// Helper function to process a single block obtained from a block lookup.
+fn process_single_block<T: BeaconChainTypes>(
+    chain: Weak<BeaconChain<T>>,
+    block: SignedBeaconBlock<T::EthSpec>,
+    peer_id: &PeerId,
+    events: &broadcast::Sender<BlockProcessorEvent>,
+    fork_choice_scheduler: &Mutex<ForkChoiceScheduler>,
+    log: &slog::Logger,
+) -> SingleBlockProcessResult {
+    let chain = match chain.upgrade() {
+        Some(chain) => chain,
+        // The chain has been dropped, we are shutting down.
+        None => return SingleBlockProcessResult::Error,
+    };
+
+    let slot = block.message.slot;
+    match chain.process_block(block) {
+        Ok(block_root) => {
+            debug!(log, "Processed single block lookup"; "block" => format!("{}", block_root));
+            send_event(
+                events,
+                BlockProcessorEvent::BlockImported { block_root, slot },
+            );
+            let run = fork_choice_scheduler
+                .lock()
+                .blocks_imported(&chain, slot, false, log);
+            if run {
+                run_fork_choice(&chain, log);
+            }
+            SingleBlockProcessResult::Imported
+        }
+        Err(BlockError::BlockIsAlreadyKnown) => {
+            trace!(log, "Single block lookup already known");
+            SingleBlockProcessResult::Imported
+        }
+        Err(BlockError::ParentUnknown(_)) => SingleBlockProcessResult::ParentUnknown,
+        Err(BlockError::BeaconChainError(e)) => {
+            // An internal error occurred, this is not necessarily the fault of the peer.
+            warn!(log, "Unexpected block processing error"; "error" => format!("{:?}", e));
+            SingleBlockProcessResult::Error
+        }
+        Err(e) => {
+            warn!(log, "Single block lookup failed"; "outcome" => format!("{:?}", e));
+            if is_invalid_block(&e) {
+                send_event(
+                    events,
+                    BlockProcessorEvent::InvalidBlock {
+                        peer_id: peer_id.clone(),
+                        reason: format!("{:?}", e),
+                    },
+                );
+            }
+            SingleBlockProcessResult::Failed
+        }
     }
 }
 
 /// Helper function to handle a `BlockError` from `process_chain_segment`
-fn handle_failed_chain_segment(error: BlockError, log: &slog::Logger) -> Result<(), String> {
-    match error {
+fn handle_failed_chain_segment(
+    error: BlockError,
+    log: &slog::Logger,
+) -> Result<(), ChainSegmentFailed> {
+    let invalid_block = is_invalid_block(&error);
+    let result = match error {
         BlockError::ParentUnknown(parent) => {
             // blocks should be sequential and all parents should exist
 
@@ -227,6 +460,29 @@ fn handle_failed_chain_segment(error: BlockError, log: &slog::Logger) -> Result<
             debug!(log, "Genesis block was processed");
             Ok(())
         }
+        BlockError::KnownInvalidBlock(block_root) => {
+            // The state transition is not run again, the batch fails and the peer is penalized.
+            debug!(
+                log, "Known invalid block received";
+                "block_root" => format!("{}", block_root),
+            );
+
+            Err(format!("Peer sent a known invalid block: {}", block_root))
+        }
+        BlockError::WeakSubjectivityConflict { checkpoint, found } => {
+            // The beacon chain has already requested a shutdown.
+            crit!(
+                log, "Peer sent a chain conflicting with weak subjectivity checkpoint";
+                "checkpoint_epoch" => checkpoint.epoch,
+                "checkpoint_root" => format!("{}", checkpoint.root),
+                "found_root" => format!("{}", found),
+            );
+
+            Err(format!(
+                "Peer sent a chain conflicting with weak subjectivity checkpoint at epoch {}",
+                checkpoint.epoch
+            ))
+        }
         BlockError::BeaconChainError(e) => {
             warn!(
                 log, "BlockProcessingFailure";
@@ -245,5 +501,37 @@ fn handle_failed_chain_segment(error: BlockError, log: &slog::Logger) -> Result<
 
             Err(format!("Peer sent invalid block. Reason: {:?}", other))
         }
+    };
+
+    result.map_err(|message| ChainSegmentFailed {
+        message,
+        invalid_block,
+    })
+}
+
+/// Returns `true` if `error` proves that the block is invalid, in which case the peer which sent
+/// it is at fault. Blocks which are merely redundant, early, or which could not be verified due to
+/// an internal error are not invalid.
+pub fn is_invalid_block(error: &BlockError) -> bool {
+    match error {
+        BlockError::ParentUnknown(_)
+        | BlockError::FutureSlot { .. }
+        | BlockError::GenesisBlock
+        | BlockError::WouldRevertFinalizedSlot { .. }
+        | BlockError::BlockIsAlreadyKnown
+        | BlockError::RepeatProposal { .. }
+        | BlockError::WeakSubjectivityConflict { .. }
+        | BlockError::BeaconChainError(_) => false,
+        BlockError::StateRootMismatch { .. }
+        | BlockError::BlockSlotLimitReached
+        | BlockError::IncorrectBlockProposer { .. }
+        | BlockError::ProposalSignatureInvalid
+        | BlockError::UnknownValidator(_)
+        | BlockError::InvalidSignature
+        | BlockError::BlockIsNotLaterThanParent { .. }
+        | BlockError::NonLinearParentRoots
+        | BlockError::NonLinearSlots
+        | BlockError::PerBlockProcessingError(_)
+        | BlockError::KnownInvalidBlock(_) => true,
     }
 }
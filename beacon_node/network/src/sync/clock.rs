@@ -0,0 +1,30 @@
+//! The source of time of the `SyncManager`.
+//!
+//! Sync measures time to delay single block lookups and to estimate the time remaining for a
+//! range sync chain. The clock is injected into the `SyncManager` so that tests can advance time
+//! explicitly, rather than waiting for it to pass.
+
+use futures::future::BoxFuture;
+use std::time::Instant;
+
+/// Provides the current time to the components of a `SyncManager`.
+pub trait Clock: Send + Sync {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+
+    /// Returns a future which completes once the clock has reached `deadline`.
+    fn delay_until(&self, deadline: Instant) -> BoxFuture<'static, ()>;
+}
+
+/// The clock of the operating system, for use on a live network.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn delay_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::delay_until(deadline.into()))
+    }
+}
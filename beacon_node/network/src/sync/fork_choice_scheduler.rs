@@ -0,0 +1,143 @@
+//! Schedules the execution of fork choice following the import of blocks by the block processor.
+//!
+//! Running fork choice after every processed batch is wasteful during a long range sync, as the
+//! head is superseded by the next batch moments later. Instead, fork choice is run at most once
+//! per `FORK_CHOICE_BATCH_INTERVAL` batches during a range sync and at most once per slot
+//! otherwise. Fork choice is always run when the imported blocks are near the current slot, and
+//! once a range sync completes.
+
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use slog::{error, trace};
+use types::Slot;
+
+/// The number of range sync batches imported between executions of fork choice.
+const FORK_CHOICE_BATCH_INTERVAL: usize = 8;
+
+/// Imported blocks within this number of slots of the current slot always trigger fork choice.
+const NEAR_HEAD_TOLERANCE: u64 = 2;
+
+/// Debounces the execution of fork choice for imported blocks.
+#[derive(Default)]
+pub struct ForkChoiceScheduler {
+    /// The slot at which fork choice was last run.
+    last_run_slot: Option<Slot>,
+    /// The number of imports since fork choice was last run.
+    pending_imports: usize,
+}
+
+impl ForkChoiceScheduler {
+    /// Registers the import of blocks, returning whether fork choice is due. If it is, the caller
+    /// must run it with `run_fork_choice`, after releasing any lock on the scheduler.
+    ///
+    /// `latest_block_slot` is the highest slot of the imported blocks and `is_range_sync`
+    /// indicates whether they were imported as part of a range sync batch.
+    pub fn blocks_imported<T: BeaconChainTypes>(
+        &mut self,
+        chain: &BeaconChain<T>,
+        latest_block_slot: Slot,
+        is_range_sync: bool,
+        log: &slog::Logger,
+    ) -> bool {
+        let current_slot = match chain.slot() {
+            Ok(slot) => slot,
+            // without a slot clock, fall back to running fork choice on every import
+            Err(_) => latest_block_slot,
+        };
+
+        if self.register_import(current_slot, latest_block_slot, is_range_sync) {
+            self.mark_run(current_slot);
+            true
+        } else {
+            trace!(log, "Fork choice deferred";
+                "pending_imports" => self.pending_imports,
+                "latest_block_slot" => latest_block_slot,
+                "current_slot" => current_slot);
+            false
+        }
+    }
+
+    /// Returns whether any imports have occurred since fork choice was last run, in which case
+    /// the caller must run it with `run_fork_choice`. This is used once a range sync completes.
+    pub fn force_run<T: BeaconChainTypes>(&mut self, chain: &BeaconChain<T>) -> bool {
+        if self.pending_imports == 0 {
+            return false;
+        }
+        let current_slot = chain.slot().unwrap_or_else(|_| Slot::new(0));
+        self.mark_run(current_slot);
+        true
+    }
+
+    /// Counts an import and returns whether fork choice should be run.
+    fn register_import(
+        &mut self,
+        current_slot: Slot,
+        latest_block_slot: Slot,
+        is_range_sync: bool,
+    ) -> bool {
+        self.pending_imports += 1;
+
+        if latest_block_slot + NEAR_HEAD_TOLERANCE >= current_slot {
+            // the head is close to the wall-clock slot, keep it up to date
+            true
+        } else if is_range_sync {
+            self.pending_imports >= FORK_CHOICE_BATCH_INTERVAL
+        } else {
+            self.last_run_slot.map_or(true, |slot| slot < current_slot)
+        }
+    }
+
+    /// Records that fork choice is run at `current_slot` and resets the pending imports.
+    fn mark_run(&mut self, current_slot: Slot) {
+        self.last_run_slot = Some(current_slot);
+        self.pending_imports = 0;
+    }
+}
+
+/// Runs fork choice. This blocks on the fork choice lock of the `chain` and must not be called on
+/// the core executor, nor whilst holding the lock of the `ForkChoiceScheduler`.
+pub fn run_fork_choice<T: BeaconChainTypes>(chain: &BeaconChain<T>, log: &slog::Logger) {
+    match chain.fork_choice() {
+        Ok(()) => trace!(
+            log,
+            "Fork choice success";
+            "location" => "batch processing"
+        ),
+        Err(e) => error!(
+            log,
+            "Fork choice failed";
+            "error" => format!("{:?}", e),
+            "location" => "batch import error"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_sync_runs_once_per_interval() {
+        let mut scheduler = ForkChoiceScheduler::default();
+        let current_slot = Slot::new(1_000);
+        for _ in 1..FORK_CHOICE_BATCH_INTERVAL {
+            assert!(!scheduler.register_import(current_slot, Slot::new(100), true));
+        }
+        assert!(scheduler.register_import(current_slot, Slot::new(100), true));
+    }
+
+    #[test]
+    fn imports_near_head_always_run() {
+        let mut scheduler = ForkChoiceScheduler::default();
+        assert!(scheduler.register_import(Slot::new(100), Slot::new(99), true));
+        assert!(scheduler.register_import(Slot::new(100), Slot::new(100), false));
+    }
+
+    #[test]
+    fn other_imports_run_once_per_slot() {
+        let mut scheduler = ForkChoiceScheduler::default();
+        assert!(scheduler.register_import(Slot::new(100), Slot::new(10), false));
+        scheduler.mark_run(Slot::new(100));
+        assert!(!scheduler.register_import(Slot::new(100), Slot::new(11), false));
+        assert!(scheduler.register_import(Slot::new(101), Slot::new(12), false));
+    }
+}
@@ -23,33 +23,66 @@
 //! ## Parent Lookup
 //!
 //! When a block with an unknown parent is received and we are in `Regular` sync mode, the block is
-//! queued for lookup. A round-robin approach is used to request the parent from the known list of
-//! fully sync'd peers. If `PARENT_FAIL_TOLERANCE` attempts at requesting the block fails, we
-//! drop the propagated block and downvote the peer that sent it to us.
+//! queued for lookup. The parents are requested from the peers that have sent us a block of the
+//! chain. If a peer fails to provide a parent, the lookup continues from the blocks downloaded so
+//! far with another of these peers. The lookup is dropped once no peer is left, after
+//! `PARENT_FAIL_TOLERANCE` failed requests, or if the chain of parents grows beyond
+//! `PARENT_DEPTH_TOLERANCE` blocks.
 //!
-//! Block Lookup
+//! The roots of chains which fail to be processed are kept in a cache, such that blocks
+//! descending from them are not re-requested. Chains which merely fail to be downloaded are not
+//! cached, so they can be looked up again from other peers.
+//!
+//! ## Block Lookup
 //!
 //! To keep the logic maintained to the syncing thread (and manage the request_ids), when a block needs to be searched for (i.e
 //! if an attestation references an unknown block) this manager can search for the block and
 //! subsequently search for parents if needed.
-
-use super::block_processor::{spawn_block_processor, BatchProcessResult, ProcessId};
+//!
+//! Only a single lookup is made for any given block root. Peers that reference a root which is
+//! already being searched for are added to the existing lookup, and are used to re-request the
+//! block if the current peer fails to provide it. A lookup is dropped after
+//! `SINGLE_BLOCK_LOOKUP_MAX_ATTEMPTS` failed requests. Downloaded blocks are processed by the block
+//! processor thread.
+//!
+//! Lookups are not requested as soon as they are made. Instead, the lookups started by all of the
+//! queued messages are coalesced into a single `BlocksByRoot` request per peer, of at most
+//! `MAX_SINGLE_BLOCK_LOOKUPS_PER_REQUEST` roots. At most `MAX_SINGLE_BLOCK_REQUESTS_PER_PEER` of
+//! these requests are in flight to each peer; further lookups wait until a request completes.
+//!
+//! A block referenced by a peer is often one which is still being propagated on gossip, so single
+//! block lookups are delayed by a configurable fraction of a slot before they are requested. A
+//! delayed lookup is cancelled if the block is received on gossip in the meantime. The delays are
+//! measured by the injected `Clock`, and the manager waits on the clock until the next one is due.
+
+use super::block_processor::{
+    spawn_block_processor, BatchProcessResult, BlockProcessorEvent, ProcessId,
+    SingleBlockProcessResult,
+};
+use super::fork_choice_scheduler::ForkChoiceScheduler;
 use super::network_context::SyncNetworkContext;
 use super::peer_sync_info::{PeerSyncInfo, PeerSyncType};
 use super::range_sync::{BatchId, ChainId, RangeSync};
-use super::RequestId;
+use super::{Clock, RequestId, SyncRng};
+use crate::beacon_processor::BeaconProcessorSend;
 use crate::service::NetworkMessage;
 use beacon_chain::{BeaconChain, BeaconChainTypes, BlockProcessingOutcome};
 use eth2_libp2p::rpc::BlocksByRootRequest;
 use eth2_libp2p::types::NetworkGlobals;
 use eth2_libp2p::PeerId;
 use fnv::FnvHashMap;
+use futures::prelude::*;
+use lru::LruCache;
+use parking_lot::Mutex;
 use slog::{crit, debug, error, info, trace, warn, Logger};
+use slot_clock::SlotClock;
 use smallvec::SmallVec;
 use std::boxed::Box;
+use std::collections::HashSet;
 use std::ops::Sub;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
 use types::{EthSpec, Hash256, SignedBeaconBlock, Slot};
 
 /// The number of slots ahead of us that is allowed before requesting a long-range (batch)  Sync
@@ -62,6 +95,15 @@ const PARENT_FAIL_TOLERANCE: usize = 5;
 /// canonical chain to its head once the peer connects. A chain should not appear where it's depth
 /// is further back than the most recent head slot.
 const PARENT_DEPTH_TOLERANCE: usize = SLOT_IMPORT_TOLERANCE * 2;
+/// The number of failed parent lookup chains to remember. Blocks descending from these chains are
+/// not looked up again.
+const FAILED_CHAINS_CACHE_SIZE: usize = 500;
+/// The maximum number of failed requests for a single block lookup before the lookup is dropped.
+const SINGLE_BLOCK_LOOKUP_MAX_ATTEMPTS: usize = 3;
+/// The maximum number of `BlocksByRoot` requests of single block lookups in flight to each peer.
+const MAX_SINGLE_BLOCK_REQUESTS_PER_PEER: usize = 2;
+/// The maximum number of block roots in each `BlocksByRoot` request of single block lookups.
+const MAX_SINGLE_BLOCK_LOOKUPS_PER_REQUEST: usize = 32;
 
 #[derive(Debug)]
 /// A message than can be sent to the sync manager thread.
@@ -90,12 +132,18 @@ pub enum SyncMessage<T: EthSpec> {
     /// manager to attempt to find the block matching the unknown hash.
     UnknownBlockHash(PeerId, Hash256),
 
+    /// A block has been received on gossip, such that it need not be looked up.
+    GossipBlockReceived(Hash256),
+
     /// A peer has disconnected.
     Disconnect(PeerId),
 
     /// An RPC Error has occurred on a request.
     RPCError(PeerId, RequestId),
 
+    /// A peer failed to respond to a request before its deadline.
+    RpcTimeout(PeerId, RequestId),
+
     /// A batch has been processed by the block processor thread.
     BatchProcessed {
         chain_id: ChainId,
@@ -104,8 +152,21 @@ pub enum SyncMessage<T: EthSpec> {
         result: BatchProcessResult,
     },
 
-    /// A parent lookup has failed for a block given by this `peer_id`.
-    ParentLookupFailed(PeerId),
+    /// A parent lookup has failed for a block given by this `peer_id`. The `chain_head` is the
+    /// root of the block that triggered the lookup.
+    ParentLookupFailed {
+        peer_id: PeerId,
+        chain_head: Hash256,
+    },
+
+    /// A block obtained from a single block lookup has been processed by the block processor
+    /// thread.
+    SingleBlockProcessed {
+        peer_id: PeerId,
+        block_root: Hash256,
+        block: Box<SignedBeaconBlock<T>>,
+        result: SingleBlockProcessResult,
+    },
 }
 
 /// Maintains a sequential list of parents to lookup and the lookup's current state.
@@ -121,6 +182,9 @@ struct ParentRequests<T: EthSpec> {
     /// downvoted.
     last_submitted_peer: PeerId,
 
+    /// The peers that have sent a block of this chain and that can be asked for its parents.
+    available_peers: HashSet<PeerId>,
+
     /// The request ID of this lookup is in progress.
     pending: Option<RequestId>,
 }
@@ -148,55 +212,120 @@ pub struct SyncManager<T: BeaconChainTypes> {
     /// A collection of parent block lookups.
     parent_queue: SmallVec<[ParentRequests<T::EthSpec>; 3]>,
 
-    /// A collection of block hashes being searched for and a flag indicating if a result has been
-    /// received or not.
-    ///
-    /// The flag allows us to determine if the peer returned data or sent us nothing.
-    single_block_lookups: FnvHashMap<RequestId, SingleBlockRequest>,
+    /// A cache of block roots of parent lookups that have failed.
+    failed_chains: LruCache<Hash256, ()>,
+
+    /// The time to wait before requesting the block of a new single block lookup.
+    block_lookup_delay: Duration,
+
+    /// The single block lookups which are waiting for `block_lookup_delay` to elapse, by block
+    /// root, along with the instant at which they are due.
+    delayed_single_block_lookups: FnvHashMap<Hash256, (Instant, SingleBlockLookup)>,
+
+    /// The source of time of the delays of single block lookups and of range sync.
+    clock: Arc<dyn Clock>,
+
+    /// The single block lookups which are waiting to be requested.
+    pending_single_block_lookups: Vec<SingleBlockLookup>,
+
+    /// The `BlocksByRoot` requests of single block lookups in progress, by request id.
+    single_block_requests: FnvHashMap<RequestId, SingleBlockRequest>,
+
+    /// The single block lookups whose block is being processed, by block root.
+    processing_single_block_lookups: FnvHashMap<Hash256, SingleBlockLookup>,
 
     /// The logger for the import manager.
     log: Logger,
 
     /// The sending part of input_channel
     sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+
+    /// The broadcast channel of the events of the block processor threads.
+    block_processor_events: broadcast::Sender<BlockProcessorEvent>,
+
+    /// Schedules fork choice following the imports of the block processor threads.
+    fork_choice_scheduler: Arc<Mutex<ForkChoiceScheduler>>,
+
+    /// The queue to which the processing of downloaded blocks is submitted.
+    beacon_processor: BeaconProcessorSend,
 }
 
-/// Object representing a single block lookup request.
-struct SingleBlockRequest {
+/// Object representing a single block lookup.
+///
+/// Any peer that references the block whilst the lookup is in progress is added to the lookup, so
+/// that the block can be requested from another peer if the current request fails.
+struct SingleBlockLookup {
     /// The hash of the requested block.
     pub hash: Hash256,
-    /// Whether a block was received from this request, or the peer returned an empty response.
-    pub block_returned: bool,
+    /// The peers that have referenced this block and that can be asked for it.
+    pub available_peers: HashSet<PeerId>,
+    /// The number of failed attempts to retrieve the block.
+    pub failed_attempts: usize,
 }
 
-impl SingleBlockRequest {
-    pub fn new(hash: Hash256) -> Self {
+impl SingleBlockLookup {
+    pub fn new(hash: Hash256, peer_id: PeerId) -> Self {
+        let mut available_peers = HashSet::new();
+        available_peers.insert(peer_id);
         Self {
             hash,
-            block_returned: false,
+            available_peers,
+            failed_attempts: 0,
         }
     }
 }
 
+/// A `BlocksByRoot` request for the blocks of one or more single block lookups.
+struct SingleBlockRequest {
+    /// The peer to which the request was sent.
+    peer_id: PeerId,
+    /// The lookups whose block has not yet been received from the peer.
+    lookups: Vec<SingleBlockLookup>,
+}
+
 /// Spawns a new `SyncManager` thread which has a weak reference to underlying beacon
 /// chain. This allows the chain to be
 /// dropped during the syncing process which will gracefully end the `SyncManager`.
+///
+/// Single block lookups are requested once `block_lookup_delay` (a fraction of a slot) has elapsed.
+///
+/// All of the randomness of sync is drawn from `rng`, which may be seeded for reproducible tests,
+/// and all of its time from `clock`, which may be advanced manually by tests.
+#[allow(clippy::too_many_arguments)]
 pub fn spawn<T: BeaconChainTypes>(
     executor: environment::TaskExecutor,
     beacon_chain: Arc<BeaconChain<T>>,
     network_globals: Arc<NetworkGlobals<T::EthSpec>>,
     network_send: mpsc::UnboundedSender<NetworkMessage<T::EthSpec>>,
+    beacon_processor: BeaconProcessorSend,
+    block_processor_events: broadcast::Sender<BlockProcessorEvent>,
+    block_lookup_delay: f64,
+    batch_lookahead: u64,
+    rng: SyncRng,
+    clock: Arc<dyn Clock>,
     log: slog::Logger,
 ) -> mpsc::UnboundedSender<SyncMessage<T::EthSpec>> {
     // generate the message channel
     let (sync_send, sync_recv) = mpsc::unbounded_channel::<SyncMessage<T::EthSpec>>();
 
+    let fork_choice_scheduler = Arc::new(Mutex::new(ForkChoiceScheduler::default()));
+    let block_lookup_delay = beacon_chain
+        .slot_clock
+        .slot_duration()
+        .mul_f64(block_lookup_delay);
+
     // create an instance of the SyncManager
     let mut sync_manager = SyncManager {
         range_sync: RangeSync::new(
             beacon_chain.clone(),
             network_globals.clone(),
             sync_send.clone(),
+            beacon_processor.clone(),
+            block_processor_events.clone(),
+            fork_choice_scheduler.clone(),
+            batch_lookahead,
+            rng,
+            clock.clone(),
             log.clone(),
         ),
         network: SyncNetworkContext::new(network_send, network_globals.clone(), log.clone()),
@@ -204,9 +333,18 @@ pub fn spawn<T: BeaconChainTypes>(
         network_globals,
         input_channel: sync_recv,
         parent_queue: SmallVec::new(),
-        single_block_lookups: FnvHashMap::default(),
+        failed_chains: LruCache::new(FAILED_CHAINS_CACHE_SIZE),
+        block_lookup_delay,
+        delayed_single_block_lookups: FnvHashMap::default(),
+        clock,
+        pending_single_block_lookups: Vec::new(),
+        single_block_requests: FnvHashMap::default(),
+        processing_single_block_lookups: FnvHashMap::default(),
         log: log.clone(),
         sync_send: sync_send.clone(),
+        block_processor_events,
+        fork_choice_scheduler,
+        beacon_processor,
     };
 
     // spawn the sync manager thread
@@ -290,10 +428,8 @@ impl<T: BeaconChainTypes> SyncManager<T> {
     }
 
     /// The response to a `BlocksByRoot` request.
-    /// The current implementation takes one block at a time. As blocks are streamed, any
-    /// subsequent blocks will simply be ignored.
     /// There are two reasons we could have received a BlocksByRoot response
-    /// - We requested a single hash and have received a response for the single_block_lookup
+    /// - We requested the blocks of single block lookups, each of which may be in the response
     /// - We are looking up parent blocks in parent lookup search
     fn blocks_by_root_response(
         &mut self,
@@ -305,15 +441,9 @@ impl<T: BeaconChainTypes> SyncManager<T> {
             Some(block) => {
                 // data was returned, not just a stream termination
 
-                // check if this is a single block lookup - i.e we were searching for a specific hash
-                let mut single_block_hash = None;
-                if let Some(block_request) = self.single_block_lookups.get_mut(&request_id) {
-                    // update the state of the lookup indicating a block was received from the peer
-                    block_request.block_returned = true;
-                    single_block_hash = Some(block_request.hash.clone());
-                }
-                if let Some(block_hash) = single_block_hash {
-                    self.single_block_lookup_response(peer_id, block, block_hash);
+                // check if this is a single block lookup - i.e we were searching for specific hashes
+                if self.single_block_requests.contains_key(&request_id) {
+                    self.single_block_lookup_response(peer_id, request_id, block);
                     return;
                 }
 
@@ -333,6 +463,7 @@ impl<T: BeaconChainTypes> SyncManager<T> {
                     }
                 };
                 // add the block to response
+                parent_request.last_submitted_peer = peer_id;
                 parent_request.downloaded_blocks.push(block);
                 // queue for processing
                 self.process_parent_request(parent_request);
@@ -340,12 +471,15 @@ impl<T: BeaconChainTypes> SyncManager<T> {
             None => {
                 // this is a stream termination
 
-                // stream termination for a single block lookup, remove the key
-                if let Some(single_block_request) = self.single_block_lookups.remove(&request_id) {
-                    // the peer didn't respond with a block that it referenced
-                    if !single_block_request.block_returned {
-                        warn!(self.log, "Peer didn't respond with a block it referenced"; "referenced_block_hash" => format!("{}", single_block_request.hash), "peer_id" =>  format!("{}", peer_id));
-                        self.network.downvote_peer(peer_id);
+                // stream termination for single block lookups
+                if let Some(request) = self.single_block_requests.remove(&request_id) {
+                    // the lookups that remain in the request were not answered by the peer
+                    if !request.lookups.is_empty() {
+                        warn!(self.log, "Peer didn't respond with blocks it referenced"; "missing_blocks" => request.lookups.len(), "peer_id" =>  format!("{}", peer_id));
+                        self.network.downvote_peer(peer_id.clone());
+                        for lookup in request.lookups {
+                            self.single_block_lookup_failed(lookup, &peer_id);
+                        }
                     }
                     return;
                 }
@@ -365,67 +499,195 @@ impl<T: BeaconChainTypes> SyncManager<T> {
                     }
                 };
                 // An empty response has been returned to a parent request
-                // if an empty response is given, the peer didn't have the requested block, try again
-                parent_request.failed_attempts += 1;
-                parent_request.last_submitted_peer = peer_id;
-                self.request_parent(parent_request);
+                // if an empty response is given, the peer didn't have the requested block, try
+                // another peer
+                self.parent_lookup_failed(parent_request, &peer_id);
             }
         }
     }
 
-    /// Processes the response obtained from a single block lookup search. If the block is
-    /// processed or errors, the search ends. If the blocks parent is unknown, a block parent
-    /// lookup search is started.
+    /// Handles a block obtained from a single block lookup search. If the block is one we
+    /// requested, it is sent to the block processor thread. Otherwise the peer is downvoted.
     fn single_block_lookup_response(
         &mut self,
         peer_id: PeerId,
+        request_id: RequestId,
         block: SignedBeaconBlock<T::EthSpec>,
-        expected_block_hash: Hash256,
     ) {
-        // verify the hash is correct and try and process the block
-        if expected_block_hash != block.canonical_root() {
-            // the peer that sent this, sent us the wrong block
-            warn!(self.log, "Peer sent incorrect block for single block lookup"; "peer_id" => format!("{}", peer_id));
-            self.network.downvote_peer(peer_id);
+        let request = match self.single_block_requests.get_mut(&request_id) {
+            Some(request) => request,
+            None => return,
+        };
+        let block_root = block.canonical_root();
+
+        let lookup = match request
+            .lookups
+            .iter()
+            .position(|lookup| lookup.hash == block_root)
+        {
+            Some(pos) => request.lookups.remove(pos),
+            // a block repeated in the stream is ignored
+            None if self
+                .processing_single_block_lookups
+                .contains_key(&block_root) =>
+            {
+                return
+            }
+            None => {
+                // the peer that sent this, sent us a block we didn't request
+                warn!(self.log, "Peer sent incorrect block for single block lookup"; "peer_id" => format!("{}", peer_id), "block" => format!("{}", block_root));
+                self.network.downvote_peer(peer_id);
+                return;
+            }
+        };
+        // the lookup is kept until the block is processed
+        self.processing_single_block_lookups
+            .insert(block_root, lookup);
+
+        // we have a requested block, send it to be processed
+        spawn_block_processor(
+            &self.beacon_processor,
+            Arc::downgrade(&self.chain),
+            ProcessId::SingleBlock {
+                peer_id,
+                block_root,
+            },
+            vec![block],
+            self.sync_send.clone(),
+            self.block_processor_events.clone(),
+            self.fork_choice_scheduler.clone(),
+            self.log.clone(),
+        );
+    }
+
+    /// A block obtained from a single block lookup has been processed. The lookup is complete. If
+    /// the blocks parent is unknown, a block parent lookup search is started.
+    fn single_block_processed(
+        &mut self,
+        peer_id: PeerId,
+        block_root: Hash256,
+        block: SignedBeaconBlock<T::EthSpec>,
+        result: SingleBlockProcessResult,
+    ) {
+        self.processing_single_block_lookups.remove(&block_root);
+
+        match result {
+            SingleBlockProcessResult::Imported | SingleBlockProcessResult::Error => {}
+            SingleBlockProcessResult::ParentUnknown => {
+                // We don't know of the blocks parent, begin a parent lookup search
+                self.add_unknown_block(peer_id, block);
+            }
+            SingleBlockProcessResult::Failed => {
+                self.network.downvote_peer(peer_id);
+            }
+        }
+    }
+
+    /// A request for a single block lookup has failed. The peer is removed from the lookup and the
+    /// block will be requested from another peer that has referenced it, if any.
+    fn single_block_lookup_failed(&mut self, mut lookup: SingleBlockLookup, peer_id: &PeerId) {
+        lookup.available_peers.remove(peer_id);
+        lookup.failed_attempts += 1;
+        self.pending_single_block_lookups.push(lookup);
+    }
+
+    /// Returns every single block lookup, whether it is delayed, pending, requested or being
+    /// processed.
+    fn single_block_lookups_mut(&mut self) -> impl Iterator<Item = &mut SingleBlockLookup> {
+        self.delayed_single_block_lookups
+            .values_mut()
+            .map(|(_, lookup)| lookup)
+            .chain(self.pending_single_block_lookups.iter_mut())
+            .chain(
+                self.single_block_requests
+                    .values_mut()
+                    .flat_map(|request| request.lookups.iter_mut()),
+            )
+            .chain(self.processing_single_block_lookups.values_mut())
+    }
+
+    /// Requests the blocks of the pending single block lookups from the peers that referenced
+    /// them.
+    ///
+    /// Lookups which share a peer are coalesced into a single `BlocksByRoot` request. Lookups
+    /// whose peers all have `MAX_SINGLE_BLOCK_REQUESTS_PER_PEER` requests in flight remain
+    /// pending.
+    fn request_single_blocks(&mut self) {
+        if self.pending_single_block_lookups.is_empty() {
             return;
         }
 
-        // we have the correct block, try and process it
-        match BlockProcessingOutcome::shim(self.chain.process_block(block.clone())) {
-            Ok(outcome) => {
-                match outcome {
-                    BlockProcessingOutcome::Processed { block_root } => {
-                        info!(self.log, "Processed block"; "block" => format!("{}", block_root));
-
-                        match self.chain.fork_choice() {
-                            Ok(()) => trace!(
-                                self.log,
-                                "Fork choice success";
-                                "location" => "single block"
-                            ),
-                            Err(e) => error!(
-                                self.log,
-                                "Fork choice failed";
-                                "error" => format!("{:?}", e),
-                                "location" => "single block"
-                            ),
-                        }
-                    }
-                    BlockProcessingOutcome::ParentUnknown { .. } => {
-                        // We don't know of the blocks parent, begin a parent lookup search
-                        self.add_unknown_block(peer_id, block);
-                    }
-                    BlockProcessingOutcome::BlockIsAlreadyKnown => {
-                        trace!(self.log, "Single block lookup already known");
-                    }
-                    _ => {
-                        warn!(self.log, "Single block lookup failed"; "outcome" => format!("{:?}", outcome));
-                        self.network.downvote_peer(peer_id);
-                    }
+        // the number of requests in flight to each peer, including the requests built below
+        let mut peer_requests: FnvHashMap<PeerId, usize> = FnvHashMap::default();
+        for request in self.single_block_requests.values() {
+            *peer_requests.entry(request.peer_id.clone()).or_default() += 1;
+        }
+
+        let mut new_requests: Vec<SingleBlockRequest> = vec![];
+        for lookup in std::mem::replace(&mut self.pending_single_block_lookups, vec![]) {
+            if lookup.failed_attempts >= SINGLE_BLOCK_LOOKUP_MAX_ATTEMPTS {
+                debug!(self.log, "Single block lookup failed";
+                    "block" => format!("{}", lookup.hash),
+                    "reason" => "too many failed attempts"
+                );
+                continue; // drop the lookup
+            }
+            if lookup.available_peers.is_empty() {
+                debug!(self.log, "Single block lookup failed";
+                    "block" => format!("{}", lookup.hash),
+                    "reason" => "no peers available"
+                );
+                continue; // drop the lookup
+            }
+
+            // add the lookup to a request already being built for one of its peers
+            if let Some(pos) = new_requests.iter().position(|request| {
+                request.lookups.len() < MAX_SINGLE_BLOCK_LOOKUPS_PER_REQUEST
+                    && lookup.available_peers.contains(&request.peer_id)
+            }) {
+                new_requests[pos].lookups.push(lookup);
+                continue;
+            }
+
+            // otherwise start a new request, to a peer which can accept another request
+            let peer_id = lookup
+                .available_peers
+                .iter()
+                .find(|peer_id| {
+                    peer_requests.get(*peer_id).copied().unwrap_or(0)
+                        < MAX_SINGLE_BLOCK_REQUESTS_PER_PEER
+                })
+                .cloned();
+            match peer_id {
+                Some(peer_id) => {
+                    *peer_requests.entry(peer_id.clone()).or_default() += 1;
+                    new_requests.push(SingleBlockRequest {
+                        peer_id,
+                        lookups: vec![lookup],
+                    });
                 }
+                None => self.pending_single_block_lookups.push(lookup),
             }
-            Err(e) => {
-                warn!(self.log, "Unexpected block processing error"; "error" => format!("{:?}", e));
+        }
+
+        for SingleBlockRequest { peer_id, lookups } in new_requests {
+            let request = BlocksByRootRequest {
+                block_roots: lookups.iter().map(|lookup| lookup.hash).collect(),
+            };
+
+            match self
+                .network
+                .blocks_by_root_request(peer_id.clone(), request)
+            {
+                Ok(request_id) => {
+                    self.single_block_requests
+                        .insert(request_id, SingleBlockRequest { peer_id, lookups });
+                }
+                Err(_) => {
+                    for lookup in lookups {
+                        self.single_block_lookup_failed(lookup, &peer_id);
+                    }
+                }
             }
         }
     }
@@ -453,64 +715,125 @@ impl<T: BeaconChainTypes> SyncManager<T> {
             }
         }
 
+        // Do not search for blocks that descend from a chain that has already failed
+        let block_root = block.canonical_root();
+        self.cancel_delayed_single_block_lookup(block_root);
+        if self.failed_chains.contains(&block_root)
+            || self.failed_chains.contains(&block.parent_root())
+        {
+            debug!(self.log, "Block descends from a failed chain. Ignoring"; "block_hash" => format!("{}", block_root));
+            self.failed_chains.put(block_root, ());
+            return;
+        }
+
         // Make sure this block is not already being searched for
         // NOTE: Potentially store a hashset of blocks for O(1) lookups
-        for parent_req in self.parent_queue.iter() {
+        for parent_req in self.parent_queue.iter_mut() {
             if parent_req
                 .downloaded_blocks
                 .iter()
                 .any(|d_block| d_block == &block)
             {
-                // we are already searching for this block, ignore it
+                // we are already searching for this block, the peer can be asked for its parents
+                parent_req.available_peers.insert(peer_id);
                 return;
             }
         }
 
-        debug!(self.log, "Unknown block received. Starting a parent lookup"; "block_slot" => block.message.slot, "block_hash" => format!("{}", block.canonical_root()));
+        debug!(self.log, "Unknown block received. Starting a parent lookup"; "block_slot" => block.message.slot, "block_hash" => format!("{}", block_root));
 
+        let mut available_peers = HashSet::new();
+        available_peers.insert(peer_id.clone());
         let parent_request = ParentRequests {
             downloaded_blocks: vec![block],
             failed_attempts: 0,
             last_submitted_peer: peer_id,
+            available_peers,
             pending: None,
         };
 
         self.request_parent(parent_request)
     }
 
-    /// A request to search for a block hash has been received. This function begins a BlocksByRoot
-    /// request to find the requested block.
+    /// A request to search for a block hash has been received. This function queues a lookup of
+    /// the block, which is requested by `Self::request_single_blocks`.
     fn search_for_block(&mut self, peer_id: PeerId, block_hash: Hash256) {
         // If we are not synced, ignore this block
         if !self.network_globals.sync_state.read().is_synced() {
             return;
         }
 
-        // Do not re-request a block that is already being requested
-        if self
-            .single_block_lookups
-            .values()
-            .find(|single_block_request| single_block_request.hash == block_hash)
-            .is_some()
+        // The block may have been imported since it was referenced, or belong to a failed chain
+        if self.chain.fork_choice.contains_block(&block_hash)
+            || self.failed_chains.contains(&block_hash)
         {
             return;
         }
 
-        let request = BlocksByRootRequest {
-            block_roots: vec![block_hash],
-        };
+        // Do not re-request a block that is already being requested, instead add the peer to the
+        // existing lookup
+        if let Some(lookup) = self
+            .single_block_lookups_mut()
+            .find(|lookup| lookup.hash == block_hash)
+        {
+            lookup.available_peers.insert(peer_id);
+            return;
+        }
+
+        debug!(self.log, "Searching for unknown block"; "block" => format!("{}", block_hash), "peer_id" => format!("{}", peer_id));
+        let lookup = SingleBlockLookup::new(block_hash, peer_id);
+        if self.block_lookup_delay == Duration::from_secs(0) {
+            self.pending_single_block_lookups.push(lookup);
+        } else {
+            // wait for the block to arrive on gossip before requesting it
+            let due = self.clock.now() + self.block_lookup_delay;
+            self.delayed_single_block_lookups
+                .insert(block_hash, (due, lookup));
+        }
+    }
+
+    /// The delays of the single block lookups which are due have elapsed. Their blocks are
+    /// requested in the order the lookups were made, unless they have been imported in the
+    /// meantime.
+    fn single_block_lookup_delays_elapsed(&mut self) {
+        let now = self.clock.now();
+        let mut due = self
+            .delayed_single_block_lookups
+            .iter()
+            .filter(|(_, (due, _))| *due <= now)
+            .map(|(block_root, (due, _))| (*due, *block_root))
+            .collect::<Vec<_>>();
+        due.sort();
+
+        for (_, block_root) in due {
+            if let Some((_, lookup)) = self.delayed_single_block_lookups.remove(&block_root) {
+                if !self.chain.fork_choice.contains_block(&block_root) {
+                    self.pending_single_block_lookups.push(lookup);
+                }
+            }
+        }
+    }
 
-        if let Ok(request_id) = self.network.blocks_by_root_request(peer_id, request) {
-            self.single_block_lookups
-                .insert(request_id, SingleBlockRequest::new(block_hash));
+    /// The block with `block_root` has been received other than by a single block lookup, so any
+    /// delayed lookup of the block is cancelled.
+    fn cancel_delayed_single_block_lookup(&mut self, block_root: Hash256) {
+        if self
+            .delayed_single_block_lookups
+            .remove(&block_root)
+            .is_some()
+        {
+            trace!(self.log, "Cancelled delayed block lookup"; "block" => format!("{}", block_root));
         }
     }
 
     fn inject_error(&mut self, peer_id: PeerId, request_id: RequestId) {
         trace!(self.log, "Sync manager received a failed RPC");
-        // remove any single block lookups
-        if self.single_block_lookups.remove(&request_id).is_some() {
-            // this was a single block request lookup, look no further
+        // if this was a request of single block lookups, try other peers for the blocks which
+        // were not returned
+        if let Some(request) = self.single_block_requests.remove(&request_id) {
+            for lookup in request.lookups {
+                self.single_block_lookup_failed(lookup, &peer_id);
+            }
             return;
         }
 
@@ -520,10 +843,8 @@ impl<T: BeaconChainTypes> SyncManager<T> {
             .iter()
             .position(|request| request.pending == Some(request_id))
         {
-            let mut parent_request = self.parent_queue.remove(pos);
-            parent_request.failed_attempts += 1;
-            parent_request.last_submitted_peer = peer_id;
-            self.request_parent(parent_request);
+            let parent_request = self.parent_queue.remove(pos);
+            self.parent_lookup_failed(parent_request, &peer_id);
             return;
         }
 
@@ -533,6 +854,13 @@ impl<T: BeaconChainTypes> SyncManager<T> {
     }
 
     fn peer_disconnect(&mut self, peer_id: &PeerId) {
+        // the peer can no longer serve any single block lookups
+        for lookup in self.single_block_lookups_mut() {
+            lookup.available_peers.remove(peer_id);
+        }
+        for parent_request in self.parent_queue.iter_mut() {
+            parent_request.available_peers.remove(peer_id);
+        }
         self.range_sync.peer_disconnect(&mut self.network, peer_id);
         self.update_sync_state();
     }
@@ -651,10 +979,16 @@ impl<T: BeaconChainTypes> SyncManager<T> {
                 Ok(BlockProcessingOutcome::Processed { .. })
                 | Ok(BlockProcessingOutcome::BlockIsAlreadyKnown { .. }) => {
                     spawn_block_processor(
+                        &self.beacon_processor,
                         Arc::downgrade(&self.chain),
-                        ProcessId::ParentLookup(parent_request.last_submitted_peer.clone()),
+                        ProcessId::ParentLookup {
+                            peer_id: parent_request.last_submitted_peer.clone(),
+                            chain_head: parent_request.downloaded_blocks[0].canonical_root(),
+                        },
                         parent_request.downloaded_blocks,
                         self.sync_send.clone(),
+                        self.block_processor_events.clone(),
+                        self.fork_choice_scheduler.clone(),
                         self.log.clone(),
                     );
                 }
@@ -668,37 +1002,60 @@ impl<T: BeaconChainTypes> SyncManager<T> {
                     );
                     self.network
                         .downvote_peer(parent_request.last_submitted_peer.clone());
+                    self.add_failed_chain(&parent_request);
                     return;
                 }
                 Err(e) => {
+                    // an internal error is not the fault of the chain, so it is neither
+                    // downvoted nor cached and can be looked up again
                     warn!(
-                        self.log, "Parent chain processing error. Downvoting peer";
+                        self.log, "Parent chain processing error";
                         "error" => format!("{:?}", e),
                         "last_peer" => format!("{:?}", parent_request.last_submitted_peer),
                     );
-                    self.network
-                        .downvote_peer(parent_request.last_submitted_peer.clone());
                     return;
                 }
             }
         }
     }
 
+    /// Adds the roots of all blocks of a failed parent lookup to the failed chains cache.
+    fn add_failed_chain(&mut self, parent_request: &ParentRequests<T::EthSpec>) {
+        for block in parent_request.downloaded_blocks.iter() {
+            self.failed_chains.put(block.canonical_root(), ());
+        }
+    }
+
+    /// A request for the next parent of a parent lookup has failed. The peer is removed from the
+    /// lookup and the parent is requested from another peer that has sent a block of the chain,
+    /// keeping the blocks downloaded so far.
+    fn parent_lookup_failed(
+        &mut self,
+        mut parent_request: ParentRequests<T::EthSpec>,
+        peer_id: &PeerId,
+    ) {
+        parent_request.available_peers.remove(peer_id);
+        parent_request.failed_attempts += 1;
+        self.request_parent(parent_request);
+    }
+
     /// Progresses a parent request query.
     ///
     /// This checks to ensure there a peers to progress the query, checks for failures and
     /// initiates requests.
     fn request_parent(&mut self, mut parent_request: ParentRequests<T::EthSpec>) {
         // check to make sure this request hasn't failed
-        if parent_request.failed_attempts >= PARENT_FAIL_TOLERANCE
-            || parent_request.downloaded_blocks.len() >= PARENT_DEPTH_TOLERANCE
-        {
-            let error = if parent_request.failed_attempts >= PARENT_FAIL_TOLERANCE {
-                "too many failed attempts"
-            } else {
-                "reached maximum lookup-depth"
-            };
-
+        let error = if parent_request.failed_attempts >= PARENT_FAIL_TOLERANCE {
+            Some("too many failed attempts")
+        } else if parent_request.downloaded_blocks.len() >= PARENT_DEPTH_TOLERANCE {
+            Some("reached maximum lookup-depth")
+        } else if parent_request.available_peers.is_empty() {
+            Some("no peers available")
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            // The chain has not been shown to be invalid, so it is not added to the failed chains
             debug!(self.log, "Parent import failed";
             "block" => format!("{:?}",parent_request.downloaded_blocks[0].canonical_root()),
             "ancestors_found" => parent_request.downloaded_blocks.len(),
@@ -714,18 +1071,124 @@ impl<T: BeaconChainTypes> SyncManager<T> {
             return;
         };
 
+        // The parent is known to belong to a failed chain
+        if self.failed_chains.contains(&parent_hash) {
+            debug!(self.log, "Parent lookup reached a failed chain";
+                "block" => format!("{:?}", parent_request.downloaded_blocks[0].canonical_root()),
+                "parent" => format!("{}", parent_hash),
+            );
+            self.add_failed_chain(&parent_request);
+            return; // drop the request
+        }
+
         let request = BlocksByRootRequest {
             block_roots: vec![parent_hash],
         };
 
-        // We continue to search for the chain of blocks from the same peer. Other peers are not
-        // guaranteed to have this chain of blocks.
-        let peer_id = parent_request.last_submitted_peer.clone();
+        // We continue to search for the chain of blocks from the peer that sent the last block,
+        // if it is still available. Otherwise another peer that has sent a block of the chain is
+        // asked, as peers which have not are not guaranteed to have this chain of blocks.
+        let peer_id = if parent_request
+            .available_peers
+            .contains(&parent_request.last_submitted_peer)
+        {
+            parent_request.last_submitted_peer.clone()
+        } else {
+            parent_request
+                .available_peers
+                .iter()
+                .next()
+                .cloned()
+                .expect("available peers is not empty")
+        };
 
-        if let Ok(request_id) = self.network.blocks_by_root_request(peer_id, request) {
-            // if the request was successful add the queue back into self
-            parent_request.pending = Some(request_id);
-            self.parent_queue.push(parent_request);
+        match self
+            .network
+            .blocks_by_root_request(peer_id.clone(), request)
+        {
+            Ok(request_id) => {
+                // if the request was successful add the queue back into self
+                parent_request.pending = Some(request_id);
+                self.parent_queue.push(parent_request);
+            }
+            Err(_) => self.parent_lookup_failed(parent_request, &peer_id),
+        }
+    }
+
+    /// Handles a message sent to the sync manager.
+    fn handle_message(&mut self, sync_message: SyncMessage<T::EthSpec>) {
+        match sync_message {
+            SyncMessage::AddPeer(peer_id, info) => {
+                self.add_peer(peer_id, info);
+            }
+            SyncMessage::BlocksByRangeResponse {
+                peer_id,
+                request_id,
+                beacon_block,
+            } => {
+                self.range_sync.blocks_by_range_response(
+                    &mut self.network,
+                    peer_id,
+                    request_id,
+                    beacon_block.map(|b| *b),
+                );
+            }
+            SyncMessage::BlocksByRootResponse {
+                peer_id,
+                request_id,
+                beacon_block,
+            } => {
+                self.blocks_by_root_response(peer_id, request_id, beacon_block.map(|b| *b));
+            }
+            SyncMessage::UnknownBlock(peer_id, block) => {
+                self.add_unknown_block(peer_id, *block);
+            }
+            SyncMessage::UnknownBlockHash(peer_id, block_hash) => {
+                self.search_for_block(peer_id, block_hash);
+            }
+            SyncMessage::GossipBlockReceived(block_root) => {
+                self.cancel_delayed_single_block_lookup(block_root);
+            }
+            SyncMessage::Disconnect(peer_id) => {
+                self.peer_disconnect(&peer_id);
+            }
+            SyncMessage::RPCError(peer_id, request_id) => {
+                self.inject_error(peer_id, request_id);
+            }
+            SyncMessage::RpcTimeout(peer_id, request_id) => {
+                debug!(self.log, "Sync request timed out"; "peer_id" => peer_id.to_string(), "request_id" => request_id);
+                // a timed out request is handled as a failed request
+                self.inject_error(peer_id, request_id);
+            }
+            SyncMessage::BatchProcessed {
+                chain_id,
+                batch_id,
+                downloaded_blocks,
+                result,
+            } => {
+                self.range_sync.handle_block_process_result(
+                    &mut self.network,
+                    chain_id,
+                    batch_id,
+                    downloaded_blocks,
+                    result,
+                );
+            }
+            SyncMessage::ParentLookupFailed {
+                peer_id,
+                chain_head,
+            } => {
+                self.failed_chains.put(chain_head, ());
+                self.network.downvote_peer(peer_id);
+            }
+            SyncMessage::SingleBlockProcessed {
+                peer_id,
+                block_root,
+                block,
+                result,
+            } => {
+                self.single_block_processed(peer_id, block_root, *block, result);
+            }
         }
     }
 
@@ -733,61 +1196,34 @@ impl<T: BeaconChainTypes> SyncManager<T> {
     async fn main(&mut self) {
         // process any inbound messages
         loop {
-            if let Some(sync_message) = self.input_channel.recv().await {
-                match sync_message {
-                    SyncMessage::AddPeer(peer_id, info) => {
-                        self.add_peer(peer_id, info);
-                    }
-                    SyncMessage::BlocksByRangeResponse {
-                        peer_id,
-                        request_id,
-                        beacon_block,
-                    } => {
-                        self.range_sync.blocks_by_range_response(
-                            &mut self.network,
-                            peer_id,
-                            request_id,
-                            beacon_block.map(|b| *b),
-                        );
-                    }
-                    SyncMessage::BlocksByRootResponse {
-                        peer_id,
-                        request_id,
-                        beacon_block,
-                    } => {
-                        self.blocks_by_root_response(peer_id, request_id, beacon_block.map(|b| *b));
-                    }
-                    SyncMessage::UnknownBlock(peer_id, block) => {
-                        self.add_unknown_block(peer_id, *block);
-                    }
-                    SyncMessage::UnknownBlockHash(peer_id, block_hash) => {
-                        self.search_for_block(peer_id, block_hash);
-                    }
-                    SyncMessage::Disconnect(peer_id) => {
-                        self.peer_disconnect(&peer_id);
-                    }
-                    SyncMessage::RPCError(peer_id, request_id) => {
-                        self.inject_error(peer_id, request_id);
-                    }
-                    SyncMessage::BatchProcessed {
-                        chain_id,
-                        batch_id,
-                        downloaded_blocks,
-                        result,
-                    } => {
-                        self.range_sync.handle_block_process_result(
-                            &mut self.network,
-                            chain_id,
-                            batch_id,
-                            downloaded_blocks,
-                            result,
-                        );
-                    }
-                    SyncMessage::ParentLookupFailed(peer_id) => {
-                        self.network.downvote_peer(peer_id);
+            // wait for the delay of the next single block lookup which is due, if any
+            let next_lookup_due = match self
+                .delayed_single_block_lookups
+                .values()
+                .map(|(due, _)| *due)
+                .min()
+            {
+                Some(due) => self.clock.delay_until(due),
+                None => future::pending::<()>().boxed(),
+            };
+
+            tokio::select! {
+                sync_message = self.input_channel.recv() => {
+                    if let Some(sync_message) = sync_message {
+                        self.handle_message(sync_message);
                     }
                 }
+                _ = next_lookup_due => {
+                    self.single_block_lookup_delays_elapsed();
+                }
+            }
+            // handle the other queued messages before requesting blocks, such that the single
+            // block lookups they start are coalesced into as few requests as possible
+            while let Ok(sync_message) = self.input_channel.try_recv() {
+                self.handle_message(sync_message);
             }
+            self.request_single_blocks();
+            self.range_sync.update_syncing_chains();
         }
     }
 }
@@ -2,13 +2,22 @@
 //!
 //! Stores the various syncing methods for the beacon chain.
 mod block_processor;
+mod clock;
+mod fork_choice_scheduler;
 pub mod manager;
 mod network_context;
 mod peer_sync_info;
 mod range_sync;
+mod rng;
+#[cfg(test)]
+mod tests;
 
+pub use block_processor::{is_invalid_block, BatchProcessResult, BlockProcessorEvent};
+pub use clock::{Clock, SystemClock};
 pub use manager::SyncMessage;
 pub use peer_sync_info::PeerSyncInfo;
+pub use range_sync::{BatchId, ChainId};
+pub use rng::SyncRng;
 
 /// Type of id of rpc requests sent by sync
 pub type RequestId = usize;
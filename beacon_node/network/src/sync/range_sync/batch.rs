@@ -1,4 +1,5 @@
 use super::chain::EPOCHS_PER_BATCH;
+use crate::metrics;
 use eth2_libp2p::rpc::methods::*;
 use eth2_libp2p::PeerId;
 use fnv::FnvHashMap;
@@ -51,6 +52,8 @@ pub struct Batch<T: EthSpec> {
     pub reprocess_retries: u8,
     /// Marks the batch as undergoing a re-process, with a hash of the original blocks it received.
     pub original_hash: Option<u64>,
+    /// The peers that have supplied blocks for this batch which failed processing.
+    pub failed_peers: HashSet<PeerId>,
     /// The blocks that have been downloaded.
     pub downloaded_blocks: Vec<SignedBeaconBlock<T>>,
 }
@@ -68,6 +71,7 @@ impl<T: EthSpec> Batch<T> {
             retries: 0,
             reprocess_retries: 0,
             original_hash: None,
+            failed_peers: HashSet::new(),
             downloaded_blocks: Vec::new(),
         }
     }
@@ -130,7 +134,11 @@ impl<T: EthSpec> PendingBatches<T> {
             .entry(peer_request)
             .or_insert_with(HashSet::new)
             .insert(request_id);
-        self.batches.insert(request_id, batch)
+        let previous = self.batches.insert(request_id, batch);
+        if previous.is_none() {
+            metrics::inc_gauge(&metrics::SYNC_BATCHES_IN_FLIGHT);
+        }
+        previous
     }
 
     pub fn remove(&mut self, request_id: usize) -> Option<Batch<T>> {
@@ -143,6 +151,7 @@ impl<T: EthSpec> PendingBatches<T> {
                     entry.remove();
                 }
             }
+            metrics::dec_gauge(&metrics::SYNC_BATCHES_IN_FLIGHT);
             Some(batch)
         } else {
             None
@@ -176,3 +185,12 @@ impl<T: EthSpec> PendingBatches<T> {
         self.remove(request_id)
     }
 }
+
+impl<T: EthSpec> Drop for PendingBatches<T> {
+    /// The batches of a dropped chain are no longer in flight.
+    fn drop(&mut self) {
+        if let Ok(gauge) = &*metrics::SYNC_BATCHES_IN_FLIGHT {
+            gauge.sub(self.batches.len() as i64);
+        }
+    }
+}
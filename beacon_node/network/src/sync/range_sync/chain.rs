@@ -1,14 +1,20 @@
 use super::batch::{Batch, BatchId, PendingBatches};
-use crate::sync::block_processor::{spawn_block_processor, BatchProcessResult, ProcessId};
+use crate::beacon_processor::BeaconProcessorSend;
+use crate::sync::block_processor::{
+    spawn_block_processor, BatchProcessResult, BlockProcessorEvent, ProcessId,
+};
+use crate::sync::fork_choice_scheduler::ForkChoiceScheduler;
 use crate::sync::network_context::SyncNetworkContext;
-use crate::sync::{RequestId, SyncMessage};
+use crate::sync::{Clock, RequestId, SyncMessage, SyncRng};
 use beacon_chain::{BeaconChain, BeaconChainTypes};
+use eth2_libp2p::types::{SyncingChainInfo, SyncingChainType};
 use eth2_libp2p::PeerId;
-use rand::prelude::*;
+use parking_lot::Mutex;
 use slog::{crit, debug, warn};
 use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
 use types::{Epoch, EthSpec, Hash256, SignedBeaconBlock, Slot};
 
 /// Blocks are downloaded in batches from peers. This constant specifies how many epochs worth of
@@ -22,9 +28,6 @@ pub const EPOCHS_PER_BATCH: u64 = 2;
 /// The number of times to retry a batch before the chain is considered failed and removed.
 const MAX_BATCH_RETRIES: u8 = 5;
 
-/// The maximum number of batches to queue before requesting more.
-const BATCH_BUFFER_SIZE: u8 = 5;
-
 /// Invalid batches are attempted to be re-downloaded from other peers. If they cannot be processed
 /// after `INVALID_BATCH_LOOKUP_ATTEMPTS` times, the chain is considered faulty and all peers will
 /// be downvoted.
@@ -84,10 +87,37 @@ pub struct SyncingChain<T: BeaconChainTypes> {
     /// The current processing batch, if any.
     current_processing_batch: Option<Batch<T::EthSpec>>,
 
+    /// The time the chain has spent syncing, excluding the current period of syncing (if any),
+    /// from which its sync rate is estimated.
+    syncing_duration: Duration,
+
+    /// The time at which the chain started or resumed syncing, if it is syncing.
+    syncing_since: Option<Instant>,
+
     /// A send channel to the sync manager. This is given to the batch processor thread to report
     /// back once batch processing has completed.
     sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
 
+    /// The queue to which downloaded batches are submitted for processing.
+    beacon_processor: BeaconProcessorSend,
+
+    /// A broadcast channel of the events of the block processor thread.
+    block_processor_events: broadcast::Sender<BlockProcessorEvent>,
+
+    /// Schedules fork choice for the processed batches.
+    fork_choice_scheduler: Arc<Mutex<ForkChoiceScheduler>>,
+
+    /// The number of batches, starting from the next batch to be processed, that can be requested
+    /// at any one time. Batches are downloaded in parallel within this window and may complete out
+    /// of order, in which case they are buffered until they can be processed in order.
+    batch_lookahead: u64,
+
+    /// The source of randomness for load balancing requests across the peer pool.
+    rng: SyncRng,
+
+    /// The source of time, from which the sync rate is estimated.
+    clock: Arc<dyn Clock>,
+
     /// A reference to the underlying beacon chain.
     chain: Arc<BeaconChain<T>>,
 
@@ -104,6 +134,7 @@ pub enum ChainSyncingState {
 }
 
 impl<T: BeaconChainTypes> SyncingChain<T> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: u64,
         start_epoch: Epoch,
@@ -111,6 +142,12 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
         target_head_root: Hash256,
         peer_id: PeerId,
         sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+        beacon_processor: BeaconProcessorSend,
+        block_processor_events: broadcast::Sender<BlockProcessorEvent>,
+        fork_choice_scheduler: Arc<Mutex<ForkChoiceScheduler>>,
+        batch_lookahead: u64,
+        rng: SyncRng,
+        clock: Arc<dyn Clock>,
         chain: Arc<BeaconChain<T>>,
         log: slog::Logger,
     ) -> Self {
@@ -130,7 +167,15 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
             to_be_processed_id: BatchId(1),
             state: ChainSyncingState::Stopped,
             current_processing_batch: None,
+            syncing_duration: Duration::from_secs(0),
+            syncing_since: None,
             sync_send,
+            beacon_processor,
+            block_processor_events,
+            fork_choice_scheduler,
+            batch_lookahead,
+            rng,
+            clock,
             chain,
             log,
         }
@@ -147,6 +192,46 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
             )
     }
 
+    /// Returns the total time the chain has spent syncing, excluding the time it was stopped.
+    fn syncing_duration(&self) -> Duration {
+        let current = self.syncing_since.map_or_else(
+            || Duration::from_secs(0),
+            |since| self.clock.now().saturating_duration_since(since),
+        );
+        self.syncing_duration + current
+    }
+
+    /// Returns the progress of the chain, for the HTTP API.
+    pub fn info(&self, chain_type: SyncingChainType) -> SyncingChainInfo {
+        let start_slot = self.start_epoch.start_slot(T::EthSpec::slots_per_epoch());
+        let processed_slot = self.current_processed_slot();
+
+        // extrapolate the rate at which slots have been processed to the rest of the chain
+        let processed_slots = processed_slot.saturating_sub(start_slot).as_u64();
+        let remaining_slots = self
+            .target_head_slot
+            .saturating_sub(processed_slot)
+            .as_u64();
+        let elapsed = self.syncing_duration().as_secs_f64();
+        let estimated_seconds_remaining = if processed_slots > 0 {
+            Some((remaining_slots as f64 * elapsed / processed_slots as f64) as u64)
+        } else {
+            None
+        };
+
+        SyncingChainInfo {
+            chain_type,
+            is_syncing: self.state == ChainSyncingState::Syncing,
+            start_slot,
+            target_head_slot: self.target_head_slot,
+            target_head_root: self.target_head_root,
+            processed_slot,
+            processed_batches: self.to_be_processed_id.saturating_sub(1u64),
+            peers: self.peer_pool.len(),
+            estimated_seconds_remaining,
+        }
+    }
+
     /// A batch of blocks has been received. This function gets run on all chains and should
     /// return Some if the request id matches a pending request on this chain, or None if it does
     /// not.
@@ -255,13 +340,20 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
     /// Sends a batch to the batch processor.
     fn process_batch(&mut self, mut batch: Batch<T::EthSpec>) {
         let downloaded_blocks = std::mem::replace(&mut batch.downloaded_blocks, Vec::new());
-        let process_id = ProcessId::RangeBatchId(self.id.clone(), batch.id.clone());
+        let process_id = ProcessId::RangeBatchId(
+            self.id.clone(),
+            batch.id.clone(),
+            batch.current_peer.clone(),
+        );
         self.current_processing_batch = Some(batch);
         spawn_block_processor(
+            &self.beacon_processor,
             Arc::downgrade(&self.chain.clone()),
             process_id,
             downloaded_blocks,
             self.sync_send.clone(),
+            self.block_processor_events.clone(),
+            self.fork_choice_scheduler.clone(),
             self.log.clone(),
         );
     }
@@ -361,23 +453,9 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
                 // batch.
                 self.mark_processed_batches_as_valid(network, &batch);
 
-                // check that we have not exceeded the re-process retry counter
-                if batch.reprocess_retries > INVALID_BATCH_LOOKUP_ATTEMPTS {
-                    // if a batch has exceeded the invalid batch lookup attempts limit, it means
-                    // that it is likely all peers in this chain are are sending invalid batches
-                    // repeatedly and are either malicious or faulty. We drop the chain and
-                    // downvote all peers.
-                    warn!(self.log, "Batch failed to download. Dropping chain and downvoting peers";
-                        "chain_id" => self.id, "id"=> *batch.id);
-                    for peer_id in self.peer_pool.drain() {
-                        network.downvote_peer(peer_id);
-                    }
-                    ProcessingResult::RemoveChain
-                } else {
-                    // Handle this invalid batch, that is within the re-process retries limit.
-                    self.handle_invalid_batch(network, batch);
-                    ProcessingResult::KeepChain
-                }
+                // record the peer that supplied the invalid blocks
+                batch.failed_peers.insert(batch.current_peer.clone());
+                self.handle_failed_processing(network, batch)
             }
             BatchProcessResult::Failed => {
                 debug!(self.log, "Batch processing failed";
@@ -387,29 +465,43 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
                 // is invalid. We need to find out which and downvote the peer that has sent us
                 // an invalid batch.
 
-                // check that we have not exceeded the re-process retry counter
-                if batch.reprocess_retries > INVALID_BATCH_LOOKUP_ATTEMPTS {
-                    // if a batch has exceeded the invalid batch lookup attempts limit, it means
-                    // that it is likely all peers in this chain are are sending invalid batches
-                    // repeatedly and are either malicious or faulty. We drop the chain and
-                    // downvote all peers.
-                    warn!(self.log, "Batch failed to download. Dropping chain and downvoting peers";
-                        "chain_id" => self.id, "id"=> *batch.id);
-                    for peer_id in self.peer_pool.drain() {
-                        network.downvote_peer(peer_id);
-                    }
-                    ProcessingResult::RemoveChain
-                } else {
-                    // Handle this invalid batch, that is within the re-process retries limit.
-                    self.handle_invalid_batch(network, batch);
-                    ProcessingResult::KeepChain
-                }
+                // record the peer that supplied the invalid blocks
+                batch.failed_peers.insert(batch.current_peer.clone());
+                self.handle_failed_processing(network, batch)
             }
         };
 
         Some(res)
     }
 
+    /// Handles a batch that could not be processed.
+    ///
+    /// The batch is re-downloaded from a different peer until it has exceeded
+    /// `INVALID_BATCH_LOOKUP_ATTEMPTS`, at which point the chain is dropped and only the peers that
+    /// supplied failing blocks for this batch are downvoted.
+    fn handle_failed_processing(
+        &mut self,
+        network: &mut SyncNetworkContext<T::EthSpec>,
+        batch: Batch<T::EthSpec>,
+    ) -> ProcessingResult {
+        // check that we have not exceeded the re-process retry counter
+        if batch.reprocess_retries > INVALID_BATCH_LOOKUP_ATTEMPTS {
+            // if a batch has exceeded the invalid batch lookup attempts limit, it means
+            // that it is likely the peers that have served this batch are either malicious or
+            // faulty. We drop the chain and downvote these peers.
+            warn!(self.log, "Batch failed to download. Dropping chain and downvoting peers";
+                "chain_id" => self.id, "id"=> *batch.id, "failed_peers" => batch.failed_peers.len());
+            for peer_id in batch.failed_peers {
+                network.downvote_peer(peer_id);
+            }
+            ProcessingResult::RemoveChain
+        } else {
+            // Handle this invalid batch, that is within the re-process retries limit.
+            self.handle_invalid_batch(network, batch);
+            ProcessingResult::KeepChain
+        }
+    }
+
     /// Removes any batches awaiting validation.
     ///
     /// All blocks in `processed_batches` should be prior batches. As the `last_batch` has been
@@ -511,13 +603,16 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
         // increment the re-process counter
         batch.reprocess_retries += 1;
 
-        // attempt to find another peer to download the batch from (this potentially doubles up
-        // requests on a single peer)
+        // attempt to find another peer to download the batch from, preferring peers that have not
+        // already supplied a failing version of this batch (this potentially doubles up requests
+        // on a single peer)
         let current_peer = &batch.current_peer;
+        let failed_peers = &batch.failed_peers;
         let new_peer = self
             .peer_pool
             .iter()
-            .find(|peer| *peer != current_peer)
+            .find(|peer| *peer != current_peer && !failed_peers.contains(*peer))
+            .or_else(|| self.peer_pool.iter().find(|peer| *peer != current_peer))
             .unwrap_or_else(|| current_peer);
 
         batch.current_peer = new_peer.clone();
@@ -534,6 +629,8 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
     }
 
     pub fn stop_syncing(&mut self) {
+        self.syncing_duration = self.syncing_duration();
+        self.syncing_since = None;
         self.state = ChainSyncingState::Stopped;
     }
 
@@ -573,8 +670,15 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
             // remove any completed or processed batches
             self.completed_batches.clear();
             self.processed_batches.clear();
+
+            // the sync rate is estimated from the new start of the chain
+            self.syncing_duration = Duration::from_secs(0);
+            self.syncing_since = None;
         }
 
+        if self.syncing_since.is_none() {
+            self.syncing_since = Some(self.clock.now());
+        }
         self.state = ChainSyncingState::Syncing;
 
         // start processing batches if needed
@@ -670,7 +774,7 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
     }
 
     /// Attempts to request the next required batches from the peer pool if the chain is syncing. It will exhaust the peer
-    /// pool and left over batches until the batch buffer is reached or all peers are exhausted.
+    /// pool and left over batches until the end of the `batch_lookahead` window is reached or all peers are exhausted.
     fn request_batches(&mut self, network: &mut SyncNetworkContext<T::EthSpec>) {
         if let ChainSyncingState::Syncing = self.state {
             while self.send_range_request(network) {}
@@ -702,10 +806,11 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
     /// This is used to create the next request.
     fn get_next_peer(&self) -> Option<PeerId> {
         // TODO: Optimize this by combining with above two functions.
-        // randomize the peers for load balancing
-        let mut rng = rand::thread_rng();
+        // randomize the peers for load balancing. The peers are sorted first, so that the order
+        // only depends on the rng and not on the iteration order of the pool.
         let mut peers = self.peer_pool.iter().collect::<Vec<_>>();
-        peers.shuffle(&mut rng);
+        peers.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        self.rng.shuffle(&mut peers);
         for peer in peers {
             if self.pending_batches.peer_is_idle(peer) {
                 return Some(peer.clone());
@@ -720,13 +825,10 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
         let slots_per_epoch = T::EthSpec::slots_per_epoch();
         let blocks_per_batch = slots_per_epoch * EPOCHS_PER_BATCH;
 
-        // only request batches up to the buffer size limit
-        if self
-            .completed_batches
-            .len()
-            .saturating_add(self.pending_batches.len())
-            > BATCH_BUFFER_SIZE as usize
-        {
+        // only request batches within the lookahead window. A slow peer only holds up the
+        // processing of its own batch, whilst other peers continue to download later batches.
+        let lookahead_end = self.to_be_processed_id.saturating_add(self.batch_lookahead);
+        if *self.to_be_downloaded_id >= lookahead_end {
             return None;
         }
 
@@ -2,16 +2,28 @@
 //!
 //! Each chain type is stored in it's own vector. A variety of helper functions are given along
 //! with this struct to to simplify the logic of the other layers of sync.
+//!
+//! Finalized chains take priority over head chains. Only a single finalized chain syncs at a time,
+//! preferring the chain with the most peers. Head chains are paused whilst a finalized chain is
+//! syncing and are resumed once no finalized chains remain.
 
 use super::chain::{ChainSyncingState, SyncingChain};
+use crate::beacon_processor::{BeaconProcessorSend, WorkType};
+use crate::sync::block_processor::BlockProcessorEvent;
+use crate::sync::fork_choice_scheduler::{run_fork_choice, ForkChoiceScheduler};
 use crate::sync::manager::SyncMessage;
 use crate::sync::network_context::SyncNetworkContext;
 use crate::sync::PeerSyncInfo;
+use crate::sync::{Clock, SyncRng};
 use beacon_chain::{BeaconChain, BeaconChainTypes};
-use eth2_libp2p::{types::SyncState, NetworkGlobals, PeerId};
+use eth2_libp2p::{
+    types::{SyncState, SyncingChainType},
+    NetworkGlobals, PeerId,
+};
+use parking_lot::Mutex;
 use slog::{debug, error, info};
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use types::EthSpec;
 use types::{Epoch, Hash256, Slot};
 
@@ -85,6 +97,17 @@ pub struct ChainCollection<T: BeaconChainTypes> {
     head_chains: Vec<SyncingChain<T>>,
     /// The current sync state of the process.
     state: RangeSyncState,
+    /// The queue to which the chains submit their downloaded batches for processing.
+    beacon_processor: BeaconProcessorSend,
+    /// Schedules fork choice for the batches imported by the chains. Fork choice is forced once a
+    /// range sync completes.
+    fork_choice_scheduler: Arc<Mutex<ForkChoiceScheduler>>,
+    /// The number of batches each chain may download ahead of the batch being processed.
+    batch_lookahead: u64,
+    /// The source of the chain ids and of the randomness of the chains.
+    rng: SyncRng,
+    /// The source of time of the chains.
+    clock: Arc<dyn Clock>,
     /// Logger for the collection.
     log: slog::Logger,
 }
@@ -93,6 +116,11 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
     pub fn new(
         beacon_chain: Arc<BeaconChain<T>>,
         network_globals: Arc<NetworkGlobals<T::EthSpec>>,
+        beacon_processor: BeaconProcessorSend,
+        fork_choice_scheduler: Arc<Mutex<ForkChoiceScheduler>>,
+        batch_lookahead: u64,
+        rng: SyncRng,
+        clock: Arc<dyn Clock>,
         log: slog::Logger,
     ) -> Self {
         ChainCollection {
@@ -101,6 +129,11 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
             finalized_chains: Vec::new(),
             head_chains: Vec::new(),
             state: RangeSyncState::Idle,
+            beacon_processor,
+            fork_choice_scheduler,
+            batch_lookahead,
+            rng,
+            clock,
             log,
         }
     }
@@ -128,7 +161,28 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
             if new_state != *peer_state {
                 info!(self.log, "Sync state updated"; "old_state" => format!("{}",peer_state), "new_state" => format!("{}",new_state));
             }
+            let sync_completed = peer_state.is_syncing();
             *peer_state = new_state;
+            drop(peer_state);
+
+            if sync_completed {
+                // the range sync has completed, ensure the head reflects all imported batches
+                let run = self
+                    .fork_choice_scheduler
+                    .lock()
+                    .force_run(&self.beacon_chain);
+                if run {
+                    // fork choice blocks, run it on a worker rather than the sync task
+                    let chain = Arc::downgrade(&self.beacon_chain);
+                    let log = self.log.clone();
+                    self.beacon_processor
+                        .submit(WorkType::ChainSegment, move || {
+                            if let Some(chain) = chain.upgrade() {
+                                run_fork_choice(&chain, &log);
+                            }
+                        });
+                }
+            }
         } else {
             // The state is based on a range sync state, update it
             let mut node_sync_state = self.network_globals.sync_state.write();
@@ -141,6 +195,21 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
         }
     }
 
+    /// Publishes the progress of every chain to the network globals, for the HTTP API.
+    pub fn update_syncing_chains(&self) {
+        let chains = self
+            .finalized_chains
+            .iter()
+            .map(|chain| chain.info(SyncingChainType::Finalized))
+            .chain(
+                self.head_chains
+                    .iter()
+                    .map(|chain| chain.info(SyncingChainType::Head)),
+            )
+            .collect();
+        *self.network_globals.syncing_chains.write() = chains;
+    }
+
     /// A fully synced peer has joined.
     ///
     /// We could be awaiting a head sync. If we are in the head syncing state, without any head
@@ -204,8 +273,8 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
 
     /// Updates the state of the chain collection.
     ///
-    /// This removes any out-dated chains, swaps to any higher priority finalized chains and
-    /// updates the state of the collection.
+    /// This removes any out-dated chains, swaps to any higher priority finalized chains, pauses
+    /// or resumes head chains and updates the state of the collection.
     pub fn update_finalized(&mut self, network: &mut SyncNetworkContext<T::EthSpec>) {
         let local_epoch = {
             let local = match PeerSyncInfo::from_chain(&self.beacon_chain) {
@@ -230,14 +299,15 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
             // There is a current finalized chain syncing
             let syncing_chain_peer_count = self.finalized_chains[index].peer_pool.len();
 
-            // search for a chain with more peers
-            if let Some((new_index, chain)) =
-                self.finalized_chains
-                    .iter_mut()
-                    .enumerate()
-                    .find(|(iter_index, chain)| {
-                        *iter_index != index && chain.peer_pool.len() > syncing_chain_peer_count
-                    })
+            // search for the chain with the most peers, if it has more than the syncing chain
+            if let Some((new_index, chain)) = self
+                .finalized_chains
+                .iter_mut()
+                .enumerate()
+                .filter(|(iter_index, chain)| {
+                    *iter_index != index && chain.peer_pool.len() > syncing_chain_peer_count
+                })
+                .max_by_key(|(_, chain)| chain.peer_pool.len())
             {
                 // A chain has more peers. Swap the syncing chain
                 debug!(self.log, "Switching finalized chains to sync"; "new_target_root" => format!("{}", chain.target_head_root), "new_end_slot" => chain.target_head_slot, "new_start_epoch"=> local_epoch);
@@ -255,6 +325,8 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
                 // Start the new chain
                 self.finalized_chains[new_index].start_syncing(network, local_epoch);
             }
+            // Head chains do not sync whilst a finalized chain is syncing
+            self.pause_head_chains();
         } else if let Some(chain) = self
             .finalized_chains
             .iter_mut()
@@ -269,11 +341,23 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
                 head_root: chain.target_head_root,
             };
             self.state = state;
+            // Head chains do not sync whilst a finalized chain is syncing
+            self.pause_head_chains();
         } else {
             // There are no finalized chains, update the state.
             if self.head_chains.is_empty() {
                 self.state = RangeSyncState::Idle;
             } else {
+                // resume any head chains that were paused during a finalized sync
+                for chain in self
+                    .head_chains
+                    .iter_mut()
+                    .filter(|chain| chain.state == ChainSyncingState::Stopped)
+                {
+                    debug!(self.log, "Resuming head chain"; "head_root" => format!("{}", chain.target_head_root), "head_slot" => chain.target_head_slot);
+                    chain.start_syncing(network, local_epoch);
+                }
+
                 // for the syncing API, we find the minimal start_slot and the maximum
                 // target_slot of all head chains to report back.
 
@@ -303,8 +387,9 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
         target_slot: Slot,
         peer_id: PeerId,
         sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+        block_processor_events: broadcast::Sender<BlockProcessorEvent>,
     ) {
-        let chain_id = rand::random();
+        let chain_id = self.rng.next_u64();
         self.finalized_chains.push(SyncingChain::new(
             chain_id,
             local_finalized_epoch,
@@ -312,12 +397,31 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
             target_head,
             peer_id,
             sync_send,
+            self.beacon_processor.clone(),
+            block_processor_events,
+            self.fork_choice_scheduler.clone(),
+            self.batch_lookahead,
+            self.rng.clone(),
+            self.clock.clone(),
             self.beacon_chain.clone(),
             self.log.clone(),
         ));
     }
 
-    /// Add a new finalized chain to the collection and starts syncing it.
+    /// Pauses all syncing head chains.
+    fn pause_head_chains(&mut self) {
+        for chain in self
+            .head_chains
+            .iter_mut()
+            .filter(|chain| chain.state == ChainSyncingState::Syncing)
+        {
+            debug!(self.log, "Pausing head chain"; "head_root" => format!("{}", chain.target_head_root), "head_slot" => chain.target_head_slot);
+            chain.stop_syncing();
+        }
+    }
+
+    /// Add a new head chain to the collection. The chain starts syncing immediately, unless a
+    /// finalized chain is being synced.
     #[allow(clippy::too_many_arguments)]
     pub fn new_head_chain(
         &mut self,
@@ -327,6 +431,7 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
         target_slot: Slot,
         peer_id: PeerId,
         sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+        block_processor_events: broadcast::Sender<BlockProcessorEvent>,
     ) {
         // remove the peer from any other head chains
 
@@ -335,7 +440,7 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
         });
         self.head_chains.retain(|chain| !chain.peer_pool.is_empty());
 
-        let chain_id = rand::random();
+        let chain_id = self.rng.next_u64();
         let mut new_head_chain = SyncingChain::new(
             chain_id,
             remote_finalized_epoch,
@@ -343,11 +448,19 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
             target_head,
             peer_id,
             sync_send,
+            self.beacon_processor.clone(),
+            block_processor_events,
+            self.fork_choice_scheduler.clone(),
+            self.batch_lookahead,
+            self.rng.clone(),
+            self.clock.clone(),
             self.beacon_chain.clone(),
             self.log.clone(),
         );
-        // All head chains can sync simultaneously
-        new_head_chain.start_syncing(network, remote_finalized_epoch);
+        // All head chains can sync simultaneously, but only once all finalized chains are synced
+        if !self.is_finalizing_sync() {
+            new_head_chain.start_syncing(network, remote_finalized_epoch);
+        }
         self.head_chains.push(new_head_chain);
     }
 
@@ -425,8 +538,9 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
 
     /// Removes any outdated finalized or head chains.
     ///
-    /// This removes chains with no peers, or chains whose start block slot is less than our current
-    /// finalized block slot.
+    /// This removes chains with no peers, or chains whose target head slot is less than our current
+    /// finalized block slot. Head chains are therefore purged once they fall behind a newly
+    /// finalized epoch.
     pub fn purge_outdated_chains(&mut self, network: &mut SyncNetworkContext<T::EthSpec>) {
         // Remove any chains that have no peers
         self.finalized_chains
@@ -43,17 +43,21 @@ use super::chain::{ChainId, ProcessingResult};
 use super::chain_collection::{ChainCollection, RangeSyncState};
 use super::sync_type::RangeSyncType;
 use super::BatchId;
-use crate::sync::block_processor::BatchProcessResult;
+use crate::beacon_processor::BeaconProcessorSend;
+use crate::sync::block_processor::{BatchProcessResult, BlockProcessorEvent};
+use crate::sync::fork_choice_scheduler::ForkChoiceScheduler;
 use crate::sync::manager::SyncMessage;
 use crate::sync::network_context::SyncNetworkContext;
 use crate::sync::PeerSyncInfo;
 use crate::sync::RequestId;
+use crate::sync::{Clock, SyncRng};
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use eth2_libp2p::{NetworkGlobals, PeerId};
+use parking_lot::Mutex;
 use slog::{debug, error, trace};
 use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use types::{EthSpec, SignedBeaconBlock};
 
 /// The primary object dealing with long range/batch syncing. This contains all the active and
@@ -72,22 +76,41 @@ pub struct RangeSync<T: BeaconChainTypes> {
     /// The sync manager channel, allowing the batch processor thread to callback the sync task
     /// once complete.
     sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+    /// The broadcast channel of the block processor events, given to the batch processor thread.
+    block_processor_events: broadcast::Sender<BlockProcessorEvent>,
     /// The syncing logger.
     log: slog::Logger,
 }
 
 impl<T: BeaconChainTypes> RangeSync<T> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         beacon_chain: Arc<BeaconChain<T>>,
         network_globals: Arc<NetworkGlobals<T::EthSpec>>,
         sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+        beacon_processor: BeaconProcessorSend,
+        block_processor_events: broadcast::Sender<BlockProcessorEvent>,
+        fork_choice_scheduler: Arc<Mutex<ForkChoiceScheduler>>,
+        batch_lookahead: u64,
+        rng: SyncRng,
+        clock: Arc<dyn Clock>,
         log: slog::Logger,
     ) -> Self {
         RangeSync {
             beacon_chain: beacon_chain.clone(),
-            chains: ChainCollection::new(beacon_chain, network_globals, log.clone()),
+            chains: ChainCollection::new(
+                beacon_chain,
+                network_globals,
+                beacon_processor,
+                fork_choice_scheduler,
+                batch_lookahead,
+                rng,
+                clock,
+                log.clone(),
+            ),
             awaiting_head_peers: HashSet::new(),
             sync_send,
+            block_processor_events,
             log,
         }
     }
@@ -104,6 +127,11 @@ impl<T: BeaconChainTypes> RangeSync<T> {
         self.chains.fully_synced_peer_found()
     }
 
+    /// Publishes the progress of the chains being synced, for the HTTP API.
+    pub fn update_syncing_chains(&self) {
+        self.chains.update_syncing_chains()
+    }
+
     /// A useful peer has been added. The SyncManager has identified this peer as needing either
     /// a finalized or head chain sync. This processes the peer and starts/resumes any chain that
     /// may need to be synced as a result. A new peer, may increase the peer pool of a finalized
@@ -182,6 +210,7 @@ impl<T: BeaconChainTypes> RangeSync<T> {
                         remote_finalized_slot,
                         peer_id,
                         self.sync_send.clone(),
+                        self.block_processor_events.clone(),
                     );
                     self.chains.update_finalized(network);
                     // update the global sync state
@@ -229,6 +258,7 @@ impl<T: BeaconChainTypes> RangeSync<T> {
                         remote_info.head_slot,
                         peer_id,
                         self.sync_send.clone(),
+                        self.block_processor_events.clone(),
                     );
                 }
                 self.chains.update_finalized(network);
@@ -0,0 +1,79 @@
+//! The source of randomness of the `SyncManager`.
+//!
+//! Sync uses randomness to identify range sync chains and to balance batch requests across the
+//! peers of a chain. The randomness is injected into the `SyncManager` so that tests can seed it
+//! and replay exactly the same peer selection for the same sequence of events.
+//!
+//! Time is injected separately, by the `Clock` of the `clock` module, which both measures and
+//! awaits the delays of single block lookups. Sync otherwise reads the current slot from the
+//! `SlotClock` of the `BeaconChain`, and RPC timeouts are reported by the network as
+//! `SyncMessage::RpcTimeout`.
+
+use parking_lot::Mutex;
+use rand::prelude::*;
+use std::sync::Arc;
+
+/// A random number generator shared by the components of a `SyncManager`.
+#[derive(Clone)]
+pub struct SyncRng {
+    rng: Arc<Mutex<Box<dyn RngCore + Send>>>,
+}
+
+impl SyncRng {
+    /// Wraps any `RngCore`.
+    pub fn new<R: RngCore + Send + 'static>(rng: R) -> Self {
+        Self {
+            rng: Arc::new(Mutex::new(Box::new(rng))),
+        }
+    }
+
+    /// A generator seeded from the entropy of the operating system, for use on a live network.
+    pub fn from_entropy() -> Self {
+        Self::new(StdRng::from_entropy())
+    }
+
+    /// A deterministic generator, for use in tests and simulations.
+    #[cfg(test)]
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+
+    /// Returns a random `u64`.
+    pub fn next_u64(&self) -> u64 {
+        self.rng.lock().next_u64()
+    }
+
+    /// Shuffles `items` in place.
+    pub fn shuffle<X>(&self, items: &mut [X]) {
+        items.shuffle(&mut *self.rng.lock());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_rngs_are_reproducible() {
+        let a = SyncRng::from_seed(42);
+        let b = SyncRng::from_seed(42);
+
+        let mut items_a = (0..32).collect::<Vec<_>>();
+        let mut items_b = items_a.clone();
+        a.shuffle(&mut items_a);
+        b.shuffle(&mut items_b);
+
+        assert_eq!(items_a, items_b);
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn clones_share_state() {
+        let a = SyncRng::from_seed(42);
+        let b = a.clone();
+        let c = SyncRng::from_seed(42);
+
+        assert_eq!(a.next_u64(), c.next_u64());
+        assert_eq!(b.next_u64(), c.next_u64());
+    }
+}
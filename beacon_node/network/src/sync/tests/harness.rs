@@ -0,0 +1,517 @@
+//! An in-memory network of `SyncManager`s, allowing sync to be tested without libp2p.
+//!
+//! Each `TestNode` is a `BeaconChain` backed by an in-memory store, with its own `SyncManager`.
+//! Nodes are connected with `TestNetwork::connect`, which adds a peer to one node that is served
+//! by the chain of another node. The `NetworkMessage`s sent by each `SyncManager` are intercepted
+//! by `TestNetwork::process_messages`, which answers RPC requests from the chain of the serving
+//! node (according to the `PeerBehaviour` of the peer) and returns the responses to the
+//! `SyncManager`, as the router would.
+//!
+//! The `SyncManager` of each node is seeded with its index, so that its peer selection is
+//! reproducible, and reads the time from the `ManualClock` of the network, which only advances
+//! when a test advances it.
+
+use crate::beacon_processor::{self, BeaconProcessorSend};
+use crate::service::NetworkMessage;
+use crate::sync::manager::{self, SyncMessage};
+use crate::sync::{Clock, PeerSyncInfo, SyncRng};
+use beacon_chain::test_utils::{
+    AttestationStrategy, BeaconChainHarness, BlockStrategy, HarnessType,
+};
+use beacon_chain::BeaconChain;
+use eth2_libp2p::discovery::{build_enr, CombinedKey, Keypair};
+use eth2_libp2p::rpc::{BlocksByRangeRequest, BlocksByRootRequest, RequestId};
+use eth2_libp2p::{CombinedKeyExt, NetworkConfig, NetworkGlobals, PeerId, Request};
+use futures::future::{self, BoxFuture};
+use parking_lot::Mutex;
+use slog::Logger;
+use sloggers::{null::NullLoggerBuilder, Build};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use store::config::StoreConfig;
+use tokio::sync::{broadcast, mpsc, watch};
+use types::{
+    test_utils::generate_deterministic_keypairs, EnrForkId, Hash256, MinimalEthSpec, Signature,
+    SignedBeaconBlock, Slot,
+};
+
+pub type E = MinimalEthSpec;
+pub type TestBeaconChain = BeaconChain<HarnessType<E>>;
+
+/// The number of validators in the genesis state of every node.
+pub const VALIDATOR_COUNT: usize = 8;
+/// The time to wait for the `SyncManager`s between processing network messages.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// The number of workers of the beacon processor shared by the nodes.
+const BEACON_PROCESSOR_WORKERS: usize = 2;
+
+/// How a peer responds to the RPC requests of the node it is connected to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PeerBehaviour {
+    /// Responds with the blocks of its chain.
+    Honest,
+    /// Responds to every request with an RPC error.
+    RpcError,
+    /// Never responds, so that every request times out.
+    Unresponsive,
+    /// Responds with the blocks of its chain, but with invalid signatures.
+    InvalidBlocks,
+    /// Disconnects upon receiving its first request.
+    Disconnects,
+}
+
+/// A `Clock` which only advances when told to.
+#[derive(Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+    /// Wakes the delays waiting on the clock whenever it is advanced.
+    advanced_send: Arc<watch::Sender<()>>,
+    advanced_recv: watch::Receiver<()>,
+}
+
+impl ManualClock {
+    fn new() -> Self {
+        let (advanced_send, advanced_recv) = watch::channel(());
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+            advanced_send: Arc::new(advanced_send),
+            advanced_recv,
+        }
+    }
+
+    /// Advances the clock by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock() += duration;
+        let _ = self.advanced_send.broadcast(());
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock()
+    }
+
+    fn delay_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        let now = self.now.clone();
+        let mut advanced_recv = self.advanced_recv.clone();
+        Box::pin(async move {
+            while *now.lock() < deadline {
+                if advanced_recv.recv().await.is_none() {
+                    // the clock can no longer be advanced
+                    future::pending::<()>().await;
+                }
+            }
+        })
+    }
+}
+
+/// A peer of a `TestNode`, which is served by the chain of another node.
+struct TestPeer {
+    server: usize,
+    behaviour: PeerBehaviour,
+    disconnected: bool,
+}
+
+/// A `BeaconChain` and the channels to and from its `SyncManager`.
+pub struct TestNode {
+    pub chain: Arc<TestBeaconChain>,
+    pub network_globals: Arc<NetworkGlobals<E>>,
+    sync_send: mpsc::UnboundedSender<SyncMessage<E>>,
+    network_recv: mpsc::UnboundedReceiver<NetworkMessage<E>>,
+    peers: HashMap<PeerId, TestPeer>,
+}
+
+impl TestNode {
+    /// Returns the root of the head block of the node.
+    pub fn head_root(&self) -> Hash256 {
+        self.chain
+            .head_info()
+            .expect("should get head info")
+            .block_root
+    }
+
+    /// Returns `true` if the node has imported the block with the given root.
+    pub fn contains_block(&self, block_root: &Hash256) -> bool {
+        self.chain.fork_choice.contains_block(block_root)
+    }
+
+    /// Returns `true` if the node has disconnected from the peer.
+    pub fn is_disconnected(&self, peer_id: &PeerId) -> bool {
+        self.peers
+            .get(peer_id)
+            .map_or(false, |peer| peer.disconnected)
+    }
+}
+
+/// An RPC request sent by a node.
+#[derive(Debug, Clone)]
+pub struct SentRequest {
+    pub node: usize,
+    pub peer_id: PeerId,
+    pub request: Request,
+}
+
+/// A set of `TestNode`s and the simulated network between them.
+///
+/// Must be created within a tokio runtime, on which the `SyncManager`s are spawned.
+pub struct TestNetwork {
+    pub nodes: Vec<TestNode>,
+    /// Every RPC request sent by the nodes, in the order they were sent.
+    pub requests: Vec<SentRequest>,
+    /// The fraction of a slot by which the nodes added afterwards delay their single block
+    /// lookups. Zero by default, such that lookups are requested immediately.
+    pub block_lookup_delay: f64,
+    /// The clock of the `SyncManager`s of all nodes.
+    pub clock: ManualClock,
+    executor: environment::TaskExecutor,
+    beacon_processor: BeaconProcessorSend,
+    _exit_signal: exit_future::Signal,
+    log: Logger,
+}
+
+impl TestNetwork {
+    pub fn new() -> Self {
+        let log = NullLoggerBuilder.build().expect("logger should build");
+        let (exit_signal, exit) = exit_future::signal();
+        let (shutdown_tx, _) = futures::channel::mpsc::channel(1);
+        let executor = environment::TaskExecutor::new(
+            tokio::runtime::Handle::current(),
+            exit,
+            log.clone(),
+            shutdown_tx,
+        );
+        let beacon_processor =
+            beacon_processor::spawn(executor.clone(), BEACON_PROCESSOR_WORKERS, log.clone());
+
+        Self {
+            nodes: vec![],
+            requests: vec![],
+            block_lookup_delay: 0.0,
+            clock: ManualClock::new(),
+            executor,
+            beacon_processor,
+            _exit_signal: exit_signal,
+            log,
+        }
+    }
+
+    /// Adds a node which has extended the common genesis state with `num_blocks` blocks, returning
+    /// its index.
+    ///
+    /// The slot clocks of all nodes are set to the highest slot of any node, so that every node
+    /// is able to import the blocks of the others.
+    pub fn add_node(&mut self, num_blocks: usize) -> usize {
+        let harness = BeaconChainHarness::new(
+            E::default(),
+            generate_deterministic_keypairs(VALIDATOR_COUNT),
+            StoreConfig::default(),
+        );
+
+        if num_blocks > 0 {
+            harness.advance_slot();
+            harness.extend_chain(
+                num_blocks,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            );
+        }
+
+        let chain = Arc::new(harness.chain);
+
+        let config = NetworkConfig::default();
+        let enr_key = CombinedKey::from_libp2p(&Keypair::generate_secp256k1())
+            .expect("should create enr key");
+        let enr =
+            build_enr::<E>(&enr_key, &config, EnrForkId::default()).expect("should build enr");
+        let network_globals = Arc::new(NetworkGlobals::new(enr, 0, 0, &self.log));
+
+        let (network_send, network_recv) = mpsc::unbounded_channel();
+        let (block_processor_events, _) = broadcast::channel(16);
+        let sync_send = manager::spawn(
+            self.executor.clone(),
+            chain.clone(),
+            network_globals.clone(),
+            network_send,
+            self.beacon_processor.clone(),
+            block_processor_events,
+            self.block_lookup_delay,
+            config.batch_lookahead,
+            SyncRng::from_seed(self.nodes.len() as u64),
+            Arc::new(self.clock.clone()),
+            self.log.clone(),
+        );
+
+        self.nodes.push(TestNode {
+            chain,
+            network_globals,
+            sync_send,
+            network_recv,
+            peers: HashMap::new(),
+        });
+
+        let current_slot = self
+            .nodes
+            .iter()
+            .filter_map(|node| node.chain.slot().ok())
+            .max()
+            .unwrap_or_else(|| Slot::new(0));
+        for node in &self.nodes {
+            node.chain.slot_clock.set_slot(current_slot.as_u64());
+        }
+
+        self.nodes.len() - 1
+    }
+
+    /// Connects `node` to a new peer which is served by the chain of `server`, returning the id
+    /// of the peer.
+    ///
+    /// The status of the peer is sent to the `SyncManager` of `node`.
+    pub fn connect(&mut self, node: usize, server: usize, behaviour: PeerBehaviour) -> PeerId {
+        let peer_id = PeerId::random();
+
+        self.nodes[node]
+            .network_globals
+            .peers
+            .write()
+            .connect_outgoing(&peer_id);
+        self.nodes[node].peers.insert(
+            peer_id.clone(),
+            TestPeer {
+                server,
+                behaviour,
+                disconnected: false,
+            },
+        );
+        self.send_status(node, &peer_id);
+
+        peer_id
+    }
+
+    /// Sends `message` to the `SyncManager` of `node`.
+    pub fn send(&self, node: usize, message: SyncMessage<E>) {
+        self.nodes[node]
+            .sync_send
+            .send(message)
+            .expect("sync manager should be running");
+    }
+
+    /// Informs `node` that `peer_id` has gossiped a block whose parent is unknown.
+    pub fn gossip_unknown_block(&self, node: usize, peer_id: &PeerId, block: SignedBeaconBlock<E>) {
+        self.send(
+            node,
+            SyncMessage::UnknownBlock(peer_id.clone(), Box::new(block)),
+        );
+    }
+
+    /// Returns the RPC requests sent by `node` to `peer_id`.
+    pub fn requests_to(&self, node: usize, peer_id: &PeerId) -> Vec<&Request> {
+        self.requests
+            .iter()
+            .filter(|sent| sent.node == node && sent.peer_id == *peer_id)
+            .map(|sent| &sent.request)
+            .collect()
+    }
+
+    /// Handles all of the pending `NetworkMessage`s of every node, returning the number of
+    /// messages handled.
+    pub fn process_messages(&mut self) -> usize {
+        let mut handled = 0;
+        for node in 0..self.nodes.len() {
+            while let Ok(message) = self.nodes[node].network_recv.try_recv() {
+                self.handle_message(node, message);
+                handled += 1;
+            }
+        }
+        handled
+    }
+
+    /// Handles network messages until `condition` is true.
+    ///
+    /// ## Panics
+    ///
+    /// If `condition` is not true before `timeout` has elapsed.
+    pub async fn run_until<F>(&mut self, timeout: Duration, condition: F)
+    where
+        F: Fn(&Self) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        while !condition(self) {
+            if Instant::now() > deadline {
+                panic!("Condition was not met within {:?}", timeout);
+            }
+            self.process_messages();
+            tokio::time::delay_for(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Handles network messages for `duration`.
+    pub async fn run_for(&mut self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline {
+            self.process_messages();
+            tokio::time::delay_for(POLL_INTERVAL).await;
+        }
+    }
+
+    fn handle_message(&mut self, node: usize, message: NetworkMessage<E>) {
+        match message {
+            NetworkMessage::SendRequest {
+                peer_id,
+                request,
+                request_id: RequestId::Sync(request_id),
+            } => {
+                self.requests.push(SentRequest {
+                    node,
+                    peer_id: peer_id.clone(),
+                    request: request.clone(),
+                });
+                self.handle_request(node, peer_id, request_id, request);
+            }
+            NetworkMessage::Disconnect { peer_id } => self.disconnect(node, peer_id),
+            // Gossip and the other messages are not used by sync.
+            _ => {}
+        }
+    }
+
+    /// Disconnects `node` from `peer_id`, informing its `SyncManager`.
+    fn disconnect(&mut self, node: usize, peer_id: PeerId) {
+        if let Some(peer) = self.nodes[node].peers.get_mut(&peer_id) {
+            peer.disconnected = true;
+        }
+        self.nodes[node]
+            .network_globals
+            .peers
+            .write()
+            .disconnect(&peer_id);
+        self.send(node, SyncMessage::Disconnect(peer_id));
+    }
+
+    fn handle_request(
+        &mut self,
+        node: usize,
+        peer_id: PeerId,
+        request_id: crate::sync::RequestId,
+        request: Request,
+    ) {
+        let (server, behaviour) = match self.nodes[node].peers.get(&peer_id) {
+            Some(peer) if !peer.disconnected => (peer.server, peer.behaviour),
+            _ => return self.send(node, SyncMessage::RPCError(peer_id, request_id)),
+        };
+
+        match behaviour {
+            PeerBehaviour::RpcError => {
+                return self.send(node, SyncMessage::RPCError(peer_id, request_id))
+            }
+            PeerBehaviour::Unresponsive => {
+                return self.send(node, SyncMessage::RpcTimeout(peer_id, request_id))
+            }
+            // The stream of the request is closed along with the connection.
+            PeerBehaviour::Disconnects => {
+                self.disconnect(node, peer_id.clone());
+                return self.send(node, SyncMessage::RPCError(peer_id, request_id));
+            }
+            PeerBehaviour::Honest | PeerBehaviour::InvalidBlocks => {}
+        }
+
+        let serve = |mut block: SignedBeaconBlock<E>| {
+            if behaviour == PeerBehaviour::InvalidBlocks {
+                block.signature = Signature::empty_signature();
+            }
+            Some(Box::new(block))
+        };
+        let chain = &self.nodes[server].chain;
+
+        match request {
+            Request::Status(_) => self.send_status(node, &peer_id),
+            Request::BlocksByRange(request) => {
+                for block in blocks_by_range(chain, &request) {
+                    self.send(
+                        node,
+                        SyncMessage::BlocksByRangeResponse {
+                            peer_id: peer_id.clone(),
+                            request_id,
+                            beacon_block: serve(block),
+                        },
+                    );
+                }
+                self.send(
+                    node,
+                    SyncMessage::BlocksByRangeResponse {
+                        peer_id,
+                        request_id,
+                        beacon_block: None,
+                    },
+                );
+            }
+            Request::BlocksByRoot(request) => {
+                for block in blocks_by_root(chain, &request) {
+                    self.send(
+                        node,
+                        SyncMessage::BlocksByRootResponse {
+                            peer_id: peer_id.clone(),
+                            request_id,
+                            beacon_block: serve(block),
+                        },
+                    );
+                }
+                self.send(
+                    node,
+                    SyncMessage::BlocksByRootResponse {
+                        peer_id,
+                        request_id,
+                        beacon_block: None,
+                    },
+                );
+            }
+            // A goodbye is always followed by a `NetworkMessage::Disconnect`.
+            Request::Goodbye(_) => {}
+        }
+    }
+
+    fn send_status(&self, node: usize, peer_id: &PeerId) {
+        let server = self.nodes[node].peers[peer_id].server;
+        let info = PeerSyncInfo::from_chain(&self.nodes[server].chain)
+            .expect("should get sync info of server");
+        self.send(node, SyncMessage::AddPeer(peer_id.clone(), info));
+    }
+}
+
+/// Returns the blocks of `chain` which match a `BlocksByRange` request.
+fn blocks_by_range(
+    chain: &TestBeaconChain,
+    request: &BlocksByRangeRequest,
+) -> Vec<SignedBeaconBlock<E>> {
+    let end_slot = request.start_slot + request.count * request.step;
+
+    let mut block_roots = match chain.forwards_iter_block_roots(Slot::new(request.start_slot)) {
+        Ok(iter) => iter
+            .filter_map(Result::ok)
+            .take_while(|(_, slot)| slot.as_u64() < end_slot)
+            .filter(|(_, slot)| (slot.as_u64() - request.start_slot) % request.step == 0)
+            .map(|(root, _)| root)
+            .collect::<Vec<_>>(),
+        // The start slot is beyond the head of the chain.
+        Err(_) => vec![],
+    };
+    // Skip slots repeat the root of the prior block.
+    block_roots.dedup();
+
+    block_roots
+        .iter()
+        .filter_map(|root| chain.get_block(root).expect("should read block"))
+        .filter(|block| block.slot() >= request.start_slot && block.slot() < end_slot)
+        .collect()
+}
+
+/// Returns the blocks of `chain` which match a `BlocksByRoot` request.
+fn blocks_by_root(
+    chain: &TestBeaconChain,
+    request: &BlocksByRootRequest,
+) -> Vec<SignedBeaconBlock<E>> {
+    request
+        .block_roots
+        .iter()
+        .filter_map(|root| chain.get_block(root).expect("should read block"))
+        .collect()
+}
@@ -0,0 +1,359 @@
+//! Tests of range sync, parent lookups and single block lookups, run over the in-memory network of `harness`.
+
+mod harness;
+
+use self::harness::{PeerBehaviour, TestNetwork};
+use super::manager::SyncMessage;
+use beacon_chain::test_utils::HARNESS_SLOT_TIME;
+use eth2_libp2p::Request;
+use std::time::Duration;
+
+/// The maximum time for nodes to sync in a test.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(60);
+/// The number of blocks in the chain of the node being synced from. Four epochs of the minimal
+/// spec, which requires a range sync over several batches.
+const RANGE_SYNC_BLOCKS: usize = 32;
+/// The number of blocks in the chain of the node being synced from, when testing parent lookups.
+const PARENT_LOOKUP_BLOCKS: usize = 4;
+/// The fraction of a slot by which single block lookups are delayed, when testing delayed lookups.
+const BLOCK_LOOKUP_DELAY: f64 = 0.5;
+/// The real time for which the nodes are run to check that they do not act before the clock is
+/// advanced.
+const IDLE_DURATION: Duration = Duration::from_millis(500);
+
+/// Counts the `BlocksByRoot` requests in `requests`.
+fn blocks_by_root_requests(requests: &[&Request]) -> usize {
+    requests
+        .iter()
+        .filter(|request| matches!(request, Request::BlocksByRoot(_)))
+        .count()
+}
+
+#[tokio::test]
+async fn range_sync_from_single_peer() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(RANGE_SYNC_BLOCKS);
+    let syncing = network.add_node(0);
+    let target_head = network.nodes[synced].head_root();
+
+    network.connect(syncing, synced, PeerBehaviour::Honest);
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            network.nodes[syncing].contains_block(&target_head)
+        })
+        .await;
+
+    assert!(
+        network
+            .requests
+            .iter()
+            .any(|sent| sent.node == syncing && matches!(sent.request, Request::BlocksByRange(_))),
+        "should have synced with blocks by range requests"
+    );
+}
+
+#[tokio::test]
+async fn range_sync_retries_rpc_errors() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(RANGE_SYNC_BLOCKS);
+    let syncing = network.add_node(0);
+    let target_head = network.nodes[synced].head_root();
+
+    network.connect(syncing, synced, PeerBehaviour::RpcError);
+    network.connect(syncing, synced, PeerBehaviour::Unresponsive);
+    network.connect(syncing, synced, PeerBehaviour::Honest);
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            network.nodes[syncing].contains_block(&target_head)
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn range_sync_retries_invalid_batches() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(RANGE_SYNC_BLOCKS);
+    let syncing = network.add_node(0);
+    let target_head = network.nodes[synced].head_root();
+
+    network.connect(syncing, synced, PeerBehaviour::InvalidBlocks);
+    network.connect(syncing, synced, PeerBehaviour::Honest);
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            network.nodes[syncing].contains_block(&target_head)
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn range_sync_survives_disconnects() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(RANGE_SYNC_BLOCKS);
+    let syncing = network.add_node(0);
+    let target_head = network.nodes[synced].head_root();
+
+    let disconnecting = network.connect(syncing, synced, PeerBehaviour::Disconnects);
+    network.connect(syncing, synced, PeerBehaviour::Honest);
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            network.nodes[syncing].contains_block(&target_head)
+        })
+        .await;
+
+    assert!(network.nodes[syncing].is_disconnected(&disconnecting));
+    assert_eq!(
+        network.requests_to(syncing, &disconnecting).len(),
+        1,
+        "should not send requests to a disconnected peer"
+    );
+}
+
+#[tokio::test]
+async fn range_sync_without_valid_peers() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(RANGE_SYNC_BLOCKS);
+    let syncing = network.add_node(0);
+    let genesis_root = network.nodes[syncing].head_root();
+
+    let peer_id = network.connect(syncing, synced, PeerBehaviour::InvalidBlocks);
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            network.nodes[syncing].is_disconnected(&peer_id)
+        })
+        .await;
+
+    assert_eq!(
+        network.nodes[syncing].head_root(),
+        genesis_root,
+        "should not import any invalid blocks"
+    );
+}
+
+#[tokio::test]
+async fn parent_lookup() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(PARENT_LOOKUP_BLOCKS);
+    let syncing = network.add_node(0);
+    let head_root = network.nodes[synced].head_root();
+    let head_block = network.nodes[synced]
+        .chain
+        .get_block(&head_root)
+        .expect("should read head block")
+        .expect("should have head block");
+
+    let peer_id = network.connect(syncing, synced, PeerBehaviour::Honest);
+    network.gossip_unknown_block(syncing, &peer_id, head_block);
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            network.nodes[syncing].contains_block(&head_root)
+        })
+        .await;
+
+    assert_eq!(
+        blocks_by_root_requests(&network.requests_to(syncing, &peer_id)),
+        PARENT_LOOKUP_BLOCKS - 1,
+        "should request each of the unknown parents"
+    );
+}
+
+#[tokio::test]
+async fn parent_lookup_retries_other_peers() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(PARENT_LOOKUP_BLOCKS);
+    let syncing = network.add_node(0);
+    let head_root = network.nodes[synced].head_root();
+    let head_block = network.nodes[synced]
+        .chain
+        .get_block(&head_root)
+        .expect("should read head block")
+        .expect("should have head block");
+
+    let failing = network.connect(syncing, synced, PeerBehaviour::RpcError);
+    let honest = network.connect(syncing, synced, PeerBehaviour::Honest);
+    network.gossip_unknown_block(syncing, &failing, head_block.clone());
+    network.gossip_unknown_block(syncing, &honest, head_block);
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            network.nodes[syncing].contains_block(&head_root)
+        })
+        .await;
+
+    assert_eq!(
+        blocks_by_root_requests(&network.requests_to(syncing, &failing)),
+        1,
+        "should not ask the failing peer again"
+    );
+}
+
+#[tokio::test]
+async fn failed_parent_download_is_repeated() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(PARENT_LOOKUP_BLOCKS);
+    let syncing = network.add_node(0);
+    let head_root = network.nodes[synced].head_root();
+    let head_block = network.nodes[synced]
+        .chain
+        .get_block(&head_root)
+        .expect("should read head block")
+        .expect("should have head block");
+
+    let failing = network.connect(syncing, synced, PeerBehaviour::RpcError);
+    network.gossip_unknown_block(syncing, &failing, head_block.clone());
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            blocks_by_root_requests(&network.requests_to(syncing, &failing)) >= 1
+        })
+        .await;
+    network.run_for(Duration::from_millis(500)).await;
+
+    // The chain was not shown to be invalid, so it is looked up again from another peer.
+    let honest = network.connect(syncing, synced, PeerBehaviour::Honest);
+    network.gossip_unknown_block(syncing, &honest, head_block);
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            network.nodes[syncing].contains_block(&head_root)
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn failed_parent_lookup_is_not_repeated() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(PARENT_LOOKUP_BLOCKS);
+    let syncing = network.add_node(0);
+    let head_root = network.nodes[synced].head_root();
+    let head_block = network.nodes[synced]
+        .chain
+        .get_block(&head_root)
+        .expect("should read head block")
+        .expect("should have head block");
+
+    let peer_id = network.connect(syncing, synced, PeerBehaviour::InvalidBlocks);
+    network.gossip_unknown_block(syncing, &peer_id, head_block.clone());
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            blocks_by_root_requests(&network.requests_to(syncing, &peer_id))
+                >= PARENT_LOOKUP_BLOCKS - 1
+        })
+        .await;
+    network.run_for(Duration::from_millis(500)).await;
+
+    // Gossiping the block again should not start another lookup.
+    network.gossip_unknown_block(syncing, &peer_id, head_block);
+    network.run_for(Duration::from_millis(500)).await;
+
+    assert_eq!(
+        blocks_by_root_requests(&network.requests_to(syncing, &peer_id)),
+        PARENT_LOOKUP_BLOCKS - 1,
+        "should not look up a chain which failed to be processed again"
+    );
+    assert!(!network.nodes[syncing].contains_block(&head_root));
+}
+
+#[tokio::test]
+async fn single_block_lookups_are_coalesced() {
+    let mut network = TestNetwork::new();
+    let synced = network.add_node(PARENT_LOOKUP_BLOCKS);
+    let syncing = network.add_node(0);
+    let genesis_root = network.nodes[syncing].head_root();
+
+    // The roots of the blocks of the synced node, in ascending slot order.
+    let mut block_roots = vec![];
+    let mut block_root = network.nodes[synced].head_root();
+    while block_root != genesis_root {
+        block_roots.push(block_root);
+        block_root = network.nodes[synced]
+            .chain
+            .get_block(&block_root)
+            .expect("should read block")
+            .expect("should have block")
+            .parent_root();
+    }
+    block_roots.reverse();
+
+    let peer_id = network.connect(syncing, synced, PeerBehaviour::Honest);
+    for block_root in block_roots.iter() {
+        network.send(
+            syncing,
+            SyncMessage::UnknownBlockHash(peer_id.clone(), *block_root),
+        );
+    }
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            block_roots
+                .iter()
+                .all(|block_root| network.nodes[syncing].contains_block(block_root))
+        })
+        .await;
+
+    let first_request = network
+        .requests_to(syncing, &peer_id)
+        .into_iter()
+        .find_map(|request| match request {
+            Request::BlocksByRoot(request) => Some(request.block_roots.clone()),
+            _ => None,
+        })
+        .expect("should request blocks by root");
+    assert_eq!(
+        first_request, block_roots,
+        "should request every referenced block in a single request"
+    );
+}
+
+#[tokio::test]
+async fn delayed_block_lookup() {
+    let mut network = TestNetwork::new();
+    network.block_lookup_delay = BLOCK_LOOKUP_DELAY;
+    let synced = network.add_node(PARENT_LOOKUP_BLOCKS);
+    let syncing = network.add_node(0);
+    let head_root = network.nodes[synced].head_root();
+
+    let peer_id = network.connect(syncing, synced, PeerBehaviour::Honest);
+    network.send(
+        syncing,
+        SyncMessage::UnknownBlockHash(peer_id.clone(), head_root),
+    );
+    let delay = HARNESS_SLOT_TIME.mul_f64(BLOCK_LOOKUP_DELAY);
+    network.run_for(IDLE_DURATION).await;
+    network.clock.advance(delay - Duration::from_millis(1));
+    network.run_for(IDLE_DURATION).await;
+
+    assert_eq!(
+        blocks_by_root_requests(&network.requests_to(syncing, &peer_id)),
+        0,
+        "should not request the block before the delay"
+    );
+
+    network.clock.advance(Duration::from_millis(1));
+    network
+        .run_until(SYNC_TIMEOUT, |network| {
+            network.nodes[syncing].contains_block(&head_root)
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn delayed_block_lookup_is_cancelled_by_gossip() {
+    let mut network = TestNetwork::new();
+    network.block_lookup_delay = BLOCK_LOOKUP_DELAY;
+    let synced = network.add_node(PARENT_LOOKUP_BLOCKS);
+    let syncing = network.add_node(0);
+    let head_root = network.nodes[synced].head_root();
+
+    let peer_id = network.connect(syncing, synced, PeerBehaviour::Honest);
+    network.send(
+        syncing,
+        SyncMessage::UnknownBlockHash(peer_id.clone(), head_root),
+    );
+    network.send(syncing, SyncMessage::GossipBlockReceived(head_root));
+    network
+        .clock
+        .advance(HARNESS_SLOT_TIME.mul_f64(BLOCK_LOOKUP_DELAY) * 2);
+    network.run_for(IDLE_DURATION).await;
+
+    assert_eq!(
+        blocks_by_root_requests(&network.requests_to(syncing, &peer_id)),
+        0,
+        "should not request a block received on gossip"
+    );
+}
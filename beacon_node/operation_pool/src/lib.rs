@@ -311,6 +311,19 @@ impl<T: EthSpec> OperationPool<T> {
         });
     }
 
+    /// Returns `true` if the pool holds a proposer slashing of `proposer_index`.
+    pub fn contains_proposer_slashing(&self, proposer_index: u64) -> bool {
+        self.proposer_slashings.read().contains_key(&proposer_index)
+    }
+
+    /// Returns `true` if the pool holds the given attester slashing.
+    pub fn contains_attester_slashing(&self, slashing: &AttesterSlashing<T>) -> bool {
+        self.attester_slashings
+            .read()
+            .values()
+            .any(|existing| existing == slashing)
+    }
+
     /// Total number of attester slashings in the pool.
     pub fn num_attester_slashings(&self) -> usize {
         self.attester_slashings.read().len()
@@ -335,6 +348,11 @@ impl<T: EthSpec> OperationPool<T> {
         Ok(())
     }
 
+    /// Returns `true` if the pool holds an exit of `validator_index`.
+    pub fn contains_voluntary_exit(&self, validator_index: u64) -> bool {
+        self.voluntary_exits.read().contains_key(&validator_index)
+    }
+
     /// Get a list of voluntary exits for inclusion in a block.
     pub fn get_voluntary_exits(
         &self,
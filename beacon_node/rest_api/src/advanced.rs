@@ -15,6 +15,27 @@ pub fn get_fork_choice<T: BeaconChainTypes>(
     ResponseBuilder::new(&req)?.body_no_ssz(&*beacon_chain.fork_choice.core_proto_array())
 }
 
+/// Returns the blocks of the fork choice tree, referencing their parents by root, encoded as JSON.
+///
+/// Useful for visualizing the forks of the chain.
+pub fn get_fork_choice_block_tree<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    ResponseBuilder::new(&req)?.body_no_ssz(&beacon_chain.fork_choice.block_tree())
+}
+
+/// Returns a `ForkChoiceSnapshot` of the fork choice state and the inputs to its head search,
+/// encoded as JSON.
+///
+/// Useful for replaying the head search offline with hypothetical votes.
+pub fn get_fork_choice_snapshot<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    ResponseBuilder::new(&req)?.body_no_ssz(&beacon_chain.fork_choice.snapshot())
+}
+
 /// Returns the `PersistedOperationPool` struct.
 ///
 /// Useful for debugging or advanced inspection of the stored operations.
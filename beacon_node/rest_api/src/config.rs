@@ -7,6 +7,9 @@ pub enum ApiEncodingFormat {
     JSON,
     YAML,
     SSZ,
+    /// SSZ bytes, as requested from the standard `/eth/v1/` endpoints which support SSZ. Other
+    /// endpoints respond to it with 415 Unsupported Media Type.
+    OctetStream,
 }
 
 impl ApiEncodingFormat {
@@ -15,6 +18,7 @@ impl ApiEncodingFormat {
             ApiEncodingFormat::JSON => "application/json",
             ApiEncodingFormat::YAML => "application/yaml",
             ApiEncodingFormat::SSZ => "application/ssz",
+            ApiEncodingFormat::OctetStream => "application/octet-stream",
         }
     }
 }
@@ -24,6 +28,7 @@ impl From<&str> for ApiEncodingFormat {
         match f {
             "application/yaml" => ApiEncodingFormat::YAML,
             "application/ssz" => ApiEncodingFormat::SSZ,
+            "application/octet-stream" => ApiEncodingFormat::OctetStream,
             _ => ApiEncodingFormat::JSON,
         }
     }
@@ -0,0 +1,194 @@
+//! Streams beacon chain events to HTTP clients as server-sent events.
+
+use crate::{ApiError, ApiResult, UrlQuery};
+use beacon_chain::events::EventKind;
+use hyper::body::Bytes;
+use hyper::{Body, Request, Response};
+use serde::Serialize;
+use slog::{debug, error, Logger};
+use std::io::Write;
+use tokio::sync::mpsc::Receiver;
+use types::{Epoch, EthSpec, Hash256, Slot};
+
+const HEAD_TOPIC: &str = "head";
+const BLOCK_TOPIC: &str = "block";
+const FINALIZED_CHECKPOINT_TOPIC: &str = "finalized_checkpoint";
+const CHAIN_REORG_TOPIC: &str = "chain_reorg";
+
+/// All topics which may be subscribed to.
+const TOPICS: &[&str] = &[
+    HEAD_TOPIC,
+    BLOCK_TOPIC,
+    FINALIZED_CHECKPOINT_TOPIC,
+    CHAIN_REORG_TOPIC,
+];
+
+#[derive(Serialize)]
+struct HeadEvent {
+    block: Hash256,
+}
+
+#[derive(Serialize)]
+struct BlockEvent {
+    slot: Slot,
+    block: Hash256,
+    /// The time between the start of the slot and the block being received on gossip, omitted if
+    /// the block was not received on gossip.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    observed_delay_ms: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct FinalizedCheckpointEvent {
+    block: Hash256,
+    epoch: Epoch,
+}
+
+#[derive(Serialize)]
+struct ChainReorgEvent {
+    slot: Slot,
+    depth: u64,
+    old_head_block: Hash256,
+    new_head_block: Hash256,
+}
+
+/// HTTP handler to stream the events of the `topics` given in the URL query (e.g.,
+/// `?topics=head,block`) as server-sent events.
+///
+/// The events of each client are forwarded by a task on the `executor`, from the bounded queue of
+/// its subscription.
+pub fn stream_events<T: EthSpec>(
+    req: Request<Body>,
+    log: Logger,
+    mut events: Receiver<EventKind<T>>,
+    executor: &environment::TaskExecutor,
+) -> ApiResult {
+    let topics = parse_topics(&req)?;
+
+    let (mut sender, body) = Body::channel();
+    let forward_events = async move {
+        // The stream ends if the client lags too far behind the events and is unsubscribed.
+        while let Some(event) = events.recv().await {
+            let chunks = match make_sse_response_chunks(&event, &topics) {
+                Ok(chunks) => chunks,
+                Err(e) => {
+                    error!(log, "Failed to make SSE chunk"; "error" => e);
+                    sender.abort();
+                    break;
+                }
+            };
+
+            for chunk in chunks {
+                if sender.send_data(chunk).await.is_err() {
+                    // The client has disconnected, dropping `events` unsubscribes it.
+                    debug!(log, "Event stream closed");
+                    return;
+                }
+            }
+        }
+    };
+    executor.spawn(forward_events, "event_stream");
+
+    Response::builder()
+        .status(200)
+        .header("Content-Type", "text/event-stream")
+        .header("Connection", "Keep-Alive")
+        .header("Cache-Control", "no-cache")
+        .header("Access-Control-Allow-Origin", "*")
+        .body(body)
+        .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
+}
+
+/// Parses the comma-separated `topics` URL query parameter.
+fn parse_topics<U>(req: &Request<U>) -> Result<Vec<&'static str>, ApiError> {
+    let (_key, value) = UrlQuery::from_request(req)?.first_of(&["topics"])?;
+
+    value
+        .split(',')
+        .map(|topic| {
+            TOPICS
+                .iter()
+                .find(|known| **known == topic)
+                .copied()
+                .ok_or_else(|| {
+                    ApiError::BadRequest(format!(
+                        "Unknown topic {}, expected one of {:?}",
+                        topic, TOPICS
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Returns an SSE message for each of the `topics` to which `event` belongs.
+fn make_sse_response_chunks<T: EthSpec>(
+    event: &EventKind<T>,
+    topics: &[&str],
+) -> Result<Vec<Bytes>, String> {
+    let messages = match event {
+        EventKind::BeaconHeadChanged {
+            current_head_beacon_block_root,
+            ..
+        } => vec![(
+            HEAD_TOPIC,
+            to_json(&HeadEvent {
+                block: *current_head_beacon_block_root,
+            })?,
+        )],
+        EventKind::ChainReorg {
+            slot,
+            depth,
+            old_head_block,
+            new_head_block,
+            ..
+        } => vec![(
+            CHAIN_REORG_TOPIC,
+            to_json(&ChainReorgEvent {
+                slot: *slot,
+                depth: *depth,
+                old_head_block: *old_head_block,
+                new_head_block: *new_head_block,
+            })?,
+        )],
+        EventKind::BeaconBlockImported {
+            block_root,
+            block,
+            observed_delay_ms,
+        } => vec![(
+            BLOCK_TOPIC,
+            to_json(&BlockEvent {
+                slot: block.slot(),
+                block: *block_root,
+                observed_delay_ms: *observed_delay_ms,
+            })?,
+        )],
+        EventKind::BeaconFinalization { epoch, root } => vec![(
+            FINALIZED_CHECKPOINT_TOPIC,
+            to_json(&FinalizedCheckpointEvent {
+                block: *root,
+                epoch: *epoch,
+            })?,
+        )],
+        _ => vec![],
+    };
+
+    messages
+        .into_iter()
+        .filter(|(topic, _)| topics.contains(topic))
+        .map(|(topic, data)| make_sse_message(topic, &data).map_err(|e| e.to_string()))
+        .collect()
+}
+
+fn make_sse_message(topic: &str, data: &str) -> std::io::Result<Bytes> {
+    let mut buffer = Vec::new();
+    {
+        let mut sse_message = uhttp_sse::SseMessage::new(&mut buffer);
+        write!(sse_message.event()?, "{}", topic)?;
+        write!(sse_message.data()?, "{}", data)?;
+    }
+    Ok(buffer.into())
+}
+
+fn to_json<S: Serialize>(item: &S) -> Result<String, String> {
+    serde_json::to_string(item).map_err(|e| format!("Unable to serialize event: {:?}", e))
+}
@@ -6,13 +6,21 @@ use hex;
 use http::header;
 use hyper::{Body, Request};
 use itertools::process_results;
-use network::NetworkMessage;
+use network::{NetworkMessage, PublishResultReceiver};
+use rest_types::{BlockId, StateId, ValidatorId};
+use slog::{warn, Logger};
 use ssz::Decode;
+use std::time::Duration;
 use store::iter::AncestorIter;
+use tokio::sync::oneshot;
 use types::{
     BeaconState, CommitteeIndex, Epoch, EthSpec, Hash256, RelativeEpoch, SignedBeaconBlock, Slot,
 };
 
+/// The time to wait for messages to be published to the network before responding, if no peer is
+/// yet subscribed to their topics.
+const PUBLISH_RESULT_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// Parse a slot.
 ///
 /// E.g., `"1234"`
@@ -111,6 +119,45 @@ pub fn parse_pubkey_bytes(string: &str) -> Result<PublicKeyBytes, ApiError> {
     }
 }
 
+/// Parse a `StateId` from a string, which may be `head`, `genesis`, `finalized`, `justified`, a
+/// slot or a `0x` prefixed state root.
+pub fn parse_state_id(string: &str) -> Result<StateId, ApiError> {
+    match string {
+        "head" => Ok(StateId::Head),
+        "genesis" => Ok(StateId::Genesis),
+        "finalized" => Ok(StateId::Finalized),
+        "justified" => Ok(StateId::Justified),
+        other if other.starts_with("0x") => parse_root(other).map(StateId::Root),
+        other => parse_slot(other).map(StateId::Slot),
+    }
+}
+
+/// Parse a `BlockId` from a string, which may be `head`, `genesis`, `finalized`, `justified`, a
+/// slot or a `0x` prefixed block root.
+pub fn parse_block_id(string: &str) -> Result<BlockId, ApiError> {
+    match string {
+        "head" => Ok(BlockId::Head),
+        "genesis" => Ok(BlockId::Genesis),
+        "finalized" => Ok(BlockId::Finalized),
+        "justified" => Ok(BlockId::Justified),
+        other if other.starts_with("0x") => parse_root(other).map(BlockId::Root),
+        other => parse_slot(other).map(BlockId::Slot),
+    }
+}
+
+/// Parse a `ValidatorId` from a string, which may be a validator index or a `0x` prefixed public
+/// key.
+pub fn parse_validator_id(string: &str) -> Result<ValidatorId, ApiError> {
+    if string.starts_with("0x") {
+        parse_pubkey_bytes(string).map(ValidatorId::PublicKey)
+    } else {
+        string
+            .parse::<u64>()
+            .map(ValidatorId::Index)
+            .map_err(|e| ApiError::BadRequest(format!("Unable to parse validator index: {:?}", e)))
+    }
+}
+
 /// Returns the root of the `SignedBeaconBlock` in the canonical chain of `beacon_chain` at the given
 /// `slot`, if possible.
 ///
@@ -235,22 +282,59 @@ pub fn implementation_pending_response(_req: Request<Body>) -> ApiResult {
     ))
 }
 
+/// Sends `block` to the network to be published, returning a receiver of the outcome which may be
+/// awaited with `await_publish_result`.
 pub fn publish_beacon_block_to_network<T: BeaconChainTypes + 'static>(
     chan: NetworkChannel<T::EthSpec>,
     block: SignedBeaconBlock<T::EthSpec>,
-) -> Result<(), ApiError> {
+) -> Result<PublishResultReceiver, ApiError> {
     // send the block via SSZ encoding
     let messages = vec![PubsubMessage::BeaconBlock(Box::new(block))];
+    let (result, publish_result) = oneshot::channel();
 
     // Publish the block to the p2p network via gossipsub.
-    if let Err(e) = chan.send(NetworkMessage::Publish { messages }) {
+    if let Err(e) = chan.send(NetworkMessage::Publish {
+        messages,
+        result: Some(result),
+    }) {
         return Err(ApiError::ServerError(format!(
             "Unable to send new block to network: {:?}",
             e
         )));
     }
 
-    Ok(())
+    Ok(publish_result)
+}
+
+/// Waits for the outcome of publishing the messages described by `description`.
+///
+/// The network queues messages until a peer subscribes to their topics, and reports a failure if
+/// they are no longer valid by then. Messages which are still queued after
+/// `PUBLISH_RESULT_TIMEOUT` may yet be published, so they are not treated as a failure.
+pub async fn await_publish_result(
+    publish_result: PublishResultReceiver,
+    description: &str,
+    log: &Logger,
+) -> Result<(), ApiError> {
+    match tokio::time::timeout(PUBLISH_RESULT_TIMEOUT, publish_result).await {
+        Ok(Ok(Ok(()))) => Ok(()),
+        Ok(Ok(Err(e))) => Err(ApiError::ServerError(format!(
+            "Unable to publish {}: {:?}",
+            description, e
+        ))),
+        Ok(Err(_)) => Err(ApiError::ServerError(format!(
+            "Network stopped before publishing {}",
+            description
+        ))),
+        Err(_) => {
+            warn!(
+                log,
+                "Publication queued until peers subscribe";
+                "message" => description,
+            );
+            Ok(())
+        }
+    }
 }
 
 #[cfg(test)]
@@ -281,4 +365,38 @@ mod test {
         assert_eq!(parse_slot("10000000"), Ok(Slot::new(10_000_000)));
         assert!(parse_slot("cats").is_err());
     }
+
+    #[test]
+    fn parse_state_id_works() {
+        assert_eq!(parse_state_id("head"), Ok(StateId::Head));
+        assert_eq!(parse_state_id("genesis"), Ok(StateId::Genesis));
+        assert_eq!(parse_state_id("finalized"), Ok(StateId::Finalized));
+        assert_eq!(parse_state_id("justified"), Ok(StateId::Justified));
+        assert_eq!(parse_state_id("42"), Ok(StateId::Slot(Slot::new(42))));
+        assert_eq!(
+            parse_state_id("0x000000000000000000000000000000000000000000000000000000000000002a"),
+            Ok(StateId::Root(Hash256::from_low_u64_be(42)))
+        );
+        assert!(parse_state_id("0x00").is_err());
+        assert!(parse_state_id("cats").is_err());
+    }
+
+    #[test]
+    fn parse_block_id_works() {
+        assert_eq!(parse_block_id("head"), Ok(BlockId::Head));
+        assert_eq!(parse_block_id("genesis"), Ok(BlockId::Genesis));
+        assert_eq!(parse_block_id("7"), Ok(BlockId::Slot(Slot::new(7))));
+        assert_eq!(
+            parse_block_id("0x0000000000000000000000000000000000000000000000000000000000000000"),
+            Ok(BlockId::Root(Hash256::zero()))
+        );
+        assert!(parse_block_id("cats").is_err());
+    }
+
+    #[test]
+    fn parse_validator_id_works() {
+        assert_eq!(parse_validator_id("7"), Ok(ValidatorId::Index(7)));
+        assert!(parse_validator_id("0x00").is_err());
+        assert!(parse_validator_id("cats").is_err());
+    }
 }
@@ -9,6 +9,7 @@ mod beacon;
 pub mod config;
 mod consensus;
 mod error;
+mod event_stream;
 mod helpers;
 mod lighthouse;
 mod metrics;
@@ -17,12 +18,12 @@ mod node;
 mod response_builder;
 mod router;
 mod spec;
+mod standard;
 mod url_query;
 mod validator;
 
-use beacon_chain::{BeaconChain, BeaconChainTypes};
-use bus::Bus;
-use client_network::NetworkMessage;
+use beacon_chain::{events::EventBuses, BeaconChain, BeaconChainTypes};
+use client_network::{BlockProcessorEvent, NetworkMessage};
 pub use config::ApiEncodingFormat;
 use error::{ApiError, ApiResult};
 use eth2_config::Eth2Config;
@@ -31,13 +32,11 @@ use futures::future::TryFutureExt;
 use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Request, Server};
-use parking_lot::Mutex;
 use slog::{info, warn};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use types::SignedBeaconBlockHash;
+use tokio::sync::{broadcast, mpsc};
 use url_query::UrlQuery;
 
 pub use crate::helpers::parse_pubkey_bytes;
@@ -48,6 +47,8 @@ pub type NetworkChannel<T> = mpsc::UnboundedSender<NetworkMessage<T>>;
 pub struct NetworkInfo<T: BeaconChainTypes> {
     pub network_globals: Arc<NetworkGlobals<T::EthSpec>>,
     pub network_chan: NetworkChannel<T::EthSpec>,
+    /// Subscribe to receive the events of the block processor.
+    pub block_processor_events: broadcast::Sender<BlockProcessorEvent>,
 }
 
 // Allowing more than 7 arguments.
@@ -60,10 +61,11 @@ pub fn start_server<T: BeaconChainTypes>(
     db_path: PathBuf,
     freezer_db_path: PathBuf,
     eth2_config: Eth2Config,
-    events: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+    events: EventBuses<T::EthSpec>,
 ) -> Result<SocketAddr, hyper::Error> {
     let log = executor.log();
     let inner_log = log.clone();
+    let inner_executor = executor.clone();
     let eth2_config = Arc::new(eth2_config);
 
     // Define the function that will build the request handler.
@@ -76,6 +78,7 @@ pub fn start_server<T: BeaconChainTypes>(
         let db_path = db_path.clone();
         let freezer_db_path = freezer_db_path.clone();
         let events = events.clone();
+        let executor = inner_executor.clone();
 
         async move {
             Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
@@ -89,6 +92,7 @@ pub fn start_server<T: BeaconChainTypes>(
                     db_path.clone(),
                     freezer_db_path.clone(),
                     events.clone(),
+                    executor.clone(),
                 )
             }))
         }
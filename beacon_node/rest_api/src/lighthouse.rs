@@ -1,19 +1,43 @@
 //! This contains a collection of lighthouse specific HTTP endpoints.
 
+use crate::helpers::{
+    parse_block_id, parse_epoch, parse_slot, parse_state_id, parse_validator_id, state_at_slot,
+};
 use crate::response_builder::ResponseBuilder;
-use crate::ApiResult;
-use eth2_libp2p::{NetworkGlobals, PeerInfo};
+use crate::standard::beacon::validator_indices;
+use crate::standard::{block_from_id, state_from_id};
+use crate::{ApiError, ApiResult, UrlQuery};
+use beacon_chain::{BeaconChain, BeaconChainTypes, StateSkipConfig};
+use eth2_libp2p::{NetworkGlobals, PeerInfo, Rep};
 use hyper::{Body, Request};
+use itertools::process_results;
+use rest_types::{BlockReward, ValidatorInclusionData};
 use serde::Serialize;
+use state_processing::common::{get_attesting_indices, get_base_reward};
+use state_processing::per_block_processing::get_slashable_indices;
+use state_processing::per_epoch_processing::{ValidatorStatus, ValidatorStatuses};
+use state_processing::{per_block_processing, per_slot_processing, BlockSignatureStrategy};
+use std::cmp;
+use std::collections::HashSet;
 use std::sync::Arc;
-use types::EthSpec;
+use types::{
+    AttesterSlashing, BeaconBlock, BeaconState, ChainSpec, Epoch, EthSpec, Hash256, Multiproof,
+    ProposerSlashing, RelativeEpoch,
+};
 
-/// The syncing state of the beacon node.
+const VALIDATOR_INCLUSION_PREFIX: &str = "/lighthouse/validator_inclusion/";
+const PROOFS_PREFIX: &str = "/lighthouse/proofs/";
+/// The maximum number of generalized indices which may be proven by a single request.
+const MAX_PROOF_GINDICES: usize = 1_024;
+/// The maximum number of slots which may be replayed by a single block rewards request.
+const MAX_BLOCK_REWARDS_SLOTS: u64 = 8_192;
+
+/// The syncing state of the beacon node, along with the progress of each chain being synced.
 pub fn syncing<T: EthSpec>(
     req: Request<Body>,
     network_globals: Arc<NetworkGlobals<T>>,
 ) -> ApiResult {
-    ResponseBuilder::new(&req)?.body_no_ssz(&network_globals.sync_state())
+    ResponseBuilder::new(&req)?.body_no_ssz(&network_globals.sync_status())
 }
 
 /// Returns all known peers and corresponding information
@@ -47,6 +71,432 @@ pub fn connected_peers<T: EthSpec>(
     ResponseBuilder::new(&req)?.body_no_ssz(&peers)
 }
 
+/// Returns the gossip score and reputation of each connected peer, from the lowest gossip score to
+/// the highest.
+pub fn peer_scores<T: EthSpec>(
+    req: Request<Body>,
+    network_globals: Arc<NetworkGlobals<T>>,
+) -> ApiResult {
+    let mut scores: Vec<PeerScore> = network_globals
+        .peers
+        .read()
+        .connected_peers()
+        .map(|(peer_id, peer_info)| PeerScore {
+            peer_id: peer_id.to_string(),
+            gossip_score: peer_info.gossip_score,
+            reputation: peer_info.reputation,
+        })
+        .collect();
+    scores.sort_by(|a, b| {
+        a.gossip_score
+            .partial_cmp(&b.gossip_score)
+            .unwrap_or(cmp::Ordering::Equal)
+    });
+    ResponseBuilder::new(&req)?.body_no_ssz(&scores)
+}
+
+/// HTTP handler for `/lighthouse/slasher/slashings`.
+///
+/// Returns the slashings detected by the slasher within its history, along with whether they are
+/// broadcast by this node.
+pub fn slasher_slashings<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let slasher = beacon_chain
+        .slasher
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("The slasher is not enabled".to_string()))?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&SlasherSlashings {
+        broadcast: slasher.config().broadcast,
+        attester_slashings: slasher.detected_attester_slashings(),
+        proposer_slashings: slasher.detected_proposer_slashings(),
+    })
+}
+
+/// HTTP handler for `/lighthouse/validator_inclusion/{epoch}/{validator_id}`.
+///
+/// Returns how the attestation of the validator for `epoch` was included in the canonical chain.
+/// The `validator_id` may be a validator index or a public key. The `epoch` must be prior to the
+/// current epoch, since attestations are only included in the state of the following epoch.
+pub fn validator_inclusion<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let path = req.uri().path().to_string();
+    let segments = path
+        .trim_start_matches(VALIDATOR_INCLUSION_PREFIX)
+        .trim_end_matches('/')
+        .split('/')
+        .collect::<Vec<_>>();
+
+    let (epoch, validator_id) = match segments.as_slice() {
+        [epoch, validator_id] => (parse_epoch(epoch)?, parse_validator_id(validator_id)?),
+        _ => {
+            return Err(ApiError::NotFound(
+                "Request path and/or method not found.".to_owned(),
+            ))
+        }
+    };
+
+    let current_epoch = beacon_chain.epoch()?;
+    if epoch >= current_epoch {
+        return Err(ApiError::BadRequest(format!(
+            "Epoch {} must be prior to the current epoch {}",
+            epoch, current_epoch
+        )));
+    }
+
+    // Attestations for `epoch` may be included until the end of the following epoch, where they
+    // are the previous epoch attestations of the state. The following epoch may not have ended.
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+    let end_of_next_epoch = (epoch + 2).start_slot(slots_per_epoch) - 1;
+    let target_slot = cmp::min(end_of_next_epoch, beacon_chain.slot()?);
+
+    // The target slot may be later than the head, if the slots since the head were skipped.
+    let mut state = beacon_chain
+        .state_at_slot(target_slot, StateSkipConfig::WithStateRoots)
+        .map_err(|e| {
+            ApiError::ServerError(format!("Unable to load state for epoch {}: {:?}", epoch, e))
+        })?;
+    let spec = &beacon_chain.spec;
+
+    state.build_committee_cache(RelativeEpoch::Previous, spec)?;
+    state.build_committee_cache(RelativeEpoch::Current, spec)?;
+
+    let validator_index = validator_indices(&mut state, Some(vec![validator_id]))?
+        .pop()
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown validator {}", segments[1])))?;
+
+    let mut validator_statuses = ValidatorStatuses::new(&state, spec)?;
+    validator_statuses.process_attestations(&state, spec)?;
+
+    let status = validator_statuses
+        .statuses
+        .get(validator_index)
+        .ok_or_else(|| ApiError::ServerError("Validator status missing".to_string()))?;
+
+    let data = ValidatorInclusionData {
+        epoch,
+        validator_index: validator_index as u64,
+        is_included: status.is_previous_epoch_attester,
+        inclusion_distance: status.inclusion_info.map(|info| info.delay),
+        is_correct_target: status.is_previous_epoch_target_attester,
+        is_correct_head: status.is_previous_epoch_head_attester,
+    };
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&data)
+}
+
+/// HTTP handler for the `/lighthouse/proofs/states/{state_id}` and
+/// `/lighthouse/proofs/blocks/{block_id}` endpoints.
+///
+/// Returns a multiproof of the nodes of the state or block at the generalized indices given by
+/// the `gindex` query parameters, of which there may be at most `MAX_PROOF_GINDICES`.
+pub fn proofs<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let path = req.uri().path().to_string();
+    let segments = path
+        .trim_start_matches(PROOFS_PREFIX)
+        .trim_end_matches('/')
+        .split('/')
+        .collect::<Vec<_>>();
+
+    let gindices = UrlQuery::from_request(&req)?
+        .all_of("gindex")?
+        .iter()
+        .flat_map(|value| value.split(','))
+        .map(|gindex| {
+            gindex.parse::<u64>().map_err(|e| {
+                ApiError::BadRequest(format!("Unable to parse gindex {}: {:?}", gindex, e))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if gindices.len() > MAX_PROOF_GINDICES {
+        return Err(ApiError::BadRequest(format!(
+            "Too many gindices: {} > {}",
+            gindices.len(),
+            MAX_PROOF_GINDICES
+        )));
+    }
+
+    let (root, proof) = match segments.as_slice() {
+        ["states", state_id] => {
+            let (root, mut state) = state_from_id(&beacon_chain, parse_state_id(state_id)?)?;
+            // Proofs of the nodes of lists and vectors are read from the tree hash cache.
+            state.update_tree_hash_cache().map_err(|e| {
+                ApiError::ServerError(format!("Unable to build tree hash cache: {:?}", e))
+            })?;
+            (root, Multiproof::generate(&state, &gindices))
+        }
+        ["blocks", block_id] => {
+            let (root, block) = block_from_id(&beacon_chain, parse_block_id(block_id)?)?;
+            (root, Multiproof::generate(&block.message, &gindices))
+        }
+        _ => {
+            return Err(ApiError::NotFound(
+                "Request path and/or method not found.".to_owned(),
+            ))
+        }
+    };
+    let proof =
+        proof.map_err(|e| ApiError::BadRequest(format!("Unable to generate proof: {:?}", e)))?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&ProofData {
+        root,
+        gindices: proof.gindices,
+        leaves: proof.leaves,
+        proof: proof.proof,
+    })
+}
+
+/// HTTP handler for `/lighthouse/analysis/block_rewards?start_slot=&end_slot=`.
+///
+/// Replays the blocks of the canonical chain from `start_slot` to `end_slot` inclusive and returns
+/// the rewards of each proposer for the operations included in its block. The range may span at
+/// most `MAX_BLOCK_REWARDS_SLOTS` slots.
+pub fn block_rewards<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let start_slot = UrlQuery::from_request(&req)?
+        .first_of(&["start_slot"])
+        .and_then(|(_key, value)| parse_slot(&value))?;
+    let end_slot = UrlQuery::from_request(&req)?
+        .first_of(&["end_slot"])
+        .and_then(|(_key, value)| parse_slot(&value))?;
+
+    if start_slot == 0 || start_slot > end_slot {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid slot range {}..={}, the genesis block has no proposer",
+            start_slot, end_slot
+        )));
+    }
+    if end_slot - start_slot >= MAX_BLOCK_REWARDS_SLOTS {
+        return Err(ApiError::BadRequest(format!(
+            "Slot range {}..={} is longer than the maximum of {} slots",
+            start_slot, end_slot, MAX_BLOCK_REWARDS_SLOTS
+        )));
+    }
+
+    // The iterator repeats the root of the previous block at skipped slots.
+    let mut block_roots = process_results(
+        beacon_chain.forwards_iter_block_roots(start_slot)?,
+        |iter| {
+            iter.take_while(|(_, slot)| *slot <= end_slot)
+                .map(|(root, _)| root)
+                .collect::<Vec<_>>()
+        },
+    )?;
+    block_roots.dedup();
+
+    let (state_root, mut state) = state_at_slot(&beacon_chain, start_slot - 1)?;
+    let mut state_root = Some(state_root);
+    let spec = &beacon_chain.spec;
+    let mut rewards = vec![];
+    let mut included_attesters: Option<IncludedAttesters> = None;
+
+    for block_root in block_roots {
+        let block = beacon_chain
+            .get_block(&block_root)?
+            .ok_or_else(|| ApiError::NotFound(format!("Unable to find block {}", block_root)))?;
+        if block.slot() < start_slot {
+            continue;
+        }
+
+        while state.slot < block.slot() {
+            per_slot_processing(&mut state, state_root.take(), spec)?;
+        }
+        state.build_committee_cache(RelativeEpoch::Previous, spec)?;
+        state.build_committee_cache(RelativeEpoch::Current, spec)?;
+
+        let attesters = match included_attesters.take() {
+            Some(attesters) if attesters.epoch == state.current_epoch() => attesters,
+            _ => IncludedAttesters::new(&state, spec)?,
+        };
+        let attesters = included_attesters.get_or_insert(attesters);
+
+        rewards.push(compute_block_reward(
+            block_root,
+            &block.message,
+            &state,
+            attesters,
+            spec,
+        )?);
+
+        per_block_processing(
+            &mut state,
+            &block,
+            Some(block_root),
+            BlockSignatureStrategy::NoVerification,
+            spec,
+        )
+        .map_err(|e| {
+            ApiError::ServerError(format!("Unable to replay block {}: {:?}", block_root, e))
+        })?;
+        state_root = Some(block.state_root());
+    }
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&rewards)
+}
+
+/// The validators whose attestations of the previous and current epochs of `epoch` have been
+/// included in the chain.
+///
+/// It is built from the state once per epoch and then kept up to date with the attestations of
+/// each replayed block.
+struct IncludedAttesters {
+    epoch: Epoch,
+    total_active_balance: u64,
+    previous_epoch: HashSet<usize>,
+    current_epoch: HashSet<usize>,
+}
+
+impl IncludedAttesters {
+    /// Returns the attesters whose attestations have been included in `state`.
+    fn new<E: EthSpec>(state: &BeaconState<E>, spec: &ChainSpec) -> Result<Self, ApiError> {
+        let mut validator_statuses = ValidatorStatuses::new(state, spec)?;
+        validator_statuses.process_attestations(state, spec)?;
+
+        let attesters = |is_attester: fn(&ValidatorStatus) -> bool| {
+            validator_statuses
+                .statuses
+                .iter()
+                .enumerate()
+                .filter(|(_, status)| is_attester(status))
+                .map(|(index, _)| index)
+                .collect()
+        };
+
+        Ok(Self {
+            epoch: state.current_epoch(),
+            total_active_balance: validator_statuses.total_balances.current_epoch(),
+            previous_epoch: attesters(|status| status.is_previous_epoch_attester),
+            current_epoch: attesters(|status| status.is_current_epoch_attester),
+        })
+    }
+}
+
+/// Returns the rewards of the proposer of `block` for its operations, given the `state` at the
+/// slot of the block, prior to processing it, and the `attesters` included in the state, which
+/// are updated with the attesters included by `block`.
+///
+/// Attestation rewards are only applied at the end of the epoch following the attestation, so
+/// they are estimated from the effective balances in `state`. They are attributed to the first
+/// block to include an attestation of each validator, which has the shortest inclusion delay.
+fn compute_block_reward<E: EthSpec>(
+    block_root: Hash256,
+    block: &BeaconBlock<E>,
+    state: &BeaconState<E>,
+    attesters: &mut IncludedAttesters,
+    spec: &ChainSpec,
+) -> Result<BlockReward, ApiError> {
+    let total_active_balance = attesters.total_active_balance;
+    let mut attestations = 0;
+    for attestation in block.body.attestations.iter() {
+        let data = &attestation.data;
+        let committee = state.get_beacon_committee(data.slot, data.index)?;
+        let attesting_indices =
+            get_attesting_indices::<E>(committee.committee, &attestation.aggregation_bits)?;
+        let included = if data.target.epoch == state.current_epoch() {
+            &mut attesters.current_epoch
+        } else {
+            &mut attesters.previous_epoch
+        };
+
+        for index in attesting_indices {
+            let is_slashed = state
+                .validators
+                .get(index)
+                .ok_or_else(|| ApiError::ServerError("Validator missing".to_string()))?
+                .slashed;
+
+            if !is_slashed && included.insert(index) {
+                let base_reward = get_base_reward(state, index, total_active_balance, spec)?;
+                attestations += base_reward / spec.proposer_reward_quotient;
+            }
+        }
+    }
+
+    // Without a whistleblower, the proposer receives the whole whistleblower reward. A validator
+    // is slashed at most once.
+    let mut slashed = HashSet::new();
+    let mut whistleblower_reward = |index: u64| -> Result<u64, ApiError> {
+        if slashed.insert(index) {
+            Ok(state.get_effective_balance(index as usize, spec)?
+                / spec.whistleblower_reward_quotient)
+        } else {
+            Ok(0)
+        }
+    };
+
+    let mut proposer_slashings = 0;
+    for slashing in block.body.proposer_slashings.iter() {
+        proposer_slashings +=
+            whistleblower_reward(slashing.signed_header_1.message.proposer_index)?;
+    }
+
+    let mut attester_slashings = 0;
+    for slashing in block.body.attester_slashings.iter() {
+        let slashable_indices = get_slashable_indices(state, slashing).map_err(|e| {
+            ApiError::ServerError(format!("Unable to get slashable indices: {:?}", e))
+        })?;
+        for index in slashable_indices {
+            attester_slashings += whistleblower_reward(index)?;
+        }
+    }
+
+    Ok(BlockReward {
+        block_root,
+        slot: block.slot,
+        proposer_index: block.proposer_index,
+        total: attestations + proposer_slashings + attester_slashings,
+        attestations,
+        proposer_slashings,
+        attester_slashings,
+    })
+}
+
+/// The scores of a peer returned by `peer_scores`.
+#[derive(Clone, Debug, Serialize)]
+struct PeerScore {
+    /// The Peer's ID
+    peer_id: String,
+    /// The score of the peer on the gossip messages it forwarded.
+    gossip_score: f64,
+    /// The reputation of the peer.
+    reputation: Rep,
+}
+
+/// A multiproof returned by `proofs`.
+#[derive(Clone, Debug, Serialize)]
+struct ProofData {
+    /// The root of the state or block.
+    root: Hash256,
+    /// The generalized indices of the proven nodes.
+    gindices: Vec<u64>,
+    /// The values of the nodes at `gindices`.
+    leaves: Vec<Hash256>,
+    /// The values of the helper nodes which, with `leaves`, prove `root`.
+    proof: Vec<Hash256>,
+}
+
+/// The offences returned by `slasher_slashings`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(bound = "T: EthSpec")]
+struct SlasherSlashings<T: EthSpec> {
+    /// Whether the slashings are added to the op pool and published on gossip.
+    broadcast: bool,
+    /// The pairs of slashable attestations, from the oldest to the most recently detected.
+    attester_slashings: Vec<AttesterSlashing<T>>,
+    /// The pairs of slashable block headers, from the oldest to the most recently detected.
+    proposer_slashings: Vec<ProposerSlashing>,
+}
+
 /// Information returned by `peers` and `connected_peers`.
 #[derive(Clone, Debug, Serialize)]
 #[serde(bound = "T: EthSpec")]
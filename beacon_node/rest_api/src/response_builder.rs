@@ -2,6 +2,7 @@ use super::{ApiError, ApiResult};
 use crate::config::ApiEncodingFormat;
 use hyper::header;
 use hyper::{Body, Request, Response, StatusCode};
+use rest_types::GenericResponse;
 use serde::Serialize;
 use ssz::Encode;
 
@@ -30,15 +31,29 @@ impl ResponseBuilder {
 
     pub fn body<T: Serialize + Encode>(self, item: &T) -> ApiResult {
         match self.encoding {
-            ApiEncodingFormat::SSZ => Response::builder()
-                .status(StatusCode::OK)
-                .header("content-type", "application/ssz")
-                .body(Body::from(item.as_ssz_bytes()))
-                .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e))),
+            ApiEncodingFormat::SSZ => self.body_ssz(item),
             _ => self.body_no_ssz(item),
         }
     }
 
+    /// Responds with `item` as SSZ, if requested as either `application/ssz` or
+    /// `application/octet-stream`, otherwise with `item` in the `data` field of a
+    /// `GenericResponse`. Used by the standard `/eth/v1/` endpoints which support SSZ.
+    pub fn body_data<T: Serialize + Encode>(self, item: &T) -> ApiResult {
+        match self.encoding {
+            ApiEncodingFormat::SSZ | ApiEncodingFormat::OctetStream => self.body_ssz(item),
+            _ => self.body_no_ssz(&GenericResponse::from(item)),
+        }
+    }
+
+    fn body_ssz<T: Encode>(self, item: &T) -> ApiResult {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", self.encoding.get_content_type())
+            .body(Body::from(item.as_ssz_bytes()))
+            .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
+    }
+
     pub fn body_no_ssz<T: Serialize>(self, item: &T) -> ApiResult {
         let (body, content_type) = match self.encoding {
             ApiEncodingFormat::JSON => (
@@ -50,7 +65,7 @@ impl ResponseBuilder {
                 })?),
                 "application/json",
             ),
-            ApiEncodingFormat::SSZ => {
+            ApiEncodingFormat::SSZ | ApiEncodingFormat::OctetStream => {
                 return Err(ApiError::UnsupportedType(
                     "Response cannot be encoded as SSZ.".into(),
                 ));
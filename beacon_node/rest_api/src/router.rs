@@ -1,18 +1,16 @@
 use crate::{
-    advanced, beacon, consensus, error::ApiError, helpers, lighthouse, metrics, network, node,
-    spec, validator, NetworkChannel,
+    advanced, beacon, consensus, error::ApiError, event_stream, helpers, lighthouse, metrics,
+    network, node, spec, standard, validator, NetworkChannel,
 };
-use beacon_chain::{BeaconChain, BeaconChainTypes};
-use bus::Bus;
+use beacon_chain::{events::EventBuses, BeaconChain, BeaconChainTypes};
 use eth2_config::Eth2Config;
 use eth2_libp2p::NetworkGlobals;
 use hyper::{Body, Error, Method, Request, Response};
-use parking_lot::Mutex;
 use slog::debug;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
-use types::{SignedBeaconBlockHash, Slot};
+use types::Slot;
 
 // Allowing more than 7 arguments.
 #[allow(clippy::too_many_arguments)]
@@ -25,7 +23,8 @@ pub async fn route<T: BeaconChainTypes>(
     local_log: slog::Logger,
     db_path: PathBuf,
     freezer_db_path: PathBuf,
-    events: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+    events: EventBuses<T::EthSpec>,
+    executor: environment::TaskExecutor,
 ) -> Result<Response<Body>, Error> {
     metrics::inc_counter(&metrics::REQUEST_COUNT);
     let timer = metrics::start_timer(&metrics::REQUEST_RESPONSE_TIME);
@@ -67,9 +66,13 @@ pub async fn route<T: BeaconChainTypes>(
         (&Method::GET, "/beacon/block_root") => beacon::get_block_root::<T>(req, beacon_chain),
         (&Method::GET, "/beacon/fork") => beacon::get_fork::<T>(req, beacon_chain),
         (&Method::GET, "/beacon/fork/stream") => {
-            let reader = events.lock().add_rx();
+            let reader = events.head_changed.lock().add_rx();
             beacon::stream_forks::<T>(log, reader)
         }
+        (&Method::GET, "/eth/v1/events") => {
+            let reader = events.chain_events.lock().subscribe();
+            event_stream::stream_events::<T::EthSpec>(req, log, reader, &executor)
+        }
         (&Method::GET, "/beacon/genesis_time") => beacon::get_genesis_time::<T>(req, beacon_chain),
         (&Method::GET, "/beacon/genesis_validators_root") => {
             beacon::get_genesis_validators_root::<T>(req, beacon_chain)
@@ -158,6 +161,12 @@ pub async fn route<T: BeaconChainTypes>(
         (&Method::GET, "/advanced/fork_choice") => {
             advanced::get_fork_choice::<T>(req, beacon_chain)
         }
+        (&Method::GET, "/advanced/fork_choice/block_tree") => {
+            advanced::get_fork_choice_block_tree::<T>(req, beacon_chain)
+        }
+        (&Method::GET, "/advanced/fork_choice/snapshot") => {
+            advanced::get_fork_choice_snapshot::<T>(req, beacon_chain)
+        }
         (&Method::GET, "/advanced/operation_pool") => {
             advanced::get_operation_pool::<T>(req, beacon_chain)
         }
@@ -178,6 +187,81 @@ pub async fn route<T: BeaconChainTypes>(
         (&Method::GET, "/lighthouse/connected_peers") => {
             lighthouse::connected_peers::<T::EthSpec>(req, network_globals)
         }
+
+        (&Method::GET, "/lighthouse/peer_scores") => {
+            lighthouse::peer_scores::<T::EthSpec>(req, network_globals)
+        }
+
+        (&Method::GET, "/lighthouse/slasher/slashings") => {
+            lighthouse::slasher_slashings::<T>(req, beacon_chain)
+        }
+
+        (&Method::GET, "/lighthouse/analysis/block_rewards") => {
+            lighthouse::block_rewards::<T>(req, beacon_chain)
+        }
+
+        (&Method::GET, path) if path.starts_with("/lighthouse/validator_inclusion/") => {
+            lighthouse::validator_inclusion::<T>(req, beacon_chain)
+        }
+
+        (&Method::GET, path) if path.starts_with("/lighthouse/proofs/") => {
+            lighthouse::proofs::<T>(req, beacon_chain)
+        }
+
+        // Methods for the standard Eth2 API
+        (&Method::GET, "/eth/v1/node/identity") => {
+            standard::node::get_identity::<T::EthSpec>(req, network_globals)
+        }
+        (&Method::GET, "/eth/v1/node/peers") => {
+            standard::node::get_peers::<T::EthSpec>(req, network_globals)
+        }
+        (&Method::GET, "/eth/v1/node/peer_count") => {
+            standard::node::get_peer_count::<T::EthSpec>(req, network_globals)
+        }
+        (&Method::GET, "/eth/v1/node/syncing") => {
+            standard::node::get_syncing::<T>(req, beacon_chain, network_globals)
+        }
+        (&Method::GET, path) if path.starts_with("/eth/v1/beacon/states/") => {
+            standard::beacon::get_state_endpoint::<T>(req, beacon_chain)
+        }
+        (&Method::GET, path) if path.starts_with("/eth/v1/beacon/blocks/") => {
+            standard::beacon::get_block_endpoint::<T>(req, beacon_chain)
+        }
+        (&Method::GET, "/eth/v1/beacon/deposit_snapshot") => {
+            standard::beacon::get_deposit_snapshot::<T>(req, beacon_chain)
+        }
+        (&Method::POST, path) if path.starts_with("/eth/v1/beacon/rewards/attestations/") => {
+            standard::rewards::post_attestation_rewards::<T>(req, beacon_chain).await
+        }
+        (&Method::GET, "/eth/v1/debug/beacon/heads") => {
+            standard::debug::get_heads::<T>(req, beacon_chain)
+        }
+        (&Method::GET, path) if path.starts_with("/eth/v2/debug/beacon/states/") => {
+            standard::debug::get_state::<T>(req, beacon_chain)
+        }
+        (&Method::POST, "/eth/v1/beacon/blocks") => {
+            standard::beacon::publish_block::<T>(req, beacon_chain, network_channel, log).await
+        }
+        (&Method::POST, path) if path.starts_with("/eth/v1/validator/duties/attester/") => {
+            standard::validator::post_attester_duties::<T>(req, beacon_chain).await
+        }
+        (&Method::GET, path) if path.starts_with("/eth/v1/validator/duties/proposer/") => {
+            standard::validator::get_proposer_duties::<T>(req, beacon_chain)
+        }
+        (&Method::POST, "/eth/v1/validator/beacon_committee_subscriptions") => {
+            standard::validator::post_beacon_committee_subscriptions::<T>(req, network_channel)
+                .await
+        }
+        (&Method::POST, path) if path.starts_with("/eth/v1/validator/liveness/") => {
+            standard::validator::post_liveness::<T>(req, beacon_chain).await
+        }
+        (&Method::GET, "/eth/v1/validator/aggregate_attestation") => {
+            standard::validator::get_aggregate_attestation::<T>(req, beacon_chain)
+        }
+        (&Method::POST, "/eth/v1/validator/aggregate_and_proofs") => {
+            validator::publish_aggregate_and_proofs::<T>(req, beacon_chain, network_channel, log)
+                .await
+        }
         _ => Err(ApiError::NotFound(
             "Request path and/or method not found.".to_owned(),
         )),
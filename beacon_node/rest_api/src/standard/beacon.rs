@@ -0,0 +1,371 @@
+use super::{block_from_id, state_from_id};
+use crate::helpers::{
+    await_publish_result, parse_block_id, parse_committee_index, parse_epoch, parse_slot,
+    parse_state_id, parse_validator_id, publish_beacon_block_to_network,
+};
+use crate::response_builder::ResponseBuilder;
+use crate::{ApiError, ApiResult, NetworkChannel, UrlQuery};
+use beacon_chain::{BeaconChain, BeaconChainTypes, BlockError};
+use hyper::{header, Body, Request};
+use rest_types::{
+    CommitteeData, FinalityCheckpointsData, ForkData, GenericResponse, RootData, StateId,
+    ValidatorBalanceData, ValidatorData, ValidatorId, ValidatorStatus,
+};
+use slog::{error, info, warn, Logger};
+use ssz::Decode;
+use std::sync::Arc;
+use types::{BeaconState, EthSpec, RelativeEpoch, SignedBeaconBlock};
+
+const STATES_PREFIX: &str = "/eth/v1/beacon/states/";
+const BLOCKS_PREFIX: &str = "/eth/v1/beacon/blocks/";
+
+/// HTTP handler for all the `/eth/v1/beacon/states/{state_id}/...` endpoints.
+///
+/// The `state_id` may be `head`, `genesis`, `finalized`, `justified`, a slot or a state root.
+pub fn get_state_endpoint<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let path = req.uri().path().to_string();
+    let segments = path
+        .trim_start_matches(STATES_PREFIX)
+        .trim_end_matches('/')
+        .split('/')
+        .collect::<Vec<_>>();
+
+    let state_id = parse_state_id(segments[0])?;
+
+    match &segments[1..] {
+        ["root"] => {
+            let (root, _state) = state_from_id(&beacon_chain, state_id)?;
+            ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(RootData { root }))
+        }
+        ["fork"] => {
+            let (_root, state) = state_from_id(&beacon_chain, state_id)?;
+            ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(ForkData(state.fork)))
+        }
+        ["finality_checkpoints"] => {
+            let (_root, state) = state_from_id(&beacon_chain, state_id)?;
+            let data = FinalityCheckpointsData {
+                previous_justified: state.previous_justified_checkpoint,
+                current_justified: state.current_justified_checkpoint,
+                finalized: state.finalized_checkpoint,
+            };
+            ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(data))
+        }
+        ["validators"] => {
+            let (_root, mut state) = state_from_id(&beacon_chain, state_id)?;
+            let ids = validator_ids_from_query(&req)?;
+            let data = validator_indices(&mut state, ids)?
+                .into_iter()
+                .map(|index| validator_data(&state, index, &beacon_chain))
+                .collect::<Result<Vec<_>, _>>()?;
+            ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(data))
+        }
+        ["validators", validator_id] => {
+            let (_root, mut state) = state_from_id(&beacon_chain, state_id)?;
+            let id = parse_validator_id(validator_id)?;
+            let index = validator_indices(&mut state, Some(vec![id]))?
+                .pop()
+                .ok_or_else(|| ApiError::NotFound(format!("Unknown validator {}", validator_id)))?;
+            let data = validator_data(&state, index, &beacon_chain)?;
+            ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(data))
+        }
+        ["validator_balances"] => {
+            let (_root, mut state) = state_from_id(&beacon_chain, state_id)?;
+            let ids = validator_ids_from_query(&req)?;
+            let data = validator_indices(&mut state, ids)?
+                .into_iter()
+                .map(|index| ValidatorBalanceData {
+                    index: index as u64,
+                    balance: state.balances[index],
+                })
+                .collect::<Vec<_>>();
+            ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(data))
+        }
+        ["committees"] => {
+            let (_root, state) = state_from_id(&beacon_chain, state_id)?;
+            let data = committees(&req, state, &beacon_chain)?;
+            ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(data))
+        }
+        _ => Err(ApiError::NotFound(
+            "Request path and/or method not found.".to_owned(),
+        )),
+    }
+}
+
+/// HTTP handler for the `/eth/v1/beacon/blocks/{block_id}` endpoints.
+///
+/// The `block_id` may be `head`, `genesis`, `finalized`, `justified`, a slot or a block root. The
+/// block is SSZ encoded if requested by the `Accept` header.
+pub fn get_block_endpoint<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let path = req.uri().path().to_string();
+    let segments = path
+        .trim_start_matches(BLOCKS_PREFIX)
+        .trim_end_matches('/')
+        .split('/')
+        .collect::<Vec<_>>();
+
+    let block_id = parse_block_id(segments[0])?;
+
+    match &segments[1..] {
+        [] => {
+            let (_root, block) = block_from_id(&beacon_chain, block_id)?;
+            ResponseBuilder::new(&req)?.body_data(&block)
+        }
+        ["root"] => {
+            let (root, _block) = block_from_id(&beacon_chain, block_id)?;
+            ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(RootData { root }))
+        }
+        _ => Err(ApiError::NotFound(
+            "Request path and/or method not found.".to_owned(),
+        )),
+    }
+}
+
+/// HTTP handler for `GET /eth/v1/beacon/deposit_snapshot`.
+///
+/// Returns a snapshot of the deposit tree at the `Eth1Data` of the finalized state, which another
+/// node may use to bootstrap its eth1 deposit cache.
+pub fn get_deposit_snapshot<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let eth1_chain = beacon_chain
+        .eth1_chain
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Node does not have an eth1 backend".to_string()))?;
+
+    let (_root, state) = state_from_id(&beacon_chain, StateId::Finalized)?;
+    let snapshot = eth1_chain.deposit_snapshot(&state).ok_or_else(|| {
+        ApiError::NotFound("No deposit snapshot is available for the finalized state".to_string())
+    })?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(snapshot))
+}
+
+/// HTTP handler to publish a `SignedBeaconBlock`, provided as SSZ or JSON.
+///
+/// The block is published to the network once it passes gossip validation, and then imported.
+/// A 202 response indicates that the block was published but could not be imported.
+pub async fn publish_block<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network_chan: NetworkChannel<T::EthSpec>,
+    log: Logger,
+) -> ApiResult {
+    let response_builder = ResponseBuilder::new(&req)?;
+    let is_ssz = match req.headers().get(header::CONTENT_TYPE) {
+        Some(h) => h == "application/octet-stream" || h == "application/ssz",
+        None => false,
+    };
+
+    let chunks = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
+
+    let block: SignedBeaconBlock<T::EthSpec> = if is_ssz {
+        SignedBeaconBlock::from_ssz_bytes(&chunks).map_err(|e| {
+            ApiError::BadRequest(format!(
+                "Unable to parse SSZ into SignedBeaconBlock: {:?}",
+                e
+            ))
+        })?
+    } else {
+        serde_json::from_slice(&chunks).map_err(|e| {
+            ApiError::BadRequest(format!(
+                "Unable to parse JSON into SignedBeaconBlock: {:?}",
+                e
+            ))
+        })?
+    };
+
+    let slot = block.slot();
+    let verified_block = match beacon_chain.verify_block_for_gossip(block.clone()) {
+        Ok(verified_block) => verified_block,
+        Err(BlockError::BeaconChainError(e)) => {
+            return Err(ApiError::ServerError(format!(
+                "Error while verifying block: {:?}",
+                e
+            )))
+        }
+        Err(e) => {
+            warn!(
+                log,
+                "Invalid block published via HTTP";
+                "outcome" => format!("{:?}", e)
+            );
+            return Err(ApiError::BadRequest(format!(
+                "The SignedBeaconBlock failed gossip validation and has not been published: {:?}",
+                e
+            )));
+        }
+    };
+
+    let publish_result = publish_beacon_block_to_network::<T>(network_chan, block)?;
+
+    let import_result = match beacon_chain.process_block(verified_block) {
+        Ok(block_root) => {
+            info!(
+                log,
+                "Block published via HTTP";
+                "block_root" => format!("{}", block_root),
+                "block_slot" => slot,
+            );
+
+            if let Err(e) = beacon_chain.fork_choice() {
+                error!(
+                    log,
+                    "Failed to find beacon chain head";
+                    "error" => format!("{:?}", e)
+                );
+            }
+
+            Ok(())
+        }
+        Err(e) => {
+            warn!(
+                log,
+                "Published block could not be imported";
+                "outcome" => format!("{:?}", e)
+            );
+            Err(ApiError::ProcessingError(format!(
+                "The SignedBeaconBlock was published but could not be imported: {:?}",
+                e
+            )))
+        }
+    };
+
+    await_publish_result(publish_result, "block", &log).await?;
+    import_result?;
+
+    response_builder.body_no_ssz(&())
+}
+
+/// Returns the validators identified by the `id` query parameters, which may be repeated or
+/// comma-separated. Returns `None` if no `id` is provided, which selects all validators.
+fn validator_ids_from_query(req: &Request<Body>) -> Result<Option<Vec<ValidatorId>>, ApiError> {
+    let ids = UrlQuery::from_request(req)?
+        .all_of("id")?
+        .iter()
+        .flat_map(|value| value.split(','))
+        .map(parse_validator_id)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if ids.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(ids))
+    }
+}
+
+/// Maps `ids` to indices in the validator registry of `state`, ignoring any unknown validators.
+///
+/// Returns all indices if `ids` is `None`.
+pub(crate) fn validator_indices<E: EthSpec>(
+    state: &mut BeaconState<E>,
+    ids: Option<Vec<ValidatorId>>,
+) -> Result<Vec<usize>, ApiError> {
+    let ids = match ids {
+        Some(ids) => ids,
+        None => return Ok((0..state.validators.len()).collect()),
+    };
+
+    state.update_pubkey_cache()?;
+
+    ids.into_iter()
+        .filter_map(|id| match id {
+            ValidatorId::Index(index) => {
+                let index = index as usize;
+                if index < state.validators.len() {
+                    Some(Ok(index))
+                } else {
+                    None
+                }
+            }
+            ValidatorId::PublicKey(pubkey) => state
+                .get_validator_index(&pubkey)
+                .map_err(|e| ApiError::ServerError(format!("Unable to read pubkey cache: {:?}", e)))
+                .transpose(),
+        })
+        .collect()
+}
+
+/// Returns the `ValidatorData` for the validator at `index`, which must be a valid index.
+fn validator_data<T: BeaconChainTypes>(
+    state: &BeaconState<T::EthSpec>,
+    index: usize,
+    beacon_chain: &BeaconChain<T>,
+) -> Result<ValidatorData, ApiError> {
+    let validator = state
+        .validators
+        .get(index)
+        .ok_or_else(|| ApiError::ServerError(format!("Invalid validator index: {:?}", index)))?;
+    let balance = *state
+        .balances
+        .get(index)
+        .ok_or_else(|| ApiError::ServerError(format!("Invalid balances index: {:?}", index)))?;
+
+    Ok(ValidatorData {
+        index: index as u64,
+        balance,
+        status: ValidatorStatus::from_validator(
+            validator,
+            balance,
+            state.current_epoch(),
+            beacon_chain.spec.far_future_epoch,
+        ),
+        validator: validator.clone(),
+    })
+}
+
+/// Returns the committees of `state`, filtered by the optional `epoch`, `index` and `slot` query
+/// parameters. The epoch defaults to the current epoch of `state`.
+fn committees<T: BeaconChainTypes>(
+    req: &Request<Body>,
+    mut state: BeaconState<T::EthSpec>,
+    beacon_chain: &BeaconChain<T>,
+) -> Result<Vec<CommitteeData>, ApiError> {
+    let query = UrlQuery::from_request(req)?;
+    let epoch = match query.first_of_opt(&["epoch"]) {
+        Some((_key, value)) => parse_epoch(&value)?,
+        None => state.current_epoch(),
+    };
+    let index = match query.first_of_opt(&["index"]) {
+        Some((_key, value)) => Some(parse_committee_index(&value)?),
+        None => None,
+    };
+    let slot = match query.first_of_opt(&["slot"]) {
+        Some((_key, value)) => Some(parse_slot(&value)?),
+        None => None,
+    };
+
+    let relative_epoch = RelativeEpoch::from_epoch(state.current_epoch(), epoch).map_err(|_| {
+        ApiError::BadRequest(format!(
+            "Epoch {} is not within one epoch of the state at slot {}",
+            epoch, state.slot
+        ))
+    })?;
+
+    state
+        .build_committee_cache(relative_epoch, &beacon_chain.spec)
+        .map_err(|e| ApiError::ServerError(format!("Unable to build committee cache: {:?}", e)))?;
+
+    let committees = state
+        .get_beacon_committees_at_epoch(relative_epoch)
+        .map_err(|e| ApiError::ServerError(format!("Unable to get all committees: {:?}", e)))?
+        .into_iter()
+        .filter(|c| index.map_or(true, |index| c.index == index))
+        .filter(|c| slot.map_or(true, |slot| c.slot == slot))
+        .map(|c| CommitteeData {
+            index: c.index,
+            slot: c.slot,
+            validators: c.committee.iter().map(|i| *i as u64).collect(),
+        })
+        .collect();
+
+    Ok(committees)
+}
@@ -0,0 +1,42 @@
+use super::state_from_id;
+use crate::helpers::parse_state_id;
+use crate::response_builder::ResponseBuilder;
+use crate::ApiResult;
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use hyper::{Body, Request};
+use rest_types::{ChainHeadData, GenericResponse};
+use std::sync::Arc;
+
+const STATES_PREFIX: &str = "/eth/v2/debug/beacon/states/";
+
+/// HTTP handler to return the root and slot of every head in the fork choice block tree.
+pub fn get_heads<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let heads = beacon_chain
+        .heads()
+        .into_iter()
+        .map(|(root, slot)| ChainHeadData { slot, root })
+        .collect::<Vec<_>>();
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(heads))
+}
+
+/// HTTP handler to return the full `BeaconState` identified by the `state_id` in the request path.
+///
+/// The state is SSZ encoded if requested by the `Accept` header.
+pub fn get_state<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let state_id = parse_state_id(
+        req.uri()
+            .path()
+            .trim_start_matches(STATES_PREFIX)
+            .trim_end_matches('/'),
+    )?;
+    let (_root, state) = state_from_id(&beacon_chain, state_id)?;
+
+    ResponseBuilder::new(&req)?.body_data(&state)
+}
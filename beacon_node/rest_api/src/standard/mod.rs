@@ -0,0 +1,93 @@
+//! Handlers for the standard Eth2 Beacon Node API (i.e., the `/eth/v1/` endpoints).
+
+pub mod beacon;
+pub mod debug;
+pub mod node;
+pub mod rewards;
+pub mod validator;
+
+use crate::helpers::{block_root_at_slot, state_at_slot};
+use crate::ApiError;
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use rest_types::{BlockId, StateId};
+use types::{BeaconState, EthSpec, Hash256, SignedBeaconBlock};
+
+/// Returns the root and `BeaconState` identified by `state_id`.
+pub fn state_from_id<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    state_id: StateId,
+) -> Result<(Hash256, BeaconState<T::EthSpec>), ApiError> {
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+
+    match state_id {
+        StateId::Head => {
+            let head = beacon_chain.head()?;
+            Ok((head.beacon_state_root, head.beacon_state))
+        }
+        StateId::Genesis => state_at_slot(beacon_chain, beacon_chain.spec.genesis_slot),
+        StateId::Finalized => {
+            let checkpoint = beacon_chain.head_info()?.finalized_checkpoint;
+            state_at_slot(beacon_chain, checkpoint.epoch.start_slot(slots_per_epoch))
+        }
+        StateId::Justified => {
+            let checkpoint = beacon_chain.head_info()?.current_justified_checkpoint;
+            state_at_slot(beacon_chain, checkpoint.epoch.start_slot(slots_per_epoch))
+        }
+        StateId::Slot(slot) => state_at_slot(beacon_chain, slot),
+        StateId::Root(root) => beacon_chain
+            .get_state(&root, None)?
+            .map(|state| (root, state))
+            .ok_or_else(|| ApiError::NotFound(format!("Unable to find state at root {}", root))),
+    }
+}
+
+/// Returns the root and `SignedBeaconBlock` identified by `block_id`.
+pub fn block_from_id<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    block_id: BlockId,
+) -> Result<(Hash256, SignedBeaconBlock<T::EthSpec>), ApiError> {
+    let root = match block_id {
+        BlockId::Head => {
+            let head = beacon_chain.head()?;
+            return Ok((head.beacon_block_root, head.beacon_block));
+        }
+        BlockId::Genesis => beacon_chain.genesis_block_root,
+        BlockId::Finalized => checkpoint_block_root(
+            beacon_chain,
+            beacon_chain.head_info()?.finalized_checkpoint.root,
+        ),
+        BlockId::Justified => checkpoint_block_root(
+            beacon_chain,
+            beacon_chain.head_info()?.current_justified_checkpoint.root,
+        ),
+        BlockId::Slot(slot) => block_root_at_slot(beacon_chain, slot)?.ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Unable to find SignedBeaconBlock for slot {}",
+                slot
+            ))
+        })?,
+        BlockId::Root(root) => root,
+    };
+
+    beacon_chain
+        .get_block(&root)?
+        .map(|block| (root, block))
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Unable to find SignedBeaconBlock for root {}",
+                root
+            ))
+        })
+}
+
+/// Checkpoints in the genesis epoch have a zero root, which refers to the genesis block.
+fn checkpoint_block_root<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    root: Hash256,
+) -> Hash256 {
+    if root == Hash256::zero() {
+        beacon_chain.genesis_block_root
+    } else {
+        root
+    }
+}
@@ -0,0 +1,188 @@
+use crate::response_builder::ResponseBuilder;
+use crate::{ApiError, ApiResult, UrlQuery};
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use eth2_libp2p::{types::SyncState, EnrExt, NetworkGlobals, PeerInfo};
+use hyper::{Body, Request};
+use rest_types::{
+    GenericResponse, IdentityData, MetaData, PeerCount, PeerData, PeerDirection, PeerState,
+    SyncingData,
+};
+use ssz::Encode;
+use std::sync::Arc;
+use types::EthSpec;
+
+/// HTTP handler to return the peer id, ENR, addresses and metadata of the local node.
+pub fn get_identity<T: EthSpec>(
+    req: Request<Body>,
+    network_globals: Arc<NetworkGlobals<T>>,
+) -> ApiResult {
+    let enr = network_globals.local_enr();
+    let tcp_addresses = enr.multiaddr_tcp();
+    let discovery_addresses = enr
+        .multiaddr()
+        .into_iter()
+        .filter(|addr| !tcp_addresses.contains(addr))
+        .map(|addr| addr.to_string())
+        .collect();
+    let meta_data = network_globals.meta_data.read().clone();
+
+    let identity = IdentityData {
+        peer_id: network_globals.local_peer_id().to_base58(),
+        enr: enr.to_base64(),
+        p2p_addresses: network_globals
+            .listen_multiaddrs()
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        discovery_addresses,
+        metadata: MetaData {
+            seq_number: meta_data.seq_number,
+            attnets: format!("0x{}", hex::encode(meta_data.attnets.as_ssz_bytes())),
+        },
+    };
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(identity))
+}
+
+/// HTTP handler to return all known peers, optionally filtered by any number of `state` and
+/// `direction` query parameters.
+pub fn get_peers<T: EthSpec>(
+    req: Request<Body>,
+    network_globals: Arc<NetworkGlobals<T>>,
+) -> ApiResult {
+    let query = UrlQuery::from_request(&req)?;
+    let states = query
+        .all_of("state")?
+        .iter()
+        .map(|state| parse_peer_state(state))
+        .collect::<Result<Vec<_>, _>>()?;
+    let directions = query
+        .all_of("direction")?
+        .iter()
+        .map(|direction| parse_peer_direction(direction))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let peers = network_globals
+        .peers
+        .read()
+        .peers()
+        .map(|(peer_id, peer_info)| {
+            let (state, direction) = peer_state(peer_info);
+            PeerData {
+                peer_id: peer_id.to_string(),
+                last_seen_p2p_address: peer_info
+                    .listening_addresses
+                    .first()
+                    .map(ToString::to_string),
+                state,
+                direction,
+            }
+        })
+        .filter(|peer| states.is_empty() || states.contains(&peer.state))
+        .filter(|peer| {
+            directions.is_empty()
+                || peer
+                    .direction
+                    .map_or(false, |direction| directions.contains(&direction))
+        })
+        .collect::<Vec<_>>();
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(peers))
+}
+
+/// HTTP handler to return the number of known peers in each connection state.
+pub fn get_peer_count<T: EthSpec>(
+    req: Request<Body>,
+    network_globals: Arc<NetworkGlobals<T>>,
+) -> ApiResult {
+    let mut count = PeerCount::default();
+
+    for (_peer_id, peer_info) in network_globals.peers.read().peers() {
+        match peer_state(peer_info).0 {
+            PeerState::Disconnected => count.disconnected += 1,
+            PeerState::Connecting => count.connecting += 1,
+            PeerState::Connected => count.connected += 1,
+            PeerState::Disconnecting => count.disconnecting += 1,
+        }
+    }
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(count))
+}
+
+/// HTTP handler to return the head slot and the distance to the target of the sync.
+///
+/// Whilst syncing, the target is the head slot of the chain being synced. Otherwise, it is the
+/// current slot.
+pub fn get_syncing<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network_globals: Arc<NetworkGlobals<T::EthSpec>>,
+) -> ApiResult {
+    let head_slot = beacon_chain.head_info()?.slot;
+
+    let target_slot = match network_globals.sync_state() {
+        SyncState::SyncingFinalized { head_slot, .. }
+        | SyncState::SyncingHead { head_slot, .. } => head_slot,
+        SyncState::Synced | SyncState::Stalled => beacon_chain
+            .slot()
+            .map_err(|_| ApiError::ServerError("Unable to read slot clock".to_string()))?,
+    };
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&GenericResponse::from(SyncingData {
+        is_syncing: network_globals.is_syncing(),
+        head_slot,
+        sync_distance: target_slot.saturating_sub(head_slot),
+    }))
+}
+
+/// Maps the connection status of a peer to its state and, if known, the direction of the
+/// connection.
+///
+/// Peers with both inbound and outbound connections are considered outbound. Connected peers are
+/// disconnecting once a GOODBYE has been sent to or received from them.
+fn peer_state<T: EthSpec>(peer_info: &PeerInfo<T>) -> (PeerState, Option<PeerDirection>) {
+    let status = &peer_info.connection_status;
+
+    if status.is_connected() {
+        let (_n_in, n_out) = status.connections();
+        let direction = if n_out > 0 {
+            PeerDirection::Outbound
+        } else {
+            PeerDirection::Inbound
+        };
+        let state = if peer_info.disconnecting {
+            PeerState::Disconnecting
+        } else {
+            PeerState::Connected
+        };
+        (state, Some(direction))
+    } else if status.is_dialing() {
+        (PeerState::Connecting, Some(PeerDirection::Outbound))
+    } else {
+        (PeerState::Disconnected, None)
+    }
+}
+
+fn parse_peer_state(string: &str) -> Result<PeerState, ApiError> {
+    match string {
+        "disconnected" => Ok(PeerState::Disconnected),
+        "connecting" => Ok(PeerState::Connecting),
+        "connected" => Ok(PeerState::Connected),
+        "disconnecting" => Ok(PeerState::Disconnecting),
+        other => Err(ApiError::BadRequest(format!(
+            "Invalid peer state: {}",
+            other
+        ))),
+    }
+}
+
+fn parse_peer_direction(string: &str) -> Result<PeerDirection, ApiError> {
+    match string {
+        "inbound" => Ok(PeerDirection::Inbound),
+        "outbound" => Ok(PeerDirection::Outbound),
+        other => Err(ApiError::BadRequest(format!(
+            "Invalid peer direction: {}",
+            other
+        ))),
+    }
+}
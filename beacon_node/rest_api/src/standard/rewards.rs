@@ -0,0 +1,180 @@
+use crate::helpers::parse_epoch;
+use crate::response_builder::ResponseBuilder;
+use crate::{ApiError, ApiResult};
+use beacon_chain::{BeaconChain, BeaconChainTypes, StateSkipConfig};
+use hyper::{Body, Request};
+use rest_types::{
+    AttestationRewardsData, GenericResponse, IdealAttestationRewards, TotalAttestationRewards,
+    ValidatorIndices,
+};
+use state_processing::common::{get_base_reward, get_base_reward_from_effective_balance};
+use state_processing::per_epoch_processing::{
+    apply_rewards::{get_attestation_delta, AttestationDelta, Delta},
+    process_justification_and_finalization,
+    validator_statuses::InclusionInfo,
+    TotalBalances, ValidatorStatus, ValidatorStatuses,
+};
+use std::sync::Arc;
+use types::{ChainSpec, EthSpec, RelativeEpoch};
+
+const ATTESTATION_REWARDS_PREFIX: &str = "/eth/v1/beacon/rewards/attestations/";
+
+/// HTTP handler to return the attestation rewards for the epoch in the request path, of the
+/// validators whose indices are provided as a JSON array in the request body. An empty array
+/// requests the rewards of all validators.
+///
+/// The ideal rewards of a perfect attestation are also returned for each effective balance.
+/// Attestation rewards are applied at the end of the epoch following the attestations, so that
+/// epoch must have ended.
+pub async fn post_attestation_rewards<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let epoch = parse_epoch(
+        req.uri()
+            .path()
+            .trim_start_matches(ATTESTATION_REWARDS_PREFIX),
+    )?;
+    let response_builder = ResponseBuilder::new(&req)?;
+
+    let chunks = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
+    let ValidatorIndices(indices) = serde_json::from_slice(&chunks).map_err(|e| {
+        ApiError::BadRequest(format!(
+            "Unable to parse JSON into validator indices: {:?}",
+            e
+        ))
+    })?;
+
+    let current_epoch = beacon_chain.epoch()?;
+    if epoch + 2 > current_epoch {
+        return Err(ApiError::BadRequest(format!(
+            "The attestation rewards of epoch {} are not known until epoch {} has ended",
+            epoch,
+            epoch + 1
+        )));
+    }
+
+    // The rewards are computed from the last state of the following epoch, in which the
+    // attestations of `epoch` are the previous epoch attestations.
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+    let mut state = beacon_chain
+        .state_at_slot(
+            (epoch + 2).start_slot(slots_per_epoch) - 1,
+            StateSkipConfig::WithStateRoots,
+        )
+        .map_err(|e| {
+            ApiError::ServerError(format!("Unable to load state for epoch {}: {:?}", epoch, e))
+        })?;
+    let spec = &beacon_chain.spec;
+
+    state.build_committee_cache(RelativeEpoch::Previous, spec)?;
+    state.build_committee_cache(RelativeEpoch::Current, spec)?;
+
+    let mut validator_statuses = ValidatorStatuses::new(&state, spec)?;
+    validator_statuses.process_attestations(&state, spec)?;
+
+    // Rewards are applied after justification and finalization, such that the finality delay
+    // accounts for a checkpoint finalized by the epoch transition. The state is our own copy, so
+    // it can be advanced without affecting the chain.
+    process_justification_and_finalization(&mut state, &validator_statuses.total_balances)
+        .map_err(|e| ApiError::ServerError(format!("Unable to process justification: {:?}", e)))?;
+
+    let total_balances = &validator_statuses.total_balances;
+    let total_active_balance = total_balances.current_epoch();
+    let finality_delay = (state.previous_epoch() - state.finalized_checkpoint.epoch).as_u64();
+
+    let ideal_rewards = (1..=spec.max_effective_balance / spec.effective_balance_increment)
+        .map(|increments| {
+            let effective_balance = increments * spec.effective_balance_increment;
+            let status = ValidatorStatus {
+                is_active_in_previous_epoch: true,
+                current_epoch_effective_balance: effective_balance,
+                is_previous_epoch_attester: true,
+                is_previous_epoch_target_attester: true,
+                is_previous_epoch_head_attester: true,
+                inclusion_info: Some(InclusionInfo {
+                    delay: 1,
+                    proposer_index: 0,
+                }),
+                ..ValidatorStatus::default()
+            };
+            let base_reward = get_base_reward_from_effective_balance(
+                effective_balance,
+                total_active_balance,
+                spec,
+            )?;
+            let delta = attestation_delta::<T::EthSpec>(
+                &status,
+                total_balances,
+                base_reward,
+                finality_delay,
+                spec,
+            )?;
+
+            Ok(IdealAttestationRewards {
+                effective_balance,
+                head: net_reward(&delta.head_delta),
+                target: net_reward(&delta.target_delta),
+                source: net_reward(&delta.source_delta),
+                inclusion_delay: net_reward(&delta.inclusion_delay_delta),
+                inactivity: net_reward(&delta.inactivity_penalty_delta),
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let indices = if indices.is_empty() {
+        (0..state.validators.len() as u64).collect()
+    } else {
+        indices
+    };
+
+    let total_rewards = indices
+        .into_iter()
+        .map(|index| {
+            let status = validator_statuses
+                .statuses
+                .get(index as usize)
+                .ok_or_else(|| ApiError::NotFound(format!("Unknown validator {}", index)))?;
+            let base_reward = get_base_reward(&state, index as usize, total_active_balance, spec)?;
+            let delta = attestation_delta::<T::EthSpec>(
+                status,
+                total_balances,
+                base_reward,
+                finality_delay,
+                spec,
+            )?;
+
+            Ok(TotalAttestationRewards {
+                validator_index: index,
+                head: net_reward(&delta.head_delta),
+                target: net_reward(&delta.target_delta),
+                source: net_reward(&delta.source_delta),
+                inclusion_delay: net_reward(&delta.inclusion_delay_delta),
+                inactivity: net_reward(&delta.inactivity_penalty_delta),
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    response_builder.body_no_ssz(&GenericResponse::from(AttestationRewardsData {
+        ideal_rewards,
+        total_rewards,
+    }))
+}
+
+fn attestation_delta<E: EthSpec>(
+    status: &ValidatorStatus,
+    total_balances: &TotalBalances,
+    base_reward: u64,
+    finality_delay: u64,
+    spec: &ChainSpec,
+) -> Result<AttestationDelta, ApiError> {
+    get_attestation_delta::<E>(status, total_balances, base_reward, finality_delay, spec)
+        .map_err(|e| ApiError::ServerError(format!("Unable to compute rewards: {:?}", e)))
+}
+
+/// Returns the rewards of `delta` less its penalties.
+fn net_reward(delta: &Delta) -> i64 {
+    delta.rewards as i64 - delta.penalties as i64
+}
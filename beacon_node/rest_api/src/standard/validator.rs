@@ -0,0 +1,322 @@
+use crate::helpers::{block_root_at_slot, parse_epoch, parse_root};
+use crate::response_builder::ResponseBuilder;
+use crate::validator::get_state_for_epoch;
+use crate::{ApiError, ApiResult, NetworkChannel, UrlQuery};
+use beacon_chain::{BeaconChain, BeaconChainTypes, StateSkipConfig};
+use hyper::{Body, Request};
+use network::NetworkMessage;
+use rest_types::{
+    AttesterData, BeaconCommitteeSubscription, DutiesResponse, GenericResponse, LivenessData,
+    ProposerData, ValidatorIndices,
+};
+use std::sync::Arc;
+use types::{beacon_state::CommitteeCache, Epoch, EthSpec, Hash256, RelativeEpoch, Slot};
+
+const ATTESTER_DUTIES_PREFIX: &str = "/eth/v1/validator/duties/attester/";
+const PROPOSER_DUTIES_PREFIX: &str = "/eth/v1/validator/duties/proposer/";
+const LIVENESS_PREFIX: &str = "/eth/v1/validator/liveness/";
+
+/// HTTP handler to return the attestation duties of the validators whose indices are provided as
+/// a JSON array in the request body, for the epoch in the request path.
+///
+/// Duties are available up until the epoch following the current epoch. Validators without
+/// duties are omitted from the response.
+pub async fn post_attester_duties<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let epoch = parse_epoch(req.uri().path().trim_start_matches(ATTESTER_DUTIES_PREFIX))?;
+    let response_builder = ResponseBuilder::new(&req)?;
+
+    let chunks = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
+    let ValidatorIndices(indices) = serde_json::from_slice(&chunks).map_err(|e| {
+        ApiError::BadRequest(format!(
+            "Unable to parse JSON into validator indices: {:?}",
+            e
+        ))
+    })?;
+
+    let current_epoch = beacon_chain.epoch()?;
+    if epoch > current_epoch + 1 {
+        return Err(ApiError::BadRequest(format!(
+            "Attester duties are not known beyond the next epoch {}",
+            current_epoch + 1
+        )));
+    }
+
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+    // The attester shuffling for `epoch` is decided by the last block of `epoch - 2`.
+    let dependent_root = canonical_root_at_or_before(
+        &beacon_chain,
+        (epoch - 1)
+            .start_slot(slots_per_epoch)
+            .saturating_sub(1_u64),
+    )?;
+    let committee_cache = committee_cache_for_epoch(&beacon_chain, epoch)?;
+
+    let data = indices
+        .into_iter()
+        .filter_map(|index| {
+            let duty = committee_cache.get_attestation_duties(index as usize)?;
+            Some((index, duty))
+        })
+        .map(|(index, duty)| {
+            let pubkey = beacon_chain
+                .validator_pubkey(index as usize)?
+                .ok_or_else(|| ApiError::ServerError(format!("Unknown validator {}", index)))?;
+
+            Ok(AttesterData {
+                pubkey: pubkey.into(),
+                validator_index: index,
+                committee_index: duty.index,
+                committee_length: duty.committee_len as u64,
+                committees_at_slot: committee_cache.committees_per_slot(),
+                validator_committee_index: duty.committee_position as u64,
+                slot: duty.slot,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    response_builder.body_no_ssz(&DutiesResponse {
+        dependent_root,
+        data,
+    })
+}
+
+/// HTTP handler to return whether each of the validators whose indices are provided as a JSON
+/// array in the request body has been observed on the network during the epoch in the request
+/// path.
+///
+/// A validator is live if an unaggregated attestation or an aggregate it produced has been seen on
+/// gossip. Only the current and previous epochs are known.
+pub async fn post_liveness<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let epoch = parse_epoch(req.uri().path().trim_start_matches(LIVENESS_PREFIX))?;
+    let response_builder = ResponseBuilder::new(&req)?;
+
+    let chunks = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
+    let ValidatorIndices(indices) = serde_json::from_slice(&chunks).map_err(|e| {
+        ApiError::BadRequest(format!(
+            "Unable to parse JSON into validator indices: {:?}",
+            e
+        ))
+    })?;
+
+    let current_epoch = beacon_chain.epoch()?;
+    if epoch > current_epoch || epoch + 1 < current_epoch {
+        return Err(ApiError::BadRequest(format!(
+            "Liveness is only known for the current epoch {} and the previous epoch",
+            current_epoch
+        )));
+    }
+
+    let data = indices
+        .into_iter()
+        .map(|index| LivenessData {
+            index,
+            epoch,
+            is_live: beacon_chain
+                .observed_attesters
+                .index_seen_at_epoch(index as usize, epoch)
+                || beacon_chain
+                    .observed_aggregators
+                    .index_seen_at_epoch(index as usize, epoch),
+        })
+        .collect::<Vec<_>>();
+
+    response_builder.body_no_ssz(&GenericResponse::from(data))
+}
+
+/// HTTP handler to return the block proposers for all slots of the epoch in the request path.
+///
+/// The proposers of future epochs are not known, therefore the epoch may not be later than the
+/// current epoch.
+pub fn get_proposer_duties<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let epoch = parse_epoch(req.uri().path().trim_start_matches(PROPOSER_DUTIES_PREFIX))?;
+
+    let current_epoch = beacon_chain.epoch()?;
+    if epoch > current_epoch {
+        return Err(ApiError::BadRequest(format!(
+            "Proposer duties are not known beyond the current epoch {}",
+            current_epoch
+        )));
+    }
+
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+    // The proposer shuffling for `epoch` is decided by the last block of `epoch - 1`.
+    let dependent_root = canonical_root_at_or_before(
+        &beacon_chain,
+        epoch.start_slot(slots_per_epoch).saturating_sub(1_u64),
+    )?;
+
+    let mut state = beacon_chain
+        .state_at_slot(
+            epoch.start_slot(slots_per_epoch),
+            StateSkipConfig::WithoutStateRoots,
+        )
+        .map_err(|e| {
+            ApiError::ServerError(format!("Unable to load state for epoch {}: {:?}", epoch, e))
+        })?;
+    state
+        .build_committee_cache(RelativeEpoch::Current, &beacon_chain.spec)
+        .map_err(|e| ApiError::ServerError(format!("Unable to build committee cache: {:?}", e)))?;
+
+    let data = epoch
+        .slot_iter(slots_per_epoch)
+        .map(|slot| {
+            let index = state
+                .get_beacon_proposer_index(slot, &beacon_chain.spec)
+                .map_err(|e| {
+                    ApiError::ServerError(format!("Unable to get proposer index: {:?}", e))
+                })?;
+            let pubkey = state
+                .validators
+                .get(index)
+                .map(|validator| validator.pubkey.clone())
+                .ok_or_else(|| {
+                    ApiError::ServerError(format!("Invalid validator index: {:?}", index))
+                })?;
+
+            Ok(ProposerData {
+                pubkey,
+                validator_index: index as u64,
+                slot,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&DutiesResponse {
+        dependent_root,
+        data,
+    })
+}
+
+/// HTTP handler to return the aggregate attestation at the `slot` query parameter whose
+/// `AttestationData` has the `attestation_data_root` query parameter as its tree hash root.
+///
+/// The aggregate is obtained from the naive aggregation pool, which only retains recent slots.
+pub fn get_aggregate_attestation<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let query = UrlQuery::from_request(&req)?;
+    let slot = query.slot()?;
+    let (_key, value) = query.first_of(&["attestation_data_root"])?;
+    let attestation_data_root = parse_root(&value)?;
+
+    let attestation = beacon_chain
+        .get_aggregated_attestation_by_slot_and_root(slot, &attestation_data_root)
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "No matching aggregate attestation for slot {} and root {} is known",
+                slot, attestation_data_root
+            ))
+        })?;
+
+    ResponseBuilder::new(&req)?.body_data(&attestation)
+}
+
+/// HTTP handler to subscribe to the attestation subnets of upcoming attestation duties.
+///
+/// The network service subscribes to the subnet of each duty ahead of its slot and, if the
+/// validator is an aggregator, collects the unaggregated attestations of the subnet.
+pub async fn post_beacon_committee_subscriptions<T: BeaconChainTypes>(
+    req: Request<Body>,
+    network_chan: NetworkChannel<T::EthSpec>,
+) -> ApiResult {
+    let response_builder = ResponseBuilder::new(&req)?;
+
+    let chunks = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
+    let subscriptions = serde_json::from_slice::<Vec<BeaconCommitteeSubscription>>(&chunks)
+        .map_err(|e| {
+            ApiError::BadRequest(format!(
+                "Unable to parse JSON into BeaconCommitteeSubscriptions: {:?}",
+                e
+            ))
+        })?;
+
+    if let Some(subscription) = subscriptions
+        .iter()
+        .find(|subscription| subscription.committee_index >= subscription.committees_at_slot)
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Committee index {} is not less than committees_at_slot {}",
+            subscription.committee_index, subscription.committees_at_slot
+        )));
+    }
+
+    network_chan
+        .send(NetworkMessage::Subscribe {
+            subscriptions: subscriptions.into_iter().map(Into::into).collect(),
+        })
+        .map_err(|e| {
+            ApiError::ServerError(format!(
+                "Unable to send subscriptions to the network: {:?}",
+                e
+            ))
+        })?;
+
+    response_builder.body_no_ssz(&())
+}
+
+/// Returns the committee cache for `epoch`, from the shuffling cache if possible.
+///
+/// On a cache miss the committees are computed from a state and added to the shuffling cache.
+fn committee_cache_for_epoch<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    epoch: Epoch,
+) -> Result<CommitteeCache, ApiError> {
+    let epoch_start_slot = epoch.start_slot(T::EthSpec::slots_per_epoch());
+
+    // The shuffling cache is keyed by the root of the first block of the epoch, which is only
+    // known once the chain has reached the epoch.
+    if epoch_start_slot <= beacon_chain.head_info()?.slot {
+        let target_root = canonical_root_at_or_before(beacon_chain, epoch_start_slot)?;
+        if let Some(committee_cache) =
+            beacon_chain.cached_committee_shuffling(epoch, target_root)?
+        {
+            return Ok(committee_cache);
+        }
+    }
+
+    let mut state = get_state_for_epoch(beacon_chain, epoch, StateSkipConfig::WithoutStateRoots)?;
+    let relative_epoch = RelativeEpoch::from_epoch(state.current_epoch(), epoch)
+        .map_err(|_| ApiError::ServerError(String::from("Loaded state is in the wrong epoch")))?;
+    state
+        .build_committee_cache(relative_epoch, &beacon_chain.spec)
+        .map_err(|e| ApiError::ServerError(format!("Unable to build committee cache: {:?}", e)))?;
+    beacon_chain.cache_committee_shuffling(&state, relative_epoch);
+
+    state
+        .committee_cache(relative_epoch)
+        .map(Clone::clone)
+        .map_err(|e| ApiError::ServerError(format!("Unable to get committee cache: {:?}", e)))
+}
+
+/// Returns the root of the block at `slot` in the canonical chain or, if `slot` was skipped, the
+/// root of the latest block prior to it. Slots later than the head resolve to the head block.
+fn canonical_root_at_or_before<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    slot: Slot,
+) -> Result<Hash256, ApiError> {
+    let head_info = beacon_chain.head_info()?;
+
+    if slot >= head_info.slot {
+        Ok(head_info.block_root)
+    } else {
+        block_root_at_slot(beacon_chain, slot)?.ok_or_else(|| {
+            ApiError::ServerError(format!("Unable to find the block root at slot {}", slot))
+        })
+    }
+}
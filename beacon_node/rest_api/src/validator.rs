@@ -1,4 +1,6 @@
-use crate::helpers::{check_content_type_for_json, publish_beacon_block_to_network};
+use crate::helpers::{
+    await_publish_result, check_content_type_for_json, publish_beacon_block_to_network,
+};
 use crate::response_builder::ResponseBuilder;
 use crate::{ApiError, ApiResult, NetworkChannel, UrlQuery};
 use beacon_chain::{
@@ -7,12 +9,14 @@ use beacon_chain::{
 };
 use bls::PublicKeyBytes;
 use eth2_libp2p::PubsubMessage;
+use futures::future::join_all;
 use hyper::{Body, Request};
-use network::NetworkMessage;
+use network::{NetworkMessage, PublishResultReceiver};
 use rayon::prelude::*;
 use rest_types::{ValidatorDutiesRequest, ValidatorDutyBytes, ValidatorSubscription};
 use slog::{error, info, trace, warn, Logger};
 use std::sync::Arc;
+use tokio::sync::oneshot;
 use types::beacon_state::EthSpec;
 use types::{
     Attestation, AttestationData, BeaconState, Epoch, RelativeEpoch, SelectionProof,
@@ -41,6 +45,12 @@ pub async fn post_validator_duties<T: BeaconChainTypes>(
             ))
         })
         .and_then(|bulk_request| {
+            let mut validator_monitor = beacon_chain.validator_monitor.write();
+            for pubkey in &bulk_request.pubkeys {
+                validator_monitor.auto_register_local_validator(pubkey);
+            }
+            drop(validator_monitor);
+
             return_validator_duties(
                 beacon_chain,
                 bulk_request.epoch,
@@ -175,6 +185,8 @@ fn return_validator_duties<T: BeaconChainTypes>(
     state
         .build_committee_cache(relative_epoch, &beacon_chain.spec)
         .map_err(|e| ApiError::ServerError(format!("Unable to build committee cache: {:?}", e)))?;
+    // share the shuffling with attestation verification
+    beacon_chain.cache_committee_shuffling(&state, relative_epoch);
     state
         .update_pubkey_cache()
         .map_err(|e| ApiError::ServerError(format!("Unable to build pubkey cache: {:?}", e)))?;
@@ -301,10 +313,10 @@ pub async fn publish_beacon_block<T: BeaconChainTypes>(
         .await
         .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
 
-    serde_json::from_slice(&chunks).map_err(|e| {
+    let publish_result = serde_json::from_slice(&chunks).map_err(|e| {
                     ApiError::BadRequest(format!("Unable to parse JSON into SignedBeaconBlock: {:?}", e))
                 })
-            .and_then(move |block: SignedBeaconBlock<T::EthSpec>| {
+            .and_then(|block: SignedBeaconBlock<T::EthSpec>| {
                 let slot = block.slot();
                 match beacon_chain.process_block(block.clone()) {
                     Ok(block_root) => {
@@ -316,7 +328,8 @@ pub async fn publish_beacon_block<T: BeaconChainTypes>(
                             "block_slot" => slot,
                         );
 
-                        publish_beacon_block_to_network::<T>(network_chan, block)?;
+                        let publish_result =
+                            publish_beacon_block_to_network::<T>(network_chan, block)?;
 
                         // Run the fork choice algorithm and enshrine a new canonical head, if
                         // found.
@@ -348,7 +361,7 @@ pub async fn publish_beacon_block<T: BeaconChainTypes>(
                             }
                         }
 
-                        Ok(())
+                        Ok(publish_result)
                     }
                     Err(BlockError::BeaconChainError(e)) => {
                         error!(
@@ -375,8 +388,10 @@ pub async fn publish_beacon_block<T: BeaconChainTypes>(
                         )))
                     }
                 }
-        })
-        .and_then(|_| response_builder?.body_no_ssz(&()))
+        })?;
+
+    await_publish_result(publish_result, "block", &log).await?;
+    response_builder?.body_no_ssz(&())
 }
 
 /// HTTP Handler to produce a new Attestation from the current state, ready to be signed by a validator.
@@ -435,7 +450,7 @@ pub async fn publish_attestations<T: BeaconChainTypes>(
         .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
 
     let chunks = chunk.iter().cloned().collect::<Vec<u8>>();
-    serde_json::from_slice(&chunks.as_slice())
+    let publish_results: Vec<_> = serde_json::from_slice(&chunks.as_slice())
         .map_err(|e| {
             ApiError::BadRequest(format!(
                 "Unable to deserialize JSON into a list of attestations: {:?}",
@@ -443,7 +458,7 @@ pub async fn publish_attestations<T: BeaconChainTypes>(
             ))
         })
         // Process all of the aggregates _without_ exiting early if one fails.
-        .map(move |attestations: Vec<Attestation<T::EthSpec>>| {
+        .map(|attestations: Vec<Attestation<T::EthSpec>>| {
             attestations
                 .into_par_iter()
                 .enumerate()
@@ -461,24 +476,30 @@ pub async fn publish_attestations<T: BeaconChainTypes>(
         // Iterate through all the results and return on the first `Err`.
         //
         // Note: this will only provide info about the _first_ failure, not all failures.
-        .and_then(|processing_results| processing_results.into_iter().try_for_each(|result| result))
-        .and_then(|_| response_builder?.body_no_ssz(&()))
+        .and_then(|processing_results| processing_results.into_iter().collect())?;
+
+    await_attestation_publish_results(publish_results, "unaggregated attestation", &log).await?;
+    response_builder?.body_no_ssz(&())
 }
 
 /// Processes an unaggregrated attestation that was included in a list of attestations with the
-/// index `i`.
+/// index `i`, returning a receiver of the outcome of publishing it.
 fn process_unaggregated_attestation<T: BeaconChainTypes>(
     beacon_chain: &BeaconChain<T>,
     network_chan: NetworkChannel<T::EthSpec>,
     attestation: Attestation<T::EthSpec>,
     i: usize,
     log: &Logger,
-) -> Result<(), ApiError> {
+) -> Result<Option<PublishResultReceiver>, ApiError> {
     let data = &attestation.data.clone();
 
+    let subnet_id = attestation
+        .subnet_id(&beacon_chain.spec)
+        .map_err(|e| ApiError::ServerError(format!("Unable to get subnet id: {:?}", e)))?;
+
     // Verify that the attestation is valid to included on the gossip network.
     let verified_attestation = beacon_chain
-        .verify_unaggregated_attestation_for_gossip(attestation.clone())
+        .verify_unaggregated_attestation_for_gossip(attestation.clone(), subnet_id)
         .map_err(|e| {
             handle_attestation_error(
                 e,
@@ -489,13 +510,13 @@ fn process_unaggregated_attestation<T: BeaconChainTypes>(
         })?;
 
     // Publish the attestation to the network
+    let (result, publish_result) = oneshot::channel();
     if let Err(e) = network_chan.send(NetworkMessage::Publish {
         messages: vec![PubsubMessage::Attestation(Box::new((
-            attestation
-                .subnet_id(&beacon_chain.spec)
-                .map_err(|e| ApiError::ServerError(format!("Unable to get subnet id: {:?}", e)))?,
+            subnet_id,
             attestation,
         )))],
+        result: Some(result),
     }) {
         return Err(ApiError::ServerError(format!(
             "Unable to send unaggregated attestation {} to network: {:?}",
@@ -531,7 +552,7 @@ fn process_unaggregated_attestation<T: BeaconChainTypes>(
             )
         })?;
 
-    Ok(())
+    Ok(Some(publish_result))
 }
 
 /// HTTP Handler to publish an Attestation, which has been signed by a validator.
@@ -548,7 +569,7 @@ pub async fn publish_aggregate_and_proofs<T: BeaconChainTypes>(
         .await
         .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
     let chunks = chunk.iter().cloned().collect::<Vec<u8>>();
-    serde_json::from_slice(&chunks.as_slice())
+    let publish_results: Vec<_> = serde_json::from_slice(&chunks.as_slice())
         .map_err(|e| {
             ApiError::BadRequest(format!(
                 "Unable to deserialize JSON into a list of SignedAggregateAndProof: {:?}",
@@ -557,7 +578,7 @@ pub async fn publish_aggregate_and_proofs<T: BeaconChainTypes>(
         })
         // Process all of the aggregates _without_ exiting early if one fails.
         .map(
-            move |signed_aggregates: Vec<SignedAggregateAndProof<T::EthSpec>>| {
+            |signed_aggregates: Vec<SignedAggregateAndProof<T::EthSpec>>| {
                 signed_aggregates
                     .into_par_iter()
                     .enumerate()
@@ -576,19 +597,21 @@ pub async fn publish_aggregate_and_proofs<T: BeaconChainTypes>(
         // Iterate through all the results and return on the first `Err`.
         //
         // Note: this will only provide info about the _first_ failure, not all failures.
-        .and_then(|processing_results| processing_results.into_iter().try_for_each(|result| result))
-        .and_then(|_| response_builder?.body_no_ssz(&()))
+        .and_then(|processing_results| processing_results.into_iter().collect())?;
+
+    await_attestation_publish_results(publish_results, "aggregated attestation", &log).await?;
+    response_builder?.body_no_ssz(&())
 }
 
 /// Processes an aggregrated attestation that was included in a list of attestations with the index
-/// `i`.
+/// `i`, returning a receiver of the outcome of publishing it, unless it was already known.
 fn process_aggregated_attestation<T: BeaconChainTypes>(
     beacon_chain: &BeaconChain<T>,
     network_chan: NetworkChannel<T::EthSpec>,
     signed_aggregate: SignedAggregateAndProof<T::EthSpec>,
     i: usize,
     log: &Logger,
-) -> Result<(), ApiError> {
+) -> Result<Option<PublishResultReceiver>, ApiError> {
     let data = &signed_aggregate.message.aggregate.data.clone();
 
     // Verify that the attestation is valid to be included on the gossip network.
@@ -610,7 +633,7 @@ fn process_aggregated_attestation<T: BeaconChainTypes>(
 
                 // Exit early with success for a known attestation, there's no need to re-process
                 // an aggregate we already know.
-                return Ok(());
+                return Ok(None);
             }
             /*
              * It's worth noting that we don't check for `Error::AggregatorAlreadyKnown` since (at
@@ -631,10 +654,12 @@ fn process_aggregated_attestation<T: BeaconChainTypes>(
         };
 
     // Publish the attestation to the network
+    let (result, publish_result) = oneshot::channel();
     if let Err(e) = network_chan.send(NetworkMessage::Publish {
         messages: vec![PubsubMessage::AggregateAndProofAttestation(Box::new(
             signed_aggregate,
         ))],
+        result: Some(result),
     }) {
         return Err(ApiError::ServerError(format!(
             "Unable to send aggregated attestation {} to network: {:?}",
@@ -670,7 +695,25 @@ fn process_aggregated_attestation<T: BeaconChainTypes>(
             )
         })?;
 
-    Ok(())
+    Ok(Some(publish_result))
+}
+
+/// Waits for the outcome of publishing each of the attestations of a request, which are described
+/// by `kind` and their index in the request, returning the first failure.
+async fn await_attestation_publish_results(
+    publish_results: Vec<Option<PublishResultReceiver>>,
+    kind: &str,
+    log: &Logger,
+) -> Result<(), ApiError> {
+    let publish_results = publish_results
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, publish_result)| Some((i, publish_result?)))
+        .map(|(i, publish_result)| async move {
+            await_publish_result(publish_result, &format!("{} {}", kind, i), log).await
+        });
+
+    join_all(publish_results).await.into_iter().collect()
 }
 
 /// Common handler for `AttnError` during attestation verification.
@@ -4,6 +4,7 @@
 extern crate assert_matches;
 
 use beacon_chain::{BeaconChain, BeaconChainTypes, StateSkipConfig};
+use eth2_libp2p::types::SyncStatus;
 use node_test_rig::{
     environment::{Environment, EnvironmentBuilder},
     testing_client_config, ClientConfig, ClientGenesis, LocalBeaconNode,
@@ -11,17 +12,25 @@ use node_test_rig::{
 use remote_beacon_node::{
     Committee, HeadBeaconBlock, PersistedOperationPool, PublishStatus, ValidatorResponse,
 };
-use rest_types::ValidatorDutyBytes;
+use rest_types::{
+    AttesterData, ChainHeadData, CommitteeData, DutiesResponse, FinalityCheckpointsData, ForkData,
+    GenericResponse, IdentityData, PeerData, ProposerData, RootData, SyncingData,
+    ValidatorBalanceData, ValidatorData, ValidatorDutyBytes,
+};
+use serde_json::{json, Value};
+use ssz::Decode;
 use std::convert::TryInto;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tree_hash::TreeHash;
 use types::{
     test_utils::{
         build_double_vote_attester_slashing, build_proposer_slashing,
         generate_deterministic_keypair, AttesterSlashingTestTask, ProposerSlashingTestTask,
     },
-    BeaconBlock, BeaconState, ChainSpec, Domain, Epoch, EthSpec, MinimalEthSpec, PublicKey,
-    RelativeEpoch, Signature, SignedAggregateAndProof, SignedBeaconBlock, SignedRoot, Slot,
-    Validator,
+    Attestation, BeaconBlock, BeaconState, ChainSpec, Domain, Epoch, EthSpec, MinimalEthSpec,
+    PublicKey, RelativeEpoch, Signature, SignedAggregateAndProof, SignedBeaconBlock, SignedRoot,
+    Slot, Validator,
 };
 use version;
 
@@ -44,6 +53,25 @@ fn build_node<E: EthSpec>(env: &mut Environment<E>, config: ClientConfig) -> Loc
         .expect("should block until node created")
 }
 
+/// Builds a node with 8 interop validators whose genesis was `epochs` epochs ago, such that no
+/// blocks or attestations have been produced since genesis.
+fn build_node_with_empty_epochs(env: &mut Environment<E>, epochs: u64) -> LocalBeaconNode<E> {
+    let spec = &E::default_spec();
+    let epoch_seconds = spec.milliseconds_per_slot / 1000 * E::slots_per_epoch();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("should get system time")
+        .as_secs();
+
+    let mut config = testing_client_config();
+    config.genesis = ClientGenesis::Interop {
+        validator_count: 8,
+        genesis_time: now - epochs * epoch_seconds,
+    };
+
+    build_node(env, config)
+}
+
 /// Returns the randao reveal for the given slot (assuming the given `beacon_chain` uses
 /// deterministic keypairs).
 fn get_randao_reveal<T: BeaconChainTypes>(
@@ -236,6 +264,19 @@ fn validator_produce_attestation() {
         )
         .expect("should fetch aggregated attestation from http api");
 
+    let standard_aggregated_attestation: GenericResponse<Attestation<E>> = env
+        .runtime()
+        .block_on(remote_node.http.standard_get(&format!(
+            "eth/v1/validator/aggregate_attestation?slot={}&attestation_data_root={:?}",
+            attestation.data.slot,
+            attestation.data.tree_hash_root()
+        )))
+        .expect("should fetch aggregated attestation from the standard api");
+    assert_eq!(
+        standard_aggregated_attestation.data, aggregated_attestation,
+        "the standard api should return the same aggregate"
+    );
+
     let signed_aggregate_and_proof = SignedAggregateAndProof::from_aggregate(
         validator_index as u64,
         aggregated_attestation,
@@ -253,13 +294,21 @@ fn validator_produce_attestation() {
             remote_node
                 .http
                 .validator()
-                .publish_aggregate_and_proof(vec![signed_aggregate_and_proof]),
+                .publish_aggregate_and_proof(vec![signed_aggregate_and_proof.clone()]),
         )
         .expect("should publish aggregate and proof");
     assert!(
         publish_status.is_valid(),
         "the signed aggregate and proof should be valid"
     );
+
+    // Publishing the same aggregate via the standard api succeeds, since it is already known.
+    env.runtime()
+        .block_on(remote_node.http.standard_post::<_, ()>(
+            "eth/v1/validator/aggregate_and_proofs",
+            vec![signed_aggregate_and_proof],
+        ))
+        .expect("should publish aggregate and proof via the standard api");
 }
 
 #[test]
@@ -404,6 +453,321 @@ fn check_duties<T: BeaconChainTypes>(
     assert_eq!(all_proposer_slots, all_slots);
 }
 
+#[test]
+fn validator_liveness() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+    let epoch = beacon_chain.epoch().expect("should get epoch");
+
+    let attestation = beacon_chain
+        .produce_unaggregated_attestation(beacon_chain.slot().expect("should get slot"), 0)
+        .expect("should produce attestation");
+    beacon_chain
+        .observed_attesters
+        .observe_validator(&attestation, 1)
+        .expect("should observe attester");
+
+    let liveness = env
+        .runtime()
+        .block_on(remote_node.http.validator().get_liveness(epoch, &[0, 1]))
+        .expect("should fetch liveness from http api");
+
+    assert_eq!(liveness.len(), 2, "should return liveness for each index");
+    assert_eq!(liveness[0].index, 0);
+    assert!(
+        !liveness[0].is_live,
+        "unobserved validator should not be live"
+    );
+    assert_eq!(liveness[1].index, 1);
+    assert!(liveness[1].is_live, "observed validator should be live");
+
+    env.runtime()
+        .block_on(remote_node.http.validator().get_liveness(epoch + 1, &[0]))
+        .expect_err("should not return liveness for a future epoch");
+}
+
+#[test]
+fn validator_inclusion() {
+    let mut env = build_env();
+    let node = build_node_with_empty_epochs(&mut env, 3);
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let inclusion = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .consensus()
+                .get_validator_inclusion(Epoch::new(1), 0),
+        )
+        .expect("should fetch inclusion from http api");
+
+    assert_eq!(inclusion.epoch, Epoch::new(1));
+    assert_eq!(inclusion.validator_index, 0);
+    assert!(!inclusion.is_included, "no attestations were produced");
+    assert_eq!(inclusion.inclusion_distance, None);
+    assert!(!inclusion.is_correct_target);
+    assert!(!inclusion.is_correct_head);
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .consensus()
+                .get_validator_inclusion(Epoch::new(1), 8),
+        )
+        .expect_err("should not return inclusion for an unknown validator");
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .consensus()
+                .get_validator_inclusion(Epoch::new(3), 0),
+        )
+        .expect_err("should not return inclusion for the current epoch");
+}
+
+#[test]
+fn attestation_rewards() {
+    let mut env = build_env();
+    let spec = &E::default_spec();
+    let node = build_node_with_empty_epochs(&mut env, 3);
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let rewards = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .get_attestation_rewards(Epoch::new(1), &[0, 1]),
+        )
+        .expect("should fetch rewards from http api");
+
+    assert_eq!(
+        rewards.ideal_rewards.len() as u64,
+        spec.max_effective_balance / spec.effective_balance_increment,
+        "should return ideal rewards for each effective balance"
+    );
+    let ideal = rewards
+        .ideal_rewards
+        .last()
+        .expect("should have ideal rewards");
+    assert_eq!(ideal.effective_balance, spec.max_effective_balance);
+    assert!(ideal.inclusion_delay > 0);
+
+    assert_eq!(rewards.total_rewards.len(), 2);
+    for (index, reward) in rewards.total_rewards.iter().enumerate() {
+        assert_eq!(reward.validator_index, index as u64);
+        assert!(reward.source < 0, "no attestations were produced");
+        assert_eq!(reward.target, reward.source);
+        assert_eq!(reward.head, reward.source);
+        assert_eq!(reward.inclusion_delay, 0);
+        assert_eq!(reward.inactivity, 0);
+    }
+
+    let all_rewards = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .get_attestation_rewards(Epoch::new(1), &[]),
+        )
+        .expect("should fetch rewards of all validators from http api");
+    assert_eq!(all_rewards.total_rewards.len(), 8);
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .get_attestation_rewards(Epoch::new(1), &[8]),
+        )
+        .expect_err("should not return rewards for an unknown validator");
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .get_attestation_rewards(Epoch::new(2), &[0]),
+        )
+        .expect_err("should not return rewards before the following epoch has ended");
+}
+
+#[test]
+fn attestation_rewards_match_balance_changes() {
+    let mut env = build_env();
+    let slots_per_epoch = E::slots_per_epoch();
+
+    // Start the chain long enough ago for the missing attestations to incur inactivity
+    // penalties.
+    let node = build_node_with_empty_epochs(&mut env, 8);
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let epoch = Epoch::new(5);
+    let rewards = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .get_attestation_rewards(epoch, &[]),
+        )
+        .expect("should fetch rewards from http api");
+
+    // The rewards of `epoch` are applied by the transition into `epoch + 2`.
+    let transition_slot = (epoch + 2).start_slot(slots_per_epoch);
+    let pre_state = beacon_chain
+        .state_at_slot(transition_slot - 1, StateSkipConfig::WithStateRoots)
+        .expect("should get state before the epoch transition");
+    let post_state = beacon_chain
+        .state_at_slot(transition_slot, StateSkipConfig::WithStateRoots)
+        .expect("should get state after the epoch transition");
+
+    assert_eq!(rewards.total_rewards.len(), 8);
+    for reward in rewards.total_rewards {
+        let index = reward.validator_index as usize;
+        assert!(reward.inactivity < 0, "should be an inactivity leak");
+        assert_eq!(
+            post_state.balances[index] as i64 - pre_state.balances[index] as i64,
+            reward.head
+                + reward.target
+                + reward.source
+                + reward.inclusion_delay
+                + reward.inactivity,
+            "rewards should match the balance change of validator {}",
+            index
+        );
+    }
+}
+
+#[test]
+fn block_rewards() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let mut config = testing_client_config();
+    config.genesis = ClientGenesis::Interop {
+        validator_count: 8,
+        genesis_time: 13_371_337,
+    };
+
+    let node = build_node(&mut env, config);
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+    let state = beacon_chain.head().expect("should get head").beacon_state;
+
+    // Slash a validator other than the proposer of the block which includes the slashing.
+    let slot = Slot::new(1);
+    let proposer_index = beacon_chain
+        .block_proposer(slot)
+        .expect("should get proposer index");
+    let slashed_index = (proposer_index + 1) % 8;
+    let proposer_slashing = build_proposer_slashing::<E>(
+        ProposerSlashingTestTask::Valid,
+        slashed_index as u64,
+        &generate_deterministic_keypair(slashed_index).sk,
+        &state.fork,
+        state.genesis_validators_root,
+        spec,
+    );
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .proposer_slashing(proposer_slashing),
+        )
+        .expect("should submit proposer slashing");
+
+    let randao_reveal = get_randao_reveal(beacon_chain.clone(), slot, spec);
+    let block = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .produce_block(slot, randao_reveal),
+        )
+        .expect("should fetch block from http api");
+    assert_eq!(block.body.proposer_slashings.len(), 1);
+
+    let signed_block = sign_block(beacon_chain.clone(), block, spec);
+    let block_root = signed_block.canonical_root();
+    env.runtime()
+        .block_on(remote_node.http.validator().publish_block(signed_block))
+        .expect("should publish block");
+
+    let rewards = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .consensus()
+                .get_block_rewards(Slot::new(1), Slot::new(2)),
+        )
+        .expect("should fetch block rewards from http api");
+
+    assert_eq!(rewards.len(), 1, "should only return the published block");
+    let reward = &rewards[0];
+    assert_eq!(reward.block_root, block_root);
+    assert_eq!(reward.slot, slot);
+    assert_eq!(reward.proposer_index, proposer_index as u64);
+    assert_eq!(reward.attestations, 0, "no attestations were produced");
+    assert_eq!(
+        reward.proposer_slashings,
+        spec.max_effective_balance / spec.whistleblower_reward_quotient
+    );
+    assert_eq!(reward.attester_slashings, 0);
+    assert_eq!(reward.total, reward.proposer_slashings);
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .consensus()
+                .get_block_rewards(Slot::new(0), Slot::new(1)),
+        )
+        .expect_err("should not return rewards for the genesis block");
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .consensus()
+                .get_block_rewards(Slot::new(2), Slot::new(1)),
+        )
+        .expect_err("should not return rewards for an empty range");
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .consensus()
+                .get_block_rewards(Slot::new(1), Slot::new(1 + 8_192)),
+        )
+        .expect_err("should not return rewards for an overly long range");
+}
+
 #[test]
 fn validator_block_post() {
     let mut env = build_env();
@@ -942,6 +1306,39 @@ fn get_fork_choice() {
     );
 }
 
+#[test]
+fn get_fork_choice_snapshot() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+
+    let snapshot = env
+        .runtime()
+        .block_on(remote_node.http.advanced().get_fork_choice_snapshot())
+        .expect("should not error when getting fork choice snapshot");
+
+    assert_eq!(
+        snapshot,
+        beacon_chain.fork_choice.snapshot(),
+        "result should be as expected"
+    );
+
+    let (head, _) = snapshot.replay(&[]).expect("should replay snapshot");
+    assert_eq!(
+        head,
+        beacon_chain
+            .head_info()
+            .expect("should get head")
+            .block_root,
+        "the replayed head should be the head of the chain"
+    );
+}
+
 #[test]
 fn get_operation_pool() {
     let mut env = build_env();
@@ -1267,3 +1664,610 @@ fn get_health() {
         .block_on(remote_node.http.node().get_health())
         .unwrap();
 }
+
+#[test]
+fn lighthouse_syncing() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let syncing: Value = env
+        .runtime()
+        .block_on(remote_node.http.standard_get("lighthouse/syncing"))
+        .expect("should fetch syncing status");
+
+    assert!(syncing.get("state").is_some(), "should have the sync state");
+    assert_eq!(
+        syncing["chains"],
+        json!([]),
+        "should not sync any chains without peers"
+    );
+    serde_json::from_value::<SyncStatus>(syncing).expect("should be a sync status");
+}
+
+#[test]
+fn standard_beacon_state() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+    let head = chain.head().expect("should get head");
+    let state = &head.beacon_state;
+
+    let root: GenericResponse<RootData> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/root"),
+        )
+        .expect("should fetch state root");
+    assert_eq!(root.data.root, head.beacon_state_root, "state root");
+
+    let fork: GenericResponse<ForkData> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/fork"),
+        )
+        .expect("should fetch fork");
+    assert_eq!(fork.data, ForkData(state.fork.clone()), "fork");
+
+    let checkpoints: GenericResponse<FinalityCheckpointsData> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/finality_checkpoints"),
+        )
+        .expect("should fetch finality checkpoints");
+    assert_eq!(
+        checkpoints.data,
+        FinalityCheckpointsData {
+            previous_justified: state.previous_justified_checkpoint.clone(),
+            current_justified: state.current_justified_checkpoint.clone(),
+            finalized: state.finalized_checkpoint.clone(),
+        },
+        "finality checkpoints"
+    );
+
+    let validators: GenericResponse<Vec<ValidatorData>> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/validators"),
+        )
+        .expect("should fetch validators");
+    assert_eq!(
+        validators.data.len(),
+        state.validators.len(),
+        "should return all validators"
+    );
+    for (i, data) in validators.data.iter().enumerate() {
+        assert_eq!(data.index, i as u64, "validator index");
+        assert_eq!(data.balance, state.balances[i], "validator balance");
+        assert_eq!(data.validator, state.validators[i], "validator");
+    }
+
+    let validator: GenericResponse<ValidatorData> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/validators/1"),
+        )
+        .expect("should fetch validator");
+    assert_eq!(validator.data, validators.data[1], "validator by index");
+
+    let balances: GenericResponse<Vec<ValidatorBalanceData>> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/validator_balances?id=0,1"),
+        )
+        .expect("should fetch validator balances");
+    assert_eq!(
+        balances.data,
+        vec![
+            ValidatorBalanceData {
+                index: 0,
+                balance: state.balances[0],
+            },
+            ValidatorBalanceData {
+                index: 1,
+                balance: state.balances[1],
+            },
+        ],
+        "validator balances"
+    );
+
+    let committees: GenericResponse<Vec<CommitteeData>> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/committees?epoch=0"),
+        )
+        .expect("should fetch committees");
+    let expected = state
+        .get_beacon_committees_at_epoch(RelativeEpoch::Current)
+        .expect("should get committees")
+        .iter()
+        .map(|c| CommitteeData {
+            index: c.index,
+            slot: c.slot,
+            validators: c.committee.iter().map(|&i| i as u64).collect(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(committees.data, expected, "committees");
+}
+
+#[test]
+fn standard_api_quotes_integers() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+    let state = chain.head().expect("should get head").beacon_state;
+
+    let validator: Value = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/validators/0"),
+        )
+        .expect("should fetch validator");
+    assert_eq!(validator["data"]["index"], json!("0"));
+    assert_eq!(
+        validator["data"]["balance"],
+        json!(state.balances[0].to_string())
+    );
+    assert_eq!(
+        validator["data"]["validator"]["effective_balance"],
+        json!(state.validators[0].effective_balance.to_string())
+    );
+    assert_eq!(
+        validator["data"]["validator"]["activation_epoch"],
+        json!("0")
+    );
+
+    let fork: Value = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/fork"),
+        )
+        .expect("should fetch fork");
+    assert_eq!(fork["data"]["epoch"], json!("0"));
+
+    let checkpoints: Value = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/finality_checkpoints"),
+        )
+        .expect("should fetch finality checkpoints");
+    assert_eq!(checkpoints["data"]["finalized"]["epoch"], json!("0"));
+
+    let committees: Value = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/states/head/committees?epoch=0"),
+        )
+        .expect("should fetch committees");
+    assert_eq!(committees["data"][0]["slot"], json!("0"));
+    assert!(
+        committees["data"][0]["validators"]
+            .as_array()
+            .expect("should have validators")
+            .iter()
+            .all(Value::is_string),
+        "validator indices should be strings"
+    );
+}
+
+#[test]
+fn standard_beacon_block() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+
+    let db_block = chain
+        .block_at_slot(Slot::new(0))
+        .expect("should find block")
+        .expect("block should not be none");
+
+    let block: GenericResponse<SignedBeaconBlock<E>> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/blocks/genesis"),
+        )
+        .expect("should fetch block");
+    assert_eq!(block.data, db_block, "genesis block");
+
+    let root: GenericResponse<RootData> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/beacon/blocks/head/root"),
+        )
+        .expect("should fetch block root");
+    assert_eq!(root.data.root, db_block.canonical_root(), "block root");
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get::<Value>("eth/v1/beacon/blocks/0x0000/root"),
+        )
+        .expect_err("should not parse an invalid block id");
+
+    let ssz_block = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get_ssz("eth/v1/beacon/blocks/genesis"),
+        )
+        .expect("should fetch block as ssz");
+    assert_eq!(
+        SignedBeaconBlock::<E>::from_ssz_bytes(&ssz_block),
+        Ok(db_block),
+        "ssz genesis block"
+    );
+
+    let unsupported = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get_ssz("eth/v1/beacon/states/head/fork"),
+        )
+        .expect_err("the fork should not be served as ssz");
+    assert_matches!(
+        unsupported,
+        remote_beacon_node::Error::DidNotSucceed { status, .. } => {
+            assert_eq!(status, http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+    );
+}
+
+#[test]
+fn standard_block_post() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let mut config = testing_client_config();
+    config.genesis = ClientGenesis::Interop {
+        validator_count: 8,
+        genesis_time: 13_371_337,
+    };
+
+    let node = build_node(&mut env, config);
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let slot = Slot::new(1);
+    let randao_reveal = get_randao_reveal(beacon_chain.clone(), slot, spec);
+
+    let block = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .produce_block(slot, randao_reveal),
+        )
+        .expect("should fetch block from http api");
+
+    if cfg!(not(feature = "fake_crypto")) {
+        let empty_sig_block = SignedBeaconBlock {
+            message: block.clone(),
+            signature: Signature::empty_signature(),
+        };
+        env.runtime()
+            .block_on(
+                remote_node
+                    .http
+                    .standard_post::<_, ()>("eth/v1/beacon/blocks", empty_sig_block),
+            )
+            .expect_err("the unsigned block should not be published");
+    }
+
+    let signed_block = sign_block(beacon_chain.clone(), block, spec);
+    let block_root = signed_block.canonical_root();
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_post::<_, ()>("eth/v1/beacon/blocks", signed_block),
+        )
+        .expect("should publish block");
+
+    assert_eq!(
+        beacon_chain
+            .head_info()
+            .expect("should get head")
+            .block_root,
+        block_root,
+        "the published block should become the head block"
+    );
+}
+
+#[test]
+fn standard_validator_duties() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+    let state = chain.head().expect("should get head").beacon_state;
+    let epoch = Epoch::new(0);
+
+    let attester_duties: DutiesResponse<Vec<AttesterData>> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_post("eth/v1/validator/duties/attester/0", vec!["0", "1"]),
+        )
+        .expect("should fetch attester duties");
+    assert_eq!(
+        attester_duties.dependent_root, chain.genesis_block_root,
+        "attester dependent root"
+    );
+    assert_eq!(attester_duties.data.len(), 2, "should return each duty");
+    for duty in &attester_duties.data {
+        let index = duty.validator_index as usize;
+        let expected = state
+            .get_attestation_duties(index, RelativeEpoch::Current)
+            .expect("should have attestation duties cache")
+            .expect("should have attestation duties");
+
+        assert_eq!(duty.pubkey, state.validators[index].pubkey, "pubkey");
+        assert_eq!(duty.slot, expected.slot, "slot");
+        assert_eq!(duty.committee_index, expected.index, "committee index");
+        assert_eq!(
+            duty.validator_committee_index, expected.committee_position as u64,
+            "committee position"
+        );
+        assert_eq!(
+            duty.committee_length, expected.committee_len as u64,
+            "committee length"
+        );
+    }
+
+    let proposer_duties: DutiesResponse<Vec<ProposerData>> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v1/validator/duties/proposer/0"),
+        )
+        .expect("should fetch proposer duties");
+    assert_eq!(
+        proposer_duties
+            .data
+            .iter()
+            .map(|duty| duty.slot)
+            .collect::<Vec<_>>(),
+        epoch.slot_iter(E::slots_per_epoch()).collect::<Vec<_>>(),
+        "should return a proposer for each slot"
+    );
+    for duty in &proposer_duties.data {
+        assert_eq!(
+            duty.validator_index,
+            chain
+                .block_proposer(duty.slot)
+                .expect("should get proposer") as u64,
+            "proposer index"
+        );
+    }
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get::<Value>("eth/v1/validator/duties/proposer/1"),
+        )
+        .expect_err("should not return proposer duties of a future epoch");
+}
+
+#[test]
+fn standard_beacon_committee_subscriptions() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let subscriptions = json!([{
+        "validator_index": "0",
+        "committee_index": "0",
+        "committees_at_slot": "1",
+        "slot": "1",
+        "is_aggregator": true
+    }]);
+
+    env.runtime()
+        .block_on(remote_node.http.standard_post::<_, ()>(
+            "eth/v1/validator/beacon_committee_subscriptions",
+            subscriptions,
+        ))
+        .expect("should subscribe");
+
+    env.runtime()
+        .block_on(remote_node.http.standard_post::<_, ()>(
+            "eth/v1/validator/beacon_committee_subscriptions",
+            json!([{ "validator_index": "0" }]),
+        ))
+        .expect_err("should not accept an incomplete subscription");
+
+    env.runtime()
+        .block_on(remote_node.http.standard_post::<_, ()>(
+            "eth/v1/validator/beacon_committee_subscriptions",
+            json!([{
+                "validator_index": "0",
+                "committee_index": "1",
+                "committees_at_slot": "1",
+                "slot": "1",
+                "is_aggregator": true
+            }]),
+        ))
+        .expect_err("should not accept a committee index beyond committees_at_slot");
+}
+
+#[test]
+fn standard_node() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+
+    let identity: GenericResponse<IdentityData> = env
+        .runtime()
+        .block_on(remote_node.http.standard_get("eth/v1/node/identity"))
+        .expect("should fetch identity");
+    assert_eq!(
+        identity.data.enr,
+        node.client.enr().expect("should have enr").to_base64(),
+        "enr"
+    );
+    assert!(!identity.data.peer_id.is_empty(), "should have a peer id");
+
+    let peers: GenericResponse<Vec<PeerData>> = env
+        .runtime()
+        .block_on(remote_node.http.standard_get("eth/v1/node/peers"))
+        .expect("should fetch peers");
+    assert!(peers.data.is_empty(), "should not have any peers");
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get::<Value>("eth/v1/node/peers?state=unknown"),
+        )
+        .expect_err("should not accept an unknown peer state");
+
+    let peer_count: Value = env
+        .runtime()
+        .block_on(remote_node.http.standard_get("eth/v1/node/peer_count"))
+        .expect("should fetch peer count");
+    assert_eq!(
+        peer_count,
+        json!({
+            "data": {
+                "disconnected": "0",
+                "connecting": "0",
+                "connected": "0",
+                "disconnecting": "0"
+            }
+        })
+    );
+
+    let syncing: GenericResponse<SyncingData> = env
+        .runtime()
+        .block_on(remote_node.http.standard_get("eth/v1/node/syncing"))
+        .expect("should fetch syncing status");
+    assert_eq!(
+        syncing.data.head_slot,
+        chain.head_info().expect("should get head").slot,
+        "head slot"
+    );
+}
+
+#[test]
+fn standard_debug() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+
+    let heads: GenericResponse<Vec<ChainHeadData>> = env
+        .runtime()
+        .block_on(remote_node.http.standard_get("eth/v1/debug/beacon/heads"))
+        .expect("should fetch heads");
+    assert_eq!(
+        heads.data,
+        chain
+            .heads()
+            .into_iter()
+            .map(|(root, slot)| ChainHeadData { slot, root })
+            .collect::<Vec<_>>(),
+        "heads"
+    );
+
+    let state: GenericResponse<BeaconState<E>> = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_get("eth/v2/debug/beacon/states/head"),
+        )
+        .expect("should fetch state");
+    let mut head_state = chain.head().expect("should get head").beacon_state;
+    head_state.drop_all_caches();
+    assert_eq!(state.data, head_state, "head state");
+}
+
+#[test]
+fn standard_validator_liveness() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    // Validator indices may be provided as either strings or numbers.
+    let liveness: Value = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .standard_post("eth/v1/validator/liveness/0", json!(["0", 1])),
+        )
+        .expect("should fetch liveness");
+    assert_eq!(liveness["data"][0]["index"], json!("0"));
+    assert_eq!(liveness["data"][1]["index"], json!("1"));
+    assert_eq!(liveness["data"][1]["epoch"], json!("0"));
+}
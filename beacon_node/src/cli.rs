@@ -41,8 +41,13 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
             Arg::with_name("listen-address")
                 .long("listen-address")
                 .value_name("ADDRESS")
-                .help("The address lighthouse will listen for UDP and TCP connections.")
+                .help("The address lighthouse will listen for UDP and TCP connections. To listen \
+                      on both IPv4 and IPv6, provide this flag twice, once with an IPv4 address \
+                      and once with an IPv6 address. Discovery only listens on the IPv4 address \
+                      of such dual-stack nodes.")
                 .default_value("0.0.0.0")
+                .multiple(true)
+                .number_of_values(1)
                 .takes_value(true)
         )
         .arg(
@@ -53,6 +58,14 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value("9000")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("port6")
+                .long("port6")
+                .value_name("PORT")
+                .help("The TCP port to listen on with the IPv6 listen address of a dual-stack \
+                      node. Defaults to 9090.")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("discovery-port")
                 .long("discovery-port")
@@ -61,12 +74,82 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("max_peers")
-                .long("max-peers")
-                .help("The maximum number of peers.")
+            Arg::with_name("target-peers")
+                .long("target-peers")
+                .help("The target number of peers. It is reduced to the maximum number of peers \
+                      if it exceeds it.")
                 .default_value("50")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("max-peers")
+                .long("max-peers")
+                .help("The maximum number of peers. Inbound connections beyond this limit are \
+                      rejected.")
+                .default_value("55")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("block-lookup-delay")
+                .long("block-lookup-delay")
+                .value_name("FRACTION")
+                .help("The fraction of a slot to wait before requesting an unknown block that is \
+                      referenced by a peer from the network, in case the block is received on \
+                      gossip in the meantime. Set to 0 to request such blocks immediately.")
+                .default_value("0.25")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("batch-lookahead")
+                .long("batch-lookahead")
+                .value_name("BATCHES")
+                .help("The number of batches of blocks that range sync may download ahead of the \
+                      batch being processed. Larger values keep more peers busy at the cost of \
+                      buffering more blocks in memory.")
+                .default_value("5")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rpc-quotas")
+                .long("rpc-quotas")
+                .value_name("QUOTAS")
+                .help("Overrides the rate limits of inbound RPC requests of each peer. A comma \
+                      separated list of PROTOCOL:TOKENS/SECONDS, e.g. \
+                      \"beacon_blocks_by_range:1024/10,beacon_blocks_by_root:128/10\". The \
+                      protocols are status, goodbye, ping, metadata, beacon_blocks_by_range and \
+                      beacon_blocks_by_root. A block request costs one token per block.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("enable-upnp")
+                .long("enable-upnp")
+                .help("Enables UPnP support. Setting this will make Lighthouse attempt to \
+                      establish external port mappings on the local router, and to advertise \
+                      them in the local ENR unless the ENR fields are set explicitly.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("disable-secio")
+                .long("disable-secio")
+                .help("Disables the secio fallback for peers that do not support Noise. Setting \
+                      this will prevent Lighthouse from connecting to such peers.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("disable-attestation-gossip")
+                .long("disable-attestation-gossip")
+                .help("Runs a blocks-only node, which neither subscribes to nor accepts messages \
+                      on the aggregate and attestation subnet topics.")
+                .conflicts_with("subscribe-all-subnets")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("subscribe-all-subnets")
+                .long("subscribe-all-subnets")
+                .help("Subscribes to all attestation subnets, rather than only those required by \
+                      the connected validators, and advertises them in the local ENR.")
+                .takes_value(false),
+        )
         .arg(
             Arg::with_name("boot-nodes")
                 .long("boot-nodes")
@@ -102,6 +185,24 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .requires("enr-udp-port")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("enr-address6")
+                .long("enr-address6")
+                .value_name("ADDRESS")
+                .help("The IPv6 address to broadcast to other peers in addition to --enr-address, \
+                      for dual-stack nodes. Set this only if you are sure other nodes can connect \
+                      to your local node on this address.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("enr-tcp6-port")
+                .long("enr-tcp6-port")
+                .value_name("PORT")
+                .help("The TCP port of the IPv6 address of the local ENR. The --port6 flag is \
+                      used if this is not set.")
+                .requires("enr-address6")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("enr-match")
                 .short("e")
@@ -122,7 +223,15 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .long("libp2p-addresses")
                 .value_name("MULTIADDR")
                 .help("One or more comma-delimited multiaddrs to manually connect to a libp2p peer \
-                       without an ENR.")
+                       without an ENR. Multiaddrs ending with a /p2p/ peer id are trusted peers.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("trusted-peers")
+                .long("trusted-peers")
+                .value_name("PEER_ID")
+                .help("One or more comma-delimited trusted peer ids. Trusted peers are never banned \
+                       or pruned and are re-dialed when disconnected.")
                 .takes_value(true),
         )
         /* REST API related arguments */
@@ -196,6 +305,82 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("Specifies the server for a web3 connection to the Eth1 chain. Also enables the --eth1 flag. Defaults to http://127.0.0.1:8545.")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("eth1-endpoints")
+                .long("eth1-endpoints")
+                .value_name("HTTP-ENDPOINTS")
+                .conflicts_with("eth1-endpoint")
+                .help("One or more comma-delimited servers for web3 connections to the Eth1 chain, \
+                       in order of priority. Requests are sent to the first healthy server and \
+                       fall back to the next if it fails. Also enables the --eth1 flag.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("interop-genesis-validators")
+                .long("interop-genesis-validators")
+                .value_name("COUNT")
+                .conflicts_with_all(&["eth1", "eth1-endpoint", "eth1-endpoints"])
+                .requires("interop-genesis-time")
+                .help("If present, ignores the genesis state of the testnet and creates one with \
+                       COUNT validators, using the insecure, deterministic interop keys. Implies \
+                       --dummy-eth1. Only effective if there is no existing database. Requires \
+                       --interop-genesis-time.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("interop-genesis-time")
+                .long("interop-genesis-time")
+                .value_name("UNIX_SECONDS")
+                .requires("interop-genesis-validators")
+                .help("The genesis time of the state created by --interop-genesis-validators. \
+                       Every node of the testnet must be given the same time, so that they \
+                       create the same genesis state.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("eth1-network-id")
+                .long("eth1-network-id")
+                .value_name("NETWORK-ID")
+                .help("Eth1 servers which are not connected to a network with this network id \
+                       (e.g., 5 for Goerli) are considered unhealthy and are not used. Defaults \
+                       to the DEPOSIT_NETWORK_ID of the Eth2 network config. If neither is set, the \
+                       network id is not checked.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("eth1-chain-id")
+                .long("eth1-chain-id")
+                .value_name("CHAIN-ID")
+                .help("Eth1 servers which are not connected to a chain with this chain id \
+                       (e.g., 5 for Goerli) are considered unhealthy and are not used. Defaults \
+                       to the DEPOSIT_CHAIN_ID of the Eth2 network config. If neither is set, the \
+                       chain id is not checked.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("eth1-deposit-snapshot-file")
+                .long("eth1-deposit-snapshot-file")
+                .value_name("FILE")
+                .help("A JSON file containing a finalized deposit tree snapshot, as returned by the \
+                       /eth/v1/beacon/deposit_snapshot API of a beacon node. If the eth1 deposit \
+                       cache is empty, it is initialized from the snapshot and only the deposit \
+                       logs after it are downloaded. The snapshot is checked against the deposit \
+                       contract, if it does not match all deposit logs are downloaded instead.")
+                .takes_value(true)
+                .conflicts_with("eth1-deposit-snapshot-url")
+        )
+        .arg(
+            Arg::with_name("eth1-deposit-snapshot-url")
+                .long("eth1-deposit-snapshot-url")
+                .value_name("HTTP-ENDPOINT")
+                .help("The HTTP API of a trusted beacon node from which to download a finalized \
+                       deposit tree snapshot. If the eth1 deposit cache is empty, it is \
+                       initialized from the snapshot and only the deposit logs after it are \
+                       downloaded. The snapshot is checked against the deposit contract, if it \
+                       cannot be downloaded or does not match all deposit logs are downloaded \
+                       instead.")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("slots-per-restore-point")
                 .long("slots-per-restore-point")
@@ -211,6 +396,129 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("Specifies how many blocks the database should cache in memory [default: 5]")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("prefinalization-cache-size")
+                .long("prefinalization-cache-size")
+                .value_name("MEGABYTES")
+                .help("Specifies how many megabytes of unfinalized states the database may hold \
+                       in memory rather than writing them to disk. States are written to disk \
+                       when the limit is reached, when they are needed to load the head after an \
+                       unclean shutdown, and on shutdown. [default: 0 (disabled)]")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("snapshot-cache-size")
+                .long("snapshot-cache-size")
+                .value_name("SIZE")
+                .help("Specifies how many recent block and state pairs should be cached in memory \
+                       for block processing [default: 4]")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("disable-tree-hash-cache")
+                .long("disable-tree-hash-cache")
+                .help("If present, state roots are computed from scratch instead of updating a \
+                       cached tree of hashes. This is very slow and is intended for debugging.")
+        )
+        .arg(
+            Arg::with_name("epoch-processing-threads")
+                .long("epoch-processing-threads")
+                .value_name("COUNT")
+                .help("Specifies the number of threads used for parallel epoch processing. Only \
+                       has an effect if Lighthouse was built with the parallel_epoch_processing \
+                       feature. [default: 0 (one per CPU, shared with other tasks)]")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("wss-checkpoint")
+                .long("wss-checkpoint")
+                .value_name("ROOT:EPOCH")
+                .help("A weak subjectivity checkpoint, given as a 0x-prefixed block root and an \
+                       epoch separated by a colon. The beacon node rejects blocks which finalize a \
+                       different block at that epoch and shuts down, protecting against \
+                       long-range attacks. It refuses to start if its own chain has finalized a \
+                       different block.")
+                .takes_value(true)
+        )
+
+        /*
+         * Validator monitoring.
+         */
+        .arg(
+            Arg::with_name("validator-monitor-auto")
+                .long("validator-monitor-auto")
+                .help("If present, the validators of any validator client which requests duties \
+                       from this beacon node are monitored, logging their attestations, blocks \
+                       and slashings. Only enable this if the HTTP API is not exposed to \
+                       untrusted clients.")
+        )
+        .arg(
+            Arg::with_name("validator-monitor-auto-limit")
+                .long("validator-monitor-auto-limit")
+                .value_name("COUNT")
+                .help("The maximum number of validators which are monitored due to \
+                       --validator-monitor-auto. Further validators are not monitored. \
+                       [default: 64]")
+                .requires("validator-monitor-auto")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("validator-monitor-pubkeys")
+                .long("validator-monitor-pubkeys")
+                .value_name("PUBKEYS")
+                .help("A comma-separated list of 0x-prefixed validator public keys. These \
+                       validators are monitored, logging their attestations, blocks and \
+                       slashings.")
+                .takes_value(true)
+        )
+
+        /*
+         * Slasher.
+         */
+        .arg(
+            Arg::with_name("slasher")
+                .long("slasher")
+                .help("If present, the attestations and blocks seen by this beacon node are \
+                       checked for slashable offences, which are logged and reported on the \
+                       HTTP API.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("slasher-dir")
+                .long("slasher-dir")
+                .value_name("DIR")
+                .help("Sets the directory of the slasher database [default: $datadir/slasher_db]")
+                .requires("slasher")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("slasher-update-period")
+                .long("slasher-update-period")
+                .value_name("SECONDS")
+                .help("The number of seconds between two batches of slasher processing \
+                       [default: 12]")
+                .requires("slasher")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("slasher-history-length")
+                .long("slasher-history-length")
+                .value_name("EPOCHS")
+                .help("The number of epochs of attestations and blocks retained by the slasher. \
+                       Must be a multiple of 16 [default: 4096]")
+                .requires("slasher")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("slasher-broadcast")
+                .long("slasher-broadcast")
+                .help("If present, the slashings detected by the slasher are added to the op \
+                       pool and published on gossip, rather than only being reported. Detected \
+                       slashings were previously broadcast by default; this flag is now \
+                       required to do so.")
+                .requires("slasher")
+                .takes_value(false)
+        )
 
         /*
          * Purge.
@@ -2,21 +2,23 @@ use beacon_chain::builder::PUBKEY_CACHE_FILENAME;
 use clap::ArgMatches;
 use clap_utils::BAD_TESTNET_DIR_MESSAGE;
 use client::{config::DEFAULT_DATADIR, ClientConfig, ClientGenesis};
-use eth2_libp2p::{Enr, Multiaddr};
+use eth2_libp2p::rpc::{Protocol, Quota};
+use eth2_libp2p::{types::GossipKind, Enr, Multiaddr, PeerId};
 use eth2_testnet_config::Eth2TestnetConfig;
-use slog::{crit, info, Logger};
+use slog::{crit, info, warn, Logger};
 use ssz::Encode;
 use std::fs;
 use std::fs::File;
 use std::io::prelude::*;
-use std::net::{IpAddr, Ipv4Addr, ToSocketAddrs};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, ToSocketAddrs};
 use std::net::{TcpListener, UdpSocket};
 use std::path::PathBuf;
-use types::{ChainSpec, EthSpec};
+use types::{ChainSpec, Checkpoint, Epoch, EthSpec, Hash256, PublicKeyBytes};
 
 pub const CLIENT_CONFIG_FILENAME: &str = "beacon-node.toml";
 pub const BEACON_NODE_DIR: &str = "beacon";
 pub const NETWORK_DIR: &str = "network";
+pub const DEFAULT_SLASHER_DIR: &str = "slasher_db";
 
 /// Gets the fully-initialized global client.
 ///
@@ -95,11 +97,41 @@ pub fn get_config<E: EthSpec>(
         client_config.network.network_dir = client_config.data_dir.join(NETWORK_DIR);
     };
 
-    if let Some(listen_address_str) = cli_args.value_of("listen-address") {
-        let listen_address = listen_address_str
-            .parse()
-            .map_err(|_| format!("Invalid listen address: {:?}", listen_address_str))?;
-        client_config.network.listen_address = listen_address;
+    if let Some(listen_address_strs) = cli_args.values_of("listen-address") {
+        let mut listen_address_v4 = None;
+        let mut listen_address_v6 = None;
+        for listen_address_str in listen_address_strs {
+            let listen_address = listen_address_str
+                .parse()
+                .map_err(|_| format!("Invalid listen address: {:?}", listen_address_str))?;
+            let duplicate = match listen_address {
+                IpAddr::V4(address) => listen_address_v4.replace(address).is_some(),
+                IpAddr::V6(address) => listen_address_v6.replace(address).is_some(),
+            };
+            if duplicate {
+                return Err(format!(
+                    "At most one IPv4 and one IPv6 listen address may be given: {:?}",
+                    listen_address_str
+                ));
+            }
+        }
+
+        match (listen_address_v4, listen_address_v6) {
+            (Some(address_v4), address_v6) => {
+                client_config.network.listen_address = address_v4.into();
+                client_config.network.listen_address_v6 = address_v6;
+            }
+            (None, Some(address_v6)) => {
+                client_config.network.listen_address = address_v6.into();
+            }
+            (None, None) => {}
+        }
+    }
+
+    if let Some(target_peers_str) = cli_args.value_of("target-peers") {
+        client_config.network.target_peers = target_peers_str
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid number of target peers: {}", target_peers_str))?;
     }
 
     if let Some(max_peers_str) = cli_args.value_of("max-peers") {
@@ -108,6 +140,82 @@ pub fn get_config<E: EthSpec>(
             .map_err(|_| format!("Invalid number of max peers: {}", max_peers_str))?;
     }
 
+    if let Some(delay_str) = cli_args.value_of("block-lookup-delay") {
+        let delay = delay_str
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid block lookup delay: {}", delay_str))?;
+        if !delay.is_finite() || !(0.0..=1.0).contains(&delay) {
+            return Err(format!(
+                "The block lookup delay ({}) must be a fraction of a slot between 0 and 1",
+                delay
+            ));
+        }
+        client_config.network.block_lookup_delay = delay;
+    }
+
+    if let Some(lookahead_str) = cli_args.value_of("batch-lookahead") {
+        let lookahead = lookahead_str
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid batch lookahead: {}", lookahead_str))?;
+        if lookahead == 0 {
+            return Err("The batch lookahead must be at least 1".to_string());
+        }
+        client_config.network.batch_lookahead = lookahead;
+    }
+
+    if let Some(quotas_str) = cli_args.value_of("rpc-quotas") {
+        for quota_str in quotas_str.split(',') {
+            let mut parts = quota_str.splitn(2, ':');
+            let protocol = parts.next().unwrap_or("").trim().parse::<Protocol>()?;
+            let quota = parts
+                .next()
+                .ok_or_else(|| format!("Missing quota for protocol {}", protocol))?
+                .parse::<Quota>()?;
+            client_config.network.rpc_quotas.set(protocol, quota);
+        }
+    }
+
+    if client_config.network.target_peers > client_config.network.max_peers {
+        warn!(
+            log,
+            "Target peers exceeds max peers";
+            "info" => "reducing target peers to max peers",
+            "target_peers" => client_config.network.target_peers,
+            "max_peers" => client_config.network.max_peers,
+        );
+        client_config.network.target_peers = client_config.network.max_peers;
+    }
+
+    if cli_args.is_present("enable-upnp") {
+        client_config.network.upnp_enabled = true;
+    }
+
+    if cli_args.is_present("disable-secio") {
+        client_config.network.secio_enabled = false;
+    }
+
+    if cli_args.is_present("disable-attestation-gossip") {
+        client_config.network.disable_attestation_gossip = true;
+        client_config
+            .network
+            .topics
+            .retain(|kind| *kind != GossipKind::BeaconAggregateAndProof);
+    }
+
+    if cli_args.is_present("subscribe-all-subnets") {
+        client_config.network.subscribe_all_subnets = true;
+    }
+
+    // Subscribing to all subnets would advertise every subnet in the ENR, although a node without
+    // attestation gossip is subscribed to none of them.
+    if client_config.network.subscribe_all_subnets
+        && client_config.network.disable_attestation_gossip
+    {
+        return Err(
+            "subscribe-all-subnets cannot be used with disable-attestation-gossip".to_string(),
+        );
+    }
+
     if let Some(port_str) = cli_args.value_of("port") {
         let port = port_str
             .parse::<u16>()
@@ -116,6 +224,12 @@ pub fn get_config<E: EthSpec>(
         client_config.network.discovery_port = port;
     }
 
+    if let Some(port_str) = cli_args.value_of("port6") {
+        client_config.network.libp2p_port_v6 = port_str
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid port: {}", port_str))?;
+    }
+
     if let Some(port_str) = cli_args.value_of("discovery-port") {
         let port = port_str
             .parse::<u16>()
@@ -141,6 +255,17 @@ pub fn get_config<E: EthSpec>(
             .collect::<Result<Vec<Multiaddr>, _>>()?;
     }
 
+    if let Some(trusted_peers_str) = cli_args.value_of("trusted-peers") {
+        client_config.network.trusted_peers = trusted_peers_str
+            .split(',')
+            .map(|peer_id| {
+                peer_id
+                    .parse()
+                    .map_err(|_| format!("Invalid trusted peer id: {}", peer_id))
+            })
+            .collect::<Result<Vec<PeerId>, _>>()?;
+    }
+
     if let Some(enr_udp_port_str) = cli_args.value_of("enr-udp-port") {
         client_config.network.enr_udp_port = Some(
             enr_udp_port_str
@@ -157,6 +282,22 @@ pub fn get_config<E: EthSpec>(
         );
     }
 
+    if let Some(enr_address_str) = cli_args.value_of("enr-address6") {
+        client_config.network.enr_address_v6 = Some(
+            enr_address_str
+                .parse::<Ipv6Addr>()
+                .map_err(|_| format!("Invalid IPv6 ENR address: {}", enr_address_str))?,
+        );
+    }
+
+    if let Some(enr_tcp_port_str) = cli_args.value_of("enr-tcp6-port") {
+        client_config.network.enr_tcp_port_v6 = Some(
+            enr_tcp_port_str
+                .parse::<u16>()
+                .map_err(|_| format!("Invalid ENR TCP6 port: {}", enr_tcp_port_str))?,
+        );
+    }
+
     if cli_args.is_present("enr-match") {
         // set the enr address to localhost if the address is 0.0.0.0
         if client_config.network.listen_address
@@ -269,7 +410,52 @@ pub fn get_config<E: EthSpec>(
     // Defines the URL to reach the eth1 node.
     if let Some(val) = cli_args.value_of("eth1-endpoint") {
         client_config.sync_eth1_chain = true;
-        client_config.eth1.endpoint = val.to_string();
+        client_config.eth1.endpoints = vec![val.to_string()];
+    }
+
+    // Defines the URLs to reach the eth1 nodes, in order of priority.
+    if let Some(val) = cli_args.value_of("eth1-endpoints") {
+        client_config.sync_eth1_chain = true;
+        client_config.eth1.endpoints = val
+            .split(',')
+            .map(|endpoint| endpoint.trim().to_string())
+            .filter(|endpoint| !endpoint.is_empty())
+            .collect();
+    }
+
+    // Eth1 servers must be connected to the deposit chain of the network, if it is specified.
+    if spec.deposit_network_id.is_some() {
+        client_config.eth1.network_id = spec.deposit_network_id;
+    }
+    if spec.deposit_chain_id.is_some() {
+        client_config.eth1.chain_id = spec.deposit_chain_id;
+    }
+
+    if let Some(network_id) = cli_args.value_of("eth1-network-id") {
+        client_config.eth1.network_id = Some(
+            network_id
+                .parse()
+                .map_err(|_| "eth1-network-id is not a valid integer".to_string())?,
+        );
+    }
+
+    if let Some(chain_id) = cli_args.value_of("eth1-chain-id") {
+        client_config.eth1.chain_id = Some(
+            chain_id
+                .parse()
+                .map_err(|_| "eth1-chain-id is not a valid integer".to_string())?,
+        );
+    }
+
+    // Defines a snapshot from which to initialize the eth1 deposit cache.
+    if let Some(path) = cli_args.value_of("eth1-deposit-snapshot-file") {
+        client_config.eth1.deposit_snapshot_path = Some(PathBuf::from(path));
+        client_config.eth1.deposit_snapshot_url = None;
+    }
+
+    if let Some(url) = cli_args.value_of("eth1-deposit-snapshot-url") {
+        client_config.eth1.deposit_snapshot_url = Some(url.to_string());
+        client_config.eth1.deposit_snapshot_path = None;
     }
 
     if let Some(freezer_dir) = cli_args.value_of("freezer-dir") {
@@ -293,6 +479,83 @@ pub fn get_config<E: EthSpec>(
             .map_err(|_| "block-cache-size is not a valid integer".to_string())?;
     }
 
+    if let Some(prefinalization_cache_size) = cli_args.value_of("prefinalization-cache-size") {
+        let megabytes: usize = prefinalization_cache_size
+            .parse()
+            .map_err(|_| "prefinalization-cache-size is not a valid integer".to_string())?;
+        client_config.store.prefinalization_cache_size = megabytes * 1024 * 1024;
+    }
+
+    if let Some(snapshot_cache_size) = cli_args.value_of("snapshot-cache-size") {
+        client_config.snapshot_cache_size = snapshot_cache_size
+            .parse()
+            .map_err(|_| "snapshot-cache-size is not a valid integer".to_string())?;
+    }
+
+    if cli_args.is_present("disable-tree-hash-cache") {
+        client_config.disable_tree_hash_cache = true;
+    }
+
+    if let Some(epoch_processing_threads) = cli_args.value_of("epoch-processing-threads") {
+        client_config.epoch_processing_threads = epoch_processing_threads
+            .parse()
+            .map_err(|_| "epoch-processing-threads is not a valid integer".to_string())?;
+    }
+
+    if let Some(checkpoint) = cli_args.value_of("wss-checkpoint") {
+        client_config.weak_subjectivity_checkpoint = Some(parse_checkpoint(checkpoint)?);
+    }
+
+    /*
+     * Validator monitoring
+     */
+    if cli_args.is_present("validator-monitor-auto") {
+        client_config.validator_monitor_auto = true;
+    }
+
+    if let Some(limit) = cli_args.value_of("validator-monitor-auto-limit") {
+        client_config.validator_monitor_auto_limit = limit
+            .parse()
+            .map_err(|_| "validator-monitor-auto-limit is not a valid integer".to_string())?;
+    }
+
+    if let Some(pubkeys) = cli_args.value_of("validator-monitor-pubkeys") {
+        client_config.validator_monitor_pubkeys = pubkeys
+            .split(',')
+            .map(parse_public_key_bytes)
+            .collect::<Result<_, _>>()?;
+    }
+
+    /*
+     * Slasher
+     */
+    if cli_args.is_present("slasher") {
+        let database_path = cli_args
+            .value_of("slasher-dir")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| client_config.data_dir.join(DEFAULT_SLASHER_DIR));
+        let mut slasher_config = slasher::Config::new(database_path);
+
+        if let Some(update_period) = cli_args.value_of("slasher-update-period") {
+            slasher_config.update_period = update_period
+                .parse()
+                .map_err(|_| "slasher-update-period is not a valid integer".to_string())?;
+        }
+
+        if let Some(history_length) = cli_args.value_of("slasher-history-length") {
+            slasher_config.history_length = history_length
+                .parse()
+                .map_err(|_| "slasher-history-length is not a valid integer".to_string())?;
+        }
+
+        slasher_config.broadcast = cli_args.is_present("slasher-broadcast");
+
+        slasher_config
+            .validate()
+            .map_err(|e| format!("Invalid slasher configuration: {:?}", e))?;
+        client_config.slasher = Some(slasher_config);
+    }
+
     if spec_constants != client_config.spec_constants {
         crit!(log, "Specification constants do not match.";
               "client_config" => client_config.spec_constants.to_string(),
@@ -318,6 +581,8 @@ pub fn get_config<E: EthSpec>(
             unused_port("tcp").map_err(|e| format!("Failed to get port for libp2p: {}", e))?;
         client_config.network.discovery_port =
             unused_port("udp").map_err(|e| format!("Failed to get port for discovery: {}", e))?;
+        client_config.network.libp2p_port_v6 =
+            unused_port("tcp").map_err(|e| format!("Failed to get port for libp2p: {}", e))?;
         client_config.rest_api.port = 0;
         client_config.websocket_server.port = 0;
     }
@@ -351,6 +616,31 @@ pub fn get_config<E: EthSpec>(
         client_config.genesis = ClientGenesis::DepositContract;
     }
 
+    /*
+     * Interop genesis
+     *
+     * Replaces the genesis state of the testnet dir.
+     */
+    if let Some(validator_count) = cli_args.value_of("interop-genesis-validators") {
+        let validator_count = validator_count
+            .parse()
+            .map_err(|_| "interop-genesis-validators is not a valid integer".to_string())?;
+
+        let genesis_time = cli_args
+            .value_of("interop-genesis-time")
+            .ok_or_else(|| "interop-genesis-time is required".to_string())?
+            .parse()
+            .map_err(|_| "interop-genesis-time is not a valid integer".to_string())?;
+
+        client_config.genesis = ClientGenesis::Interop {
+            validator_count,
+            genesis_time,
+        };
+        // The interop genesis state does not use the deposit contract.
+        client_config.dummy_eth1_backend = true;
+        client_config.sync_eth1_chain = false;
+    }
+
     if !config_file_existed {
         write_to_file(config_file_path, &client_config)?;
     }
@@ -436,6 +726,49 @@ pub fn unused_port(transport: &str) -> Result<u16, String> {
     Ok(local_addr.port())
 }
 
+/// Parses a 0x-prefixed hex validator public key.
+fn parse_public_key_bytes(hex_public_key: &str) -> Result<PublicKeyBytes, String> {
+    let hex_public_key = hex_public_key.trim();
+    hex::decode(hex_public_key.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid public key hex {}: {:?}", hex_public_key, e))
+        .and_then(|bytes| {
+            PublicKeyBytes::from_bytes(&bytes)
+                .map_err(|e| format!("Invalid public key {}: {:?}", hex_public_key, e))
+        })
+}
+
+/// Parses a checkpoint given as a 0x-prefixed hex block root and an epoch, separated by a colon.
+fn parse_checkpoint(checkpoint: &str) -> Result<Checkpoint, String> {
+    let mut split = checkpoint.trim().split(':');
+    let (root, epoch) = match (split.next(), split.next(), split.next()) {
+        (Some(root), Some(epoch), None) => (root, epoch),
+        _ => {
+            return Err(format!(
+                "Invalid checkpoint {}, expected ROOT:EPOCH",
+                checkpoint
+            ))
+        }
+    };
+
+    let root_bytes = hex::decode(root.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid checkpoint root {}: {:?}", root, e))?;
+    if root_bytes.len() != 32 {
+        return Err(format!("Checkpoint root {} must be 32 bytes", root));
+    }
+    let epoch = epoch
+        .parse::<u64>()
+        .map_err(|_| format!("Checkpoint epoch {} is not a valid integer", epoch))?;
+    // The genesis checkpoint is trivially part of the chain and has no meaningful root.
+    if epoch == 0 {
+        return Err("Checkpoint epoch must be after genesis".to_string());
+    }
+
+    Ok(Checkpoint {
+        epoch: Epoch::new(epoch),
+        root: Hash256::from_slice(&root_bytes),
+    })
+}
+
 /// Write a configuration to file.
 pub fn write_to_file<T>(path: PathBuf, config: &T) -> Result<(), String>
 where
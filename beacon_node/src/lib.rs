@@ -97,7 +97,7 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
             info!(
                 log,
                 "Block production enabled";
-                "endpoint" => &client_config.eth1.endpoint,
+                "endpoints" => format!("{:?}", &client_config.eth1.endpoints),
                 "method" => "json rpc via http"
             );
             builder.caching_eth1_backend(client_config.eth1.clone())?
@@ -127,7 +127,8 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
         let builder = builder
             .build_beacon_chain()?
             .network(&mut client_config.network)?
-            .notifier()?;
+            .notifier()?
+            .slasher_service()?;
 
         let builder = if client_config.rest_api.enabled {
             builder.http_server(&client_config, &http_eth2_config, events)?
@@ -8,9 +8,10 @@ use crate::iter::{ParentRootBlockIterator, StateRootsIterator};
 use crate::leveldb_store::LevelDB;
 use crate::memory_store::MemoryStore;
 use crate::metrics;
+use crate::prefinalization_cache::PreFinalizationCache;
 use crate::{
-    get_key_for_col, DBColumn, Error, ItemStore, KeyValueStoreOp, PartialBeaconState, StoreItem,
-    StoreOp,
+    get_key_for_col, ColumnIter, DBColumn, Error, ItemStore, KeyValueStoreOp, PartialBeaconState,
+    StoreItem, StoreOp,
 };
 use lru::LruCache;
 use parking_lot::{Mutex, RwLock};
@@ -49,6 +50,10 @@ pub struct HotColdDB<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> {
     pub(crate) hot_db: Hot,
     /// LRU cache of deserialized blocks. Updated whenever a block is loaded.
     block_cache: Mutex<LruCache<Hash256, SignedBeaconBlock<E>>>,
+    /// Unfinalized epoch boundary states which have not been written to the hot database.
+    ///
+    /// Disabled unless `config.prefinalization_cache_size` is non-zero.
+    prefinalization_cache: Mutex<PreFinalizationCache<E>>,
     /// Chain spec.
     spec: ChainSpec,
     /// Logger.
@@ -181,6 +186,7 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
 
         // Delete the full state if it lies on an epoch boundary.
         if slot % E::slots_per_epoch() == 0 {
+            self.prefinalization_cache.lock().remove(state_root);
             self.hot_db
                 .key_delete(DBColumn::BeaconState.into(), state_root.as_bytes())?;
         }
@@ -279,7 +285,12 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
                     let untyped_hash: Hash256 = (*block_hash).into();
                     guard.pop(&untyped_hash);
                 }
-                StoreOp::DeleteState(_, _) => (),
+                StoreOp::DeleteState(state_hash, slot) => {
+                    if *slot % E::slots_per_epoch() == 0 {
+                        let untyped_hash: Hash256 = (*state_hash).into();
+                        self.prefinalization_cache.lock().remove(&untyped_hash);
+                    }
+                }
             }
         }
         Ok(())
@@ -299,6 +310,9 @@ impl<E: EthSpec> HotColdDB<E, MemoryStore<E>, MemoryStore<E>> {
             cold_db: MemoryStore::open(),
             hot_db: MemoryStore::open(),
             block_cache: Mutex::new(LruCache::new(config.block_cache_size)),
+            prefinalization_cache: Mutex::new(PreFinalizationCache::new(
+                config.prefinalization_cache_size,
+            )),
             config,
             spec,
             log,
@@ -327,6 +341,9 @@ impl<E: EthSpec> HotColdDB<E, LevelDB<E>, LevelDB<E>> {
             cold_db: LevelDB::open(cold_path)?,
             hot_db: LevelDB::open(hot_path)?,
             block_cache: Mutex::new(LruCache::new(config.block_cache_size)),
+            prefinalization_cache: Mutex::new(PreFinalizationCache::new(
+                config.prefinalization_cache_size,
+            )),
             config,
             spec,
             log,
@@ -347,6 +364,9 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     ///
     /// On an epoch boundary, store a full state. On an intermediate slot, store
     /// just a backpointer to the nearest epoch boundary.
+    ///
+    /// If the pre-finalization cache is enabled, full states are held in memory rather than
+    /// written to disk, until the cache overflows or they are flushed.
     pub fn store_hot_state(
         &self,
         state_root: &Hash256,
@@ -354,13 +374,35 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     ) -> Result<(), Error> {
         // On the epoch boundary, store the full state.
         if state.slot % E::slots_per_epoch() == 0 {
-            trace!(
-                self.log,
-                "Storing full state on epoch boundary";
-                "slot" => state.slot.as_u64(),
-                "state_root" => format!("{:?}", state_root)
-            );
-            store_full_state(&self.hot_db, state_root, &state)?;
+            let mut cache = self.prefinalization_cache.lock();
+            if cache.is_enabled() {
+                trace!(
+                    self.log,
+                    "Caching full state on epoch boundary";
+                    "slot" => state.slot.as_u64(),
+                    "state_root" => format!("{:?}", state_root)
+                );
+                let overflow = cache.insert(*state_root, state.clone_with_only_committee_caches());
+
+                // The lock is held whilst writing the overflow, so that the states can always be
+                // found either in the cache or on disk.
+                for (overflow_root, overflow_state) in overflow {
+                    metrics::inc_counter(&metrics::PREFINALIZATION_CACHE_OVERFLOW_COUNT);
+                    store_full_state(&self.hot_db, &overflow_root, &overflow_state)?;
+                }
+                metrics::set_gauge(
+                    &metrics::PREFINALIZATION_CACHE_SIZE,
+                    cache.size_bytes() as i64,
+                );
+            } else {
+                trace!(
+                    self.log,
+                    "Storing full state on epoch boundary";
+                    "slot" => state.slot.as_u64(),
+                    "state_root" => format!("{:?}", state_root)
+                );
+                store_full_state(&self.hot_db, state_root, &state)?;
+            }
         }
 
         // Store a summary of the state.
@@ -383,7 +425,8 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
             epoch_boundary_state_root,
         }) = self.load_hot_state_summary(state_root)?
         {
-            let boundary_state = get_full_state(&self.hot_db, &epoch_boundary_state_root)?
+            let boundary_state = self
+                .get_hot_full_state(&epoch_boundary_state_root)?
                 .ok_or_else(|| {
                     HotColdDBError::MissingEpochBoundaryState(epoch_boundary_state_root)
                 })?;
@@ -404,6 +447,65 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         }
     }
 
+    /// Load a full epoch boundary state from the pre-finalization cache, or the hot database.
+    fn get_hot_full_state(&self, state_root: &Hash256) -> Result<Option<BeaconState<E>>, Error> {
+        if let Some(state) = self.prefinalization_cache.lock().get(state_root) {
+            metrics::inc_counter(&metrics::PREFINALIZATION_CACHE_HIT_COUNT);
+            return Ok(Some(state));
+        }
+        get_full_state(&self.hot_db, state_root)
+    }
+
+    /// Write the state with `state_root` from the pre-finalization cache to the hot database,
+    /// if it is cached.
+    fn persist_cached_state(&self, state_root: &Hash256) -> Result<(), Error> {
+        let mut cache = self.prefinalization_cache.lock();
+        if let Some(state) = cache.remove(state_root) {
+            store_full_state(&self.hot_db, state_root, &state)?;
+        }
+        metrics::set_gauge(
+            &metrics::PREFINALIZATION_CACHE_SIZE,
+            cache.size_bytes() as i64,
+        );
+        Ok(())
+    }
+
+    /// Write the epoch boundary state from which the hot state with `state_root` is loaded to the
+    /// hot database, if it is held in the pre-finalization cache.
+    ///
+    /// This must be called for each unfinalized state which is referred to by something persisted
+    /// (e.g., the head), otherwise the state is lost on an unclean shutdown.
+    pub fn persist_epoch_boundary_state(&self, state_root: &Hash256) -> Result<(), Error> {
+        if let Some(summary) = self.load_hot_state_summary(state_root)? {
+            self.persist_cached_state(&summary.epoch_boundary_state_root)?;
+        }
+        Ok(())
+    }
+
+    /// Write all the states in the pre-finalization cache to the hot database.
+    ///
+    /// This must be called before shutting down, otherwise the unfinalized states which are only
+    /// held in memory are lost.
+    pub fn flush_prefinalization_cache(&self) -> Result<(), Error> {
+        let mut cache = self.prefinalization_cache.lock();
+        if cache.is_empty() {
+            return Ok(());
+        }
+
+        debug!(
+            self.log,
+            "Flushing pre-finalization cache";
+            "states" => cache.len(),
+            "size_bytes" => cache.size_bytes(),
+        );
+
+        for (state_root, state) in cache.drain() {
+            store_full_state(&self.hot_db, &state_root, &state)?;
+        }
+        metrics::set_gauge(&metrics::PREFINALIZATION_CACHE_SIZE, 0);
+        Ok(())
+    }
+
     /// Store a pre-finalization state in the freezer database.
     ///
     /// Will log a warning and not store anything if the state does not lie on a restore point
@@ -637,6 +739,22 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
             * self.config.slots_per_restore_point
     }
 
+    /// Compact both the hot and the cold database.
+    pub fn compact(&self) -> Result<(), Error> {
+        self.hot_db.compact()?;
+        self.cold_db.compact()
+    }
+
+    /// Iterate through the keys and values of `column` in the hot database.
+    pub fn iter_hot_column(&self, column: DBColumn) -> ColumnIter {
+        self.hot_db.iter_column(column)
+    }
+
+    /// Iterate through the keys and values of `column` in the cold database.
+    pub fn iter_cold_column(&self, column: DBColumn) -> ColumnIter {
+        self.cold_db.iter_column(column)
+    }
+
     /// Load the split point from disk.
     fn load_split(&self) -> Result<Option<Split>, Error> {
         let key = Hash256::from_slice(SPLIT_DB_KEY.as_bytes());
@@ -771,7 +889,8 @@ pub fn process_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
     }) {
         let (state_root, slot) = maybe_pair?;
         if slot % store.config.slots_per_restore_point == 0 {
-            let state: BeaconState<E> = get_full_state(&store.hot_db, &state_root)?
+            let state: BeaconState<E> = store
+                .get_hot_full_state(&state_root)?
                 .ok_or_else(|| HotColdDBError::MissingStateToFreeze(state_root))?;
 
             store.store_cold_state(&state_root, &state)?;
@@ -785,7 +904,9 @@ pub fn process_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
         to_delete.push((state_root, slot));
     }
 
-    // 2. Update the split slot
+    // 2. Update the split slot, making sure that the new split state is on disk (it is read
+    // from the hot DB when reconstructing frozen states, and after a restart).
+    store.persist_cached_state(&frozen_head_root)?;
     *store.split.write() = Split {
         slot: frozen_head.slot,
         state_root: frozen_head_root,
@@ -797,6 +918,17 @@ pub fn process_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
         store.delete_state(&state_root, slot)?;
     }
 
+    // Drop any cached states which are now prior to the split, such as those of abandoned forks
+    // that are yet to be pruned.
+    {
+        let mut cache = store.prefinalization_cache.lock();
+        cache.prune(frozen_head.slot);
+        metrics::set_gauge(
+            &metrics::PREFINALIZATION_CACHE_SIZE,
+            cache.size_bytes() as i64,
+        );
+    }
+
     debug!(
         store.log,
         "Freezer migration complete";
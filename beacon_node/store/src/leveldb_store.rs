@@ -1,10 +1,12 @@
 use super::*;
 use crate::metrics;
 use db_key::Key;
+use leveldb::compaction::Compaction;
 use leveldb::database::batch::{Batch, Writebatch};
 use leveldb::database::kv::KV;
 use leveldb::database::Database;
 use leveldb::error::Error as LevelDBError;
+use leveldb::iterator::{Iterable, LevelDBIterator};
 use leveldb::options::{Options, ReadOptions, WriteOptions};
 use std::marker::PhantomData;
 use std::path::Path;
@@ -110,6 +112,27 @@ impl<E: EthSpec> KeyValueStore<E> for LevelDB<E> {
         self.db.write(self.write_options(), &leveldb_batch)?;
         Ok(())
     }
+
+    /// Compacts the entire key space, which spans every column.
+    fn compact(&self) -> Result<(), Error> {
+        // All column prefixes are ASCII, so every key lies between the empty key and this one.
+        let start_key = BytesKey::from_vec(vec![]);
+        let end_key = BytesKey::from_vec(vec![0xff; 64]);
+        self.db.compact(&start_key, &end_key);
+        Ok(())
+    }
+
+    fn iter_column(&self, column: DBColumn) -> ColumnIter {
+        let prefix = Into::<&'static str>::into(column).as_bytes();
+
+        let iter = self.db.iter(self.read_options());
+        iter.seek(&BytesKey::from_vec(prefix.to_vec()));
+
+        Box::new(
+            iter.take_while(move |(key, _)| key.key.starts_with(prefix))
+                .map(move |(key, value)| Ok((key.key[prefix.len()..].to_vec(), value))),
+        )
+    }
 }
 
 impl<E: EthSpec> ItemStore<E> for LevelDB<E> {}
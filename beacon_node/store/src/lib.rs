@@ -21,6 +21,7 @@ mod leveldb_store;
 mod memory_store;
 mod metrics;
 mod partial_beacon_state;
+mod prefinalization_cache;
 mod state_batch;
 
 pub mod iter;
@@ -51,8 +52,17 @@ pub trait KeyValueStore<E: EthSpec>: Sync + Send + Sized + 'static {
 
     /// Execute either all of the operations in `batch` or none at all, returning an error.
     fn do_atomically(&self, batch: &[KeyValueStoreOp]) -> Result<(), Error>;
+
+    /// Compact the database, reclaiming the space used by deleted and overwritten items.
+    fn compact(&self) -> Result<(), Error>;
+
+    /// Iterate through all keys and values in `column`, with the column prefix removed from keys.
+    fn iter_column(&self, column: DBColumn) -> ColumnIter;
 }
 
+/// An iterator over the `(key, value)` pairs of a single column.
+pub type ColumnIter<'a> = Box<dyn Iterator<Item = Result<(Vec<u8>, Vec<u8>), Error>> + 'a>;
+
 pub fn get_key_for_col(column: &str, key: &[u8]) -> Vec<u8> {
     let mut result = column.as_bytes().to_vec();
     result.extend_from_slice(key);
@@ -129,6 +139,43 @@ pub enum DBColumn {
     BeaconHistoricalRoots,
     BeaconRandaoMixes,
     DhtEnrs,
+    PeerDb,
+    /// For the indexed attestations retained by the slasher, by target epoch and root.
+    SlasherIndexedAttestation,
+    /// For the slasher's record of each validator's vote for a target epoch.
+    SlasherAttesterRecord,
+    /// For the slasher's chunks of minimum target distances of each validator.
+    SlasherMinTargets,
+    /// For the slasher's chunks of maximum target distances of each validator.
+    SlasherMaxTargets,
+    /// For the block headers retained by the slasher, by slot and proposer.
+    SlasherProposal,
+}
+
+impl DBColumn {
+    /// All columns, in no particular order.
+    pub const ALL: [DBColumn; 20] = [
+        DBColumn::BeaconMeta,
+        DBColumn::BeaconBlock,
+        DBColumn::BeaconState,
+        DBColumn::BeaconChain,
+        DBColumn::OpPool,
+        DBColumn::Eth1Cache,
+        DBColumn::ForkChoice,
+        DBColumn::BeaconRestorePoint,
+        DBColumn::BeaconStateSummary,
+        DBColumn::BeaconBlockRoots,
+        DBColumn::BeaconStateRoots,
+        DBColumn::BeaconHistoricalRoots,
+        DBColumn::BeaconRandaoMixes,
+        DBColumn::DhtEnrs,
+        DBColumn::PeerDb,
+        DBColumn::SlasherIndexedAttestation,
+        DBColumn::SlasherAttesterRecord,
+        DBColumn::SlasherMinTargets,
+        DBColumn::SlasherMaxTargets,
+        DBColumn::SlasherProposal,
+    ];
 }
 
 impl Into<&'static str> for DBColumn {
@@ -149,6 +196,12 @@ impl Into<&'static str> for DBColumn {
             DBColumn::BeaconHistoricalRoots => "bhr",
             DBColumn::BeaconRandaoMixes => "brm",
             DBColumn::DhtEnrs => "dht",
+            DBColumn::PeerDb => "pdb",
+            DBColumn::SlasherIndexedAttestation => "sia",
+            DBColumn::SlasherAttesterRecord => "sar",
+            DBColumn::SlasherMinTargets => "smn",
+            DBColumn::SlasherMaxTargets => "smx",
+            DBColumn::SlasherProposal => "spr",
         }
     }
 }
@@ -207,7 +260,18 @@ mod tests {
         let retrieved = store.get(&key).unwrap().unwrap();
         assert_eq!(item, retrieved);
 
+        let column = store
+            .iter_column(StorableThing::db_column())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            column,
+            vec![(key.as_bytes().to_vec(), item.as_store_bytes())]
+        );
+        assert!(store.iter_column(DBColumn::BeaconState).next().is_none());
+
         store.delete::<StorableThing>(&key).unwrap();
+        store.compact().unwrap();
 
         assert_eq!(store.exists::<StorableThing>(&key).unwrap(), false);
 
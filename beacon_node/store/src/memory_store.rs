@@ -1,4 +1,4 @@
-use super::{Error, ItemStore, KeyValueStore, KeyValueStoreOp};
+use super::{ColumnIter, DBColumn, Error, ItemStore, KeyValueStore, KeyValueStoreOp};
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::marker::PhantomData;
@@ -74,6 +74,23 @@ impl<E: EthSpec> KeyValueStore<E> for MemoryStore<E> {
         }
         Ok(())
     }
+
+    /// Memory is reclaimed as soon as keys are deleted, there is nothing to compact.
+    fn compact(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn iter_column(&self, column: DBColumn) -> ColumnIter {
+        let prefix = Into::<&'static str>::into(column).as_bytes();
+        let items = self
+            .db
+            .read()
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| Ok((key[prefix.len()..].to_vec(), value.clone())))
+            .collect::<Vec<_>>();
+        Box::new(items.into_iter())
+    }
 }
 
 impl<E: EthSpec> ItemStore<E> for MemoryStore<E> {}
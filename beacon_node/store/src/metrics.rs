@@ -94,6 +94,21 @@ lazy_static! {
         "store_beacon_state_write_bytes_total",
         "Total number of beacon state bytes written to the DB"
     );
+    /*
+     * Pre-finalization Cache
+     */
+    pub static ref PREFINALIZATION_CACHE_SIZE: Result<IntGauge> = try_create_int_gauge(
+        "store_prefinalization_cache_size_bytes",
+        "Estimated number of bytes of unfinalized states held in memory"
+    );
+    pub static ref PREFINALIZATION_CACHE_HIT_COUNT: Result<IntCounter> = try_create_int_counter(
+        "store_prefinalization_cache_hit_total",
+        "Number of hits to the store's pre-finalization cache"
+    );
+    pub static ref PREFINALIZATION_CACHE_OVERFLOW_COUNT: Result<IntCounter> = try_create_int_counter(
+        "store_prefinalization_cache_overflow_total",
+        "Number of states written to disk due to the pre-finalization cache being full"
+    );
     /*
      * Beacon Block
      */
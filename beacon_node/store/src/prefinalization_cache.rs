@@ -0,0 +1,178 @@
+//! Provides the `PreFinalizationCache`, which holds the full epoch boundary states of the hot
+//! database in memory until they are finalized.
+//!
+//! Most of the states written to the hot database during sync are finalized shortly after, at
+//! which point they are either copied to the freezer (restore points only) or deleted. Keeping
+//! them in memory avoids writing states to disk which are never read from it.
+use ssz::Encode;
+use std::collections::HashMap;
+use types::{BeaconState, EthSpec, Hash256, Slot};
+
+/// A cache of unfinalized states, bounded by the (estimated) number of bytes it holds.
+pub struct PreFinalizationCache<E: EthSpec> {
+    states: HashMap<Hash256, (BeaconState<E>, usize)>,
+    size_bytes: usize,
+    capacity_bytes: usize,
+}
+
+impl<E: EthSpec> PreFinalizationCache<E> {
+    /// Create a cache holding at most `capacity_bytes` of states. A zero capacity disables the
+    /// cache.
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            states: HashMap::new(),
+            size_bytes: 0,
+            capacity_bytes,
+        }
+    }
+
+    /// Returns `true` if states should be inserted into the cache.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity_bytes > 0
+    }
+
+    /// The estimated number of bytes of the states in the cache.
+    pub fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Insert `state` into the cache.
+    ///
+    /// If the cache exceeds its capacity, the states with the lowest slots are removed and
+    /// returned, so they can be written to disk. These are the states closest to finalization,
+    /// which are the least likely to be read again before they are frozen.
+    pub fn insert(
+        &mut self,
+        state_root: Hash256,
+        state: BeaconState<E>,
+    ) -> Vec<(Hash256, BeaconState<E>)> {
+        let size = state.ssz_bytes_len();
+        if let Some((_, old_size)) = self.states.insert(state_root, (state, size)) {
+            self.size_bytes -= old_size;
+        }
+        self.size_bytes += size;
+
+        let mut overflow = vec![];
+        while self.size_bytes > self.capacity_bytes {
+            let lowest = self
+                .states
+                .iter()
+                .min_by_key(|(_, (state, _))| state.slot)
+                .map(|(root, _)| *root);
+
+            match lowest.and_then(|root| self.remove(&root).map(|state| (root, state))) {
+                Some(pair) => overflow.push(pair),
+                None => break,
+            }
+        }
+        overflow
+    }
+
+    /// Returns a clone of the state with `state_root`, if it is cached.
+    pub fn get(&self, state_root: &Hash256) -> Option<BeaconState<E>> {
+        self.states
+            .get(state_root)
+            .map(|(state, _)| state.clone_with_only_committee_caches())
+    }
+
+    /// Remove the state with `state_root` from the cache.
+    pub fn remove(&mut self, state_root: &Hash256) -> Option<BeaconState<E>> {
+        let (state, size) = self.states.remove(state_root)?;
+        self.size_bytes -= size;
+        Some(state)
+    }
+
+    /// Remove all states with slots less than `slot`, which can no longer be read once the split
+    /// point of the database has advanced past them.
+    pub fn prune(&mut self, slot: Slot) {
+        let size_bytes = &mut self.size_bytes;
+        self.states.retain(|_, (state, size)| {
+            let keep = state.slot >= slot;
+            if !keep {
+                *size_bytes -= *size;
+            }
+            keep
+        });
+    }
+
+    /// Remove and return all the states in the cache.
+    pub fn drain(&mut self) -> Vec<(Hash256, BeaconState<E>)> {
+        self.size_bytes = 0;
+        self.states
+            .drain()
+            .map(|(root, (state, _))| (root, state))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{test_utils::TestingBeaconStateBuilder, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    fn get_state(slot: u64) -> BeaconState<E> {
+        let spec = E::default_spec();
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(8, &spec);
+        let (mut state, _) = builder.build();
+        state.slot = Slot::new(slot);
+        state
+    }
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    #[test]
+    fn overflows_lowest_slots() {
+        let state_size = get_state(0).ssz_bytes_len();
+        let mut cache = PreFinalizationCache::new(state_size * 2);
+
+        assert!(cache.insert(root(2), get_state(16)).is_empty());
+        assert!(cache.insert(root(1), get_state(8)).is_empty());
+        assert_eq!(cache.size_bytes(), state_size * 2);
+
+        let overflow = cache.insert(root(3), get_state(24));
+        assert_eq!(overflow.len(), 1);
+        assert_eq!(overflow[0].0, root(1));
+        assert_eq!(overflow[0].1.slot, Slot::new(8));
+
+        assert!(cache.get(&root(1)).is_none());
+        assert!(cache.get(&root(2)).is_some());
+        assert!(cache.get(&root(3)).is_some());
+        assert_eq!(cache.size_bytes(), state_size * 2);
+    }
+
+    #[test]
+    fn prune_and_drain() {
+        let state_size = get_state(0).ssz_bytes_len();
+        let mut cache = PreFinalizationCache::new(state_size * 4);
+
+        for i in 1..=3 {
+            cache.insert(root(i), get_state(i * 8));
+        }
+
+        cache.prune(Slot::new(16));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&root(1)).is_none());
+        assert_eq!(cache.size_bytes(), state_size * 2);
+
+        assert!(cache.remove(&root(2)).is_some());
+        assert_eq!(cache.size_bytes(), state_size);
+
+        let drained = cache.drain();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].0, root(3));
+        assert!(cache.is_empty());
+        assert_eq!(cache.size_bytes(), 0);
+    }
+}
@@ -4,11 +4,12 @@
 
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use futures::stream::StreamExt;
-use slog::info;
+use slog::{debug, error, info};
 use slot_clock::SlotClock;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::{interval_at, Instant};
+use types::EthSpec;
 
 /// Spawns a timer service which periodically executes tasks for the beacon chain
 pub fn spawn_timer<T: BeaconChainTypes>(
@@ -16,22 +17,76 @@ pub fn spawn_timer<T: BeaconChainTypes>(
     beacon_chain: Arc<BeaconChain<T>>,
     milliseconds_per_slot: u64,
 ) -> Result<(), &'static str> {
-    let log = executor.log();
-    let start_instant = Instant::now()
-        + beacon_chain
-            .slot_clock
-            .duration_to_next_slot()
-            .ok_or_else(|| "slot_notifier unable to determine time to next slot")?;
+    let log = executor.log().clone();
+    let slot_duration = Duration::from_millis(milliseconds_per_slot);
+    let duration_to_next_slot = beacon_chain
+        .slot_clock
+        .duration_to_next_slot()
+        .ok_or_else(|| "slot_notifier unable to determine time to next slot")?;
+    let start_instant = Instant::now() + duration_to_next_slot;
+
+    // The state advance runs three-quarters of the way through each slot.
+    let advance_lead = slot_duration / 4;
+    let advance_start_instant = if duration_to_next_slot > advance_lead {
+        start_instant - advance_lead
+    } else {
+        start_instant + slot_duration - advance_lead
+    };
 
     // Warning: `interval_at` panics if `milliseconds_per_slot` = 0.
-    let mut interval = interval_at(start_instant, Duration::from_millis(milliseconds_per_slot));
+    let mut interval = interval_at(start_instant, slot_duration);
+    let per_slot_chain = beacon_chain.clone();
     let timer_future = async move {
         while interval.next().await.is_some() {
-            beacon_chain.per_slot_task();
+            per_slot_chain.per_slot_task();
+        }
+    };
+
+    // The op pool is persisted once per epoch, so that it survives a crash as well as a clean
+    // shutdown.
+    let mut persist_interval = interval_at(
+        start_instant,
+        slot_duration * T::EthSpec::slots_per_epoch() as u32,
+    );
+    let persist_executor = executor.clone();
+    let persist_chain = beacon_chain.clone();
+    let persist_log = log.clone();
+    let persist_future = async move {
+        while persist_interval.next().await.is_some() {
+            let beacon_chain = persist_chain.clone();
+            let log = persist_log.clone();
+            persist_executor.spawn_blocking(
+                move || {
+                    if let Err(e) = beacon_chain.persist_op_pool() {
+                        error!(log, "Failed to persist op pool"; "error" => format!("{:?}", e));
+                    }
+                },
+                "persist_op_pool",
+            );
+        }
+    };
+
+    let mut advance_interval = interval_at(advance_start_instant, slot_duration);
+    let advance_executor = executor.clone();
+    let advance_log = log.clone();
+    let state_advance_future = async move {
+        while advance_interval.next().await.is_some() {
+            let beacon_chain = beacon_chain.clone();
+            let log = advance_log.clone();
+            advance_executor.spawn_blocking(
+                move || {
+                    if let Err(e) = beacon_chain.advance_head_state() {
+                        debug!(log, "Unable to advance head state"; "error" => format!("{:?}", e));
+                    }
+                },
+                "state_advance",
+            );
         }
     };
 
     executor.spawn(timer_future, "timer");
+    executor.spawn(state_advance_future, "state_advance_timer");
+    executor.spawn(persist_future, "persist_op_pool_timer");
     info!(log, "Timer service started");
 
     Ok(())
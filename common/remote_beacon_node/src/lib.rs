@@ -4,7 +4,7 @@
 //! Presently, this is only used for testing but it _could_ become a user-facing library.
 
 use eth2_config::Eth2Config;
-use reqwest::{Client, ClientBuilder, Response, StatusCode};
+use reqwest::{header::ACCEPT, Client, ClientBuilder, Response, StatusCode};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use ssz::Encode;
 use std::marker::PhantomData;
@@ -17,10 +17,11 @@ use types::{
 use url::Url;
 
 pub use operation_pool::PersistedOperationPool;
-pub use proto_array_fork_choice::core::ProtoArray;
+pub use proto_array_fork_choice::{core::ProtoArray, ForkChoiceSnapshot};
 pub use rest_types::{
-    CanonicalHeadResponse, Committee, HeadBeaconBlock, Health, IndividualVotesRequest,
-    IndividualVotesResponse, SyncingResponse, ValidatorDutiesRequest, ValidatorDutyBytes,
+    AttestationRewardsData, BlockReward, CanonicalHeadResponse, Committee, GenericResponse,
+    HeadBeaconBlock, Health, IndividualVotesRequest, IndividualVotesResponse, LivenessData,
+    SyncingResponse, ValidatorDutiesRequest, ValidatorDutyBytes, ValidatorInclusionData,
     ValidatorRequest, ValidatorResponse, ValidatorSubscription,
 };
 
@@ -143,6 +144,43 @@ impl<E: EthSpec> HttpClient<E> {
         let success = error_for_status(response).await.map_err(Error::from)?;
         success.json::<T>().await.map_err(Error::from)
     }
+
+    /// Returns the response to a GET request to `path` (e.g., `eth/v1/beacon/states/head/root`) of the
+    /// standard Eth2 API.
+    pub async fn standard_get<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let url = self.url(path)?;
+        self.json_get(url, vec![]).await
+    }
+
+    /// Returns the SSZ bytes of the response to a GET request of `path` of the standard Eth2 API.
+    pub async fn standard_get_ssz(&self, path: &str) -> Result<Vec<u8>, Error> {
+        let url = self.url(path)?;
+        let response = self
+            .client
+            .get(&url.to_string())
+            .header(ACCEPT, "application/octet-stream")
+            .send()
+            .await
+            .map_err(Error::from)?;
+        let success = error_for_status(response).await.map_err(Error::from)?;
+        success
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(Error::from)
+    }
+
+    /// Returns the response to a POST request of `body` to `path` of the standard Eth2 API.
+    pub async fn standard_post<T: Serialize, U: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: T,
+    ) -> Result<U, Error> {
+        let url = self.url(path)?;
+        let response = self.json_post(url, body).await?;
+        let success = error_for_status(response).await.map_err(Error::from)?;
+        success.json::<U>().await.map_err(Error::from)
+    }
 }
 
 /// Returns an `Error` (with a description) if the `response` was not a 200-type success response.
@@ -347,6 +385,24 @@ impl<E: EthSpec> Validator<E> {
                 .map(|_| PublishStatus::Unknown),
         }
     }
+
+    /// Returns whether each of the validators in `indices` has been observed on the network
+    /// during `epoch`.
+    pub async fn get_liveness(
+        &self,
+        epoch: Epoch,
+        indices: &[u64],
+    ) -> Result<Vec<LivenessData>, Error> {
+        let client = self.0.clone();
+        let url = client.url(&format!("eth/v1/validator/liveness/{}", epoch))?;
+        let response = client.json_post::<_>(url, indices).await?;
+        let success = error_for_status(response).await.map_err(Error::from)?;
+        success
+            .json::<GenericResponse<Vec<LivenessData>>>()
+            .await
+            .map(|response| response.data)
+            .map_err(Error::from)
+    }
 }
 
 /// Provides the functions on the `/beacon` endpoint of the node.
@@ -368,6 +424,25 @@ impl<E: EthSpec> Beacon<E> {
         client.json_get(url, vec![]).await
     }
 
+    /// Returns the attestation rewards of the validators in `indices` for their attestations in
+    /// `epoch`, along with the ideal rewards of each effective balance. An empty `indices`
+    /// requests the rewards of all validators.
+    pub async fn get_attestation_rewards(
+        &self,
+        epoch: Epoch,
+        indices: &[u64],
+    ) -> Result<AttestationRewardsData, Error> {
+        let client = self.0.clone();
+        let url = client.url(&format!("eth/v1/beacon/rewards/attestations/{}", epoch))?;
+        let response = client.json_post::<_>(url, indices).await?;
+        let success = error_for_status(response).await.map_err(Error::from)?;
+        success
+            .json::<GenericResponse<AttestationRewardsData>>()
+            .await
+            .map(|response| response.data)
+            .map_err(Error::from)
+    }
+
     /// Returns the genesis validators root.
     pub async fn get_genesis_validators_root(&self) -> Result<Hash256, Error> {
         let client = self.0.clone();
@@ -644,6 +719,13 @@ impl<E: EthSpec> Advanced<E> {
         client.json_get(url, vec![]).await
     }
 
+    /// Gets a `ForkChoiceSnapshot` of the fork choice state from the node.
+    pub async fn get_fork_choice_snapshot(&self) -> Result<ForkChoiceSnapshot, Error> {
+        let client = self.0.clone();
+        let url = self.url("fork_choice/snapshot")?;
+        client.json_get(url, vec![]).await
+    }
+
     /// Gets the core `PersistedOperationPool` struct from the node.
     pub async fn get_operation_pool(&self) -> Result<PersistedOperationPool<E>, Error> {
         let client = self.0.clone();
@@ -686,6 +768,35 @@ impl<E: EthSpec> Consensus<E> {
         let url = self.url("vote_count")?;
         client.json_get(url, query_params).await
     }
+
+    /// Gets the `ValidatorInclusionData` of the validator with `validator_index` for `epoch`.
+    pub async fn get_validator_inclusion(
+        &self,
+        epoch: Epoch,
+        validator_index: u64,
+    ) -> Result<ValidatorInclusionData, Error> {
+        let client = self.0.clone();
+        let url = client.url(&format!(
+            "lighthouse/validator_inclusion/{}/{}",
+            epoch, validator_index
+        ))?;
+        client.json_get(url, vec![]).await
+    }
+
+    /// Gets the `BlockReward` of each canonical block from `start_slot` to `end_slot` inclusive.
+    pub async fn get_block_rewards(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<Vec<BlockReward>, Error> {
+        let client = self.0.clone();
+        let query_params = vec![
+            ("start_slot".into(), format!("{}", start_slot.as_u64())),
+            ("end_slot".into(), format!("{}", end_slot.as_u64())),
+        ];
+        let url = client.url("lighthouse/analysis/block_rewards")?;
+        client.json_get(url, query_params).await
+    }
 }
 
 #[derive(Deserialize)]
@@ -1,7 +1,44 @@
 use serde::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
 use state_processing::per_epoch_processing::ValidatorStatus;
-use types::{Epoch, PublicKeyBytes};
+use types::{Epoch, Hash256, PublicKeyBytes, Slot};
+
+/// Describes how a validator's attestation for an epoch was included in the chain.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
+pub struct ValidatorInclusionData {
+    /// The epoch of the attestation.
+    pub epoch: Epoch,
+    /// The index of the validator in state.validators.
+    pub validator_index: u64,
+    /// True if an attestation from the validator was included in a block.
+    pub is_included: bool,
+    /// The distance between the attestation slot and the slot of the first block which included
+    /// it, if any.
+    pub inclusion_distance: Option<u64>,
+    /// True if the validator voted for the block at the first slot of the epoch.
+    pub is_correct_target: bool,
+    /// True if the validator voted for the block at the attestation slot.
+    pub is_correct_head: bool,
+}
+
+/// The rewards of the proposer of a block for the operations it included, in Gwei.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
+pub struct BlockReward {
+    /// The root of the block.
+    pub block_root: Hash256,
+    /// The slot of the block.
+    pub slot: Slot,
+    /// The index of the validator which proposed the block.
+    pub proposer_index: u64,
+    /// The sum of the rewards below.
+    pub total: u64,
+    /// The rewards for including attestations from validators which had not been included yet.
+    pub attestations: u64,
+    /// The whistleblower rewards for including proposer slashings.
+    pub proposer_slashings: u64,
+    /// The whistleblower rewards for including attester slashings.
+    pub attester_slashings: u64,
+}
 
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
 pub struct IndividualVotesRequest {
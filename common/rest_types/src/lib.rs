@@ -5,6 +5,8 @@
 mod beacon;
 mod consensus;
 mod node;
+mod quoted;
+mod standard;
 mod validator;
 
 pub use beacon::{
@@ -16,6 +18,19 @@ pub use validator::{
     ValidatorDutiesRequest, ValidatorDuty, ValidatorDutyBytes, ValidatorSubscription,
 };
 
-pub use consensus::{IndividualVote, IndividualVotesRequest, IndividualVotesResponse};
+pub use consensus::{
+    BlockReward, IndividualVote, IndividualVotesRequest, IndividualVotesResponse,
+    ValidatorInclusionData,
+};
+
+pub use quoted::{QuotedU64, ValidatorIndices};
 
 pub use node::{Health, SyncingResponse, SyncingStatus};
+
+pub use standard::{
+    AttestationRewardsData, AttesterData, BeaconCommitteeSubscription, BlockId, ChainHeadData,
+    CommitteeData, DutiesResponse, FinalityCheckpointsData, ForkData, GenericResponse,
+    IdealAttestationRewards, IdentityData, LivenessData, MetaData, PeerCount, PeerData,
+    PeerDirection, PeerState, ProposerData, RootData, StateId, SyncingData,
+    TotalAttestationRewards, ValidatorBalanceData, ValidatorData, ValidatorId, ValidatorStatus,
+};
@@ -0,0 +1,266 @@
+//! Serde modules which encode integers as decimal strings (e.g., `"42"`), as required by the
+//! standard Eth2 Beacon Node API.
+//!
+//! Decoding accepts both decimal strings and JSON numbers.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Foo {
+//!     #[serde(with = "crate::quoted::quoted_u64")]
+//!     slot: Slot,
+//! }
+//! ```
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+
+struct U64Visitor;
+
+impl<'de> Visitor<'de> for U64Visitor {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an unsigned integer or a decimal string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+        Ok(value)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<u64, E> {
+        u64::try_from(value).map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+        value
+            .parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
+
+struct I64Visitor;
+
+impl<'de> Visitor<'de> for I64Visitor {
+    type Value = i64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a signed integer or a decimal string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<i64, E> {
+        i64::try_from(value).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<i64, E> {
+        Ok(value)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<i64, E> {
+        value
+            .parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
+
+/// Encodes a `u64` (or a type which converts to and from one, such as `Slot`) as a decimal
+/// string.
+pub mod quoted_u64 {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Copy + Into<u64>,
+        S: Serializer,
+    {
+        let value: u64 = (*value).into();
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<u64>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(U64Visitor).map(T::from)
+    }
+}
+
+/// Encodes an `i64` as a decimal string.
+pub mod quoted_i64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        deserializer.deserialize_any(I64Visitor)
+    }
+}
+
+/// Encodes a `Vec<u64>` as a list of decimal strings.
+pub mod quoted_u64_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(u64::to_string))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+        struct VecVisitor;
+
+        impl<'de> Visitor<'de> for VecVisitor {
+            type Value = Vec<u64>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a list of unsigned integers or decimal strings")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u64>, A::Error> {
+                let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(value) = seq.next_element::<QuotedU64>()? {
+                    values.push(value.0);
+                }
+                Ok(values)
+            }
+        }
+
+        deserializer.deserialize_seq(VecVisitor)
+    }
+}
+
+/// A `u64` which is encoded as a decimal string.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct QuotedU64(#[serde(with = "quoted_u64")] pub u64);
+
+/// A list of validator indices, as provided in the body of a request. The indices may be either
+/// decimal strings or numbers.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ValidatorIndices(#[serde(with = "quoted_u64_vec")] pub Vec<u64>);
+
+/// Encodes a `Checkpoint` with its epoch as a decimal string.
+pub mod quoted_checkpoint {
+    use super::*;
+    use types::{Checkpoint, Epoch, Hash256};
+
+    #[derive(Serialize, Deserialize)]
+    struct QuotedCheckpoint {
+        #[serde(with = "quoted_u64")]
+        epoch: Epoch,
+        root: Hash256,
+    }
+
+    pub fn serialize<S: Serializer>(value: &Checkpoint, serializer: S) -> Result<S::Ok, S::Error> {
+        QuotedCheckpoint {
+            epoch: value.epoch,
+            root: value.root,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Checkpoint, D::Error> {
+        let checkpoint = QuotedCheckpoint::deserialize(deserializer)?;
+        Ok(Checkpoint {
+            epoch: checkpoint.epoch,
+            root: checkpoint.root,
+        })
+    }
+}
+
+/// Encodes a `Fork` with its epoch as a decimal string.
+pub mod quoted_fork {
+    use super::*;
+    use types::utils::{fork_from_hex_str, fork_to_hex_str};
+    use types::{Epoch, Fork};
+
+    #[derive(Serialize, Deserialize)]
+    struct QuotedFork {
+        #[serde(
+            serialize_with = "fork_to_hex_str",
+            deserialize_with = "fork_from_hex_str"
+        )]
+        previous_version: [u8; 4],
+        #[serde(
+            serialize_with = "fork_to_hex_str",
+            deserialize_with = "fork_from_hex_str"
+        )]
+        current_version: [u8; 4],
+        #[serde(with = "quoted_u64")]
+        epoch: Epoch,
+    }
+
+    pub fn serialize<S: Serializer>(value: &Fork, serializer: S) -> Result<S::Ok, S::Error> {
+        QuotedFork {
+            previous_version: value.previous_version,
+            current_version: value.current_version,
+            epoch: value.epoch,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Fork, D::Error> {
+        let fork = QuotedFork::deserialize(deserializer)?;
+        Ok(Fork {
+            previous_version: fork.previous_version,
+            current_version: fork.current_version,
+            epoch: fork.epoch,
+        })
+    }
+}
+
+/// Encodes a `Validator` with its balance and epochs as decimal strings.
+pub mod quoted_validator {
+    use super::*;
+    use types::{Epoch, Hash256, PublicKeyBytes, Validator};
+
+    #[derive(Serialize, Deserialize)]
+    struct QuotedValidator {
+        pubkey: PublicKeyBytes,
+        withdrawal_credentials: Hash256,
+        #[serde(with = "quoted_u64")]
+        effective_balance: u64,
+        slashed: bool,
+        #[serde(with = "quoted_u64")]
+        activation_eligibility_epoch: Epoch,
+        #[serde(with = "quoted_u64")]
+        activation_epoch: Epoch,
+        #[serde(with = "quoted_u64")]
+        exit_epoch: Epoch,
+        #[serde(with = "quoted_u64")]
+        withdrawable_epoch: Epoch,
+    }
+
+    pub fn serialize<S: Serializer>(value: &Validator, serializer: S) -> Result<S::Ok, S::Error> {
+        QuotedValidator {
+            pubkey: value.pubkey.clone(),
+            withdrawal_credentials: value.withdrawal_credentials,
+            effective_balance: value.effective_balance,
+            slashed: value.slashed,
+            activation_eligibility_epoch: value.activation_eligibility_epoch,
+            activation_epoch: value.activation_epoch,
+            exit_epoch: value.exit_epoch,
+            withdrawable_epoch: value.withdrawable_epoch,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Validator, D::Error> {
+        let v = QuotedValidator::deserialize(deserializer)?;
+        Ok(Validator {
+            pubkey: v.pubkey,
+            withdrawal_credentials: v.withdrawal_credentials,
+            effective_balance: v.effective_balance,
+            slashed: v.slashed,
+            activation_eligibility_epoch: v.activation_eligibility_epoch,
+            activation_epoch: v.activation_epoch,
+            exit_epoch: v.exit_epoch,
+            withdrawable_epoch: v.withdrawable_epoch,
+        })
+    }
+}
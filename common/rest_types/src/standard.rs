@@ -0,0 +1,340 @@
+//! Types for the standard Eth2 Beacon Node API (i.e., the `/eth/v1/` endpoints).
+
+use crate::ValidatorSubscription;
+use bls::PublicKeyBytes;
+use serde::{Deserialize, Serialize};
+use types::{Checkpoint, CommitteeIndex, Epoch, Fork, Hash256, Slot, Validator};
+
+/// The wrapper around the payload of all responses.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenericResponse<T> {
+    pub data: T,
+}
+
+impl<T> From<T> for GenericResponse<T> {
+    fn from(data: T) -> Self {
+        Self { data }
+    }
+}
+
+/// Identifies a `BeaconState` in the canonical chain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateId {
+    Head,
+    Genesis,
+    Finalized,
+    Justified,
+    Slot(Slot),
+    Root(Hash256),
+}
+
+/// Identifies a `SignedBeaconBlock` in the canonical chain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockId {
+    Head,
+    Genesis,
+    Finalized,
+    Justified,
+    Slot(Slot),
+    Root(Hash256),
+}
+
+/// Identifies a validator, either by its index in the registry or by its public key.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidatorId {
+    Index(u64),
+    PublicKey(PublicKeyBytes),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RootData {
+    pub root: Hash256,
+}
+
+/// The `Fork` of a state, with its epoch as a decimal string.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ForkData(#[serde(with = "crate::quoted::quoted_fork")] pub Fork);
+
+/// A head of the fork choice block tree.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChainHeadData {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub slot: Slot,
+    pub root: Hash256,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FinalityCheckpointsData {
+    #[serde(with = "crate::quoted::quoted_checkpoint")]
+    pub previous_justified: Checkpoint,
+    #[serde(with = "crate::quoted::quoted_checkpoint")]
+    pub current_justified: Checkpoint,
+    #[serde(with = "crate::quoted::quoted_checkpoint")]
+    pub finalized: Checkpoint,
+}
+
+/// The identity of the local node on the p2p network.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IdentityData {
+    pub peer_id: String,
+    pub enr: String,
+    pub p2p_addresses: Vec<String>,
+    pub discovery_addresses: Vec<String>,
+    pub metadata: MetaData,
+}
+
+/// The p2p `MetaData` of the local node, with the `attnets` bitfield as a `0x` prefixed hex string.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetaData {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub seq_number: u64,
+    pub attnets: String,
+}
+
+/// The connection state of a peer, as defined by the Beacon Node API.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Disconnecting,
+}
+
+/// The direction of the connection with a peer, as defined by the Beacon Node API.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerDirection {
+    Inbound,
+    Outbound,
+}
+
+/// A peer known to the local node. The `direction` is unknown for peers that have never been
+/// connected.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeerData {
+    pub peer_id: String,
+    pub last_seen_p2p_address: Option<String>,
+    pub state: PeerState,
+    pub direction: Option<PeerDirection>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerCount {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub disconnected: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub connecting: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub connected: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub disconnecting: u64,
+}
+
+/// The sync status of the local node. The `sync_distance` is the number of slots the head is
+/// behind the target of the sync.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncingData {
+    pub is_syncing: bool,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub head_slot: Slot,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub sync_distance: Slot,
+}
+
+/// The status of a validator, as defined by the Beacon Node API.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidatorStatus {
+    PendingInitialized,
+    PendingQueued,
+    ActiveOngoing,
+    ActiveExiting,
+    ActiveSlashed,
+    ExitedUnslashed,
+    ExitedSlashed,
+    WithdrawalPossible,
+    WithdrawalDone,
+}
+
+impl ValidatorStatus {
+    /// Returns the status of `validator` at `epoch`, given its `balance`.
+    pub fn from_validator(
+        validator: &Validator,
+        balance: u64,
+        epoch: Epoch,
+        far_future_epoch: Epoch,
+    ) -> Self {
+        if validator.is_withdrawable_at(epoch) {
+            if balance == 0 {
+                ValidatorStatus::WithdrawalDone
+            } else {
+                ValidatorStatus::WithdrawalPossible
+            }
+        } else if validator.is_exited_at(epoch) {
+            if validator.slashed {
+                ValidatorStatus::ExitedSlashed
+            } else {
+                ValidatorStatus::ExitedUnslashed
+            }
+        } else if validator.is_active_at(epoch) {
+            if validator.exit_epoch == far_future_epoch {
+                ValidatorStatus::ActiveOngoing
+            } else if validator.slashed {
+                ValidatorStatus::ActiveSlashed
+            } else {
+                ValidatorStatus::ActiveExiting
+            }
+        } else if validator.activation_eligibility_epoch == far_future_epoch {
+            ValidatorStatus::PendingInitialized
+        } else {
+            ValidatorStatus::PendingQueued
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorData {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub index: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub balance: u64,
+    pub status: ValidatorStatus,
+    #[serde(with = "crate::quoted::quoted_validator")]
+    pub validator: Validator,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorBalanceData {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub index: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub balance: u64,
+}
+
+/// The response to a request for validator duties.
+///
+/// The `dependent_root` is the root of the block that the duties were computed from. If the block
+/// at the `dependent_root` is re-orged out of the chain the duties must be requested again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DutiesResponse<T> {
+    pub dependent_root: Hash256,
+    pub data: T,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AttesterData {
+    pub pubkey: PublicKeyBytes,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub validator_index: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub committee_index: CommitteeIndex,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub committee_length: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub committees_at_slot: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub validator_committee_index: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub slot: Slot,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProposerData {
+    pub pubkey: PublicKeyBytes,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub validator_index: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub slot: Slot,
+}
+
+/// Whether a validator has been observed on the network during an epoch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LivenessData {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub index: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub epoch: Epoch,
+    pub is_live: bool,
+}
+
+/// The attestation rewards of a validator with `effective_balance`, had it attested to the
+/// correct source, target and head and been included after a single slot. Penalties are
+/// negative.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IdealAttestationRewards {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub effective_balance: u64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub head: i64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub target: i64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub source: i64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub inclusion_delay: i64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub inactivity: i64,
+}
+
+/// The attestation rewards of a validator, as applied at the end of the epoch following the
+/// epoch of the attestation. Penalties are negative.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TotalAttestationRewards {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub validator_index: u64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub head: i64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub target: i64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub source: i64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub inclusion_delay: i64,
+    #[serde(with = "crate::quoted::quoted_i64")]
+    pub inactivity: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AttestationRewardsData {
+    pub ideal_rewards: Vec<IdealAttestationRewards>,
+    pub total_rewards: Vec<TotalAttestationRewards>,
+}
+
+/// A notification that a validator has an attestation duty, such that the beacon node can
+/// subscribe to the relevant attestation subnet.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BeaconCommitteeSubscription {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub validator_index: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub committee_index: CommitteeIndex,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub committees_at_slot: u64,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub slot: Slot,
+    pub is_aggregator: bool,
+}
+
+impl From<BeaconCommitteeSubscription> for ValidatorSubscription {
+    fn from(subscription: BeaconCommitteeSubscription) -> Self {
+        ValidatorSubscription {
+            validator_index: subscription.validator_index,
+            attestation_committee_index: subscription.committee_index,
+            committee_count_at_slot: Some(subscription.committees_at_slot),
+            slot: subscription.slot,
+            is_aggregator: subscription.is_aggregator,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommitteeData {
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub index: CommitteeIndex,
+    #[serde(with = "crate::quoted::quoted_u64")]
+    pub slot: Slot,
+    #[serde(with = "crate::quoted::quoted_u64_vec")]
+    pub validators: Vec<u64>,
+}
@@ -64,6 +64,10 @@ pub struct ValidatorSubscription {
     /// The index of the committee within `slot` of which the validator is a member. Used by the
     /// beacon node to quickly evaluate the associated `SubnetId`.
     pub attestation_committee_index: CommitteeIndex,
+    /// The number of committees in `slot`, if known. Subscriptions to committees beyond this
+    /// number are ignored.
+    #[serde(default)]
+    pub committee_count_at_slot: Option<u64>,
     /// The slot in which to subscribe.
     pub slot: Slot,
     /// If true, the validator is an aggregator and the beacon node should aggregate attestations
@@ -44,6 +44,10 @@ impl SlotClock for SystemTimeSlotClock {
         self.clock.slot_duration()
     }
 
+    fn start_of(&self, slot: Slot) -> Option<Duration> {
+        self.clock.start_of(slot)
+    }
+
     fn duration_to_slot(&self, slot: Slot) -> Option<Duration> {
         let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
         self.clock.duration_to_slot(slot, now)
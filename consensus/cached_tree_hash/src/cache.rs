@@ -155,6 +155,26 @@ impl TreeHashCache {
             .unwrap_or_else(|| Hash256::from_slice(&ZERO_HASHES[self.depth]))
     }
 
+    /// Get the depth of this cache, such that it has a capacity for 2^depth leaves.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Get the node at `index` of `layer` (where layer `0` is the root and layer `self.depth` the
+    /// leaves), without doing any updates/computation.
+    ///
+    /// Nodes which are not stored in the cache are padding and are therefore zero hashes.
+    pub fn node(&self, arena: &CacheArena, layer: usize, index: usize) -> Result<Hash256, Error> {
+        if layer > self.depth || index >= 1 << layer {
+            return Err(Error::NodeOutOfBounds { layer, index });
+        }
+
+        Ok(self.layers[layer]
+            .get(arena, index)?
+            .copied()
+            .unwrap_or_else(|| Hash256::from_slice(&ZERO_HASHES[self.depth - layer])))
+    }
+
     pub fn leaves(&mut self) -> &mut CacheArenaAllocation {
         &mut self.layers[self.depth]
     }
@@ -219,6 +239,52 @@ mod test {
             .expect("should calculate root");
     }
 
+    #[test]
+    fn nodes_of_unbalanced_tree() {
+        let arena = &mut CacheArena::default();
+
+        let depth = 3;
+        let leaves = (1..=5u8).map(|i| [i; BYTES_PER_CHUNK]).collect::<Vec<_>>();
+
+        let mut cache = TreeHashCache::new(arena, depth, leaves.len());
+        let root = cache
+            .recalculate_merkle_root(arena, leaves.clone().into_iter())
+            .expect("should calculate root");
+
+        assert_eq!(cache.node(arena, 0, 0), Ok(root));
+        assert_eq!(
+            cache.node(arena, depth, 4),
+            Ok(Hash256::from_slice(&leaves[4]))
+        );
+        // The right-most leaves and their parent are padding.
+        assert_eq!(
+            cache.node(arena, depth, 7),
+            Ok(Hash256::from_slice(&ZERO_HASHES[0]))
+        );
+        assert_eq!(
+            cache.node(arena, 2, 3),
+            Ok(Hash256::from_slice(&ZERO_HASHES[1]))
+        );
+        assert_eq!(
+            cache.node(arena, 1, 1),
+            Ok(Hash256::from_slice(&hash32_concat(
+                cache.node(arena, 2, 2).unwrap().as_bytes(),
+                &ZERO_HASHES[1],
+            )))
+        );
+        assert_eq!(
+            cache.node(arena, 2, 4),
+            Err(Error::NodeOutOfBounds { layer: 2, index: 4 })
+        );
+        assert_eq!(
+            cache.node(arena, depth + 1, 0),
+            Err(Error::NodeOutOfBounds {
+                layer: depth + 1,
+                index: 0
+            })
+        );
+    }
+
     #[test]
     fn test_node_per_layer_unbalanced_tree() {
         assert_eq!(nodes_per_layer(0, 3, 5), 1);
@@ -25,6 +25,11 @@ pub enum Error {
     CacheArenaError(cache_arena::Error),
     /// Unable to find left index in Merkle tree.
     MissingLeftIdx(usize),
+    /// The requested node lies outside of the Merkle tree.
+    NodeOutOfBounds {
+        layer: usize,
+        index: usize,
+    },
 }
 
 impl From<cache_arena::Error> for Error {
@@ -5,6 +5,10 @@ use safe_arith::ArithError;
 
 const MAX_TREE_DEPTH: usize = 32;
 const EMPTY_SLICE: &[H256] = &[];
+/// The minimum number of leaves for which the two subtrees of a node are hashed in parallel.
+///
+/// Below this, the overhead of scheduling the work on another thread outweighs the hashing.
+const PARALLEL_HASHING_THRESHOLD: usize = 1_024;
 
 lazy_static! {
     /// Zero nodes to act as "synthetic" left and right subtrees of other zero nodes.
@@ -27,6 +31,11 @@ pub enum MerkleTree {
     ///
     /// It represents a Merkle tree of 2^depth zero leaves.
     Zero(usize),
+    /// Full subtree with the hash of its content, without any of its leaves.
+    ///
+    /// It represents a subtree of a finalized deposit tree snapshot. Leaves can not be pushed into
+    /// it, nor can proofs be generated through it.
+    Finalized(H256),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -41,6 +50,10 @@ pub enum MerkleTreeError {
     DepthTooSmall,
     // Overflow occurred
     ArithError,
+    // Trying to generate a proof through a finalized subtree
+    ProofEncounteredFinalizedNode,
+    // The finalized hashes do not describe a tree with the given number of leaves
+    InvalidFinalizedSnapshot,
 }
 
 impl MerkleTree {
@@ -66,8 +79,17 @@ impl MerkleTree {
                     leaves.split_at(subtree_capacity)
                 };
 
-                let left_subtree = MerkleTree::create(left_leaves, depth - 1);
-                let right_subtree = MerkleTree::create(right_leaves, depth - 1);
+                let (left_subtree, right_subtree) = if leaves.len() >= PARALLEL_HASHING_THRESHOLD {
+                    rayon::join(
+                        || MerkleTree::create(left_leaves, depth - 1),
+                        || MerkleTree::create(right_leaves, depth - 1),
+                    )
+                } else {
+                    (
+                        MerkleTree::create(left_leaves, depth - 1),
+                        MerkleTree::create(right_leaves, depth - 1),
+                    )
+                };
                 let hash = H256::from_slice(&hash32_concat(
                     left_subtree.hash().as_bytes(),
                     right_subtree.hash().as_bytes(),
@@ -78,6 +100,89 @@ impl MerkleTree {
         }
     }
 
+    /// Create a new Merkle tree from the hashes of the finalized subtrees of a tree with
+    /// `deposit_count` leaves.
+    ///
+    /// The `finalized` hashes must be the roots of the largest full subtrees containing the
+    /// leaves, from left to right (i.e., as returned by `Self::finalized_hashes`).
+    pub fn from_finalized_snapshot(
+        finalized: &[H256],
+        deposit_count: usize,
+        depth: usize,
+    ) -> Result<Self, MerkleTreeError> {
+        use MerkleTree::*;
+
+        let capacity = 2usize.pow(depth as u32);
+
+        match finalized.split_first() {
+            None if deposit_count == 0 => Ok(Zero(depth)),
+            Some((&hash, rest)) if deposit_count == capacity && rest.is_empty() => {
+                Ok(Finalized(hash))
+            }
+            Some((&hash, rest)) if depth > 0 && deposit_count < capacity => {
+                let subtree_capacity = capacity / 2;
+                let (left_subtree, right_subtree) = if deposit_count > subtree_capacity {
+                    (
+                        Finalized(hash),
+                        MerkleTree::from_finalized_snapshot(
+                            rest,
+                            deposit_count - subtree_capacity,
+                            depth - 1,
+                        )?,
+                    )
+                } else {
+                    (
+                        MerkleTree::from_finalized_snapshot(finalized, deposit_count, depth - 1)?,
+                        Zero(depth - 1),
+                    )
+                };
+                let hash = H256::from_slice(&hash32_concat(
+                    left_subtree.hash().as_bytes(),
+                    right_subtree.hash().as_bytes(),
+                ));
+
+                Ok(Node(hash, Box::new(left_subtree), Box::new(right_subtree)))
+            }
+            _ => Err(MerkleTreeError::InvalidFinalizedSnapshot),
+        }
+    }
+
+    /// Return the roots of the largest full subtrees containing the first `deposit_count` leaves,
+    /// from left to right.
+    ///
+    /// These hashes are sufficient to restore the tree with `Self::from_finalized_snapshot` and
+    /// continue pushing leaves on to it.
+    pub fn finalized_hashes(
+        &self,
+        deposit_count: usize,
+        depth: usize,
+    ) -> Result<Vec<H256>, MerkleTreeError> {
+        let capacity = 2usize.pow(depth as u32);
+
+        if deposit_count == 0 {
+            Ok(vec![])
+        } else if deposit_count == capacity {
+            Ok(vec![self.hash()])
+        } else if deposit_count > capacity || depth == 0 {
+            Err(MerkleTreeError::InvalidFinalizedSnapshot)
+        } else {
+            let (left, right) = self
+                .left_and_right_branches()
+                .ok_or(MerkleTreeError::ProofEncounteredFinalizedNode)?;
+            let subtree_capacity = capacity / 2;
+
+            if deposit_count > subtree_capacity {
+                let mut hashes = vec![left.hash()];
+                hashes.append(
+                    &mut right.finalized_hashes(deposit_count - subtree_capacity, depth - 1)?,
+                );
+                Ok(hashes)
+            } else {
+                left.finalized_hashes(deposit_count, depth - 1)
+            }
+        }
+    }
+
     /// Push an element in the MerkleTree.
     /// MerkleTree and depth must be correct, as the algorithm expects valid data.
     pub fn push_leaf(&mut self, elem: H256, depth: usize) -> Result<(), MerkleTreeError> {
@@ -90,6 +195,7 @@ impl MerkleTree {
 
         match self {
             Leaf(_) => return Err(MerkleTreeError::LeafReached),
+            Finalized(_) => return Err(MerkleTreeError::MerkleTreeFull),
             Zero(_) => {
                 mem::replace(self, MerkleTree::create(&[elem], depth));
             }
@@ -98,9 +204,11 @@ impl MerkleTree {
                 let right: &mut MerkleTree = &mut *right;
                 match (&*left, &*right) {
                     // Tree is full
-                    (Leaf(_), Leaf(_)) => return Err(MerkleTreeError::MerkleTreeFull),
+                    (Leaf(_), Leaf(_)) | (Finalized(_), Leaf(_)) | (Finalized(_), Finalized(_)) => {
+                        return Err(MerkleTreeError::MerkleTreeFull)
+                    }
                     // There is a right node so insert in right node
-                    (Node(_, _, _), Node(_, _, _)) => {
+                    (Node(_, _, _), Node(_, _, _)) | (Finalized(_), Node(_, _, _)) => {
                         if let Err(e) = right.push_leaf(elem, depth - 1) {
                             return Err(e);
                         }
@@ -110,7 +218,7 @@ impl MerkleTree {
                         mem::replace(left, MerkleTree::create(&[elem], depth - 1));
                     }
                     // Leaf on left branch and zero on right branch, insert on right side
-                    (Leaf(_), Zero(_)) => {
+                    (Leaf(_), Zero(_)) | (Finalized(_), Zero(_)) => {
                         mem::replace(right, MerkleTree::create(&[elem], depth - 1));
                     }
                     // Try inserting on the left node -> if it fails because it is full, insert in right side.
@@ -137,19 +245,119 @@ impl MerkleTree {
         Ok(())
     }
 
+    /// Push a batch of `leaves` into a tree which already contains `leaf_count` leaves.
+    ///
+    /// Produces the same tree as pushing the leaves one at a time with `Self::push_leaf`, but each
+    /// internal node is only hashed once, and large disjoint subtrees are hashed in parallel.
+    pub fn push_leaves(
+        &mut self,
+        leaves: &[H256],
+        leaf_count: usize,
+        depth: usize,
+    ) -> Result<(), MerkleTreeError> {
+        use MerkleTree::*;
+
+        if leaves.is_empty() {
+            return Ok(());
+        }
+
+        let capacity = 2usize.pow(depth as u32);
+        if leaf_count
+            .checked_add(leaves.len())
+            .map_or(true, |total| total > capacity)
+        {
+            return Err(MerkleTreeError::MerkleTreeFull);
+        }
+
+        match self {
+            Leaf(_) | Finalized(_) => Err(MerkleTreeError::MerkleTreeFull),
+            Zero(_) => {
+                *self = MerkleTree::create(leaves, depth);
+                Ok(())
+            }
+            Node(ref mut hash, ref mut left, ref mut right) => {
+                let subtree_capacity = capacity / 2;
+                if leaf_count >= subtree_capacity {
+                    right.push_leaves(leaves, leaf_count - subtree_capacity, depth - 1)?;
+                } else {
+                    let split = std::cmp::min(subtree_capacity - leaf_count, leaves.len());
+                    let (left_leaves, right_leaves) = leaves.split_at(split);
+                    let (left_result, right_result) = if leaves.len() >= PARALLEL_HASHING_THRESHOLD
+                    {
+                        rayon::join(
+                            || left.push_leaves(left_leaves, leaf_count, depth - 1),
+                            || right.push_leaves(right_leaves, 0, depth - 1),
+                        )
+                    } else {
+                        (
+                            left.push_leaves(left_leaves, leaf_count, depth - 1),
+                            right.push_leaves(right_leaves, 0, depth - 1),
+                        )
+                    };
+                    left_result?;
+                    right_result?;
+                }
+                hash.assign_from_slice(&hash32_concat(
+                    left.hash().as_bytes(),
+                    right.hash().as_bytes(),
+                ));
+                Ok(())
+            }
+        }
+    }
+
+    /// Return the root hash of this tree as it was when it contained only its first
+    /// `leaf_count` leaves.
+    ///
+    /// Returns an error if the first `leaf_count` leaves end within a finalized subtree.
+    pub fn hash_of_first(&self, leaf_count: usize, depth: usize) -> Result<H256, MerkleTreeError> {
+        let capacity = 2usize.pow(depth as u32);
+
+        if leaf_count == 0 {
+            Ok(H256::from_slice(&ZERO_HASHES[depth]))
+        } else if leaf_count == capacity {
+            Ok(self.hash())
+        } else if leaf_count > capacity || depth == 0 {
+            Err(MerkleTreeError::Invalid)
+        } else {
+            let (left, right) = self
+                .left_and_right_branches()
+                .ok_or(MerkleTreeError::ProofEncounteredFinalizedNode)?;
+            let subtree_capacity = capacity / 2;
+
+            let (left_hash, right_hash) = if leaf_count > subtree_capacity {
+                (
+                    left.hash(),
+                    right.hash_of_first(leaf_count - subtree_capacity, depth - 1)?,
+                )
+            } else {
+                (
+                    left.hash_of_first(leaf_count, depth - 1)?,
+                    H256::from_slice(&ZERO_HASHES[depth - 1]),
+                )
+            };
+
+            Ok(H256::from_slice(&hash32_concat(
+                left_hash.as_bytes(),
+                right_hash.as_bytes(),
+            )))
+        }
+    }
+
     /// Retrieve the root hash of this Merkle tree.
     pub fn hash(&self) -> H256 {
         match *self {
             MerkleTree::Leaf(h) => h,
             MerkleTree::Node(h, _, _) => h,
             MerkleTree::Zero(depth) => H256::from_slice(&ZERO_HASHES[depth]),
+            MerkleTree::Finalized(h) => h,
         }
     }
 
     /// Get a reference to the left and right subtrees if they exist.
     pub fn left_and_right_branches(&self) -> Option<(&Self, &Self)> {
         match *self {
-            MerkleTree::Leaf(_) | MerkleTree::Zero(0) => None,
+            MerkleTree::Leaf(_) | MerkleTree::Zero(0) | MerkleTree::Finalized(_) => None,
             MerkleTree::Node(_, ref l, ref r) => Some((l, r)),
             MerkleTree::Zero(depth) => Some((&ZERO_NODES[depth - 1], &ZERO_NODES[depth - 1])),
         }
@@ -167,14 +375,23 @@ impl MerkleTree {
     ///
     /// The Merkle proof is in "bottom-up" order, starting with a leaf node
     /// and moving up the tree. Its length will be exactly equal to `depth`.
-    pub fn generate_proof(&self, index: usize, depth: usize) -> (H256, Vec<H256>) {
+    ///
+    /// Returns an error if the leaf at `index` is within a finalized subtree.
+    pub fn generate_proof(
+        &self,
+        index: usize,
+        depth: usize,
+    ) -> Result<(H256, Vec<H256>), MerkleTreeError> {
         let mut proof = vec![];
         let mut current_node = self;
         let mut current_depth = depth;
         while current_depth > 0 {
             let ith_bit = (index >> (current_depth - 1)) & 0x01;
-            // Note: unwrap is safe because leaves are only ever constructed at depth == 0.
-            let (left, right) = current_node.left_and_right_branches().unwrap();
+            // Note: leaves are only ever constructed at depth == 0, so only a finalized node can
+            // be missing its branches.
+            let (left, right) = current_node
+                .left_and_right_branches()
+                .ok_or(MerkleTreeError::ProofEncounteredFinalizedNode)?;
 
             // Go right, include the left branch in the proof.
             if ith_bit == 1 {
@@ -187,13 +404,17 @@ impl MerkleTree {
             current_depth -= 1;
         }
 
+        if let MerkleTree::Finalized(_) = current_node {
+            return Err(MerkleTreeError::ProofEncounteredFinalizedNode);
+        }
+
         debug_assert_eq!(proof.len(), depth);
         debug_assert!(current_node.is_leaf());
 
         // Put proof in bottom-up order.
         proof.reverse();
 
-        (current_node.hash(), proof)
+        Ok((current_node.hash(), proof))
     }
 }
 
@@ -261,7 +482,9 @@ mod tests {
         let merkle_root = merkle_tree.hash();
 
         let proofs_ok = (0..leaves.len()).all(|i| {
-            let (leaf, branch) = merkle_tree.generate_proof(i, depth);
+            let (leaf, branch) = merkle_tree
+                .generate_proof(i, depth)
+                .expect("should generate proof");
             leaf == leaves[i] && verify_merkle_proof(leaf, &branch, depth, i, merkle_root)
         });
 
@@ -280,13 +503,150 @@ mod tests {
 
         let proofs_ok = leaves.into_iter().enumerate().all(|(i, leaf)| {
             assert_eq!(merkle_tree.push_leaf(leaf, depth), Ok(()));
-            let (stored_leaf, branch) = merkle_tree.generate_proof(i, depth);
+            let (stored_leaf, branch) = merkle_tree
+                .generate_proof(i, depth)
+                .expect("should generate proof");
             stored_leaf == leaf && verify_merkle_proof(leaf, &branch, depth, i, merkle_tree.hash())
         });
 
         TestResult::from_bool(proofs_ok)
     }
 
+    /// Check that pushing a batch of leaves on to a tree produces the same tree as creating it
+    /// from all of the leaves, and that the roots of its prefixes are those of smaller trees.
+    #[quickcheck]
+    fn quickcheck_push_leaves_and_hash_of_first(
+        int_leaves: Vec<u64>,
+        split: usize,
+        depth: usize,
+    ) -> TestResult {
+        if depth > MAX_TREE_DEPTH
+            || int_leaves.len() > 2usize.pow(depth as u32)
+            || split > int_leaves.len()
+        {
+            return TestResult::discard();
+        }
+
+        let leaves: Vec<_> = int_leaves.into_iter().map(H256::from_low_u64_be).collect();
+        let mut merkle_tree = MerkleTree::create(&leaves[..split], depth);
+        assert_eq!(
+            merkle_tree.push_leaves(&leaves[split..], split, depth),
+            Ok(())
+        );
+
+        let prefix_hashes_ok = (0..=leaves.len()).all(|i| {
+            merkle_tree.hash_of_first(i, depth)
+                == Ok(MerkleTree::create(&leaves[..i], depth).hash())
+        });
+
+        TestResult::from_bool(merkle_tree == MerkleTree::create(&leaves, depth) && prefix_hashes_ok)
+    }
+
+    #[test]
+    fn push_leaves_in_parallel() {
+        let depth = 16;
+        let leaves: Vec<_> = (0..5_000).map(H256::from_low_u64_be).collect();
+
+        let mut merkle_tree = MerkleTree::create(&leaves[..1_000], depth);
+        for (i, leaf) in leaves.iter().enumerate().skip(1_000).take(3) {
+            assert_eq!(merkle_tree.push_leaf(*leaf, depth), Ok(()));
+            assert_eq!(
+                merkle_tree.hash_of_first(i, depth),
+                Ok(MerkleTree::create(&leaves[..i], depth).hash())
+            );
+        }
+        assert_eq!(
+            merkle_tree.push_leaves(&leaves[1_003..], 1_003, depth),
+            Ok(())
+        );
+        assert_eq!(merkle_tree, MerkleTree::create(&leaves, depth));
+
+        // The batch must fit in the tree.
+        let mut small_tree = MerkleTree::create(&leaves[..2], 2);
+        assert_eq!(
+            small_tree.push_leaves(&leaves[2..5], 2, 2),
+            Err(MerkleTreeError::MerkleTreeFull)
+        );
+        assert_eq!(small_tree, MerkleTree::create(&leaves[..2], 2));
+    }
+
+    /// Check that a MerkleTree restored from the finalized hashes of its first `split` leaves has
+    /// the same root and can generate valid proofs for all subsequently pushed leaves.
+    #[quickcheck]
+    fn quickcheck_finalized_snapshot_and_verify(
+        int_leaves: Vec<u64>,
+        split: usize,
+        depth: usize,
+    ) -> TestResult {
+        if depth == 0
+            || depth > MAX_TREE_DEPTH
+            || int_leaves.len() > 2usize.pow(depth as u32)
+            || split > int_leaves.len()
+        {
+            return TestResult::discard();
+        }
+
+        let leaves: Vec<_> = int_leaves.into_iter().map(H256::from_low_u64_be).collect();
+        let finalized_tree = MerkleTree::create(&leaves[..split], depth);
+        let finalized = finalized_tree
+            .finalized_hashes(split, depth)
+            .expect("should get finalized hashes");
+
+        let mut merkle_tree = MerkleTree::from_finalized_snapshot(&finalized, split, depth)
+            .expect("should restore tree");
+        if merkle_tree.hash() != finalized_tree.hash() {
+            return TestResult::failed();
+        }
+
+        for leaf in &leaves[split..] {
+            assert_eq!(merkle_tree.push_leaf(*leaf, depth), Ok(()));
+        }
+
+        let finalized_proofs_fail = (0..split).all(|i| {
+            merkle_tree.generate_proof(i, depth)
+                == Err(MerkleTreeError::ProofEncounteredFinalizedNode)
+        });
+        let proofs_ok = (split..leaves.len()).all(|i| {
+            let (leaf, branch) = merkle_tree
+                .generate_proof(i, depth)
+                .expect("should generate proof");
+            leaf == leaves[i] && verify_merkle_proof(leaf, &branch, depth, i, merkle_tree.hash())
+        });
+
+        TestResult::from_bool(
+            merkle_tree.hash() == MerkleTree::create(&leaves, depth).hash()
+                && finalized_proofs_fail
+                && proofs_ok,
+        )
+    }
+
+    #[test]
+    fn invalid_finalized_snapshot() {
+        let depth = 2;
+        let leaf = H256::from([0xAA; 32]);
+
+        // Three leaves require two finalized subtrees (of two and one leaves).
+        assert_eq!(
+            MerkleTree::from_finalized_snapshot(&[leaf], 3, depth),
+            Err(MerkleTreeError::InvalidFinalizedSnapshot)
+        );
+        assert_eq!(
+            MerkleTree::from_finalized_snapshot(&[leaf, leaf, leaf], 3, depth),
+            Err(MerkleTreeError::InvalidFinalizedSnapshot)
+        );
+        // An empty tree has no finalized subtrees.
+        assert_eq!(
+            MerkleTree::from_finalized_snapshot(&[leaf], 0, depth),
+            Err(MerkleTreeError::InvalidFinalizedSnapshot)
+        );
+        // The tree can not hold more than 2^depth leaves.
+        assert_eq!(
+            MerkleTree::from_finalized_snapshot(&[leaf], 5, depth),
+            Err(MerkleTreeError::InvalidFinalizedSnapshot)
+        );
+        assert!(MerkleTree::from_finalized_snapshot(&[leaf, leaf], 3, depth).is_ok());
+    }
+
     #[test]
     fn sparse_zero_correct() {
         let depth = 2;
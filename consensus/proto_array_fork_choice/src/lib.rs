@@ -2,11 +2,14 @@ mod error;
 pub mod fork_choice_test_definition;
 mod proto_array;
 mod proto_array_fork_choice;
+mod snapshot;
 mod ssz_container;
 
 pub use crate::proto_array_fork_choice::ProtoArrayForkChoice;
+pub use crate::snapshot::{ForkChoiceSnapshot, HypotheticalVote};
 pub use error::Error;
 
 pub mod core {
-    pub use super::proto_array::ProtoArray;
+    pub use super::proto_array::{BlockTreeNode, ProtoArray};
+    pub use super::proto_array_fork_choice::VoteTracker;
 }
@@ -21,7 +21,21 @@ pub struct ProtoNode {
     best_descendant: Option<usize>,
 }
 
-#[derive(PartialEq, Debug, Serialize, Deserialize)]
+/// A block of the tree stored by `ProtoArray`, referencing other blocks by root rather than by
+/// index such that it can be used without knowledge of the array layout (e.g., to visualize
+/// forks).
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct BlockTreeNode {
+    pub root: Hash256,
+    pub parent_root: Option<Hash256>,
+    pub slot: Slot,
+    pub justified_epoch: Epoch,
+    pub finalized_epoch: Epoch,
+    pub weight: u64,
+    pub best_descendant_root: Option<Hash256>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ProtoArray {
     /// Do not attempt to prune the tree unless it has at least this many nodes. Small prunes
     /// simply waste time.
@@ -408,6 +422,28 @@ impl ProtoArray {
                 || self.finalized_epoch == Epoch::new(0))
     }
 
+    /// Returns all the blocks in the tree. Parents always precede their children.
+    pub fn block_tree(&self) -> Vec<BlockTreeNode> {
+        let root_at = |index: Option<usize>| {
+            index
+                .and_then(|index| self.nodes.get(index))
+                .map(|node| node.root)
+        };
+
+        self.nodes
+            .iter()
+            .map(|node| BlockTreeNode {
+                root: node.root,
+                parent_root: root_at(node.parent),
+                slot: node.slot,
+                justified_epoch: node.justified_epoch,
+                finalized_epoch: node.finalized_epoch,
+                weight: node.weight,
+                best_descendant_root: root_at(node.best_descendant),
+            })
+            .collect()
+    }
+
     /// Return a reverse iterator over the nodes which comprise the chain ending at `block_root`.
     pub fn iter_nodes<'a>(&'a self, block_root: &Hash256) -> Iter<'a> {
         let next_node_index = self.indices.get(block_root).copied();
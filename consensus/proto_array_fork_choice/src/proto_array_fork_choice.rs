@@ -1,16 +1,18 @@
 use crate::error::Error;
-use crate::proto_array::ProtoArray;
+use crate::proto_array::{BlockTreeNode, ProtoArray};
+use crate::snapshot::ForkChoiceSnapshot;
 use crate::ssz_container::SszContainer;
 use parking_lot::{RwLock, RwLockReadGuard};
+use serde_derive::{Deserialize, Serialize};
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use std::collections::HashMap;
 use std::ptr;
-use types::{Epoch, Hash256, Slot};
+use types::{Checkpoint, Epoch, Hash256, Slot};
 
 pub const DEFAULT_PRUNE_THRESHOLD: usize = 256;
 
-#[derive(Default, PartialEq, Clone, Encode, Decode)]
+#[derive(Default, PartialEq, Clone, Debug, Encode, Decode, Serialize, Deserialize)]
 pub struct VoteTracker {
     current_root: Hash256,
     next_root: Hash256,
@@ -219,6 +221,11 @@ impl ProtoArrayForkChoice {
         }
     }
 
+    /// Returns all the blocks known to fork choice, with parents preceding their children.
+    pub fn block_tree(&self) -> Vec<BlockTreeNode> {
+        self.proto_array.read().block_tree()
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
         SszContainer::from(self).as_ssz_bytes()
     }
@@ -229,6 +236,24 @@ impl ProtoArrayForkChoice {
             .map_err(|e| format!("Failed to decode ProtoArrayForkChoice: {:?}", e))
     }
 
+    /// Returns a copy of the complete state of `self`, along with the checkpoints and balances
+    /// which are given to `Self::find_head`.
+    pub fn snapshot(
+        &self,
+        justified_checkpoint: Checkpoint,
+        finalized_checkpoint: Checkpoint,
+        justified_balances: Vec<u64>,
+    ) -> ForkChoiceSnapshot {
+        ForkChoiceSnapshot {
+            justified_checkpoint,
+            finalized_checkpoint,
+            justified_balances,
+            proto_array: self.proto_array.read().clone(),
+            votes: self.votes.read().0.clone(),
+            balances: self.balances.read().clone(),
+        }
+    }
+
     /// Returns a read-lock to core `ProtoArray` struct.
     ///
     /// Should only be used when encoding/decoding during troubleshooting.
@@ -712,3 +737,49 @@ mod test_compute_deltas {
         }
     }
 }
+
+#[cfg(test)]
+mod test_block_tree {
+    use super::*;
+
+    #[test]
+    fn parents_are_referenced_by_root() {
+        let root = |i| Hash256::from_low_u64_be(i);
+        let fork_choice = ProtoArrayForkChoice::new(
+            Slot::new(0),
+            Hash256::zero(),
+            Epoch::new(0),
+            Epoch::new(0),
+            root(1),
+        )
+        .expect("should create fork choice");
+
+        // two competing children of the finalized block
+        for i in &[2, 3] {
+            fork_choice
+                .process_block(
+                    Slot::new(*i),
+                    root(*i),
+                    root(1),
+                    Hash256::zero(),
+                    Epoch::new(0),
+                    Epoch::new(0),
+                )
+                .expect("should process block");
+        }
+
+        let tree = fork_choice.block_tree();
+        let parents = tree
+            .iter()
+            .map(|node| (node.root, node.parent_root))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parents,
+            vec![
+                (root(1), None),
+                (root(2), Some(root(1))),
+                (root(3), Some(root(1)))
+            ]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Provides `ForkChoiceSnapshot`, a copy of the fork choice state which can be exported as JSON and
+//! replayed offline with hypothetical votes (e.g., to debug a consensus incident).
+
+use crate::proto_array::ProtoArray;
+use crate::proto_array_fork_choice::{ElasticList, ProtoArrayForkChoice, VoteTracker};
+use parking_lot::RwLock;
+use serde_derive::{Deserialize, Serialize};
+use types::{Checkpoint, Epoch, Hash256};
+
+/// The complete state of a `ProtoArrayForkChoice`, along with the inputs to its head search.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ForkChoiceSnapshot {
+    /// The checkpoint whose block is the starting point of the head search.
+    pub justified_checkpoint: Checkpoint,
+    pub finalized_checkpoint: Checkpoint,
+    /// The effective balances of the justified state, which weigh the votes.
+    pub justified_balances: Vec<u64>,
+    /// The blocks known to fork choice, with the weights applied by the last head search.
+    pub proto_array: ProtoArray,
+    /// The latest vote of each validator.
+    pub votes: Vec<VoteTracker>,
+    /// The balances with which `votes` were applied to the weights in `proto_array`.
+    pub balances: Vec<u64>,
+}
+
+/// A vote for `block_root` by the validator with `validator_index`, applied to a snapshot as if
+/// it had been received in an attestation.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct HypotheticalVote {
+    pub validator_index: usize,
+    pub block_root: Hash256,
+    /// Only replaces the latest vote of the validator if it is later than the target of that vote.
+    pub target_epoch: Epoch,
+}
+
+impl ForkChoiceSnapshot {
+    /// Applies `votes` to a copy of `self` and runs the head search, returning the head and the
+    /// resulting snapshot, with updated weights.
+    ///
+    /// Votes for blocks which are not in the snapshot are ignored by the head search.
+    pub fn replay(&self, votes: &[HypotheticalVote]) -> Result<(Hash256, Self), String> {
+        let fork_choice = ProtoArrayForkChoice::from(self.clone());
+
+        for vote in votes {
+            fork_choice.process_attestation(
+                vote.validator_index,
+                vote.block_root,
+                vote.target_epoch,
+            )?;
+        }
+
+        let head = fork_choice.find_head(
+            self.justified_checkpoint.epoch,
+            self.justified_checkpoint.root,
+            self.finalized_checkpoint.epoch,
+            &self.justified_balances,
+        )?;
+
+        Ok((
+            head,
+            fork_choice.snapshot(
+                self.justified_checkpoint.clone(),
+                self.finalized_checkpoint.clone(),
+                self.justified_balances.clone(),
+            ),
+        ))
+    }
+}
+
+impl From<ForkChoiceSnapshot> for ProtoArrayForkChoice {
+    fn from(from: ForkChoiceSnapshot) -> Self {
+        Self {
+            proto_array: RwLock::new(from.proto_array),
+            votes: RwLock::new(ElasticList(from.votes)),
+            balances: RwLock::new(from.balances),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Slot;
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    /// Returns a snapshot of a finalized block with two competing children, where the validator
+    /// with index 0 votes for the first child.
+    fn get_snapshot() -> ForkChoiceSnapshot {
+        let fork_choice = ProtoArrayForkChoice::new(
+            Slot::new(0),
+            Hash256::zero(),
+            Epoch::new(0),
+            Epoch::new(0),
+            root(1),
+        )
+        .expect("should create fork choice");
+
+        for i in &[2, 3] {
+            fork_choice
+                .process_block(
+                    Slot::new(*i),
+                    root(*i),
+                    root(1),
+                    Hash256::zero(),
+                    Epoch::new(0),
+                    Epoch::new(0),
+                )
+                .expect("should process block");
+        }
+
+        let checkpoint = Checkpoint {
+            epoch: Epoch::new(0),
+            root: root(1),
+        };
+        let balances = vec![1, 1, 1];
+
+        fork_choice
+            .process_attestation(0, root(2), Epoch::new(1))
+            .expect("should process attestation");
+        fork_choice
+            .find_head(Epoch::new(0), root(1), Epoch::new(0), &balances)
+            .expect("should find head");
+
+        fork_choice.snapshot(checkpoint.clone(), checkpoint, balances)
+    }
+
+    #[test]
+    fn replay_without_votes() {
+        let snapshot = get_snapshot();
+
+        let (head, replayed) = snapshot.replay(&[]).expect("should replay");
+
+        assert_eq!(head, root(2));
+        assert_eq!(replayed, snapshot, "the snapshot should be unchanged");
+    }
+
+    #[test]
+    fn replay_with_votes() {
+        let snapshot = get_snapshot();
+        let vote = |validator_index, target_epoch| HypotheticalVote {
+            validator_index,
+            block_root: root(3),
+            target_epoch: Epoch::new(target_epoch),
+        };
+
+        let (head, replayed) = snapshot
+            .replay(&[vote(1, 1), vote(2, 1)])
+            .expect("should replay");
+        assert_eq!(head, root(3), "the votes should outweigh the existing vote");
+        assert_eq!(
+            replayed
+                .proto_array
+                .block_tree()
+                .iter()
+                .map(|node| node.weight)
+                .collect::<Vec<_>>(),
+            vec![3, 1, 2]
+        );
+        assert_ne!(replayed, snapshot);
+
+        let (head, _) = snapshot.replay(&[vote(0, 1)]).expect("should replay");
+        assert_eq!(
+            head,
+            root(2),
+            "a vote which is not later than the latest vote should be ignored"
+        );
+    }
+}
@@ -2,7 +2,7 @@ use eth2_hashing::hash;
 use int_to_bytes::int_to_bytes32;
 use merkle_proof::{MerkleTree, MerkleTreeError};
 use safe_arith::SafeArith;
-use types::Hash256;
+use types::{DepositTreeSnapshot, Hash256};
 
 /// Emulates the eth1 deposit contract merkle tree.
 pub struct DepositDataTree {
@@ -21,6 +21,38 @@ impl DepositDataTree {
         }
     }
 
+    /// Restore a Merkle tree of a fixed depth from a finalized `snapshot`, without any of its
+    /// leaves.
+    ///
+    /// Returns an error if the `snapshot` does not match its `deposit_root`.
+    pub fn from_snapshot(
+        snapshot: &DepositTreeSnapshot,
+        depth: usize,
+    ) -> Result<Self, MerkleTreeError> {
+        let tree = Self {
+            tree: MerkleTree::from_finalized_snapshot(
+                &snapshot.finalized,
+                snapshot.deposit_count as usize,
+                depth,
+            )?,
+            mix_in_length: snapshot.deposit_count as usize,
+            depth,
+        };
+
+        if tree.root() == snapshot.deposit_root {
+            Ok(tree)
+        } else {
+            Err(MerkleTreeError::InvalidFinalizedSnapshot)
+        }
+    }
+
+    /// Returns the roots of the largest full subtrees containing the first `deposit_count` leaves.
+    ///
+    /// See `MerkleTree::finalized_hashes`.
+    pub fn finalized_hashes(&self, deposit_count: usize) -> Result<Vec<Hash256>, MerkleTreeError> {
+        self.tree.finalized_hashes(deposit_count, self.depth)
+    }
+
     /// Returns 32 bytes representing the "mix in length" for the merkle root of this tree.
     fn length_bytes(&self) -> Vec<u8> {
         int_to_bytes32(self.mix_in_length as u64)
@@ -28,20 +60,30 @@ impl DepositDataTree {
 
     /// Retrieve the root hash of this Merkle tree with the length mixed in.
     pub fn root(&self) -> Hash256 {
-        let mut preimage = [0; 64];
-        preimage[0..32].copy_from_slice(&self.tree.hash()[..]);
-        preimage[32..64].copy_from_slice(&self.length_bytes());
-        Hash256::from_slice(&hash(&preimage))
+        mix_in_length(self.tree.hash(), self.mix_in_length)
+    }
+
+    /// Retrieve the root hash of this Merkle tree, with the length mixed in, as it was when it
+    /// contained only its first `deposit_count` leaves.
+    ///
+    /// Returns an error if the tree has fewer leaves, or if they end within the finalized
+    /// snapshot from which the tree was created.
+    pub fn root_at(&self, deposit_count: usize) -> Result<Hash256, MerkleTreeError> {
+        if deposit_count > self.mix_in_length {
+            return Err(MerkleTreeError::Invalid);
+        }
+        let tree_hash = self.tree.hash_of_first(deposit_count, self.depth)?;
+        Ok(mix_in_length(tree_hash, deposit_count))
     }
 
     /// Return the leaf at `index` and a Merkle proof of its inclusion.
     ///
     /// The Merkle proof is in "bottom-up" order, starting with a leaf node
     /// and moving up the tree. Its length will be exactly equal to `depth + 1`.
-    pub fn generate_proof(&self, index: usize) -> (Hash256, Vec<Hash256>) {
-        let (root, mut proof) = self.tree.generate_proof(index, self.depth);
+    pub fn generate_proof(&self, index: usize) -> Result<(Hash256, Vec<Hash256>), MerkleTreeError> {
+        let (root, mut proof) = self.tree.generate_proof(index, self.depth)?;
         proof.push(Hash256::from_slice(&self.length_bytes()));
-        (root, proof)
+        Ok((root, proof))
     }
 
     /// Add a deposit to the merkle tree.
@@ -50,4 +92,20 @@ impl DepositDataTree {
         self.mix_in_length.increment()?;
         Ok(())
     }
+
+    /// Add a batch of deposits to the merkle tree, hashing large subtrees in parallel.
+    pub fn push_leaves(&mut self, leaves: &[Hash256]) -> Result<(), MerkleTreeError> {
+        self.tree
+            .push_leaves(leaves, self.mix_in_length, self.depth)?;
+        self.mix_in_length.safe_add_assign(leaves.len())?;
+        Ok(())
+    }
+}
+
+/// Returns the hash of `root` with the `length` of the list it represents mixed in.
+fn mix_in_length(root: Hash256, length: usize) -> Hash256 {
+    let mut preimage = [0; 64];
+    preimage[0..32].copy_from_slice(&root[..]);
+    preimage[32..64].copy_from_slice(&int_to_bytes32(length as u64));
+    Hash256::from_slice(&hash(&preimage))
 }
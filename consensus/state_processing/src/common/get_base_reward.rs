@@ -11,12 +11,24 @@ pub fn get_base_reward<T: EthSpec>(
     // Should be == get_total_active_balance(state, spec)
     total_active_balance: u64,
     spec: &ChainSpec,
+) -> Result<u64, BeaconStateError> {
+    get_base_reward_from_effective_balance(
+        state.get_effective_balance(index, spec)?,
+        total_active_balance,
+        spec,
+    )
+}
+
+/// Returns the base reward of a validator with the given `effective_balance`.
+pub fn get_base_reward_from_effective_balance(
+    effective_balance: u64,
+    total_active_balance: u64,
+    spec: &ChainSpec,
 ) -> Result<u64, BeaconStateError> {
     if total_active_balance == 0 {
         Ok(0)
     } else {
-        Ok(state
-            .get_effective_balance(index, spec)?
+        Ok(effective_balance
             .safe_mul(spec.base_reward_factor)?
             .safe_div(total_active_balance.integer_sqrt())?
             .safe_div(spec.base_rewards_per_epoch)?)
@@ -1,10 +1,12 @@
 use errors::EpochProcessingError as Error;
+use rayon::prelude::*;
 use safe_arith::SafeArith;
 use tree_hash::TreeHash;
 use types::*;
 
 pub mod apply_rewards;
 pub mod errors;
+pub mod parallel;
 pub mod process_slashings;
 pub mod registry_updates;
 pub mod tests;
@@ -29,6 +31,18 @@ pub struct EpochProcessingSummary {
 pub fn per_epoch_processing<T: EthSpec>(
     state: &mut BeaconState<T>,
     spec: &ChainSpec,
+) -> Result<EpochProcessingSummary, Error> {
+    per_epoch_processing_with(state, parallel::is_enabled(), spec)
+}
+
+/// Performs per-epoch processing, running the per-validator loops of the rewards and penalties
+/// and the effective balance updates in parallel if `parallel` is `true`.
+///
+/// The result is identical either way.
+pub(crate) fn per_epoch_processing_with<T: EthSpec>(
+    state: &mut BeaconState<T>,
+    parallel: bool,
+    spec: &ChainSpec,
 ) -> Result<EpochProcessingSummary, Error> {
     // Ensure the committee caches are built.
     state.build_committee_cache(RelativeEpoch::Previous, spec)?;
@@ -45,7 +59,12 @@ pub fn per_epoch_processing<T: EthSpec>(
     process_justification_and_finalization(state, &validator_statuses.total_balances)?;
 
     // Rewards and Penalties.
-    process_rewards_and_penalties(state, &mut validator_statuses, spec)?;
+    apply_rewards::process_rewards_and_penalties_with(
+        state,
+        &mut validator_statuses,
+        parallel,
+        spec,
+    )?;
 
     // Registry Updates.
     process_registry_updates(state, spec)?;
@@ -58,7 +77,7 @@ pub fn per_epoch_processing<T: EthSpec>(
     )?;
 
     // Final updates.
-    process_final_updates(state, spec)?;
+    process_final_updates_with(state, parallel, spec)?;
 
     // Rotate the epoch caches to suit the epoch transition.
     state.advance_caches();
@@ -158,6 +177,16 @@ pub fn process_justification_and_finalization<T: EthSpec>(
 pub fn process_final_updates<T: EthSpec>(
     state: &mut BeaconState<T>,
     spec: &ChainSpec,
+) -> Result<(), Error> {
+    process_final_updates_with(state, parallel::is_enabled(), spec)
+}
+
+/// Finish up an epoch update, updating the effective balances in parallel if `parallel` is
+/// `true`.
+pub(crate) fn process_final_updates_with<T: EthSpec>(
+    state: &mut BeaconState<T>,
+    parallel: bool,
+    spec: &ChainSpec,
 ) -> Result<(), Error> {
     let current_epoch = state.current_epoch();
     let next_epoch = state.next_epoch();
@@ -173,17 +202,32 @@ pub fn process_final_updates<T: EthSpec>(
         .safe_div(spec.hysteresis_quotient)?;
     let downward_threshold = hysteresis_increment.safe_mul(spec.hysteresis_downward_multiplier)?;
     let upward_threshold = hysteresis_increment.safe_mul(spec.hysteresis_upward_multiplier)?;
-    for (index, validator) in state.validators.iter_mut().enumerate() {
-        let balance = state.balances[index];
-
-        if balance.safe_add(downward_threshold)? < validator.effective_balance
-            || validator.effective_balance.safe_add(upward_threshold)? < balance
-        {
-            validator.effective_balance = std::cmp::min(
-                balance.safe_sub(balance.safe_rem(spec.effective_balance_increment)?)?,
-                spec.max_effective_balance,
-            );
-        }
+    let update_effective_balance =
+        |(validator, &balance): (&mut Validator, &u64)| -> Result<(), Error> {
+            if balance.safe_add(downward_threshold)? < validator.effective_balance
+                || validator.effective_balance.safe_add(upward_threshold)? < balance
+            {
+                validator.effective_balance = std::cmp::min(
+                    balance.safe_sub(balance.safe_rem(spec.effective_balance_increment)?)?,
+                    spec.max_effective_balance,
+                );
+            }
+            Ok(())
+        };
+    if parallel {
+        let (validators, balances) = (&mut state.validators, &state.balances);
+        parallel::install(|| {
+            validators
+                .par_iter_mut()
+                .zip(balances.par_iter())
+                .try_for_each(update_effective_balance)
+        })?;
+    } else {
+        state
+            .validators
+            .iter_mut()
+            .zip(state.balances.iter())
+            .try_for_each(update_effective_balance)?;
     }
 
     // Reset slashings
@@ -1,15 +1,17 @@
 use super::super::common::get_base_reward;
+use super::parallel;
 use super::validator_statuses::{TotalBalances, ValidatorStatus, ValidatorStatuses};
 use super::Error;
+use rayon::prelude::*;
 use safe_arith::SafeArith;
 
 use types::*;
 
 /// Use to track the changes to a validators balance.
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Debug, PartialEq)]
 pub struct Delta {
-    rewards: u64,
-    penalties: u64,
+    pub rewards: u64,
+    pub penalties: u64,
 }
 
 impl Delta {
@@ -32,6 +34,30 @@ impl Delta {
     }
 }
 
+/// The changes to a validators balance for each component of its attestation in the previous
+/// epoch, sans proposer rewards.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct AttestationDelta {
+    pub source_delta: Delta,
+    pub target_delta: Delta,
+    pub head_delta: Delta,
+    pub inclusion_delay_delta: Delta,
+    pub inactivity_penalty_delta: Delta,
+}
+
+impl AttestationDelta {
+    /// Flatten the components into a single delta.
+    pub fn flatten(self) -> Result<Delta, Error> {
+        let mut result = Delta::default();
+        result.combine(self.source_delta)?;
+        result.combine(self.target_delta)?;
+        result.combine(self.head_delta)?;
+        result.combine(self.inclusion_delay_delta)?;
+        result.combine(self.inactivity_penalty_delta)?;
+        Ok(result)
+    }
+}
+
 /// Apply attester and proposer rewards.
 ///
 /// Spec v0.11.1
@@ -39,6 +65,17 @@ pub fn process_rewards_and_penalties<T: EthSpec>(
     state: &mut BeaconState<T>,
     validator_statuses: &mut ValidatorStatuses,
     spec: &ChainSpec,
+) -> Result<(), Error> {
+    process_rewards_and_penalties_with(state, validator_statuses, parallel::is_enabled(), spec)
+}
+
+/// Apply attester and proposer rewards, computing the attestation deltas and updating the
+/// balances in parallel if `parallel` is `true`.
+pub(crate) fn process_rewards_and_penalties_with<T: EthSpec>(
+    state: &mut BeaconState<T>,
+    validator_statuses: &mut ValidatorStatuses,
+    parallel: bool,
+    spec: &ChainSpec,
 ) -> Result<(), Error> {
     if state.current_epoch() == T::genesis_epoch() {
         return Ok(());
@@ -53,15 +90,32 @@ pub fn process_rewards_and_penalties<T: EthSpec>(
 
     let mut deltas = vec![Delta::default(); state.balances.len()];
 
-    get_attestation_deltas(&mut deltas, state, &validator_statuses, spec)?;
+    get_attestation_deltas(&mut deltas, state, &validator_statuses, parallel, spec)?;
 
     get_proposer_deltas(&mut deltas, state, validator_statuses, spec)?;
 
     // Apply the deltas, erroring on overflow above but not on overflow below (saturating at 0
     // instead).
-    for (i, delta) in deltas.iter().enumerate() {
-        state.balances[i] = state.balances[i].safe_add(delta.rewards)?;
-        state.balances[i] = state.balances[i].saturating_sub(delta.penalties);
+    let apply_delta = |(balance, delta): (&mut u64, &Delta)| -> Result<(), Error> {
+        *balance = balance
+            .safe_add(delta.rewards)?
+            .saturating_sub(delta.penalties);
+        Ok(())
+    };
+    if parallel {
+        let balances = &mut state.balances;
+        parallel::install(|| {
+            balances
+                .par_iter_mut()
+                .zip(deltas.par_iter())
+                .try_for_each(apply_delta)
+        })?;
+    } else {
+        state
+            .balances
+            .iter_mut()
+            .zip(deltas.iter())
+            .try_for_each(apply_delta)?;
     }
 
     Ok(())
@@ -108,11 +162,12 @@ fn get_attestation_deltas<T: EthSpec>(
     deltas: &mut Vec<Delta>,
     state: &BeaconState<T>,
     validator_statuses: &ValidatorStatuses,
+    parallel: bool,
     spec: &ChainSpec,
 ) -> Result<(), Error> {
     let finality_delay = (state.previous_epoch() - state.finalized_checkpoint.epoch).as_u64();
 
-    for (index, validator) in validator_statuses.statuses.iter().enumerate() {
+    let validator_delta = |(index, validator): (usize, &ValidatorStatus)| -> Result<Delta, Error> {
         let base_reward = get_base_reward(
             state,
             index,
@@ -120,15 +175,33 @@ fn get_attestation_deltas<T: EthSpec>(
             spec,
         )?;
 
-        let delta = get_attestation_delta::<T>(
+        get_attestation_delta::<T>(
             &validator,
             &validator_statuses.total_balances,
             base_reward,
             finality_delay,
             spec,
-        )?;
-
-        deltas[index].combine(delta)?;
+        )?
+        .flatten()
+    };
+
+    if parallel {
+        let validator_deltas = parallel::install(|| {
+            validator_statuses
+                .statuses
+                .par_iter()
+                .enumerate()
+                .map(validator_delta)
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+
+        for (delta, validator_delta) in deltas.iter_mut().zip(validator_deltas) {
+            delta.combine(validator_delta)?;
+        }
+    } else {
+        for (index, validator) in validator_statuses.statuses.iter().enumerate() {
+            deltas[index].combine(validator_delta((index, validator))?)?;
+        }
     }
 
     Ok(())
@@ -137,14 +210,14 @@ fn get_attestation_deltas<T: EthSpec>(
 /// Determine the delta for a single validator, sans proposer rewards.
 ///
 /// Spec v0.11.1
-fn get_attestation_delta<T: EthSpec>(
+pub fn get_attestation_delta<T: EthSpec>(
     validator: &ValidatorStatus,
     total_balances: &TotalBalances,
     base_reward: u64,
     finality_delay: u64,
     spec: &ChainSpec,
-) -> Result<Delta, Error> {
-    let mut delta = Delta::default();
+) -> Result<AttestationDelta, Error> {
+    let mut delta = AttestationDelta::default();
 
     // Is this validator eligible to be rewarded or penalized?
     // Spec: validator index in `eligible_validator_indices`
@@ -177,7 +250,7 @@ fn get_attestation_delta<T: EthSpec>(
     // Spec:
     // - validator index in `get_unslashed_attesting_indices(state, matching_source_attestations)`
     if validator.is_previous_epoch_attester && !validator.is_slashed {
-        delta.reward(
+        delta.source_delta.reward(
             base_reward
                 .safe_mul(total_attesting_balance_ebi)?
                 .safe_div(total_balance_ebi)?,
@@ -188,47 +261,51 @@ fn get_attestation_delta<T: EthSpec>(
         let inclusion = validator
             .inclusion_info
             .expect("It is a logic error for an attester not to have an inclusion delay.");
-        delta.reward(max_attester_reward.safe_div(inclusion.delay)?)?;
+        delta
+            .inclusion_delay_delta
+            .reward(max_attester_reward.safe_div(inclusion.delay)?)?;
     } else {
-        delta.penalize(base_reward)?;
+        delta.source_delta.penalize(base_reward)?;
     }
 
     // Expected FFG target.
     // Spec:
     // - validator index in `get_unslashed_attesting_indices(state, matching_target_attestations)`
     if validator.is_previous_epoch_target_attester && !validator.is_slashed {
-        delta.reward(
+        delta.target_delta.reward(
             base_reward
                 .safe_mul(matching_target_balance_ebi)?
                 .safe_div(total_balance_ebi)?,
         )?;
     } else {
-        delta.penalize(base_reward)?;
+        delta.target_delta.penalize(base_reward)?;
     }
 
     // Expected head.
     // Spec:
     // - validator index in `get_unslashed_attesting_indices(state, matching_head_attestations)`
     if validator.is_previous_epoch_head_attester && !validator.is_slashed {
-        delta.reward(
+        delta.head_delta.reward(
             base_reward
                 .safe_mul(matching_head_balance_ebi)?
                 .safe_div(total_balance_ebi)?,
         )?;
     } else {
-        delta.penalize(base_reward)?;
+        delta.head_delta.penalize(base_reward)?;
     }
 
     // Inactivity penalty
     if finality_delay > spec.min_epochs_to_inactivity_penalty {
         // All eligible validators are penalized
-        delta.penalize(spec.base_rewards_per_epoch.safe_mul(base_reward)?)?;
+        delta
+            .inactivity_penalty_delta
+            .penalize(spec.base_rewards_per_epoch.safe_mul(base_reward)?)?;
 
         // Additionally, all validators whose FFG target didn't match are penalized extra
         // This condition is equivalent to this condition from the spec:
         // `index not in get_unslashed_attesting_indices(state, matching_target_attestations)`
         if validator.is_slashed || !validator.is_previous_epoch_target_attester {
-            delta.penalize(
+            delta.inactivity_penalty_delta.penalize(
                 validator
                     .current_epoch_effective_balance
                     .safe_mul(finality_delay)?
@@ -0,0 +1,51 @@
+//! Configuration of the thread pool which runs the per-validator loops of epoch processing.
+//!
+//! The loops only run in parallel if the `parallel_epoch_processing` feature is enabled. They
+//! run on a dedicated thread pool if one has been set with `set_num_threads`, otherwise on the
+//! global `rayon` thread pool.
+use lazy_static::lazy_static;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::sync::{Arc, RwLock};
+
+lazy_static! {
+    static ref THREAD_POOL: RwLock<Option<Arc<ThreadPool>>> = RwLock::new(None);
+}
+
+/// Returns `true` if epoch processing uses the parallel implementation by default.
+pub fn is_enabled() -> bool {
+    cfg!(feature = "parallel_epoch_processing")
+}
+
+/// Run the parallel loops of epoch processing on a dedicated pool of `num_threads` threads.
+///
+/// A `num_threads` of zero reverts to the global `rayon` thread pool.
+pub fn set_num_threads(num_threads: usize) -> Result<(), String> {
+    let pool = if num_threads == 0 {
+        None
+    } else {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|i| format!("epoch_processing_{}", i))
+            .build()
+            .map_err(|e| format!("Unable to build epoch processing thread pool: {:?}", e))?;
+        Some(Arc::new(pool))
+    };
+
+    *THREAD_POOL
+        .write()
+        .map_err(|_| "Epoch processing thread pool lock is poisoned".to_string())? = pool;
+    Ok(())
+}
+
+/// Run `f` on the configured thread pool, if any.
+pub(crate) fn install<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R + Send,
+    R: Send,
+{
+    let pool = THREAD_POOL.read().ok().and_then(|pool| pool.clone());
+    match pool {
+        Some(pool) => pool.install(f),
+        None => f(),
+    }
+}
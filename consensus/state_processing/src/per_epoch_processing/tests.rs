@@ -1,7 +1,8 @@
 #![cfg(test)]
-use crate::per_epoch_processing::per_epoch_processing;
+#![allow(clippy::integer_arithmetic)]
+use crate::per_epoch_processing::{parallel, per_epoch_processing, per_epoch_processing_with};
 use env_logger::{Builder, Env};
-use types::test_utils::TestingBeaconStateBuilder;
+use types::test_utils::{RngCore, SeedableRng, TestingBeaconStateBuilder, XorShiftRng};
 use types::*;
 
 #[test]
@@ -21,3 +22,72 @@ fn runs_without_error() {
 
     per_epoch_processing(&mut state, &spec).unwrap();
 }
+
+/// Returns a state at the end of an epoch with partial participation, random balances and some
+/// slashed validators, such that most branches of the rewards and penalties and the effective
+/// balance updates are taken. If `inactivity_leak` is `true`, the chain has not finalized for
+/// several epochs.
+fn get_differential_state(inactivity_leak: bool, spec: &ChainSpec) -> BeaconState<MinimalEthSpec> {
+    let mut builder: TestingBeaconStateBuilder<MinimalEthSpec> =
+        TestingBeaconStateBuilder::from_deterministic_keypairs(32, spec);
+
+    let target_slot =
+        (MinimalEthSpec::genesis_epoch() + 8).end_slot(MinimalEthSpec::slots_per_epoch());
+    builder.teleport_to_slot(target_slot);
+    builder.insert_attestations(spec);
+
+    let (mut state, _keypairs) = builder.build();
+
+    let attestations = state
+        .previous_epoch_attestations
+        .iter()
+        .step_by(2)
+        .cloned()
+        .collect::<Vec<_>>();
+    state.previous_epoch_attestations = VariableList::new(attestations).unwrap();
+
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+    for balance in state.balances.iter_mut() {
+        *balance = rng.next_u64() % (2 * spec.max_effective_balance);
+    }
+    for validator in state.validators.iter_mut().step_by(5) {
+        validator.slashed = true;
+    }
+
+    if inactivity_leak {
+        state.finalized_checkpoint.epoch = MinimalEthSpec::genesis_epoch();
+    }
+
+    state
+}
+
+fn assert_parallel_matches_serial(spec: &ChainSpec) {
+    for &inactivity_leak in &[false, true] {
+        let state = get_differential_state(inactivity_leak, spec);
+
+        let mut serial_state = state.clone();
+        per_epoch_processing_with(&mut serial_state, false, spec).unwrap();
+
+        let mut parallel_state = state;
+        per_epoch_processing_with(&mut parallel_state, true, spec).unwrap();
+
+        assert_eq!(serial_state.balances, parallel_state.balances);
+        assert_eq!(serial_state.validators, parallel_state.validators);
+        assert_eq!(
+            serial_state.canonical_root(),
+            parallel_state.canonical_root()
+        );
+    }
+}
+
+#[test]
+fn parallel_matches_serial() {
+    assert_parallel_matches_serial(&MinimalEthSpec::default_spec());
+}
+
+#[test]
+fn parallel_matches_serial_on_dedicated_thread_pool() {
+    parallel::set_num_threads(2).unwrap();
+    assert_parallel_matches_serial(&MinimalEthSpec::default_spec());
+    parallel::set_num_threads(0).unwrap();
+}
@@ -21,7 +21,7 @@ fn get_state<E: EthSpec>(validator_count: usize) -> BeaconState<E> {
         state.balances.push(i as u64).expect("should add balance");
     }
 
-    state.validators = (0..validator_count)
+    *state.validators = (0..validator_count)
         .collect::<Vec<_>>()
         .par_iter()
         .map(|&i| Validator {
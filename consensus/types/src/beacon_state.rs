@@ -171,10 +171,13 @@ where
     pub eth1_deposit_index: u64,
 
     // Registry
+    //
+    // These lists are shared between clones of the state until they are modified, since they are
+    // the largest part of the state and are rarely modified during block processing.
     #[compare_fields(as_slice)]
-    pub validators: VariableList<Validator, T::ValidatorRegistryLimit>,
+    pub validators: CopyOnWrite<VariableList<Validator, T::ValidatorRegistryLimit>>,
     #[compare_fields(as_slice)]
-    pub balances: VariableList<u64, T::ValidatorRegistryLimit>,
+    pub balances: CopyOnWrite<VariableList<u64, T::ValidatorRegistryLimit>>,
 
     // Randomness
     pub randao_mixes: FixedVector<Hash256, T::EpochsPerHistoricalVector>,
@@ -250,8 +253,8 @@ impl<T: EthSpec> BeaconState<T> {
             eth1_deposit_index: 0,
 
             // Validator registry
-            validators: VariableList::empty(), // Set later.
-            balances: VariableList::empty(),   // Set later.
+            validators: CopyOnWrite::new(VariableList::empty()), // Set later.
+            balances: CopyOnWrite::new(VariableList::empty()),   // Set later.
 
             // Randomness
             randao_mixes: FixedVector::from_elem(Hash256::zero()),
@@ -1173,8 +1176,8 @@ impl<T: EthSpec> arbitrary::Arbitrary for BeaconState<T> {
             eth1_data: Eth1Data::arbitrary(u)?,
             eth1_data_votes: <VariableList<Eth1Data, T::SlotsPerEth1VotingPeriod>>::arbitrary(u)?,
             eth1_deposit_index: u64::arbitrary(u)?,
-            validators: <VariableList<Validator, T::ValidatorRegistryLimit>>::arbitrary(u)?,
-            balances: <VariableList<u64, T::ValidatorRegistryLimit>>::arbitrary(u)?,
+            validators: <VariableList<Validator, T::ValidatorRegistryLimit>>::arbitrary(u)?.into(),
+            balances: <VariableList<u64, T::ValidatorRegistryLimit>>::arbitrary(u)?.into(),
             randao_mixes: <FixedVector<Hash256, T::EpochsPerHistoricalVector>>::arbitrary(u)?,
             slashings: <FixedVector<u64, T::EpochsPerSlashingsVector>>::arbitrary(u)?,
             previous_epoch_attestations: <VariableList<
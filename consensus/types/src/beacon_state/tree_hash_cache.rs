@@ -164,6 +164,23 @@ impl BeaconTreeHashCache {
     ) -> Result<Hash256, Error> {
         self.validators.recalculate_tree_hash_root(validators)
     }
+
+    /// Returns the cached Merkle tree of the state field at `field_index` and the arena it is
+    /// stored in, if that field is cached.
+    ///
+    /// The tree of a list does not include the mixed-in length.
+    pub(crate) fn field_tree(&self, field_index: usize) -> Option<(&TreeHashCache, &CacheArena)> {
+        match field_index {
+            5 => Some((&self.block_roots, &self.fixed_arena)),
+            6 => Some((&self.state_roots, &self.fixed_arena)),
+            7 => Some((&self.historical_roots, &self.fixed_arena)),
+            11 => Some((&self.validators.list_cache, &self.validators.list_arena)),
+            12 => Some((&self.balances, &self.balances_arena)),
+            13 => Some((&self.randao_mixes, &self.fixed_arena)),
+            14 => Some((&self.slashings, &self.slashings_arena)),
+            _ => None,
+        }
+    }
 }
 
 /// A specialized cache for computing the tree hash root of `state.validators`.
@@ -114,6 +114,10 @@ pub struct ChainSpec {
      */
     pub eth1_follow_distance: u64,
     pub seconds_per_eth1_block: u64,
+    /// If set, eth1 nodes must be connected to the chain with this chain id.
+    pub deposit_chain_id: Option<u64>,
+    /// If set, eth1 nodes must be connected to the network with this network id.
+    pub deposit_network_id: Option<u64>,
 
     /*
      * Networking
@@ -324,6 +328,8 @@ impl ChainSpec {
              */
             eth1_follow_distance: 1_024,
             seconds_per_eth1_block: 14,
+            deposit_chain_id: None,
+            deposit_network_id: None,
 
             /*
              * Network specific
@@ -551,6 +557,12 @@ pub struct YamlConfig {
     random_subnets_per_validator: u64,
     epochs_per_random_subnet_subscription: u64,
     seconds_per_eth1_block: u64,
+
+    // Deposit contract
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deposit_chain_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deposit_network_id: Option<u64>,
 }
 
 impl Default for YamlConfig {
@@ -631,6 +643,10 @@ impl YamlConfig {
             random_subnets_per_validator: spec.random_subnets_per_validator,
             epochs_per_random_subnet_subscription: spec.epochs_per_random_subnet_subscription,
             seconds_per_eth1_block: spec.seconds_per_eth1_block,
+
+            // Deposit contract
+            deposit_chain_id: spec.deposit_chain_id,
+            deposit_network_id: spec.deposit_network_id,
         }
     }
 
@@ -705,6 +721,8 @@ impl YamlConfig {
             boot_nodes: chain_spec.boot_nodes.clone(),
             genesis_fork_version: self.genesis_fork_version,
             eth1_follow_distance: self.eth1_follow_distance,
+            deposit_chain_id: self.deposit_chain_id,
+            deposit_network_id: self.deposit_network_id,
             ..*chain_spec
         })
     }
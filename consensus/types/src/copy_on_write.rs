@@ -0,0 +1,200 @@
+//! Provides `CopyOnWrite`, a wrapper which shares a value between its clones until one of them is
+//! mutated.
+//!
+//! This is used for the largest fields of the `BeaconState` (e.g., the validator registry), which
+//! are cloned for every block but are only rarely modified by block processing.
+
+use crate::test_utils::TestRandom;
+use rand::RngCore;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ssz::{Decode, DecodeError, Encode};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use tree_hash::{TreeHash, TreeHashType};
+
+/// A value which is shared by all its clones.
+///
+/// Cloning is cheap, since it only increments a reference count. The value is copied the first
+/// time it is mutably dereferenced whilst it is shared, so that the mutation is not visible to
+/// the other clones.
+#[derive(Default)]
+pub struct CopyOnWrite<T>(Arc<T>);
+
+impl<T> CopyOnWrite<T> {
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+
+    /// Returns `true` if `self` and `other` share the same value, i.e., neither has been mutated
+    /// since one was cloned from the other.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T: Clone> CopyOnWrite<T> {
+    /// Returns the value, copying it if it is shared.
+    pub fn into_inner(self) -> T {
+        Arc::try_unwrap(self.0).unwrap_or_else(|shared| (*shared).clone())
+    }
+}
+
+impl<T> From<T> for CopyOnWrite<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Clone for CopyOnWrite<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Deref for CopyOnWrite<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Clone> DerefMut for CopyOnWrite<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CopyOnWrite<T>
+where
+    &'a T: IntoIterator,
+{
+    type Item = <&'a T as IntoIterator>::Item;
+    type IntoIter = <&'a T as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.as_ref().into_iter()
+    }
+}
+
+impl<T: PartialEq> PartialEq for CopyOnWrite<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || *self.0 == *other.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CopyOnWrite<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl<T: Serialize> Serialize for CopyOnWrite<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for CopyOnWrite<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl<T: Encode> Encode for CopyOnWrite<T> {
+    fn is_ssz_fixed_len() -> bool {
+        <T as Encode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <T as Encode>::ssz_fixed_len()
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        self.0.ssz_bytes_len()
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        self.0.ssz_append(buf)
+    }
+}
+
+impl<T: Decode> Decode for CopyOnWrite<T> {
+    fn is_ssz_fixed_len() -> bool {
+        <T as Decode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <T as Decode>::ssz_fixed_len()
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        T::from_ssz_bytes(bytes).map(Self::new)
+    }
+}
+
+impl<T: TreeHash> TreeHash for CopyOnWrite<T> {
+    fn tree_hash_type() -> TreeHashType {
+        T::tree_hash_type()
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        self.0.tree_hash_packed_encoding()
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        T::tree_hash_packing_factor()
+    }
+
+    fn tree_hash_root(&self) -> tree_hash::Hash256 {
+        self.0.tree_hash_root()
+    }
+}
+
+impl<T: TestRandom> TestRandom for CopyOnWrite<T> {
+    fn random_for_test(rng: &mut impl RngCore) -> Self {
+        Self::new(T::random_for_test(rng))
+    }
+}
+
+#[cfg(feature = "arbitrary-fuzz")]
+impl<T: arbitrary::Arbitrary> arbitrary::Arbitrary for CopyOnWrite<T> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        T::arbitrary(u).map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VariableList;
+    use ssz_types::typenum::U8;
+
+    type List = CopyOnWrite<VariableList<u64, U8>>;
+
+    #[test]
+    fn clones_share_until_mutated() {
+        let list: List = VariableList::from(vec![1, 2, 3]).into();
+        let mut clone = list.clone();
+        assert!(list.ptr_eq(&clone));
+
+        clone[0] = 42;
+        assert!(!list.ptr_eq(&clone));
+        assert_eq!(list[..], [1, 2, 3]);
+        assert_eq!(clone[..], [42, 2, 3]);
+    }
+
+    #[test]
+    fn encoding_matches_inner() {
+        let inner = VariableList::<u64, U8>::from(vec![1, 2, 3]);
+        let list: List = inner.clone().into();
+
+        assert_eq!(list.as_ssz_bytes(), inner.as_ssz_bytes());
+        assert_eq!(
+            List::from_ssz_bytes(&inner.as_ssz_bytes()),
+            Ok(list.clone())
+        );
+        assert_eq!(list.tree_hash_root(), inner.tree_hash_root());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::*;
+use eth2_hashing::hash32_concat;
+use int_to_bytes::int_to_bytes32;
+use merkle_proof::{MerkleTree, MerkleTreeError};
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+
+/// A compact representation of the eth1 deposit contract merkle tree at some finalized deposit
+/// count, sufficient to continue building the tree without any of the prior deposits.
+///
+/// The `finalized` hashes are the roots of the largest full subtrees of the deposit tree, from left
+/// to right.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct DepositTreeSnapshot {
+    pub finalized: Vec<Hash256>,
+    pub deposit_root: Hash256,
+    pub deposit_count: u64,
+    /// The hash of the eth1 block at which the deposit contract had `deposit_count` deposits.
+    pub execution_block_hash: Hash256,
+    /// The number of the eth1 block at which the deposit contract had `deposit_count` deposits.
+    pub execution_block_height: u64,
+}
+
+impl DepositTreeSnapshot {
+    /// Returns the merkle tree of deposits described by `self`, without the length mixed in.
+    pub fn merkle_tree(&self) -> Result<MerkleTree, MerkleTreeError> {
+        MerkleTree::from_finalized_snapshot(
+            &self.finalized,
+            self.deposit_count as usize,
+            DEPOSIT_TREE_DEPTH,
+        )
+    }
+
+    /// Computes the deposit root from the `finalized` hashes and the `deposit_count`.
+    pub fn calculate_root(&self) -> Result<Hash256, MerkleTreeError> {
+        let tree_root = self.merkle_tree()?.hash();
+        Ok(Hash256::from_slice(&hash32_concat(
+            tree_root.as_bytes(),
+            &int_to_bytes32(self.deposit_count),
+        )))
+    }
+
+    /// Returns `true` if the `finalized` hashes match the `deposit_root`.
+    pub fn is_valid(&self) -> bool {
+        self.calculate_root() == Ok(self.deposit_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(leaves: &[Hash256]) -> DepositTreeSnapshot {
+        let tree = MerkleTree::create(leaves, DEPOSIT_TREE_DEPTH);
+        let deposit_root = Hash256::from_slice(&hash32_concat(
+            tree.hash().as_bytes(),
+            &int_to_bytes32(leaves.len() as u64),
+        ));
+
+        DepositTreeSnapshot {
+            finalized: tree
+                .finalized_hashes(leaves.len(), DEPOSIT_TREE_DEPTH)
+                .expect("should get finalized hashes"),
+            deposit_root,
+            deposit_count: leaves.len() as u64,
+            execution_block_hash: Hash256::repeat_byte(42),
+            execution_block_height: 42,
+        }
+    }
+
+    #[test]
+    fn valid_snapshots() {
+        let leaves = (0..11).map(Hash256::from_low_u64_be).collect::<Vec<_>>();
+
+        for n in 0..=leaves.len() {
+            let snapshot = snapshot(&leaves[..n]);
+            assert!(
+                snapshot.is_valid(),
+                "snapshot of {} leaves should be valid",
+                n
+            );
+            assert_eq!(snapshot.finalized.len(), n.count_ones() as usize);
+        }
+    }
+
+    #[test]
+    fn invalid_snapshots() {
+        let leaves = (0..11).map(Hash256::from_low_u64_be).collect::<Vec<_>>();
+        let valid = snapshot(&leaves);
+
+        let mut wrong_root = valid.clone();
+        wrong_root.deposit_root = Hash256::repeat_byte(1);
+        assert!(!wrong_root.is_valid());
+
+        let mut wrong_count = valid.clone();
+        wrong_count.deposit_count += 1;
+        assert!(!wrong_count.is_valid());
+
+        let mut wrong_hashes = valid;
+        wrong_hashes.finalized.pop();
+        assert!(!wrong_hashes.is_valid());
+    }
+}
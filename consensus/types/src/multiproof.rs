@@ -0,0 +1,582 @@
+#![allow(clippy::integer_arithmetic)]
+
+//! Generates and verifies Merkle multiproofs of the nodes of the tree hash trees of SSZ
+//! containers, as described in the `ssz/merkle-proofs.md` document of the specification.
+//!
+//! Nodes are identified by their generalized index: the root is `1` and the children of the node
+//! `i` are `2 * i` and `2 * i + 1`. Proofs may descend into the fields of a container which are
+//! themselves containers implementing `ProofTree`, and into the lists and vectors of a
+//! `BeaconState` which are held in its tree hash cache, down to the tree hash root of each
+//! element.
+
+use crate::*;
+use cached_tree_hash::{CacheArena, TreeHashCache};
+use eth2_hashing::{hash32_concat, ZERO_HASHES};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use tree_hash::{mix_in_length, TreeHash};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    /// The generalized index is zero, or refers to a descendant of a padding node.
+    InvalidGeneralizedIndex(u64),
+    /// The generalized index refers to a descendant of a field which does not support proofs.
+    UnsupportedGeneralizedIndex(u64),
+    /// The node could not be read from a tree hash cache.
+    TreeHashCache(cached_tree_hash::Error),
+}
+
+/// An SSZ container whose tree hash tree can be traversed to generate proofs.
+pub trait ProofTree {
+    /// Returns the number of fields of the container.
+    fn num_fields(&self) -> usize;
+
+    /// Returns the tree hash root of the field at `index`, if any.
+    fn field_root(&self, index: usize) -> Option<Hash256>;
+
+    /// Returns the field at `index`, if it is a container which supports proofs.
+    fn field(&self, _index: usize) -> Option<&dyn ProofTree> {
+        None
+    }
+
+    /// Returns the node at `gindex` of the tree of the field at `index`, where `gindex` is relative
+    /// to the root of the field, if the field is a list or vector which supports proofs.
+    fn field_node(&self, _index: usize, _gindex: u64) -> Option<Result<Hash256, Error>> {
+        None
+    }
+}
+
+/// A proof of the nodes at `gindices`, which have the values `leaves`, against the root of a tree.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Multiproof {
+    /// The generalized indices of the proven nodes.
+    pub gindices: Vec<u64>,
+    /// The values of the nodes at `gindices`.
+    pub leaves: Vec<Hash256>,
+    /// The values of the nodes at `get_helper_indices(gindices)`.
+    pub proof: Vec<Hash256>,
+}
+
+impl Multiproof {
+    /// Generates a proof of the nodes of `tree` at `gindices`.
+    ///
+    /// The nodes of the lists and vectors of a `BeaconState` are read from its tree hash cache,
+    /// which must be up to date (see `BeaconState::update_tree_hash_cache`).
+    pub fn generate(tree: &dyn ProofTree, gindices: &[u64]) -> Result<Self, Error> {
+        let leaves = gindices
+            .iter()
+            .map(|gindex| get_node(tree, *gindex))
+            .collect::<Result<_, _>>()?;
+        let proof = get_helper_indices(gindices)
+            .into_iter()
+            .map(|gindex| get_node(tree, gindex))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            gindices: gindices.to_vec(),
+            leaves,
+            proof,
+        })
+    }
+
+    /// Returns the root of the tree which the proof is for, or `None` if the proof is malformed.
+    pub fn calculate_root(&self) -> Option<Hash256> {
+        let helper_indices = get_helper_indices(&self.gindices);
+        if self.leaves.len() != self.gindices.len() || self.proof.len() != helper_indices.len() {
+            return None;
+        }
+
+        let mut nodes = self
+            .gindices
+            .iter()
+            .copied()
+            .zip(self.leaves.iter().copied())
+            .chain(helper_indices.into_iter().zip(self.proof.iter().copied()))
+            .collect::<HashMap<_, _>>();
+
+        // Visiting the nodes from the deepest up ensures that each parent is computed from its
+        // children before it is needed itself.
+        let mut keys = nodes.keys().copied().collect::<Vec<_>>();
+        keys.sort_unstable_by(|a, b| b.cmp(a));
+        let mut pos = 0;
+        while pos < keys.len() {
+            let gindex = keys[pos];
+            let parent = gindex / 2;
+            if gindex > 1 && !nodes.contains_key(&parent) {
+                if let (Some(left), Some(right)) =
+                    (nodes.get(&(gindex & !1)), nodes.get(&(gindex | 1)))
+                {
+                    let parent_node =
+                        Hash256::from_slice(&hash32_concat(left.as_bytes(), right.as_bytes()));
+                    nodes.insert(parent, parent_node);
+                    keys.push(parent);
+                }
+            }
+            pos += 1;
+        }
+
+        nodes.get(&1).copied()
+    }
+}
+
+/// Returns the generalized indices of the nodes, other than the proven nodes and their
+/// ancestors, which are required to prove the nodes at `gindices`, in descending order.
+pub fn get_helper_indices(gindices: &[u64]) -> Vec<u64> {
+    let mut branch_indices = BTreeSet::new();
+    let mut path_indices = BTreeSet::new();
+
+    for &gindex in gindices {
+        let mut node = gindex;
+        while node > 1 {
+            branch_indices.insert(node ^ 1);
+            path_indices.insert(node);
+            node /= 2;
+        }
+    }
+
+    let mut helper_indices = branch_indices
+        .difference(&path_indices)
+        .copied()
+        .collect::<Vec<_>>();
+    helper_indices.reverse();
+    helper_indices
+}
+
+/// Returns the node of the tree of `tree` at `gindex`.
+pub fn get_node(tree: &dyn ProofTree, gindex: u64) -> Result<Hash256, Error> {
+    if gindex == 0 {
+        return Err(Error::InvalidGeneralizedIndex(gindex));
+    }
+
+    let num_fields = tree.num_fields();
+    let depth = num_fields.next_power_of_two().trailing_zeros() as usize;
+    let gindex_depth = 63 - gindex.leading_zeros() as usize;
+
+    if gindex_depth < depth {
+        // An internal node of the container, which is a zero hash if all the leaves beneath it
+        // are padding.
+        let height = depth - gindex_depth;
+        let first_leaf = (gindex << height) - (1 << depth);
+        if first_leaf >= num_fields as u64 {
+            Ok(Hash256::from_slice(&ZERO_HASHES[height]))
+        } else {
+            let left = get_node(tree, gindex * 2)?;
+            let right = get_node(tree, gindex * 2 + 1)?;
+            Ok(Hash256::from_slice(&hash32_concat(
+                left.as_bytes(),
+                right.as_bytes(),
+            )))
+        }
+    } else if gindex_depth == depth {
+        let index = (gindex - (1 << depth)) as usize;
+        Ok(tree.field_root(index).unwrap_or_else(Hash256::zero))
+    } else {
+        // A node within the tree of a field, given by stripping the path to the field from the
+        // front of `gindex`.
+        let sub_depth = gindex_depth - depth;
+        let index = ((gindex >> sub_depth) - (1 << depth)) as usize;
+        let sub_gindex = (1 << sub_depth) | (gindex & ((1 << sub_depth) - 1));
+
+        if index >= num_fields {
+            return Err(Error::InvalidGeneralizedIndex(gindex));
+        }
+
+        let node = if let Some(field) = tree.field(index) {
+            get_node(field, sub_gindex)
+        } else {
+            tree.field_node(index, sub_gindex)
+                .ok_or_else(|| Error::UnsupportedGeneralizedIndex(gindex))?
+        };
+        node.map_err(|e| match e {
+            Error::InvalidGeneralizedIndex(_) => Error::InvalidGeneralizedIndex(gindex),
+            Error::UnsupportedGeneralizedIndex(_) => Error::UnsupportedGeneralizedIndex(gindex),
+            e => e,
+        })
+    }
+}
+
+/// Returns the node at `gindex` of the cached Merkle `tree` of a vector, where `gindex` is
+/// relative to the root of the vector.
+fn cached_vector_node(
+    tree: &TreeHashCache,
+    arena: &CacheArena,
+    gindex: u64,
+) -> Result<Hash256, Error> {
+    let layer = 63 - gindex.leading_zeros() as usize;
+    if layer > tree.depth() {
+        // A node within the tree of an element, e.g. a field of a validator.
+        return Err(Error::UnsupportedGeneralizedIndex(gindex));
+    }
+    let index = (gindex - (1 << layer)) as usize;
+    tree.node(arena, layer, index).map_err(Error::TreeHashCache)
+}
+
+/// Returns the node at `gindex` of the cached Merkle `tree` of a list of `len` elements, where
+/// `gindex` is relative to the root of the list.
+///
+/// The root of a list mixes the root of the tree of its elements (at `2`) with its length (at
+/// `3`).
+fn cached_list_node(
+    tree: &TreeHashCache,
+    arena: &CacheArena,
+    len: usize,
+    gindex: u64,
+) -> Result<Hash256, Error> {
+    let layer = 63 - gindex.leading_zeros() as usize;
+    if gindex == 1 {
+        Ok(mix_in_length(&tree.root(arena), len))
+    } else if gindex == 3 {
+        Ok(root(&(len as u64)))
+    } else if gindex >> (layer - 1) == 2 {
+        let elements_gindex = (1 << (layer - 1)) | (gindex & ((1 << (layer - 1)) - 1));
+        cached_vector_node(tree, arena, elements_gindex)
+    } else {
+        // A descendant of the length.
+        Err(Error::InvalidGeneralizedIndex(gindex))
+    }
+}
+
+fn root<T: TreeHash>(value: &T) -> Hash256 {
+    Hash256::from_slice(&value.tree_hash_root()[..])
+}
+
+impl<T: EthSpec> ProofTree for BeaconState<T> {
+    fn num_fields(&self) -> usize {
+        21
+    }
+
+    fn field_root(&self, index: usize) -> Option<Hash256> {
+        if let Some(Ok(field_root)) = self.field_node(index, 1) {
+            return Some(field_root);
+        }
+
+        let field_root = match index {
+            0 => root(&self.genesis_time),
+            1 => root(&self.genesis_validators_root),
+            2 => root(&self.slot),
+            3 => root(&self.fork),
+            4 => root(&self.latest_block_header),
+            5 => root(&self.block_roots),
+            6 => root(&self.state_roots),
+            7 => root(&self.historical_roots),
+            8 => root(&self.eth1_data),
+            9 => root(&self.eth1_data_votes),
+            10 => root(&self.eth1_deposit_index),
+            11 => root(&self.validators),
+            12 => root(&self.balances),
+            13 => root(&self.randao_mixes),
+            14 => root(&self.slashings),
+            15 => root(&self.previous_epoch_attestations),
+            16 => root(&self.current_epoch_attestations),
+            17 => root(&self.justification_bits),
+            18 => root(&self.previous_justified_checkpoint),
+            19 => root(&self.current_justified_checkpoint),
+            20 => root(&self.finalized_checkpoint),
+            _ => return None,
+        };
+        Some(field_root)
+    }
+
+    fn field(&self, index: usize) -> Option<&dyn ProofTree> {
+        match index {
+            3 => Some(&self.fork),
+            4 => Some(&self.latest_block_header),
+            8 => Some(&self.eth1_data),
+            18 => Some(&self.previous_justified_checkpoint),
+            19 => Some(&self.current_justified_checkpoint),
+            20 => Some(&self.finalized_checkpoint),
+            _ => None,
+        }
+    }
+
+    fn field_node(&self, index: usize, gindex: u64) -> Option<Result<Hash256, Error>> {
+        let (tree, arena) = self.tree_hash_cache.as_ref()?.field_tree(index)?;
+        let list_len = match index {
+            7 => Some(self.historical_roots.len()),
+            11 => Some(self.validators.len()),
+            12 => Some(self.balances.len()),
+            _ => None,
+        };
+        Some(match list_len {
+            Some(len) => cached_list_node(tree, arena, len, gindex),
+            None => cached_vector_node(tree, arena, gindex),
+        })
+    }
+}
+
+impl<T: EthSpec> ProofTree for BeaconBlock<T> {
+    fn num_fields(&self) -> usize {
+        5
+    }
+
+    fn field_root(&self, index: usize) -> Option<Hash256> {
+        let field_root = match index {
+            0 => root(&self.slot),
+            1 => root(&self.proposer_index),
+            2 => root(&self.parent_root),
+            3 => root(&self.state_root),
+            4 => root(&self.body),
+            _ => return None,
+        };
+        Some(field_root)
+    }
+
+    fn field(&self, index: usize) -> Option<&dyn ProofTree> {
+        match index {
+            4 => Some(&self.body),
+            _ => None,
+        }
+    }
+}
+
+impl<T: EthSpec> ProofTree for BeaconBlockBody<T> {
+    fn num_fields(&self) -> usize {
+        8
+    }
+
+    fn field_root(&self, index: usize) -> Option<Hash256> {
+        let field_root = match index {
+            0 => root(&self.randao_reveal),
+            1 => root(&self.eth1_data),
+            2 => root(&self.graffiti),
+            3 => root(&self.proposer_slashings),
+            4 => root(&self.attester_slashings),
+            5 => root(&self.attestations),
+            6 => root(&self.deposits),
+            7 => root(&self.voluntary_exits),
+            _ => return None,
+        };
+        Some(field_root)
+    }
+
+    fn field(&self, index: usize) -> Option<&dyn ProofTree> {
+        match index {
+            1 => Some(&self.eth1_data),
+            _ => None,
+        }
+    }
+}
+
+impl ProofTree for BeaconBlockHeader {
+    fn num_fields(&self) -> usize {
+        5
+    }
+
+    fn field_root(&self, index: usize) -> Option<Hash256> {
+        let field_root = match index {
+            0 => root(&self.slot),
+            1 => root(&self.proposer_index),
+            2 => root(&self.parent_root),
+            3 => root(&self.state_root),
+            4 => root(&self.body_root),
+            _ => return None,
+        };
+        Some(field_root)
+    }
+}
+
+impl ProofTree for Checkpoint {
+    fn num_fields(&self) -> usize {
+        2
+    }
+
+    fn field_root(&self, index: usize) -> Option<Hash256> {
+        let field_root = match index {
+            0 => root(&self.epoch),
+            1 => root(&self.root),
+            _ => return None,
+        };
+        Some(field_root)
+    }
+}
+
+impl ProofTree for Eth1Data {
+    fn num_fields(&self) -> usize {
+        3
+    }
+
+    fn field_root(&self, index: usize) -> Option<Hash256> {
+        let field_root = match index {
+            0 => root(&self.deposit_root),
+            1 => root(&self.deposit_count),
+            2 => root(&self.block_hash),
+            _ => return None,
+        };
+        Some(field_root)
+    }
+}
+
+impl ProofTree for Fork {
+    fn num_fields(&self) -> usize {
+        3
+    }
+
+    fn field_root(&self, index: usize) -> Option<Hash256> {
+        let field_root = match index {
+            0 => root(&self.previous_version),
+            1 => root(&self.current_version),
+            2 => root(&self.epoch),
+            _ => return None,
+        };
+        Some(field_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestingBeaconStateBuilder;
+
+    /// The generalized index of `state.slot`.
+    const SLOT_GINDEX: u64 = 32 + 2;
+    /// The generalized index of `state.finalized_checkpoint.root`.
+    const FINALIZED_ROOT_GINDEX: u64 = (32 + 20) * 2 + 1;
+    /// The generalized index of the first node beneath `state.validators`.
+    const VALIDATORS_CHILD_GINDEX: u64 = (32 + 11) * 2;
+    /// The generalized index of the length of `state.validators`.
+    const VALIDATORS_LENGTH_GINDEX: u64 = (32 + 11) * 2 + 1;
+    /// The depth of the tree of `state.validators`, which has a limit of 2^40 validators.
+    const VALIDATORS_DEPTH: u64 = 40;
+    /// The depth of the tree of `state.balances`, which packs four balances per leaf.
+    const BALANCES_DEPTH: u64 = 38;
+    /// The depth of the tree of `state.block_roots`, which has 64 roots with `MinimalEthSpec`.
+    const BLOCK_ROOTS_DEPTH: u64 = 6;
+
+    /// Returns the generalized index of the validator at `index`.
+    fn validator_gindex(index: u64) -> u64 {
+        (VALIDATORS_CHILD_GINDEX << VALIDATORS_DEPTH) | index
+    }
+
+    fn get_state() -> BeaconState<MinimalEthSpec> {
+        let spec = MinimalEthSpec::default_spec();
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(8, &spec);
+        let (mut state, _keypairs) = builder.build();
+        state.slot = Slot::new(42);
+        state.finalized_checkpoint.root = Hash256::repeat_byte(42);
+        state.block_roots[5] = Hash256::repeat_byte(5);
+        state
+            .update_tree_hash_cache()
+            .expect("should update tree hash cache");
+        state
+    }
+
+    #[test]
+    fn multiproof_of_state() {
+        let state = get_state();
+
+        let proof = Multiproof::generate(&state, &[SLOT_GINDEX, FINALIZED_ROOT_GINDEX])
+            .expect("should generate proof");
+
+        assert_eq!(
+            proof.leaves,
+            vec![root(&state.slot), Hash256::repeat_byte(42)]
+        );
+        assert_eq!(proof.calculate_root(), Some(state.canonical_root()));
+    }
+
+    #[test]
+    fn single_proof_of_state() {
+        let state = get_state();
+
+        let proof =
+            Multiproof::generate(&state, &[FINALIZED_ROOT_GINDEX]).expect("should generate proof");
+
+        // A proof of a single node has one sibling per level of the tree.
+        assert_eq!(proof.proof.len(), 6);
+        assert_eq!(proof.calculate_root(), Some(state.canonical_root()));
+    }
+
+    #[test]
+    fn multiproof_of_lists_and_vectors() {
+        let state = get_state();
+        let balances_gindex = ((32 + 12) * 2) << BALANCES_DEPTH;
+        let block_root_gindex = ((32 + 5) << BLOCK_ROOTS_DEPTH) | 5;
+
+        let proof = Multiproof::generate(
+            &state,
+            &[
+                validator_gindex(3),
+                validator_gindex(6),
+                VALIDATORS_LENGTH_GINDEX,
+                balances_gindex,
+                block_root_gindex,
+            ],
+        )
+        .expect("should generate proof");
+
+        assert_eq!(proof.leaves[0], root(&state.validators[3]));
+        assert_eq!(proof.leaves[1], root(&state.validators[6]));
+        assert_eq!(proof.leaves[2], root(&(state.validators.len() as u64)));
+        assert_eq!(proof.leaves[4], Hash256::repeat_byte(5));
+        assert_eq!(proof.calculate_root(), Some(state.canonical_root()));
+    }
+
+    #[test]
+    fn proof_of_padding_in_list() {
+        let state = get_state();
+        let gindex = validator_gindex(state.validators.len() as u64);
+
+        let proof = Multiproof::generate(&state, &[gindex]).expect("should generate proof");
+
+        assert_eq!(proof.leaves, vec![Hash256::zero()]);
+        assert_eq!(proof.calculate_root(), Some(state.canonical_root()));
+    }
+
+    #[test]
+    fn multiproof_of_block() {
+        let block = BeaconBlock::<MinimalEthSpec>::empty(&MinimalEthSpec::default_spec());
+        // The generalized index of `block.body.eth1_data.deposit_count`.
+        let gindex = ((8 + 4) * 8 + 1) * 4 + 1;
+
+        let proof = Multiproof::generate(&block, &[gindex]).expect("should generate proof");
+
+        assert_eq!(proof.calculate_root(), Some(block.canonical_root()));
+    }
+
+    #[test]
+    fn invalid_gindices() {
+        let state = get_state();
+
+        assert_eq!(
+            Multiproof::generate(&state, &[0]),
+            Err(Error::InvalidGeneralizedIndex(0))
+        );
+        assert_eq!(
+            Multiproof::generate(&state, &[(32 + 21) * 2]),
+            Err(Error::InvalidGeneralizedIndex((32 + 21) * 2))
+        );
+        assert_eq!(
+            Multiproof::generate(&state, &[VALIDATORS_LENGTH_GINDEX * 2]),
+            Err(Error::InvalidGeneralizedIndex(VALIDATORS_LENGTH_GINDEX * 2))
+        );
+        // Proofs do not descend into the elements of lists.
+        assert_eq!(
+            Multiproof::generate(&state, &[validator_gindex(3) * 2]),
+            Err(Error::UnsupportedGeneralizedIndex(validator_gindex(3) * 2))
+        );
+    }
+
+    #[test]
+    fn lists_require_tree_hash_cache() {
+        let mut state = get_state();
+        state.drop_tree_hash_cache();
+
+        assert_eq!(
+            Multiproof::generate(&state, &[VALIDATORS_CHILD_GINDEX]),
+            Err(Error::UnsupportedGeneralizedIndex(VALIDATORS_CHILD_GINDEX))
+        );
+        // The roots of fields do not require the cache.
+        let proof = Multiproof::generate(&state, &[SLOT_GINDEX]).expect("should generate proof");
+        assert_eq!(proof.calculate_root(), Some(state.canonical_root()));
+    }
+
+    #[test]
+    fn rejects_malformed_proof() {
+        let state = get_state();
+        let mut proof = Multiproof::generate(&state, &[SLOT_GINDEX]).expect("should generate");
+
+        proof.proof.pop();
+
+        assert_eq!(proof.calculate_root(), None);
+    }
+}
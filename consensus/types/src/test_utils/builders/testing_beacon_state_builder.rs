@@ -102,8 +102,8 @@ impl<T: EthSpec> TestingBeaconStateBuilder<T> {
         let balances = vec![starting_balance; validator_count].into();
 
         debug!("Importing {} existing validators...", validator_count);
-        state.validators = validators;
-        state.balances = balances;
+        *state.validators = validators;
+        *state.balances = balances;
 
         debug!("BeaconState initialized.");
 
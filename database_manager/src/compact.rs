@@ -0,0 +1,23 @@
+use crate::DiskStore;
+use clap::App;
+use types::EthSpec;
+
+pub const CMD: &str = "compact";
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD).about(
+        "Compacts the hot and freezer databases, reclaiming the disk space used by deleted data.",
+    )
+}
+
+pub fn cli_run<T: EthSpec>(store: &DiskStore<T>) -> Result<(), String> {
+    println!("Compacting the database, this may take several minutes...");
+
+    store
+        .compact()
+        .map_err(|e| format!("Unable to compact database: {:?}", e))?;
+
+    println!("Compaction complete");
+
+    Ok(())
+}
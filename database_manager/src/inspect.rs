@@ -0,0 +1,47 @@
+use crate::DiskStore;
+use clap::App;
+use store::{ColumnIter, DBColumn};
+use types::EthSpec;
+
+pub const CMD: &str = "inspect";
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD).about("Reports the number of keys and bytes stored in each database column.")
+}
+
+pub fn cli_run<T: EthSpec>(store: &DiskStore<T>) -> Result<(), String> {
+    print_column_stats("Hot database", |column| store.iter_hot_column(column))?;
+    println!();
+    print_column_stats("Freezer database", |column| store.iter_cold_column(column))
+}
+
+/// Prints the key count and total size of every column returned by `iter_column`.
+fn print_column_stats<'a, F>(db_name: &str, iter_column: F) -> Result<(), String>
+where
+    F: Fn(DBColumn) -> ColumnIter<'a>,
+{
+    println!("{}:", db_name);
+    println!("{:<24} {:>12} {:>16}", "Column", "Keys", "Bytes");
+
+    let (mut total_keys, mut total_bytes) = (0, 0);
+
+    for column in DBColumn::ALL.iter() {
+        let (mut keys, mut bytes) = (0, 0);
+
+        for item in iter_column(*column) {
+            let (key, value) =
+                item.map_err(|e| format!("Unable to read {:?} column: {:?}", column, e))?;
+            keys += 1;
+            bytes += key.len() + value.len();
+        }
+
+        println!("{:<24} {:>12} {:>16}", format!("{:?}", column), keys, bytes);
+
+        total_keys += keys;
+        total_bytes += bytes;
+    }
+
+    println!("{:<24} {:>12} {:>16}", "Total", total_keys, total_bytes);
+
+    Ok(())
+}
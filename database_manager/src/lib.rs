@@ -0,0 +1,101 @@
+//! Utilities for inspecting and maintaining the beacon node database.
+//!
+//! LevelDB only permits a single process to open a database, so the beacon node must be stopped
+//! before running any of these commands.
+mod compact;
+mod inspect;
+mod verify;
+
+use beacon_node::{get_data_dir, ClientConfig};
+use clap::{App, Arg, ArgMatches};
+use clap_utils::parse_optional;
+use environment::Environment;
+use std::path::PathBuf;
+use store::{HotColdDB, LevelDB, StoreConfig};
+use types::EthSpec;
+
+pub const CMD: &str = "database_manager";
+pub const FREEZER_DIR_FLAG: &str = "freezer-dir";
+pub const SLOTS_PER_RESTORE_POINT_FLAG: &str = "slots-per-restore-point";
+
+/// The on-disk database used by the beacon node.
+pub type DiskStore<E> = HotColdDB<E, LevelDB<E>, LevelDB<E>>;
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD)
+        .visible_aliases(&["db"])
+        .about(
+            "Utilities for inspecting and maintaining the beacon node database. The beacon node \
+             must not be running.",
+        )
+        .arg(
+            Arg::with_name(FREEZER_DIR_FLAG)
+                .long(FREEZER_DIR_FLAG)
+                .value_name("DIR")
+                .help("Data directory for the freezer database, if it differs from the default.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(SLOTS_PER_RESTORE_POINT_FLAG)
+                .long(SLOTS_PER_RESTORE_POINT_FLAG)
+                .value_name("SLOT_COUNT")
+                .help(
+                    "The --slots-per-restore-point the beacon node was run with, if it differs \
+                     from the default.",
+                )
+                .takes_value(true),
+        )
+        .subcommand(compact::cli_app())
+        .subcommand(inspect::cli_app())
+        .subcommand(verify::cli_app())
+}
+
+/// Run the database manager, returning an error if the operation did not succeed.
+pub fn run<T: EthSpec>(matches: &ArgMatches<'_>, mut env: Environment<T>) -> Result<(), String> {
+    let store = open_store(matches, &mut env)?;
+
+    match matches.subcommand() {
+        (compact::CMD, Some(_)) => compact::cli_run(&store),
+        (inspect::CMD, Some(_)) => inspect::cli_run(&store),
+        (verify::CMD, Some(_)) => verify::cli_run(&store),
+        (unknown, _) => Err(format!(
+            "{} is not a valid {} command. See --help.",
+            unknown, CMD
+        )),
+    }
+}
+
+/// Opens the existing hot and freezer databases of the beacon node.
+fn open_store<T: EthSpec>(
+    matches: &ArgMatches<'_>,
+    env: &mut Environment<T>,
+) -> Result<DiskStore<T>, String> {
+    let mut client_config = ClientConfig::default();
+    client_config.data_dir = get_data_dir(matches);
+    client_config.freezer_db_path = parse_optional::<PathBuf>(matches, FREEZER_DIR_FLAG)?;
+
+    let mut store_config = StoreConfig::default();
+    if let Some(slots_per_restore_point) = parse_optional(matches, SLOTS_PER_RESTORE_POINT_FLAG)? {
+        store_config.slots_per_restore_point = slots_per_restore_point;
+    }
+
+    let hot_path = client_config
+        .get_db_path()
+        .ok_or_else(|| "Unable to locate the database".to_string())?;
+    let cold_path = client_config
+        .get_freezer_db_path()
+        .ok_or_else(|| "Unable to locate the freezer database".to_string())?;
+
+    // Opening a LevelDB database creates it if it is missing, which is never intended here.
+    for path in &[&hot_path, &cold_path] {
+        if !path.exists() {
+            return Err(format!("No database found at {:?}", path));
+        }
+    }
+
+    let spec = env.eth2_config.spec.clone();
+    let log = env.core_context().log().clone();
+
+    HotColdDB::open(&hot_path, &cold_path, store_config, spec, log)
+        .map_err(|e| format!("Unable to open database: {:?}", e))
+}
@@ -0,0 +1,98 @@
+use crate::DiskStore;
+use clap::App;
+use store::{ColumnIter, DBColumn, Error, StoreItem};
+use types::{BeaconState, EthSpec, Hash256, SignedBeaconBlock};
+
+pub const CMD: &str = "verify";
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD).about(
+        "Verifies the integrity of all stored blocks and full states by re-computing their roots \
+         and comparing them to the roots they are stored under. Can take a long time.",
+    )
+}
+
+pub fn cli_run<T: EthSpec>(store: &DiskStore<T>) -> Result<(), String> {
+    let mut failures = 0;
+
+    failures += verify_column(
+        "blocks",
+        store.iter_hot_column(DBColumn::BeaconBlock),
+        |root, bytes| {
+            SignedBeaconBlock::<T>::from_store_bytes(bytes)
+                .map(|block| block.canonical_root() == root)
+        },
+    )?;
+
+    failures += verify_column(
+        "hot states",
+        store.iter_hot_column(DBColumn::BeaconState),
+        |root, _| {
+            store
+                .load_hot_state(&root)
+                .map(|state| matches_root(state, root))
+        },
+    )?;
+
+    failures += verify_column(
+        "freezer restore points",
+        store.iter_cold_column(DBColumn::BeaconState),
+        |root, _| {
+            store
+                .load_cold_state(&root)
+                .map(|state| matches_root(state, root))
+        },
+    )?;
+
+    if failures == 0 {
+        println!("Verification complete, no errors found");
+        Ok(())
+    } else {
+        Err(format!("{} items failed verification", failures))
+    }
+}
+
+/// Returns `true` if `state` exists and has the given `root`.
+fn matches_root<T: EthSpec>(state: Option<BeaconState<T>>, root: Hash256) -> bool {
+    state.map_or(false, |state| state.canonical_root() == root)
+}
+
+/// Verifies each item in `column` using `verify`, returning the number of failures.
+///
+/// `verify` is supplied with the root the item is stored under and its bytes, and returns whether
+/// the item's contents match that root.
+fn verify_column<F>(name: &str, column: ColumnIter, verify: F) -> Result<usize, String>
+where
+    F: Fn(Hash256, &[u8]) -> Result<bool, Error>,
+{
+    println!("Verifying {}...", name);
+
+    let (mut verified, mut failures) = (0, 0);
+
+    for item in column {
+        let (key, value) = item.map_err(|e| format!("Unable to read {}: {:?}", name, e))?;
+
+        if key.len() != 32 {
+            println!("Invalid key: {:?}", key);
+            failures += 1;
+            continue;
+        }
+
+        let root = Hash256::from_slice(&key);
+        match verify(root, &value) {
+            Ok(true) => verified += 1,
+            Ok(false) => {
+                println!("Root mismatch: {:?}", root);
+                failures += 1;
+            }
+            Err(e) => {
+                println!("Unable to load {:?}: {:?}", root, e);
+                failures += 1;
+            }
+        }
+    }
+
+    println!("Verified {} {}, {} failures", verified, name, failures);
+
+    Ok(failures)
+}
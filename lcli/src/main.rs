@@ -11,6 +11,7 @@ mod interop_genesis;
 mod new_testnet;
 mod parse_hex;
 mod refund_deposit_contract;
+mod replay_fork_choice;
 mod skip_slots;
 mod transition_blocks;
 
@@ -131,6 +132,30 @@ fn main() {
                         .help("Path to output a SSZ file."),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("replay-fork-choice")
+                .about(
+                    "Finds the head of a fork choice snapshot from the /advanced/fork_choice/snapshot \
+                    HTTP endpoint, after applying hypothetical votes",
+                )
+                .arg(
+                    Arg::with_name("snapshot")
+                        .value_name("JSON_FILE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to a JSON file of the fork choice snapshot."),
+                )
+                .arg(
+                    Arg::with_name("votes")
+                        .long("votes")
+                        .value_name("JSON_FILE")
+                        .takes_value(true)
+                        .help(
+                            "Path to a JSON file of an array of votes, each with a validator_index, \
+                            block_root and target_epoch.",
+                        ),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("pretty-hex")
                 .about("Parses SSZ encoded as ASCII 0x-prefixed hex")
@@ -549,6 +574,8 @@ fn run<T: EthSpec>(
         ("skip-slots", Some(matches)) => {
             skip_slots::run::<T>(matches).map_err(|e| format!("Failed to skip slots: {}", e))
         }
+        ("replay-fork-choice", Some(matches)) => replay_fork_choice::run(matches)
+            .map_err(|e| format!("Failed to replay fork choice: {}", e)),
         ("pretty-hex", Some(matches)) => {
             run_parse_hex::<T>(matches).map_err(|e| format!("Failed to pretty print hex: {}", e))
         }
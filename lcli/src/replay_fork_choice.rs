@@ -0,0 +1,49 @@
+use clap::ArgMatches;
+use proto_array_fork_choice::{ForkChoiceSnapshot, HypotheticalVote};
+use serde::de::DeserializeOwned;
+use std::fs::File;
+use std::path::PathBuf;
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let snapshot_path = matches
+        .value_of("snapshot")
+        .ok_or_else(|| "No snapshot file supplied".to_string())?
+        .parse::<PathBuf>()
+        .map_err(|e| format!("Failed to parse snapshot path: {}", e))?;
+
+    let snapshot: ForkChoiceSnapshot = load_from_json(snapshot_path)?;
+    let votes: Vec<HypotheticalVote> = if let Some(path) = matches.value_of("votes") {
+        load_from_json(
+            path.parse::<PathBuf>()
+                .map_err(|e| format!("Failed to parse votes path: {}", e))?,
+        )?
+    } else {
+        vec![]
+    };
+
+    info!(
+        "Justified checkpoint: {:?}, finalized checkpoint: {:?}",
+        snapshot.justified_checkpoint, snapshot.finalized_checkpoint
+    );
+
+    let (original_head, _) = snapshot.replay(&[])?;
+    info!("Head without hypothetical votes: {:?}", original_head);
+
+    info!("Applying {} hypothetical votes", votes.len());
+    let (head, replayed) = snapshot.replay(&votes)?;
+    info!("Head with hypothetical votes: {:?}", head);
+
+    println!(
+        "{}",
+        serde_yaml::to_string(&replayed.proto_array.block_tree())
+            .map_err(|e| format!("Unable to write block tree to YAML: {:?}", e))?
+    );
+
+    Ok(())
+}
+
+fn load_from_json<T: DeserializeOwned>(path: PathBuf) -> Result<T, String> {
+    let file =
+        File::open(path.clone()).map_err(|e| format!("Unable to open {:?}: {:?}", path, e))?;
+    serde_json::from_reader(file).map_err(|e| format!("Unable to decode {:?}: {:?}", path, e))
+}
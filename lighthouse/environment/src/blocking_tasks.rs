@@ -0,0 +1,105 @@
+//! Tracks the blocking tasks spawned by a `TaskExecutor`, so that a shutdown can wait for them.
+//!
+//! Firing the exit signal cancels async tasks, but a blocking task (e.g., a block import) cannot
+//! be interrupted. Instead, once shutdown begins no new blocking tasks are started and the
+//! `Environment` waits for the running tasks to finish before the node persists its state.
+
+use parking_lot::{Condvar, Mutex};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct State {
+    running: usize,
+    shutting_down: bool,
+}
+
+/// Counts the blocking tasks which are running.
+#[derive(Default)]
+pub struct BlockingTasks {
+    state: Mutex<State>,
+    idle: Condvar,
+}
+
+impl BlockingTasks {
+    /// Registers the start of a blocking task, which is running until the returned guard is
+    /// dropped.
+    ///
+    /// Returns `None` if shutdown has begun, in which case the task should not be started.
+    pub fn start(self: &Arc<Self>) -> Option<BlockingTaskGuard> {
+        let mut state = self.state.lock();
+        if state.shutting_down {
+            return None;
+        }
+        state.running += 1;
+
+        Some(BlockingTaskGuard {
+            tasks: self.clone(),
+        })
+    }
+
+    /// Prevents any further blocking tasks from starting, then blocks the current thread until
+    /// the running tasks have finished or `timeout` has elapsed.
+    ///
+    /// Returns the number of tasks still running.
+    pub fn shutdown(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock();
+        state.shutting_down = true;
+
+        while state.running > 0 {
+            if self.idle.wait_until(&mut state, deadline).timed_out() {
+                break;
+            }
+        }
+
+        state.running
+    }
+}
+
+/// Marks a blocking task as running for as long as it is held.
+pub struct BlockingTaskGuard {
+    tasks: Arc<BlockingTasks>,
+}
+
+impl Drop for BlockingTaskGuard {
+    fn drop(&mut self) {
+        let mut state = self.tasks.state.lock();
+        state.running -= 1;
+        if state.running == 0 {
+            self.tasks.idle.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn shutdown_waits_for_running_tasks() {
+        let tasks = Arc::new(BlockingTasks::default());
+        let guard = tasks.start().expect("should start before shutdown");
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            drop(guard);
+        });
+
+        assert_eq!(tasks.shutdown(Duration::from_secs(10)), 0);
+        assert!(
+            tasks.start().is_none(),
+            "should not start tasks after shutdown"
+        );
+        handle.join().expect("task thread should not panic");
+    }
+
+    #[test]
+    fn shutdown_times_out() {
+        let tasks = Arc::new(BlockingTasks::default());
+        let _guard = tasks.start().expect("should start before shutdown");
+
+        assert_eq!(tasks.shutdown(Duration::from_millis(10)), 1);
+    }
+}
@@ -1,6 +1,9 @@
+use crate::blocking_tasks::BlockingTasks;
 use crate::metrics;
+use futures::channel::mpsc::Sender;
 use futures::prelude::*;
 use slog::{debug, trace};
+use std::sync::Arc;
 use tokio::runtime::Handle;
 
 /// A wrapper over a runtime handle which can spawn async and blocking tasks.
@@ -10,6 +13,10 @@ pub struct TaskExecutor {
     pub(crate) handle: Handle,
     /// The receiver exit future which on receiving shuts down the task
     pub(crate) exit: exit_future::Exit,
+    /// Sender given to tasks, so that they can request a shutdown of the whole process.
+    pub(crate) signal_tx: Sender<&'static str>,
+    /// The blocking tasks which are running, which a shutdown waits for.
+    pub(crate) blocking_tasks: Arc<BlockingTasks>,
     pub(crate) log: slog::Logger,
 }
 
@@ -18,8 +25,19 @@ impl TaskExecutor {
     ///
     /// Note: this function is mainly useful in tests. A `TaskExecutor` should be normally obtained from
     /// a [`RuntimeContext`](struct.RuntimeContext.html)
-    pub fn new(handle: Handle, exit: exit_future::Exit, log: slog::Logger) -> Self {
-        Self { handle, exit, log }
+    pub fn new(
+        handle: Handle,
+        exit: exit_future::Exit,
+        log: slog::Logger,
+        signal_tx: Sender<&'static str>,
+    ) -> Self {
+        Self {
+            handle,
+            exit,
+            signal_tx,
+            blocking_tasks: Arc::new(BlockingTasks::default()),
+            log,
+        }
     }
 
     /// Spawn a future on the tokio runtime wrapped in an `exit_future::Exit`. The task is canceled
@@ -77,6 +95,9 @@ impl TaskExecutor {
 
     /// Spawn a blocking task on a dedicated tokio thread pool wrapped in an exit future.
     /// This function generates prometheus metrics on number of tasks and task duration.
+    ///
+    /// The task is not started if a shutdown has begun. Once started, the task cannot be
+    /// canceled, instead the shutdown waits for it to complete.
     pub fn spawn_blocking<F>(&self, task: F, name: &'static str)
     where
         F: FnOnce() -> () + Send + 'static,
@@ -84,6 +105,17 @@ impl TaskExecutor {
         let exit = self.exit.clone();
         let log = self.log.clone();
 
+        let guard = if let Some(guard) = self.blocking_tasks.start() {
+            guard
+        } else {
+            debug!(log, "Blocking task not started, shutting down"; "task" => name);
+            return;
+        };
+        let task = move || {
+            task();
+            drop(guard);
+        };
+
         if let Some(metric) = metrics::get_histogram(&metrics::BLOCKING_TASKS_HISTOGRAM, &[name]) {
             if let Some(int_gauge) = metrics::get_int_gauge(&metrics::BLOCKING_TASKS_COUNT, &[name])
             {
@@ -121,6 +153,12 @@ impl TaskExecutor {
         self.exit.clone()
     }
 
+    /// Returns a sender which may be used to request a shutdown of the process, giving the reason
+    /// for the shutdown.
+    pub fn shutdown_sender(&self) -> Sender<&'static str> {
+        self.signal_tx.clone()
+    }
+
     /// Returns a reference to the logger.
     pub fn log(&self) -> &slog::Logger {
         &self.log
@@ -9,20 +9,27 @@
 
 use eth2_config::Eth2Config;
 use eth2_testnet_config::Eth2TestnetConfig;
-use futures::channel::oneshot;
+use futures::channel::{
+    mpsc::{channel, Receiver, Sender},
+    oneshot,
+};
+use futures::{future, StreamExt};
 
+use blocking_tasks::BlockingTasks;
 pub use executor::TaskExecutor;
-use slog::{info, o, Drain, Level, Logger};
+use rotating_file::RotatingFile;
+use slog::{info, o, warn, Drain, Level, Logger};
 use sloggers::{null::NullLoggerBuilder, Build};
 use std::cell::RefCell;
-use std::ffi::OsStr;
-use std::fs::{rename as FsRename, OpenOptions};
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
 use types::{EthSpec, InteropEthSpec, MainnetEthSpec, MinimalEthSpec};
+mod blocking_tasks;
 mod executor;
 mod metrics;
+mod rotating_file;
 
 pub const ETH2_CONFIG_FILENAME: &str = "eth2-spec.toml";
 
@@ -177,12 +184,16 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
     /// Consumes the builder, returning an `Environment`.
     pub fn build(self) -> Result<Environment<E>, String> {
         let (signal, exit) = exit_future::signal();
+        let (signal_tx, signal_rx) = channel(1);
         Ok(Environment {
             runtime: self
                 .runtime
                 .ok_or_else(|| "Cannot build environment without runtime".to_string())?,
+            signal_tx,
+            signal_rx: Some(signal_rx),
             signal: Some(signal),
             exit,
+            blocking_tasks: Arc::new(BlockingTasks::default()),
             log: self
                 .log
                 .ok_or_else(|| "Cannot build environment without log".to_string())?,
@@ -213,6 +224,8 @@ impl<E: EthSpec> RuntimeContext<E> {
             executor: TaskExecutor {
                 handle: self.executor.handle.clone(),
                 exit: self.executor.exit.clone(),
+                signal_tx: self.executor.signal_tx.clone(),
+                blocking_tasks: self.executor.blocking_tasks.clone(),
                 log: self.executor.log.new(o!("service" => service_name)),
             },
             eth_spec_instance: self.eth_spec_instance.clone(),
@@ -235,8 +248,14 @@ impl<E: EthSpec> RuntimeContext<E> {
 /// validator client, or to run tests that involve logging and async task execution.
 pub struct Environment<E: EthSpec> {
     runtime: Runtime,
+    /// Receives shutdown requests from services.
+    signal_rx: Option<Receiver<&'static str>>,
+    /// Sender given to services, so they may request a shutdown.
+    signal_tx: Sender<&'static str>,
     signal: Option<exit_future::Signal>,
     exit: exit_future::Exit,
+    /// The blocking tasks spawned by the executors of this environment.
+    blocking_tasks: Arc<BlockingTasks>,
     log: Logger,
     eth_spec_instance: E,
     pub eth2_config: Eth2Config,
@@ -257,6 +276,8 @@ impl<E: EthSpec> Environment<E> {
         RuntimeContext {
             executor: TaskExecutor {
                 exit: self.exit.clone(),
+                signal_tx: self.signal_tx.clone(),
+                blocking_tasks: self.blocking_tasks.clone(),
                 handle: self.runtime().handle().clone(),
                 log: self.log.clone(),
             },
@@ -270,6 +291,8 @@ impl<E: EthSpec> Environment<E> {
         RuntimeContext {
             executor: TaskExecutor {
                 exit: self.exit.clone(),
+                signal_tx: self.signal_tx.clone(),
+                blocking_tasks: self.blocking_tasks.clone(),
                 handle: self.runtime().handle().clone(),
                 log: self.log.new(o!("service" => service_name.clone())),
             },
@@ -278,8 +301,10 @@ impl<E: EthSpec> Environment<E> {
         }
     }
 
-    /// Block the current thread until Ctrl+C is received.
-    pub fn block_until_ctrl_c(&mut self) -> Result<(), String> {
+    /// Block the current thread until Ctrl+C is received or a service requests a shutdown.
+    ///
+    /// Returns the reason given by the service, if the shutdown was requested by a service.
+    pub fn block_until_shutdown_requested(&mut self) -> Result<Option<&'static str>, String> {
         let (ctrlc_send, ctrlc_oneshot) = oneshot::channel();
         let ctrlc_send_c = RefCell::new(Some(ctrlc_send));
         ctrlc::set_handler(move || {
@@ -289,10 +314,21 @@ impl<E: EthSpec> Environment<E> {
         })
         .map_err(|e| format!("Could not set ctrlc handler: {:?}", e))?;
 
-        // Block this thread until Crtl+C is pressed.
-        self.runtime()
-            .block_on(ctrlc_oneshot)
-            .map_err(|e| format!("Ctrlc oneshot failed: {:?}", e))
+        let mut signal_rx = self
+            .signal_rx
+            .take()
+            .ok_or_else(|| "Shutdown has already been requested".to_string())?;
+
+        // Block this thread until Crtl+C is pressed or a service requests a shutdown.
+        match self
+            .runtime()
+            .block_on(future::select(ctrlc_oneshot, signal_rx.next()))
+        {
+            future::Either::Left((Ok(()), _)) => Ok(None),
+            future::Either::Left((Err(e), _)) => Err(format!("Ctrlc oneshot failed: {:?}", e)),
+            future::Either::Right((Some(reason), _)) => Ok(Some(reason)),
+            future::Either::Right((None, _)) => Err("Shutdown channel closed".to_string()),
+        }
     }
 
     /// Shutdown the `tokio` runtime when all tasks are idle.
@@ -308,36 +344,38 @@ impl<E: EthSpec> Environment<E> {
         }
     }
 
-    /// Sets the logger (and all child loggers) to log to a file.
+    /// Stops any new blocking tasks from being spawned and blocks the current thread until the
+    /// running blocking tasks have finished, or until `timeout` has elapsed.
+    ///
+    /// Should be called after `Self::fire_signal`, so that services stop producing work.
+    pub fn wait_for_blocking_tasks(&self, timeout: Duration) {
+        let running = self.blocking_tasks.shutdown(timeout);
+        if running > 0 {
+            warn!(
+                self.log,
+                "Blocking tasks still running at shutdown";
+                "count" => running,
+                "timeout_secs" => timeout.as_secs(),
+            );
+        }
+    }
+
+    /// Sets the logger (and all child loggers) to log to a file, in addition to the existing
+    /// logger.
+    ///
+    /// The file is rotated once it reaches `max_size` bytes or once it has been written to for
+    /// `max_age`, keeping up to `max_number` rotated files. A `max_size` of zero disables rotation
+    /// by size and a `max_age` of `None` disables rotation by time.
     pub fn log_to_json_file(
         &mut self,
         path: PathBuf,
+        max_size: u64,
+        max_age: Option<Duration>,
+        max_number: usize,
         debug_level: &str,
         log_format: Option<&str>,
     ) -> Result<(), String> {
-        // Creating a backup if the logfile already exists.
-        if path.exists() {
-            let start = SystemTime::now();
-            let timestamp = start
-                .duration_since(UNIX_EPOCH)
-                .map_err(|e| e.to_string())?
-                .as_secs();
-            let file_stem = path
-                .file_stem()
-                .ok_or_else(|| "Invalid file name".to_string())?
-                .to_str()
-                .ok_or_else(|| "Failed to create str from filename".to_string())?;
-            let file_ext = path.extension().unwrap_or_else(|| OsStr::new(""));
-            let backup_name = format!("{}_backup_{}", file_stem, timestamp);
-            let backup_path = path.with_file_name(backup_name).with_extension(file_ext);
-            FsRename(&path, &backup_path).map_err(|e| e.to_string())?;
-        }
-
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&path)
+        let file = RotatingFile::new(path.clone(), max_size, max_age, max_number)
             .map_err(|e| format!("Unable to open logfile: {:?}", e))?;
 
         let log_format = log_format.unwrap_or("JSON");
@@ -359,12 +397,18 @@ impl<E: EthSpec> Environment<E> {
             unknown => return Err(format!("Unknown debug-level: {}", unknown)),
         };
 
-        self.log = Logger::root(drain.fuse(), o!());
+        self.log = Logger::root(
+            slog::Duplicate::new(self.log.clone(), drain.fuse()).fuse(),
+            o!(),
+        );
 
         info!(
             self.log,
             "Logging to JSON file";
-            "path" => format!("{:?}", path)
+            "path" => format!("{:?}", path),
+            "max_size" => max_size,
+            "max_age_secs" => max_age.map(|age| age.as_secs()),
+            "max_number" => max_number,
         );
 
         Ok(())
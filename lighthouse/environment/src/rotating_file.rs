@@ -0,0 +1,267 @@
+//! Provides a file writer which rotates the file once it exceeds a maximum size or age.
+//!
+//! Rotated files are named by appending an index to the path of the file (e.g., `beacon.log.1`),
+//! where a lower index indicates a more recent file.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A file which is rotated once `max_size` bytes have been written to it or once it has been
+/// written to for `max_age`, retaining up to `max_number` rotated files.
+///
+/// Rotation only happens after a write which ends with a newline, so that a log record is never
+/// split across two files. A `max_size` of zero disables rotation by size and a `max_age` of
+/// `None` disables rotation by time.
+///
+/// A failed rotation is reported on stderr rather than as a write error (the record has already
+/// been written), and is retried after the next record.
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    opened: Instant,
+    max_size: u64,
+    max_age: Option<Duration>,
+    max_number: usize,
+    /// Set once a rotation failure has been reported, so that it is not reported for every record.
+    rotation_failed: bool,
+}
+
+impl RotatingFile {
+    /// Opens the file at `path` in append mode, creating it if it does not exist.
+    pub fn new(
+        path: PathBuf,
+        max_size: u64,
+        max_age: Option<Duration>,
+        max_number: usize,
+    ) -> io::Result<Self> {
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            file,
+            size,
+            opened: Instant::now(),
+            max_size,
+            max_age,
+            max_number,
+            rotation_failed: false,
+        })
+    }
+
+    /// Returns `true` if the file has reached its maximum size or age.
+    fn is_full(&self) -> bool {
+        let too_large = self.max_size > 0 && self.size >= self.max_size;
+        let too_old = self
+            .max_age
+            .map_or(false, |max_age| self.opened.elapsed() >= max_age);
+        too_large || too_old
+    }
+
+    /// Returns the path of the `index`th rotated file.
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    /// Shifts each of the rotated files up by one index, discarding the oldest, then moves the
+    /// current file into the first index and starts a new, empty file.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.max_number == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = self.rotated_path(self.max_number);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+
+            for index in (1..self.max_number).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        self.opened = Instant::now();
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+
+        let ends_record = buf[..written].last() == Some(&b'\n');
+        if ends_record && self.is_full() {
+            // The bytes have been written, so returning an error would cause them to be written
+            // again by `write_all`.
+            match self.rotate() {
+                Ok(()) => self.rotation_failed = false,
+                Err(e) => {
+                    if !self.rotation_failed {
+                        eprintln!("Unable to rotate log file {:?}: {:?}", self.path, e);
+                    }
+                    self.rotation_failed = true;
+                }
+            }
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "lighthouse_rotating_file_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("should create test dir");
+        dir
+    }
+
+    #[test]
+    fn rotates_on_record_boundaries() {
+        let dir = test_dir("boundaries");
+        let path = dir.join("test.log");
+        let mut file = RotatingFile::new(path.clone(), 8, None, 2).expect("should open file");
+
+        file.write_all(b"0123456789").expect("should write");
+        assert!(
+            !file.rotated_path(1).exists(),
+            "should not rotate within a record"
+        );
+
+        file.write_all(b"\n").expect("should write");
+        assert!(
+            file.rotated_path(1).exists(),
+            "should rotate after a record"
+        );
+        assert_eq!(fs::read(&path).expect("should read file"), b"");
+
+        for record in &[b"aaaaaaaa\n", b"bbbbbbbb\n", b"cccccccc\n"] {
+            file.write_all(*record).expect("should write");
+        }
+
+        assert_eq!(
+            fs::read(file.rotated_path(1)).expect("should read file"),
+            b"cccccccc\n"
+        );
+        assert_eq!(
+            fs::read(file.rotated_path(2)).expect("should read file"),
+            b"bbbbbbbb\n"
+        );
+        assert!(
+            !file.rotated_path(3).exists(),
+            "should not keep more than max_number files"
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn zero_max_size_never_rotates() {
+        let dir = test_dir("no_rotation");
+        let path = dir.join("test.log");
+        let mut file = RotatingFile::new(path.clone(), 0, None, 2).expect("should open file");
+
+        for _ in 0..16 {
+            file.write_all(b"record\n").expect("should write");
+        }
+
+        assert!(!file.rotated_path(1).exists(), "should not rotate");
+        assert_eq!(fs::read(&path).expect("should read file").len(), 16 * 7);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rotates_after_max_age() {
+        let dir = test_dir("max_age");
+        let path = dir.join("test.log");
+        let max_age = Duration::from_millis(50);
+        let mut file = RotatingFile::new(path.clone(), 0, Some(max_age), 2).expect("should open");
+
+        file.write_all(b"first\n").expect("should write");
+        assert!(
+            !file.rotated_path(1).exists(),
+            "should not rotate before max_age"
+        );
+
+        std::thread::sleep(max_age);
+        file.write_all(b"second\n").expect("should write");
+        assert_eq!(
+            fs::read(file.rotated_path(1)).expect("should read file"),
+            b"first\nsecond\n"
+        );
+        assert_eq!(fs::read(&path).expect("should read file"), b"");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failed_rotation_does_not_fail_writes() {
+        let dir = test_dir("failed_rotation");
+        let path = dir.join("test.log");
+        let mut file = RotatingFile::new(path.clone(), 4, None, 1).expect("should open file");
+
+        // The oldest rotated file cannot be removed, since it is a non-empty directory.
+        fs::create_dir_all(file.rotated_path(1).join("dir")).expect("should create dir");
+
+        file.write_all(b"first\n")
+            .expect("should write despite the failed rotation");
+        file.write_all(b"second\n")
+            .expect("should write despite the failed rotation");
+
+        assert_eq!(
+            fs::read(&path).expect("should read file"),
+            b"first\nsecond\n",
+            "each record should be written exactly once"
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn appends_to_existing_file() {
+        let dir = test_dir("append");
+        let path = dir.join("test.log");
+        fs::write(&path, b"existing\n").expect("should write file");
+
+        let mut file = RotatingFile::new(path.clone(), 0, None, 2).expect("should open file");
+        file.write_all(b"new\n").expect("should write");
+
+        assert_eq!(
+            fs::read(&path).expect("should read file"),
+            b"existing\nnew\n"
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -10,12 +10,15 @@ use eth2_testnet_config::HARDCODED_TESTNET;
 use slog::{crit, info, warn};
 use std::path::PathBuf;
 use std::process::exit;
+use std::time::Duration;
 use types::EthSpec;
 use validator_client::ProductionValidatorClient;
 
 pub const DEFAULT_DATA_DIR: &str = ".lighthouse";
 pub const CLIENT_CONFIG_FILENAME: &str = "beacon-node.toml";
 pub const ETH2_CONFIG_FILENAME: &str = "eth2-spec.toml";
+/// The time to wait for running blocking tasks (e.g., block imports) to finish during shutdown.
+pub const BLOCKING_TASKS_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
 fn main() {
     // Parse the CLI parameters.
@@ -50,16 +53,48 @@ fn main() {
                 .long("logfile")
                 .value_name("FILE")
                 .help(
-                    "File path where output will be written. Default file logging format is JSON.",
+                    "File path where output will be written, in addition to the terminal. \
+                    Default file logging format is JSON.",
                 )
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("logfile-max-size")
+                .long("logfile-max-size")
+                .value_name("SIZE")
+                .help(
+                    "The maximum size (in MB) of the log file before it is rotated. \
+                    A value of 0 disables rotation.",
+                )
+                .takes_value(true)
+                .default_value("200"),
+        )
+        .arg(
+            Arg::with_name("logfile-max-age")
+                .long("logfile-max-age")
+                .value_name("HOURS")
+                .help(
+                    "The number of hours the log file is written to before it is rotated. \
+                    A value of 0 disables rotation by time.",
+                )
+                .takes_value(true)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("logfile-max-number")
+                .long("logfile-max-number")
+                .value_name("COUNT")
+                .help("The maximum number of rotated log files to keep.")
+                .takes_value(true)
+                .default_value("5"),
+        )
         .arg(
             Arg::with_name("log-format")
                 .long("log-format")
                 .value_name("FORMAT")
                 .help("Specifies the format used for logging.")
                 .possible_values(&["JSON"])
+                .case_insensitive(true)
                 .takes_value(true),
         )
         .arg(
@@ -98,6 +133,7 @@ fn main() {
         .subcommand(boot_node::cli_app())
         .subcommand(validator_client::cli_app())
         .subcommand(account_manager::cli_app())
+        .subcommand(database_manager::cli_app())
         .get_matches();
 
     // boot node subcommand circumvents the environment
@@ -170,7 +206,22 @@ fn run<E: EthSpec>(
         let path = log_path
             .parse::<PathBuf>()
             .map_err(|e| format!("Failed to parse log path: {:?}", e))?;
-        environment.log_to_json_file(path, debug_level, log_format)?;
+        let max_size: u64 = clap_utils::parse_required(matches, "logfile-max-size")?;
+        let max_age_hours: u64 = clap_utils::parse_required(matches, "logfile-max-age")?;
+        let max_number: usize = clap_utils::parse_required(matches, "logfile-max-number")?;
+        let max_age = if max_age_hours == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(max_age_hours.saturating_mul(60 * 60)))
+        };
+        environment.log_to_json_file(
+            path,
+            max_size.saturating_mul(1024 * 1024),
+            max_age,
+            max_number,
+            debug_level,
+            log_format,
+        )?;
     }
 
     if std::mem::size_of::<usize>() != 8 {
@@ -198,6 +249,13 @@ fn run<E: EthSpec>(
         return Ok(());
     };
 
+    if let Some(sub_matches) = matches.subcommand_matches(database_manager::CMD) {
+        database_manager::run(sub_matches, environment)?;
+
+        // Exit as soon as database manager returns control.
+        return Ok(());
+    };
+
     warn!(
         log,
         "Ethereum 2.0 is pre-release. This software is experimental."
@@ -259,14 +317,28 @@ fn run<E: EthSpec>(
         return Err("No subcommand supplied.".into());
     }
 
-    // Block this thread until Crtl+C is pressed.
-    environment.block_until_ctrl_c()?;
-    info!(log, "Shutting down..");
+    // Block this thread until Crtl+C is pressed or a service requests a shutdown.
+    let shutdown_reason = environment.block_until_shutdown_requested()?;
+    info!(log, "Shutting down.."; "reason" => shutdown_reason.unwrap_or("Ctrl+C"));
 
+    // Stop the services from accepting new work, then let the blocking tasks that are running
+    // (e.g., block imports) finish before persisting the beacon chain.
     environment.fire_signal();
+    environment.wait_for_blocking_tasks(BLOCKING_TASKS_SHUTDOWN_TIMEOUT);
+    if let Some(beacon_chain) = beacon_node.as_ref().and_then(|node| node.beacon_chain()) {
+        if let Err(e) = beacon_chain.persist_to_disk() {
+            crit!(log, "Failed to persist beacon chain"; "error" => format!("{:?}", e));
+        }
+    }
     drop(beacon_node);
     drop(validator_client);
 
     // Shutdown the environment once all tasks have completed.
-    Ok(environment.shutdown_on_idle())
+    environment.shutdown_on_idle();
+
+    // A shutdown requested by a service is an error, so the process exits with a non-zero code.
+    match shutdown_reason {
+        Some(reason) => Err(reason.to_string()),
+        None => Ok(()),
+    }
 }
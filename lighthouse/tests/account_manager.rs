@@ -2,7 +2,11 @@
 
 use account_manager::{
     upgrade_legacy_keypairs::{CMD as UPGRADE_CMD, *},
-    validator::{create::*, CMD as VALIDATOR_CMD},
+    validator::{
+        create::*,
+        import::{self, CMD as IMPORT_CMD},
+        CMD as VALIDATOR_CMD,
+    },
     wallet::{
         create::{CMD as CREATE_CMD, *},
         list::CMD as LIST_CMD,
@@ -17,7 +21,7 @@ use std::process::{Command, Output};
 use std::str::from_utf8;
 use tempfile::{tempdir, TempDir};
 use types::Keypair;
-use validator_dir::ValidatorDir;
+use validator_dir::{ValidatorDir, VOTING_KEYSTORE_FILE};
 
 // TODO: create tests for the `lighthouse account validator deposit` command. This involves getting
 // access to an IPC endpoint during testing or adding support for deposit submission via HTTP and
@@ -366,6 +370,76 @@ fn validator_create() {
     assert_eq!(dir_child_count(validator_dir.path()), 6);
 }
 
+#[test]
+fn validator_import() {
+    let base_dir = tempdir().unwrap();
+    let validator_dir = tempdir().unwrap();
+    let secrets_dir = tempdir().unwrap();
+    let keystores_dir = tempdir().unwrap();
+    let import_validator_dir = tempdir().unwrap();
+    let import_secrets_dir = tempdir().unwrap();
+
+    let wallet = TestWallet::new(base_dir.path(), "wally");
+    wallet.create_expect_success();
+
+    let validator = TestValidator::new(validator_dir.path(), secrets_dir.path(), wallet);
+    let created = validator
+        .create_expect_success(COUNT_FLAG, 1, false)
+        .pop()
+        .unwrap();
+    let pubkey = created
+        .dir()
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap()
+        .to_string();
+
+    // Lay out the keystore and password in the same way as the `eth2.0-deposit-cli`, alongside a
+    // file which should be ignored.
+    fs::copy(
+        created.dir().join(VOTING_KEYSTORE_FILE),
+        keystores_dir
+            .path()
+            .join("keystore-m_12381_3600_0_0_0.json"),
+    )
+    .unwrap();
+    fs::write(keystores_dir.path().join("deposit_data.json"), "[]").unwrap();
+    let password_path = base_dir.path().join("keystore.pass");
+    fs::copy(secrets_dir.path().join(&pubkey), &password_path).unwrap();
+
+    let run_import = || {
+        output_result(
+            validator_cmd()
+                .arg(IMPORT_CMD)
+                .arg(format!("--{}", import::DIR_FLAG))
+                .arg(keystores_dir.path().as_os_str())
+                .arg(format!("--{}", import::PASSWORD_FLAG))
+                .arg(password_path.as_os_str())
+                .arg(format!("--{}", VALIDATOR_DIR_FLAG))
+                .arg(import_validator_dir.path().as_os_str())
+                .arg(format!("--{}", SECRETS_DIR_FLAG))
+                .arg(import_secrets_dir.path().as_os_str()),
+        )
+    };
+
+    run_import().unwrap();
+
+    assert_eq!(dir_child_count(import_validator_dir.path()), 1);
+
+    let imported = ValidatorDir::open(import_validator_dir.path().join(&pubkey))
+        .expect("should open imported validator dir");
+    assert_eq!(
+        imported
+            .voting_keypair(import_secrets_dir.path())
+            .unwrap()
+            .pk,
+        created.voting_keypair(secrets_dir.path()).unwrap().pk
+    );
+
+    // The validator already exists, so importing it again should fail.
+    run_import().unwrap_err();
+}
+
 fn write_legacy_keypair<P: AsRef<Path>>(name: &str, dir: P) -> Keypair {
     let keypair = Keypair::random();
 
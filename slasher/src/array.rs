@@ -0,0 +1,183 @@
+//! The min-max span records of each validator, stored in chunks of `Config::chunk_size` epochs.
+
+use crate::{Config, Error, SlasherDB};
+use std::collections::BTreeMap;
+use store::DBColumn;
+use types::{Epoch, EthSpec};
+
+/// A prior vote of a validator which is slashable together with a new vote.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurroundVote {
+    /// The new vote surrounds the prior vote with this target epoch.
+    Surrounds(Epoch),
+    /// The new vote is surrounded by the prior vote with this target epoch.
+    SurroundedBy(Epoch),
+}
+
+/// The kinds of span records kept for each validator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SpanKind {
+    Min,
+    Max,
+}
+
+impl SpanKind {
+    fn column(self) -> DBColumn {
+        match self {
+            SpanKind::Min => DBColumn::SlasherMinTargets,
+            SpanKind::Max => DBColumn::SlasherMaxTargets,
+        }
+    }
+
+    /// The distance recorded for epochs which no vote spans.
+    fn neutral(self) -> u16 {
+        match self {
+            SpanKind::Min => u16::max_value(),
+            SpanKind::Max => 0,
+        }
+    }
+}
+
+/// The span records of one kind of a single validator, loaded from the database chunk by chunk.
+/// Modified chunks are only written back on `commit`.
+struct Spans<'a, E: EthSpec> {
+    db: &'a SlasherDB<E>,
+    kind: SpanKind,
+    validator_index: u64,
+    chunk_size: u64,
+    /// The loaded chunks by index, along with whether they have been modified.
+    chunks: BTreeMap<u64, (Vec<u16>, bool)>,
+}
+
+impl<'a, E: EthSpec> Spans<'a, E> {
+    fn new(db: &'a SlasherDB<E>, kind: SpanKind, validator_index: u64, config: &Config) -> Self {
+        Self {
+            db,
+            kind,
+            validator_index,
+            chunk_size: config.chunk_size,
+            chunks: BTreeMap::new(),
+        }
+    }
+
+    fn chunk_mut(&mut self, epoch: Epoch) -> Result<(&mut (Vec<u16>, bool), usize), Error> {
+        let chunk_index = epoch.as_u64() / self.chunk_size;
+        let offset = (epoch.as_u64() % self.chunk_size) as usize;
+
+        if !self.chunks.contains_key(&chunk_index) {
+            let chunk_len = self.chunk_size as usize;
+            let chunk = self
+                .db
+                .get_span_chunk(self.kind.column(), chunk_index, self.validator_index)?
+                .unwrap_or_else(|| vec![self.kind.neutral(); chunk_len]);
+            if chunk.len() != chunk_len {
+                return Err(Error::InvalidSpanChunkLength {
+                    expected: chunk_len,
+                    found: chunk.len(),
+                });
+            }
+            self.chunks.insert(chunk_index, (chunk, false));
+        }
+
+        let chunk = self
+            .chunks
+            .get_mut(&chunk_index)
+            .expect("chunk was inserted above");
+        Ok((chunk, offset))
+    }
+
+    fn get(&mut self, epoch: Epoch) -> Result<u16, Error> {
+        let ((chunk, _), offset) = self.chunk_mut(epoch)?;
+        Ok(chunk[offset])
+    }
+
+    fn set(&mut self, epoch: Epoch, distance: u16) -> Result<(), Error> {
+        let ((chunk, modified), offset) = self.chunk_mut(epoch)?;
+        chunk[offset] = distance;
+        *modified = true;
+        Ok(())
+    }
+
+    /// Writes the modified chunks to the database.
+    fn commit(self) -> Result<(), Error> {
+        for (chunk_index, (chunk, modified)) in self.chunks {
+            if modified {
+                self.db.put_span_chunk(
+                    self.kind.column(),
+                    chunk_index,
+                    self.validator_index,
+                    &chunk,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the distance from `epoch` to `target`, which must not be before `epoch`.
+fn distance(target: Epoch, epoch: Epoch) -> u16 {
+    let distance = target.as_u64().saturating_sub(epoch.as_u64());
+    distance.min(u64::from(u16::max_value() - 1)) as u16
+}
+
+/// Checks the vote of `validator_index` with `source` and `target` against the span records of
+/// the validator, before updating the records to include the vote.
+///
+/// Epochs before `lowest_epoch` have fallen out of the history and are neither checked nor
+/// updated.
+pub fn check_and_update<E: EthSpec>(
+    db: &SlasherDB<E>,
+    config: &Config,
+    validator_index: u64,
+    source: Epoch,
+    target: Epoch,
+    lowest_epoch: Epoch,
+    current_epoch: Epoch,
+) -> Result<Option<SurroundVote>, Error> {
+    let mut min_spans = Spans::new(db, SpanKind::Min, validator_index, config);
+    let mut max_spans = Spans::new(db, SpanKind::Max, validator_index, config);
+    let span = distance(target, source);
+
+    let surround_vote = if source >= lowest_epoch {
+        let min_distance = min_spans.get(source)?;
+        let max_distance = max_spans.get(source)?;
+        if min_distance != SpanKind::Min.neutral() && span > min_distance {
+            Some(SurroundVote::Surrounds(source + u64::from(min_distance)))
+        } else if max_distance != SpanKind::Max.neutral() && span < max_distance {
+            Some(SurroundVote::SurroundedBy(source + u64::from(max_distance)))
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    // The min spans of the epochs before the source. Once a min span is no greater than the
+    // distance to this vote, all earlier ones are too.
+    let mut epoch = source;
+    while epoch > lowest_epoch {
+        epoch -= 1;
+        let new_distance = distance(target, epoch);
+        if min_spans.get(epoch)? <= new_distance {
+            break;
+        }
+        min_spans.set(epoch, new_distance)?;
+    }
+
+    // The max spans of the epochs after the source. Once a max span is no less than the
+    // distance to this vote, all later ones are too.
+    let mut epoch = std::cmp::max(source + 1, lowest_epoch);
+    while epoch <= current_epoch && epoch < target {
+        let new_distance = distance(target, epoch);
+        if max_spans.get(epoch)? >= new_distance {
+            break;
+        }
+        max_spans.set(epoch, new_distance)?;
+        epoch += 1;
+    }
+
+    min_spans.commit()?;
+    max_spans.commit()?;
+
+    Ok(surround_vote)
+}
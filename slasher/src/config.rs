@@ -0,0 +1,60 @@
+use crate::Error;
+use serde_derive::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// The default number of epochs of span records stored under a single database key.
+pub const DEFAULT_CHUNK_SIZE: u64 = 16;
+/// The default number of epochs for which attestations and blocks are retained.
+pub const DEFAULT_HISTORY_LENGTH: u64 = 4096;
+/// The default number of seconds between two batches of processing.
+pub const DEFAULT_UPDATE_PERIOD: u64 = 12;
+/// The default maximum number of attestations, or of blocks, awaiting processing.
+pub const DEFAULT_MAX_QUEUE_LENGTH: usize = 262_144;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    /// The directory of the slasher database.
+    pub database_path: PathBuf,
+    /// The number of epochs of span records stored under a single database key.
+    pub chunk_size: u64,
+    /// The number of epochs for which attestations and blocks are retained. Offences involving
+    /// older messages are not detected.
+    pub history_length: u64,
+    /// The number of seconds between two batches of processing of the queued messages.
+    pub update_period: u64,
+    /// The maximum number of attestations, or of blocks, awaiting processing. Messages which
+    /// arrive whilst a queue is full are dropped.
+    pub max_queue_length: usize,
+    /// Whether detected slashings are added to the op pool and published on gossip, rather than
+    /// only being reported.
+    pub broadcast: bool,
+}
+
+impl Config {
+    pub fn new(database_path: PathBuf) -> Self {
+        Self {
+            database_path,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            history_length: DEFAULT_HISTORY_LENGTH,
+            update_period: DEFAULT_UPDATE_PERIOD,
+            max_queue_length: DEFAULT_MAX_QUEUE_LENGTH,
+            broadcast: false,
+        }
+    }
+
+    /// Returns an error if the parameters cannot be used together.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.history_length == 0 || self.history_length >= u64::from(u16::max_value()) {
+            Err(Error::ConfigInvalidHistoryLength {
+                history_length: self.history_length,
+            })
+        } else if self.chunk_size == 0 || self.history_length % self.chunk_size != 0 {
+            Err(Error::ConfigInvalidChunkSize {
+                chunk_size: self.chunk_size,
+                history_length: self.history_length,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
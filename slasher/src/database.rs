@@ -0,0 +1,193 @@
+use crate::{Config, Error};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::convert::TryInto;
+use std::fs;
+use store::{get_key_for_col, DBColumn, KeyValueStore, KeyValueStoreOp, LevelDB};
+use types::{Epoch, EthSpec, Hash256, IndexedAttestation, SignedBeaconBlockHeader};
+
+/// The vote of a validator for a target epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct AttesterRecord {
+    /// The root of the attestation data voted for.
+    pub attestation_data_root: Hash256,
+    /// The root of the indexed attestation which contained the vote.
+    pub indexed_attestation_root: Hash256,
+}
+
+/// The database of the slasher.
+///
+/// All keys start with a big-endian epoch, span chunk index or slot, such that the records which
+/// have fallen out of the history are at the start of each column.
+pub struct SlasherDB<E: EthSpec> {
+    db: LevelDB<E>,
+}
+
+/// Returns the key made of the big-endian `prefix` followed by `suffix`.
+fn prefixed_key(prefix: u64, suffix: &[u8]) -> Vec<u8> {
+    let mut key = prefix.to_be_bytes().to_vec();
+    key.extend_from_slice(suffix);
+    key
+}
+
+impl<E: EthSpec> SlasherDB<E> {
+    /// Opens the database at the path given by `config`, creating it if necessary.
+    pub fn open(config: &Config) -> Result<Self, Error> {
+        fs::create_dir_all(&config.database_path)?;
+        let db = LevelDB::open(&config.database_path)?;
+        Ok(Self { db })
+    }
+
+    /// Stores `indexed_attestation` under its target epoch and `root`.
+    pub fn store_indexed_attestation(
+        &self,
+        root: Hash256,
+        indexed_attestation: &IndexedAttestation<E>,
+    ) -> Result<(), Error> {
+        let key = prefixed_key(
+            indexed_attestation.data.target.epoch.as_u64(),
+            root.as_bytes(),
+        );
+        self.db.put_bytes(
+            DBColumn::SlasherIndexedAttestation.into(),
+            &key,
+            &indexed_attestation.as_ssz_bytes(),
+        )?;
+        Ok(())
+    }
+
+    /// Returns the indexed attestation with `root` and target `target_epoch`.
+    pub fn get_indexed_attestation(
+        &self,
+        target_epoch: Epoch,
+        root: Hash256,
+    ) -> Result<IndexedAttestation<E>, Error> {
+        let key = prefixed_key(target_epoch.as_u64(), root.as_bytes());
+        let bytes = self
+            .db
+            .get_bytes(DBColumn::SlasherIndexedAttestation.into(), &key)?
+            .ok_or_else(|| Error::MissingIndexedAttestation { target_epoch, root })?;
+        Ok(IndexedAttestation::from_ssz_bytes(&bytes)?)
+    }
+
+    /// Returns the vote of `validator_index` for `target_epoch`, if any.
+    pub fn get_attester_record(
+        &self,
+        validator_index: u64,
+        target_epoch: Epoch,
+    ) -> Result<Option<AttesterRecord>, Error> {
+        let key = prefixed_key(target_epoch.as_u64(), &validator_index.to_be_bytes());
+        self.db
+            .get_bytes(DBColumn::SlasherAttesterRecord.into(), &key)?
+            .map(|bytes| AttesterRecord::from_ssz_bytes(&bytes).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Stores the vote of `validator_index` for `target_epoch`.
+    pub fn put_attester_record(
+        &self,
+        validator_index: u64,
+        target_epoch: Epoch,
+        record: &AttesterRecord,
+    ) -> Result<(), Error> {
+        let key = prefixed_key(target_epoch.as_u64(), &validator_index.to_be_bytes());
+        self.db.put_bytes(
+            DBColumn::SlasherAttesterRecord.into(),
+            &key,
+            &record.as_ssz_bytes(),
+        )?;
+        Ok(())
+    }
+
+    /// Returns the span chunk of `validator_index` with `chunk_index` in `column`, if any.
+    pub fn get_span_chunk(
+        &self,
+        column: DBColumn,
+        chunk_index: u64,
+        validator_index: u64,
+    ) -> Result<Option<Vec<u16>>, Error> {
+        let key = prefixed_key(chunk_index, &validator_index.to_be_bytes());
+        self.db
+            .get_bytes(column.into(), &key)?
+            .map(|bytes| Vec::<u16>::from_ssz_bytes(&bytes).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Stores the span chunk of `validator_index` with `chunk_index` in `column`.
+    pub fn put_span_chunk(
+        &self,
+        column: DBColumn,
+        chunk_index: u64,
+        validator_index: u64,
+        chunk: &[u16],
+    ) -> Result<(), Error> {
+        let key = prefixed_key(chunk_index, &validator_index.to_be_bytes());
+        self.db
+            .put_bytes(column.into(), &key, &chunk.to_vec().as_ssz_bytes())?;
+        Ok(())
+    }
+
+    /// Stores `header` unless its proposer already proposed a block at its slot, in which case
+    /// the previously stored header is returned if it differs from `header`.
+    pub fn check_or_insert_block_proposal(
+        &self,
+        header: &SignedBeaconBlockHeader,
+    ) -> Result<Option<SignedBeaconBlockHeader>, Error> {
+        let key = prefixed_key(
+            header.message.slot.as_u64(),
+            &header.message.proposer_index.to_be_bytes(),
+        );
+        match self.db.get_bytes(DBColumn::SlasherProposal.into(), &key)? {
+            Some(bytes) => {
+                let existing = SignedBeaconBlockHeader::from_ssz_bytes(&bytes)?;
+                if existing == *header {
+                    Ok(None)
+                } else {
+                    Ok(Some(existing))
+                }
+            }
+            None => {
+                self.db.put_bytes(
+                    DBColumn::SlasherProposal.into(),
+                    &key,
+                    &header.as_ssz_bytes(),
+                )?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Deletes all records older than `lowest_epoch`.
+    pub fn prune(&self, lowest_epoch: Epoch, config: &Config) -> Result<(), Error> {
+        let lowest_slot = lowest_epoch.start_slot(E::slots_per_epoch());
+        let lowest_chunk_index = lowest_epoch.as_u64() / config.chunk_size;
+
+        self.prune_column(DBColumn::SlasherIndexedAttestation, lowest_epoch.as_u64())?;
+        self.prune_column(DBColumn::SlasherAttesterRecord, lowest_epoch.as_u64())?;
+        self.prune_column(DBColumn::SlasherMinTargets, lowest_chunk_index)?;
+        self.prune_column(DBColumn::SlasherMaxTargets, lowest_chunk_index)?;
+        self.prune_column(DBColumn::SlasherProposal, lowest_slot.as_u64())
+    }
+
+    /// Deletes the keys of `column` whose prefix is less than `lowest_prefix`.
+    fn prune_column(&self, column: DBColumn, lowest_prefix: u64) -> Result<(), Error> {
+        let mut ops = vec![];
+        for item in self.db.iter_column(column) {
+            let (key, _) = item?;
+            let prefix = key
+                .get(0..8)
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(u64::from_be_bytes)
+                .unwrap_or(0);
+            if prefix >= lowest_prefix {
+                break;
+            }
+            ops.push(KeyValueStoreOp::DeleteKey(get_key_for_col(
+                column.into(),
+                &key,
+            )));
+        }
+        self.db.do_atomically(&ops)?;
+        Ok(())
+    }
+}
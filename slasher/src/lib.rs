@@ -0,0 +1,81 @@
+//! Detects slashable offences amongst the attestations and blocks seen by the beacon node.
+//!
+//! The slasher is fed with the indexed attestations and signed block headers that pass gossip
+//! verification, which are queued and periodically processed in a batch by the slasher service
+//! of the beacon node. It detects:
+//!
+//! - Double votes, by recording the attestation data each validator voted for at each target
+//!   epoch.
+//! - Surround votes, by maintaining the min-max span records of each validator. For each epoch
+//!   `e`, the min span is the smallest distance from `e` to the target of an attestation with a
+//!   source after `e`, and the max span is the largest distance from `e` to the target of an
+//!   attestation with a source before `e`. An attestation with source `s` and target `t`
+//!   surrounds a prior attestation if `t - s` exceeds the min span at `s`, and is surrounded by
+//!   a prior attestation if `t - s` is less than the max span at `s`.
+//! - Double proposals, by recording the block header of each proposer at each slot.
+//!
+//! The records are kept in a database of their own for a configurable number of epochs.
+
+#[macro_use]
+extern crate lazy_static;
+
+mod array;
+mod config;
+mod database;
+mod metrics;
+mod slasher;
+
+pub use crate::config::Config;
+pub use crate::database::{AttesterRecord, SlasherDB};
+pub use crate::slasher::Slasher;
+
+use types::{Epoch, Hash256};
+
+#[derive(Debug)]
+pub enum Error {
+    DatabaseError(store::Error),
+    DatabaseIOError(std::io::Error),
+    SszDecodeError(ssz::DecodeError),
+    /// The chunk size must be non-zero and divide the history length.
+    ConfigInvalidChunkSize {
+        chunk_size: u64,
+        history_length: u64,
+    },
+    /// The history length must be non-zero and representable as a span distance.
+    ConfigInvalidHistoryLength {
+        history_length: u64,
+    },
+    /// A span chunk in the database has the wrong number of entries.
+    InvalidSpanChunkLength {
+        expected: usize,
+        found: usize,
+    },
+    /// An attester record refers to an indexed attestation which is not in the database.
+    MissingIndexedAttestation {
+        target_epoch: Epoch,
+        root: Hash256,
+    },
+    /// A span record refers to a vote of a validator which is not in the database.
+    MissingAttesterRecord {
+        validator_index: u64,
+        target_epoch: Epoch,
+    },
+}
+
+impl From<store::Error> for Error {
+    fn from(e: store::Error) -> Self {
+        Error::DatabaseError(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::DatabaseIOError(e)
+    }
+}
+
+impl From<ssz::DecodeError> for Error {
+    fn from(e: ssz::DecodeError) -> Self {
+        Error::SszDecodeError(e)
+    }
+}
@@ -0,0 +1,36 @@
+pub use lighthouse_metrics::*;
+
+lazy_static! {
+    pub static ref SLASHER_PROCESS_BATCH_TIMES: Result<Histogram> = try_create_histogram(
+        "slasher_process_batch_seconds",
+        "Time taken to process a batch of queued attestations and blocks"
+    );
+    pub static ref SLASHER_ATTESTATIONS_PROCESSED: Result<IntCounter> = try_create_int_counter(
+        "slasher_attestations_processed_total",
+        "Number of indexed attestations processed by the slasher"
+    );
+    pub static ref SLASHER_BLOCKS_PROCESSED: Result<IntCounter> = try_create_int_counter(
+        "slasher_blocks_processed_total",
+        "Number of block headers processed by the slasher"
+    );
+    pub static ref SLASHER_ATTESTATIONS_DROPPED: Result<IntCounter> = try_create_int_counter(
+        "slasher_attestations_dropped_total",
+        "Number of indexed attestations dropped because the slasher queue was full"
+    );
+    pub static ref SLASHER_BLOCKS_DROPPED: Result<IntCounter> = try_create_int_counter(
+        "slasher_blocks_dropped_total",
+        "Number of block headers dropped because the slasher queue was full"
+    );
+    pub static ref SLASHER_PROCESSING_ERRORS: Result<IntCounter> = try_create_int_counter(
+        "slasher_processing_errors_total",
+        "Number of queued attestations and blocks which the slasher failed to process"
+    );
+    pub static ref SLASHER_ATTESTER_SLASHINGS: Result<IntCounter> = try_create_int_counter(
+        "slasher_attester_slashings_total",
+        "Number of attester slashings detected by the slasher"
+    );
+    pub static ref SLASHER_PROPOSER_SLASHINGS: Result<IntCounter> = try_create_int_counter(
+        "slasher_proposer_slashings_total",
+        "Number of proposer slashings detected by the slasher"
+    );
+}
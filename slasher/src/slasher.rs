@@ -0,0 +1,595 @@
+use crate::array::{check_and_update, SurroundVote};
+use crate::{metrics, AttesterRecord, Config, Error, SlasherDB};
+use parking_lot::Mutex;
+use slog::{debug, error, info, Logger};
+use std::collections::HashSet;
+use tree_hash::TreeHash;
+use types::{
+    AttesterSlashing, Epoch, EthSpec, Hash256, IndexedAttestation, ProposerSlashing,
+    SignedBeaconBlockHeader,
+};
+
+/// Detects slashable offences amongst the attestations and blocks it is fed with.
+///
+/// Attestations and blocks are queued as they are accepted and only checked against the
+/// database when `process_queued` is called, such that gossip verification is not slowed down.
+pub struct Slasher<E: EthSpec> {
+    db: SlasherDB<E>,
+    attestation_queue: Mutex<Vec<IndexedAttestation<E>>>,
+    block_queue: Mutex<Vec<SignedBeaconBlockHeader>>,
+    /// The slashings detected since they were last taken.
+    attester_slashings: Mutex<Vec<AttesterSlashing<E>>>,
+    proposer_slashings: Mutex<Vec<ProposerSlashing>>,
+    /// All slashings detected within the history, which are retained for reporting.
+    detected_attester_slashings: Mutex<Vec<AttesterSlashing<E>>>,
+    detected_proposer_slashings: Mutex<Vec<ProposerSlashing>>,
+    /// The tree hash roots of the detected slashings, by which duplicates are recognised.
+    detected_roots: Mutex<HashSet<Hash256>>,
+    /// Held whilst processing, such that batches never modify the database concurrently.
+    process_lock: Mutex<()>,
+    config: Config,
+    log: Logger,
+}
+
+impl<E: EthSpec> Slasher<E> {
+    /// Opens the slasher database given by `config`, creating it if necessary.
+    pub fn open(config: Config, log: Logger) -> Result<Self, Error> {
+        config.validate()?;
+        let db = SlasherDB::open(&config)?;
+        Ok(Self {
+            db,
+            attestation_queue: Mutex::new(vec![]),
+            block_queue: Mutex::new(vec![]),
+            attester_slashings: Mutex::new(vec![]),
+            proposer_slashings: Mutex::new(vec![]),
+            detected_attester_slashings: Mutex::new(vec![]),
+            detected_proposer_slashings: Mutex::new(vec![]),
+            detected_roots: Mutex::new(HashSet::new()),
+            process_lock: Mutex::new(()),
+            config,
+            log,
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Queues an attestation with a valid signature for processing, dropping it if the queue is
+    /// full.
+    pub fn accept_attestation(&self, attestation: IndexedAttestation<E>) {
+        let mut queue = self.attestation_queue.lock();
+        if queue.len() < self.config.max_queue_length {
+            queue.push(attestation);
+        } else {
+            metrics::inc_counter(&metrics::SLASHER_ATTESTATIONS_DROPPED);
+        }
+    }
+
+    /// Queues a block header with a valid signature for processing, dropping it if the queue is
+    /// full.
+    pub fn accept_block_header(&self, block_header: SignedBeaconBlockHeader) {
+        let mut queue = self.block_queue.lock();
+        if queue.len() < self.config.max_queue_length {
+            queue.push(block_header);
+        } else {
+            metrics::inc_counter(&metrics::SLASHER_BLOCKS_DROPPED);
+        }
+    }
+
+    /// Returns `true` whilst a batch is being processed.
+    pub fn is_processing(&self) -> bool {
+        self.process_lock.try_lock().is_none()
+    }
+
+    /// Takes the attester slashings detected since the last call.
+    pub fn get_attester_slashings(&self) -> Vec<AttesterSlashing<E>> {
+        std::mem::replace(&mut *self.attester_slashings.lock(), vec![])
+    }
+
+    /// Takes the proposer slashings detected since the last call.
+    pub fn get_proposer_slashings(&self) -> Vec<ProposerSlashing> {
+        std::mem::replace(&mut *self.proposer_slashings.lock(), vec![])
+    }
+
+    /// Returns the attester slashings detected within the history, from the oldest to the most
+    /// recent.
+    pub fn detected_attester_slashings(&self) -> Vec<AttesterSlashing<E>> {
+        self.detected_attester_slashings.lock().clone()
+    }
+
+    /// Returns the proposer slashings detected within the history, from the oldest to the most
+    /// recent.
+    pub fn detected_proposer_slashings(&self) -> Vec<ProposerSlashing> {
+        self.detected_proposer_slashings.lock().clone()
+    }
+
+    /// Checks all queued attestations and blocks for slashable offences, recording them in the
+    /// database, before pruning the records which have fallen out of the history.
+    ///
+    /// A message which fails to be processed is logged and skipped, such that it does not prevent
+    /// the rest of the batch from being processed.
+    pub fn process_queued(&self, current_epoch: Epoch) -> Result<(), Error> {
+        let _process_lock = self.process_lock.lock();
+        let _timer = metrics::start_timer(&metrics::SLASHER_PROCESS_BATCH_TIMES);
+        let lowest_epoch = self.lowest_epoch(current_epoch);
+
+        let blocks = std::mem::replace(&mut *self.block_queue.lock(), vec![]);
+        let num_blocks = blocks.len();
+        for block_header in blocks {
+            let proposer_index = block_header.message.proposer_index;
+            let slot = block_header.message.slot;
+            if let Err(e) = self.process_block_header(block_header, lowest_epoch) {
+                metrics::inc_counter(&metrics::SLASHER_PROCESSING_ERRORS);
+                error!(
+                    self.log,
+                    "Slasher failed to process block";
+                    "proposer_index" => proposer_index,
+                    "slot" => slot,
+                    "error" => format!("{:?}", e),
+                );
+            }
+        }
+        metrics::inc_counter_by(&metrics::SLASHER_BLOCKS_PROCESSED, num_blocks as i64);
+
+        let attestations = std::mem::replace(&mut *self.attestation_queue.lock(), vec![]);
+        let num_attestations = attestations.len();
+        for attestation in attestations {
+            let target_epoch = attestation.data.target.epoch;
+            if let Err(e) = self.process_attestation(attestation, lowest_epoch, current_epoch) {
+                metrics::inc_counter(&metrics::SLASHER_PROCESSING_ERRORS);
+                error!(
+                    self.log,
+                    "Slasher failed to process attestation";
+                    "target_epoch" => target_epoch,
+                    "error" => format!("{:?}", e),
+                );
+            }
+        }
+        metrics::inc_counter_by(
+            &metrics::SLASHER_ATTESTATIONS_PROCESSED,
+            num_attestations as i64,
+        );
+
+        debug!(
+            self.log,
+            "Processed slasher queue";
+            "epoch" => current_epoch,
+            "attestations" => num_attestations,
+            "blocks" => num_blocks,
+        );
+
+        self.prune_detected_slashings(lowest_epoch);
+        self.db.prune(lowest_epoch, &self.config)
+    }
+
+    /// Forgets the detected slashings whose most recent message has fallen out of the history.
+    fn prune_detected_slashings(&self, lowest_epoch: Epoch) {
+        let mut detected_attester_slashings = self.detected_attester_slashings.lock();
+        let mut detected_proposer_slashings = self.detected_proposer_slashings.lock();
+        let mut detected_roots = self.detected_roots.lock();
+
+        detected_attester_slashings.retain(|slashing| {
+            let keep = std::cmp::max(
+                slashing.attestation_1.data.target.epoch,
+                slashing.attestation_2.data.target.epoch,
+            ) >= lowest_epoch;
+            if !keep {
+                detected_roots.remove(&slashing.tree_hash_root());
+            }
+            keep
+        });
+        detected_proposer_slashings.retain(|slashing| {
+            let keep = slashing
+                .signed_header_2
+                .message
+                .slot
+                .epoch(E::slots_per_epoch())
+                >= lowest_epoch;
+            if !keep {
+                detected_roots.remove(&slashing.tree_hash_root());
+            }
+            keep
+        });
+    }
+
+    /// The oldest epoch within the history at `current_epoch`.
+    fn lowest_epoch(&self, current_epoch: Epoch) -> Epoch {
+        Epoch::new(
+            current_epoch
+                .as_u64()
+                .saturating_sub(self.config.history_length - 1),
+        )
+    }
+
+    fn process_block_header(
+        &self,
+        block_header: SignedBeaconBlockHeader,
+        lowest_epoch: Epoch,
+    ) -> Result<(), Error> {
+        if block_header.message.slot.epoch(E::slots_per_epoch()) < lowest_epoch {
+            return Ok(());
+        }
+
+        if let Some(existing) = self.db.check_or_insert_block_proposal(&block_header)? {
+            info!(
+                self.log,
+                "Detected double proposal";
+                "proposer_index" => block_header.message.proposer_index,
+                "slot" => block_header.message.slot,
+            );
+            let slashing = ProposerSlashing {
+                signed_header_1: existing,
+                signed_header_2: block_header,
+            };
+            if self.detected_roots.lock().insert(slashing.tree_hash_root()) {
+                metrics::inc_counter(&metrics::SLASHER_PROPOSER_SLASHINGS);
+                self.detected_proposer_slashings
+                    .lock()
+                    .push(slashing.clone());
+                self.proposer_slashings.lock().push(slashing);
+            }
+        }
+        Ok(())
+    }
+
+    fn process_attestation(
+        &self,
+        attestation: IndexedAttestation<E>,
+        lowest_epoch: Epoch,
+        current_epoch: Epoch,
+    ) -> Result<(), Error> {
+        let source = attestation.data.source.epoch;
+        let target = attestation.data.target.epoch;
+        if source > target || target < lowest_epoch {
+            return Ok(());
+        }
+
+        let record = AttesterRecord {
+            attestation_data_root: attestation.data.tree_hash_root(),
+            indexed_attestation_root: attestation.tree_hash_root(),
+        };
+        let mut stored = false;
+
+        for &validator_index in attestation.attesting_indices.iter() {
+            match self.db.get_attester_record(validator_index, target)? {
+                // The vote has already been processed, perhaps as part of another aggregate.
+                Some(existing)
+                    if existing.attestation_data_root == record.attestation_data_root =>
+                {
+                    continue
+                }
+                Some(existing) => {
+                    let existing_attestation = self
+                        .db
+                        .get_indexed_attestation(target, existing.indexed_attestation_root)?;
+                    info!(
+                        self.log,
+                        "Detected double vote";
+                        "validator_index" => validator_index,
+                        "target_epoch" => target,
+                    );
+                    self.record_attester_slashing(existing_attestation, attestation.clone());
+                    continue;
+                }
+                None => {}
+            }
+
+            if !stored {
+                self.db
+                    .store_indexed_attestation(record.indexed_attestation_root, &attestation)?;
+                stored = true;
+            }
+            self.db
+                .put_attester_record(validator_index, target, &record)?;
+
+            let surround_vote = check_and_update(
+                &self.db,
+                &self.config,
+                validator_index,
+                source,
+                target,
+                lowest_epoch,
+                current_epoch,
+            )?;
+            if let Some(surround_vote) = surround_vote {
+                let existing_target = match surround_vote {
+                    SurroundVote::Surrounds(epoch) | SurroundVote::SurroundedBy(epoch) => epoch,
+                };
+                let existing = self
+                    .db
+                    .get_attester_record(validator_index, existing_target)?
+                    .ok_or_else(|| Error::MissingAttesterRecord {
+                        validator_index,
+                        target_epoch: existing_target,
+                    })?;
+                let existing_attestation = self
+                    .db
+                    .get_indexed_attestation(existing_target, existing.indexed_attestation_root)?;
+                info!(
+                    self.log,
+                    "Detected surround vote";
+                    "validator_index" => validator_index,
+                    "source_epoch" => source,
+                    "target_epoch" => target,
+                    "prior_target_epoch" => existing_target,
+                );
+                match surround_vote {
+                    SurroundVote::Surrounds(_) => {
+                        self.record_attester_slashing(attestation.clone(), existing_attestation)
+                    }
+                    SurroundVote::SurroundedBy(_) => {
+                        self.record_attester_slashing(existing_attestation, attestation.clone())
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a slashing of `attestation_1` and `attestation_2`, unless it has already been
+    /// detected. A single slashing covers all validators which signed both attestations.
+    fn record_attester_slashing(
+        &self,
+        attestation_1: IndexedAttestation<E>,
+        attestation_2: IndexedAttestation<E>,
+    ) {
+        let slashing = AttesterSlashing {
+            attestation_1,
+            attestation_2,
+        };
+        if self.detected_roots.lock().insert(slashing.tree_hash_root()) {
+            metrics::inc_counter(&metrics::SLASHER_ATTESTER_SLASHINGS);
+            self.detected_attester_slashings
+                .lock()
+                .push(slashing.clone());
+            self.attester_slashings.lock().push(slashing);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sloggers::{null::NullLoggerBuilder, Build};
+    use tempfile::{tempdir, TempDir};
+    use types::{
+        AggregateSignature, AttestationData, BeaconBlockHeader, Checkpoint, Hash256,
+        MainnetEthSpec, Signature, Slot, VariableList,
+    };
+
+    type E = MainnetEthSpec;
+
+    fn slasher() -> (Slasher<E>, TempDir) {
+        let dir = tempdir().unwrap();
+        let config = Config::new(dir.path().into());
+        let log = NullLoggerBuilder.build().unwrap();
+        (Slasher::open(config, log).unwrap(), dir)
+    }
+
+    fn attestation(
+        indices: &[u64],
+        source: u64,
+        target: u64,
+        block_root: u64,
+    ) -> IndexedAttestation<E> {
+        IndexedAttestation {
+            attesting_indices: VariableList::new(indices.to_vec()).unwrap(),
+            data: AttestationData {
+                slot: Epoch::new(target).start_slot(E::slots_per_epoch()),
+                index: 0,
+                beacon_block_root: Hash256::from_low_u64_be(block_root),
+                source: Checkpoint {
+                    epoch: Epoch::new(source),
+                    root: Hash256::from_low_u64_be(source),
+                },
+                target: Checkpoint {
+                    epoch: Epoch::new(target),
+                    root: Hash256::from_low_u64_be(target),
+                },
+            },
+            signature: AggregateSignature::new(),
+        }
+    }
+
+    fn block_header(proposer_index: u64, slot: u64, state_root: u64) -> SignedBeaconBlockHeader {
+        SignedBeaconBlockHeader {
+            message: BeaconBlockHeader {
+                slot: Slot::new(slot),
+                proposer_index,
+                parent_root: Hash256::zero(),
+                state_root: Hash256::from_low_u64_be(state_root),
+                body_root: Hash256::zero(),
+            },
+            signature: Signature::empty_signature(),
+        }
+    }
+
+    fn process(slasher: &Slasher<E>, attestations: Vec<IndexedAttestation<E>>, epoch: u64) {
+        for attestation in attestations {
+            slasher.accept_attestation(attestation);
+        }
+        slasher.process_queued(Epoch::new(epoch)).unwrap();
+    }
+
+    #[test]
+    fn test_no_slashing_for_consistent_votes() {
+        let (slasher, _dir) = slasher();
+        let first = attestation(&[0, 1], 0, 1, 1);
+        process(
+            &slasher,
+            vec![first.clone(), first, attestation(&[0, 1], 1, 2, 2)],
+            2,
+        );
+        process(&slasher, vec![attestation(&[0], 2, 3, 3)], 3);
+
+        assert!(slasher.get_attester_slashings().is_empty());
+    }
+
+    #[test]
+    fn test_double_vote() {
+        let (slasher, _dir) = slasher();
+        let first = attestation(&[0, 1, 2], 0, 1, 1);
+        let second = attestation(&[1, 2], 0, 1, 2);
+        process(&slasher, vec![first.clone(), second.clone()], 1);
+
+        assert_eq!(
+            slasher.get_attester_slashings(),
+            vec![AttesterSlashing {
+                attestation_1: first,
+                attestation_2: second,
+            }]
+        );
+        assert!(slasher.get_attester_slashings().is_empty());
+    }
+
+    #[test]
+    fn test_surrounding_vote() {
+        let (slasher, _dir) = slasher();
+        let surrounded = attestation(&[3], 2, 3, 1);
+        let surrounding = attestation(&[3], 1, 4, 2);
+        process(&slasher, vec![surrounded.clone()], 3);
+        process(&slasher, vec![surrounding.clone()], 4);
+
+        assert_eq!(
+            slasher.get_attester_slashings(),
+            vec![AttesterSlashing {
+                attestation_1: surrounding,
+                attestation_2: surrounded,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_surrounded_vote() {
+        let (slasher, _dir) = slasher();
+        let surrounding = attestation(&[3], 1, 5, 1);
+        let surrounded = attestation(&[3], 2, 4, 2);
+        process(&slasher, vec![surrounding.clone()], 5);
+        process(&slasher, vec![surrounded.clone()], 5);
+
+        assert_eq!(
+            slasher.get_attester_slashings(),
+            vec![AttesterSlashing {
+                attestation_1: surrounding,
+                attestation_2: surrounded,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_double_proposal() {
+        let (slasher, _dir) = slasher();
+        let first = block_header(7, 10, 1);
+        let second = block_header(7, 10, 2);
+        for header in vec![
+            first.clone(),
+            first.clone(),
+            block_header(8, 10, 3),
+            second.clone(),
+        ] {
+            slasher.accept_block_header(header);
+        }
+        slasher.process_queued(Epoch::new(1)).unwrap();
+
+        assert_eq!(
+            slasher.get_proposer_slashings(),
+            vec![ProposerSlashing {
+                signed_header_1: first,
+                signed_header_2: second,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_detected_slashings_are_retained_within_history() {
+        let (slasher, _dir) = slasher();
+        let history_length = slasher.config().history_length;
+        let first = attestation(&[0], 0, 1, 1);
+        let second = attestation(&[0], 0, 1, 2);
+        process(&slasher, vec![first.clone(), second.clone()], 1);
+        assert_eq!(slasher.get_attester_slashings().len(), 1);
+
+        // The slashing is not detected again once it has been taken.
+        process(&slasher, vec![second.clone()], 2);
+        assert!(slasher.get_attester_slashings().is_empty());
+        assert_eq!(
+            slasher.detected_attester_slashings(),
+            vec![AttesterSlashing {
+                attestation_1: first,
+                attestation_2: second,
+            }]
+        );
+
+        process(&slasher, vec![], history_length + 1);
+        assert!(slasher.detected_attester_slashings().is_empty());
+    }
+
+    #[test]
+    fn test_records_outside_history_are_pruned() {
+        let (slasher, _dir) = slasher();
+        let history_length = slasher.config().history_length;
+        process(&slasher, vec![attestation(&[0], 0, 1, 1)], 1);
+        process(&slasher, vec![], history_length + 1);
+
+        assert!(slasher
+            .db
+            .get_attester_record(0, Epoch::new(1))
+            .unwrap()
+            .is_none());
+    }
+    #[test]
+    fn test_full_queue_drops_messages() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::new(dir.path().into());
+        config.max_queue_length = 1;
+        let slasher = Slasher::<E>::open(config, NullLoggerBuilder.build().unwrap()).unwrap();
+        let first = attestation(&[0], 0, 1, 1);
+        let second = attestation(&[0], 0, 1, 2);
+        process(&slasher, vec![first.clone(), second.clone()], 1);
+        assert!(slasher.get_attester_slashings().is_empty());
+
+        // The queue has room again once it has been processed.
+        process(&slasher, vec![second.clone()], 1);
+        assert_eq!(
+            slasher.get_attester_slashings(),
+            vec![AttesterSlashing {
+                attestation_1: first,
+                attestation_2: second,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_processing_error_does_not_drop_batch() {
+        let (slasher, _dir) = slasher();
+        // A record of a vote whose attestation is missing from the database, such that a
+        // conflicting vote fails to be processed.
+        slasher
+            .db
+            .put_attester_record(
+                0,
+                Epoch::new(1),
+                &AttesterRecord {
+                    attestation_data_root: Hash256::repeat_byte(1),
+                    indexed_attestation_root: Hash256::repeat_byte(2),
+                },
+            )
+            .unwrap();
+        let first = attestation(&[1], 0, 1, 1);
+        let second = attestation(&[1], 0, 1, 2);
+        process(
+            &slasher,
+            vec![attestation(&[0], 0, 1, 1), first.clone(), second.clone()],
+            1,
+        );
+
+        assert_eq!(
+            slasher.get_attester_slashings(),
+            vec![AttesterSlashing {
+                attestation_1: first,
+                attestation_2: second,
+            }]
+        );
+    }
+}
@@ -122,3 +122,18 @@ fn invalid_unregistered_validator() {
     }
     .run()
 }
+
+#[test]
+fn invalid_slot_below_lower_bound() {
+    StreamTest {
+        cases: vec![
+            Test::single(block(10)),
+            Test::single(block(9)).expect_invalid_block(InvalidBlock::SlotViolatesLowerBound {
+                block_slot: Slot::new(9),
+                bound_slot: Slot::new(10),
+            }),
+        ],
+        ..StreamTest::default()
+    }
+    .run()
+}
@@ -0,0 +1,140 @@
+//! Types for the EIP-3076 slashing protection interchange format.
+//!
+//! Both the "complete" format, which lists every signed block and attestation for each validator,
+//! and the "minimal" format, which only lists the most recent ones, can be imported. Imports are
+//! always treated as minimal, see `SlashingDatabase::import_interchange_info`.
+
+use crate::NotSafe;
+use serde_derive::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use types::{Epoch, Hash256, PublicKey, Slot};
+
+/// The version of the interchange format produced and accepted by this crate.
+pub const SUPPORTED_INTERCHANGE_FORMAT_VERSION: u64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InterchangeMetadata {
+    #[serde(with = "quoted_u64")]
+    pub interchange_format_version: u64,
+    pub genesis_validators_root: Hash256,
+}
+
+/// A block that has been signed, without the block itself.
+///
+/// The `signing_root` is optional in the interchange format. Blocks without a `signing_root` are
+/// never considered to be the same as a block that is about to be signed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InterchangeBlock {
+    #[serde(with = "quoted_u64")]
+    pub slot: Slot,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub signing_root: Option<Hash256>,
+}
+
+/// An attestation that has been signed, without the attestation itself.
+///
+/// The `signing_root` is optional, as for `InterchangeBlock`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InterchangeAttestation {
+    #[serde(with = "quoted_u64")]
+    pub source_epoch: Epoch,
+    #[serde(with = "quoted_u64")]
+    pub target_epoch: Epoch,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub signing_root: Option<Hash256>,
+}
+
+/// The signing history of a single validator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InterchangeData {
+    pub pubkey: PublicKey,
+    pub signed_blocks: Vec<InterchangeBlock>,
+    pub signed_attestations: Vec<InterchangeAttestation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Interchange {
+    pub metadata: InterchangeMetadata,
+    pub data: Vec<InterchangeData>,
+}
+
+/// Reasons why an interchange file could not be imported or exported.
+#[derive(Debug)]
+pub enum InterchangeError {
+    /// The interchange file uses a version of the format which is not supported.
+    UnsupportedVersion(u64),
+    /// The interchange file is for a different network to the one the database is used for.
+    GenesisValidatorsMismatch {
+        interchange_file: Hash256,
+        client: Hash256,
+    },
+    /// The public key of a validator in the database is invalid.
+    InvalidPubkey(String),
+    /// Unable to read or write the interchange file.
+    JsonError(String),
+    /// An error occurred whilst reading from or writing to the database.
+    NotSafe(NotSafe),
+}
+
+impl Interchange {
+    pub fn from_json_str(json: &str) -> Result<Self, InterchangeError> {
+        serde_json::from_str(json).map_err(|e| InterchangeError::JsonError(e.to_string()))
+    }
+
+    pub fn from_json_reader(reader: impl Read) -> Result<Self, InterchangeError> {
+        serde_json::from_reader(reader).map_err(|e| InterchangeError::JsonError(e.to_string()))
+    }
+
+    pub fn write_to(&self, writer: impl Write) -> Result<(), InterchangeError> {
+        serde_json::to_writer(writer, self).map_err(|e| InterchangeError::JsonError(e.to_string()))
+    }
+}
+
+impl From<NotSafe> for InterchangeError {
+    fn from(error: NotSafe) -> Self {
+        InterchangeError::NotSafe(error)
+    }
+}
+
+impl From<rusqlite::Error> for InterchangeError {
+    fn from(error: rusqlite::Error) -> Self {
+        NotSafe::from(error).into()
+    }
+}
+
+impl From<r2d2::Error> for InterchangeError {
+    fn from(error: r2d2::Error) -> Self {
+        NotSafe::from(error).into()
+    }
+}
+
+/// Serialize integers as decimal strings, as required by the interchange format.
+mod quoted_u64 {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Copy + Into<u64>,
+    {
+        let value: u64 = (*value).into();
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: From<u64>,
+    {
+        let string = String::deserialize(deserializer)?;
+        string
+            .parse::<u64>()
+            .map(T::from)
+            .map_err(|e| D::Error::custom(format!("invalid quoted integer {}: {:?}", string, e)))
+    }
+}
@@ -0,0 +1,386 @@
+#![cfg(test)]
+
+use crate::attestation_tests::attestation_data_builder;
+use crate::block_tests::block;
+use crate::test_utils::*;
+use crate::*;
+use tempfile::{tempdir, TempDir};
+use types::{Epoch, Hash256, Slot};
+
+const GENESIS_VALIDATORS_ROOT: Hash256 = Hash256::zero();
+
+fn open_db() -> (TempDir, SlashingDatabase) {
+    let dir = tempdir().unwrap();
+    let slashing_db_file = dir.path().join("slashing_protection.sqlite");
+    let slashing_db = SlashingDatabase::create(&slashing_db_file).unwrap();
+    (dir, slashing_db)
+}
+
+fn interchange(data: Vec<InterchangeData>) -> Interchange {
+    Interchange {
+        metadata: InterchangeMetadata {
+            interchange_format_version: SUPPORTED_INTERCHANGE_FORMAT_VERSION,
+            genesis_validators_root: GENESIS_VALIDATORS_ROOT,
+        },
+        data,
+    }
+}
+
+#[test]
+fn parse_interchange_json() {
+    let json = format!(
+        r#"{{
+            "metadata": {{
+                "interchange_format_version": "5",
+                "genesis_validators_root": "0x04700007fabc8282644aed6d1c7c9e21d38a03a0c4ba193f3afe428824b3a673"
+            }},
+            "data": [
+                {{
+                    "pubkey": "{}",
+                    "signed_blocks": [
+                        {{
+                            "slot": "81952",
+                            "signing_root": "0x4ff6f743a43f3b4f95350831aeaf0a122a1a392922c45d804280284a69eb850b"
+                        }},
+                        {{
+                            "slot": "81951"
+                        }}
+                    ],
+                    "signed_attestations": [
+                        {{
+                            "source_epoch": "2290",
+                            "target_epoch": "3007",
+                            "signing_root": "0x587d6a4f59a58fe24f406e0502413e77fe1babddee641fda30034ed37ecc884d"
+                        }},
+                        {{
+                            "source_epoch": "2290",
+                            "target_epoch": "3008"
+                        }}
+                    ]
+                }}
+            ]
+        }}"#,
+        pubkey(0).as_hex_string()
+    );
+
+    let interchange = Interchange::from_json_str(&json).unwrap();
+
+    assert_eq!(interchange.metadata.interchange_format_version, 5);
+    assert_eq!(interchange.data.len(), 1);
+    assert_eq!(interchange.data[0].pubkey, pubkey(0));
+    assert_eq!(interchange.data[0].signed_blocks[0].slot, Slot::new(81952));
+    assert!(interchange.data[0].signed_blocks[0].signing_root.is_some());
+    assert!(interchange.data[0].signed_blocks[1].signing_root.is_none());
+    assert_eq!(
+        interchange.data[0].signed_attestations[1].target_epoch,
+        Epoch::new(3008)
+    );
+    assert!(interchange.data[0].signed_attestations[1]
+        .signing_root
+        .is_none());
+
+    // Integers must be quoted.
+    let unquoted = json.replace(r#""81952""#, "81952");
+    Interchange::from_json_str(&unquoted).unwrap_err();
+}
+
+#[test]
+fn export_import_round_trip() {
+    let (_dir1, db1) = open_db();
+    db1.register_validators(vec![pubkey(0), pubkey(1)].iter())
+        .unwrap();
+
+    for slot in &[1, 2, 5] {
+        db1.check_and_insert_block_proposal(&pubkey(0), &block(*slot), DEFAULT_DOMAIN)
+            .unwrap();
+    }
+    for (source, target) in &[(0, 1), (1, 2), (2, 4)] {
+        db1.check_and_insert_attestation(
+            &pubkey(1),
+            &attestation_data_builder(*source, *target),
+            DEFAULT_DOMAIN,
+        )
+        .unwrap();
+    }
+
+    let exported = db1
+        .export_interchange_info(GENESIS_VALIDATORS_ROOT)
+        .unwrap();
+
+    assert_eq!(exported.data.len(), 2);
+    assert_eq!(exported.data[0].signed_blocks.len(), 3);
+    assert_eq!(exported.data[1].signed_attestations.len(), 3);
+
+    // Write to JSON and back.
+    let mut json = vec![];
+    exported.write_to(&mut json).unwrap();
+    let parsed = Interchange::from_json_reader(json.as_slice()).unwrap();
+    assert_eq!(parsed, exported);
+
+    // Import into a fresh database, without registering any validators. Only the most recent
+    // block and attestation of each validator are kept.
+    let (_dir2, db2) = open_db();
+    db2.import_interchange_info(&parsed, GENESIS_VALIDATORS_ROOT)
+        .unwrap();
+
+    let mut minimal = exported.clone();
+    minimal.data[0].signed_blocks.drain(..2);
+    minimal.data[1].signed_attestations.drain(..2);
+    assert_eq!(
+        db2.export_interchange_info(GENESIS_VALIDATORS_ROOT)
+            .unwrap(),
+        minimal
+    );
+
+    // Importing the minimal history again changes nothing.
+    db2.import_interchange_info(&minimal, GENESIS_VALIDATORS_ROOT)
+        .unwrap();
+    assert_eq!(
+        db2.export_interchange_info(GENESIS_VALIDATORS_ROOT)
+            .unwrap(),
+        minimal
+    );
+}
+
+#[test]
+fn import_minimal_history_sets_low_watermarks() {
+    let (_dir, db) = open_db();
+
+    // Some older history signed by this database.
+    db.register_validator(&pubkey(0)).unwrap();
+    db.check_and_insert_block_proposal(&pubkey(0), &block(10), DEFAULT_DOMAIN)
+        .unwrap();
+    db.check_and_insert_attestation(&pubkey(0), &attestation_data_builder(1, 2), DEFAULT_DOMAIN)
+        .unwrap();
+
+    // Minimal history from another client, which has since signed many more messages.
+    db.import_interchange_info(
+        &interchange(vec![InterchangeData {
+            pubkey: pubkey(0),
+            signed_blocks: vec![InterchangeBlock {
+                slot: Slot::new(100),
+                signing_root: None,
+            }],
+            signed_attestations: vec![InterchangeAttestation {
+                source_epoch: Epoch::new(40),
+                target_epoch: Epoch::new(50),
+                signing_root: None,
+            }],
+        }]),
+        GENESIS_VALIDATORS_ROOT,
+    )
+    .unwrap();
+
+    // Blocks at or below the imported slot may have been signed by the other client.
+    assert_eq!(
+        db.check_and_insert_block_proposal(&pubkey(0), &block(50), DEFAULT_DOMAIN),
+        Err(NotSafe::InvalidBlock(
+            InvalidBlock::SlotViolatesLowerBound {
+                block_slot: Slot::new(50),
+                bound_slot: Slot::new(100),
+            }
+        ))
+    );
+    assert!(matches!(
+        db.check_and_insert_block_proposal(&pubkey(0), &block(100), DEFAULT_DOMAIN),
+        Err(NotSafe::InvalidBlock(InvalidBlock::DoubleBlockProposal(_)))
+    ));
+
+    // A vote surrounding the imported attestation.
+    assert!(matches!(
+        db.check_and_insert_attestation(
+            &pubkey(0),
+            &attestation_data_builder(30, 60),
+            DEFAULT_DOMAIN
+        ),
+        Err(NotSafe::InvalidAttestation(
+            InvalidAttestation::NewSurroundsPrev { .. }
+        ))
+    ));
+    // Votes which don't conflict with the imported attestation, but are below the watermarks.
+    assert_eq!(
+        db.check_and_insert_attestation(
+            &pubkey(0),
+            &attestation_data_builder(20, 30),
+            DEFAULT_DOMAIN
+        ),
+        Err(NotSafe::InvalidAttestation(
+            InvalidAttestation::SourceLessThanLowerBound {
+                source_epoch: Epoch::new(20),
+                bound_epoch: Epoch::new(40),
+            }
+        ))
+    );
+    assert_eq!(
+        db.check_and_insert_attestation(
+            &pubkey(0),
+            &attestation_data_builder(40, 45),
+            DEFAULT_DOMAIN
+        ),
+        Err(NotSafe::InvalidAttestation(
+            InvalidAttestation::TargetLessThanOrEqLowerBound {
+                target_epoch: Epoch::new(45),
+                bound_epoch: Epoch::new(50),
+            }
+        ))
+    );
+
+    // Signing above the watermarks is safe.
+    assert_eq!(
+        db.check_and_insert_block_proposal(&pubkey(0), &block(101), DEFAULT_DOMAIN),
+        Ok(Safe::Valid)
+    );
+    assert_eq!(
+        db.check_and_insert_attestation(
+            &pubkey(0),
+            &attestation_data_builder(50, 51),
+            DEFAULT_DOMAIN
+        ),
+        Ok(Safe::Valid)
+    );
+}
+
+#[test]
+fn import_synthesizes_attestation_watermark() {
+    let (_dir, db) = open_db();
+
+    // The highest source and the highest target belong to different attestations.
+    db.import_interchange_info(
+        &interchange(vec![InterchangeData {
+            pubkey: pubkey(0),
+            signed_blocks: vec![],
+            signed_attestations: vec![
+                InterchangeAttestation {
+                    source_epoch: Epoch::new(5),
+                    target_epoch: Epoch::new(6),
+                    signing_root: Some(Hash256::repeat_byte(1)),
+                },
+                InterchangeAttestation {
+                    source_epoch: Epoch::new(2),
+                    target_epoch: Epoch::new(8),
+                    signing_root: Some(Hash256::repeat_byte(2)),
+                },
+            ],
+        }]),
+        GENESIS_VALIDATORS_ROOT,
+    )
+    .unwrap();
+
+    let exported = db.export_interchange_info(GENESIS_VALIDATORS_ROOT).unwrap();
+    assert_eq!(
+        exported.data[0].signed_attestations,
+        vec![InterchangeAttestation {
+            source_epoch: Epoch::new(5),
+            target_epoch: Epoch::new(8),
+            signing_root: None,
+        }]
+    );
+
+    // (4, 9) would surround (5, 6), which is no longer in the database.
+    assert!(matches!(
+        db.check_and_insert_attestation(
+            &pubkey(0),
+            &attestation_data_builder(4, 9),
+            DEFAULT_DOMAIN
+        ),
+        Err(NotSafe::InvalidAttestation(
+            InvalidAttestation::NewSurroundsPrev { .. }
+        ))
+    ));
+}
+
+#[test]
+fn imported_history_prevents_slashing() {
+    let (_dir, db) = open_db();
+
+    db.import_interchange_info(
+        &interchange(vec![InterchangeData {
+            pubkey: pubkey(0),
+            signed_blocks: vec![InterchangeBlock {
+                slot: Slot::new(10),
+                signing_root: None,
+            }],
+            signed_attestations: vec![InterchangeAttestation {
+                source_epoch: Epoch::new(2),
+                target_epoch: Epoch::new(5),
+                signing_root: None,
+            }],
+        }]),
+        GENESIS_VALIDATORS_ROOT,
+    )
+    .unwrap();
+
+    // Without a signing root, even the same block is considered a double proposal.
+    assert!(matches!(
+        db.check_and_insert_block_proposal(&pubkey(0), &block(10), DEFAULT_DOMAIN),
+        Err(NotSafe::InvalidBlock(InvalidBlock::DoubleBlockProposal(_)))
+    ));
+    assert_eq!(
+        db.check_and_insert_block_proposal(&pubkey(0), &block(11), DEFAULT_DOMAIN),
+        Ok(Safe::Valid)
+    );
+
+    assert!(matches!(
+        db.check_and_insert_attestation(
+            &pubkey(0),
+            &attestation_data_builder(3, 4),
+            DEFAULT_DOMAIN
+        ),
+        Err(NotSafe::InvalidAttestation(
+            InvalidAttestation::PrevSurroundsNew { .. }
+        ))
+    ));
+    assert_eq!(
+        db.check_and_insert_attestation(
+            &pubkey(0),
+            &attestation_data_builder(5, 6),
+            DEFAULT_DOMAIN
+        ),
+        Ok(Safe::Valid)
+    );
+}
+
+#[test]
+fn import_keeps_existing_records() {
+    let (_dir, db) = open_db();
+    db.register_validator(&pubkey(0)).unwrap();
+    db.check_and_insert_block_proposal(&pubkey(0), &block(10), DEFAULT_DOMAIN)
+        .unwrap();
+    let existing = db.export_interchange_info(GENESIS_VALIDATORS_ROOT).unwrap();
+
+    // A conflicting block at the same slot is ignored.
+    db.import_interchange_info(
+        &interchange(vec![InterchangeData {
+            pubkey: pubkey(0),
+            signed_blocks: vec![InterchangeBlock {
+                slot: Slot::new(10),
+                signing_root: Some(Hash256::repeat_byte(0xff)),
+            }],
+            signed_attestations: vec![],
+        }]),
+        GENESIS_VALIDATORS_ROOT,
+    )
+    .unwrap();
+
+    assert_eq!(
+        db.export_interchange_info(GENESIS_VALIDATORS_ROOT).unwrap(),
+        existing
+    );
+}
+
+#[test]
+fn import_invalid_metadata() {
+    let (_dir, db) = open_db();
+
+    let mut wrong_version = interchange(vec![]);
+    wrong_version.metadata.interchange_format_version = 4;
+    assert!(matches!(
+        db.import_interchange_info(&wrong_version, GENESIS_VALIDATORS_ROOT),
+        Err(InterchangeError::UnsupportedVersion(4))
+    ));
+
+    assert!(matches!(
+        db.import_interchange_info(&interchange(vec![]), Hash256::repeat_byte(0xff)),
+        Err(InterchangeError::GenesisValidatorsMismatch { .. })
+    ));
+}
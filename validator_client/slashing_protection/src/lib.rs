@@ -1,11 +1,17 @@
 mod attestation_tests;
 mod block_tests;
+mod interchange;
+mod interchange_tests;
 mod parallel_tests;
 mod signed_attestation;
 mod signed_block;
 mod slashing_database;
 mod test_utils;
 
+pub use crate::interchange::{
+    Interchange, InterchangeAttestation, InterchangeBlock, InterchangeData, InterchangeError,
+    InterchangeMetadata, SUPPORTED_INTERCHANGE_FORMAT_VERSION,
+};
 pub use crate::signed_attestation::{InvalidAttestation, SignedAttestation};
 pub use crate::signed_block::{InvalidBlock, SignedBlock};
 pub use crate::slashing_database::SlashingDatabase;
@@ -14,6 +20,9 @@ use std::io::{Error as IOError, ErrorKind};
 use std::string::ToString;
 use types::{Hash256, PublicKey};
 
+/// The filename within the validator client data directory of the slashing protection database.
+pub const SLASHING_PROTECTION_FILENAME: &str = "slashing_protection.sqlite";
+
 /// The attestation or block is not safe to sign.
 ///
 /// This could be because it's slashable, or because an error occurred.
@@ -12,6 +12,12 @@ pub struct SignedBlock {
 #[derive(PartialEq, Debug)]
 pub enum InvalidBlock {
     DoubleBlockProposal(SignedBlock),
+    /// The block is at or below the lowest slot in the database, below which the history of the
+    /// validator may be incomplete.
+    SlotViolatesLowerBound {
+        block_slot: Slot,
+        bound_slot: Slot,
+    },
 }
 
 impl SignedBlock {
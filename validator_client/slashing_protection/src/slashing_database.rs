@@ -1,3 +1,7 @@
+use crate::interchange::{
+    Interchange, InterchangeAttestation, InterchangeBlock, InterchangeData, InterchangeError,
+    InterchangeMetadata, SUPPORTED_INTERCHANGE_FORMAT_VERSION,
+};
 use crate::signed_attestation::InvalidAttestation;
 use crate::signed_block::InvalidBlock;
 use crate::{NotSafe, Safe, SignedAttestation, SignedBlock};
@@ -6,7 +10,7 @@ use rusqlite::{params, OptionalExtension, Transaction, TransactionBehavior};
 use std::fs::{File, OpenOptions};
 use std::path::Path;
 use std::time::Duration;
-use types::{AttestationData, BeaconBlockHeader, Hash256, PublicKey, SignedRoot};
+use types::{AttestationData, BeaconBlockHeader, Epoch, Hash256, PublicKey, SignedRoot, Slot};
 
 type Pool = r2d2::Pool<SqliteConnectionManager>;
 
@@ -170,6 +174,23 @@ impl SlashingDatabase {
         .ok_or_else(|| NotSafe::UnregisteredValidator(public_key.clone()))
     }
 
+    /// Get the database-internal ID for a validator, registering the validator if it is unknown.
+    fn get_or_insert_validator_id(
+        txn: &Transaction,
+        public_key: &PublicKey,
+    ) -> Result<i64, NotSafe> {
+        match Self::get_validator_id(txn, public_key) {
+            Err(NotSafe::UnregisteredValidator(_)) => {
+                txn.execute(
+                    "INSERT INTO validators (public_key) VALUES (?1)",
+                    params![&public_key.as_hex_string()],
+                )?;
+                Ok(txn.last_insert_rowid())
+            }
+            result => result,
+        }
+    }
+
     /// Check a block proposal from `validator_pubkey` for slash safety.
     fn check_block_proposal(
         &self,
@@ -195,16 +216,33 @@ impl SlashingDatabase {
         if let Some(existing_block) = existing_block {
             if existing_block.signing_root == block_header.signing_root(domain) {
                 // Same slot and same hash -> we're re-broadcasting a previously signed block
-                Ok(Safe::SameData)
+                return Ok(Safe::SameData);
             } else {
                 // Same epoch but not the same hash -> it's a DoubleBlockProposal
-                Err(NotSafe::InvalidBlock(InvalidBlock::DoubleBlockProposal(
+                return Err(NotSafe::InvalidBlock(InvalidBlock::DoubleBlockProposal(
                     existing_block,
-                )))
+                )));
+            }
+        }
+
+        // The lowest slot in the database is a low watermark, below which the history of the
+        // validator may be incomplete (e.g. after importing a minimal interchange file).
+        let min_slot: Option<Slot> = txn
+            .prepare("SELECT MIN(slot) FROM signed_blocks WHERE validator_id = ?1")?
+            .query_row(params![validator_id], |row| row.get(0))?;
+
+        if let Some(bound_slot) = min_slot {
+            if block_header.slot <= bound_slot {
+                return Err(NotSafe::InvalidBlock(
+                    InvalidBlock::SlotViolatesLowerBound {
+                        block_slot: block_header.slot,
+                        bound_slot,
+                    },
+                ));
             }
-        } else {
-            Ok(Safe::Valid)
         }
+
+        Ok(Safe::Valid)
     }
 
     /// Check an attestation from `validator_pubkey` for slash safety.
@@ -299,6 +337,38 @@ impl SlashingDatabase {
             ));
         }
 
+        // 4. Check that the attestation is above the low watermarks of the database, below which
+        //    the history of the validator may be incomplete.
+        let (min_source, min_target): (Option<Epoch>, Option<Epoch>) = txn
+            .prepare(
+                "SELECT MIN(source_epoch), MIN(target_epoch)
+                 FROM signed_attestations
+                 WHERE validator_id = ?1",
+            )?
+            .query_row(params![validator_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        if let Some(bound_epoch) = min_source {
+            if att_source_epoch < bound_epoch {
+                return Err(NotSafe::InvalidAttestation(
+                    InvalidAttestation::SourceLessThanLowerBound {
+                        source_epoch: att_source_epoch,
+                        bound_epoch,
+                    },
+                ));
+            }
+        }
+
+        if let Some(bound_epoch) = min_target {
+            if att_target_epoch <= bound_epoch {
+                return Err(NotSafe::InvalidAttestation(
+                    InvalidAttestation::TargetLessThanOrEqLowerBound {
+                        target_epoch: att_target_epoch,
+                        bound_epoch,
+                    },
+                ));
+            }
+        }
+
         // Everything has been checked, return Valid
         Ok(Safe::Valid)
     }
@@ -403,6 +473,184 @@ impl SlashingDatabase {
         txn.commit()?;
         Ok(safe)
     }
+
+    /// Import the signing history from an EIP-3076 interchange file, registering any validators
+    /// that are not yet known to the database.
+    ///
+    /// Interchange files in both the complete and the minimal format are accepted, by treating
+    /// every file as minimal: only the block with the highest slot and an attestation with the
+    /// highest source and target epochs are imported for each validator, and any records below
+    /// them are pruned. These records become the validator's low watermarks, so signing at or
+    /// below them is refused, even if the history before them was never imported.
+    ///
+    /// Existing records at the same slot or target epoch as an imported record take precedence,
+    /// as they already prevent signing at that slot or target epoch. Records without a signing
+    /// root are stored with a zero signing root, such that they never match the data that is
+    /// about to be signed.
+    pub fn import_interchange_info(
+        &self,
+        interchange: &Interchange,
+        genesis_validators_root: Hash256,
+    ) -> Result<(), InterchangeError> {
+        let version = interchange.metadata.interchange_format_version;
+        if version != SUPPORTED_INTERCHANGE_FORMAT_VERSION {
+            return Err(InterchangeError::UnsupportedVersion(version));
+        }
+
+        if interchange.metadata.genesis_validators_root != genesis_validators_root {
+            return Err(InterchangeError::GenesisValidatorsMismatch {
+                interchange_file: interchange.metadata.genesis_validators_root,
+                client: genesis_validators_root,
+            });
+        }
+
+        let mut conn = self.conn_pool.get()?;
+        let txn = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+
+        for record in &interchange.data {
+            let validator_id = Self::get_or_insert_validator_id(&txn, &record.pubkey)?;
+
+            if let Some(max_block) = record.signed_blocks.iter().max_by_key(|block| block.slot) {
+                txn.execute(
+                    "INSERT OR IGNORE INTO signed_blocks (validator_id, slot, signing_root)
+                     VALUES (?1, ?2, ?3)",
+                    params![
+                        validator_id,
+                        max_block.slot,
+                        max_block
+                            .signing_root
+                            .unwrap_or_else(Hash256::zero)
+                            .as_bytes()
+                    ],
+                )?;
+                txn.execute(
+                    "DELETE FROM signed_blocks WHERE validator_id = ?1 AND slot < ?2",
+                    params![validator_id, max_block.slot],
+                )?;
+            }
+
+            let max_source = record
+                .signed_attestations
+                .iter()
+                .map(|attestation| attestation.source_epoch)
+                .max();
+            let max_target = record
+                .signed_attestations
+                .iter()
+                .max_by_key(|attestation| attestation.target_epoch);
+
+            if let (Some(max_source), Some(max_target)) = (max_source, max_target) {
+                // The signing root only applies if the attestation with the highest target also
+                // has the highest source, otherwise the stored attestation is a synthetic one.
+                let signing_root = max_target
+                    .signing_root
+                    .filter(|_| max_target.source_epoch == max_source)
+                    .unwrap_or_else(Hash256::zero);
+
+                txn.execute(
+                    "INSERT OR IGNORE INTO signed_attestations
+                     (validator_id, source_epoch, target_epoch, signing_root)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        validator_id,
+                        max_source,
+                        max_target.target_epoch,
+                        signing_root.as_bytes()
+                    ],
+                )?;
+                txn.execute(
+                    "DELETE FROM signed_attestations WHERE validator_id = ?1 AND target_epoch < ?2",
+                    params![validator_id, max_target.target_epoch],
+                )?;
+            }
+        }
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Export the signing history of all registered validators as an EIP-3076 interchange file.
+    ///
+    /// History which has been pruned by an import is not exported, so the file may be minimal.
+    pub fn export_interchange_info(
+        &self,
+        genesis_validators_root: Hash256,
+    ) -> Result<Interchange, InterchangeError> {
+        let mut conn = self.conn_pool.get()?;
+        let txn = conn.transaction()?;
+
+        // A validator may have been registered more than once, in which case all of its records
+        // belong to the first registration.
+        let validators = txn
+            .prepare(
+                "SELECT MIN(id), public_key
+                 FROM validators
+                 GROUP BY public_key
+                 ORDER BY MIN(id)",
+            )?
+            .query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(i64, String)>, _>>()?;
+
+        let data = validators
+            .into_iter()
+            .map(|(validator_id, public_key)| {
+                let pubkey = hex::decode(public_key.trim_start_matches("0x"))
+                    .map_err(|e| format!("{:?}", e))
+                    .and_then(|bytes| PublicKey::from_bytes(&bytes).map_err(|e| format!("{:?}", e)))
+                    .map_err(|e| {
+                        InterchangeError::InvalidPubkey(format!("{}: {}", public_key, e))
+                    })?;
+
+                let signed_blocks = txn
+                    .prepare(
+                        "SELECT slot, signing_root
+                         FROM signed_blocks
+                         WHERE validator_id = ?1
+                         ORDER BY slot ASC",
+                    )?
+                    .query_map(params![validator_id], SignedBlock::from_row)?
+                    .map(|block| {
+                        block.map(|block| InterchangeBlock {
+                            slot: block.slot,
+                            signing_root: Some(block.signing_root).filter(|root| !root.is_zero()),
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                let signed_attestations = txn
+                    .prepare(
+                        "SELECT source_epoch, target_epoch, signing_root
+                         FROM signed_attestations
+                         WHERE validator_id = ?1
+                         ORDER BY target_epoch ASC",
+                    )?
+                    .query_map(params![validator_id], SignedAttestation::from_row)?
+                    .map(|attestation| {
+                        attestation.map(|attestation| InterchangeAttestation {
+                            source_epoch: attestation.source_epoch,
+                            target_epoch: attestation.target_epoch,
+                            signing_root: Some(attestation.signing_root)
+                                .filter(|root| !root.is_zero()),
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Ok(InterchangeData {
+                    pubkey,
+                    signed_blocks,
+                    signed_attestations,
+                })
+            })
+            .collect::<Result<_, InterchangeError>>()?;
+
+        Ok(Interchange {
+            metadata: InterchangeMetadata {
+                interchange_format_version: SUPPORTED_INTERCHANGE_FORMAT_VERSION,
+                genesis_validators_root,
+            },
+            data,
+        })
+    }
 }
 
 #[cfg(test)]
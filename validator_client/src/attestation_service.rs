@@ -3,6 +3,7 @@ use crate::{
     validator_store::ValidatorStore,
 };
 use environment::RuntimeContext;
+use futures::future::join_all;
 use futures::StreamExt;
 use remote_beacon_node::{PublishStatus, RemoteBeaconNode};
 use slog::{crit, debug, info, trace};
@@ -327,52 +328,56 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
             .map_err(|e| format!("Failed to produce attestation: {:?}", e))?;
 
         // For each validator in `validator_duties`, clone the `attestation` and add
-        // their signature.
+        // their signature. The validators are signed for concurrently, since signing may
+        // require a request to a remote signer.
         //
         // If any validator is unable to sign, they are simply skipped.
-        let signed_attestations = validator_duties
-            .iter()
-            .filter_map(|duty| {
-                // Ensure that all required fields are present in the validator duty.
-                let (duty_slot, duty_committee_index, validator_committee_position, _) =
-                    if let Some(tuple) = duty.attestation_duties() {
-                        tuple
-                    } else {
-                        crit!(
-                            log,
-                            "Missing validator duties when signing";
-                            "duties" => format!("{:?}", duty)
-                        );
-                        return None;
-                    };
-
-                // Ensure that the attestation matches the duties.
-                if duty_slot != attestation.data.slot
-                    || duty_committee_index != attestation.data.index
-                {
+        let attestation = &attestation;
+        let signing_futures = validator_duties.iter().map(|duty| async move {
+            // Ensure that all required fields are present in the validator duty.
+            let (duty_slot, duty_committee_index, validator_committee_position, _) =
+                if let Some(tuple) = duty.attestation_duties() {
+                    tuple
+                } else {
                     crit!(
                         log,
-                        "Inconsistent validator duties during signing";
-                        "validator" => format!("{:?}", duty.validator_pubkey()),
-                        "duty_slot" => duty_slot,
-                        "attestation_slot" => attestation.data.slot,
-                        "duty_index" => duty_committee_index,
-                        "attestation_index" => attestation.data.index,
+                        "Missing validator duties when signing";
+                        "duties" => format!("{:?}", duty)
                     );
                     return None;
-                }
+                };
 
-                let mut attestation = attestation.clone();
+            // Ensure that the attestation matches the duties.
+            if duty_slot != attestation.data.slot || duty_committee_index != attestation.data.index
+            {
+                crit!(
+                    log,
+                    "Inconsistent validator duties during signing";
+                    "validator" => format!("{:?}", duty.validator_pubkey()),
+                    "duty_slot" => duty_slot,
+                    "attestation_slot" => attestation.data.slot,
+                    "duty_index" => duty_committee_index,
+                    "attestation_index" => attestation.data.index,
+                );
+                return None;
+            }
 
-                self.validator_store
-                    .sign_attestation(
-                        duty.validator_pubkey(),
-                        validator_committee_position,
-                        &mut attestation,
-                        current_epoch,
-                    )
-                    .map(|_| attestation)
-            })
+            let mut attestation = attestation.clone();
+
+            self.validator_store
+                .sign_attestation(
+                    duty.validator_pubkey(),
+                    validator_committee_position,
+                    &mut attestation,
+                    current_epoch,
+                )
+                .await
+                .map(|_| attestation)
+        });
+        let signed_attestations = join_all(signing_futures)
+            .await
+            .into_iter()
+            .flatten()
             .collect::<Vec<_>>();
 
         // If there are any signed attestations, publish them to the BN. Otherwise,
@@ -452,42 +457,48 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
 
         // For each validator, clone the `aggregated_attestation` and convert it into
         // a `SignedAggregateAndProof`
-        let signed_aggregate_and_proofs = validator_duties
-            .iter()
-            .filter_map(|duty_and_proof| {
-                // Do not produce a signed aggregator for validators that are not
-                // subscribed aggregators.
-                let selection_proof = duty_and_proof.selection_proof.as_ref()?.clone();
-
-                let (duty_slot, duty_committee_index, _, validator_index) =
-                    duty_and_proof.attestation_duties().or_else(|| {
-                        crit!(log, "Missing duties when signing aggregate");
-                        None
-                    })?;
-
-                let pubkey = &duty_and_proof.duty.validator_pubkey;
-                let slot = attestation.data.slot;
-                let committee_index = attestation.data.index;
-
-                if duty_slot != slot || duty_committee_index != committee_index {
-                    crit!(log, "Inconsistent validator duties during signing");
-                    return None;
-                }
-
-                if let Some(signed_aggregate_and_proof) =
-                    self.validator_store.produce_signed_aggregate_and_proof(
-                        pubkey,
-                        validator_index,
-                        aggregated_attestation.clone(),
-                        selection_proof,
-                    )
-                {
-                    Some(signed_aggregate_and_proof)
-                } else {
-                    crit!(log, "Failed to sign attestation");
+        let attestation = &attestation;
+        let aggregated_attestation = &aggregated_attestation;
+        let signing_futures = validator_duties.iter().map(|duty_and_proof| async move {
+            // Do not produce a signed aggregator for validators that are not
+            // subscribed aggregators.
+            let selection_proof = duty_and_proof.selection_proof.as_ref()?.clone();
+
+            let (duty_slot, duty_committee_index, _, validator_index) =
+                duty_and_proof.attestation_duties().or_else(|| {
+                    crit!(log, "Missing duties when signing aggregate");
                     None
-                }
-            })
+                })?;
+
+            let pubkey = &duty_and_proof.duty.validator_pubkey;
+            let slot = attestation.data.slot;
+            let committee_index = attestation.data.index;
+
+            if duty_slot != slot || duty_committee_index != committee_index {
+                crit!(log, "Inconsistent validator duties during signing");
+                return None;
+            }
+
+            if let Some(signed_aggregate_and_proof) = self
+                .validator_store
+                .produce_signed_aggregate_and_proof(
+                    pubkey,
+                    validator_index,
+                    aggregated_attestation.clone(),
+                    selection_proof,
+                )
+                .await
+            {
+                Some(signed_aggregate_and_proof)
+            } else {
+                crit!(log, "Failed to sign attestation");
+                None
+            }
+        });
+        let signed_aggregate_and_proofs = join_all(signing_futures)
+            .await
+            .into_iter()
+            .flatten()
             .collect::<Vec<_>>();
 
         // If there any signed aggregates and proofs were produced, publish them to the
@@ -211,6 +211,7 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
         let randao_reveal = self
             .validator_store
             .randao_reveal(&validator_pubkey, slot.epoch(E::slots_per_epoch()))
+            .await
             .ok_or_else(|| "Unable to produce randao reveal".to_string())?;
 
         let block = self
@@ -224,6 +225,7 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
         let signed_block = self
             .validator_store
             .sign_block(&validator_pubkey, block, current_slot)
+            .await
             .ok_or_else(|| "Unable to sign block".to_string())?;
 
         let publish_status = self
@@ -44,4 +44,105 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                       node is not synced.",
                 ),
         )
+        .arg(
+            Arg::with_name("doppelganger-detection-epochs")
+                .long("doppelganger-detection-epochs")
+                .value_name("EPOCHS")
+                .help(
+                    "If non-zero, the validator client will observe the network for this many \
+                    full epochs after the epoch in which it started, refusing to sign. If any \
+                    attestations from its validators are seen during this time, the validator \
+                    client shuts down to avoid a slashing. \
+                    Requires a beacon node which supports the liveness endpoint. Defaults to 0 \
+                    (disabled).",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("interop-keys")
+                .long("interop-keys")
+                .value_name("START..END")
+                .help(
+                    "Use the insecure, deterministic interop keypairs with indices from START \
+                    (inclusive) to END (exclusive) as validators, e.g. 0..8. For use with a \
+                    beacon node started with --interop-genesis-validators. NEVER use on a \
+                    public network.",
+                )
+                .takes_value(true),
+        )
+        /* Remote signer */
+        .arg(
+            Arg::with_name("remote-signer-url")
+                .long("remote-signer-url")
+                .value_name("URL")
+                .help(
+                    "The URL of a remote signer which implements the Web3Signer API (e.g., \
+                    https://localhost:9000). Signatures for the validators listed in \
+                    --remote-signer-public-keys will be requested from this signer.",
+                )
+                .requires("remote-signer-public-keys")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("remote-signer-public-keys")
+                .long("remote-signer-public-keys")
+                .value_name("PUBLIC_KEYS")
+                .help(
+                    "A comma-separated list of the 0x-prefixed voting public keys held by the \
+                    remote signer.",
+                )
+                .requires("remote-signer-url")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("remote-signer-timeout")
+                .long("remote-signer-timeout")
+                .value_name("MILLISECONDS")
+                .help(
+                    "The time to wait for a response from the remote signer before trying the \
+                    next signing method for the validator. Defaults to 2000.",
+                )
+                .requires("remote-signer-url")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("remote-signer-root-certificate")
+                .long("remote-signer-root-certificate")
+                .value_name("PEM_PATH")
+                .help(
+                    "A PEM encoded certificate to trust when connecting to the remote signer, \
+                    such as a self-signed certificate.",
+                )
+                .requires("remote-signer-url")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("remote-signer-client-identity")
+                .long("remote-signer-client-identity")
+                .value_name("PKCS12_PATH")
+                .help(
+                    "A PKCS#12 archive containing the certificate and private key with which to \
+                    authenticate to the remote signer.",
+                )
+                .requires("remote-signer-url")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("remote-signer-client-identity-password-file")
+                .long("remote-signer-client-identity-password-file")
+                .value_name("PASSWORD_PATH")
+                .help("A file containing the password for --remote-signer-client-identity.")
+                .requires("remote-signer-client-identity")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("prefer-remote-signer")
+                .long("prefer-remote-signer")
+                .help(
+                    "If present, the remote signer is used before any local keystore for the \
+                    same validator. Otherwise, the remote signer is only used if signing with \
+                    the local keystore fails or there is no local keystore.",
+                )
+                .requires("remote-signer-url"),
+        )
 }
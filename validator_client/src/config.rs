@@ -1,13 +1,15 @@
+use crate::signing_method::{RemoteSignerConfig, DEFAULT_REMOTE_SIGNER_TIMEOUT};
 use clap::ArgMatches;
 use clap_utils::{parse_optional, parse_path_with_default_in_home_dir};
 use serde_derive::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::time::Duration;
+use types::PublicKey;
 
 pub const DEFAULT_HTTP_SERVER: &str = "http://localhost:5052/";
 pub const DEFAULT_DATA_DIR: &str = ".lighthouse/validators";
 pub const DEFAULT_SECRETS_DIR: &str = ".lighthouse/secrets";
-/// Path to the slashing protection database within the datadir.
-pub const SLASHING_PROTECTION_FILENAME: &str = "slashing_protection.sqlite";
+pub use slashing_protection::SLASHING_PROTECTION_FILENAME;
 
 /// Stores the core configuration for this validator instance.
 #[derive(Clone, Serialize, Deserialize)]
@@ -25,6 +27,15 @@ pub struct Config {
     pub allow_unsynced_beacon_node: bool,
     /// If true, register new validator keys with the slashing protection database.
     pub auto_register: bool,
+    /// If set, validators may sign via a remote signer rather than (or as well as) a local
+    /// keystore.
+    pub remote_signer: Option<RemoteSignerConfig>,
+    /// The number of epochs to observe the network for other instances of our validators before
+    /// signing. Zero disables doppelganger detection.
+    pub doppelganger_detection_epochs: u64,
+    /// The indices of the insecure, deterministic interop keypairs to use as validators, in
+    /// addition to those in `data_dir`.
+    pub interop_key_indices: Vec<usize>,
 }
 
 impl Default for Config {
@@ -42,6 +53,9 @@ impl Default for Config {
             http_server: DEFAULT_HTTP_SERVER.to_string(),
             allow_unsynced_beacon_node: false,
             auto_register: false,
+            remote_signer: None,
+            doppelganger_detection_epochs: 0,
+            interop_key_indices: vec![],
         }
     }
 }
@@ -83,6 +97,99 @@ impl Config {
             ));
         }
 
+        if let Some(epochs) = parse_optional(cli_args, "doppelganger-detection-epochs")? {
+            config.doppelganger_detection_epochs = epochs;
+        }
+
+        if let Some(range) = cli_args.value_of("interop-keys") {
+            config.interop_key_indices = parse_index_range(range)?;
+        }
+
+        if let Some(url) = parse_optional(cli_args, "remote-signer-url")? {
+            let public_keys = cli_args
+                .value_of("remote-signer-public-keys")
+                .ok_or_else(|| "--remote-signer-public-keys is required".to_string())?
+                .split(',')
+                .map(parse_public_key)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            config.remote_signer = Some(RemoteSignerConfig {
+                url,
+                public_keys,
+                request_timeout: parse_optional(cli_args, "remote-signer-timeout")?
+                    .map(Duration::from_millis)
+                    .unwrap_or(DEFAULT_REMOTE_SIGNER_TIMEOUT),
+                root_certificate_path: parse_optional(cli_args, "remote-signer-root-certificate")?,
+                client_identity_path: parse_optional(cli_args, "remote-signer-client-identity")?,
+                client_identity_password_path: parse_optional(
+                    cli_args,
+                    "remote-signer-client-identity-password-file",
+                )?,
+                prefer_remote: cli_args.is_present("prefer-remote-signer"),
+            });
+        }
+
         Ok(config)
     }
 }
+
+/// Parses a range of validator indices in the form `START..END`, where `END` is exclusive.
+fn parse_index_range(range: &str) -> Result<Vec<usize>, String> {
+    let mut split = range.trim().splitn(2, "..");
+    let mut parse_index = |name: &str| {
+        split
+            .next()
+            .ok_or_else(|| format!("Index range {} has no {}", range, name))?
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid {} of index range {}: {:?}", name, range, e))
+    };
+    let start = parse_index("start")?;
+    let end = parse_index("end")?;
+
+    if start >= end {
+        return Err(format!("Index range {} is empty", range));
+    }
+
+    Ok((start..end).collect())
+}
+
+/// Parses a `0x` prefixed hex public key, as provided on the command line.
+fn parse_public_key(hex_public_key: &str) -> Result<PublicKey, String> {
+    let hex_public_key = hex_public_key.trim();
+    hex::decode(hex_public_key.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid public key hex {}: {:?}", hex_public_key, e))
+        .and_then(|bytes| {
+            PublicKey::from_bytes(&bytes)
+                .map_err(|e| format!("Invalid public key {}: {:?}", hex_public_key, e))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_index_range_is_exclusive_of_end() {
+        assert_eq!(parse_index_range("0..4"), Ok(vec![0, 1, 2, 3]));
+        assert_eq!(parse_index_range(" 4..6 "), Ok(vec![4, 5]));
+    }
+
+    #[test]
+    fn parse_index_range_rejects_empty_and_reversed_ranges() {
+        assert!(parse_index_range("4..4").is_err());
+        assert!(parse_index_range("8..4").is_err());
+    }
+
+    #[test]
+    fn parse_index_range_rejects_malformed_ranges() {
+        for range in &[
+            "", "4", "4..", "..4", "a..4", "0..b", "0...4", "-1..4", "0..4..8",
+        ] {
+            assert!(
+                parse_index_range(range).is_err(),
+                "{} should not parse",
+                range
+            );
+        }
+    }
+}
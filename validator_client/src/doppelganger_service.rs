@@ -0,0 +1,409 @@
+use crate::{duties_service::DutiesService, validator_store::ValidatorStore};
+use environment::RuntimeContext;
+use futures::StreamExt;
+use remote_beacon_node::RemoteBeaconNode;
+use slog::{crit, error, info};
+use slot_clock::SlotClock;
+use std::ops::Deref;
+use std::sync::Arc;
+use tokio::time::{interval_at, Duration, Instant};
+use types::{ChainSpec, Epoch, EthSpec};
+
+/// Delay this period of time after the slot starts. This allows the node to process the
+/// attestations of the previous slot.
+const TIME_DELAY_FROM_SLOT: Duration = Duration::from_millis(200);
+
+/// Builds a `DoppelgangerService`.
+pub struct DoppelgangerServiceBuilder<T, E: EthSpec> {
+    duties_service: Option<DutiesService<T, E>>,
+    validator_store: Option<ValidatorStore<T, E>>,
+    slot_clock: Option<T>,
+    beacon_node: Option<RemoteBeaconNode<E>>,
+    context: Option<RuntimeContext<E>>,
+    detection_epochs: u64,
+}
+
+impl<T: SlotClock + 'static, E: EthSpec> DoppelgangerServiceBuilder<T, E> {
+    pub fn new() -> Self {
+        Self {
+            duties_service: None,
+            validator_store: None,
+            slot_clock: None,
+            beacon_node: None,
+            context: None,
+            detection_epochs: 0,
+        }
+    }
+
+    pub fn duties_service(mut self, service: DutiesService<T, E>) -> Self {
+        self.duties_service = Some(service);
+        self
+    }
+
+    pub fn validator_store(mut self, store: ValidatorStore<T, E>) -> Self {
+        self.validator_store = Some(store);
+        self
+    }
+
+    pub fn slot_clock(mut self, slot_clock: T) -> Self {
+        self.slot_clock = Some(slot_clock);
+        self
+    }
+
+    pub fn beacon_node(mut self, beacon_node: RemoteBeaconNode<E>) -> Self {
+        self.beacon_node = Some(beacon_node);
+        self
+    }
+
+    pub fn runtime_context(mut self, context: RuntimeContext<E>) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// The number of epochs to observe the network before signing is enabled.
+    pub fn detection_epochs(mut self, detection_epochs: u64) -> Self {
+        self.detection_epochs = detection_epochs;
+        self
+    }
+
+    pub fn build(self) -> Result<DoppelgangerService<T, E>, String> {
+        Ok(DoppelgangerService {
+            inner: Arc::new(Inner {
+                duties_service: self
+                    .duties_service
+                    .ok_or_else(|| "Cannot build DoppelgangerService without duties_service")?,
+                validator_store: self
+                    .validator_store
+                    .ok_or_else(|| "Cannot build DoppelgangerService without validator_store")?,
+                slot_clock: self
+                    .slot_clock
+                    .ok_or_else(|| "Cannot build DoppelgangerService without slot_clock")?,
+                beacon_node: self
+                    .beacon_node
+                    .ok_or_else(|| "Cannot build DoppelgangerService without beacon_node")?,
+                context: self
+                    .context
+                    .ok_or_else(|| "Cannot build DoppelgangerService without runtime_context")?,
+                detection_epochs: self.detection_epochs,
+            }),
+        })
+    }
+}
+
+/// Helper to minimise `Arc` usage.
+pub struct Inner<T, E: EthSpec> {
+    duties_service: DutiesService<T, E>,
+    validator_store: ValidatorStore<T, E>,
+    slot_clock: T,
+    beacon_node: RemoteBeaconNode<E>,
+    context: RuntimeContext<E>,
+    detection_epochs: u64,
+}
+
+/// Prevents the validator client from signing until it has observed the network for some number
+/// of epochs without seeing any attestations from its own validators.
+///
+/// If another instance of any of the validators is seen (a "doppelganger"), signing is never
+/// enabled and the validator client shuts down.
+pub struct DoppelgangerService<T, E: EthSpec> {
+    inner: Arc<Inner<T, E>>,
+}
+
+impl<T, E: EthSpec> Clone for DoppelgangerService<T, E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T, E: EthSpec> Deref for DoppelgangerService<T, E> {
+    type Target = Inner<T, E>;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner.deref()
+    }
+}
+
+/// The outcome of checking the liveness of our validators.
+#[derive(Debug, PartialEq)]
+enum DetectionOutcome {
+    /// Detection should continue at the next slot.
+    Continue,
+    /// No doppelganger was detected for the entire detection period.
+    Complete,
+    /// A doppelganger was detected, signing must remain disabled and the process must shut down.
+    Detected { epoch: Epoch, indices: Vec<u64> },
+}
+
+/// Returns the epoch from which signing is enabled, if no doppelganger is detected.
+///
+/// Our validators may have been live in `start_epoch` and the epoch prior to it (e.g., the
+/// validator client was just restarted), so those epochs are never checked. Instead, signing is
+/// enabled once `detection_epochs` full epochs after `start_epoch` have been observed.
+fn signing_epoch(start_epoch: Epoch, detection_epochs: u64) -> Epoch {
+    start_epoch + 1 + detection_epochs
+}
+
+/// Returns the epochs which should be checked for liveness during `current_epoch`.
+///
+/// These are the current and previous epochs, restricted to those strictly after `start_epoch`
+/// and prior to `signing_epoch`.
+fn epochs_to_check(start_epoch: Epoch, signing_epoch: Epoch, current_epoch: Epoch) -> Vec<Epoch> {
+    let mut epochs = vec![current_epoch.saturating_sub(1_u64), current_epoch];
+    epochs.dedup();
+    epochs.retain(|epoch| *epoch > start_epoch && *epoch < signing_epoch);
+    epochs
+}
+
+/// Determines the outcome of a check during `current_epoch`, given the indices of our validators
+/// which were live in each of the checked epochs.
+fn detection_outcome(
+    signing_epoch: Epoch,
+    current_epoch: Epoch,
+    liveness: Vec<(Epoch, Vec<u64>)>,
+) -> DetectionOutcome {
+    if let Some((epoch, indices)) = liveness
+        .into_iter()
+        .find(|(_, indices)| !indices.is_empty())
+    {
+        DetectionOutcome::Detected { epoch, indices }
+    } else if current_epoch >= signing_epoch {
+        DetectionOutcome::Complete
+    } else {
+        DetectionOutcome::Continue
+    }
+}
+
+impl<T: SlotClock + 'static, E: EthSpec> DoppelgangerService<T, E> {
+    /// Starts the service which checks for doppelgangers at each slot, until the detection period
+    /// has completed.
+    ///
+    /// Does nothing if signing is already enabled (i.e., detection is disabled).
+    pub fn start_update_service(self, spec: &ChainSpec) -> Result<(), String> {
+        if self.validator_store.signing_enabled() {
+            return Ok(());
+        }
+
+        let start_epoch = self
+            .slot_clock
+            .now()
+            .ok_or_else(|| "Unable to determine current slot from clock".to_string())?
+            .epoch(E::slots_per_epoch());
+        let signing_epoch = signing_epoch(start_epoch, self.detection_epochs);
+
+        info!(
+            self.context.log(),
+            "Doppelganger detection enabled";
+            "signing_epoch" => signing_epoch.as_u64(),
+            "current_epoch" => start_epoch.as_u64(),
+            "msg" => "no blocks or attestations will be signed until the signing epoch"
+        );
+
+        let duration_to_next_slot = self
+            .slot_clock
+            .duration_to_next_slot()
+            .ok_or_else(|| "Unable to determine duration to next slot".to_string())?;
+
+        let mut interval = {
+            let slot_duration = Duration::from_millis(spec.milliseconds_per_slot);
+            // Note: interval_at panics if `slot_duration` is 0
+            interval_at(
+                Instant::now() + duration_to_next_slot + TIME_DELAY_FROM_SLOT,
+                slot_duration,
+            )
+        };
+
+        let executor = self.inner.context.executor.clone();
+
+        let interval_fut = async move {
+            while interval.next().await.is_some() {
+                match self.detect_doppelgangers(start_epoch, signing_epoch).await {
+                    DetectionOutcome::Continue => (),
+                    DetectionOutcome::Complete => {
+                        info!(
+                            self.context.log(),
+                            "Doppelganger detection complete";
+                            "msg" => "signing is enabled"
+                        );
+                        self.validator_store.enable_signing();
+                        break;
+                    }
+                    DetectionOutcome::Detected { epoch, indices } => {
+                        crit!(
+                            self.context.log(),
+                            "Doppelganger detected";
+                            "msg" => "another instance of these validators is active on the \
+                            network. Shutting down to avoid a slashing.",
+                            "validator_indices" => format!("{:?}", indices),
+                            "epoch" => epoch.as_u64()
+                        );
+                        if let Err(e) = self
+                            .context
+                            .executor
+                            .shutdown_sender()
+                            .try_send("Doppelganger detected")
+                        {
+                            crit!(
+                                self.context.log(),
+                                "Unable to request shutdown";
+                                "error" => format!("{:?}", e),
+                            );
+                        }
+                        break;
+                    }
+                }
+            }
+        };
+
+        executor.spawn(interval_fut, "doppelganger_service");
+
+        Ok(())
+    }
+
+    /// Checks the liveness of all our validators in the current and previous epochs, ignoring any
+    /// epoch in which we may have signed (see `epochs_to_check`).
+    ///
+    /// Signing is only enabled after a successful check during `signing_epoch`, which ensures the
+    /// whole of the epoch prior to `signing_epoch` has been checked.
+    async fn detect_doppelgangers(
+        &self,
+        start_epoch: Epoch,
+        signing_epoch: Epoch,
+    ) -> DetectionOutcome {
+        let log = self.context.log();
+
+        let current_epoch = if let Some(slot) = self.slot_clock.now() {
+            slot.epoch(E::slots_per_epoch())
+        } else {
+            error!(log, "Unable to determine current slot from clock");
+            return DetectionOutcome::Continue;
+        };
+
+        let indices = self.duties_service.validator_indices();
+        if indices.is_empty() && current_epoch < signing_epoch {
+            return DetectionOutcome::Continue;
+        }
+
+        let mut liveness = vec![];
+        for epoch in epochs_to_check(start_epoch, signing_epoch, current_epoch) {
+            let live_indices = match self
+                .beacon_node
+                .http
+                .validator()
+                .get_liveness(epoch, &indices)
+                .await
+            {
+                Ok(data) => data
+                    .into_iter()
+                    .filter(|data| data.is_live)
+                    .map(|data| data.index)
+                    .collect(),
+                Err(e) => {
+                    error!(
+                        log,
+                        "Unable to check for doppelgangers";
+                        "error" => format!("{:?}", e),
+                        "epoch" => epoch.as_u64()
+                    );
+                    return DetectionOutcome::Continue;
+                }
+            };
+            liveness.push((epoch, live_indices));
+        }
+
+        detection_outcome(signing_epoch, current_epoch, liveness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DETECTION_EPOCHS: u64 = 2;
+    const START_EPOCH: u64 = 10;
+
+    /// Runs the checks for each epoch from the start epoch until detection completes, using
+    /// `live_epochs` as the epochs in which another instance of validator 0 was live.
+    fn run_detection(live_epochs: &[u64]) -> DetectionOutcome {
+        let start_epoch = Epoch::new(START_EPOCH);
+        let signing_epoch = signing_epoch(start_epoch, DETECTION_EPOCHS);
+
+        let mut current_epoch = start_epoch;
+        loop {
+            let liveness = epochs_to_check(start_epoch, signing_epoch, current_epoch)
+                .into_iter()
+                .map(|epoch| {
+                    let indices = if live_epochs.contains(&epoch.as_u64()) {
+                        vec![0]
+                    } else {
+                        vec![]
+                    };
+                    (epoch, indices)
+                })
+                .collect();
+
+            match detection_outcome(signing_epoch, current_epoch, liveness) {
+                DetectionOutcome::Continue => current_epoch += 1,
+                outcome => return outcome,
+            }
+        }
+    }
+
+    #[test]
+    fn start_epoch_and_prior_are_not_checked() {
+        let start_epoch = Epoch::new(START_EPOCH);
+        let signing_epoch = signing_epoch(start_epoch, DETECTION_EPOCHS);
+
+        assert!(epochs_to_check(start_epoch, signing_epoch, start_epoch).is_empty());
+        assert_eq!(
+            epochs_to_check(start_epoch, signing_epoch, start_epoch + 1),
+            vec![start_epoch + 1]
+        );
+        assert_eq!(
+            epochs_to_check(start_epoch, signing_epoch, signing_epoch),
+            vec![signing_epoch - 1]
+        );
+        assert!(epochs_to_check(start_epoch, signing_epoch, signing_epoch + 1).is_empty());
+    }
+
+    #[test]
+    fn restart_is_not_a_doppelganger() {
+        // The previous instance of the validator client attested up until it was stopped.
+        assert_eq!(
+            run_detection(&[START_EPOCH - 1, START_EPOCH]),
+            DetectionOutcome::Complete
+        );
+    }
+
+    #[test]
+    fn doppelganger_is_detected() {
+        for epoch in START_EPOCH + 1..=START_EPOCH + DETECTION_EPOCHS {
+            assert_eq!(
+                run_detection(&[START_EPOCH, epoch]),
+                DetectionOutcome::Detected {
+                    epoch: Epoch::new(epoch),
+                    indices: vec![0]
+                },
+                "should detect liveness in epoch {}",
+                epoch
+            );
+        }
+    }
+
+    #[test]
+    fn signing_waits_for_detection_epochs() {
+        let start_epoch = Epoch::new(START_EPOCH);
+        let signing_epoch = signing_epoch(start_epoch, DETECTION_EPOCHS);
+
+        assert_eq!(signing_epoch, start_epoch + 1 + DETECTION_EPOCHS);
+        assert_eq!(
+            detection_outcome(signing_epoch, signing_epoch - 1, vec![]),
+            DetectionOutcome::Continue
+        );
+        assert_eq!(
+            detection_outcome(signing_epoch, signing_epoch, vec![]),
+            DetectionOutcome::Complete
+        );
+    }
+}
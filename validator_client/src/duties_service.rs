@@ -38,7 +38,7 @@ impl DutyAndProof {
     ///
     /// - `self.validator_pubkey` is not known in `validator_store`.
     /// - There's an arith error during computation.
-    pub fn compute_selection_proof<T: SlotClock + 'static, E: EthSpec>(
+    pub async fn compute_selection_proof<T: SlotClock + 'static, E: EthSpec>(
         &mut self,
         validator_store: &ValidatorStore<T, E>,
     ) -> Result<(), String> {
@@ -55,6 +55,7 @@ impl DutyAndProof {
 
         let selection_proof = validator_store
             .produce_selection_proof(&self.duty.validator_pubkey, slot)
+            .await
             .ok_or_else(|| "Failed to produce selection proof".to_string())?;
 
         self.selection_proof = selection_proof
@@ -230,6 +231,19 @@ impl DutiesStore {
             .collect()
     }
 
+    /// Returns the indices of all validators with known duties.
+    fn validator_indices(&self) -> Vec<u64> {
+        self.store
+            .read()
+            .values()
+            .filter_map(|validator_map| {
+                validator_map
+                    .values()
+                    .find_map(|duties| duties.duty.validator_index)
+            })
+            .collect()
+    }
+
     fn is_aggregator(&self, validator_pubkey: &PublicKey, epoch: &Epoch) -> Option<bool> {
         Some(
             self.store
@@ -241,17 +255,24 @@ impl DutiesStore {
         )
     }
 
-    fn insert<T: SlotClock + 'static, E: EthSpec>(
-        &self,
-        epoch: Epoch,
-        mut duties: DutyAndProof,
-        slots_per_epoch: u64,
-        validator_store: &ValidatorStore<T, E>,
-    ) -> Result<InsertOutcome, String> {
+    /// Returns `true` if the store already holds `duty` for the given `epoch`.
+    fn contains_identical(&self, epoch: Epoch, duty: &ValidatorDuty) -> bool {
+        self.store
+            .read()
+            .get(&duty.validator_pubkey)
+            .and_then(|validator_map| validator_map.get(&epoch))
+            .map_or(false, |known_duties| known_duties.duty == *duty)
+    }
+
+    /// Inserts `duties` into the store.
+    ///
+    /// The selection proof of `duties` must already have been computed, since signing cannot
+    /// occur whilst the store is locked.
+    fn insert(&self, epoch: Epoch, duties: DutyAndProof, slots_per_epoch: u64) -> InsertOutcome {
         let mut store = self.store.write();
 
         if !duties_match_epoch(&duties.duty, epoch, slots_per_epoch) {
-            return Ok(InsertOutcome::Invalid);
+            return InsertOutcome::Invalid;
         }
 
         // TODO: refactor with Entry.
@@ -259,31 +280,22 @@ impl DutiesStore {
         if let Some(validator_map) = store.get_mut(&duties.duty.validator_pubkey) {
             if let Some(known_duties) = validator_map.get_mut(&epoch) {
                 if known_duties.duty == duties.duty {
-                    Ok(InsertOutcome::Identical)
+                    InsertOutcome::Identical
                 } else {
-                    // Compute the selection proof.
-                    duties.compute_selection_proof(validator_store)?;
-
                     // Determine if a re-subscription is required.
                     let should_resubscribe = duties.subscription_eq(known_duties);
 
                     // Replace the existing duties.
                     *known_duties = duties;
 
-                    Ok(InsertOutcome::Replaced { should_resubscribe })
+                    InsertOutcome::Replaced { should_resubscribe }
                 }
             } else {
-                // Compute the selection proof.
-                duties.compute_selection_proof(validator_store)?;
-
                 validator_map.insert(epoch, duties);
 
-                Ok(InsertOutcome::NewEpoch)
+                InsertOutcome::NewEpoch
             }
         } else {
-            // Compute the selection proof.
-            duties.compute_selection_proof(validator_store)?;
-
             let validator_pubkey = duties.duty.validator_pubkey.clone();
 
             let mut validator_map = HashMap::new();
@@ -291,7 +303,7 @@ impl DutiesStore {
 
             store.insert(validator_pubkey, validator_map);
 
-            Ok(InsertOutcome::NewValidator)
+            InsertOutcome::NewValidator
         }
     }
 
@@ -419,6 +431,12 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
         self.store.proposer_count(epoch)
     }
 
+    /// Returns the indices of all validators which have been assigned duties, i.e., those which
+    /// are known to the beacon chain.
+    pub fn validator_indices(&self) -> Vec<u64> {
+        self.store.validator_indices()
+    }
+
     /// Returns the total number of validators that should attest in the given epoch.
     pub fn attester_count(&self, epoch: Epoch) -> usize {
         self.store.attester_count(epoch)
@@ -552,70 +570,89 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
 
         // For each of the duties, attempt to insert them into our local store and build a
         // list of new or changed selections proofs for any aggregating validators.
-        let validator_subscriptions = all_duties
-            .into_iter()
-            .filter_map(|remote_duties| {
-                // Convert the remote duties into our local representation.
-                let duties: DutyAndProof = remote_duties
-                    .clone()
-                    .try_into()
-                    .map_err(|e| {
-                        error!(
-                            log,
-                            "Unable to convert remote duties";
-                            "error" => e
-                        )
-                    })
-                    .ok()?;
-
-                let validator_pubkey = duties.duty.validator_pubkey.clone();
-
-                // Attempt to update our local store.
-                let outcome = self
-                    .store
-                    .insert(epoch, duties, E::slots_per_epoch(), &self.validator_store)
-                    .map_err(|e| {
-                        error!(
-                            log,
-                            "Unable to store duties";
-                            "error" => e
-                        )
-                    })
-                    .ok()?;
+        let mut validator_subscriptions = vec![];
+        for remote_duties in all_duties {
+            // Convert the remote duties into our local representation.
+            let mut duties: DutyAndProof = match remote_duties.clone().try_into() {
+                Ok(duties) => duties,
+                Err(e) => {
+                    error!(
+                        log,
+                        "Unable to convert remote duties";
+                        "error" => e
+                    );
+                    continue;
+                }
+            };
 
-                match &outcome {
-                    InsertOutcome::NewValidator => {
-                        debug!(
-                            log,
-                            "First duty assignment for validator";
-                            "proposal_slots" => format!("{:?}", &remote_duties.block_proposal_slots),
-                            "attestation_slot" => format!("{:?}", &remote_duties.attestation_slot),
-                            "validator" => format!("{:?}", &remote_duties.validator_pubkey)
-                        );
-                        new_validator += 1;
-                    }
-                    InsertOutcome::NewEpoch => new_epoch += 1,
-                    InsertOutcome::Identical => identical += 1,
-                    InsertOutcome::Replaced { .. } => replaced += 1,
-                    InsertOutcome::Invalid => invalid += 1,
-                };
-
-                // The selection proof is computed on `store.insert`, so it's necessary to check
-                // with the store that the validator is an aggregator.
-                let is_aggregator = self.store.is_aggregator(&validator_pubkey, &epoch)?;
-
-                if outcome.is_subscription_candidate() {
-                    Some(ValidatorSubscription {
-                        validator_index: remote_duties.validator_index?,
-                        attestation_committee_index: remote_duties.attestation_committee_index?,
-                        slot: remote_duties.attestation_slot?,
-                        is_aggregator,
-                    })
-                } else {
-                    None
+            let validator_pubkey = duties.duty.validator_pubkey.clone();
+
+            // Only compute the selection proof for duties which are new or have changed, since
+            // it requires a signature.
+            if !self.store.contains_identical(epoch, &duties.duty) {
+                if let Err(e) = duties.compute_selection_proof(&self.validator_store).await {
+                    error!(
+                        log,
+                        "Unable to compute selection proof";
+                        "error" => e
+                    );
+                    continue;
                 }
-            })
-            .collect::<Vec<_>>();
+            }
+
+            // Attempt to update our local store.
+            let outcome = self.store.insert(epoch, duties, E::slots_per_epoch());
+
+            match &outcome {
+                InsertOutcome::NewValidator => {
+                    debug!(
+                        log,
+                        "First duty assignment for validator";
+                        "proposal_slots" => format!("{:?}", &remote_duties.block_proposal_slots),
+                        "attestation_slot" => format!("{:?}", &remote_duties.attestation_slot),
+                        "validator" => format!("{:?}", &remote_duties.validator_pubkey)
+                    );
+                    new_validator += 1;
+                }
+                InsertOutcome::NewEpoch => new_epoch += 1,
+                InsertOutcome::Identical => identical += 1,
+                InsertOutcome::Replaced { .. } => replaced += 1,
+                InsertOutcome::Invalid => invalid += 1,
+            };
+
+            // Identical duties are not stored, so it's necessary to check with the store
+            // that the validator is an aggregator.
+            let is_aggregator = match self.store.is_aggregator(&validator_pubkey, &epoch) {
+                Some(is_aggregator) => is_aggregator,
+                None => continue,
+            };
+
+            // The beacon node only subscribes to the subnets of aggregators.
+            let subscribe_to_subnet = if self.validator_store.signing_enabled() {
+                is_aggregator
+            } else {
+                // No attestations or aggregates are produced whilst doppelganger detection is
+                // running. Instead, subscribe to the subnets of all validators so the beacon node
+                // observes any attestations they produce elsewhere.
+                true
+            };
+
+            if outcome.is_subscription_candidate() {
+                if let (Some(validator_index), Some(attestation_committee_index), Some(slot)) = (
+                    remote_duties.validator_index,
+                    remote_duties.attestation_committee_index,
+                    remote_duties.attestation_slot,
+                ) {
+                    validator_subscriptions.push(ValidatorSubscription {
+                        validator_index,
+                        attestation_committee_index,
+                        committee_count_at_slot: None,
+                        slot,
+                        is_aggregator: subscribe_to_subnet,
+                    });
+                }
+            }
+        }
 
         if invalid > 0 {
             error!(
@@ -2,10 +2,12 @@ mod attestation_service;
 mod block_service;
 mod cli;
 mod config;
+mod doppelganger_service;
 mod duties_service;
 mod fork_service;
 mod is_synced;
 mod notifier;
+mod signing_method;
 mod validator_store;
 
 pub use cli::cli_app;
@@ -15,6 +17,7 @@ use attestation_service::{AttestationService, AttestationServiceBuilder};
 use block_service::{BlockService, BlockServiceBuilder};
 use clap::ArgMatches;
 use config::SLASHING_PROTECTION_FILENAME;
+use doppelganger_service::{DoppelgangerService, DoppelgangerServiceBuilder};
 use duties_service::{DutiesService, DutiesServiceBuilder};
 use environment::RuntimeContext;
 use fork_service::{ForkService, ForkServiceBuilder};
@@ -40,6 +43,7 @@ pub struct ProductionValidatorClient<T: EthSpec> {
     fork_service: ForkService<SystemTimeSlotClock, T>,
     block_service: BlockService<SystemTimeSlotClock, T>,
     attestation_service: AttestationService<SystemTimeSlotClock, T>,
+    doppelganger_service: DoppelgangerService<SystemTimeSlotClock, T>,
     config: Config,
 }
 
@@ -202,11 +206,20 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
             .build()?;
 
         let attestation_service = AttestationServiceBuilder::new()
+            .duties_service(duties_service.clone())
+            .slot_clock(slot_clock.clone())
+            .validator_store(validator_store.clone())
+            .beacon_node(beacon_node.clone())
+            .runtime_context(context.service_context("attestation".into()))
+            .build()?;
+
+        let doppelganger_service = DoppelgangerServiceBuilder::new()
             .duties_service(duties_service.clone())
             .slot_clock(slot_clock)
             .validator_store(validator_store)
             .beacon_node(beacon_node)
-            .runtime_context(context.service_context("attestation".into()))
+            .runtime_context(context.service_context("doppelganger".into()))
+            .detection_epochs(config.doppelganger_detection_epochs)
             .build()?;
 
         Ok(Self {
@@ -215,6 +228,7 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
             fork_service,
             block_service,
             attestation_service,
+            doppelganger_service,
             config,
         })
     }
@@ -240,6 +254,11 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
             .start_update_service(&self.context.eth2_config.spec)
             .map_err(|e| format!("Unable to start attestation service: {}", e))?;
 
+        self.doppelganger_service
+            .clone()
+            .start_update_service(&self.context.eth2_config.spec)
+            .map_err(|e| format!("Unable to start doppelganger service: {}", e))?;
+
         spawn_notifier(self).map_err(|e| format!("Failed to start notifier: {}", e))?;
 
         Ok(())
@@ -0,0 +1,243 @@
+//! Provides the methods with which a validator may produce signatures.
+//!
+//! A validator may either hold its secret key locally, or delegate signing to a remote signer
+//! (e.g., [Web3Signer](https://docs.web3signer.consensys.net/)) via HTTP.
+
+use bls::BLS_SIG_BYTE_SIZE;
+use reqwest::{Certificate, Client, Identity};
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use types::{Hash256, Keypair, PublicKey, Signature};
+
+/// The default timeout for a request to a remote signer.
+///
+/// This is short enough that an attestation may still be signed by a fallback signing method
+/// within the slot.
+pub const DEFAULT_REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request to the remote signer could not be sent or did not succeed.
+    RemoteSignerRequestFailed(String),
+    /// The response from the remote signer was not a valid signature.
+    InvalidRemoteSignature(String),
+    /// The remote signer returned a signature which does not verify against the public key of
+    /// the validator.
+    IncorrectRemoteSignature,
+}
+
+/// The configuration for a remote signer, as provided on the command line.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RemoteSignerConfig {
+    /// The base URL of the remote signer, e.g. `https://localhost:9000`.
+    pub url: String,
+    /// The public keys of the validators whose secret keys are held by the remote signer.
+    pub public_keys: Vec<PublicKey>,
+    /// The timeout applied to each signing request.
+    pub request_timeout: Duration,
+    /// A PEM encoded certificate to trust when connecting to the remote signer, in addition to the
+    /// system certificates.
+    pub root_certificate_path: Option<PathBuf>,
+    /// A PKCS#12 archive containing the certificate and key used to authenticate to the remote
+    /// signer.
+    pub client_identity_path: Option<PathBuf>,
+    /// A file containing the password for the `client_identity_path` archive.
+    pub client_identity_password_path: Option<PathBuf>,
+    /// If true, the remote signer is used before any local keystore for the same validator.
+    pub prefer_remote: bool,
+}
+
+impl RemoteSignerConfig {
+    /// Builds an HTTP client with the timeout and TLS settings of `self`.
+    pub fn build_client(&self) -> Result<Client, String> {
+        let mut builder = Client::builder().timeout(self.request_timeout);
+
+        if let Some(path) = &self.root_certificate_path {
+            let pem = fs::read(path)
+                .map_err(|e| format!("Unable to read root certificate {:?}: {:?}", path, e))?;
+            let certificate = Certificate::from_pem(&pem)
+                .map_err(|e| format!("Invalid root certificate {:?}: {:?}", path, e))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        if let Some(path) = &self.client_identity_path {
+            let archive = fs::read(path)
+                .map_err(|e| format!("Unable to read client identity {:?}: {:?}", path, e))?;
+            let password = match &self.client_identity_password_path {
+                Some(password_path) => fs::read_to_string(password_path)
+                    .map_err(|e| format!("Unable to read {:?}: {:?}", password_path, e))?
+                    .trim_end_matches(|c| c == '\n' || c == '\r')
+                    .to_string(),
+                None => String::new(),
+            };
+            let identity = Identity::from_pkcs12_der(&archive, &password)
+                .map_err(|e| format!("Invalid client identity {:?}: {:?}", path, e))?;
+            builder = builder.identity(identity);
+        }
+
+        builder
+            .build()
+            .map_err(|e| format!("Unable to build remote signer client: {:?}", e))
+    }
+}
+
+/// A method of producing signatures for a single validator.
+pub enum SigningMethod {
+    /// The secret key is held in memory, having been decrypted from a local keystore.
+    LocalKeystore { voting_keypair: Keypair },
+    /// The secret key is held by a remote signer which implements the Web3Signer API.
+    RemoteSigner {
+        url: String,
+        http_client: Client,
+        voting_public_key: PublicKey,
+    },
+}
+
+/// The body of a signing request to a remote signer.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SigningRequest {
+    signing_root: Hash256,
+}
+
+impl SigningMethod {
+    /// Returns a signature across `signing_root`, which must already include the signing domain.
+    pub async fn get_signature(&self, signing_root: Hash256) -> Result<Signature, Error> {
+        match self {
+            SigningMethod::LocalKeystore { voting_keypair } => {
+                Ok(Signature::new(signing_root.as_bytes(), &voting_keypair.sk))
+            }
+            SigningMethod::RemoteSigner {
+                url,
+                http_client,
+                voting_public_key,
+            } => {
+                let signing_url = format!(
+                    "{}/api/v1/eth2/sign/{}",
+                    url.trim_end_matches('/'),
+                    voting_public_key.as_hex_string()
+                );
+
+                let response = http_client
+                    .post(&signing_url)
+                    .json(&SigningRequest { signing_root })
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| Error::RemoteSignerRequestFailed(format!("{:?}", e)))?;
+                let body = response
+                    .text()
+                    .await
+                    .map_err(|e| Error::RemoteSignerRequestFailed(format!("{:?}", e)))?;
+
+                let signature = parse_signature(&body)?;
+                verify_remote_signature(signature, signing_root, voting_public_key)
+            }
+        }
+    }
+
+    /// Returns a short description of `self`, for logging.
+    pub fn description(&self) -> &'static str {
+        match self {
+            SigningMethod::LocalKeystore { .. } => "local_keystore",
+            SigningMethod::RemoteSigner { .. } => "remote_signer",
+        }
+    }
+}
+
+/// Returns `signature` if it is a signature across `signing_root` by `public_key`.
+///
+/// A remote signer is not trusted to sign with the right key, or to sign at all, so its signatures
+/// are verified before they are published or stored.
+fn verify_remote_signature(
+    signature: Signature,
+    signing_root: Hash256,
+    public_key: &PublicKey,
+) -> Result<Signature, Error> {
+    if signature.verify(signing_root.as_bytes(), public_key) {
+        Ok(signature)
+    } else {
+        Err(Error::IncorrectRemoteSignature)
+    }
+}
+
+/// Parses the body of a response from a remote signer, which is either a `0x` prefixed hex
+/// signature or a JSON object with a `signature` field.
+fn parse_signature(body: &str) -> Result<Signature, Error> {
+    #[derive(Deserialize)]
+    struct SigningResponse {
+        signature: String,
+    }
+
+    let body = body.trim();
+    let hex_signature = if body.starts_with('{') {
+        serde_json::from_str::<SigningResponse>(body)
+            .map_err(|e| Error::InvalidRemoteSignature(format!("{:?}", e)))?
+            .signature
+    } else {
+        body.to_string()
+    };
+
+    let bytes = hex::decode(hex_signature.trim_start_matches("0x"))
+        .map_err(|e| Error::InvalidRemoteSignature(format!("{:?}", e)))?;
+
+    if bytes.len() != BLS_SIG_BYTE_SIZE {
+        return Err(Error::InvalidRemoteSignature(format!(
+            "Invalid signature length: {}",
+            bytes.len()
+        )));
+    }
+
+    Signature::from_bytes(&bytes).map_err(|e| Error::InvalidRemoteSignature(format!("{:?}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::generate_deterministic_keypair;
+
+    #[test]
+    fn parse_remote_signatures() {
+        let keypair = generate_deterministic_keypair(0);
+        let signature = Signature::new(Hash256::repeat_byte(42).as_bytes(), &keypair.sk);
+        let hex_signature = format!("0x{}", hex::encode(signature.as_bytes()));
+
+        assert_eq!(parse_signature(&hex_signature).unwrap(), signature);
+        assert_eq!(
+            parse_signature(&format!("{}\n", hex_signature)).unwrap(),
+            signature
+        );
+        assert_eq!(
+            parse_signature(&format!(r#"{{"signature": "{}"}}"#, hex_signature)).unwrap(),
+            signature
+        );
+
+        assert!(parse_signature("0x1234").is_err());
+        assert!(parse_signature("").is_err());
+        assert!(parse_signature("not a signature").is_err());
+    }
+
+    #[test]
+    fn verify_remote_signatures() {
+        let keypair = generate_deterministic_keypair(0);
+        let other_keypair = generate_deterministic_keypair(1);
+        let signing_root = Hash256::repeat_byte(42);
+        let signature = Signature::new(signing_root.as_bytes(), &keypair.sk);
+
+        assert_eq!(
+            verify_remote_signature(signature.clone(), signing_root, &keypair.pk).unwrap(),
+            signature
+        );
+        assert!(
+            verify_remote_signature(signature.clone(), Hash256::repeat_byte(7), &keypair.pk)
+                .is_err(),
+            "signature across another root"
+        );
+        assert!(
+            verify_remote_signature(signature, signing_root, &other_keypair.pk).is_err(),
+            "signature by another key"
+        );
+    }
+}
@@ -1,48 +1,37 @@
 use crate::config::SLASHING_PROTECTION_FILENAME;
+use crate::signing_method::SigningMethod;
 use crate::{config::Config, fork_service::ForkService};
 use parking_lot::RwLock;
 use slashing_protection::{NotSafe, Safe, SlashingDatabase};
-use slog::{crit, error, warn, Logger};
+use slog::{crit, debug, error, warn, Logger};
 use slot_clock::SlotClock;
 use std::collections::HashMap;
-use std::iter::FromIterator;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tempdir::TempDir;
+use types::test_utils::generate_deterministic_keypair;
 use types::{
-    Attestation, BeaconBlock, ChainSpec, Domain, Epoch, EthSpec, Fork, Hash256, Keypair, PublicKey,
-    SelectionProof, Signature, SignedAggregateAndProof, SignedBeaconBlock, SignedRoot, Slot,
+    AggregateAndProof, Attestation, BeaconBlock, ChainSpec, Domain, Epoch, EthSpec, Fork, Hash256,
+    PublicKey, SelectionProof, Signature, SignedAggregateAndProof, SignedBeaconBlock, SignedRoot,
+    Slot,
 };
 use validator_dir::{Manager as ValidatorManager, ValidatorDir};
 
-struct LocalValidator {
-    validator_dir: ValidatorDir,
-    voting_keypair: Keypair,
-}
-
-/// We derive our own `PartialEq` to avoid doing equality checks between secret keys.
-///
-/// It's nice to avoid secret key comparisons from a security perspective, but it's also a little
-/// risky when it comes to `HashMap` integrity (that's why we need `PartialEq`).
-///
-/// Currently, we obtain keypairs from keystores where we derive the `PublicKey` from a `SecretKey`
-/// via a hash function. In order to have two equal `PublicKey` with different `SecretKey` we would
-/// need to have either:
-///
-/// - A serious upstream integrity error.
-/// - A hash collision.
-///
-/// It seems reasonable to make these two assumptions in order to avoid the equality checks.
-impl PartialEq for LocalValidator {
-    fn eq(&self, other: &Self) -> bool {
-        self.validator_dir == other.validator_dir
-            && self.voting_keypair.pk == other.voting_keypair.pk
-    }
+struct InitializedValidator {
+    /// The directory of the local voting keystore, if any. It is held so that the directory
+    /// remains locked.
+    _validator_dir: Option<ValidatorDir>,
+    /// The methods with which the validator may sign, in order of preference.
+    signing_methods: Vec<Arc<SigningMethod>>,
 }
 
 #[derive(Clone)]
 pub struct ValidatorStore<T, E: EthSpec> {
-    validators: Arc<RwLock<HashMap<PublicKey, LocalValidator>>>,
+    validators: Arc<RwLock<HashMap<PublicKey, InitializedValidator>>>,
+    /// Set to `false` whilst doppelganger detection is running, preventing any blocks or
+    /// attestations from being signed.
+    signing_enabled: Arc<AtomicBool>,
     slashing_protection: SlashingDatabase,
     genesis_validators_root: Hash256,
     spec: Arc<ChainSpec>,
@@ -69,7 +58,7 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
                 )
             })?;
 
-        let validator_key_values = ValidatorManager::open(&config.data_dir)
+        let mut validators = ValidatorManager::open(&config.data_dir)
             .map_err(|e| format!("unable to read data_dir: {:?}", e))?
             .decrypt_all_validators(config.secrets_dir.clone(), Some(&log))
             .map_err(|e| format!("unable to decrypt all validator directories: {:?}", e))?
@@ -77,15 +66,66 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
             .map(|(kp, dir)| {
                 (
                     kp.pk.clone(),
-                    LocalValidator {
-                        validator_dir: dir,
-                        voting_keypair: kp,
+                    InitializedValidator {
+                        _validator_dir: Some(dir),
+                        signing_methods: vec![Arc::new(SigningMethod::LocalKeystore {
+                            voting_keypair: kp,
+                        })],
                     },
                 )
-            });
+            })
+            .collect::<HashMap<_, _>>();
+
+        if !config.interop_key_indices.is_empty() {
+            warn!(
+                log,
+                "Using insecure interop keypairs";
+                "msg" => "these keys are publicly known, never use them on a public network",
+                "count" => config.interop_key_indices.len()
+            );
+        }
+
+        for &index in &config.interop_key_indices {
+            let voting_keypair = generate_deterministic_keypair(index);
+            validators
+                .entry(voting_keypair.pk.clone())
+                .or_insert_with(|| InitializedValidator {
+                    _validator_dir: None,
+                    signing_methods: vec![Arc::new(SigningMethod::LocalKeystore {
+                        voting_keypair,
+                    })],
+                });
+        }
+
+        if let Some(remote_signer) = &config.remote_signer {
+            let http_client = remote_signer.build_client()?;
+
+            for public_key in &remote_signer.public_keys {
+                let signing_method = Arc::new(SigningMethod::RemoteSigner {
+                    url: remote_signer.url.clone(),
+                    http_client: http_client.clone(),
+                    voting_public_key: public_key.clone(),
+                });
+
+                let validator =
+                    validators
+                        .entry(public_key.clone())
+                        .or_insert_with(|| InitializedValidator {
+                            _validator_dir: None,
+                            signing_methods: vec![],
+                        });
+
+                if remote_signer.prefer_remote {
+                    validator.signing_methods.insert(0, signing_method);
+                } else {
+                    validator.signing_methods.push(signing_method);
+                }
+            }
+        }
 
         Ok(Self {
-            validators: Arc::new(RwLock::new(HashMap::from_iter(validator_key_values))),
+            validators: Arc::new(RwLock::new(validators)),
+            signing_enabled: Arc::new(AtomicBool::new(config.doppelganger_detection_epochs == 0)),
             slashing_protection,
             genesis_validators_root,
             spec: Arc::new(spec),
@@ -118,6 +158,30 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         self.validators.read().len()
     }
 
+    /// Returns `false` if doppelganger detection has not yet completed.
+    pub fn signing_enabled(&self) -> bool {
+        self.signing_enabled.load(Ordering::Relaxed)
+    }
+
+    /// Permits the signing of blocks and attestations, once doppelganger detection has completed.
+    pub fn enable_signing(&self) {
+        self.signing_enabled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `None` (and logs) if signing is not yet enabled, for use with `?`.
+    fn check_signing_enabled(&self, message_type: &str) -> Option<()> {
+        if self.signing_enabled() {
+            Some(())
+        } else {
+            debug!(
+                self.log,
+                "Not signing during doppelganger detection";
+                "type" => message_type
+            );
+            None
+        }
+    }
+
     fn fork(&self) -> Option<Fork> {
         if self.fork_service.fork().is_none() {
             error!(
@@ -128,26 +192,64 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         self.fork_service.fork()
     }
 
-    pub fn randao_reveal(&self, validator_pubkey: &PublicKey, epoch: Epoch) -> Option<Signature> {
-        // TODO: check this against the slot clock to make sure it's not an early reveal?
-        self.validators
+    /// Signs `signing_root` with the first of the signing methods for `validator_pubkey` to
+    /// succeed.
+    ///
+    /// Returns `None` if the validator is unknown or every signing method fails.
+    async fn get_signature(
+        &self,
+        validator_pubkey: &PublicKey,
+        signing_root: Hash256,
+    ) -> Option<Signature> {
+        // Clone the signing methods so the lock is not held whilst awaiting a remote signer.
+        let signing_methods = self
+            .validators
             .read()
-            .get(validator_pubkey)
-            .and_then(|local_validator| {
-                let voting_keypair = &local_validator.voting_keypair;
-                let domain = self.spec.get_domain(
-                    epoch,
-                    Domain::Randao,
-                    &self.fork()?,
-                    self.genesis_validators_root,
-                );
-                let message = epoch.signing_root(domain);
+            .get(validator_pubkey)?
+            .signing_methods
+            .clone();
 
-                Some(Signature::new(message.as_bytes(), &voting_keypair.sk))
-            })
+        for signing_method in signing_methods {
+            match signing_method.get_signature(signing_root).await {
+                Ok(signature) => return Some(signature),
+                Err(e) => warn!(
+                    self.log,
+                    "Signing method failed";
+                    "method" => signing_method.description(),
+                    "error" => format!("{:?}", e),
+                    "validator" => format!("{:?}", validator_pubkey)
+                ),
+            }
+        }
+
+        error!(
+            self.log,
+            "Unable to sign with any signing method";
+            "validator" => format!("{:?}", validator_pubkey)
+        );
+        None
+    }
+
+    pub async fn randao_reveal(
+        &self,
+        validator_pubkey: &PublicKey,
+        epoch: Epoch,
+    ) -> Option<Signature> {
+        // TODO: check this against the slot clock to make sure it's not an early reveal?
+        self.check_signing_enabled("randao_reveal")?;
+
+        let domain = self.spec.get_domain(
+            epoch,
+            Domain::Randao,
+            &self.fork()?,
+            self.genesis_validators_root,
+        );
+        let message = epoch.signing_root(domain);
+
+        self.get_signature(validator_pubkey, message).await
     }
 
-    pub fn sign_block(
+    pub async fn sign_block(
         &self,
         validator_pubkey: &PublicKey,
         block: BeaconBlock<E>,
@@ -164,6 +266,8 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
             return None;
         }
 
+        self.check_signing_enabled("block")?;
+
         // Check for slashing conditions.
         let fork = self.fork()?;
         let domain = self.spec.get_domain(
@@ -182,16 +286,13 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         match slashing_status {
             // We can safely sign this block.
             Ok(Safe::Valid) => {
-                let validators = self.validators.read();
-                let validator = validators.get(validator_pubkey)?;
-                let voting_keypair = &validator.voting_keypair;
-
-                Some(block.sign(
-                    &voting_keypair.sk,
-                    &fork,
-                    self.genesis_validators_root,
-                    &self.spec,
-                ))
+                let message = block.signing_root(domain);
+                let signature = self.get_signature(validator_pubkey, message).await?;
+
+                Some(SignedBeaconBlock {
+                    message: block,
+                    signature,
+                })
             }
             Ok(Safe::SameData) => {
                 warn!(
@@ -220,7 +321,7 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         }
     }
 
-    pub fn sign_attestation(
+    pub async fn sign_attestation(
         &self,
         validator_pubkey: &PublicKey,
         validator_committee_position: usize,
@@ -232,6 +333,8 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
             return None;
         }
 
+        self.check_signing_enabled("attestation")?;
+
         // Checking for slashing conditions.
         let fork = self.fork()?;
 
@@ -250,18 +353,11 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         match slashing_status {
             // We can safely sign this attestation.
             Ok(Safe::Valid) => {
-                let validators = self.validators.read();
-                let validator = validators.get(validator_pubkey)?;
-                let voting_keypair = &validator.voting_keypair;
+                let message = attestation.data.signing_root(domain);
+                let signature = self.get_signature(validator_pubkey, message).await?;
 
                 attestation
-                    .sign(
-                        &voting_keypair.sk,
-                        validator_committee_position,
-                        &fork,
-                        self.genesis_validators_root,
-                        &self.spec,
-                    )
+                    .add_signature(&signature, validator_committee_position)
                     .map_err(|e| {
                         error!(
                             self.log,
@@ -305,43 +401,49 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
     ///
     /// The resulting `SignedAggregateAndProof` is sent on the aggregation channel and cannot be
     /// modified by actors other than the signing validator.
-    pub fn produce_signed_aggregate_and_proof(
+    pub async fn produce_signed_aggregate_and_proof(
         &self,
         validator_pubkey: &PublicKey,
         validator_index: u64,
         aggregate: Attestation<E>,
         selection_proof: SelectionProof,
     ) -> Option<SignedAggregateAndProof<E>> {
-        let validators = self.validators.read();
-        let voting_keypair = &validators.get(validator_pubkey)?.voting_keypair;
+        self.check_signing_enabled("aggregate_and_proof")?;
 
-        Some(SignedAggregateAndProof::from_aggregate(
-            validator_index,
-            aggregate,
-            Some(selection_proof),
-            &voting_keypair.sk,
+        let domain = self.spec.get_domain(
+            aggregate.data.slot.epoch(E::slots_per_epoch()),
+            Domain::AggregateAndProof,
             &self.fork()?,
             self.genesis_validators_root,
-            &self.spec,
-        ))
+        );
+        let message = AggregateAndProof {
+            aggregator_index: validator_index,
+            aggregate,
+            selection_proof: selection_proof.into(),
+        };
+        let signing_root = message.signing_root(domain);
+        let signature = self.get_signature(validator_pubkey, signing_root).await?;
+
+        Some(SignedAggregateAndProof { message, signature })
     }
 
     /// Produces a `SelectionProof` for the `slot`, signed by with corresponding secret key to
     /// `validator_pubkey`.
-    pub fn produce_selection_proof(
+    pub async fn produce_selection_proof(
         &self,
         validator_pubkey: &PublicKey,
         slot: Slot,
     ) -> Option<SelectionProof> {
-        let validators = self.validators.read();
-        let voting_keypair = &validators.get(validator_pubkey)?.voting_keypair;
-
-        Some(SelectionProof::new::<E>(
-            slot,
-            &voting_keypair.sk,
+        let domain = self.spec.get_domain(
+            slot.epoch(E::slots_per_epoch()),
+            Domain::SelectionProof,
             &self.fork()?,
             self.genesis_validators_root,
-            &self.spec,
-        ))
+        );
+        let message = slot.signing_root(domain);
+
+        self.get_signature(validator_pubkey, message)
+            .await
+            .map(SelectionProof::from)
     }
 }